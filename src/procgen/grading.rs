@@ -0,0 +1,372 @@
+//! Road grading against the terrain height map.
+//!
+//! Every road type has a maximum grade (rise over run). Before a road is
+//! committed, its centerline is sampled against the height map and a
+//! longitudinal profile is computed that stays within that grade while
+//! hugging the terrain as closely as possible. The terrain is then cut or
+//! filled along the road corridor to match the profile.
+
+#![allow(dead_code)]
+
+use bevy::prelude::*;
+
+use super::intersections::carriageway_width;
+use super::roads::{RoadGraph, RoadType};
+use crate::render::road_mesh::RoadMeshConfig;
+use crate::world::terrain::HeightMap;
+
+pub struct GradingPlugin;
+
+impl Plugin for GradingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GradingConfig>();
+    }
+}
+
+/// Limits for cutting and filling terrain under roads.
+#[derive(Resource, Clone)]
+pub struct GradingConfig {
+    /// Deepest cut or highest fill allowed anywhere along a road.
+    pub max_cut_depth: f32,
+    /// Width of the blended embankment on each side of the corridor.
+    pub shoulder_width: f32,
+    /// Distance between profile stations along the centerline.
+    pub station_spacing: f32,
+    /// Curb and sidewalk beside roads that have them, as the road mesh lays them.
+    pub roadside_width: f32,
+}
+
+impl Default for GradingConfig {
+    fn default() -> Self {
+        let mesh = RoadMeshConfig::default();
+        Self {
+            max_cut_depth: 6.0,
+            shoulder_width: 6.0,
+            station_spacing: 2.0,
+            roadside_width: mesh.curb_width + mesh.sidewalk_width,
+        }
+    }
+}
+
+/// Why a road could not be graded.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GradeError {
+    /// The endpoints are further apart vertically than the grade allows.
+    TooSteep { required: f32, max: f32 },
+    /// Meeting the grade would need a cut or fill deeper than allowed.
+    ExcessiveCut { depth: f32, max: f32 },
+    /// The centerline has fewer than two points.
+    TooShort,
+}
+
+/// Longitudinal road profile: target surface heights at stations along a polyline.
+#[derive(Clone, Debug)]
+pub struct GradeProfile {
+    /// Station positions along the centerline.
+    pub stations: Vec<Vec2>,
+    /// Graded road surface height at each station.
+    pub heights: Vec<f32>,
+    /// Deepest cut (terrain lowered) along the road.
+    pub max_cut: f32,
+    /// Highest fill (terrain raised) along the road.
+    pub max_fill: f32,
+}
+
+/// Maximum sustained grade for each road class.
+pub fn max_grade(road_type: RoadType) -> f32 {
+    match road_type {
+        RoadType::Highway => 0.06,
+        RoadType::Major => 0.08,
+        RoadType::Minor => 0.12,
+        RoadType::Alley => 0.15,
+    }
+}
+
+/// Half-width of the flattened corridor (carriageway plus sidewalks).
+pub fn corridor_half_width(road_type: RoadType, config: &GradingConfig) -> f32 {
+    let roadside = match road_type {
+        RoadType::Major | RoadType::Minor => config.roadside_width,
+        RoadType::Highway | RoadType::Alley => 0.0,
+    };
+    carriageway_width(road_type) / 2.0 + roadside
+}
+
+/// Steepest natural terrain grade encountered along a polyline.
+pub fn natural_grade(heights: &HeightMap, points: &[Vec2], spacing: f32) -> f32 {
+    let stations = resample(points, spacing);
+    stations
+        .windows(2)
+        .map(|w| {
+            let run = w[0].distance(w[1]).max(0.001);
+            (heights.sample_world(w[1]) - heights.sample_world(w[0])).abs() / run
+        })
+        .fold(0.0, f32::max)
+}
+
+/// Compute a grade-limited profile for a road along `points`.
+///
+/// The end heights are pinned to the current terrain so the road meets
+/// neighbouring edges at their shared nodes.
+pub fn plan_road_grade(
+    heights: &HeightMap,
+    points: &[Vec2],
+    road_type: RoadType,
+    config: &GradingConfig,
+) -> Result<GradeProfile, GradeError> {
+    if points.len() < 2 {
+        return Err(GradeError::TooShort);
+    }
+    let max = max_grade(road_type);
+    let stations = resample(points, config.station_spacing);
+    let terrain: Vec<f32> = stations.iter().map(|p| heights.sample_world(*p)).collect();
+
+    let mut distances = Vec::with_capacity(stations.len());
+    let mut total = 0.0;
+    for (i, p) in stations.iter().enumerate() {
+        if i > 0 {
+            total += stations[i - 1].distance(*p);
+        }
+        distances.push(total);
+    }
+
+    let start = terrain[0];
+    let end = *terrain.last().unwrap();
+    if total > 0.0 && (end - start).abs() / total > max {
+        return Err(GradeError::TooSteep {
+            required: (end - start).abs() / total,
+            max,
+        });
+    }
+
+    // Clamp to the envelope reachable from both pinned ends, then slope-limit
+    // forward and backward so consecutive stations respect the grade.
+    let mut profile: Vec<f32> = terrain
+        .iter()
+        .zip(&distances)
+        .map(|(h, s)| {
+            let from_end = total - s;
+            let upper = (start + max * s).min(end + max * from_end);
+            let lower = (start - max * s).max(end - max * from_end);
+            h.clamp(lower, upper)
+        })
+        .collect();
+
+    for i in 1..profile.len() {
+        let limit = max * (distances[i] - distances[i - 1]);
+        profile[i] = profile[i].clamp(profile[i - 1] - limit, profile[i - 1] + limit);
+    }
+    for i in (0..profile.len().saturating_sub(1)).rev() {
+        let limit = max * (distances[i + 1] - distances[i]);
+        profile[i] = profile[i].clamp(profile[i + 1] - limit, profile[i + 1] + limit);
+    }
+
+    let mut max_cut: f32 = 0.0;
+    let mut max_fill: f32 = 0.0;
+    for (target, ground) in profile.iter().zip(&terrain) {
+        max_cut = max_cut.max(ground - target);
+        max_fill = max_fill.max(target - ground);
+    }
+
+    let depth = max_cut.max(max_fill);
+    if depth > config.max_cut_depth {
+        return Err(GradeError::ExcessiveCut {
+            depth,
+            max: config.max_cut_depth,
+        });
+    }
+
+    Ok(GradeProfile {
+        stations,
+        heights: profile,
+        max_cut,
+        max_fill,
+    })
+}
+
+/// Cut or fill the terrain along a road corridor to match its profile.
+///
+/// Samples within the corridor are set to the profile height; samples in the
+/// shoulder blend smoothly back to the original ground.
+pub fn apply_road_grade(
+    heights: &mut HeightMap,
+    profile: &GradeProfile,
+    road_type: RoadType,
+    config: &GradingConfig,
+) {
+    if profile.stations.len() < 2 {
+        return;
+    }
+
+    // Widen the flat zone by half a cell so bilinear sampling stays flat
+    // across the full road surface.
+    let flat = corridor_half_width(road_type, config) + heights.cell_size * 0.5;
+    let reach = flat + config.shoulder_width;
+
+    let mut min = Vec2::splat(f32::MAX);
+    let mut max = Vec2::splat(f32::MIN);
+    for p in &profile.stations {
+        min = min.min(*p);
+        max = max.max(*p);
+    }
+    let (xs, ys) = heights.grid_range(min - Vec2::splat(reach), max + Vec2::splat(reach));
+
+    for y in ys {
+        for x in xs.clone() {
+            let pos = heights.grid_to_world(x, y);
+            let Some((dist, target)) = nearest_on_profile(profile, pos) else {
+                continue;
+            };
+            if dist >= reach {
+                continue;
+            }
+
+            let ground = heights.get(x, y);
+            let graded = if dist <= flat {
+                target
+            } else {
+                let t = (dist - flat) / config.shoulder_width;
+                let t = t * t * (3.0 - 2.0 * t);
+                target + (ground - target) * t
+            };
+            heights.set(x, y, graded);
+        }
+    }
+}
+
+/// Grade every land edge of a road network into the terrain.
+///
+/// Edges that cannot meet their grade are left on the natural ground; the
+/// number of such edges is returned.
+pub fn grade_road_network(
+    graph: &RoadGraph,
+    heights: &mut HeightMap,
+    config: &GradingConfig,
+) -> usize {
+    let mut ungraded = 0;
+    for edge in graph.edges() {
+        if edge.is_structure() || edge.points.len() < 2 {
+            continue;
+        }
+        match plan_road_grade(heights, &edge.points, edge.road_type, config) {
+            Ok(profile) => apply_road_grade(heights, &profile, edge.road_type, config),
+            Err(_) => ungraded += 1,
+        }
+    }
+    ungraded
+}
+
+/// Distance from `pos` to the profile centerline and the profile height there.
+fn nearest_on_profile(profile: &GradeProfile, pos: Vec2) -> Option<(f32, f32)> {
+    let mut best: Option<(f32, f32)> = None;
+    for i in 0..profile.stations.len() - 1 {
+        let a = profile.stations[i];
+        let b = profile.stations[i + 1];
+        let ab = b - a;
+        let len_sq = ab.length_squared();
+        let t = if len_sq > 0.0 {
+            ((pos - a).dot(ab) / len_sq).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let dist = pos.distance(a + ab * t);
+        if best.is_none_or(|(d, _)| dist < d) {
+            let h = profile.heights[i] + (profile.heights[i + 1] - profile.heights[i]) * t;
+            best = Some((dist, h));
+        }
+    }
+    best
+}
+
+/// Resample a polyline so consecutive stations are at most `spacing` apart.
+fn resample(points: &[Vec2], spacing: f32) -> Vec<Vec2> {
+    let mut stations = Vec::new();
+    let Some(first) = points.first() else {
+        return stations;
+    };
+    stations.push(*first);
+
+    for w in points.windows(2) {
+        let len = w[0].distance(w[1]);
+        let steps = (len / spacing.max(0.1)).ceil().max(1.0) as usize;
+        for i in 1..=steps {
+            stations.push(w[0].lerp(w[1], i as f32 / steps as f32));
+        }
+    }
+
+    stations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A plane rising along +X with the given slope.
+    fn ramp(slope: f32) -> HeightMap {
+        let mut heights = HeightMap::generate(32, 32, 0, 0.0);
+        heights.origin = Vec2::ZERO;
+        heights.cell_size = 4.0;
+        for y in 0..32 {
+            for x in 0..32 {
+                heights.set(x, y, x as f32 * 4.0 * slope);
+            }
+        }
+        heights
+    }
+
+    #[test]
+    fn profile_respects_max_grade() {
+        let heights = ramp(0.2);
+        let config = GradingConfig {
+            max_cut_depth: 100.0,
+            ..default()
+        };
+        let points = [Vec2::new(10.0, 60.0), Vec2::new(110.0, 60.0)];
+
+        // Endpoints 100m apart rise 20m - too steep for any road class.
+        let err = plan_road_grade(&heights, &points, RoadType::Alley, &config).unwrap_err();
+        assert!(matches!(err, GradeError::TooSteep { .. }));
+
+        // Running across the slope needs no grading at all.
+        let across = [Vec2::new(60.0, 10.0), Vec2::new(60.0, 110.0)];
+        let profile = plan_road_grade(&heights, &across, RoadType::Highway, &config).unwrap();
+        assert!(profile.max_cut < 0.01 && profile.max_fill < 0.01);
+    }
+
+    #[test]
+    fn grading_flattens_corridor() {
+        let mut heights = ramp(0.05);
+        // A mound 4 m above the 3 m ramp, in the way but within the cut limit
+        heights.set(15, 15, 7.0);
+        let config = GradingConfig::default();
+        let points = [Vec2::new(20.0, 60.0), Vec2::new(100.0, 60.0)];
+
+        let profile = plan_road_grade(&heights, &points, RoadType::Minor, &config).unwrap();
+        apply_road_grade(&mut heights, &profile, RoadType::Minor, &config);
+
+        let grade = natural_grade(&heights, &points, config.station_spacing);
+        assert!(grade <= max_grade(RoadType::Minor) + 0.001);
+    }
+
+    #[test]
+    fn mound_deeper_than_cut_limit_is_refused() {
+        let mut heights = ramp(0.05);
+        // 7 m above the ramp: a minor road climbing at 12% from the foot
+        // would still have to cut about 6.7 m, past the 6 m limit
+        heights.set(15, 15, 10.0);
+        let config = GradingConfig::default();
+        let points = [Vec2::new(20.0, 60.0), Vec2::new(100.0, 60.0)];
+
+        let err = plan_road_grade(&heights, &points, RoadType::Minor, &config).unwrap_err();
+        assert!(matches!(err, GradeError::ExcessiveCut { depth, .. } if depth > config.max_cut_depth));
+    }
+
+    #[test]
+    fn polyline_without_two_points_is_refused() {
+        let heights = ramp(0.0);
+        let config = GradingConfig::default();
+        for points in [&[][..], &[Vec2::new(10.0, 10.0)][..]] {
+            let err = plan_road_grade(&heights, points, RoadType::Minor, &config).unwrap_err();
+            assert_eq!(err, GradeError::TooShort);
+        }
+    }
+}
@@ -0,0 +1,431 @@
+//! Parametric road templates: roundabouts and highway interchanges.
+//!
+//! A template is a small road network laid out around a centre point, ready
+//! to be added to the `RoadGraph`. Ramps and roundabout rings are one-way
+//! edges, and roads that pass over one another are grade separated with an
+//! overpass edge instead of sharing a node.
+
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+use bevy::prelude::*;
+use smallvec::SmallVec;
+
+use super::intersections::lane_side;
+use super::roads::{RoadEdge, RoadNodeType, RoadType, TravelDirection};
+
+/// Which interchange layout to build.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InterchangeKind {
+    /// Four straight ramps meeting the crossroad either side of the highway.
+    Diamond,
+    /// Loop ramps for turns across traffic, outer ramps for the others.
+    Cloverleaf,
+}
+
+/// Sizes used when laying out templates.
+#[derive(Clone, Debug)]
+pub struct TemplateConfig {
+    /// Distance from the crossing to each diamond ramp terminal on the crossroad.
+    pub diamond_terminal: f32,
+    /// Distance from the crossing to where diamond ramps leave the highway.
+    pub diamond_ramp: f32,
+    /// Radius of cloverleaf loop ramps.
+    pub loop_radius: f32,
+    /// Distance from the crossing to where cloverleaf outer ramps leave, in loop radii.
+    pub outer_ramp_factor: f32,
+    /// Length of road beyond the last ramp, for joining the rest of the network.
+    pub approach_length: f32,
+    /// Angle covered by each point of a curved edge, in radians.
+    pub arc_step: f32,
+}
+
+impl Default for TemplateConfig {
+    fn default() -> Self {
+        Self {
+            diamond_terminal: 40.0,
+            diamond_ramp: 120.0,
+            loop_radius: 35.0,
+            outer_ramp_factor: 3.2,
+            approach_length: 50.0,
+            arc_step: 15f32.to_radians(),
+        }
+    }
+}
+
+/// A template node.
+#[derive(Clone, Debug)]
+pub struct TemplateNode {
+    pub position: Vec2,
+    pub node_type: RoadNodeType,
+    /// May join an existing node at the same place instead of adding one.
+    pub attach: bool,
+}
+
+/// A template edge between two template nodes, by index.
+#[derive(Clone, Debug)]
+pub struct TemplateEdge {
+    pub from: usize,
+    pub to: usize,
+    pub edge: RoadEdge,
+}
+
+/// A road network to be added as a unit.
+#[derive(Clone, Debug, Default)]
+pub struct RoadTemplate {
+    pub nodes: Vec<TemplateNode>,
+    pub edges: Vec<TemplateEdge>,
+}
+
+impl RoadTemplate {
+    fn node(&mut self, position: Vec2, node_type: RoadNodeType, attach: bool) -> usize {
+        self.nodes.push(TemplateNode {
+            position,
+            node_type,
+            attach,
+        });
+        self.nodes.len() - 1
+    }
+
+    /// Straight two-way road between two template nodes.
+    fn road(&mut self, from: usize, to: usize, road_type: RoadType) {
+        let points = SmallVec::from_slice(&[self.nodes[from].position, self.nodes[to].position]);
+        self.edges.push(TemplateEdge {
+            from,
+            to,
+            edge: RoadEdge::new(points, road_type),
+        });
+    }
+
+    /// Straight road raised over whatever it crosses.
+    fn overpass(&mut self, from: usize, to: usize, road_type: RoadType) {
+        let points = SmallVec::from_slice(&[self.nodes[from].position, self.nodes[to].position]);
+        self.edges.push(TemplateEdge {
+            from,
+            to,
+            edge: RoadEdge::new_overpass(points, road_type),
+        });
+    }
+
+    /// One-way road along `points`, which must run from `from` to `to`.
+    fn one_way(&mut self, from: usize, to: usize, points: SmallVec<[Vec2; 8]>, road_type: RoadType) {
+        let mut edge = RoadEdge::new(points, road_type);
+        edge.direction = TravelDirection::Forward;
+        self.edges.push(TemplateEdge { from, to, edge });
+    }
+
+    /// Every edge as a list of straight pieces, for previews.
+    pub fn segments(&self) -> Vec<(Vec2, Vec2)> {
+        self.edges
+            .iter()
+            .flat_map(|e| e.edge.points.windows(2).map(|w| (w[0], w[1])).collect::<Vec<_>>())
+            .collect()
+    }
+}
+
+/// Points on a circle from `start` radians through `sweep` radians.
+pub fn arc_points(center: Vec2, radius: f32, start: f32, sweep: f32, step: f32) -> SmallVec<[Vec2; 8]> {
+    let count = (sweep.abs() / step.max(0.01)).ceil().max(1.0) as usize;
+    (0..=count)
+        .map(|i| center + Vec2::from_angle(start + sweep * i as f32 / count as f32) * radius)
+        .collect()
+}
+
+/// Cubic bezier through four control points.
+fn cubic_points(p0: Vec2, p1: Vec2, p2: Vec2, p3: Vec2, segments: usize) -> SmallVec<[Vec2; 8]> {
+    (0..=segments)
+        .map(|i| {
+            let t = i as f32 / segments as f32;
+            let u = 1.0 - t;
+            p0 * u * u * u + p1 * 3.0 * u * u * t + p2 * 3.0 * u * t * t + p3 * t * t * t
+        })
+        .collect()
+}
+
+/// Which way round a roundabout's traffic goes: +1 for increasing angle.
+///
+/// Traffic keeps the island on the side away from its lane.
+pub fn circulation() -> f32 {
+    // At angle 0 the outward normal is +X; pick the tangent whose lane side faces out
+    if lane_side(Vec2::Y).x > 0.0 {
+        1.0
+    } else {
+        -1.0
+    }
+}
+
+/// A roundabout ring of `radius` around `center`, with a node where each
+/// approach at `leg_angles` (radians) meets it.
+///
+/// Extra nodes keep every arc under a quarter turn. Returns the template and
+/// the ring node for each leg, in the order given.
+pub fn roundabout(
+    center: Vec2,
+    radius: f32,
+    leg_angles: &[f32],
+    road_type: RoadType,
+    config: &TemplateConfig,
+) -> (RoadTemplate, Vec<usize>) {
+    let mut angles: Vec<f32> = leg_angles.iter().map(|a| a.rem_euclid(TAU)).collect();
+    angles.sort_by(f32::total_cmp);
+    angles.dedup_by(|a, b| (*a - *b).abs() < 1e-3);
+    if angles.is_empty() {
+        angles.push(0.0);
+    }
+
+    // Fill long gaps so no arc exceeds a quarter turn
+    let mut ring = Vec::new();
+    for (i, &angle) in angles.iter().enumerate() {
+        ring.push(angle);
+        let next = if i + 1 < angles.len() { angles[i + 1] } else { angles[0] + TAU };
+        let fillers = ((next - angle) / FRAC_PI_2).ceil() as usize;
+        for k in 1..fillers {
+            ring.push(angle + (next - angle) * k as f32 / fillers as f32);
+        }
+    }
+
+    let mut template = RoadTemplate::default();
+    let nodes: Vec<usize> = ring
+        .iter()
+        .map(|&angle| template.node(center + Vec2::from_angle(angle) * radius, RoadNodeType::Roundabout, false))
+        .collect();
+
+    // Arcs between neighbours, one-way in the direction of circulation
+    let sign = circulation();
+    for i in 0..ring.len() {
+        let j = (i + 1) % ring.len();
+        let mut gap = ring[j] - ring[i];
+        if gap <= 0.0 {
+            gap += TAU;
+        }
+        if sign > 0.0 {
+            let points = arc_points(center, radius, ring[i], gap, config.arc_step);
+            template.one_way(nodes[i], nodes[j], points, road_type);
+        } else {
+            let points = arc_points(center, radius, ring[j], -gap, config.arc_step);
+            template.one_way(nodes[j], nodes[i], points, road_type);
+        }
+    }
+
+    let legs = leg_angles
+        .iter()
+        .map(|a| {
+            let a = a.rem_euclid(TAU);
+            let i = ring
+                .iter()
+                .position(|r| (r - a).abs() < 1e-3 || (r - a).abs() > TAU - 1e-3)
+                .unwrap_or(0);
+            nodes[i]
+        })
+        .collect();
+    (template, legs)
+}
+
+/// A highway running along `axis` through `center`, crossed by a road that
+/// passes over it, joined by ramps.
+///
+/// The four outermost nodes may join existing roads.
+pub fn interchange(
+    kind: InterchangeKind,
+    center: Vec2,
+    axis: Vec2,
+    crossroad_type: RoadType,
+    config: &TemplateConfig,
+) -> RoadTemplate {
+    let u = axis.try_normalize().unwrap_or(Vec2::X);
+    let r = lane_side(u);
+    match kind {
+        InterchangeKind::Diamond => diamond(center, u, r, crossroad_type, config),
+        InterchangeKind::Cloverleaf => cloverleaf(center, u, r, crossroad_type, config),
+    }
+}
+
+fn diamond(c: Vec2, u: Vec2, r: Vec2, crossroad_type: RoadType, config: &TemplateConfig) -> RoadTemplate {
+    let (s, f) = (config.diamond_terminal, config.diamond_ramp);
+    let highway_end = f + config.approach_length;
+    let cross_end = s + config.approach_length;
+    let mut t = RoadTemplate::default();
+
+    // Highway: ends, ramp nodes, and the stretch under the crossroad
+    let h_start = t.node(c - u * highway_end, RoadNodeType::Endpoint, true);
+    let h_before = t.node(c - u * f, RoadNodeType::Ramp, false);
+    let h_after = t.node(c + u * f, RoadNodeType::Ramp, false);
+    let h_end = t.node(c + u * highway_end, RoadNodeType::Endpoint, true);
+    t.road(h_start, h_before, RoadType::Highway);
+    t.road(h_before, h_after, RoadType::Highway);
+    t.road(h_after, h_end, RoadType::Highway);
+
+    // Crossroad over the top, with a ramp terminal either side
+    let x_start = t.node(c - r * cross_end, RoadNodeType::Endpoint, true);
+    let x_left = t.node(c - r * s, RoadNodeType::Intersection, false);
+    let x_right = t.node(c + r * s, RoadNodeType::Intersection, false);
+    let x_end = t.node(c + r * cross_end, RoadNodeType::Endpoint, true);
+    t.road(x_start, x_left, crossroad_type);
+    t.overpass(x_left, x_right, crossroad_type);
+    t.road(x_right, x_end, crossroad_type);
+
+    // Each carriageway exits to the terminal on its own side and rejoins after
+    let ramp_type = RoadType::Minor;
+    for (heading, before, after, terminal) in [(u, h_before, h_after, x_right), (-u, h_after, h_before, x_left)] {
+        let side = lane_side(heading);
+        let p = |along: f32, across: f32| c + heading * along + side * across;
+        let off = cubic_points(p(-f, 0.0), p(-0.6 * f, 0.6 * s), p(-0.3 * f, s), p(0.0, s), 6);
+        let on = cubic_points(p(0.0, s), p(0.3 * f, s), p(0.6 * f, 0.6 * s), p(f, 0.0), 6);
+        t.one_way(before, terminal, off, ramp_type);
+        t.one_way(terminal, after, on, ramp_type);
+    }
+    t
+}
+
+fn cloverleaf(c: Vec2, u: Vec2, r: Vec2, crossroad_type: RoadType, config: &TemplateConfig) -> RoadTemplate {
+    let e = config.loop_radius;
+    let f = e * config.outer_ramp_factor;
+    let end = f + config.approach_length;
+    let mut t = RoadTemplate::default();
+
+    // Each road: end, outer ramp node, loop node | crossing | loop node, outer ramp node, end
+    let mut inner = Vec::new();
+    let mut outer = Vec::new();
+    for (dir, road_type, over) in [(u, RoadType::Highway, false), (r, crossroad_type, true)] {
+        let ids: Vec<usize> = [-end, -f, -e, e, f, end]
+            .iter()
+            .enumerate()
+            .map(|(i, &d)| {
+                let node_type = if i == 0 || i == 5 { RoadNodeType::Endpoint } else { RoadNodeType::Ramp };
+                t.node(c + dir * d, node_type, i == 0 || i == 5)
+            })
+            .collect();
+        for pair in ids.windows(2) {
+            if over && pair == [ids[2], ids[3]] {
+                t.overpass(pair[0], pair[1], road_type);
+            } else {
+                t.road(pair[0], pair[1], road_type);
+            }
+        }
+        inner.push((dir, ids[2], ids[3]));
+        outer.push((dir, ids[1], ids[4]));
+    }
+
+    // Nodes (behind, ahead) of the centre along heading `h`, from the inner or outer set
+    let find = |nodes: &[(Vec2, usize, usize)], h: Vec2| -> (usize, usize) {
+        nodes
+            .iter()
+            .find_map(|&(dir, minus, plus)| {
+                if dir.dot(h) > 0.5 {
+                    Some((minus, plus))
+                } else if dir.dot(h) < -0.5 {
+                    Some((plus, minus))
+                } else {
+                    None
+                }
+            })
+            .unwrap_or((0, 0))
+    };
+
+    let ramp_type = RoadType::Minor;
+    for heading in [u, -u, r, -r] {
+        let side = lane_side(heading);
+
+        // Turn across traffic: carry on over the crossing, then loop 270° onto the other road
+        let (_, loop_start) = find(&inner, heading);
+        let (_, loop_end) = find(&inner, side);
+        let loop_center = c + (heading + side) * e;
+        let points = arc_points(loop_center, e, (-side).to_angle(), 1.5 * PI * turn_sign(heading, side), config.arc_step);
+        t.one_way(loop_start, loop_end, points, ramp_type);
+
+        // Turn towards the kerb side: leave before the crossing, sweep round outside the loop
+        let (ramp_start, _) = find(&outer, heading);
+        let (_, ramp_end) = find(&outer, side);
+        let p0 = c - heading * f;
+        let p3 = c + side * f;
+        let points = cubic_points(p0, p0 + heading * 0.25 * f + side * 0.6 * f, p3 - side * 0.25 * f - heading * 0.6 * f, p3, 6);
+        t.one_way(ramp_start, ramp_end, points, ramp_type);
+    }
+    t
+}
+
+/// Direction of increasing angle that turns `heading` towards `side`: +1 or -1.
+fn turn_sign(heading: Vec2, side: Vec2) -> f32 {
+    if heading.perp_dot(side) > 0.0 {
+        1.0
+    } else {
+        -1.0
+    }
+}
+
+/// Where a road leaving `center` along `points` crosses the circle of
+/// `radius`: the part of the road outside the circle, in the original order.
+///
+/// Returns None if the road never leaves the circle.
+pub fn clip_outside_circle(points: &[Vec2], center: Vec2, radius: f32) -> Option<SmallVec<[Vec2; 8]>> {
+    let starts_inside = points.first()?.distance(center) < points.last()?.distance(center);
+    let ordered: Vec<Vec2> = if starts_inside {
+        points.to_vec()
+    } else {
+        points.iter().rev().copied().collect()
+    };
+
+    // First segment that ends outside the circle
+    let i = ordered.windows(2).position(|w| w[1].distance(center) >= radius)?;
+    let (a, b) = (ordered[i], ordered[i + 1]);
+    let crossing = if a.distance(center) >= radius {
+        a
+    } else {
+        // Solve |a + t(b - a) - center| = radius for t in [0, 1]
+        let d = b - a;
+        let m = a - center;
+        let (qa, qb, qc) = (d.dot(d), 2.0 * m.dot(d), m.dot(m) - radius * radius);
+        let t = (-qb + (qb * qb - 4.0 * qa * qc).max(0.0).sqrt()) / (2.0 * qa).max(1e-6);
+        a + d * t.clamp(0.0, 1.0)
+    };
+
+    let mut clipped: SmallVec<[Vec2; 8]> = SmallVec::new();
+    clipped.push(crossing);
+    clipped.extend(ordered[i + 1..].iter().copied().filter(|p| p.distance(crossing) > 1e-3));
+    if clipped.len() < 2 {
+        return None;
+    }
+    if !starts_inside {
+        clipped.reverse();
+    }
+    Some(clipped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundabout_ring_is_one_way_with_lanes_outside() {
+        let config = TemplateConfig::default();
+        let (template, legs) = roundabout(Vec2::ZERO, 20.0, &[0.0, PI], RoadType::Minor, &config);
+        // Two legs half a turn apart get a filler node in each half
+        assert_eq!(template.nodes.len(), 4);
+        assert_eq!(template.edges.len(), 4);
+        assert_eq!(legs, vec![0, 2]);
+        for e in &template.edges {
+            assert_eq!(e.edge.direction, TravelDirection::Forward);
+            let (a, b) = (e.edge.points[0], e.edge.points[1]);
+            // The driving side of each arc faces away from the island
+            assert!(lane_side((b - a).normalize()).dot((a + b) / 2.0) > 0.0);
+        }
+    }
+
+    #[test]
+    fn interchanges_separate_grades_and_keep_ramps_one_way() {
+        let config = TemplateConfig::default();
+        for kind in [InterchangeKind::Diamond, InterchangeKind::Cloverleaf] {
+            let template = interchange(kind, Vec2::ZERO, Vec2::X, RoadType::Major, &config);
+            assert_eq!(template.edges.iter().filter(|e| e.edge.overpass).count(), 1);
+            assert_eq!(template.nodes.iter().filter(|n| n.attach).count(), 4);
+            let ramps = template.edges.iter().filter(|e| e.edge.direction == TravelDirection::Forward).count();
+            assert_eq!(ramps, if kind == InterchangeKind::Diamond { 4 } else { 8 });
+
+            // Every ramp starts and ends on its nodes
+            for e in &template.edges {
+                let (first, last) = (e.edge.points[0], e.edge.points[e.edge.points.len() - 1]);
+                assert!(first.distance(template.nodes[e.from].position) < 1e-3);
+                assert!(last.distance(template.nodes[e.to].position) < 1e-3);
+            }
+        }
+    }
+}
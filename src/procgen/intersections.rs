@@ -0,0 +1,602 @@
+//! Intersection geometry.
+//!
+//! Every node where two or more roads meet gets an outline built from the
+//! widths and angles of its roads. Neighbouring roads are joined by rounded
+//! curb returns, and each road is set back from the node far enough to clear
+//! them. The setback is where the road surface ends, where stop lines and
+//! crosswalks are painted, and where vehicles leave the edge to follow a
+//! turn path through the junction into their outgoing lane.
+
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::f32::consts::{FRAC_1_SQRT_2, PI, TAU};
+
+use bevy::prelude::*;
+use petgraph::graph::{EdgeIndex, NodeIndex};
+
+use super::roads::{polyline_length, RoadGraph, RoadType};
+
+pub struct IntersectionsPlugin;
+
+impl Plugin for IntersectionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<IntersectionConfig>()
+            .init_resource::<JunctionLayout>()
+            .add_systems(Update, update_junction_layout.run_if(resource_changed::<RoadGraph>));
+    }
+}
+
+/// Shape parameters for junctions.
+#[derive(Resource, Clone)]
+pub struct IntersectionConfig {
+    /// Curb return radius where two minor roads meet; scales with road width.
+    pub curb_radius: f32,
+    /// Furthest a curb return may push a road back from its node.
+    pub max_setback: f32,
+    /// Setback where roads meet without a curb return.
+    pub min_setback: f32,
+    /// Depth of a crosswalk along the road.
+    pub crosswalk_width: f32,
+    /// Gap between the crosswalk and the stop line.
+    pub stop_line_gap: f32,
+    /// Segments per curb return arc.
+    pub arc_segments: usize,
+    /// Segments per turn path.
+    pub turn_segments: usize,
+}
+
+impl Default for IntersectionConfig {
+    fn default() -> Self {
+        Self {
+            curb_radius: 4.0,
+            max_setback: 14.0,
+            min_setback: 1.0,
+            crosswalk_width: 3.0,
+            stop_line_gap: 1.0,
+            arc_segments: 6,
+            turn_segments: 8,
+        }
+    }
+}
+
+/// Carriageway width for each road class.
+pub fn carriageway_width(road_type: RoadType) -> f32 {
+    match road_type {
+        RoadType::Highway => 12.0,
+        RoadType::Major => 8.0,
+        RoadType::Minor => 5.0,
+        RoadType::Alley => 3.0,
+    }
+}
+
+/// Distance from the centreline to the middle of the travel lane.
+///
+/// Vehicles drive on the side of `lane_side(heading)`; alleys share one lane.
+/// One-way roads use the whole carriageway, so their lane sits nearer the middle.
+pub fn lane_offset(road_type: RoadType, one_way: bool) -> f32 {
+    let offset = match road_type {
+        RoadType::Highway => 3.0,
+        RoadType::Major => 2.0,
+        RoadType::Minor => 1.5,
+        RoadType::Alley => 0.0,
+    };
+    if one_way {
+        offset * 0.5
+    } else {
+        offset
+    }
+}
+
+/// Unit normal on the driving side of a heading (the counter-clockwise side in XZ).
+pub fn lane_side(heading: Vec2) -> Vec2 {
+    Vec2::new(-heading.y, heading.x)
+}
+
+/// A road as seen from the junction it leaves, before the outline is built.
+#[derive(Clone, Debug)]
+pub struct LegInput {
+    pub edge: EdgeIndex,
+    pub neighbor: NodeIndex,
+    /// Unit direction the road leaves the node in, from its first segment.
+    pub direction: Vec2,
+    pub road_type: RoadType,
+    /// Length of the whole edge, which bounds the setback.
+    pub length: f32,
+    /// Traffic may drive into the junction along this road.
+    pub incoming: bool,
+    /// Traffic may drive out of the junction along this road.
+    pub outgoing: bool,
+}
+
+/// One road leaving a junction.
+#[derive(Clone, Debug)]
+pub struct JunctionLeg {
+    pub edge: EdgeIndex,
+    pub neighbor: NodeIndex,
+    /// Unit direction the road leaves the junction in.
+    pub direction: Vec2,
+    pub road_type: RoadType,
+    pub half_width: f32,
+    /// Distance from the node to the junction boundary on this road.
+    pub setback: f32,
+    pub incoming: bool,
+    pub outgoing: bool,
+    /// Stop line across the incoming lanes; None if traffic only leaves this way.
+    pub stop_line: Option<[Vec2; 2]>,
+    /// Middle of the crosswalk just outside the boundary.
+    pub crosswalk_center: Vec2,
+}
+
+/// The curb line between two neighbouring legs.
+#[derive(Clone, Debug)]
+pub struct JunctionCorner {
+    /// Leg indices either side, counter-clockwise.
+    pub legs: (usize, usize),
+    /// From the boundary of the first leg round to the boundary of the second.
+    pub curb: Vec<Vec2>,
+    /// Rounded curb return (false on the outside of a bend or a straight run).
+    pub rounded: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TurnKind {
+    Straight,
+    Left,
+    Right,
+    UTurn,
+}
+
+/// A lane-to-lane path through a junction.
+#[derive(Clone, Debug)]
+pub struct TurnPath {
+    pub from_edge: EdgeIndex,
+    pub to_edge: EdgeIndex,
+    pub kind: TurnKind,
+    /// From the incoming lane at the boundary to the outgoing lane at the boundary.
+    pub points: Vec<Vec2>,
+    pub length: f32,
+}
+
+/// Complete geometry of one junction.
+#[derive(Clone, Debug)]
+pub struct JunctionGeometry {
+    pub node: NodeIndex,
+    pub center: Vec2,
+    /// Legs sorted counter-clockwise by direction.
+    pub legs: Vec<JunctionLeg>,
+    /// `corners[i]` joins `legs[i]` to the next leg round.
+    pub corners: Vec<JunctionCorner>,
+    /// Carriageway outline, counter-clockwise and star-shaped about `center`.
+    pub outline: Vec<Vec2>,
+    pub turn_paths: Vec<TurnPath>,
+}
+
+impl JunctionGeometry {
+    pub fn leg(&self, edge: EdgeIndex) -> Option<&JunctionLeg> {
+        self.legs.iter().find(|leg| leg.edge == edge)
+    }
+
+    pub fn turn_path(&self, from: EdgeIndex, to: EdgeIndex) -> Option<&TurnPath> {
+        self.turn_paths.iter().find(|path| path.from_edge == from && path.to_edge == to)
+    }
+
+    /// Incoming roads split into two signal groups by axis.
+    ///
+    /// Roads roughly in line with the first incoming road share group 0 and
+    /// get green together; the cross roads form group 1.
+    pub fn signal_groups(&self) -> Vec<(EdgeIndex, usize)> {
+        let Some(axis) = self.legs.iter().find(|leg| leg.incoming).map(|leg| leg.direction) else {
+            return Vec::new();
+        };
+        self.legs
+            .iter()
+            .filter(|leg| leg.incoming)
+            .map(|leg| (leg.edge, usize::from(leg.direction.dot(axis).abs() < FRAC_1_SQRT_2)))
+            .collect()
+    }
+
+    /// Farthest the outline reaches from the node.
+    pub fn radius(&self) -> f32 {
+        self.outline.iter().map(|p| p.distance(self.center)).fold(0.0, f32::max)
+    }
+}
+
+/// Junction geometry for every node, rebuilt whenever the road graph changes.
+#[derive(Resource, Default)]
+pub struct JunctionLayout {
+    junctions: HashMap<NodeIndex, JunctionGeometry>,
+}
+
+impl JunctionLayout {
+    pub fn get(&self, node: NodeIndex) -> Option<&JunctionGeometry> {
+        self.junctions.get(&node)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &JunctionGeometry> {
+        self.junctions.values()
+    }
+
+    /// How far `edge` is cut back from `node` (zero at dead ends).
+    pub fn setback(&self, node: NodeIndex, edge: EdgeIndex) -> f32 {
+        self.get(node).and_then(|j| j.leg(edge)).map_or(0.0, |leg| leg.setback)
+    }
+
+    pub fn turn_path(&self, node: NodeIndex, from: EdgeIndex, to: EdgeIndex) -> Option<&TurnPath> {
+        self.get(node)?.turn_path(from, to)
+    }
+}
+
+pub fn update_junction_layout(
+    road_graph: Res<RoadGraph>,
+    config: Res<IntersectionConfig>,
+    mut layout: ResMut<JunctionLayout>,
+) {
+    layout.junctions = road_graph
+        .nodes()
+        .filter_map(|(idx, node)| {
+            build_junction(idx, node.position, junction_legs(&road_graph, idx), &config).map(|j| (idx, j))
+        })
+        .collect();
+}
+
+/// The roads leaving a node, with their directions taken from the edge geometry.
+pub fn junction_legs(road_graph: &RoadGraph, node: NodeIndex) -> Vec<LegInput> {
+    let Some(position) = road_graph.node_by_index(node).map(|n| n.position) else {
+        return Vec::new();
+    };
+
+    let mut legs = Vec::new();
+    for edge_idx in road_graph.edges_of_node(node) {
+        let (Some((a, b)), Some(edge)) = (road_graph.edge_endpoints(edge_idx), road_graph.edge_by_index(edge_idx)) else {
+            continue;
+        };
+        if a == b || edge.points.len() < 2 {
+            continue;
+        }
+
+        // Walk in from whichever end of the polyline sits on this node
+        let n = edge.points.len();
+        let from_start = edge.points[0].distance_squared(position) <= edge.points[n - 1].distance_squared(position);
+        let (origin, next) = if from_start {
+            (edge.points[0], edge.points[1])
+        } else {
+            (edge.points[n - 1], edge.points[n - 2])
+        };
+        let direction = (next - origin).normalize_or_zero();
+        if direction == Vec2::ZERO {
+            continue;
+        }
+
+        // Forward travel runs from `a` to `b`
+        legs.push(LegInput {
+            edge: edge_idx,
+            neighbor: if a == node { b } else { a },
+            direction,
+            road_type: edge.road_type,
+            length: polyline_length(&edge.points),
+            incoming: edge.direction.allows(a != node),
+            outgoing: edge.direction.allows(a == node),
+        });
+    }
+    legs
+}
+
+/// Build a junction outline, markings and turn paths from the roads meeting at `center`.
+///
+/// Returns None for dead ends and isolated nodes.
+pub fn build_junction(
+    node: NodeIndex,
+    center: Vec2,
+    mut inputs: Vec<LegInput>,
+    config: &IntersectionConfig,
+) -> Option<JunctionGeometry> {
+    if inputs.len() < 2 {
+        return None;
+    }
+    inputs.sort_by(|a, b| a.direction.to_angle().total_cmp(&b.direction.to_angle()));
+
+    let count = inputs.len();
+    let half_widths: Vec<f32> = inputs.iter().map(|leg| carriageway_width(leg.road_type) / 2.0).collect();
+    // Keep junctions from eating more than their share of short edges
+    let limits: Vec<f32> = inputs.iter().map(|leg| leg.length * 0.45).collect();
+    let mut setbacks: Vec<f32> = limits.iter().map(|limit| config.min_setback.min(*limit)).collect();
+
+    // Corner shapes first; they decide how far each leg is set back
+    let mut shapes = Vec::with_capacity(count);
+    for i in 0..count {
+        let j = (i + 1) % count;
+        let (a, b) = (&inputs[i], &inputs[j]);
+
+        // Angle swept counter-clockwise from leg i to leg j
+        let mut theta = b.direction.to_angle() - a.direction.to_angle();
+        if theta <= 0.0 {
+            theta += TAU;
+        }
+
+        // Leg i's curb on its counter-clockwise side, leg j's on its clockwise side
+        let line_a = center + lane_side(a.direction) * half_widths[i];
+        let line_b = center - lane_side(b.direction) * half_widths[j];
+        let denom = a.direction.perp_dot(b.direction);
+        let crossing = (denom.abs() > 1e-3).then(|| {
+            let diff = line_b - line_a;
+            (diff.perp_dot(b.direction) / denom, diff.perp_dot(a.direction) / denom)
+        });
+
+        let shape = match crossing {
+            Some((s, u)) if theta < PI - 0.05 => {
+                let half = theta / 2.0;
+                let radius = config.curb_radius * (half_widths[i].min(half_widths[j]) / 2.5);
+                let cap = config.max_setback.min(limits[i] - s).min(limits[j] - u).max(0.0);
+                let tangent = (radius / half.tan()).min(cap);
+                setbacks[i] = setbacks[i].max(s + tangent);
+                setbacks[j] = setbacks[j].max(u + tangent);
+                CornerShape::Fillet {
+                    apex: line_a + a.direction * s,
+                    along: (s + tangent, u + tangent),
+                    radius: tangent * half.tan(),
+                    half_angle: half,
+                }
+            }
+            // Outside of a bend: the curbs meet behind the node
+            Some((s, u)) if theta > PI + 0.05 && s.min(u) > -4.0 * half_widths[i].max(half_widths[j]) => {
+                CornerShape::Mitre(line_a + a.direction * s)
+            }
+            _ => CornerShape::Open,
+        };
+        shapes.push(shape);
+    }
+    for (setback, limit) in setbacks.iter_mut().zip(&limits) {
+        *setback = setback.min(*limit);
+    }
+
+    let legs: Vec<JunctionLeg> = inputs
+        .iter()
+        .enumerate()
+        .map(|(i, input)| {
+            let d = input.direction;
+            let hw = half_widths[i];
+            let stop = center + d * (setbacks[i] + config.crosswalk_width + config.stop_line_gap);
+            // Incoming traffic drives on the clockwise side of the outward direction,
+            // or across the whole road if it is one-way inbound
+            let far_side = if input.outgoing { stop } else { stop + lane_side(d) * hw };
+            JunctionLeg {
+                edge: input.edge,
+                neighbor: input.neighbor,
+                direction: d,
+                road_type: input.road_type,
+                half_width: hw,
+                setback: setbacks[i],
+                incoming: input.incoming,
+                outgoing: input.outgoing,
+                stop_line: input.incoming.then_some([far_side, stop - lane_side(d) * hw]),
+                crosswalk_center: center + d * (setbacks[i] + config.crosswalk_width / 2.0),
+            }
+        })
+        .collect();
+
+    // Curb lines between neighbouring boundaries
+    let mut corners = Vec::with_capacity(count);
+    for (i, shape) in shapes.iter().enumerate() {
+        let j = (i + 1) % count;
+        let (a, b) = (&legs[i], &legs[j]);
+        let start = center + a.direction * a.setback + lane_side(a.direction) * a.half_width;
+        let end = center + b.direction * b.setback - lane_side(b.direction) * b.half_width;
+
+        let mut curb = vec![start];
+        let rounded = match *shape {
+            CornerShape::Fillet { apex, along, radius, half_angle } => {
+                let tangent_a = center + lane_side(a.direction) * a.half_width + a.direction * along.0;
+                let tangent_b = center - lane_side(b.direction) * b.half_width + b.direction * along.1;
+                let bisector = (a.direction + b.direction).normalize_or_zero();
+                let arc_center = apex + bisector * (radius / half_angle.sin());
+                let from = (tangent_a - arc_center).to_angle();
+                let mut sweep = (tangent_b - arc_center).to_angle() - from;
+                if sweep > PI {
+                    sweep -= TAU;
+                } else if sweep < -PI {
+                    sweep += TAU;
+                }
+                for k in 0..=config.arc_segments {
+                    let angle = from + sweep * k as f32 / config.arc_segments as f32;
+                    curb.push(arc_center + Vec2::from_angle(angle) * radius);
+                }
+                true
+            }
+            CornerShape::Mitre(apex) => {
+                curb.push(apex);
+                false
+            }
+            CornerShape::Open => false,
+        };
+        curb.push(end);
+        curb.dedup_by(|p, q| p.distance_squared(*q) < 1e-4);
+        corners.push(JunctionCorner { legs: (i, j), curb, rounded });
+    }
+
+    // Outline: across each leg's mouth, then round the corner to the next leg
+    let mut outline = Vec::new();
+    for (leg, corner) in legs.iter().zip(&corners) {
+        let mouth = center + leg.direction * leg.setback;
+        outline.push(mouth - lane_side(leg.direction) * leg.half_width);
+        outline.extend_from_slice(&corner.curb[..corner.curb.len() - 1]);
+    }
+
+    let mut turn_paths = Vec::new();
+    for from in legs.iter().filter(|leg| leg.incoming) {
+        for to in legs.iter().filter(|leg| leg.outgoing) {
+            if from.edge != to.edge {
+                turn_paths.push(turn_path(center, from, to, config));
+            }
+        }
+    }
+
+    Some(JunctionGeometry {
+        node,
+        center,
+        legs,
+        corners,
+        outline,
+        turn_paths,
+    })
+}
+
+enum CornerShape {
+    /// Circular curb return tangent to both curbs.
+    Fillet {
+        apex: Vec2,
+        /// Distances of the tangent points along each leg.
+        along: (f32, f32),
+        radius: f32,
+        half_angle: f32,
+    },
+    Mitre(Vec2),
+    Open,
+}
+
+/// Quadratic curve from the end of the incoming lane to the start of the outgoing one.
+fn turn_path(center: Vec2, from: &JunctionLeg, to: &JunctionLeg, config: &IntersectionConfig) -> TurnPath {
+    let heading = -from.direction;
+    let start = center + from.direction * from.setback + lane_side(heading) * lane_offset(from.road_type, !from.outgoing);
+    let end = center + to.direction * to.setback + lane_side(to.direction) * lane_offset(to.road_type, !to.incoming);
+
+    // Control point where the two lane centrelines cross
+    let midpoint = (start + end) / 2.0;
+    let denom = heading.perp_dot(to.direction);
+    let control = if denom.abs() > 0.1 {
+        let diff = end - start;
+        let t = diff.perp_dot(to.direction) / denom;
+        let v = diff.perp_dot(heading) / denom;
+        if t > 0.0 && v < 0.0 {
+            start + heading * t
+        } else {
+            midpoint
+        }
+    } else {
+        midpoint
+    };
+
+    let kind = if denom > 0.25 {
+        TurnKind::Right
+    } else if denom < -0.25 {
+        TurnKind::Left
+    } else if heading.dot(to.direction) > 0.0 {
+        TurnKind::Straight
+    } else {
+        TurnKind::UTurn
+    };
+
+    let points: Vec<Vec2> = (0..=config.turn_segments)
+        .map(|k| {
+            let t = k as f32 / config.turn_segments as f32;
+            let u = 1.0 - t;
+            start * (u * u) + control * (2.0 * u * t) + end * (t * t)
+        })
+        .collect();
+
+    TurnPath {
+        from_edge: from.edge,
+        to_edge: to.edge,
+        kind,
+        length: polyline_length(&points),
+        points,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leg(edge: usize, direction: Vec2, road_type: RoadType) -> LegInput {
+        LegInput {
+            edge: EdgeIndex::new(edge),
+            neighbor: NodeIndex::new(edge + 1),
+            direction,
+            road_type,
+            length: 100.0,
+            incoming: true,
+            outgoing: true,
+        }
+    }
+
+    #[test]
+    fn crossroads_set_back_by_curb_returns() {
+        let config = IntersectionConfig::default();
+        let legs = vec![
+            leg(0, Vec2::X, RoadType::Minor),
+            leg(1, Vec2::Y, RoadType::Minor),
+            leg(2, -Vec2::X, RoadType::Minor),
+            leg(3, -Vec2::Y, RoadType::Minor),
+        ];
+        let junction = build_junction(NodeIndex::new(0), Vec2::ZERO, legs, &config).unwrap();
+
+        // Curbs meet 2.5m along each leg; a 4m return at 90 degrees adds 4m
+        for leg in &junction.legs {
+            assert!((leg.setback - 6.5).abs() < 1e-3, "setback {}", leg.setback);
+        }
+        for corner in &junction.corners {
+            assert!(corner.rounded);
+            // Every point of the return stays outside the carriageway square
+            assert!(corner.curb.iter().all(|p| p.x.abs() >= 2.5 - 1e-3 && p.y.abs() >= 2.5 - 1e-3));
+        }
+        assert_eq!(junction.turn_paths.len(), 12);
+    }
+
+    #[test]
+    fn t_junction_turns_join_lanes_at_the_boundary() {
+        let config = IntersectionConfig::default();
+        let legs = vec![
+            leg(0, Vec2::X, RoadType::Major),
+            leg(1, -Vec2::X, RoadType::Major),
+            leg(2, Vec2::Y, RoadType::Minor),
+        ];
+        let junction = build_junction(NodeIndex::new(0), Vec2::ZERO, legs, &config).unwrap();
+
+        // The straight side of the T has no curb return
+        let straight = junction.corners.iter().find(|c| !c.rounded).unwrap();
+        let (a, b) = straight.legs;
+        assert!(junction.legs[a].direction.dot(junction.legs[b].direction) < -0.99);
+
+        // Heading east from the west arm and turning onto the side road
+        let west = EdgeIndex::new(1);
+        let side = EdgeIndex::new(2);
+        let path = junction.turn_path(west, side).unwrap();
+        assert_eq!(path.kind, TurnKind::Right);
+        let west_leg = junction.leg(west).unwrap();
+        let side_leg = junction.leg(side).unwrap();
+        let start = *path.points.first().unwrap();
+        let end = *path.points.last().unwrap();
+        assert!((start.x + west_leg.setback).abs() < 1e-3);
+        assert!((start.y - lane_offset(RoadType::Major, false)).abs() < 1e-3);
+        assert!((end.y - side_leg.setback).abs() < 1e-3);
+        assert!((end.x + lane_offset(RoadType::Minor, false)).abs() < 1e-3);
+
+        let through = junction.turn_path(west, EdgeIndex::new(0)).unwrap();
+        assert_eq!(through.kind, TurnKind::Straight);
+        assert!(through.points.iter().all(|p| (p.y - start.y).abs() < 1e-3));
+    }
+
+    #[test]
+    fn one_way_legs_limit_turns_and_signals() {
+        let config = IntersectionConfig::default();
+        let mut legs = vec![
+            leg(0, Vec2::X, RoadType::Minor),
+            leg(1, Vec2::Y, RoadType::Minor),
+            leg(2, -Vec2::X, RoadType::Minor),
+            leg(3, -Vec2::Y, RoadType::Minor),
+        ];
+        // North arm only leads away from the junction
+        legs[1].incoming = false;
+        let junction = build_junction(NodeIndex::new(0), Vec2::ZERO, legs, &config).unwrap();
+
+        assert!(junction.leg(EdgeIndex::new(1)).unwrap().stop_line.is_none());
+        assert!(junction.turn_paths.iter().all(|path| path.from_edge != EdgeIndex::new(1)));
+        assert_eq!(junction.turn_paths.len(), 9);
+
+        let groups = junction.signal_groups();
+        assert_eq!(groups.len(), 3);
+        let group_of = |edge: usize| groups.iter().find(|(e, _)| *e == EdgeIndex::new(edge)).unwrap().1;
+        assert_eq!(group_of(0), group_of(2));
+        assert_ne!(group_of(0), group_of(3));
+    }
+}
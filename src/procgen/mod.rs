@@ -1,6 +1,9 @@
 //! Procedural generation systems.
 //!
 //! - Tensor fields for road networks
+//! - Terrain grading for roads
+//! - Intersection geometry and turn paths
+//! - Roundabout and interchange templates
 //! - OBB subdivision for parcels
 //! - Shape grammars for buildings
 //! - Wave Function Collapse for zoning
@@ -11,6 +14,9 @@ use bevy::prelude::*;
 pub mod block_extractor;
 pub mod building_factory;
 pub mod buildings;
+pub mod grading;
+pub mod interchanges;
+pub mod intersections;
 pub mod lot_engine;
 pub mod lot_geometry;
 pub mod parcels;
@@ -29,6 +35,8 @@ impl Plugin for ProcgenPlugin {
         app.add_plugins(river::RiverPlugin)
             .add_plugins(tensor::TensorFieldPlugin)
             .add_plugins(roads::RoadsPlugin)
+            .add_plugins(grading::GradingPlugin)
+            .add_plugins(intersections::IntersectionsPlugin)
             .add_plugins(road_generator::RoadGeneratorPlugin)
             .add_plugins(block_extractor::BlockExtractorPlugin)
             .add_plugins(lot_engine::LotEnginePlugin)
@@ -1,6 +1,8 @@
 //! River generation for the city.
 //!
 //! Creates a meandering river that flows through the city using Perlin noise.
+//! Near its mouth the river can split into a delta of distributary channels
+//! and meet a sea along a noisy shoreline.
 //! Only generates in Procedural mode - Sandbox mode starts with a blank terrain.
 
 use bevy::prelude::*;
@@ -51,6 +53,22 @@ pub struct RiverConfig {
     pub city_size: f32,
     /// Number of points along river centerline.
     pub resolution: usize,
+    /// Number of channels the river splits into near its mouth (1 = no delta).
+    pub delta_channels: usize,
+    /// Fraction of the river's length at which the delta begins.
+    pub delta_start: f32,
+    /// Distance between neighbouring channel mouths.
+    pub delta_spread: f32,
+    /// Width of each distributary relative to the main channel.
+    pub distributary_width: f32,
+    /// Whether the river ends in a sea. The sea surface sits at `water_level`.
+    pub coastline: bool,
+    /// Fraction of the river's length at which the shoreline crosses it.
+    pub shore_position: f32,
+    /// How far the shoreline wanders in and out.
+    pub shore_roughness: f32,
+    /// Width of the beach sloping down into the sea.
+    pub beach_width: f32,
 }
 
 impl Default for RiverConfig {
@@ -66,6 +84,14 @@ impl Default for RiverConfig {
             bank_slope_width: 15.0,
             city_size: 500.0,
             resolution: 100,
+            delta_channels: 1,
+            delta_start: 0.55,
+            delta_spread: 70.0,
+            distributary_width: 0.6,
+            coastline: false,
+            shore_position: 0.85,
+            shore_roughness: 25.0,
+            beach_width: 40.0,
         }
     }
 }
@@ -81,6 +107,53 @@ pub struct RiverPoint {
     pub direction: Vec2,
 }
 
+/// A secondary channel splitting off the main river in a delta.
+#[derive(Clone, Debug, Default)]
+pub struct RiverChannel {
+    /// Points along the channel centerline.
+    pub centerline: Vec<RiverPoint>,
+    /// Left bank polyline (looking downstream).
+    pub left_bank: Vec<Vec2>,
+    /// Right bank polyline.
+    pub right_bank: Vec<Vec2>,
+}
+
+/// Boundary between land and sea.
+#[derive(Clone, Debug)]
+pub struct Shoreline {
+    /// Shore polyline, ordered along the coast and extending past the map.
+    pub points: Vec<Vec2>,
+    /// Unit direction pointing out to sea.
+    pub seaward: Vec2,
+    /// Width of the beach sloping down into the sea.
+    pub beach_width: f32,
+}
+
+impl Shoreline {
+    /// Signed distance to the shore. Negative = at sea, Positive = on land.
+    pub fn signed_distance(&self, point: Vec2) -> f32 {
+        let mut best = f32::MAX;
+        let mut nearest = point;
+        for window in self.points.windows(2) {
+            let ab = window[1] - window[0];
+            let t = ((point - window[0]).dot(ab) / ab.length_squared().max(1e-6)).clamp(0.0, 1.0);
+            let candidate = window[0] + ab * t;
+            let dist = point.distance_squared(candidate);
+            if dist < best {
+                best = dist;
+                nearest = candidate;
+            }
+        }
+
+        let dist = best.sqrt();
+        if (point - nearest).dot(self.seaward) > 0.0 {
+            -dist
+        } else {
+            dist
+        }
+    }
+}
+
 /// The river resource containing the generated river data.
 #[derive(Resource, Default)]
 pub struct River {
@@ -90,7 +163,11 @@ pub struct River {
     pub left_bank: Vec<Vec2>,
     /// Right bank polyline.
     pub right_bank: Vec<Vec2>,
-    /// Water surface height.
+    /// Delta channels branching off the main river.
+    pub distributaries: Vec<RiverChannel>,
+    /// Coast the river flows into, if any.
+    pub shoreline: Option<Shoreline>,
+    /// Water surface height (also the sea level).
     pub water_level: f32,
     /// Bank slope width for terrain carving.
     pub bank_slope_width: f32,
@@ -99,6 +176,39 @@ pub struct River {
 }
 
 impl River {
+    /// Whether any water was generated.
+    pub fn is_empty(&self) -> bool {
+        self.centerline.is_empty() && self.shoreline.is_none()
+    }
+
+    /// Check if a point is out at sea.
+    pub fn in_sea(&self, point: Vec2) -> bool {
+        self.shoreline
+            .as_ref()
+            .is_some_and(|shore| shore.signed_distance(point) < 0.0)
+    }
+
+    /// Signed distance to the nearest river or delta channel edge, ignoring the sea.
+    pub fn channel_distance(&self, point: Vec2) -> f32 {
+        std::iter::once(self.centerline.as_slice())
+            .chain(self.distributaries.iter().map(|c| c.centerline.as_slice()))
+            .flat_map(|centerline| centerline.iter())
+            .map(|river_point| point.distance(river_point.position) - river_point.width * 0.5)
+            .fold(f32::MAX, f32::min)
+    }
+
+    /// All bank polylines: main channel, distributaries, then the shoreline.
+    fn banks(&self) -> impl Iterator<Item = &[Vec2]> {
+        [self.left_bank.as_slice(), self.right_bank.as_slice()]
+            .into_iter()
+            .chain(
+                self.distributaries
+                    .iter()
+                    .flat_map(|c| [c.left_bank.as_slice(), c.right_bank.as_slice()]),
+            )
+            .chain(self.shoreline.iter().map(|shore| shore.points.as_slice()))
+    }
+
     /// Check if a 2D point is inside the river (between banks).
     pub fn contains_point(&self, point: Vec2) -> bool {
         // Quick bounding box rejection
@@ -112,32 +222,20 @@ impl River {
 
     /// Get signed distance from point to river edge.
     /// Negative = inside river, Positive = outside river.
+    /// Includes delta channels and the sea.
     pub fn signed_distance(&self, point: Vec2) -> f32 {
-        if self.centerline.is_empty() {
-            return f32::MAX;
-        }
-
-        let mut min_dist = f32::MAX;
-
-        // Find closest point on centerline and compute distance to edge
-        for river_point in &self.centerline {
-            let dist_to_center = point.distance(river_point.position);
-            let dist_to_edge = dist_to_center - river_point.width * 0.5;
-            min_dist = min_dist.min(dist_to_edge);
-        }
-
-        min_dist
+        let sea = self
+            .shoreline
+            .as_ref()
+            .map_or(f32::MAX, |shore| shore.signed_distance(point));
+        self.channel_distance(point).min(sea)
     }
 
     /// Check if a line segment intersects the river.
     /// Returns the first intersection point if any.
     pub fn intersects_segment(&self, start: Vec2, end: Vec2) -> Option<Vec2> {
-        if self.left_bank.len() < 2 {
-            return None;
-        }
-
-        // Check against both banks
-        for bank in [&self.left_bank, &self.right_bank] {
+        // Check against every bank and the shore
+        for bank in self.banks() {
             for window in bank.windows(2) {
                 if let Some(intersection) = segment_intersection(start, end, window[0], window[1]) {
                     return Some(intersection);
@@ -177,7 +275,7 @@ impl River {
         // Find intersections with both banks
         let mut intersections = Vec::new();
 
-        for bank in [&self.left_bank, &self.right_bank] {
+        for bank in self.banks() {
             for window in bank.windows(2) {
                 if let Some(pt) = segment_intersection(start, end, window[0], window[1]) {
                     intersections.push(pt);
@@ -196,6 +294,60 @@ impl River {
             None
         }
     }
+
+    /// Find where a polyline first enters and finally leaves the water.
+    ///
+    /// Crossing several delta channels in one go yields a single span from
+    /// the first bank to the last. Returns None if fewer than two banks are hit.
+    pub fn crossing_span(&self, points: &[Vec2]) -> Option<WaterCrossing> {
+        let mut hits: Vec<(f32, Vec2)> = Vec::new();
+        let mut travelled = 0.0;
+
+        for window in points.windows(2) {
+            for bank in self.banks() {
+                for edge in bank.windows(2) {
+                    if let Some(pt) = segment_intersection(window[0], window[1], edge[0], edge[1]) {
+                        hits.push((travelled + window[0].distance(pt), pt));
+                    }
+                }
+            }
+            travelled += window[0].distance(window[1]);
+        }
+
+        if hits.len() < 2 {
+            return None;
+        }
+
+        hits.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let (entry_distance, entry) = hits[0];
+        let (exit_distance, exit) = hits[hits.len() - 1];
+        Some(WaterCrossing {
+            entry,
+            exit,
+            entry_distance,
+            exit_distance,
+        })
+    }
+}
+
+/// Where a polyline crosses water, measured along the polyline.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WaterCrossing {
+    /// Bank point where the polyline enters the water.
+    pub entry: Vec2,
+    /// Bank point where the polyline leaves the water.
+    pub exit: Vec2,
+    /// Distance along the polyline to the entry point.
+    pub entry_distance: f32,
+    /// Distance along the polyline to the exit point.
+    pub exit_distance: f32,
+}
+
+impl WaterCrossing {
+    /// Straight-line length of the crossing.
+    pub fn span(&self) -> f32 {
+        self.entry.distance(self.exit)
+    }
 }
 
 /// Marker resource indicating river has been generated.
@@ -203,7 +355,7 @@ impl River {
 pub struct RiverGenerated(pub bool);
 
 /// Generate the river at startup.
-fn generate_river(
+pub fn generate_river(
     config: Res<RiverConfig>,
     mut river: ResMut<River>,
     mut generated: ResMut<RiverGenerated>,
@@ -259,41 +411,114 @@ fn generate_river(
         });
     }
 
-    // Smooth directions by averaging with neighbors
-    for i in 1..centerline.len() - 1 {
-        let prev = centerline[i - 1].position;
-        let next = centerline[i + 1].position;
-        centerline[i].direction = (next - prev).normalize();
-    }
-
-    // Generate bank polylines
-    let mut left_bank = Vec::with_capacity(config.resolution);
-    let mut right_bank = Vec::with_capacity(config.resolution);
-
-    for point in &centerline {
-        let perpendicular = Vec2::new(-point.direction.y, point.direction.x);
-        let half_width = point.width * 0.5;
+    smooth_directions(&mut centerline);
+    let (left_bank, right_bank) = build_banks(&centerline);
+
+    // Delta: distributaries peel off the main channel and fan out towards the mouth
+    let perpendicular = Vec2::new(-base_direction.y, base_direction.x);
+    let mut distributaries = Vec::new();
+    if config.delta_channels > 1 {
+        let branch_index = ((config.resolution - 1) as f32 * config.delta_start) as usize;
+        let branch = centerline[branch_index].position;
+        let mouth = start.lerp(end, config.shore_position.max(config.delta_start));
+        let remaining = config.resolution - branch_index;
+
+        for k in 1..config.delta_channels {
+            // Alternate sides of the main channel: +1, -1, +2, -2, ...
+            let rank = k.div_ceil(2) as f32;
+            let side = if k % 2 == 1 { 1.0 } else { -1.0 };
+            let channel_mouth = mouth + perpendicular * side * rank * config.delta_spread;
+            // Run past the shore so the channel opens into the sea
+            let channel_end = channel_mouth + base_direction * config.bank_slope_width * 2.0;
+            let channel_perlin = Perlin::new(config.seed.wrapping_add(2000 + k as u32));
+            // Bow towards the main channel first, then fan out
+            let control = branch.lerp(channel_end, 0.5) - perpendicular * side * rank * config.delta_spread * 0.5;
+
+            let mut channel: Vec<RiverPoint> = Vec::with_capacity(remaining);
+            for i in 0..remaining {
+                let t = i as f32 / (remaining - 1) as f32;
+                let base_pos = branch.lerp(control, t).lerp(control.lerp(channel_end, t), t);
+
+                // Meander less than the main river, and not at all at the branch point
+                let noise_val = channel_perlin.get([t as f64 * 6.0, 0.0]) as f32;
+                let position = base_pos + perpendicular * noise_val * config.meander_amplitude * 0.3 * t;
+
+                let width_scale = config.distributary_width + (1.0 - config.distributary_width) * (1.0 - t).powi(4);
+                let direction = if i == 0 {
+                    centerline[branch_index].direction
+                } else {
+                    (position - channel[i - 1].position).normalize_or(base_direction)
+                };
+
+                channel.push(RiverPoint {
+                    position,
+                    width: config.river_width * width_scale,
+                    direction,
+                });
+            }
 
-        left_bank.push(point.position + perpendicular * half_width);
-        right_bank.push(point.position - perpendicular * half_width);
+            smooth_directions(&mut channel);
+            let (left_bank, right_bank) = build_banks(&channel);
+            distributaries.push(RiverChannel {
+                centerline: channel,
+                left_bank,
+                right_bank,
+            });
+        }
     }
 
+    // Coast: a noisy line across the river's path, with the sea downstream
+    let shoreline = config.coastline.then(|| {
+        let shore_perlin = Perlin::new(config.seed.wrapping_add(3000));
+        let shore_center = start.lerp(end, config.shore_position);
+        let reach = config.city_size;
+        let steps = (reach * 2.0 / 10.0) as usize;
+        let points = (0..=steps)
+            .map(|i| {
+                let along = -reach + i as f32 * 10.0;
+                let wobble = shore_perlin.get([along as f64 * 0.01, 0.5]) as f32;
+                shore_center + perpendicular * along + base_direction * wobble * config.shore_roughness
+            })
+            .collect();
+        Shoreline {
+            points,
+            seaward: base_direction,
+            beach_width: config.beach_width,
+        }
+    });
+
     // Calculate bounding box
     let mut min = Vec2::splat(f32::MAX);
     let mut max = Vec2::splat(f32::MIN);
 
-    for point in left_bank.iter().chain(right_bank.iter()) {
+    let channel_banks = distributaries
+        .iter()
+        .flat_map(|c| c.left_bank.iter().chain(c.right_bank.iter()));
+    for point in left_bank.iter().chain(right_bank.iter()).chain(channel_banks) {
         min = min.min(*point);
         max = max.max(*point);
     }
 
+    // The sea covers everything beyond the shore
+    if let Some(shore) = &shoreline {
+        for point in &shore.points {
+            for p in [*point, *point + shore.seaward * config.city_size] {
+                min = min.min(p);
+                max = max.max(p);
+            }
+        }
+    }
+
     // Expand bounds slightly for bank slopes
-    min -= Vec2::splat(config.bank_slope_width);
-    max += Vec2::splat(config.bank_slope_width);
+    let slope = config.bank_slope_width.max(if config.coastline { config.beach_width } else { 0.0 });
+    min -= Vec2::splat(slope);
+    max += Vec2::splat(slope);
 
     river.centerline = centerline;
     river.left_bank = left_bank;
     river.right_bank = right_bank;
+    river.distributaries = distributaries;
+    river.shoreline = shoreline;
     river.water_level = config.water_level;
     river.bank_slope_width = config.bank_slope_width;
     river.bounds = Rect::from_corners(min, max);
@@ -301,13 +526,40 @@ fn generate_river(
     generated.0 = true;
 
     info!(
-        "River generated with {} points, width ~{:.0}m, water level {:.1}m",
+        "River generated with {} points, {} delta channels, width ~{:.0}m, water level {:.1}m{}",
         river.centerline.len(),
+        river.distributaries.len(),
         config.river_width,
-        config.water_level
+        config.water_level,
+        if river.shoreline.is_some() { ", with coastline" } else { "" }
     );
 }
 
+/// Point each centerline tangent along the chord between its neighbours.
+fn smooth_directions(centerline: &mut [RiverPoint]) {
+    for i in 1..centerline.len().saturating_sub(1) {
+        let prev = centerline[i - 1].position;
+        let next = centerline[i + 1].position;
+        centerline[i].direction = (next - prev).normalize_or(centerline[i].direction);
+    }
+}
+
+/// Left and right bank polylines for a channel centerline.
+fn build_banks(centerline: &[RiverPoint]) -> (Vec<Vec2>, Vec<Vec2>) {
+    let mut left_bank = Vec::with_capacity(centerline.len());
+    let mut right_bank = Vec::with_capacity(centerline.len());
+
+    for point in centerline {
+        let perpendicular = Vec2::new(-point.direction.y, point.direction.x);
+        let half_width = point.width * 0.5;
+
+        left_bank.push(point.position + perpendicular * half_width);
+        right_bank.push(point.position - perpendicular * half_width);
+    }
+
+    (left_bank, right_bank)
+}
+
 /// Line segment intersection test.
 /// Returns intersection point if segments intersect.
 fn segment_intersection(a1: Vec2, a2: Vec2, b1: Vec2, b2: Vec2) -> Option<Vec2> {
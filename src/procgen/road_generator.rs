@@ -6,14 +6,21 @@
 #![allow(dead_code)]
 //! 3. Building a graph with snapped intersections
 
+use std::sync::Arc;
+
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use smallvec::SmallVec;
 
 use crate::game_state::GameMode;
+use crate::world::terrain::{HeightMap, TerrainConfig};
 
+use super::grading::{grade_road_network, max_grade, GradingConfig};
 use super::river::River;
 use super::roads::{RoadGraph, RoadNodeType, RoadType};
-use super::streamline::{generate_seeds, Streamline, StreamlineConfig, StreamlineIntegrator};
+use super::streamline::{
+    generate_seeds, GradeLimit, Streamline, StreamlineConfig, StreamlineIntegrator,
+};
 use super::tensor::TensorField;
 
 /// Configuration for road generation.
@@ -31,6 +38,12 @@ pub struct RoadGenConfig {
     pub streamline: StreamlineConfig,
     /// Minimum road segment length.
     pub min_segment_length: f32,
+    /// How strongly slopes bend roads along contour lines (0 disables).
+    pub contour_sensitivity: f32,
+    /// Traverse length before a road climbing a steep slope switches back.
+    pub switchback_length: f32,
+    /// Decay of the pull aligning roads with rivers and the coast (0 disables).
+    pub water_influence: f32,
 }
 
 impl Default for RoadGenConfig {
@@ -47,6 +60,9 @@ impl Default for RoadGenConfig {
                 snap_distance: 8.0,
             },
             min_segment_length: 5.0,
+            contour_sensitivity: 12.0,
+            switchback_length: 60.0,
+            water_influence: 0.012,
         }
     }
 }
@@ -87,26 +103,39 @@ fn setup_sandbox_mode(mut road_graph: ResMut<RoadGraph>, mut generated: ResMut<R
     generated.0 = true;
 }
 
+/// The ground a road network is laid out on and graded into.
+#[derive(SystemParam)]
+struct RoadGround<'w> {
+    terrain: Res<'w, TerrainConfig>,
+    river: Res<'w, River>,
+    grading: Res<'w, GradingConfig>,
+    heights: ResMut<'w, HeightMap>,
+}
+
 fn generate_roads_on_event(
     mut events: EventReader<GenerateRoadsEvent>,
     mut tensor_field: ResMut<TensorField>,
     mut road_graph: ResMut<RoadGraph>,
     config: Res<RoadGenConfig>,
-    river: Res<River>,
+    mut ground: RoadGround,
     mut generated: ResMut<RoadsGenerated>,
 ) {
     for _ in events.read() {
         info!("Generating road network...");
 
-        // Clear existing
+        // Clear existing, including the cuts and fills of any earlier network
         *road_graph = RoadGraph::default();
         tensor_field.basis_fields.clear();
+        *ground.heights = HeightMap::for_world(&ground.terrain, &ground.river);
 
-        // Build the tensor field (with river influence)
-        build_tensor_field(&mut tensor_field, &config, &river);
+        // Build the tensor field (with river and terrain influence)
+        build_tensor_field(&mut tensor_field, &config, &ground.river, &ground.heights);
 
-        // Generate the road network (river-aware)
-        generate_road_network(&tensor_field, &mut road_graph, &config, &river);
+        // Generate the road network (river- and slope-aware)
+        generate_road_network(&tensor_field, &mut road_graph, &config, &ground.river, &ground.heights);
+
+        // Cut and fill the terrain along every road
+        let ungraded = grade_road_network(&road_graph, &mut ground.heights, &ground.grading);
 
         generated.0 = true;
 
@@ -114,26 +143,45 @@ fn generate_roads_on_event(
         let bridge_count = road_graph.edges().filter(|e| e.crosses_water).count();
 
         info!(
-            "Road generation complete: {} nodes, {} edges ({} bridges)",
+            "Road generation complete: {} nodes, {} edges ({} bridges, {} ungraded)",
             road_graph.node_count(),
             road_graph.edge_count(),
-            bridge_count
+            bridge_count,
+            ungraded
         );
     }
 }
 
-/// Build a tensor field with downtown radial + grid suburbs + river influence.
-fn build_tensor_field(field: &mut TensorField, config: &RoadGenConfig, river: &River) {
+/// Build a tensor field with downtown radial + grid suburbs + river and terrain influence.
+fn build_tensor_field(
+    field: &mut TensorField,
+    config: &RoadGenConfig,
+    river: &River,
+    heights: &HeightMap,
+) {
     // Global grid (aligned to axes or slight angle)
     field.add_grid(config.grid_angle);
 
     // Downtown radial field
     field.add_radial(config.downtown_center, config.radial_decay);
 
-    // Add river as a polyline to guide roads parallel to it
-    if !river.centerline.is_empty() {
-        let river_points: Vec<Vec2> = river.centerline.iter().map(|p| p.position).collect();
-        field.add_polyline(river_points, 0.012); // Moderate influence
+    // Add river channels and the coast as polylines to guide roads parallel to them
+    if config.water_influence > 0.0 {
+        let channels = std::iter::once(&river.centerline)
+            .chain(river.distributaries.iter().map(|c| &c.centerline))
+            .filter(|centerline| !centerline.is_empty());
+        for centerline in channels {
+            let points: Vec<Vec2> = centerline.iter().map(|p| p.position).collect();
+            field.add_polyline(points, config.water_influence);
+        }
+        if let Some(shore) = &river.shoreline {
+            field.add_polyline(shore.points.clone(), config.water_influence);
+        }
+    }
+
+    // Bend roads along contour lines on hilly ground
+    if config.contour_sensitivity > 0.0 {
+        field.add_contours(Arc::new(heights.clone()), config.contour_sensitivity);
     }
 }
 
@@ -143,6 +191,7 @@ fn generate_road_network(
     graph: &mut RoadGraph,
     config: &RoadGenConfig,
     river: &River,
+    heights: &HeightMap,
 ) {
     let half_size = config.city_size / 2.0;
     let bounds = Rect::new(-half_size, -half_size, half_size, half_size);
@@ -155,7 +204,15 @@ fn generate_road_network(
         .filter(|s| !river.contains_point(*s))
         .collect();
 
-    let integrator = StreamlineIntegrator::new(field, config.streamline.clone());
+    // Major and minor roads climb at their own grade limits
+    let grade_limit = |road_type| GradeLimit {
+        max_grade: max_grade(road_type),
+        switchback_length: config.switchback_length,
+    };
+    let major_integrator = StreamlineIntegrator::new(field, config.streamline.clone())
+        .with_terrain(heights, grade_limit(RoadType::Major));
+    let minor_integrator = StreamlineIntegrator::new(field, config.streamline.clone())
+        .with_terrain(heights, grade_limit(RoadType::Minor));
 
     // Track existing streamlines for separation checking
     let mut all_streamlines: Vec<Streamline> = Vec::new();
@@ -167,7 +224,7 @@ fn generate_road_network(
             continue;
         }
 
-        let streamline = integrator.trace(*seed, true);
+        let streamline = major_integrator.trace(*seed, true);
         if streamline.points.len() >= 3 {
             add_streamline_to_graph(&streamline, graph, config, RoadType::Major, river, true);
             all_streamlines.push(streamline);
@@ -181,7 +238,7 @@ fn generate_road_network(
             continue;
         }
 
-        let streamline = integrator.trace(*seed, false);
+        let streamline = minor_integrator.trace(*seed, false);
         if streamline.points.len() >= 3 {
             add_streamline_to_graph(&streamline, graph, config, RoadType::Minor, river, false);
             all_streamlines.push(streamline);
@@ -239,11 +296,11 @@ fn add_streamline_to_graph(
 
         // Check for water crossing transitions
         if in_water && !was_in_water {
-            // Entering water
-            if allow_bridges {
+            // Entering water (roads never bridge out to sea)
+            if allow_bridges && !river.in_sea(pos) {
                 water_entry = Some(prev_pos);
             } else {
-                // Minor road - terminate here
+                // Minor road or coast - terminate here
                 if segment_points.len() >= 2 {
                     let end_node = graph.add_node(prev_pos, RoadNodeType::DeadEnd);
                     if end_node != prev_node {
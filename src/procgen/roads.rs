@@ -4,7 +4,10 @@
 
 #![allow(dead_code)]
 
+use std::collections::HashMap;
+
 use bevy::prelude::*;
+use petgraph::algo::astar;
 use petgraph::graph::{EdgeIndex, NodeIndex, UnGraph};
 use petgraph::visit::EdgeRef;
 use smallvec::SmallVec;
@@ -13,7 +16,7 @@ pub struct RoadsPlugin;
 
 impl Plugin for RoadsPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<RoadGraph>();
+        app.init_resource::<RoadGraph>().add_event::<RoadsRemoved>();
     }
 }
 
@@ -29,10 +32,27 @@ pub enum RoadNodeType {
     Intersection,
     Endpoint,
     DeadEnd,
+    /// Where an approach road meets a roundabout's ring; entries give way.
+    Roundabout,
+    /// Where an interchange ramp leaves or joins a road; traffic merges freely.
+    Ramp,
+}
+
+impl RoadNodeType {
+    /// Junctions that never get traffic signals.
+    pub fn is_free_flow(self) -> bool {
+        matches!(self, RoadNodeType::Roundabout | RoadNodeType::Ramp)
+    }
 }
 
+/// Height of an overpass deck above the ground at its middle.
+pub const OVERPASS_CLEARANCE: f32 = 5.5;
+
+/// Fraction of an overpass at each end spent climbing to full height.
+pub const OVERPASS_RAMP: f32 = 0.35;
+
 /// An edge in the road network (road segment).
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct RoadEdge {
     /// Intermediate points along the road (for curved roads).
     pub points: SmallVec<[Vec2; 8]>,
@@ -46,9 +66,46 @@ pub struct RoadEdge {
     pub water_entry: Option<Vec2>,
     /// Exit point where road exits water (if crosses_water).
     pub water_exit: Option<Vec2>,
+    /// Whether this road segment runs through a tunnel between its end nodes.
+    pub tunnel: bool,
+    /// Whether this road segment rises over the roads it crosses.
+    pub overpass: bool,
+    /// Which way traffic may travel along the points.
+    pub direction: TravelDirection,
+    /// Raised median down the centreline.
+    pub median: bool,
+    /// Painted bike lanes along both edges.
+    pub bike_lanes: bool,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Which way traffic may travel along an edge, relative to its point order
+/// (forward runs from the first graph endpoint to the second).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TravelDirection {
+    #[default]
+    Both,
+    /// One-way along the points.
+    Forward,
+    /// One-way against the points.
+    Backward,
+}
+
+impl TravelDirection {
+    /// Whether travel along (`true`) or against (`false`) the points is allowed.
+    pub fn allows(self, forward: bool) -> bool {
+        match self {
+            TravelDirection::Both => true,
+            TravelDirection::Forward => forward,
+            TravelDirection::Backward => !forward,
+        }
+    }
+
+    pub fn is_one_way(self) -> bool {
+        self != TravelDirection::Both
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum RoadType {
     Highway,
     Major,
@@ -66,6 +123,11 @@ impl RoadEdge {
             crosses_water: false,
             water_entry: None,
             water_exit: None,
+            tunnel: false,
+            overpass: false,
+            direction: TravelDirection::Both,
+            median: false,
+            bike_lanes: false,
         }
     }
 
@@ -84,9 +146,75 @@ impl RoadEdge {
             crosses_water: true,
             water_entry: Some(water_entry),
             water_exit: Some(water_exit),
+            tunnel: false,
+            overpass: false,
+            direction: TravelDirection::Both,
+            median: false,
+            bike_lanes: false,
+        }
+    }
+
+    /// Create a road edge bored through the terrain.
+    pub fn new_tunnel(points: SmallVec<[Vec2; 8]>, road_type: RoadType) -> Self {
+        Self {
+            tunnel: true,
+            ..Self::new(points, road_type)
+        }
+    }
+
+    /// Create a road edge lifted over the roads it crosses.
+    pub fn new_overpass(points: SmallVec<[Vec2; 8]>, road_type: RoadType) -> Self {
+        Self {
+            overpass: true,
+            ..Self::new(points, road_type)
+        }
+    }
+
+    /// Whether this edge is a bridge, tunnel or overpass rather than a road on the ground.
+    pub fn is_structure(&self) -> bool {
+        self.crosses_water || self.tunnel || self.overpass
+    }
+
+    /// Height of the roadway above the ground at `progress` (0-1) along the edge.
+    ///
+    /// Overpasses climb over the first and last `OVERPASS_RAMP` of their length
+    /// and run level at `OVERPASS_CLEARANCE` in between; other edges stay on the ground.
+    pub fn deck_lift(&self, progress: f32) -> f32 {
+        if !self.overpass {
+            return 0.0;
+        }
+        let ramp = (progress.min(1.0 - progress).max(0.0) / OVERPASS_RAMP).min(1.0);
+        OVERPASS_CLEARANCE * ramp * ramp * (3.0 - 2.0 * ramp)
+    }
+
+    /// Traffic lanes (forward, backward) for the road class and direction.
+    ///
+    /// One-way roads put every lane in the open direction; two-way alleys
+    /// have a single shared lane.
+    pub fn lane_counts(&self) -> (usize, usize) {
+        let total = match self.road_type {
+            RoadType::Highway => 6,
+            RoadType::Major => 4,
+            RoadType::Minor => 2,
+            RoadType::Alley => 1,
+        };
+        match self.direction {
+            TravelDirection::Both => (total - total / 2, total / 2),
+            TravelDirection::Forward => (total, 0),
+            TravelDirection::Backward => (0, total),
         }
     }
 
+    /// Whether the road class can carry a median.
+    pub fn supports_median(&self) -> bool {
+        matches!(self.road_type, RoadType::Highway | RoadType::Major) && !self.direction.is_one_way()
+    }
+
+    /// Whether the road class can carry bike lanes.
+    pub fn supports_bike_lanes(&self) -> bool {
+        matches!(self.road_type, RoadType::Major | RoadType::Minor)
+    }
+
     fn calculate_length(points: &[Vec2]) -> f32 {
         points
             .windows(2)
@@ -204,6 +332,11 @@ impl RoadGraph {
         self.graph.edge_weight(idx)
     }
 
+    /// Get an edge by its index for in-place changes (type, direction, lanes).
+    pub fn edge_mut(&mut self, idx: EdgeIndex) -> Option<&mut RoadEdge> {
+        self.graph.edge_weight_mut(idx)
+    }
+
     /// Get the endpoint node indices for an edge.
     pub fn edge_endpoints(&self, idx: EdgeIndex) -> Option<(NodeIndex, NodeIndex)> {
         self.graph.edge_endpoints(idx)
@@ -232,9 +365,155 @@ impl RoadGraph {
     /// Remove a node by its index. Returns the node data if it existed.
     /// Note: This also removes all edges connected to this node.
     pub fn remove_node(&mut self, idx: NodeIndex) -> Option<RoadNode> {
-        // Remove from spatial index
+        // Remove from spatial index; petgraph moves the last node into the freed slot
         self.node_positions.retain(|(node_idx, _)| *node_idx != idx);
-        self.graph.remove_node(idx)
+        let last = NodeIndex::new(self.graph.node_count().saturating_sub(1));
+        let removed = self.graph.remove_node(idx);
+        if removed.is_some() && last != idx {
+            for (node_idx, _) in &mut self.node_positions {
+                if *node_idx == last {
+                    *node_idx = idx;
+                }
+            }
+        }
+        removed
+    }
+
+    /// Remove several edges, then any of their end nodes left unconnected.
+    ///
+    /// Removal shifts petgraph indices, so the result maps every old index
+    /// that moved or disappeared to its new value.
+    pub fn remove_edges(&mut self, edges: &[EdgeIndex]) -> RoadRemoval {
+        self.remove_edges_and_nodes(edges, None)
+    }
+
+    /// Remove several edges, then those of `nodes` left unconnected
+    /// (all end nodes of the removed edges when `nodes` is None).
+    pub fn remove_edges_and_nodes(&mut self, edges: &[EdgeIndex], nodes: Option<&[NodeIndex]>) -> RoadRemoval {
+        let mut removal = RoadRemoval::default();
+        // Original index currently occupying each slot
+        let mut edge_slots: Vec<EdgeIndex> = self.graph.edge_indices().collect();
+        let mut node_slots: Vec<NodeIndex> = self.graph.node_indices().collect();
+        let mut orphan_candidates = Vec::new();
+
+        for &original in edges {
+            let Some(slot) = edge_slots.iter().position(|&e| e == original) else {
+                continue;
+            };
+            let current = EdgeIndex::new(slot);
+            let (Some((a, b)), Some(edge)) = (self.graph.edge_endpoints(current), self.graph.remove_edge(current)) else {
+                continue;
+            };
+            edge_slots.swap_remove(slot);
+            let (from, to) = (self.graph[a].position, self.graph[b].position);
+            removal.edges.push(RemovedEdge { index: original, from, to, edge });
+            if nodes.is_none() {
+                orphan_candidates.extend([node_slots[a.index()], node_slots[b.index()]]);
+            }
+        }
+        orphan_candidates.extend(nodes.unwrap_or_default());
+
+        for original in orphan_candidates {
+            let Some(slot) = node_slots.iter().position(|&n| n == original) else {
+                continue;
+            };
+            let current = NodeIndex::new(slot);
+            if self.node_has_edges(current) {
+                continue;
+            }
+            if let Some(node) = self.remove_node(current) {
+                node_slots.swap_remove(slot);
+                removal.nodes.push(node);
+            }
+        }
+
+        for removed in &removal.edges {
+            removal.remap.edges.insert(removed.index, None);
+        }
+        for (slot, &original) in edge_slots.iter().enumerate() {
+            if original.index() != slot {
+                removal.remap.edges.insert(original, Some(EdgeIndex::new(slot)));
+            }
+        }
+        let kept: std::collections::HashSet<NodeIndex> = node_slots.iter().copied().collect();
+        for original in (0..node_slots.len() + removal.nodes.len()).map(NodeIndex::new) {
+            if !kept.contains(&original) {
+                removal.remap.nodes.insert(original, None);
+            }
+        }
+        for (slot, &original) in node_slots.iter().enumerate() {
+            if original.index() != slot {
+                removal.remap.nodes.insert(original, Some(NodeIndex::new(slot)));
+            }
+        }
+        removal
+    }
+
+    /// Edges with any part of their centreline inside `rect`.
+    pub fn edges_in_rect(&self, rect: Rect) -> Vec<EdgeIndex> {
+        self.graph
+            .edge_references()
+            .filter(|edge_ref| {
+                edge_ref
+                    .weight()
+                    .points
+                    .windows(2)
+                    .any(|w| segment_hits_rect(w[0], w[1], rect))
+            })
+            .map(|edge_ref| edge_ref.id())
+            .collect()
+    }
+
+    /// Node at `position`, give or take a few centimetres.
+    pub fn node_at(&self, position: Vec2) -> Option<NodeIndex> {
+        self.find_nearest(position, 0.05)
+    }
+
+    /// Node nearest `position`, within `radius`, among those `filter` accepts.
+    pub fn nearest_node_where(&self, position: Vec2, radius: f32, filter: impl Fn(NodeIndex) -> bool) -> Option<NodeIndex> {
+        self.nodes()
+            .filter(|&(idx, _)| filter(idx))
+            .map(|(idx, node)| (idx, node.position.distance(position)))
+            .filter(|&(_, distance)| distance <= radius)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(idx, _)| idx)
+    }
+
+    /// Shortest route between two nodes over edges accepted by `allow`,
+    /// as the edges to follow in order.
+    pub fn shortest_path(&self, from: NodeIndex, to: NodeIndex, allow: impl Fn(&RoadEdge) -> bool) -> Option<Vec<EdgeIndex>> {
+        let goal = self.node_by_index(to)?.position;
+        let (_, nodes) = astar(
+            &self.graph,
+            from,
+            |node| node == to,
+            |edge_ref| if allow(edge_ref.weight()) { edge_ref.weight().length } else { f32::INFINITY },
+            |node| self.graph[node].position.distance(goal),
+        )?;
+        let edges: Option<Vec<EdgeIndex>> = nodes.windows(2).map(|w| self.find_edge(w[0], w[1])).collect();
+        edges.filter(|edges| edges.iter().all(|&e| self.edge_by_index(e).is_some_and(&allow)))
+    }
+
+    /// Cheapest route between two nodes, where `cost` prices each edge given
+    /// whether it is travelled along its points, and infinity rules it out.
+    /// Costs must be at least the edge length, which keeps the straight-line
+    /// estimate to the goal admissible.
+    pub fn cheapest_path(&self, from: NodeIndex, to: NodeIndex, cost: impl Fn(&RoadEdge, bool) -> f32) -> Option<Vec<EdgeIndex>> {
+        let goal = self.node_by_index(to)?.position;
+        let (total, nodes) = astar(
+            &self.graph,
+            from,
+            |node| node == to,
+            |edge_ref| {
+                let forward = self.graph.edge_endpoints(edge_ref.id()).is_some_and(|(a, _)| a == edge_ref.source());
+                cost(edge_ref.weight(), forward)
+            },
+            |node| self.graph[node].position.distance(goal),
+        )?;
+        if !total.is_finite() {
+            return None;
+        }
+        nodes.windows(2).map(|w| self.find_edge(w[0], w[1])).collect()
     }
 
     /// Find the edge index between two nodes.
@@ -258,8 +537,181 @@ impl RoadGraph {
         self.add_node(position, node_type)
     }
 
+    /// Edge whose centreline passes closest to `position`, within `max_distance`.
+    ///
+    /// Returns the edge, the distance to it, and the unit direction of the
+    /// polyline at the closest point.
+    pub fn nearest_edge(&self, position: Vec2, max_distance: f32) -> Option<(EdgeIndex, f32, Vec2)> {
+        let mut best: Option<(EdgeIndex, f32, Vec2)> = None;
+        for edge_ref in self.graph.edge_references() {
+            for window in edge_ref.weight().points.windows(2) {
+                let span = window[1] - window[0];
+                let t = if span.length_squared() > 0.0 {
+                    ((position - window[0]).dot(span) / span.length_squared()).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                let distance = position.distance(window[0] + span * t);
+                if distance <= max_distance && best.is_none_or(|(_, d, _)| distance < d) {
+                    best = Some((edge_ref.id(), distance, span.normalize_or_zero()));
+                }
+            }
+        }
+        best
+    }
+
     /// Add an edge with full RoadEdge data (for undo/redo).
     pub fn add_edge_data(&mut self, a: NodeIndex, b: NodeIndex, edge: RoadEdge) -> EdgeIndex {
         self.graph.add_edge(a, b, edge)
     }
 }
+
+/// An edge taken out of the graph, with where its ends were.
+#[derive(Clone, Debug)]
+pub struct RemovedEdge {
+    /// Index the edge had before the removal.
+    pub index: EdgeIndex,
+    pub from: Vec2,
+    pub to: Vec2,
+    pub edge: RoadEdge,
+}
+
+/// How indices moved when roads were removed. Indices not listed are unchanged.
+#[derive(Clone, Debug, Default)]
+pub struct IndexRemap {
+    edges: HashMap<EdgeIndex, Option<EdgeIndex>>,
+    nodes: HashMap<NodeIndex, Option<NodeIndex>>,
+}
+
+impl IndexRemap {
+    /// New index of an edge, or None if it was removed.
+    pub fn edge(&self, old: EdgeIndex) -> Option<EdgeIndex> {
+        self.edges.get(&old).copied().unwrap_or(Some(old))
+    }
+
+    /// New index of a node, or None if it was removed.
+    pub fn node(&self, old: NodeIndex) -> Option<NodeIndex> {
+        self.nodes.get(&old).copied().unwrap_or(Some(old))
+    }
+}
+
+/// Everything a call to [`RoadGraph::remove_edges`] took out.
+#[derive(Clone, Debug, Default)]
+pub struct RoadRemoval {
+    pub edges: Vec<RemovedEdge>,
+    /// End nodes left without edges, removed with them.
+    pub nodes: Vec<RoadNode>,
+    pub remap: IndexRemap,
+}
+
+/// Sent after roads are removed so anything holding graph indices can
+/// drop or renumber them.
+#[derive(Event, Clone, Debug)]
+pub struct RoadsRemoved(pub RoadRemoval);
+
+/// Whether the segment `a`-`b` passes through `rect` (Liang-Barsky clip).
+fn segment_hits_rect(a: Vec2, b: Vec2, rect: Rect) -> bool {
+    let d = b - a;
+    let (mut t0, mut t1) = (0.0f32, 1.0f32);
+    for (p, q) in [
+        (-d.x, a.x - rect.min.x),
+        (d.x, rect.max.x - a.x),
+        (-d.y, a.y - rect.min.y),
+        (d.y, rect.max.y - a.y),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return false;
+            }
+            continue;
+        }
+        let r = q / p;
+        if p < 0.0 {
+            t0 = t0.max(r);
+        } else {
+            t1 = t1.min(r);
+        }
+        if t0 > t1 {
+            return false;
+        }
+    }
+    true
+}
+
+/// Total length of a polyline.
+pub fn polyline_length(points: &[Vec2]) -> f32 {
+    points.windows(2).map(|w| w[0].distance(w[1])).sum()
+}
+
+/// The part of a polyline between two distances along it.
+pub fn slice_polyline(points: &[Vec2], from: f32, to: f32) -> SmallVec<[Vec2; 8]> {
+    let mut section = SmallVec::new();
+    let mut travelled = 0.0;
+    for window in points.windows(2) {
+        let len = window[0].distance(window[1]);
+        let next = travelled + len;
+        if len > 0.0 && next >= from && travelled <= to {
+            if section.is_empty() {
+                section.push(window[0].lerp(window[1], ((from - travelled) / len).clamp(0.0, 1.0)));
+            }
+            section.push(window[0].lerp(window[1], ((to - travelled) / len).clamp(0.0, 1.0)));
+        }
+        travelled = next;
+    }
+    section
+}
+
+/// Straight road between two nodes, for tests that lay out small graphs.
+#[cfg(test)]
+pub fn test_road(from: NodeIndex, to: NodeIndex, road_type: RoadType, graph: &mut RoadGraph) -> EdgeIndex {
+    let points = smallvec::smallvec![graph.node_by_index(from).unwrap().position, graph.node_by_index(to).unwrap().position];
+    graph.add_edge_data(from, to, RoadEdge::new(points, road_type))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smallvec::smallvec;
+
+    /// A row of nodes 0-1-2-3 joined left to right.
+    fn row() -> RoadGraph {
+        let mut graph = RoadGraph::default();
+        let nodes: Vec<_> = (0..4)
+            .map(|i| graph.add_node(Vec2::new(i as f32 * 50.0, 0.0), RoadNodeType::Intersection))
+            .collect();
+        for w in nodes.windows(2) {
+            let points = smallvec![graph.graph[w[0]].position, graph.graph[w[1]].position];
+            graph.add_edge_data(w[0], w[1], RoadEdge::new(points, RoadType::Minor));
+        }
+        graph
+    }
+
+    #[test]
+    fn removing_edges_drops_orphans_and_remaps_indices() {
+        let mut graph = row();
+        // Remove the first edge: node 0 is orphaned, edge 2 moves into slot 0
+        let removal = graph.remove_edges(&[EdgeIndex::new(0)]);
+
+        assert_eq!(removal.edges.len(), 1);
+        assert_eq!(removal.nodes.len(), 1);
+        assert_eq!(graph.node_count(), 3);
+        assert_eq!(removal.remap.edge(EdgeIndex::new(0)), None);
+        assert_eq!(removal.remap.edge(EdgeIndex::new(1)), Some(EdgeIndex::new(1)));
+        assert_eq!(removal.remap.edge(EdgeIndex::new(2)), Some(EdgeIndex::new(0)));
+        assert_eq!(removal.remap.node(NodeIndex::new(0)), None);
+        assert_eq!(removal.remap.node(NodeIndex::new(3)), Some(NodeIndex::new(0)));
+
+        // The moved node is still found where it stands
+        assert_eq!(graph.node_at(Vec2::new(150.0, 0.0)), Some(NodeIndex::new(0)));
+        let moved = graph.edge_by_index(EdgeIndex::new(0)).unwrap();
+        assert_eq!(moved.points[1], Vec2::new(150.0, 0.0));
+    }
+
+    #[test]
+    fn edges_in_rect_clip_segments() {
+        let graph = row();
+        let hit = graph.edges_in_rect(Rect::new(60.0, -5.0, 70.0, 5.0));
+        assert_eq!(hit, vec![EdgeIndex::new(1)]);
+        assert!(graph.edges_in_rect(Rect::new(60.0, 5.0, 70.0, 10.0)).is_empty());
+    }
+}
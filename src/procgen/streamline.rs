@@ -7,6 +7,7 @@
 use bevy::prelude::*;
 
 use super::tensor::TensorField;
+use crate::world::terrain::HeightMap;
 
 /// Configuration for streamline integration.
 #[derive(Clone, Debug)]
@@ -32,6 +33,15 @@ impl Default for StreamlineConfig {
     }
 }
 
+/// Slope limit applied while tracing a streamline over terrain.
+#[derive(Clone, Copy, Debug)]
+pub struct GradeLimit {
+    /// Maximum rise over run the road may climb.
+    pub max_grade: f32,
+    /// Distance to traverse across a slope before turning back (switchback).
+    pub switchback_length: f32,
+}
+
 /// A point along a streamline.
 #[derive(Clone, Copy, Debug)]
 pub struct StreamlinePoint {
@@ -50,11 +60,26 @@ pub struct Streamline {
 pub struct StreamlineIntegrator<'a> {
     field: &'a TensorField,
     config: StreamlineConfig,
+    terrain: Option<(&'a HeightMap, GradeLimit)>,
 }
 
 impl<'a> StreamlineIntegrator<'a> {
     pub fn new(field: &'a TensorField, config: StreamlineConfig) -> Self {
-        Self { field, config }
+        Self {
+            field,
+            config,
+            terrain: None,
+        }
+    }
+
+    /// Limit streamlines to a maximum grade over the given terrain.
+    ///
+    /// Where the field points up a slope steeper than the limit, the
+    /// streamline traverses across the slope at the maximum grade instead,
+    /// turning back every `switchback_length` to form switchbacks.
+    pub fn with_terrain(mut self, heights: &'a HeightMap, limit: GradeLimit) -> Self {
+        self.terrain = Some((heights, limit));
+        self
     }
 
     /// Trace a streamline from a seed point in both directions.
@@ -80,6 +105,7 @@ impl<'a> StreamlineIntegrator<'a> {
         let mut points = Vec::new();
         let mut pos = seed;
         let mut prev_dir = Vec2::ZERO;
+        let mut traverse: Option<Traverse> = None;
 
         for _ in 0..self.config.max_steps {
             let tensor = self.field.sample(pos);
@@ -95,14 +121,20 @@ impl<'a> StreamlineIntegrator<'a> {
             }
             dir *= sign;
 
+            // RK4 integration step
+            let mut new_pos = self.rk4_step(pos, use_major, sign);
+
+            // Bend across the slope when the field climbs too steeply
+            if let Some(step) = self.limit_grade(pos, new_pos - pos, &mut traverse) {
+                dir = step.normalize_or_zero();
+                new_pos = pos + step;
+            }
+
             points.push(StreamlinePoint {
                 position: pos,
                 direction: dir,
             });
 
-            // RK4 integration step
-            let new_pos = self.rk4_step(pos, use_major, sign);
-
             // Check for degenerate tensor
             if (new_pos - pos).length() < 0.001 {
                 break;
@@ -115,6 +147,43 @@ impl<'a> StreamlineIntegrator<'a> {
         points
     }
 
+    /// Replace a step that climbs faster than the grade limit with one that
+    /// traverses the slope at exactly the limit. Returns `None` when the step
+    /// is acceptable as-is.
+    fn limit_grade(&self, pos: Vec2, step: Vec2, traverse: &mut Option<Traverse>) -> Option<Vec2> {
+        let (heights, limit) = self.terrain?;
+        let len = step.length();
+        if len < 0.001 {
+            return None;
+        }
+
+        let gradient = heights.gradient(pos);
+        let slope = gradient.length();
+        let rise = gradient.dot(step) / len;
+        if rise.abs() <= limit.max_grade || slope < 0.0001 {
+            *traverse = None;
+            return None;
+        }
+
+        let uphill = gradient / slope;
+        let contour = Vec2::new(-uphill.y, uphill.x);
+        let state = traverse.get_or_insert(Traverse {
+            side: if contour.dot(step) >= 0.0 { 1.0 } else { -1.0 },
+            travelled: 0.0,
+        });
+
+        // Hairpin: reverse across the slope and keep climbing
+        if state.travelled >= limit.switchback_length {
+            state.side = -state.side;
+            state.travelled = 0.0;
+        }
+        state.travelled += len;
+
+        let climb = rise.signum() * (limit.max_grade / slope);
+        let across = (1.0 - climb * climb).max(0.0).sqrt();
+        Some((contour * state.side * across + uphill * climb) * len)
+    }
+
     /// Single RK4 integration step.
     fn rk4_step(&self, pos: Vec2, use_major: bool, sign: f32) -> Vec2 {
         let h = self.config.step_size;
@@ -134,6 +203,14 @@ impl<'a> StreamlineIntegrator<'a> {
     }
 }
 
+/// Progress of a streamline traversing a slope that is too steep to climb directly.
+struct Traverse {
+    /// Which way along the contour the road is heading (+1 or -1).
+    side: f32,
+    /// Distance covered since the last switchback.
+    travelled: f32,
+}
+
 /// Generate a grid of seed points for streamline tracing.
 pub fn generate_seeds(bounds: Rect, spacing: f32) -> Vec<Vec2> {
     let mut seeds = Vec::new();
@@ -5,8 +5,12 @@
 
 #![allow(dead_code)]
 
+use std::sync::Arc;
+
 use bevy::prelude::*;
 
+use crate::world::terrain::HeightMap;
+
 pub struct TensorFieldPlugin;
 
 impl Plugin for TensorFieldPlugin {
@@ -76,6 +80,8 @@ pub enum BasisField {
     Radial { center: Vec2 },
     /// Field aligned to a polyline (river, highway).
     Polyline { points: Vec<Vec2> },
+    /// Field aligned to terrain contour lines, strongest on steep ground.
+    Contour { heights: Arc<HeightMap> },
 }
 
 impl BasisField {
@@ -107,6 +113,15 @@ impl BasisField {
 
                 Tensor::from_direction(closest_dir)
             }
+            BasisField::Contour { heights } => {
+                let gradient = heights.gradient(pos);
+                if gradient.length_squared() < 1e-8 {
+                    Tensor::new(0.0, 0.0)
+                } else {
+                    // Major eigenvector runs along the contour, minor climbs it
+                    Tensor::from_direction(Vec2::new(-gradient.y, gradient.x).normalize())
+                }
+            }
         }
     }
 
@@ -138,6 +153,11 @@ impl BasisField {
                 }
                 (-min_dist * decay).exp()
             }
+            BasisField::Contour { heights } => {
+                // Here `decay` acts as slope sensitivity: flat ground has no pull
+                let s = heights.slope(pos) * decay;
+                s * s
+            }
         }
     }
 }
@@ -185,4 +205,10 @@ impl TensorField {
         self.basis_fields
             .push((BasisField::Polyline { points }, decay));
     }
+
+    /// Add a terrain contour basis field with the given slope sensitivity.
+    pub fn add_contours(&mut self, heights: Arc<HeightMap>, sensitivity: f32) {
+        self.basis_fields
+            .push((BasisField::Contour { heights }, sensitivity));
+    }
 }
@@ -1,10 +1,16 @@
 //! Bridge rendering for roads that cross water.
 //!
 //! Creates bridge meshes with elevated deck, railings, and support pillars.
+//! Bridges are kept in sync with the road graph: when edges are drawn, undone
+//! or demolished only the affected bridges are spawned or despawned.
 
+use std::collections::HashSet;
+
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy::render::mesh::{Indices, PrimitiveTopology};
 
+use crate::procgen::intersections::carriageway_width;
 use crate::procgen::river::River;
 use crate::procgen::road_generator::RoadsGenerated;
 use crate::procgen::roads::{RoadGraph, RoadType};
@@ -15,12 +21,17 @@ impl Plugin for BridgesPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<BridgeConfig>()
             .init_resource::<BridgesSpawned>()
-            .add_systems(Update, spawn_bridges.run_if(should_spawn_bridges));
+            .add_systems(Startup, setup_bridge_materials)
+            .add_systems(Update, sync_bridges.run_if(should_sync_bridges));
     }
 }
 
-fn should_spawn_bridges(generated: Res<RoadsGenerated>, spawned: Res<BridgesSpawned>) -> bool {
-    generated.0 && !spawned.0
+fn should_sync_bridges(
+    generated: Res<RoadsGenerated>,
+    spawned: Res<BridgesSpawned>,
+    road_graph: Res<RoadGraph>,
+) -> bool {
+    generated.0 && (!spawned.0 || road_graph.is_changed())
 }
 
 /// Marker resource indicating bridges have been spawned.
@@ -57,10 +68,24 @@ impl Default for BridgeConfig {
     }
 }
 
+/// Shared materials for all bridges.
+#[derive(Resource)]
+struct BridgeMaterials {
+    deck: Handle<StandardMaterial>,
+    railing: Handle<StandardMaterial>,
+    pillar: Handle<StandardMaterial>,
+}
+
 /// Marker component for bridge entities.
+///
+/// Railings and pillars are spawned as children of the deck.
 #[derive(Component)]
 pub struct Bridge {
     pub road_type: RoadType,
+    /// Bank point where the bridge starts.
+    pub entry: Vec2,
+    /// Bank point where the bridge ends.
+    pub exit: Vec2,
 }
 
 /// Marker component for bridge railing.
@@ -71,136 +96,182 @@ pub struct BridgeRailing;
 #[derive(Component)]
 pub struct BridgePillar;
 
-/// Spawn bridge meshes for all water crossings.
-fn spawn_bridges(
-    mut commands: Commands,
-    road_graph: Res<RoadGraph>,
-    river: Res<River>,
-    config: Res<BridgeConfig>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    mut spawned: ResMut<BridgesSpawned>,
-) {
-    info!("Spawning bridges...");
+/// Identity of a bridge, rounded so float noise doesn't cause respawns.
+type BridgeKey = (IVec2, IVec2, RoadType);
 
-    // Bridge deck material (concrete/asphalt)
-    let deck_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.4, 0.4, 0.45),
-        perceptual_roughness: 0.85,
-        ..default()
-    });
+fn bridge_key(entry: Vec2, exit: Vec2, road_type: RoadType) -> BridgeKey {
+    ((entry * 10.0).round().as_ivec2(), (exit * 10.0).round().as_ivec2(), road_type)
+}
 
-    // Railing material (metal)
-    let railing_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.3, 0.3, 0.35),
-        metallic: 0.7,
-        perceptual_roughness: 0.4,
-        ..default()
+fn setup_bridge_materials(mut commands: Commands, mut materials: ResMut<Assets<StandardMaterial>>) {
+    commands.insert_resource(BridgeMaterials {
+        // Bridge deck material (concrete/asphalt)
+        deck: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.4, 0.4, 0.45),
+            perceptual_roughness: 0.85,
+            ..default()
+        }),
+        // Railing material (metal)
+        railing: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.3, 0.3, 0.35),
+            metallic: 0.7,
+            perceptual_roughness: 0.4,
+            ..default()
+        }),
+        // Pillar material (concrete)
+        pillar: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.5, 0.48, 0.45),
+            perceptual_roughness: 0.9,
+            ..default()
+        }),
     });
+}
 
-    // Pillar material (concrete)
-    let pillar_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.5, 0.48, 0.45),
-        perceptual_roughness: 0.9,
-        ..default()
-    });
+/// Everything a bridge is spawned with.
+#[derive(SystemParam)]
+struct BridgeBuilder<'w, 's> {
+    commands: Commands<'w, 's>,
+    meshes: ResMut<'w, Assets<Mesh>>,
+    bridge_materials: Res<'w, BridgeMaterials>,
+    config: Res<'w, BridgeConfig>,
+    river: Res<'w, River>,
+}
 
-    let mut bridge_count = 0;
+/// Spawn meshes for new water crossings and despawn those whose edge is gone.
+fn sync_bridges(
+    road_graph: Res<RoadGraph>,
+    builder: BridgeBuilder,
+    existing: Query<(Entity, &Bridge)>,
+    mut spawned: ResMut<BridgesSpawned>,
+) {
+    let BridgeBuilder { mut commands, mut meshes, bridge_materials, config, river } = builder;
+    // Every water crossing currently in the graph
+    let mut wanted: HashSet<BridgeKey> = road_graph
+        .edges()
+        .filter(|edge| edge.crosses_water)
+        .filter_map(|edge| match (edge.water_entry, edge.water_exit) {
+            (Some(entry), Some(exit)) => Some(bridge_key(entry, exit, edge.road_type)),
+            _ => None,
+        })
+        .collect();
+
+    // Keep bridges that still have an edge, remove the rest
+    let mut removed = 0;
+    for (entity, bridge) in &existing {
+        if !wanted.remove(&bridge_key(bridge.entry, bridge.exit, bridge.road_type)) {
+            commands.entity(entity).despawn_recursive();
+            removed += 1;
+        }
+    }
 
-    // Find all edges that cross water
+    let mut added = 0;
     for edge in road_graph.edges() {
-        if !edge.crosses_water {
+        let (Some(entry), Some(exit)) = (edge.water_entry, edge.water_exit) else {
             continue;
-        }
-
-        let (entry, exit) = match (edge.water_entry, edge.water_exit) {
-            (Some(e), Some(x)) => (e, x),
-            _ => continue,
         };
+        if !edge.crosses_water || !wanted.remove(&bridge_key(entry, exit, edge.road_type)) {
+            continue;
+        }
 
-        let road_width = match edge.road_type {
-            RoadType::Highway => 12.0,
-            RoadType::Major => 8.0,
-            RoadType::Minor => 5.0,
-            RoadType::Alley => 3.0,
+        let bridge = Bridge {
+            road_type: edge.road_type,
+            entry,
+            exit,
         };
+        spawn_bridge(&mut commands, &mut meshes, &bridge_materials, &config, &river, bridge);
+        added += 1;
+    }
 
-        // Calculate bridge geometry
-        let direction = (exit - entry).normalize_or_zero();
-        let perpendicular = Vec2::new(-direction.y, direction.x);
-        let bridge_length = entry.distance(exit);
-        let center = (entry + exit) / 2.0;
-
-        // Bridge deck height (above water)
-        let deck_y = river.water_level + config.deck_height;
+    if !spawned.0 {
+        info!("Spawned {} bridges", added);
+    } else if added > 0 || removed > 0 {
+        info!("Bridges updated: {} added, {} removed", added, removed);
+    }
+    spawned.0 = true;
+}
 
-        // Create bridge deck mesh
-        let deck_mesh = create_bridge_deck(
-            entry,
-            exit,
-            road_width,
-            config.deck_thickness,
-            deck_y,
-        );
+/// Spawn the deck, railings, and pillars for one water crossing.
+fn spawn_bridge(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &BridgeMaterials,
+    config: &BridgeConfig,
+    river: &River,
+    bridge: Bridge,
+) {
+    let (entry, exit) = (bridge.entry, bridge.exit);
+    let road_width = carriageway_width(bridge.road_type);
 
-        commands.spawn((
+    // Calculate bridge geometry
+    let direction = (exit - entry).normalize_or_zero();
+    let perpendicular = Vec2::new(-direction.y, direction.x);
+    let bridge_length = entry.distance(exit);
+
+    // Bridge deck height (above water)
+    let deck_y = river.water_level + config.deck_height;
+
+    // Create bridge deck mesh
+    let deck_mesh = create_bridge_deck(
+        entry,
+        exit,
+        road_width,
+        config.deck_thickness,
+        deck_y,
+    );
+
+    commands
+        .spawn((
             Mesh3d(meshes.add(deck_mesh)),
-            MeshMaterial3d(deck_material.clone()),
+            MeshMaterial3d(materials.deck.clone()),
             Transform::IDENTITY,
-            Bridge {
-                road_type: edge.road_type,
-            },
-        ));
-
-        // Create railings on both sides
-        let railing_offset = road_width / 2.0 + config.railing_width / 2.0;
-
-        for side in [-1.0, 1.0] {
-            let railing_mesh = create_railing(
-                entry + perpendicular * railing_offset * side,
-                exit + perpendicular * railing_offset * side,
-                config.railing_width,
-                config.railing_height,
-                deck_y,
-            );
-
-            commands.spawn((
-                Mesh3d(meshes.add(railing_mesh)),
-                MeshMaterial3d(railing_material.clone()),
-                Transform::IDENTITY,
-                BridgeRailing,
-            ));
-        }
-
-        // Create support pillars
-        if config.pillar_count > 0 && bridge_length > 10.0 {
-            let pillar_depth = river.water_level - (-3.0); // Extend below water
-
-            for i in 0..config.pillar_count {
-                let t = (i as f32 + 1.0) / (config.pillar_count as f32 + 1.0);
-                let pillar_pos = entry.lerp(exit, t);
-
-                let pillar_mesh = create_pillar(
-                    pillar_pos,
-                    config.pillar_width,
-                    pillar_depth + config.deck_height,
-                    deck_y - config.deck_thickness,
+            Visibility::default(),
+            bridge,
+        ))
+        .with_children(|parent| {
+            // Create railings on both sides
+            let railing_offset = road_width / 2.0 + config.railing_width / 2.0;
+
+            for side in [-1.0, 1.0] {
+                let railing_mesh = create_railing(
+                    entry + perpendicular * railing_offset * side,
+                    exit + perpendicular * railing_offset * side,
+                    config.railing_width,
+                    config.railing_height,
+                    deck_y,
                 );
 
-                commands.spawn((
-                    Mesh3d(meshes.add(pillar_mesh)),
-                    MeshMaterial3d(pillar_material.clone()),
+                parent.spawn((
+                    Mesh3d(meshes.add(railing_mesh)),
+                    MeshMaterial3d(materials.railing.clone()),
                     Transform::IDENTITY,
-                    BridgePillar,
+                    BridgeRailing,
                 ));
             }
-        }
-
-        bridge_count += 1;
-    }
 
-    spawned.0 = true;
-    info!("Spawned {} bridges", bridge_count);
+            // Create support pillars
+            if config.pillar_count > 0 && bridge_length > 10.0 {
+                let pillar_depth = river.water_level - (-3.0); // Extend below water
+
+                for i in 0..config.pillar_count {
+                    let t = (i as f32 + 1.0) / (config.pillar_count as f32 + 1.0);
+                    let pillar_pos = entry.lerp(exit, t);
+
+                    let pillar_mesh = create_pillar(
+                        pillar_pos,
+                        config.pillar_width,
+                        pillar_depth + config.deck_height,
+                        deck_y - config.deck_thickness,
+                    );
+
+                    parent.spawn((
+                        Mesh3d(meshes.add(pillar_mesh)),
+                        MeshMaterial3d(materials.pillar.clone()),
+                        Transform::IDENTITY,
+                        BridgePillar,
+                    ));
+                }
+            }
+        });
 }
 
 /// Create a bridge deck mesh (flat box along the crossing).
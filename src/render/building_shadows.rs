@@ -4,10 +4,9 @@
 
 use bevy::prelude::*;
 use bevy::render::mesh::{Indices, MeshAabb, PrimitiveTopology};
-use noise::{NoiseFn, Perlin};
 
 use crate::render::building_spawner::{Building, BuildingsSpawned};
-use crate::render::instancing::TerrainConfig;
+use crate::world::terrain::{HeightMap, TerrainAnchor};
 
 pub struct BuildingShadowsPlugin;
 
@@ -62,14 +61,13 @@ impl Default for BuildingShadowConfig {
 fn spawn_building_shadows(
     mut commands: Commands,
     config: Res<BuildingShadowConfig>,
-    terrain_config: Res<TerrainConfig>,
+    terrain: Res<HeightMap>,
     building_query: Query<(&Building, &Transform, &Mesh3d), With<Building>>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
     info!("Spawning building shadows...");
 
-    let terrain = TerrainSampler::new(&terrain_config);
 
     // Semi-transparent shadow material
     let shadow_material = materials.add(StandardMaterial {
@@ -113,7 +111,7 @@ fn spawn_building_shadows(
         let shadow_depth = (building_depth + config.size_padding) * config.spread;
 
         // Sample terrain height at shadow center
-        let terrain_height = terrain.sample(shadow_center.x, shadow_center.y);
+        let terrain_height = terrain.sample_world(shadow_center);
 
         // Create shadow mesh (simple quad)
         let shadow_mesh = create_shadow_quad(shadow_width, shadow_depth);
@@ -127,6 +125,7 @@ fn spawn_building_shadows(
                 shadow_center.y,
             ),
             BuildingShadow,
+            TerrainAnchor { offset: config.height_offset },
         ));
 
         shadow_count += 1;
@@ -170,40 +169,3 @@ fn create_shadow_quad(width: f32, depth: f32) -> Mesh {
         .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
         .with_inserted_indices(Indices::U32(indices))
 }
-
-/// Helper struct for sampling terrain height.
-struct TerrainSampler {
-    perlin: Perlin,
-    noise_scale: f32,
-    height_scale: f32,
-    octaves: u32,
-}
-
-impl TerrainSampler {
-    fn new(config: &TerrainConfig) -> Self {
-        Self {
-            perlin: Perlin::new(config.seed),
-            noise_scale: config.noise_scale,
-            height_scale: config.height_scale,
-            octaves: config.octaves,
-        }
-    }
-
-    fn sample(&self, x: f32, z: f32) -> f32 {
-        let mut height = 0.0;
-        let mut amplitude = 1.0;
-        let mut frequency = self.noise_scale;
-        let mut max_amplitude = 0.0;
-
-        for _ in 0..self.octaves {
-            let sample_x = x as f64 * frequency as f64;
-            let sample_z = z as f64 * frequency as f64;
-            height += self.perlin.get([sample_x, sample_z]) as f32 * amplitude;
-            max_amplitude += amplitude;
-            amplitude *= 0.5;
-            frequency *= 2.0;
-        }
-
-        (height / max_amplitude) * self.height_scale
-    }
-}
@@ -8,7 +8,6 @@
 #![allow(dead_code)]
 
 use bevy::prelude::*;
-use noise::{NoiseFn, Perlin};
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
 
@@ -20,7 +19,7 @@ use crate::render::building_instances::{
     BuildingInstanceBuffer, BuildingInstanceData, BuildingMaterialPalette, BuildingRef,
 };
 use crate::render::gpu_culling::GpuCullable;
-use crate::render::instancing::TerrainConfig;
+use crate::world::terrain::HeightMap;
 use crate::render::mesh_pools::{BuildingMeshPool, VegetationMeshPool};
 
 pub struct BuildingSpawnerPlugin;
@@ -67,7 +66,7 @@ fn spawn_buildings(
     mut commands: Commands,
     blueprints: Res<BuildingBlueprints>,
     config: Res<BuildingConfig>,
-    terrain_config: Res<TerrainConfig>,
+    terrain: Res<HeightMap>,
     mesh_pool: Res<BuildingMeshPool>,
     vegetation_pool: Res<VegetationMeshPool>,
     palette: Res<BuildingMaterialPalette>,
@@ -81,7 +80,6 @@ fn spawn_buildings(
         blueprints.plans.len()
     );
 
-    let terrain = TerrainSampler::new(&terrain_config);
     let mut rng = StdRng::seed_from_u64(config.seed);
 
     // Park materials (still use standard spawning for now)
@@ -120,7 +118,7 @@ fn spawn_buildings(
     for plan in &blueprints.plans {
         match plan {
             PlannedStructure::Park(park) => {
-                let terrain_height = terrain.sample(park.center.x, park.center.y);
+                let terrain_height = terrain.sample_world(Vec2::new(park.center.x, park.center.y));
                 spawn_park(
                     &mut commands,
                     &mut meshes,
@@ -144,7 +142,7 @@ fn spawn_buildings(
                 let color = BuildingMaterialPalette::get_color(plan.facade, color_variant);
 
                 // Sample terrain height at building center
-                let terrain_height = terrain.sample(plan.center.x, plan.center.y);
+                let terrain_height = terrain.sample_world(Vec2::new(plan.center.x, plan.center.y));
 
                 // Create instance data and add to buffer
                 let instance_index = spawn_building_instanced(
@@ -681,9 +679,11 @@ fn get_facade_material_params(facade: FacadeStyle) -> (f32, f32) {
 
 // Old spawn functions removed - now using shared meshes via _standard variants
 
-/// Marker for park entities.
+/// Park ground, with the size of its footprint.
 #[derive(Component)]
-pub struct Park;
+pub struct Park {
+    pub size: Vec2,
+}
 
 /// Marker for tree entities.
 #[derive(Component)]
@@ -708,7 +708,7 @@ fn spawn_park(
         Mesh3d(grass_mesh),
         MeshMaterial3d(grass_material.clone()),
         Transform::from_xyz(center.x, terrain_height + 0.075, center.y),
-        Park,
+        Park { size },
     ));
 
     // Trees
@@ -745,40 +745,3 @@ fn spawn_park(
         ));
     }
 }
-
-/// Helper struct for sampling terrain height.
-struct TerrainSampler {
-    perlin: Perlin,
-    noise_scale: f32,
-    height_scale: f32,
-    octaves: u32,
-}
-
-impl TerrainSampler {
-    fn new(config: &TerrainConfig) -> Self {
-        Self {
-            perlin: Perlin::new(config.seed),
-            noise_scale: config.noise_scale,
-            height_scale: config.height_scale,
-            octaves: config.octaves,
-        }
-    }
-
-    fn sample(&self, x: f32, z: f32) -> f32 {
-        let mut height = 0.0;
-        let mut amplitude = 1.0;
-        let mut frequency = self.noise_scale;
-        let mut max_amplitude = 0.0;
-
-        for _ in 0..self.octaves {
-            let sample_x = x as f64 * frequency as f64;
-            let sample_z = z as f64 * frequency as f64;
-            height += self.perlin.get([sample_x, sample_z]) as f32 * amplitude;
-            max_amplitude += amplitude;
-            amplitude *= 0.5;
-            frequency *= 2.0;
-        }
-
-        (height / max_amplitude) * self.height_scale
-    }
-}
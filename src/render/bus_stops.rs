@@ -1,7 +1,8 @@
 //! Bus stops with shelters along major roads.
 //!
 //! Spawns bus stop shelters at intervals along major roads, with benches,
-//! signs, and transparent shelter roofs.
+//! signs, and transparent shelter roofs. Stops on the player's bus lines get
+//! a sign post in the line's colour.
 
 use bevy::prelude::*;
 use rand::{rngs::StdRng, Rng, SeedableRng};
@@ -9,6 +10,8 @@ use std::f32::consts::PI;
 
 use crate::procgen::roads::{RoadGraph, RoadType};
 use crate::render::road_mesh::RoadMeshGenerated;
+use crate::simulation::bus_routes::BusRoutes;
+use crate::world::terrain::HeightMap;
 
 pub struct BusStopsPlugin;
 
@@ -16,7 +19,7 @@ impl Plugin for BusStopsPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<BusStopConfig>()
             .init_resource::<BusStopsSpawned>()
-            .add_systems(Update, spawn_bus_stops.run_if(should_spawn_bus_stops));
+            .add_systems(Update, (spawn_bus_stops.run_if(should_spawn_bus_stops), sync_line_stop_markers));
     }
 }
 
@@ -42,6 +45,10 @@ pub struct BusStop {
     pub right_side: bool,
 }
 
+/// Sign post at a stop on one of the player's bus lines.
+#[derive(Component)]
+pub struct LineStopMarker;
+
 /// Configuration for bus stop spawning.
 #[derive(Resource)]
 pub struct BusStopConfig {
@@ -236,6 +243,62 @@ fn spawn_bus_stops(
     info!("Spawned {} bus stops with shelters", bus_stop_count);
 }
 
+/// Rebuild the sign posts for player line stops whenever the lines change.
+fn sync_line_stop_markers(
+    mut commands: Commands,
+    routes: Res<BusRoutes>,
+    terrain: Res<HeightMap>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    markers: Query<Entity, With<LineStopMarker>>,
+) {
+    if !routes.is_changed() {
+        return;
+    }
+    for entity in &markers {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let pole_mesh = meshes.add(Cylinder::new(0.05, 2.5));
+    let sign_mesh = meshes.add(Cuboid::new(0.6, 0.4, 0.05));
+    let pole_material = materials.add(StandardMaterial {
+        base_color: Color::srgb(0.4, 0.4, 0.42),
+        metallic: 0.6,
+        ..default()
+    });
+
+    for route in routes.routes.iter().filter(|route| route.is_player_line()) {
+        let sign_material = materials.add(StandardMaterial {
+            base_color: route.color,
+            emissive: LinearRgba::from(route.color) * 0.2,
+            ..default()
+        });
+        for &stop in &route.stops {
+            commands
+                .spawn((
+                    Transform::from_xyz(stop.x, terrain.sample_world(stop), stop.y),
+                    GlobalTransform::default(),
+                    Visibility::Visible,
+                    InheritedVisibility::default(),
+                    ViewVisibility::default(),
+                    LineStopMarker,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Mesh3d(pole_mesh.clone()),
+                        MeshMaterial3d(pole_material.clone()),
+                        Transform::from_xyz(0.0, 1.25, 0.0),
+                    ));
+                    parent.spawn((
+                        Mesh3d(sign_mesh.clone()),
+                        MeshMaterial3d(sign_material.clone()),
+                        Transform::from_xyz(0.0, 2.3, 0.0),
+                    ));
+                });
+        }
+    }
+}
+
 /// Interpolate position and direction along edge waypoints.
 fn interpolate_edge_position(points: &[Vec2], progress: f32) -> (Vec2, Vec2) {
     if points.is_empty() {
@@ -17,6 +17,7 @@ impl Plugin for ElevatedRailPlugin {
         app.init_resource::<ElevatedRailConfig>()
             .init_resource::<ElevatedRailSpawned>()
             .init_resource::<RailLine>()
+            .add_systems(Startup, setup_rail_materials)
             .add_systems(Update, spawn_elevated_rail.run_if(should_spawn_rail));
     }
 }
@@ -88,12 +89,47 @@ pub struct RailStation {
     pub station_id: usize,
 }
 
+/// Materials shared by the railway and player-built track.
+#[derive(Resource)]
+pub struct RailMaterials {
+    pub concrete: Handle<StandardMaterial>,
+    pub rail: Handle<StandardMaterial>,
+    pub platform: Handle<StandardMaterial>,
+    pub safety: Handle<StandardMaterial>,
+}
+
+fn setup_rail_materials(mut commands: Commands, mut materials: ResMut<Assets<StandardMaterial>>) {
+    commands.insert_resource(RailMaterials {
+        concrete: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.55, 0.55, 0.52),
+            perceptual_roughness: 0.9,
+            ..default()
+        }),
+        rail: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.3, 0.28, 0.25),
+            metallic: 0.7,
+            perceptual_roughness: 0.4,
+            ..default()
+        }),
+        platform: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.45, 0.45, 0.48),
+            perceptual_roughness: 0.8,
+            ..default()
+        }),
+        safety: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.9, 0.8, 0.1),
+            perceptual_roughness: 0.6,
+            ..default()
+        }),
+    });
+}
+
 fn spawn_elevated_rail(
     mut commands: Commands,
     config: Res<ElevatedRailConfig>,
     road_graph: Res<RoadGraph>,
+    rail_materials: Res<RailMaterials>,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
     mut spawned: ResMut<ElevatedRailSpawned>,
     mut rail_line: ResMut<RailLine>,
 ) {
@@ -117,31 +153,10 @@ fn spawn_elevated_rail(
     rail_line.waypoints = waypoints.clone();
     rail_line.stations = stations.clone();
 
-    // Materials
-    let concrete_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.55, 0.55, 0.52),
-        perceptual_roughness: 0.9,
-        ..default()
-    });
-
-    let rail_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.3, 0.28, 0.25),
-        metallic: 0.7,
-        perceptual_roughness: 0.4,
-        ..default()
-    });
-
-    let platform_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.45, 0.45, 0.48),
-        perceptual_roughness: 0.8,
-        ..default()
-    });
-
-    let safety_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.9, 0.8, 0.1),
-        perceptual_roughness: 0.6,
-        ..default()
-    });
+    let concrete_material = rail_materials.concrete.clone();
+    let rail_material = rail_materials.rail.clone();
+    let platform_material = rail_materials.platform.clone();
+    let safety_material = rail_materials.safety.clone();
 
     // Meshes
     let pillar_mesh = meshes.add(create_pillar_mesh(config.pillar_width, config.track_height));
@@ -355,7 +370,7 @@ fn generate_rail_path(road_graph: &RoadGraph, rng: &mut StdRng) -> Vec<Vec2> {
 }
 
 /// Create a pillar mesh (tapered rectangular column).
-fn create_pillar_mesh(width: f32, height: f32) -> Mesh {
+pub(crate) fn create_pillar_mesh(width: f32, height: f32) -> Mesh {
     let hw = width / 2.0;
     let hh = height / 2.0;
     let taper = 0.8; // Top is 80% of bottom width
@@ -401,7 +416,7 @@ fn create_pillar_mesh(width: f32, height: f32) -> Mesh {
 }
 
 /// Create track bed mesh (flat viaduct segment).
-fn create_track_bed_mesh(length: f32, width: f32, thickness: f32) -> Mesh {
+pub(crate) fn create_track_bed_mesh(length: f32, width: f32, thickness: f32) -> Mesh {
     let hl = length / 2.0;
     let hw = width / 2.0;
     let ht = thickness / 2.0;
@@ -459,6 +474,6 @@ fn create_track_bed_mesh(length: f32, width: f32, thickness: f32) -> Mesh {
 }
 
 /// Create platform mesh.
-fn create_platform_mesh(length: f32, width: f32, height: f32) -> Mesh {
+pub(crate) fn create_platform_mesh(length: f32, width: f32, height: f32) -> Mesh {
     Cuboid::new(length, height, width).into()
 }
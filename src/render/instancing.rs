@@ -19,10 +19,9 @@ use bevy::{
     pbr::{MaterialPipeline, MaterialPipelineKey},
 };
 use bytemuck::{Pod, Zeroable};
-use noise::{NoiseFn, Perlin};
 
-use crate::procgen::river::River;
 use crate::render::facade_textures::{FacadeTextureArray, FacadeTexturesGenerated};
+use crate::world::terrain::{HeightMap, TerrainConfig, TerrainModified};
 
 // Re-export building instance types for convenience
 pub use crate::render::building_instances::{
@@ -39,40 +38,14 @@ impl Plugin for InstancingPlugin {
             .init_resource::<TerrainConfig>()
             .init_resource::<BuildingPbrMaterialHandle>()
             .add_systems(PostStartup, setup_instanced_cubes)
+            .add_systems(
+                Update,
+                rebuild_terrain_mesh.run_if(on_event::<TerrainModified>),
+            )
             .add_systems(Update, initialize_building_pbr_material.run_if(should_init_pbr_material));
     }
 }
 
-/// Configuration for terrain generation.
-#[derive(Resource)]
-pub struct TerrainConfig {
-    /// Size of the terrain in world units.
-    pub size: f32,
-    /// Number of subdivisions per axis.
-    pub resolution: u32,
-    /// Maximum height variation from noise.
-    pub height_scale: f32,
-    /// Noise frequency (higher = more hills).
-    pub noise_scale: f32,
-    /// Number of octaves for fractal noise.
-    pub octaves: u32,
-    /// Random seed for noise.
-    pub seed: u32,
-}
-
-impl Default for TerrainConfig {
-    fn default() -> Self {
-        Self {
-            size: 600.0,
-            resolution: 128,
-            height_scale: 8.0,
-            noise_scale: 0.008,
-            octaves: 4,
-            seed: 42,
-        }
-    }
-}
-
 /// Configuration for instancing.
 #[derive(Resource)]
 pub struct InstancingConfig {
@@ -336,13 +309,12 @@ fn setup_instanced_cubes(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     config: Res<InstancingConfig>,
-    terrain_config: Res<TerrainConfig>,
-    river: Res<River>,
+    heights: Res<HeightMap>,
 ) {
     info!("Setting up {} instanced cubes...", config.instance_count);
 
-    // Terrain with Perlin noise height variation and river carving
-    let terrain_mesh = generate_terrain_mesh(&terrain_config, &river);
+    // Terrain from the shared height map
+    let terrain_mesh = generate_terrain_mesh(&heights);
     commands.spawn((
         Mesh3d(meshes.add(terrain_mesh)),
         MeshMaterial3d(materials.add(StandardMaterial {
@@ -416,52 +388,35 @@ fn setup_instanced_cubes(
 #[derive(Component)]
 pub struct Terrain;
 
-/// Generate a terrain mesh with Perlin noise height variation and river carving.
-fn generate_terrain_mesh(config: &TerrainConfig, river: &River) -> Mesh {
-    let perlin = Perlin::new(config.seed);
-    let res = config.resolution as usize;
-    let half_size = config.size / 2.0;
-    let step = config.size / config.resolution as f32;
+/// Rebuild the terrain mesh after the height map is edited.
+fn rebuild_terrain_mesh(
+    mut events: EventReader<TerrainModified>,
+    heights: Res<HeightMap>,
+    terrain_q: Query<&Mesh3d, With<Terrain>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    events.clear();
+    for mesh in &terrain_q {
+        meshes.insert(&mesh.0, generate_terrain_mesh(&heights));
+    }
+}
+
+/// Generate a terrain mesh from the height map.
+fn generate_terrain_mesh(heights: &HeightMap) -> Mesh {
+    let res = heights.width - 1;
+    let step = heights.cell_size;
 
-    // Generate vertices with noise-based height
+    // Generate vertices from the height map samples
     let mut positions: Vec<[f32; 3]> = Vec::with_capacity((res + 1) * (res + 1));
     let mut normals: Vec<[f32; 3]> = Vec::with_capacity((res + 1) * (res + 1));
     let mut uvs: Vec<[f32; 2]> = Vec::with_capacity((res + 1) * (res + 1));
 
     for z in 0..=res {
         for x in 0..=res {
-            let world_x = (x as f32 * step) - half_size;
-            let world_z = (z as f32 * step) - half_size;
-
-            // Sample fractal Perlin noise (multiple octaves)
-            let mut height = sample_terrain_height(
-                &perlin,
-                world_x,
-                world_z,
-                config.noise_scale,
-                config.height_scale,
-                config.octaves,
-            );
-
-            // Carve river channel into terrain
-            if !river.centerline.is_empty() {
-                let point = Vec2::new(world_x, world_z);
-                let river_dist = river.signed_distance(point);
-
-                if river_dist < 0.0 {
-                    // Inside river - set to riverbed (below water level)
-                    height = river.water_level - 1.0;
-                } else if river_dist < river.bank_slope_width {
-                    // Bank slope - smooth transition from riverbed to terrain
-                    let t = river_dist / river.bank_slope_width;
-                    // Ease in/out for smoother banks
-                    let t_smooth = t * t * (3.0 - 2.0 * t);
-                    let bank_bottom = river.water_level - 0.5;
-                    height = bank_bottom + (height - bank_bottom) * t_smooth;
-                }
-            }
+            let world = heights.grid_to_world(x, z);
+            let height = heights.get(x, z);
 
-            positions.push([world_x, height, world_z]);
+            positions.push([world.x, height, world.y]);
             normals.push([0.0, 1.0, 0.0]); // Will be recalculated
             uvs.push([x as f32 / res as f32, z as f32 / res as f32]);
         }
@@ -523,33 +478,6 @@ fn generate_terrain_mesh(config: &TerrainConfig, river: &River) -> Mesh {
     mesh
 }
 
-/// Sample terrain height using fractal Perlin noise.
-fn sample_terrain_height(
-    perlin: &Perlin,
-    x: f32,
-    z: f32,
-    scale: f32,
-    height_scale: f32,
-    octaves: u32,
-) -> f32 {
-    let mut height = 0.0;
-    let mut amplitude = 1.0;
-    let mut frequency = scale;
-    let mut max_amplitude = 0.0;
-
-    for _ in 0..octaves {
-        let sample_x = x as f64 * frequency as f64;
-        let sample_z = z as f64 * frequency as f64;
-        height += perlin.get([sample_x, sample_z]) as f32 * amplitude;
-        max_amplitude += amplitude;
-        amplitude *= 0.5; // Each octave has half the amplitude
-        frequency *= 2.0; // Each octave has double the frequency
-    }
-
-    // Normalize and scale
-    (height / max_amplitude) * height_scale
-}
-
 // ============================================================================
 // Building PBR Material Initialization
 // ============================================================================
@@ -1,7 +1,9 @@
 //! Landmark buildings: clock towers, churches, and other distinctive structures.
 //!
 //! Spawns unique landmark buildings at strategic locations to provide
-//! visual focal points throughout the city.
+//! visual focal points throughout the city. Each landmark is one entity
+//! carrying [`Landmark`], with its parts as children; the landmark tool
+//! places more with [`spawn_landmark`].
 
 use bevy::prelude::*;
 use rand::rngs::StdRng;
@@ -10,6 +12,7 @@ use std::f32::consts::PI;
 
 use crate::procgen::building_factory::{BuildingArchetype, BuildingBlueprints, BuildingPlan, PlannedStructure};
 use crate::render::building_spawner::BuildingsSpawned;
+use crate::world::terrain::HeightMap;
 
 pub struct LandmarksPlugin;
 
@@ -17,6 +20,7 @@ impl Plugin for LandmarksPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<LandmarkConfig>()
             .init_resource::<LandmarksSpawned>()
+            .add_systems(Startup, setup_landmark_assets)
             .add_systems(Update, spawn_landmarks.run_if(should_spawn_landmarks));
     }
 }
@@ -32,9 +36,43 @@ fn should_spawn_landmarks(
     buildings_spawned.0 && !landmarks_spawned.0
 }
 
-/// Generic landmark marker.
-#[derive(Component)]
-pub struct Landmark;
+/// Kinds of landmark.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LandmarkKind {
+    ClockTower,
+    Church,
+}
+
+impl LandmarkKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            LandmarkKind::ClockTower => "Clock tower",
+            LandmarkKind::Church => "Church",
+        }
+    }
+
+    /// What the player pays to build one.
+    pub fn cost(self) -> i64 {
+        match self {
+            LandmarkKind::ClockTower => 15000,
+            LandmarkKind::Church => 10000,
+        }
+    }
+
+    /// How strongly the landmark draws visitors to the city.
+    pub fn attractiveness(self) -> f32 {
+        match self {
+            LandmarkKind::ClockTower => 40.0,
+            LandmarkKind::Church => 25.0,
+        }
+    }
+}
+
+/// A landmark; its parts are children of this entity.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Landmark {
+    pub kind: LandmarkKind,
+}
 
 /// Clock tower marker.
 #[derive(Component)]
@@ -67,12 +105,66 @@ impl Default for LandmarkConfig {
     }
 }
 
+/// Materials shared by every landmark.
+#[derive(Resource)]
+pub struct LandmarkAssets {
+    stone: Handle<StandardMaterial>,
+    dark_stone: Handle<StandardMaterial>,
+    copper_spire: Handle<StandardMaterial>,
+    clock_face: Handle<StandardMaterial>,
+    slate_roof: Handle<StandardMaterial>,
+    white_trim: Handle<StandardMaterial>,
+    church_stone: Handle<StandardMaterial>,
+}
+
+fn setup_landmark_assets(mut commands: Commands, mut materials: ResMut<Assets<StandardMaterial>>) {
+    commands.insert_resource(LandmarkAssets {
+        stone: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.85, 0.78, 0.68), // Warm tan stone
+            perceptual_roughness: 0.75,
+            ..default()
+        }),
+        dark_stone: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.5, 0.48, 0.45), // Darker stone for details
+            perceptual_roughness: 0.7,
+            ..default()
+        }),
+        copper_spire: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.4, 0.55, 0.45), // Copper patina green
+            metallic: 0.7,
+            perceptual_roughness: 0.4,
+            ..default()
+        }),
+        clock_face: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.95, 0.93, 0.88), // Off-white clock face
+            perceptual_roughness: 0.3,
+            ..default()
+        }),
+        slate_roof: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.35, 0.35, 0.4), // Dark slate
+            perceptual_roughness: 0.8,
+            ..default()
+        }),
+        white_trim: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.95, 0.95, 0.92), // White trim
+            perceptual_roughness: 0.5,
+            ..default()
+        }),
+        church_stone: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.8, 0.78, 0.75), // Light gray stone
+            perceptual_roughness: 0.75,
+            ..default()
+        }),
+    });
+}
+
 fn spawn_landmarks(
     mut commands: Commands,
     config: Res<LandmarkConfig>,
     blueprints: Res<BuildingBlueprints>,
+    assets: Res<LandmarkAssets>,
+    terrain: Res<HeightMap>,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
     mut landmarks_spawned: ResMut<LandmarksSpawned>,
 ) {
     landmarks_spawned.0 = true;
@@ -85,50 +177,6 @@ fn spawn_landmarks(
     info!("Spawning landmark buildings...");
     let mut rng = StdRng::seed_from_u64(config.seed);
 
-    // Create materials for landmarks
-    let stone_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.85, 0.78, 0.68), // Warm tan stone
-        perceptual_roughness: 0.75,
-        ..default()
-    });
-
-    let dark_stone_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.5, 0.48, 0.45), // Darker stone for details
-        perceptual_roughness: 0.7,
-        ..default()
-    });
-
-    let copper_spire_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.4, 0.55, 0.45), // Copper patina green
-        metallic: 0.7,
-        perceptual_roughness: 0.4,
-        ..default()
-    });
-
-    let clock_face_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.95, 0.93, 0.88), // Off-white clock face
-        perceptual_roughness: 0.3,
-        ..default()
-    });
-
-    let slate_roof_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.35, 0.35, 0.4), // Dark slate
-        perceptual_roughness: 0.8,
-        ..default()
-    });
-
-    let white_trim_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.95, 0.95, 0.92), // White trim
-        perceptual_roughness: 0.5,
-        ..default()
-    });
-
-    let church_stone_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.8, 0.78, 0.75), // Light gray stone
-        perceptual_roughness: 0.75,
-        ..default()
-    });
-
     // Find all building plans for church placement - prefer residential but allow any
     let mut all_plans: Vec<&BuildingPlan> = blueprints
         .plans
@@ -170,15 +218,8 @@ fn spawn_landmarks(
     );
 
     for pos in clock_tower_positions {
-        spawn_clock_tower(
-            &mut commands,
-            &mut meshes,
-            pos,
-            &stone_material,
-            &dark_stone_material,
-            &copper_spire_material,
-            &clock_face_material,
-        );
+        let base_y = terrain.sample_world(pos);
+        spawn_landmark(&mut commands, &mut meshes, &assets, LandmarkKind::ClockTower, pos, base_y, 0.0);
         clock_towers_spawned += 1;
     }
 
@@ -209,16 +250,10 @@ fn spawn_landmarks(
             continue;
         }
 
-        spawn_church(
-            &mut commands,
-            &mut meshes,
-            plan.center,
-            &church_stone_material,
-            &slate_roof_material,
-            &white_trim_material,
-            &copper_spire_material,
-            &mut rng,
-        );
+        // Facing one of the 4 cardinal directions
+        let rotation = (rng.gen_range(0..4) as f32) * PI / 2.0;
+        let base_y = terrain.sample_world(plan.center);
+        spawn_landmark(&mut commands, &mut meshes, &assets, LandmarkKind::Church, plan.center, base_y, rotation);
 
         church_positions.push(plan.center);
         churches_spawned += 1;
@@ -293,18 +328,32 @@ fn find_clock_tower_positions(
     positions
 }
 
-/// Spawn a clock tower at the given position.
-fn spawn_clock_tower(
+/// Spawn a landmark standing on the ground at `base_y`, turned `rotation`
+/// radians about the vertical.
+pub fn spawn_landmark(
     commands: &mut Commands,
-    meshes: &mut ResMut<Assets<Mesh>>,
+    meshes: &mut Assets<Mesh>,
+    assets: &LandmarkAssets,
+    kind: LandmarkKind,
     position: Vec2,
-    stone_material: &Handle<StandardMaterial>,
-    dark_stone_material: &Handle<StandardMaterial>,
-    copper_material: &Handle<StandardMaterial>,
-    clock_face_material: &Handle<StandardMaterial>,
-) {
-    let base_y = 0.0; // TODO: Sample terrain height
+    base_y: f32,
+    rotation: f32,
+) -> Entity {
+    let transform = Transform::from_xyz(position.x, base_y, position.y).with_rotation(Quat::from_rotation_y(rotation));
+    let mut landmark = commands.spawn((transform, Visibility::default(), Landmark { kind }));
+    match kind {
+        LandmarkKind::ClockTower => {
+            landmark.insert(ClockTower).with_children(|parent| build_clock_tower(parent, meshes, assets));
+        }
+        LandmarkKind::Church => {
+            landmark.insert(Church).with_children(|parent| build_church(parent, meshes, assets));
+        }
+    }
+    landmark.id()
+}
 
+/// Build the parts of a clock tower around its base.
+fn build_clock_tower(parent: &mut ChildBuilder, meshes: &mut Assets<Mesh>, assets: &LandmarkAssets) {
     // Tower dimensions
     let tower_width = 5.0;
     let tower_height = 28.0;
@@ -315,116 +364,58 @@ fn spawn_clock_tower(
 
     // Main tower body
     let tower_mesh = meshes.add(Cuboid::new(tower_width, tower_height, tower_width));
-    commands.spawn((
+    parent.spawn((
         Mesh3d(tower_mesh),
-        MeshMaterial3d(stone_material.clone()),
-        Transform::from_xyz(position.x, base_y + tower_height / 2.0, position.y),
-        Landmark,
-        ClockTower,
+        MeshMaterial3d(assets.stone.clone()),
+        Transform::from_xyz(0.0, tower_height / 2.0, 0.0),
     ));
 
     // Observation deck / cornice at top
     let deck_mesh = meshes.add(Cuboid::new(deck_width, deck_height, deck_width));
-    commands.spawn((
+    parent.spawn((
         Mesh3d(deck_mesh),
-        MeshMaterial3d(dark_stone_material.clone()),
-        Transform::from_xyz(position.x, base_y + tower_height + deck_height / 2.0, position.y),
-        Landmark,
-        ClockTower,
+        MeshMaterial3d(assets.dark_stone.clone()),
+        Transform::from_xyz(0.0, tower_height + deck_height / 2.0, 0.0),
     ));
 
-    // Spire (pyramid/cone shape using scaled cube)
-    // Use a tall thin box as approximation, or create a cone
+    // Spire
     let spire_mesh = meshes.add(Cone {
         radius: deck_width / 2.0 * 0.7,
         height: spire_height,
     });
-    commands.spawn((
+    parent.spawn((
         Mesh3d(spire_mesh),
-        MeshMaterial3d(copper_material.clone()),
-        Transform::from_xyz(
-            position.x,
-            base_y + tower_height + deck_height + spire_height / 2.0,
-            position.y,
-        ),
-        Landmark,
-        ClockTower,
+        MeshMaterial3d(assets.copper_spire.clone()),
+        Transform::from_xyz(0.0, tower_height + deck_height + spire_height / 2.0, 0.0),
     ));
 
     // Clock faces on all 4 sides
     let clock_mesh = meshes.add(Cuboid::new(clock_size, clock_size, 0.15));
-    let clock_y = base_y + tower_height - 4.0; // Near top of tower
+    let clock_y = tower_height - 4.0; // Near top of tower
     let clock_offset = tower_width / 2.0 + 0.1;
-
-    // North face
-    commands.spawn((
-        Mesh3d(clock_mesh.clone()),
-        MeshMaterial3d(clock_face_material.clone()),
-        Transform::from_xyz(position.x, clock_y, position.y + clock_offset),
-        Landmark,
-        ClockTower,
-    ));
-
-    // South face
-    commands.spawn((
-        Mesh3d(clock_mesh.clone()),
-        MeshMaterial3d(clock_face_material.clone()),
-        Transform::from_xyz(position.x, clock_y, position.y - clock_offset),
-        Landmark,
-        ClockTower,
-    ));
-
-    // East face
-    commands.spawn((
-        Mesh3d(clock_mesh.clone()),
-        MeshMaterial3d(clock_face_material.clone()),
-        Transform::from_xyz(position.x + clock_offset, clock_y, position.y)
-            .with_rotation(Quat::from_rotation_y(PI / 2.0)),
-        Landmark,
-        ClockTower,
-    ));
-
-    // West face
-    commands.spawn((
-        Mesh3d(clock_mesh.clone()),
-        MeshMaterial3d(clock_face_material.clone()),
-        Transform::from_xyz(position.x - clock_offset, clock_y, position.y)
-            .with_rotation(Quat::from_rotation_y(PI / 2.0)),
-        Landmark,
-        ClockTower,
-    ));
+    for side in 0..4 {
+        let facing = Quat::from_rotation_y(side as f32 * PI / 2.0);
+        parent.spawn((
+            Mesh3d(clock_mesh.clone()),
+            MeshMaterial3d(assets.clock_face.clone()),
+            Transform::from_translation(facing * Vec3::new(0.0, clock_y, clock_offset)).with_rotation(facing),
+        ));
+    }
 
     // Small windows on tower (decorative bands)
     let window_band_mesh = meshes.add(Cuboid::new(tower_width + 0.2, 0.8, tower_width + 0.2));
     for i in 0..3 {
-        let band_y = base_y + 8.0 + (i as f32 * 8.0);
-        commands.spawn((
+        let band_y = 8.0 + (i as f32 * 8.0);
+        parent.spawn((
             Mesh3d(window_band_mesh.clone()),
-            MeshMaterial3d(dark_stone_material.clone()),
-            Transform::from_xyz(position.x, band_y, position.y),
-            Landmark,
-            ClockTower,
+            MeshMaterial3d(assets.dark_stone.clone()),
+            Transform::from_xyz(0.0, band_y, 0.0),
         ));
     }
 }
 
-/// Spawn a church at the given position.
-fn spawn_church(
-    commands: &mut Commands,
-    meshes: &mut ResMut<Assets<Mesh>>,
-    position: Vec2,
-    stone_material: &Handle<StandardMaterial>,
-    roof_material: &Handle<StandardMaterial>,
-    trim_material: &Handle<StandardMaterial>,
-    copper_material: &Handle<StandardMaterial>,
-    rng: &mut StdRng,
-) {
-    let base_y = 0.0; // TODO: Sample terrain height
-
-    // Randomize orientation (facing one of 4 cardinal directions)
-    let rotation_angle = (rng.gen_range(0..4) as f32) * PI / 2.0;
-    let base_rotation = Quat::from_rotation_y(rotation_angle);
-
+/// Build the parts of a church around its base, bell tower to the front (-Z).
+fn build_church(parent: &mut ChildBuilder, meshes: &mut Assets<Mesh>, assets: &LandmarkAssets) {
     // Nave dimensions
     let nave_length = 22.0;
     let nave_width = 12.0;
@@ -438,39 +429,27 @@ fn spawn_church(
 
     // Nave (main building)
     let nave_mesh = meshes.add(Cuboid::new(nave_width, nave_height, nave_length));
-    commands.spawn((
+    parent.spawn((
         Mesh3d(nave_mesh),
-        MeshMaterial3d(stone_material.clone()),
-        Transform::from_xyz(position.x, base_y + nave_height / 2.0, position.y)
-            .with_rotation(base_rotation),
-        Landmark,
-        Church,
+        MeshMaterial3d(assets.church_stone.clone()),
+        Transform::from_xyz(0.0, nave_height / 2.0, 0.0),
     ));
 
-    // Pitched roof (using a triangular prism approximation - a rotated/scaled box for now)
-    // Create a proper triangular prism mesh
+    // Pitched roof (triangular prism)
     let roof_mesh = meshes.add(create_pitched_roof_mesh(nave_width, roof_height, nave_length));
-    commands.spawn((
+    parent.spawn((
         Mesh3d(roof_mesh),
-        MeshMaterial3d(roof_material.clone()),
-        Transform::from_xyz(position.x, base_y + nave_height + roof_height / 2.0, position.y)
-            .with_rotation(base_rotation),
-        Landmark,
-        Church,
+        MeshMaterial3d(assets.slate_roof.clone()),
+        Transform::from_xyz(0.0, nave_height + roof_height / 2.0, 0.0),
     ));
 
     // Bell tower at front of church
-    let tower_offset = Vec3::new(0.0, 0.0, -nave_length / 2.0 - tower_size / 2.0 + 2.0);
-    let rotated_offset = base_rotation * tower_offset;
-    let tower_pos = Vec3::new(position.x, base_y, position.y) + rotated_offset;
-
+    let tower_z = -nave_length / 2.0 - tower_size / 2.0 + 2.0;
     let tower_mesh = meshes.add(Cuboid::new(tower_size, tower_height, tower_size));
-    commands.spawn((
+    parent.spawn((
         Mesh3d(tower_mesh),
-        MeshMaterial3d(stone_material.clone()),
-        Transform::from_xyz(tower_pos.x, tower_pos.y + tower_height / 2.0, tower_pos.z),
-        Landmark,
-        Church,
+        MeshMaterial3d(assets.church_stone.clone()),
+        Transform::from_xyz(0.0, tower_height / 2.0, tower_z),
     ));
 
     // Steeple on bell tower
@@ -478,67 +457,42 @@ fn spawn_church(
         radius: tower_size / 2.0 * 0.85,
         height: steeple_height,
     });
-    commands.spawn((
+    parent.spawn((
         Mesh3d(steeple_mesh),
-        MeshMaterial3d(copper_material.clone()),
-        Transform::from_xyz(
-            tower_pos.x,
-            tower_pos.y + tower_height + steeple_height / 2.0,
-            tower_pos.z,
-        ),
-        Landmark,
-        Church,
+        MeshMaterial3d(assets.copper_spire.clone()),
+        Transform::from_xyz(0.0, tower_height + steeple_height / 2.0, tower_z),
     ));
 
     // Cross on top of steeple
     let cross_vertical = meshes.add(Cuboid::new(0.3, 2.5, 0.3));
     let cross_horizontal = meshes.add(Cuboid::new(1.5, 0.3, 0.3));
-    let cross_y = tower_pos.y + tower_height + steeple_height + 1.0;
-
-    commands.spawn((
+    let cross_y = tower_height + steeple_height + 1.0;
+    parent.spawn((
         Mesh3d(cross_vertical),
-        MeshMaterial3d(trim_material.clone()),
-        Transform::from_xyz(tower_pos.x, cross_y, tower_pos.z),
-        Landmark,
-        Church,
+        MeshMaterial3d(assets.white_trim.clone()),
+        Transform::from_xyz(0.0, cross_y, tower_z),
     ));
-
-    commands.spawn((
+    parent.spawn((
         Mesh3d(cross_horizontal),
-        MeshMaterial3d(trim_material.clone()),
-        Transform::from_xyz(tower_pos.x, cross_y + 0.5, tower_pos.z),
-        Landmark,
-        Church,
+        MeshMaterial3d(assets.white_trim.clone()),
+        Transform::from_xyz(0.0, cross_y + 0.5, tower_z),
     ));
 
     // Rose window marker (circular detail on front facade)
     let rose_window_mesh = meshes.add(Cylinder::new(1.8, 0.2));
-    let rose_offset = Vec3::new(0.0, nave_height - 2.0, -nave_length / 2.0 - 0.15);
-    let rotated_rose = base_rotation * rose_offset;
-    let rose_pos = Vec3::new(position.x, base_y, position.y) + rotated_rose;
-
-    commands.spawn((
+    parent.spawn((
         Mesh3d(rose_window_mesh),
-        MeshMaterial3d(trim_material.clone()),
-        Transform::from_xyz(rose_pos.x, rose_pos.y, rose_pos.z)
-            .with_rotation(base_rotation * Quat::from_rotation_x(PI / 2.0)),
-        Landmark,
-        Church,
+        MeshMaterial3d(assets.white_trim.clone()),
+        Transform::from_xyz(0.0, nave_height - 2.0, -nave_length / 2.0 - 0.15)
+            .with_rotation(Quat::from_rotation_x(PI / 2.0)),
     ));
 
     // Entrance (arched doorway represented by darker inset)
     let entrance_mesh = meshes.add(Cuboid::new(2.5, 4.0, 0.3));
-    let entrance_offset = Vec3::new(0.0, 2.0, -nave_length / 2.0 - 0.2);
-    let rotated_entrance = base_rotation * entrance_offset;
-    let entrance_pos = Vec3::new(position.x, base_y, position.y) + rotated_entrance;
-
-    commands.spawn((
+    parent.spawn((
         Mesh3d(entrance_mesh),
-        MeshMaterial3d(roof_material.clone()), // Dark like the roof
-        Transform::from_xyz(entrance_pos.x, entrance_pos.y, entrance_pos.z)
-            .with_rotation(base_rotation),
-        Landmark,
-        Church,
+        MeshMaterial3d(assets.slate_roof.clone()), // Dark like the roof
+        Transform::from_xyz(0.0, 2.0, -nave_length / 2.0 - 0.2),
     ));
 }
 
@@ -10,6 +10,7 @@ pub mod construction_sites;
 pub mod graffiti;
 pub mod landmarks;
 pub mod parking_lots;
+pub mod rail_tracks;
 pub mod parking_garages;
 pub mod subway_entrances;
 pub mod elevated_rail;
@@ -34,6 +35,7 @@ pub mod hzb;
 pub mod instancing;
 pub mod mesh_pools;
 pub mod neon_signs;
+pub mod overpasses;
 pub mod parked_cars;
 pub mod road_markings;
 pub mod road_mesh;
@@ -46,6 +48,7 @@ pub mod storefronts;
 pub mod cinematic_polish;
 pub mod tilt_shift;
 pub mod traffic_lights;
+pub mod tunnels;
 pub mod vehicle_lights;
 pub mod vehicle_meshes;
 pub mod water;
@@ -69,6 +72,8 @@ impl Plugin for RenderPlugin {
             .add_plugins(road_mesh::RoadMeshPlugin)
             .add_plugins(road_markings::RoadMarkingsPlugin)
             .add_plugins(bridges::BridgesPlugin)
+            .add_plugins(tunnels::TunnelsPlugin)
+            .add_plugins(overpasses::OverpassesPlugin)
             .add_plugins(building_spawner::BuildingSpawnerPlugin)
             .add_plugins(landmarks::LandmarksPlugin)
             .add_plugins(building_shadows::BuildingShadowsPlugin)
@@ -95,6 +100,7 @@ impl Plugin for RenderPlugin {
             .add_plugins(parking_garages::ParkingGaragesPlugin)
             .add_plugins(elevated_rail::ElevatedRailPlugin)
             .add_plugins(train_cars::TrainCarsPlugin)
+            .add_plugins(rail_tracks::RailTracksPlugin)
             .add_plugins(street_vendors::StreetVendorsPlugin)
             .add_plugins(street_amenities::StreetAmenitiesPlugin)
             .add_plugins(signage::SignagePlugin)
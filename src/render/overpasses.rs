@@ -0,0 +1,195 @@
+//! Overpass rendering.
+//!
+//! Overpass edges have no ground road surface; they get a deck that climbs
+//! from the ground at each end to full clearance over the road below, carried
+//! on pillars. Decks are kept in sync with the road graph like bridges.
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::procgen::intersections::carriageway_width;
+use crate::procgen::road_generator::RoadsGenerated;
+use crate::procgen::roads::{polyline_length, RoadEdge, RoadGraph, RoadType, OVERPASS_RAMP};
+use crate::world::terrain::HeightMap;
+
+pub struct OverpassesPlugin;
+
+impl Plugin for OverpassesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OverpassConfig>()
+            .add_systems(Startup, setup_overpass_assets)
+            .add_systems(Update, sync_overpasses.run_if(should_sync_overpasses));
+    }
+}
+
+fn should_sync_overpasses(generated: Res<RoadsGenerated>, road_graph: Res<RoadGraph>) -> bool {
+    generated.0 && road_graph.is_changed()
+}
+
+/// Configuration for overpass decks.
+#[derive(Resource)]
+pub struct OverpassConfig {
+    /// Length of each straight piece of deck.
+    pub piece_length: f32,
+    /// Thickness of the deck slab.
+    pub deck_thickness: f32,
+    /// Side of the square pillars.
+    pub pillar_size: f32,
+}
+
+impl Default for OverpassConfig {
+    fn default() -> Self {
+        Self {
+            piece_length: 4.0,
+            deck_thickness: 0.8,
+            pillar_size: 1.2,
+        }
+    }
+}
+
+/// Shared mesh and materials for decks.
+#[derive(Resource)]
+struct OverpassAssets {
+    unit_box: Handle<Mesh>,
+    deck: Handle<StandardMaterial>,
+    concrete: Handle<StandardMaterial>,
+}
+
+/// An overpass deck. Deck pieces and pillars are children.
+#[derive(Component)]
+pub struct Overpass {
+    pub road_type: RoadType,
+    pub start: Vec2,
+    pub end: Vec2,
+}
+
+/// Identity of an overpass, rounded so float noise doesn't cause respawns.
+type OverpassKey = (IVec2, IVec2, RoadType);
+
+fn overpass_key(start: Vec2, end: Vec2, road_type: RoadType) -> OverpassKey {
+    ((start * 10.0).round().as_ivec2(), (end * 10.0).round().as_ivec2(), road_type)
+}
+
+fn setup_overpass_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(OverpassAssets {
+        unit_box: meshes.add(Cuboid::new(1.0, 1.0, 1.0)),
+        deck: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.22, 0.22, 0.24),
+            perceptual_roughness: 0.85,
+            ..default()
+        }),
+        concrete: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.6, 0.58, 0.55),
+            perceptual_roughness: 0.9,
+            ..default()
+        }),
+    });
+}
+
+/// Spawn decks for new overpasses and despawn those whose edge is gone.
+fn sync_overpasses(
+    mut commands: Commands,
+    road_graph: Res<RoadGraph>,
+    terrain: Res<HeightMap>,
+    config: Res<OverpassConfig>,
+    assets: Res<OverpassAssets>,
+    existing: Query<(Entity, &Overpass)>,
+) {
+    let wanted: Vec<&RoadEdge> = road_graph.edges().filter(|e| e.overpass && e.points.len() >= 2).collect();
+    let key = |edge: &RoadEdge| overpass_key(edge.points[0], edge.points[edge.points.len() - 1], edge.road_type);
+    let mut missing: HashSet<OverpassKey> = wanted.iter().map(|e| key(e)).collect();
+
+    for (entity, overpass) in &existing {
+        if !missing.remove(&overpass_key(overpass.start, overpass.end, overpass.road_type)) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    for edge in wanted {
+        if missing.remove(&key(edge)) {
+            spawn_overpass(&mut commands, &assets, &config, &terrain, edge);
+        }
+    }
+}
+
+/// Point at `distance` along a polyline.
+fn point_along(points: &[Vec2], distance: f32) -> Vec2 {
+    let mut remaining = distance;
+    for w in points.windows(2) {
+        let span = w[0].distance(w[1]);
+        if remaining <= span && span > 0.0 {
+            return w[0].lerp(w[1], remaining / span);
+        }
+        remaining -= span;
+    }
+    points[points.len() - 1]
+}
+
+/// Spawn the deck in short straight pieces following the lift profile, with
+/// pillars where it reaches full height, clear of the road it crosses.
+fn spawn_overpass(
+    commands: &mut Commands,
+    assets: &OverpassAssets,
+    config: &OverpassConfig,
+    terrain: &HeightMap,
+    edge: &RoadEdge,
+) {
+    let width = carriageway_width(edge.road_type) + 1.0;
+    let length = polyline_length(&edge.points);
+    if length <= 0.0 {
+        return;
+    }
+    let surface = |distance: f32| {
+        let p = point_along(&edge.points, distance);
+        Vec3::new(p.x, terrain.sample_world(p) + 0.12 + edge.deck_lift(distance / length), p.y)
+    };
+
+    let pieces = (length / config.piece_length).ceil().max(1.0) as usize;
+    let overpass = Overpass {
+        road_type: edge.road_type,
+        start: edge.points[0],
+        end: edge.points[edge.points.len() - 1],
+    };
+    commands
+        .spawn((Transform::default(), Visibility::default(), overpass))
+        .with_children(|parent| {
+            for i in 0..pieces {
+                let a = surface(length * i as f32 / pieces as f32);
+                let b = surface(length * (i + 1) as f32 / pieces as f32);
+                let along = b - a;
+                if along.length_squared() < 1e-6 {
+                    continue;
+                }
+                // Deck top sits on the road surface
+                let center = (a + b) / 2.0 - Vec3::Y * config.deck_thickness / 2.0;
+                parent.spawn((
+                    Mesh3d(assets.unit_box.clone()),
+                    MeshMaterial3d(assets.deck.clone()),
+                    Transform::from_translation(center)
+                        .looking_to(along, Vec3::Y)
+                        .with_scale(Vec3::new(width, config.deck_thickness, along.length())),
+                ));
+            }
+
+            // Pillars from the ground to the underside at each end of the raised section
+            for distance in [length * OVERPASS_RAMP, length * (1.0 - OVERPASS_RAMP)] {
+                let top = surface(distance);
+                let ground = terrain.sample_world(Vec2::new(top.x, top.z));
+                let height = top.y - config.deck_thickness - ground;
+                if height <= 0.5 {
+                    continue;
+                }
+                let p = config.pillar_size;
+                parent.spawn((
+                    Mesh3d(assets.unit_box.clone()),
+                    MeshMaterial3d(assets.concrete.clone()),
+                    Transform::from_xyz(top.x, ground + height / 2.0, top.z).with_scale(Vec3::new(p, height, p)),
+                ));
+            }
+        });
+}
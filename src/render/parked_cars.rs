@@ -1,13 +1,12 @@
 //! Parked car generation along roads.
 
 use bevy::prelude::*;
-use noise::{NoiseFn, Perlin};
 use rand::{Rng, SeedableRng};
 use rand::rngs::StdRng;
 
 use crate::procgen::roads::{RoadGraph, RoadType};
 use crate::render::gpu_culling::GpuCullable;
-use crate::render::instancing::TerrainConfig;
+use crate::world::terrain::{HeightMap, TerrainAnchor};
 use crate::render::road_mesh::RoadMeshGenerated;
 use crate::render::vehicle_meshes::{generate_vehicle_mesh, generate_wheel_mesh, VehicleMeshConfig, VehicleShape};
 
@@ -152,15 +151,13 @@ fn spawn_parked_cars(
     mut commands: Commands,
     road_graph: Res<RoadGraph>,
     config: Res<ParkedCarConfig>,
-    terrain_config: Res<TerrainConfig>,
+    terrain: Res<HeightMap>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut spawned: ResMut<ParkedCarsSpawned>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
     info!("Spawning parked cars...");
 
-    // Create terrain sampler
-    let terrain = TerrainSampler::new(&terrain_config);
 
     let mut rng = StdRng::seed_from_u64(config.seed);
 
@@ -278,7 +275,7 @@ fn spawn_parked_cars(
 
                     for wheel_offset in wheel_positions {
                         let wheel_world_pos = car_pos + dir * wheel_offset.x + perp * wheel_offset.y;
-                        let wheel_terrain = terrain.sample(wheel_world_pos.x, wheel_world_pos.y);
+                        let wheel_terrain = terrain.sample_world(wheel_world_pos);
                         let wheel_road_surface = wheel_terrain + 0.12; // Road height offset
                         max_wheel_surface = max_wheel_surface.max(wheel_road_surface);
 
@@ -292,6 +289,7 @@ fn spawn_parked_cars(
                             Transform::from_xyz(wheel_world_pos.x, wheel_road_surface + wheel_radius, wheel_world_pos.y)
                                 .with_rotation(wheel_rotation),
                             ParkedCar,
+                            TerrainAnchor { offset: 0.12 + wheel_radius },
                             GpuCullable::new(wheel_radius),
                         ));
                     }
@@ -305,12 +303,14 @@ fn spawn_parked_cars(
                         + vehicle_config.height * vehicle_config.height).sqrt() / 2.0;
 
                     // Spawn car body (combined body + cabin mesh)
+                    let body_transform = Transform::from_xyz(car_pos.x, body_y, car_pos.y)
+                        .with_rotation(rotation);
                     commands.spawn((
                         Mesh3d(body_mesh_handle),
                         MeshMaterial3d(car_material.clone()),
-                        Transform::from_xyz(car_pos.x, body_y, car_pos.y)
-                            .with_rotation(rotation),
+                        body_transform,
                         ParkedCar,
+                        TerrainAnchor::at(&terrain, body_transform.translation),
                         GpuCullable::new(car_radius),
                     ));
 
@@ -327,40 +327,3 @@ fn spawn_parked_cars(
     spawned.0 = true;
     info!("Spawned {} parked cars", car_count);
 }
-
-/// Helper struct for sampling terrain height.
-struct TerrainSampler {
-    perlin: Perlin,
-    noise_scale: f32,
-    height_scale: f32,
-    octaves: u32,
-}
-
-impl TerrainSampler {
-    fn new(config: &TerrainConfig) -> Self {
-        Self {
-            perlin: Perlin::new(config.seed),
-            noise_scale: config.noise_scale,
-            height_scale: config.height_scale,
-            octaves: config.octaves,
-        }
-    }
-
-    fn sample(&self, x: f32, z: f32) -> f32 {
-        let mut height = 0.0;
-        let mut amplitude = 1.0;
-        let mut frequency = self.noise_scale;
-        let mut max_amplitude = 0.0;
-
-        for _ in 0..self.octaves {
-            let sample_x = x as f64 * frequency as f64;
-            let sample_z = z as f64 * frequency as f64;
-            height += self.perlin.get([sample_x, sample_z]) as f32 * amplitude;
-            max_amplitude += amplitude;
-            amplitude *= 0.5;
-            frequency *= 2.0;
-        }
-
-        (height / max_amplitude) * self.height_scale
-    }
-}
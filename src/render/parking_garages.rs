@@ -15,6 +15,7 @@ impl Plugin for ParkingGaragesPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<ParkingGarageConfig>()
             .init_resource::<ParkingGaragesSpawned>()
+            .add_systems(Startup, setup_parking_garage_assets)
             .add_systems(Update, spawn_parking_garages.run_if(should_spawn_garages));
     }
 }
@@ -29,13 +30,17 @@ fn should_spawn_garages(
     buildings_spawned.0 && !garages_spawned.0
 }
 
-/// Parking garage marker component.
+/// Parking garage, with its spaces and how many the parking simulation has filled.
 #[derive(Component)]
 pub struct ParkingGarage {
     pub floors: u32,
     pub capacity: u32,
+    pub occupied: u32,
 }
 
+/// Spaces on each floor of a garage.
+pub const SPACES_PER_FLOOR: u32 = 40;
+
 #[derive(Resource)]
 pub struct ParkingGarageConfig {
     pub seed: u64,
@@ -65,34 +70,17 @@ impl Default for ParkingGarageConfig {
     }
 }
 
-fn spawn_parking_garages(
-    mut commands: Commands,
-    config: Res<ParkingGarageConfig>,
-    buildings: Query<(&Building, &Transform)>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    mut spawned: ResMut<ParkingGaragesSpawned>,
-) {
-    spawned.0 = true;
-
-    let mut rng = StdRng::seed_from_u64(config.seed);
-    let mut garage_count = 0;
-    let mut placed_positions: Vec<Vec3> = Vec::new();
-
-    // Collect commercial building positions (garages near commercial areas)
-    let mut eligible_positions: Vec<Vec3> = buildings
-        .iter()
-        .filter(|(b, _)| b.building_type == BuildingArchetype::Commercial)
-        .map(|(_, t)| t.translation)
-        .collect();
-
-    // Shuffle
-    for i in (1..eligible_positions.len()).rev() {
-        let j = rng.gen_range(0..=i);
-        eligible_positions.swap(i, j);
-    }
+/// Materials shared by every garage.
+#[derive(Resource)]
+pub struct ParkingGarageAssets {
+    concrete_material: Handle<StandardMaterial>,
+    floor_material: Handle<StandardMaterial>,
+    barrier_material: Handle<StandardMaterial>,
+    ramp_material: Handle<StandardMaterial>,
+    stripe_material: Handle<StandardMaterial>,
+}
 
-    // Materials
+fn setup_parking_garage_assets(mut commands: Commands, mut materials: ResMut<Assets<StandardMaterial>>) {
     let concrete_material = materials.add(StandardMaterial {
         base_color: Color::srgb(0.55, 0.55, 0.53),
         perceptual_roughness: 0.9,
@@ -123,6 +111,43 @@ fn spawn_parking_garages(
         ..default()
     });
 
+    commands.insert_resource(ParkingGarageAssets {
+        concrete_material,
+        floor_material,
+        barrier_material,
+        ramp_material,
+        stripe_material,
+    });
+}
+
+fn spawn_parking_garages(
+    mut commands: Commands,
+    config: Res<ParkingGarageConfig>,
+    assets: Res<ParkingGarageAssets>,
+    buildings: Query<(&Building, &Transform)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut spawned: ResMut<ParkingGaragesSpawned>,
+) {
+    spawned.0 = true;
+
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut garage_count = 0;
+    let mut total_spaces = 0;
+    let mut placed_positions: Vec<Vec3> = Vec::new();
+
+    // Collect commercial building positions (garages near commercial areas)
+    let mut eligible_positions: Vec<Vec3> = buildings
+        .iter()
+        .filter(|(b, _)| b.building_type == BuildingArchetype::Commercial)
+        .map(|(_, t)| t.translation)
+        .collect();
+
+    // Shuffle
+    for i in (1..eligible_positions.len()).rev() {
+        let j = rng.gen_range(0..=i);
+        eligible_positions.swap(i, j);
+    }
+
     for pos in eligible_positions {
         if garage_count >= config.max_garages {
             break;
@@ -146,160 +171,183 @@ fn spawn_parking_garages(
 
         let rotation = Quat::from_rotation_y(rng.gen_range(0..4) as f32 * PI / 2.0);
         let floors = rng.gen_range(config.min_floors..=config.max_floors);
-        let total_height = floors as f32 * config.floor_height;
-        let capacity = floors * 40; // ~40 spaces per floor
-
-        commands
-            .spawn((
-                Transform::from_translation(garage_pos).with_rotation(rotation),
-                GlobalTransform::default(),
-                Visibility::Visible,
-                InheritedVisibility::default(),
-                ViewVisibility::default(),
-                ParkingGarage { floors, capacity },
-            ))
-            .with_children(|parent| {
-                // Create each floor
-                for floor in 0..floors {
-                    let floor_y = floor as f32 * config.floor_height;
-
-                    // Floor slab
-                    let slab_mesh = meshes.add(Cuboid::new(config.width, 0.2, config.depth));
-                    parent.spawn((
-                        Mesh3d(slab_mesh),
-                        MeshMaterial3d(floor_material.clone()),
-                        Transform::from_xyz(0.0, floor_y + 0.1, 0.0),
-                    ));
+        spawn_parking_garage(
+            &mut commands,
+            &config,
+            &assets,
+            &mut meshes,
+            Transform::from_translation(garage_pos).with_rotation(rotation),
+            floors,
+        );
 
-                    // Columns at corners and intervals
-                    let column_mesh = meshes.add(Cuboid::new(0.5, config.floor_height - 0.2, 0.5));
-                    let columns_x = 3;
-                    let columns_z = 2;
-                    for cx in 0..=columns_x {
-                        for cz in 0..=columns_z {
-                            let x = -config.width / 2.0 + 1.0 + cx as f32 * (config.width - 2.0) / columns_x as f32;
-                            let z = -config.depth / 2.0 + 1.0 + cz as f32 * (config.depth - 2.0) / columns_z as f32;
-                            parent.spawn((
-                                Mesh3d(column_mesh.clone()),
-                                MeshMaterial3d(concrete_material.clone()),
-                                Transform::from_xyz(x, floor_y + config.floor_height / 2.0, z),
-                            ));
-                        }
-                    }
+        placed_positions.push(garage_pos);
+        garage_count += 1;
+        total_spaces += floors * SPACES_PER_FLOOR;
+    }
+
+    info!(
+        "Spawned {} parking garages with {} total capacity",
+        garage_count, total_spaces
+    );
+}
 
-                    // Perimeter barriers/walls (partial height for visibility)
-                    let barrier_height = 1.0;
-                    let barrier_mesh_long = meshes.add(Cuboid::new(config.width, barrier_height, 0.15));
-                    let barrier_mesh_short = meshes.add(Cuboid::new(0.15, barrier_height, config.depth));
+/// Spawn an empty garage of `floors` storeys with its ramps and stairwell.
+pub fn spawn_parking_garage(
+    commands: &mut Commands,
+    config: &ParkingGarageConfig,
+    assets: &ParkingGarageAssets,
+    meshes: &mut Assets<Mesh>,
+    transform: Transform,
+    floors: u32,
+) -> Entity {
+    let total_height = floors as f32 * config.floor_height;
+
+    commands
+        .spawn((
+            transform,
+            GlobalTransform::default(),
+            Visibility::Visible,
+            InheritedVisibility::default(),
+            ViewVisibility::default(),
+            ParkingGarage {
+                floors,
+                capacity: floors * SPACES_PER_FLOOR,
+                occupied: 0,
+            },
+        ))
+        .with_children(|parent| {
+            // Create each floor
+            for floor in 0..floors {
+                let floor_y = floor as f32 * config.floor_height;
+
+                // Floor slab
+                let slab_mesh = meshes.add(Cuboid::new(config.width, 0.2, config.depth));
+                parent.spawn((
+                    Mesh3d(slab_mesh),
+                    MeshMaterial3d(assets.floor_material.clone()),
+                    Transform::from_xyz(0.0, floor_y + 0.1, 0.0),
+                ));
 
-                    // Front and back barriers
-                    for z in [-config.depth / 2.0 + 0.1, config.depth / 2.0 - 0.1] {
+                // Columns at corners and intervals
+                let column_mesh = meshes.add(Cuboid::new(0.5, config.floor_height - 0.2, 0.5));
+                let columns_x = 3;
+                let columns_z = 2;
+                for cx in 0..=columns_x {
+                    for cz in 0..=columns_z {
+                        let x = -config.width / 2.0 + 1.0 + cx as f32 * (config.width - 2.0) / columns_x as f32;
+                        let z = -config.depth / 2.0 + 1.0 + cz as f32 * (config.depth - 2.0) / columns_z as f32;
                         parent.spawn((
-                            Mesh3d(barrier_mesh_long.clone()),
-                            MeshMaterial3d(barrier_material.clone()),
-                            Transform::from_xyz(0.0, floor_y + barrier_height / 2.0 + 0.2, z),
+                            Mesh3d(column_mesh.clone()),
+                            MeshMaterial3d(assets.concrete_material.clone()),
+                            Transform::from_xyz(x, floor_y + config.floor_height / 2.0, z),
                         ));
                     }
+                }
 
-                    // Side barriers (with opening for ramp)
-                    if floor > 0 {
-                        // Left side with ramp opening
-                        let side_barrier_mesh =
-                            meshes.add(Cuboid::new(0.15, barrier_height, config.depth - config.ramp_width - 1.0));
-                        parent.spawn((
-                            Mesh3d(side_barrier_mesh.clone()),
-                            MeshMaterial3d(barrier_material.clone()),
-                            Transform::from_xyz(
-                                -config.width / 2.0 + 0.1,
-                                floor_y + barrier_height / 2.0 + 0.2,
-                                (config.ramp_width + 1.0) / 2.0,
-                            ),
-                        ));
-                    } else {
-                        parent.spawn((
-                            Mesh3d(barrier_mesh_short.clone()),
-                            MeshMaterial3d(barrier_material.clone()),
-                            Transform::from_xyz(-config.width / 2.0 + 0.1, floor_y + barrier_height / 2.0 + 0.2, 0.0),
-                        ));
-                    }
+                // Perimeter barriers/walls (partial height for visibility)
+                let barrier_height = 1.0;
+                let barrier_mesh_long = meshes.add(Cuboid::new(config.width, barrier_height, 0.15));
+                let barrier_mesh_short = meshes.add(Cuboid::new(0.15, barrier_height, config.depth));
 
-                    // Right side (full)
+                // Front and back barriers
+                for z in [-config.depth / 2.0 + 0.1, config.depth / 2.0 - 0.1] {
                     parent.spawn((
-                        Mesh3d(barrier_mesh_short.clone()),
-                        MeshMaterial3d(barrier_material.clone()),
-                        Transform::from_xyz(config.width / 2.0 - 0.1, floor_y + barrier_height / 2.0 + 0.2, 0.0),
+                        Mesh3d(barrier_mesh_long.clone()),
+                        MeshMaterial3d(assets.barrier_material.clone()),
+                        Transform::from_xyz(0.0, floor_y + barrier_height / 2.0 + 0.2, z),
                     ));
-
-                    // Parking space lines on floor
-                    let line_mesh = meshes.add(Cuboid::new(0.1, 0.02, 2.0));
-                    let spaces_per_row = 8;
-                    let space_width = (config.width - 4.0) / spaces_per_row as f32;
-                    for i in 0..=spaces_per_row {
-                        let x = -config.width / 2.0 + 2.0 + i as f32 * space_width;
-                        // Front row
-                        parent.spawn((
-                            Mesh3d(line_mesh.clone()),
-                            MeshMaterial3d(stripe_material.clone()),
-                            Transform::from_xyz(x, floor_y + 0.22, -config.depth / 4.0),
-                        ));
-                        // Back row
-                        parent.spawn((
-                            Mesh3d(line_mesh.clone()),
-                            MeshMaterial3d(stripe_material.clone()),
-                            Transform::from_xyz(x, floor_y + 0.22, config.depth / 4.0),
-                        ));
-                    }
                 }
 
-                // Ramps between floors (spiral style on one side)
-                let ramp_mesh = meshes.add(Cuboid::new(config.ramp_width, 0.15, config.depth * 0.6));
-                for floor in 0..floors - 1 {
-                    let floor_y = floor as f32 * config.floor_height;
-                    let next_floor_y = (floor + 1) as f32 * config.floor_height;
-                    let ramp_y = (floor_y + next_floor_y) / 2.0;
-
-                    // Inclined ramp
-                    let ramp_angle = ((next_floor_y - floor_y) / (config.depth * 0.6)).atan();
+                // Side barriers (with opening for ramp)
+                if floor > 0 {
+                    // Left side with ramp opening
+                    let side_barrier_mesh =
+                        meshes.add(Cuboid::new(0.15, barrier_height, config.depth - config.ramp_width - 1.0));
                     parent.spawn((
-                        Mesh3d(ramp_mesh.clone()),
-                        MeshMaterial3d(ramp_material.clone()),
-                        Transform::from_xyz(-config.width / 2.0 + config.ramp_width / 2.0 + 0.5, ramp_y, -config.depth / 4.0)
-                            .with_rotation(Quat::from_rotation_x(-ramp_angle)),
+                        Mesh3d(side_barrier_mesh.clone()),
+                        MeshMaterial3d(assets.barrier_material.clone()),
+                        Transform::from_xyz(
+                            -config.width / 2.0 + 0.1,
+                            floor_y + barrier_height / 2.0 + 0.2,
+                            (config.ramp_width + 1.0) / 2.0,
+                        ),
+                    ));
+                } else {
+                    parent.spawn((
+                        Mesh3d(barrier_mesh_short.clone()),
+                        MeshMaterial3d(assets.barrier_material.clone()),
+                        Transform::from_xyz(-config.width / 2.0 + 0.1, floor_y + barrier_height / 2.0 + 0.2, 0.0),
                     ));
                 }
 
-                // Roof slab
-                let roof_mesh = meshes.add(Cuboid::new(config.width, 0.3, config.depth));
+                // Right side (full)
                 parent.spawn((
-                    Mesh3d(roof_mesh),
-                    MeshMaterial3d(concrete_material.clone()),
-                    Transform::from_xyz(0.0, total_height + 0.15, 0.0),
+                    Mesh3d(barrier_mesh_short.clone()),
+                    MeshMaterial3d(assets.barrier_material.clone()),
+                    Transform::from_xyz(config.width / 2.0 - 0.1, floor_y + barrier_height / 2.0 + 0.2, 0.0),
                 ));
 
-                // Entry/exit on ground floor (opening in barrier)
-                let entry_sign_mesh = meshes.add(Cuboid::new(3.0, 0.5, 0.1));
-                parent.spawn((
-                    Mesh3d(entry_sign_mesh),
-                    MeshMaterial3d(stripe_material.clone()),
-                    Transform::from_xyz(0.0, 2.5, -config.depth / 2.0 - 0.1),
-                ));
+                // Parking space lines on floor
+                let line_mesh = meshes.add(Cuboid::new(0.1, 0.02, 2.0));
+                let spaces_per_row = 8;
+                let space_width = (config.width - 4.0) / spaces_per_row as f32;
+                for i in 0..=spaces_per_row {
+                    let x = -config.width / 2.0 + 2.0 + i as f32 * space_width;
+                    // Front row
+                    parent.spawn((
+                        Mesh3d(line_mesh.clone()),
+                        MeshMaterial3d(assets.stripe_material.clone()),
+                        Transform::from_xyz(x, floor_y + 0.22, -config.depth / 4.0),
+                    ));
+                    // Back row
+                    parent.spawn((
+                        Mesh3d(line_mesh.clone()),
+                        MeshMaterial3d(assets.stripe_material.clone()),
+                        Transform::from_xyz(x, floor_y + 0.22, config.depth / 4.0),
+                    ));
+                }
+            }
 
-                // Stairwell structure on one corner
-                let stair_tower_mesh = meshes.add(Cuboid::new(3.0, total_height + 2.0, 3.0));
+            // Ramps between floors (spiral style on one side)
+            let ramp_mesh = meshes.add(Cuboid::new(config.ramp_width, 0.15, config.depth * 0.6));
+            for floor in 0..floors - 1 {
+                let floor_y = floor as f32 * config.floor_height;
+                let next_floor_y = (floor + 1) as f32 * config.floor_height;
+                let ramp_y = (floor_y + next_floor_y) / 2.0;
+
+                // Inclined ramp
+                let ramp_angle = ((next_floor_y - floor_y) / (config.depth * 0.6)).atan();
                 parent.spawn((
-                    Mesh3d(stair_tower_mesh),
-                    MeshMaterial3d(concrete_material.clone()),
-                    Transform::from_xyz(config.width / 2.0 - 1.5, (total_height + 2.0) / 2.0, config.depth / 2.0 - 1.5),
+                    Mesh3d(ramp_mesh.clone()),
+                    MeshMaterial3d(assets.ramp_material.clone()),
+                    Transform::from_xyz(-config.width / 2.0 + config.ramp_width / 2.0 + 0.5, ramp_y, -config.depth / 4.0)
+                        .with_rotation(Quat::from_rotation_x(-ramp_angle)),
                 ));
-            });
-
-        placed_positions.push(garage_pos);
-        garage_count += 1;
-    }
-
-    info!(
-        "Spawned {} parking garages with {} total capacity",
-        garage_count,
-        garage_count * 40 * 4
-    );
+            }
+
+            // Roof slab
+            let roof_mesh = meshes.add(Cuboid::new(config.width, 0.3, config.depth));
+            parent.spawn((
+                Mesh3d(roof_mesh),
+                MeshMaterial3d(assets.concrete_material.clone()),
+                Transform::from_xyz(0.0, total_height + 0.15, 0.0),
+            ));
+
+            // Entry/exit on ground floor (opening in barrier)
+            let entry_sign_mesh = meshes.add(Cuboid::new(3.0, 0.5, 0.1));
+            parent.spawn((
+                Mesh3d(entry_sign_mesh),
+                MeshMaterial3d(assets.stripe_material.clone()),
+                Transform::from_xyz(0.0, 2.5, -config.depth / 2.0 - 0.1),
+            ));
+
+            // Stairwell structure on one corner
+            let stair_tower_mesh = meshes.add(Cuboid::new(3.0, total_height + 2.0, 3.0));
+            parent.spawn((
+                Mesh3d(stair_tower_mesh),
+                MeshMaterial3d(assets.concrete_material.clone()),
+                Transform::from_xyz(config.width / 2.0 - 1.5, (total_height + 2.0) / 2.0, config.depth / 2.0 - 1.5),
+            ));
+        })
+        .id()
 }
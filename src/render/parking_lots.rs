@@ -1,6 +1,7 @@
 //! Surface parking lots near commercial and industrial areas.
 //!
-//! Spawns parking lots with marked spaces and parked cars.
+//! Spawns parking lots with marked spaces and a car in every space, shown
+//! only while the parking simulation has the space taken.
 
 use bevy::prelude::*;
 use rand::{rngs::StdRng, Rng, SeedableRng};
@@ -15,7 +16,8 @@ impl Plugin for ParkingLotsPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<ParkingLotConfig>()
             .init_resource::<ParkingLotsSpawned>()
-            .add_systems(Update, spawn_parking_lots.run_if(should_spawn_lots));
+            .add_systems(Startup, setup_parking_lot_assets)
+            .add_systems(Update, (spawn_parking_lots.run_if(should_spawn_lots), show_lot_occupancy));
     }
 }
 
@@ -29,7 +31,7 @@ fn should_spawn_lots(
     buildings_spawned.0 && !lots_spawned.0
 }
 
-/// Parking lot marker component.
+/// Parking lot, with its spaces and how many the parking simulation has filled.
 #[derive(Component)]
 pub struct ParkingLot {
     pub capacity: u32,
@@ -61,6 +63,10 @@ impl Default for ParkingLotConfig {
     }
 }
 
+/// A parked car in a lot, shown while at least `.0 + 1` spaces are taken.
+#[derive(Component)]
+struct LotCar(u32);
+
 // Car colors for parked cars
 const CAR_COLORS: &[(f32, f32, f32)] = &[
     (0.1, 0.1, 0.12),
@@ -73,18 +79,80 @@ const CAR_COLORS: &[(f32, f32, f32)] = &[
     (0.4, 0.35, 0.25),
 ];
 
-fn spawn_parking_lots(
+/// Meshes and materials shared by every lot.
+#[derive(Resource)]
+pub struct ParkingLotAssets {
+    lot_surface: Handle<Mesh>,
+    line_mesh: Handle<Mesh>,
+    curb_mesh: Handle<Mesh>,
+    car_body_mesh: Handle<Mesh>,
+    car_cabin_mesh: Handle<Mesh>,
+    asphalt_material: Handle<StandardMaterial>,
+    marking_material: Handle<StandardMaterial>,
+    curb_material: Handle<StandardMaterial>,
+    window_material: Handle<StandardMaterial>,
+    car_materials: Vec<Handle<StandardMaterial>>,
+}
+
+fn setup_parking_lot_assets(
     mut commands: Commands,
     config: Res<ParkingLotConfig>,
-    buildings: Query<(&Building, &Transform)>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(ParkingLotAssets {
+        lot_surface: meshes.add(Cuboid::new(config.lot_width, 0.05, config.lot_depth)),
+        line_mesh: meshes.add(Cuboid::new(0.1, 0.02, config.space_depth * 0.8)),
+        curb_mesh: meshes.add(Cuboid::new(config.lot_width + 0.4, 0.15, 0.2)),
+        car_body_mesh: meshes.add(Cuboid::new(4.0, 1.2 * 0.6, 1.7)),
+        car_cabin_mesh: meshes.add(Cuboid::new(2.0, 1.2 * 0.4, 1.5)),
+        asphalt_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.2, 0.2, 0.22),
+            perceptual_roughness: 0.9,
+            ..default()
+        }),
+        marking_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.9, 0.9, 0.85),
+            perceptual_roughness: 0.7,
+            ..default()
+        }),
+        curb_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.6, 0.6, 0.58),
+            perceptual_roughness: 0.8,
+            ..default()
+        }),
+        window_material: materials.add(StandardMaterial {
+            base_color: Color::srgba(0.1, 0.15, 0.2, 0.8),
+            perceptual_roughness: 0.1,
+            metallic: 0.3,
+            ..default()
+        }),
+        car_materials: CAR_COLORS
+            .iter()
+            .map(|&(r, g, b)| {
+                materials.add(StandardMaterial {
+                    base_color: Color::srgb(r, g, b),
+                    perceptual_roughness: 0.4,
+                    metallic: 0.6,
+                    ..default()
+                })
+            })
+            .collect(),
+    });
+}
+
+fn spawn_parking_lots(
+    mut commands: Commands,
+    config: Res<ParkingLotConfig>,
+    assets: Res<ParkingLotAssets>,
+    buildings: Query<(&Building, &Transform)>,
     mut spawned: ResMut<ParkingLotsSpawned>,
 ) {
     spawned.0 = true;
 
     let mut rng = StdRng::seed_from_u64(config.seed);
     let mut lot_count = 0;
+    let mut total_spaces = 0;
     let mut placed_positions: Vec<Vec3> = Vec::new();
 
     // Collect commercial and industrial building positions
@@ -105,32 +173,6 @@ fn spawn_parking_lots(
         eligible_positions.swap(i, j);
     }
 
-    // Materials
-    let asphalt_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.2, 0.2, 0.22),
-        perceptual_roughness: 0.9,
-        ..default()
-    });
-
-    let marking_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.9, 0.9, 0.85),
-        perceptual_roughness: 0.7,
-        ..default()
-    });
-
-    let curb_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.6, 0.6, 0.58),
-        perceptual_roughness: 0.8,
-        ..default()
-    });
-
-    // Meshes
-    let lot_surface = meshes.add(Cuboid::new(config.lot_width, 0.05, config.lot_depth));
-    let line_mesh = meshes.add(Cuboid::new(0.1, 0.02, config.space_depth * 0.8));
-    let curb_mesh = meshes.add(Cuboid::new(config.lot_width + 0.4, 0.15, 0.2));
-    let car_body_mesh = meshes.add(Cuboid::new(4.0, 1.2 * 0.6, 1.7));
-    let car_cabin_mesh = meshes.add(Cuboid::new(2.0, 1.2 * 0.4, 1.5));
-
     for pos in eligible_positions {
         if lot_count >= config.max_lots {
             break;
@@ -153,31 +195,68 @@ fn spawn_parking_lots(
         }
 
         let rotation = Quat::from_rotation_y(rng.gen_range(0..4) as f32 * PI / 2.0);
-        let spaces_per_row = (config.lot_width / config.space_width).floor() as u32;
-        let rows = 2;
-        let capacity = spaces_per_row * rows;
-        let occupied = rng.gen_range(capacity / 3..capacity);
-
-        commands.spawn((
+        spawn_parking_lot(
+            &mut commands,
+            &config,
+            &assets,
             Transform::from_translation(lot_pos).with_rotation(rotation),
+            &mut rng,
+        );
+
+        placed_positions.push(lot_pos);
+        lot_count += 1;
+        total_spaces += lot_capacity(&config);
+    }
+
+    info!("Spawned {} parking lots with {} total spaces", lot_count, total_spaces);
+}
+
+/// Spaces in a lot: two facing rows across its width.
+pub fn lot_capacity(config: &ParkingLotConfig) -> u32 {
+    (config.lot_width / config.space_width).floor() as u32 * 2
+}
+
+/// Spawn an empty lot with its markings and a hidden car in every space.
+pub fn spawn_parking_lot(
+    commands: &mut Commands,
+    config: &ParkingLotConfig,
+    assets: &ParkingLotAssets,
+    transform: Transform,
+    rng: &mut impl Rng,
+) -> Entity {
+    let spaces_per_row = (config.lot_width / config.space_width).floor() as u32;
+    let rows = 2;
+    let capacity = lot_capacity(config);
+
+    // Spaces fill in a random order rather than along the rows
+    let mut fill_order: Vec<u32> = (0..capacity).collect();
+    for i in (1..fill_order.len()).rev() {
+        let j = rng.gen_range(0..=i);
+        fill_order.swap(i, j);
+    }
+
+    commands
+        .spawn((
+            transform,
             GlobalTransform::default(),
             Visibility::Visible,
             InheritedVisibility::default(),
             ViewVisibility::default(),
-            ParkingLot { capacity, occupied },
-        )).with_children(|parent| {
+            ParkingLot { capacity, occupied: 0 },
+        ))
+        .with_children(|parent| {
             // Asphalt surface
             parent.spawn((
-                Mesh3d(lot_surface.clone()),
-                MeshMaterial3d(asphalt_material.clone()),
+                Mesh3d(assets.lot_surface.clone()),
+                MeshMaterial3d(assets.asphalt_material.clone()),
                 Transform::from_xyz(0.0, 0.025, 0.0),
             ));
 
             // Curbs on front and back
             for z in [-config.lot_depth / 2.0, config.lot_depth / 2.0] {
                 parent.spawn((
-                    Mesh3d(curb_mesh.clone()),
-                    MeshMaterial3d(curb_material.clone()),
+                    Mesh3d(assets.curb_mesh.clone()),
+                    MeshMaterial3d(assets.curb_material.clone()),
                     Transform::from_xyz(0.0, 0.075, z),
                 ));
             }
@@ -188,20 +267,19 @@ fn spawn_parking_lots(
                 let x = start_x + i as f32 * config.space_width - config.space_width / 2.0;
                 // Front row lines
                 parent.spawn((
-                    Mesh3d(line_mesh.clone()),
-                    MeshMaterial3d(marking_material.clone()),
+                    Mesh3d(assets.line_mesh.clone()),
+                    MeshMaterial3d(assets.marking_material.clone()),
                     Transform::from_xyz(x, 0.06, -config.lot_depth / 4.0),
                 ));
                 // Back row lines
                 parent.spawn((
-                    Mesh3d(line_mesh.clone()),
-                    MeshMaterial3d(marking_material.clone()),
+                    Mesh3d(assets.line_mesh.clone()),
+                    MeshMaterial3d(assets.marking_material.clone()),
                     Transform::from_xyz(x, 0.06, config.lot_depth / 4.0),
                 ));
             }
 
-            // Spawn parked cars in occupied spaces
-            let mut cars_placed = 0;
+            // A car in every space, hidden until the space is taken
             for row in 0..rows {
                 let row_z = if row == 0 {
                     -config.lot_depth / 4.0
@@ -215,54 +293,48 @@ fn spawn_parking_lots(
                 };
 
                 for space in 0..spaces_per_row {
-                    if cars_placed >= occupied {
-                        break;
-                    }
-
-                    // Random chance to skip (create empty spaces)
-                    if rng.gen::<f32>() < 0.3 {
-                        continue;
-                    }
-
+                    let order = fill_order[(row * spaces_per_row + space) as usize];
                     let space_x = start_x + space as f32 * config.space_width;
-                    let (r, g, b) = CAR_COLORS[rng.gen_range(0..CAR_COLORS.len())];
-
-                    let car_material = materials.add(StandardMaterial {
-                        base_color: Color::srgb(r, g, b),
-                        perceptual_roughness: 0.4,
-                        metallic: 0.6,
-                        ..default()
-                    });
-
-                    let window_material = materials.add(StandardMaterial {
-                        base_color: Color::srgba(0.1, 0.15, 0.2, 0.8),
-                        perceptual_roughness: 0.1,
-                        metallic: 0.3,
-                        ..default()
-                    });
+                    let car_material = assets.car_materials[rng.gen_range(0..assets.car_materials.len())].clone();
 
                     // Car body
                     parent.spawn((
-                        Mesh3d(car_body_mesh.clone()),
-                        MeshMaterial3d(car_material.clone()),
+                        Mesh3d(assets.car_body_mesh.clone()),
+                        MeshMaterial3d(car_material),
                         Transform::from_xyz(space_x, 0.4, row_z).with_rotation(car_rotation),
+                        Visibility::Hidden,
+                        LotCar(order),
                     ));
 
                     // Car cabin
                     parent.spawn((
-                        Mesh3d(car_cabin_mesh.clone()),
-                        MeshMaterial3d(window_material),
+                        Mesh3d(assets.car_cabin_mesh.clone()),
+                        MeshMaterial3d(assets.window_material.clone()),
                         Transform::from_xyz(space_x, 0.8, row_z).with_rotation(car_rotation),
+                        Visibility::Hidden,
+                        LotCar(order),
                     ));
-
-                    cars_placed += 1;
                 }
             }
-        });
+        })
+        .id()
+}
 
-        placed_positions.push(lot_pos);
-        lot_count += 1;
+/// Show as many of a lot's cars as it has spaces taken.
+fn show_lot_occupancy(
+    lots: Query<(&ParkingLot, &Children), Changed<ParkingLot>>,
+    mut cars: Query<(&LotCar, &mut Visibility)>,
+) {
+    for (lot, children) in &lots {
+        for &child in children {
+            if let Ok((car, mut visibility)) = cars.get_mut(child) {
+                let wanted = if car.0 < lot.occupied {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                };
+                visibility.set_if_neq(wanted);
+            }
+        }
     }
-
-    info!("Spawned {} parking lots with {} total spaces", lot_count, lot_count * 16);
 }
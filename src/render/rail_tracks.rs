@@ -0,0 +1,178 @@
+//! Player-built rail track and stations.
+//!
+//! Rebuilt whenever the rail network changes. Elevated track uses the
+//! viaduct, pillar and platform meshes of the generated railway and
+//! at-grade track lies on the same bed at street level. Tunnels are not
+//! drawn; underground stations get a subway entrance either side of the line.
+
+use bevy::prelude::*;
+
+use crate::render::elevated_rail::{
+    create_pillar_mesh, create_platform_mesh, create_track_bed_mesh, ElevatedRailConfig, RailMaterials,
+};
+use crate::render::subway_entrances::{spawn_subway_entrance, SubwayEntrance, SubwayEntranceAssets, SubwayEntranceConfig};
+use crate::simulation::rail_network::{RailNetwork, TrackLevel};
+use crate::world::terrain::HeightMap;
+
+pub struct RailTracksPlugin;
+
+impl Plugin for RailTracksPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, sync_rail_meshes);
+    }
+}
+
+/// Marker for everything built for the player's rail network.
+#[derive(Component)]
+pub struct RailNetworkPiece;
+
+/// Rails sit this far above the centre of the bed under them.
+const RAIL_ABOVE_BED: f32 = 0.3;
+
+#[allow(clippy::too_many_arguments)]
+fn sync_rail_meshes(
+    mut commands: Commands,
+    network: Res<RailNetwork>,
+    config: Res<ElevatedRailConfig>,
+    rail_materials: Res<RailMaterials>,
+    entrance_config: Res<SubwayEntranceConfig>,
+    entrance_assets: Res<SubwayEntranceAssets>,
+    terrain: Res<HeightMap>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    pieces: Query<Entity, With<RailNetworkPiece>>,
+) {
+    if !network.is_changed() {
+        return;
+    }
+    for entity in &pieces {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let graph = &network.graph;
+    let pillar_mesh = meshes.add(create_pillar_mesh(config.pillar_width, config.track_height));
+    // Stretched along the track to its length
+    let rail_mesh = meshes.add(Cuboid::new(1.0, 0.1, 0.07));
+
+    for edge in graph.edge_indices() {
+        let Some((a, b)) = graph.edge_endpoints(edge) else {
+            continue;
+        };
+        let level = graph[edge].level;
+        if level == TrackLevel::Underground {
+            continue;
+        }
+        let (from, to) = (graph[a].position, graph[b].position);
+        let (ground_from, ground_to) = (terrain.sample_world(from), terrain.sample_world(to));
+        let start = Vec3::new(from.x, ground_from + level.height(config.track_height), from.y);
+        let end = Vec3::new(to.x, ground_to + level.height(config.track_height), to.y);
+        let length = start.distance(end);
+        let run = from.distance(to);
+        if run < 0.1 {
+            continue;
+        }
+        let dir = (to - from) / run;
+        let perp = Vec3::new(-dir.y, 0.0, dir.x);
+        let rotation = Quat::from_rotation_y(-dir.y.atan2(dir.x)) * Quat::from_rotation_z((end.y - start.y).atan2(run));
+        let mid = (start + end) / 2.0;
+
+        // Track bed (viaduct)
+        commands.spawn((
+            Mesh3d(meshes.add(create_track_bed_mesh(length, config.track_width, 0.5))),
+            MeshMaterial3d(rail_materials.concrete.clone()),
+            Transform::from_translation(mid - Vec3::Y * RAIL_ABOVE_BED).with_rotation(rotation),
+            RailNetworkPiece,
+        ));
+
+        // Rails on top of bed
+        for side in [-1.0, 1.0] {
+            commands.spawn((
+                Mesh3d(rail_mesh.clone()),
+                MeshMaterial3d(rail_materials.rail.clone()),
+                Transform::from_translation(mid + perp * side * config.rail_gauge / 2.0)
+                    .with_rotation(rotation)
+                    .with_scale(Vec3::new(length, 1.0, 1.0)),
+                RailNetworkPiece,
+            ));
+        }
+
+        // Support pillars
+        if level == TrackLevel::Elevated {
+            let pillar_count = (run / config.pillar_spacing).ceil() as i32;
+            for p in 0..=pillar_count {
+                let t = p as f32 / pillar_count as f32;
+                let pos = from.lerp(to, t);
+                let ground = ground_from.lerp(ground_to, t);
+                commands.spawn((
+                    Mesh3d(pillar_mesh.clone()),
+                    MeshMaterial3d(rail_materials.concrete.clone()),
+                    Transform::from_xyz(pos.x, ground + config.track_height / 2.0, pos.y),
+                    RailNetworkPiece,
+                ));
+            }
+        }
+    }
+
+    let platform_mesh = meshes.add(create_platform_mesh(config.station_length, config.platform_width, 0.3));
+    let safety_mesh = meshes.add(Cuboid::new(config.station_length, 0.02, 0.2));
+    let canopy_mesh = meshes.add(Cuboid::new(config.station_length * 0.8, 0.15, config.platform_width));
+
+    for node in graph.node_indices() {
+        if graph[node].station.is_none() {
+            continue;
+        }
+        let pos = graph[node].position;
+        let ground = terrain.sample_world(pos);
+        let dir = network.heading_at(node);
+        let perp = Vec2::new(-dir.y, dir.x);
+        let angle = dir.y.atan2(dir.x);
+        let level = network.station_level(node);
+
+        // Underground: an entrance each side, stairs heading down towards the line
+        if level == TrackLevel::Underground {
+            for side in [-1.0, 1.0] {
+                let offset = perp * side * (config.track_width / 2.0 + config.platform_width + 4.0);
+                let towards_line = -perp * side;
+                let transform = Transform::from_xyz(pos.x + offset.x, ground, pos.y + offset.y)
+                    .with_rotation(Quat::from_rotation_y(towards_line.x.atan2(towards_line.y)));
+                let entrance = SubwayEntrance {
+                    station_name: node.index() as u32,
+                };
+                let entity = spawn_subway_entrance(&mut commands, &entrance_config, &entrance_assets, transform, entrance);
+                commands.entity(entity).insert(RailNetworkPiece);
+            }
+            continue;
+        }
+
+        let rail_y = ground + level.height(config.track_height);
+        for side in [-1.0, 1.0] {
+            // Platform
+            let offset = perp * side * (config.track_width / 2.0 + config.platform_width / 2.0);
+            commands.spawn((
+                Mesh3d(platform_mesh.clone()),
+                MeshMaterial3d(rail_materials.platform.clone()),
+                Transform::from_xyz(pos.x + offset.x, rail_y - 0.15, pos.y + offset.y)
+                    .with_rotation(Quat::from_rotation_y(-angle)),
+                RailNetworkPiece,
+            ));
+
+            // Canopy/shelter over platform
+            commands.spawn((
+                Mesh3d(canopy_mesh.clone()),
+                MeshMaterial3d(rail_materials.concrete.clone()),
+                Transform::from_xyz(pos.x + offset.x, rail_y + 3.2, pos.y + offset.y)
+                    .with_rotation(Quat::from_rotation_y(-angle)),
+                RailNetworkPiece,
+            ));
+
+            // Safety line (yellow edge stripe)
+            let edge = perp * side * (config.track_width / 2.0 + 0.3);
+            commands.spawn((
+                Mesh3d(safety_mesh.clone()),
+                MeshMaterial3d(rail_materials.safety.clone()),
+                Transform::from_xyz(pos.x + edge.x, rail_y + 0.01, pos.y + edge.y)
+                    .with_rotation(Quat::from_rotation_y(-angle)),
+                RailNetworkPiece,
+            ));
+        }
+    }
+}
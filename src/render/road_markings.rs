@@ -1,35 +1,57 @@
-//! Road lane markings - center lines, edge lines.
+//! Road lane markings - center lines, edge lines, one-way arrows, bike lanes and bike symbols.
 //!
-//! All markings are batched into 2 meshes (yellow center, white edge) for performance.
+//! Each road segment's markings are batched into at most 3 meshes (yellow
+//! center, white edge, green bike lane), spawned as children of the segment so they are
+//! rebuilt and removed together with it.
 
 use bevy::prelude::*;
 use bevy::render::mesh::{Indices, PrimitiveTopology};
 use bevy::render::render_asset::RenderAssetUsages;
-use noise::{NoiseFn, Perlin};
 
-use crate::procgen::roads::{RoadGraph, RoadType};
-use crate::render::instancing::TerrainConfig;
-use crate::render::road_mesh::RoadMeshGenerated;
+use crate::procgen::intersections::carriageway_width;
+use crate::procgen::roads::{RoadType, TravelDirection};
+use crate::render::road_mesh::RoadSegment;
+use crate::world::terrain::HeightMap;
 
 pub struct RoadMarkingsPlugin;
 
 impl Plugin for RoadMarkingsPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<MarkingsConfig>()
-            .init_resource::<RoadMarkingsSpawned>()
-            .add_systems(Update, generate_road_markings.run_if(should_generate_markings));
+            .add_systems(Startup, setup_marking_materials)
+            .add_systems(Update, spawn_segment_markings);
     }
 }
 
-/// Marker that road markings have been generated (prevents re-running).
-#[derive(Resource, Default)]
-pub struct RoadMarkingsSpawned(pub bool);
+/// Paint materials shared by all markings.
+#[derive(Resource)]
+struct MarkingMaterials {
+    center: Handle<StandardMaterial>,
+    edge: Handle<StandardMaterial>,
+    bike: Handle<StandardMaterial>,
+}
 
-fn should_generate_markings(
-    road_mesh_query: Query<&RoadMeshGenerated>,
-    spawned: Res<RoadMarkingsSpawned>,
-) -> bool {
-    !road_mesh_query.is_empty() && !spawned.0
+fn setup_marking_materials(mut commands: Commands, mut materials: ResMut<Assets<StandardMaterial>>) {
+    commands.insert_resource(MarkingMaterials {
+        // Center lines (yellow)
+        center: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.95, 0.85, 0.3),
+            perceptual_roughness: 0.7,
+            ..default()
+        }),
+        // Edge lines (white)
+        edge: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.95, 0.95, 0.95),
+            perceptual_roughness: 0.7,
+            ..default()
+        }),
+        // Bike lanes (green)
+        bike: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.3, 0.6, 0.35),
+            perceptual_roughness: 0.8,
+            ..default()
+        }),
+    });
 }
 
 /// Marker for road marking entities.
@@ -43,6 +65,11 @@ pub struct MarkingsConfig {
     pub dash_length: f32,
     pub gap_length: f32,
     pub marking_height: f32,
+    pub bike_lane_width: f32,
+    /// Distance between direction arrows on one-way roads.
+    pub arrow_spacing: f32,
+    /// Distance between bicycle symbols painted in bike lanes.
+    pub bike_symbol_spacing: f32,
 }
 
 impl Default for MarkingsConfig {
@@ -52,6 +79,9 @@ impl Default for MarkingsConfig {
             dash_length: 3.0,
             gap_length: 2.0,
             marking_height: 0.15, // Slightly above road
+            bike_lane_width: 1.2,
+            arrow_spacing: 25.0,
+            bike_symbol_spacing: 30.0,
         }
     }
 }
@@ -75,7 +105,7 @@ impl MarkingsMeshBuilder {
     }
 
     /// Add a dash quad to the batch.
-    fn add_dash(&mut self, start: Vec2, end: Vec2, width: f32, height_offset: f32, terrain: &TerrainSampler) {
+    fn add_dash(&mut self, start: Vec2, end: Vec2, width: f32, height_offset: f32, terrain: &HeightMap) {
         let dir = (end - start).normalize_or_zero();
         let perp = Vec2::new(-dir.y, dir.x);
         let half_width = width / 2.0;
@@ -86,10 +116,10 @@ impl MarkingsMeshBuilder {
         let v3 = end - perp * half_width;
 
         // Sample terrain height at each vertex
-        let h0 = terrain.sample(v0.x, v0.y) + height_offset;
-        let h1 = terrain.sample(v1.x, v1.y) + height_offset;
-        let h2 = terrain.sample(v2.x, v2.y) + height_offset;
-        let h3 = terrain.sample(v3.x, v3.y) + height_offset;
+        let h0 = terrain.sample_world(v0) + height_offset;
+        let h1 = terrain.sample_world(v1) + height_offset;
+        let h2 = terrain.sample_world(v2) + height_offset;
+        let h3 = terrain.sample_world(v3) + height_offset;
 
         let base_index = self.vertices.len() as u32;
 
@@ -128,7 +158,7 @@ impl MarkingsMeshBuilder {
         dash_length: f32,
         gap_length: f32,
         height_offset: f32,
-        terrain: &TerrainSampler,
+        terrain: &HeightMap,
     ) {
         let cycle_length = dash_length + gap_length;
 
@@ -163,6 +193,76 @@ impl MarkingsMeshBuilder {
         }
     }
 
+    /// Add direction arrows along a polyline, pointing along the point order
+    /// unless `reverse`.
+    fn add_arrows(&mut self, points: &[Vec2], reverse: bool, spacing: f32, height_offset: f32, terrain: &HeightMap) {
+        let mut segments: Vec<(Vec2, Vec2, f32)> = Vec::new();
+        let mut total_dist = 0.0;
+        for window in points.windows(2) {
+            segments.push((window[0], window[1], total_dist));
+            total_dist += window[0].distance(window[1]);
+        }
+        let count = (total_dist / spacing).floor() as usize;
+        for i in 0..count {
+            let at = (i as f32 + 0.5) * total_dist / count as f32;
+            let (Some(tail), Some(head)) = (
+                point_at_distance(&segments, at - 1.5),
+                point_at_distance(&segments, at + 1.5),
+            ) else {
+                continue;
+            };
+            let (tail, head) = if reverse { (head, tail) } else { (tail, head) };
+            let dir = (head - tail).normalize_or_zero();
+            let perp = Vec2::new(-dir.y, dir.x);
+            self.add_dash(tail, head, 0.3, height_offset, terrain);
+            for side in [1.0, -1.0] {
+                self.add_dash(head - dir * 1.0 + perp * 0.6 * side, head, 0.3, height_offset, terrain);
+            }
+        }
+    }
+
+    /// Add bicycle symbols along a polyline: two wheels and a frame, lying
+    /// along the lane with the bars towards the left of the point order.
+    fn add_bike_symbols(&mut self, points: &[Vec2], spacing: f32, height_offset: f32, terrain: &HeightMap) {
+        let mut segments: Vec<(Vec2, Vec2, f32)> = Vec::new();
+        let mut total_dist = 0.0;
+        for window in points.windows(2) {
+            segments.push((window[0], window[1], total_dist));
+            total_dist += window[0].distance(window[1]);
+        }
+        let count = (total_dist / spacing).floor() as usize;
+        for i in 0..count {
+            let at = (i as f32 + 0.5) * total_dist / count as f32;
+            let (Some(rear), Some(front)) = (
+                point_at_distance(&segments, at - 0.5),
+                point_at_distance(&segments, at + 0.5),
+            ) else {
+                continue;
+            };
+            let dir = (front - rear).normalize_or_zero();
+            let up = Vec2::new(-dir.y, dir.x);
+            // Wheels as rings of short dashes
+            for hub in [rear, front] {
+                let ring: Vec<Vec2> = (0..=8)
+                    .map(|k| {
+                        let angle = k as f32 / 8.0 * std::f32::consts::TAU;
+                        hub + (dir * angle.cos() + up * angle.sin()) * 0.3
+                    })
+                    .collect();
+                for w in ring.windows(2) {
+                    self.add_dash(w[0], w[1], 0.08, height_offset, terrain);
+                }
+            }
+            // Chain stay, seat tube, top tube and fork
+            let pedals = (rear + front) / 2.0;
+            let saddle = pedals - dir * 0.1 + up * 0.4;
+            let bars = front - dir * 0.05 + up * 0.45;
+            for (a, b) in [(rear, pedals), (pedals, saddle), (saddle, bars), (pedals, bars), (bars, front)] {
+                self.add_dash(a, b, 0.08, height_offset, terrain);
+            }
+        }
+    }
+
     /// Build the final mesh (returns None if empty).
     fn build(self) -> Option<Mesh> {
         if self.vertices.is_empty() {
@@ -179,128 +279,134 @@ impl MarkingsMeshBuilder {
     }
 }
 
-/// Generate road markings - batched into 2 meshes.
-fn generate_road_markings(
+/// Paint markings on newly built road segments.
+fn spawn_segment_markings(
     mut commands: Commands,
-    road_graph: Res<RoadGraph>,
+    segments: Query<(Entity, &RoadSegment), Added<RoadSegment>>,
     config: Res<MarkingsConfig>,
-    terrain_config: Res<TerrainConfig>,
+    terrain: Res<HeightMap>,
+    marking_mats: Res<MarkingMaterials>,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    mut spawned: ResMut<RoadMarkingsSpawned>,
 ) {
-    info!("Generating road markings...");
-
-    let terrain = TerrainSampler::new(&terrain_config);
+    for (owner, segment) in &segments {
+        let edge = &segment.edge;
 
-    // Batched mesh builders
-    let mut center_lines = MarkingsMeshBuilder::new();
-    let mut edge_lines = MarkingsMeshBuilder::new();
-
-    let mut center_count = 0;
-    let mut edge_count = 0;
-
-    for edge in road_graph.edges() {
-        if edge.points.len() < 2 {
+        // Skip bridges and tunnels - neither gets road markings
+        if edge.is_structure() {
             continue;
         }
-
-        // Skip edges that cross water - bridges don't get road markings
-        if edge.crosses_water {
+        // Markings stop where the junctions begin
+        let Some(points) = segment.surface_points() else {
             continue;
-        }
+        };
 
-        // Determine which markings to add
+        let road_width = carriageway_width(edge.road_type);
+        let one_way = edge.direction.is_one_way();
+
+        // Determine which markings to add; medians and one-way roads need no center line
         let (add_center, add_edges) = match edge.road_type {
-            RoadType::Highway => (true, true),
-            RoadType::Major => (true, false),
+            RoadType::Highway => (!edge.median && !one_way, true),
+            RoadType::Major => (!edge.median && !one_way, false),
             RoadType::Minor => (false, false),
             RoadType::Alley => (false, false),
         };
 
+        let mut center_lines = MarkingsMeshBuilder::new();
+        let mut edge_lines = MarkingsMeshBuilder::new();
+        let mut bike_lanes = MarkingsMeshBuilder::new();
+
         if add_center {
             center_lines.add_dashed_line(
-                &edge.points,
+                &points,
                 config.center_line_width,
                 config.dash_length,
                 config.gap_length,
                 config.marking_height,
                 &terrain,
             );
-            center_count += 1;
         }
 
         if add_edges {
-            let road_width = match edge.road_type {
-                RoadType::Highway => 12.0,
-                RoadType::Major => 8.0,
-                RoadType::Minor => 5.0,
-                RoadType::Alley => 3.0,
-            };
-
-            // Left edge line
-            let left_points = offset_polyline(&edge.points, road_width / 2.0 - 0.5);
-            edge_lines.add_dashed_line(
-                &left_points,
-                config.center_line_width * 0.8,
-                config.dash_length * 2.0,
-                config.gap_length,
-                config.marking_height,
-                &terrain,
-            );
-
-            // Right edge line
-            let right_points = offset_polyline(&edge.points, -road_width / 2.0 + 0.5);
-            edge_lines.add_dashed_line(
-                &right_points,
-                config.center_line_width * 0.8,
-                config.dash_length * 2.0,
-                config.gap_length,
-                config.marking_height,
-                &terrain,
-            );
+            // Left and right edge lines
+            for side in [1.0, -1.0] {
+                let side_points = offset_polyline(&points, (road_width / 2.0 - 0.5) * side);
+                edge_lines.add_dashed_line(
+                    &side_points,
+                    config.center_line_width * 0.8,
+                    config.dash_length * 2.0,
+                    config.gap_length,
+                    config.marking_height,
+                    &terrain,
+                );
+            }
+        }
 
-            edge_count += 1;
+        if one_way {
+            // Lane dividers between same-direction lanes
+            let (forward, backward) = edge.lane_counts();
+            let lanes = forward.max(backward);
+            for lane in 1..lanes {
+                let offset = road_width * (lane as f32 / lanes as f32 - 0.5);
+                edge_lines.add_dashed_line(
+                    &offset_polyline(&points, offset),
+                    config.center_line_width * 0.8,
+                    config.dash_length,
+                    config.gap_length * 2.0,
+                    config.marking_height,
+                    &terrain,
+                );
+            }
+            // Arrows showing the permitted direction
+            let reverse = edge.direction == TravelDirection::Backward;
+            edge_lines.add_arrows(&points, reverse, config.arrow_spacing, config.marking_height, &terrain);
         }
-    }
 
-    // Spawn center line mesh (yellow)
-    if let Some(center_mesh) = center_lines.build() {
-        let center_material = materials.add(StandardMaterial {
-            base_color: Color::srgb(0.95, 0.85, 0.3),
-            perceptual_roughness: 0.7,
-            ..default()
-        });
-
-        commands.spawn((
-            Mesh3d(meshes.add(center_mesh)),
-            MeshMaterial3d(center_material),
-            Transform::IDENTITY,
-            RoadMarking,
-        ));
-    }
+        if edge.bike_lanes {
+            // Painted lane along each kerb, separated by a solid line
+            let half = road_width / 2.0;
+            for side in [1.0, -1.0] {
+                bike_lanes.add_dashed_line(
+                    &offset_polyline(&points, (half - config.bike_lane_width / 2.0) * side),
+                    config.bike_lane_width,
+                    config.dash_length,
+                    0.0,
+                    config.marking_height * 0.9,
+                    &terrain,
+                );
+                edge_lines.add_dashed_line(
+                    &offset_polyline(&points, (half - config.bike_lane_width) * side),
+                    config.center_line_width * 0.6,
+                    config.dash_length,
+                    0.0,
+                    config.marking_height,
+                    &terrain,
+                );
+                edge_lines.add_bike_symbols(
+                    &offset_polyline(&points, (half - config.bike_lane_width / 2.0) * side),
+                    config.bike_symbol_spacing,
+                    config.marking_height,
+                    &terrain,
+                );
+            }
+        }
 
-    // Spawn edge line mesh (white)
-    if let Some(edge_mesh) = edge_lines.build() {
-        let edge_material = materials.add(StandardMaterial {
-            base_color: Color::srgb(0.95, 0.95, 0.95),
-            perceptual_roughness: 0.7,
-            ..default()
-        });
-
-        commands.spawn((
-            Mesh3d(meshes.add(edge_mesh)),
-            MeshMaterial3d(edge_material),
-            Transform::IDENTITY,
-            RoadMarking,
-        ));
+        for (builder, material) in [
+            (center_lines, &marking_mats.center),
+            (edge_lines, &marking_mats.edge),
+            (bike_lanes, &marking_mats.bike),
+        ] {
+            if let Some(mesh) = builder.build() {
+                commands.entity(owner).with_children(|parent| {
+                    parent.spawn((
+                        Mesh3d(meshes.add(mesh)),
+                        MeshMaterial3d(material.clone()),
+                        Transform::IDENTITY,
+                        RoadMarking,
+                    ));
+                });
+            }
+        }
     }
-
-    spawned.0 = true;
-    info!(
-        "Road markings generated: {} center lines, {} edge lines (2 batched meshes)",
-        center_count, edge_count
-    );
 }
 
 /// Get point at a specific distance along the polyline.
@@ -341,44 +447,3 @@ fn offset_polyline(points: &[Vec2], offset: f32) -> Vec<Vec2> {
         })
         .collect()
 }
-
-/// Helper struct for sampling terrain height.
-struct TerrainSampler {
-    perlin: Perlin,
-    noise_scale: f32,
-    height_scale: f32,
-    octaves: u32,
-}
-
-impl TerrainSampler {
-    fn new(config: &TerrainConfig) -> Self {
-        Self {
-            perlin: Perlin::new(config.seed),
-            noise_scale: config.noise_scale,
-            height_scale: config.height_scale,
-            octaves: config.octaves,
-        }
-    }
-
-    fn sample(&self, x: f32, z: f32) -> f32 {
-        if !x.is_finite() || !z.is_finite() {
-            return 0.0;
-        }
-
-        let mut height = 0.0;
-        let mut amplitude = 1.0;
-        let mut frequency = self.noise_scale;
-        let mut max_amplitude = 0.0;
-
-        for _ in 0..self.octaves {
-            let sample_x = x as f64 * frequency as f64;
-            let sample_z = z as f64 * frequency as f64;
-            height += self.perlin.get([sample_x, sample_z]) as f32 * amplitude;
-            max_amplitude += amplitude;
-            amplitude *= 0.5;
-            frequency *= 2.0;
-        }
-
-        (height / max_amplitude) * self.height_scale
-    }
-}
@@ -1,73 +1,165 @@
 //! Road mesh generation from road graph.
 //!
 //! Converts road edges into renderable quad strips with proper width.
+//!
+//! Each edge and each intersection owns its meshes through a single entity
+//! (`RoadSegment` / `RoadJunction`). When the graph changes only owners whose
+//! geometry changed are respawned; other modules attach their per-edge and
+//! per-intersection props (markings, lamps, crosswalks, lights) as children
+//! of these owners so they follow automatically.
 
 #![allow(dead_code)]
 
+use std::collections::HashMap;
+
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy::render::mesh::{Indices, PrimitiveTopology};
-use noise::{NoiseFn, Perlin};
 use petgraph::graph::NodeIndex;
+use smallvec::SmallVec;
 
+use crate::procgen::intersections::{update_junction_layout, JunctionGeometry, JunctionLayout};
 use crate::procgen::road_generator::RoadsGenerated;
-use crate::procgen::roads::{RoadGraph, RoadType};
-use crate::render::instancing::TerrainConfig;
+use crate::procgen::roads::{polyline_length, slice_polyline, RoadEdge, RoadGraph, RoadType, TravelDirection};
 use crate::tools::road_draw::RoadMeshDirty;
+use crate::world::terrain::{HeightMap, TerrainModified};
 
 pub struct RoadMeshPlugin;
 
 impl Plugin for RoadMeshPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<RoadMeshConfig>()
-            .add_systems(Update, generate_road_meshes.run_if(should_generate_meshes))
-            .add_systems(Update, handle_road_mesh_dirty);
+            .add_systems(Startup, setup_road_materials)
+            .add_systems(
+                Update,
+                (
+                    sync_road_meshes.run_if(should_sync_meshes),
+                    rebuild_roads_on_terrain_edit.run_if(on_event::<TerrainModified>),
+                )
+                    .chain()
+                    .after(update_junction_layout),
+            );
     }
 }
 
-/// Handle dynamic road mesh updates when roads are added/removed.
-fn handle_road_mesh_dirty(
-    mut commands: Commands,
-    mut dirty_events: EventReader<RoadMeshDirty>,
-    road_meshes: Query<Entity, With<RoadMesh>>,
-    sidewalk_meshes: Query<Entity, With<SidewalkMesh>>,
-    curb_meshes: Query<Entity, With<CurbMesh>>,
-    intersection_meshes: Query<Entity, With<IntersectionMesh>>,
-    marker: Query<Entity, With<RoadMeshGenerated>>,
-) {
-    if dirty_events.read().next().is_none() {
-        return;
-    }
+fn should_sync_meshes(
+    generated: Res<RoadsGenerated>,
+    dirty_events: EventReader<RoadMeshDirty>,
+    road_graph: Res<RoadGraph>,
+    query: Query<&RoadMeshGenerated>,
+) -> bool {
+    generated.0 && (query.is_empty() || !dirty_events.is_empty() || road_graph.is_changed())
+}
 
-    // Clear all events
-    dirty_events.clear();
+/// Marker that road meshes have been generated.
+#[derive(Component)]
+pub struct RoadMeshGenerated;
 
-    info!("Regenerating road meshes...");
+/// Owner of one road edge's meshes.
+///
+/// The surface, curbs and sidewalks are children, as are props other
+/// modules attach per edge. Bridges and tunnels get an owner but no surface.
+#[derive(Component, Clone)]
+pub struct RoadSegment {
+    /// Copy of the edge as it was when the meshes were built.
+    pub edge: RoadEdge,
+    /// Junction setbacks at the first and last point of the edge.
+    pub trim: (f32, f32),
+    /// XZ bounds of everything the segment draws.
+    pub bounds: Rect,
+}
 
-    // Despawn existing road meshes (including curbs)
-    for entity in road_meshes.iter()
-        .chain(sidewalk_meshes.iter())
-        .chain(curb_meshes.iter())
-        .chain(intersection_meshes.iter())
-    {
-        commands.entity(entity).despawn();
+impl RoadSegment {
+    pub fn new(edge: RoadEdge, trim: (f32, f32), config: &RoadMeshConfig) -> Self {
+        let reach = config.width(edge.road_type) / 2.0 + config.curb_width + config.sidewalk_width;
+        let mut bounds = Rect::from_center_size(edge.points[0], Vec2::ZERO);
+        for p in &edge.points {
+            bounds = bounds.union_point(*p);
+        }
+        Self {
+            bounds: bounds.inflate(reach),
+            edge,
+            trim,
+        }
     }
 
-    // Remove the generated marker so meshes will regenerate
-    for entity in &marker {
-        commands.entity(entity).despawn();
+    /// Centreline between the junction boundaries, or None if junctions cover it all.
+    pub fn surface_points(&self) -> Option<SmallVec<[Vec2; 8]>> {
+        let length = polyline_length(&self.edge.points);
+        let end = length - self.trim.1;
+        if end - self.trim.0 < 0.2 {
+            return None;
+        }
+        let points = slice_polyline(&self.edge.points, self.trim.0, end);
+        (points.len() >= 2).then_some(points)
     }
 }
 
-fn should_generate_meshes(
-    generated: Res<RoadsGenerated>,
-    query: Query<&RoadMeshGenerated>,
-) -> bool {
-    generated.0 && query.is_empty()
+/// Owner of one intersection's meshes (nodes joining two or more edges).
+#[derive(Component, Clone)]
+pub struct RoadJunction {
+    /// Graph node, refreshed when node indices shift.
+    pub node: NodeIndex,
+    /// Outline, legs and turn paths the meshes were built from.
+    pub geometry: JunctionGeometry,
+    /// XZ bounds of everything the junction draws.
+    pub bounds: Rect,
 }
 
-/// Marker that road meshes have been generated.
-#[derive(Component)]
-pub struct RoadMeshGenerated;
+/// Geometry identity of a segment; equal keys draw identical meshes.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct SegmentKey {
+    points: Vec<IVec2>,
+    road_type: RoadType,
+    crosses_water: bool,
+    tunnel: bool,
+    direction: TravelDirection,
+    median: bool,
+    bike_lanes: bool,
+    trim: IVec2,
+}
+
+impl SegmentKey {
+    fn of(edge: &RoadEdge, trim: (f32, f32)) -> Self {
+        Self {
+            points: edge.points.iter().map(|p| quantize(*p, 100.0)).collect(),
+            road_type: edge.road_type,
+            crosses_water: edge.crosses_water,
+            tunnel: edge.tunnel,
+            direction: edge.direction,
+            median: edge.median,
+            bike_lanes: edge.bike_lanes,
+            trim: quantize(Vec2::new(trim.0, trim.1), 10.0),
+        }
+    }
+}
+
+/// Geometry identity of a junction.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct JunctionKey {
+    position: IVec2,
+    legs: Vec<(IVec2, RoadType, i32, bool, bool)>,
+}
+
+impl JunctionKey {
+    fn of(geometry: &JunctionGeometry) -> Self {
+        Self {
+            position: quantize(geometry.center, 100.0),
+            legs: geometry
+                .legs
+                .iter()
+                .map(|leg| {
+                    let setback = (leg.setback * 10.0).round() as i32;
+                    (quantize(leg.direction, 1000.0), leg.road_type, setback, leg.incoming, leg.outgoing)
+                })
+                .collect(),
+        }
+    }
+}
+
+fn quantize(v: Vec2, scale: f32) -> IVec2 {
+    (v * scale).round().as_ivec2()
+}
 
 /// Marker for road mesh entities.
 #[derive(Component)]
@@ -98,6 +190,21 @@ pub struct RoadMeshConfig {
     pub sidewalk_width: f32,
     pub curb_height: f32,
     pub curb_width: f32,
+    pub median_width: f32,
+    /// How far a median stops short of a junction, leaving room for the crosswalk.
+    pub median_setback: f32,
+}
+
+impl RoadMeshConfig {
+    /// Carriageway width for a road class.
+    pub fn width(&self, road_type: RoadType) -> f32 {
+        match road_type {
+            RoadType::Highway => self.highway_width,
+            RoadType::Major => self.major_width,
+            RoadType::Minor => self.minor_width,
+            RoadType::Alley => self.alley_width,
+        }
+    }
 }
 
 impl Default for RoadMeshConfig {
@@ -111,11 +218,14 @@ impl Default for RoadMeshConfig {
             sidewalk_width: 2.0,
             curb_height: 0.15,
             curb_width: 0.2,
+            median_width: 1.2,
+            median_setback: 4.0,
         }
     }
 }
 
 /// Road material presets for different road types.
+#[derive(Resource)]
 struct RoadMaterials {
     highway: Handle<StandardMaterial>,
     major: Handle<StandardMaterial>,
@@ -201,284 +311,338 @@ impl RoadMaterials {
     }
 }
 
-/// Generate road meshes from the road graph.
-fn generate_road_meshes(
-    mut commands: Commands,
+fn setup_road_materials(mut commands: Commands, mut materials: ResMut<Assets<StandardMaterial>>) {
+    commands.insert_resource(RoadMaterials::new(&mut materials));
+}
+
+/// Describe every intersection in the graph.
+fn collect_junctions(layout: &JunctionLayout, config: &RoadMeshConfig) -> Vec<RoadJunction> {
+    layout
+        .iter()
+        .map(|geometry| {
+            let reach = geometry.radius() + config.curb_width + config.sidewalk_width;
+            RoadJunction {
+                node: geometry.node,
+                bounds: Rect::from_center_half_size(geometry.center, Vec2::splat(reach)),
+                geometry: geometry.clone(),
+            }
+        })
+        .collect()
+}
+
+/// Setbacks at the first and last point of an edge.
+fn edge_trim(road_graph: &RoadGraph, layout: &JunctionLayout, edge_idx: petgraph::graph::EdgeIndex) -> (f32, f32) {
+    let (Some((a, b)), Some(edge)) = (road_graph.edge_endpoints(edge_idx), road_graph.edge_by_index(edge_idx)) else {
+        return (0.0, 0.0);
+    };
+    if edge.is_structure() {
+        return (0.0, 0.0);
+    }
+    let starts_at_a = road_graph
+        .node_by_index(a)
+        .is_some_and(|node| node.position.distance_squared(edge.points[0]) <= node.position.distance_squared(edge.points[edge.points.len() - 1]));
+    let (first, last) = if starts_at_a { (a, b) } else { (b, a) };
+    (layout.setback(first, edge_idx), layout.setback(last, edge_idx))
+}
+
+/// Everything segment and junction owners are spawned with.
+#[derive(SystemParam)]
+struct RoadSpawner<'w, 's> {
+    commands: Commands<'w, 's>,
+    meshes: ResMut<'w, Assets<Mesh>>,
+    road_mats: Res<'w, RoadMaterials>,
+    config: Res<'w, RoadMeshConfig>,
+    terrain: Res<'w, HeightMap>,
+}
+
+/// Bring road meshes in line with the graph, respawning only what changed.
+fn sync_road_meshes(
+    mut dirty_events: EventReader<RoadMeshDirty>,
     road_graph: Res<RoadGraph>,
-    config: Res<RoadMeshConfig>,
-    terrain_config: Res<TerrainConfig>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    layout: Res<JunctionLayout>,
+    spawner: RoadSpawner,
+    segments: Query<(Entity, &RoadSegment)>,
+    mut junctions: Query<(Entity, &mut RoadJunction)>,
+    marker: Query<&RoadMeshGenerated>,
 ) {
-    info!("Generating road meshes...");
-
-    // Create terrain sampler with same seed as terrain
-    let terrain = TerrainSampler::new(&terrain_config);
+    let RoadSpawner { mut commands, mut meshes, road_mats, config, terrain } = spawner;
+    dirty_events.clear();
 
-    // Create road materials with distinct appearance per road type
-    let road_mats = RoadMaterials::new(&mut materials);
+    // Segments: keep unchanged owners, despawn stale ones, spawn the rest
+    let mut wanted: HashMap<SegmentKey, (&RoadEdge, (f32, f32))> = road_graph
+        .edge_indices()
+        .filter_map(|idx| road_graph.edge_by_index(idx).map(|edge| (idx, edge)))
+        .filter(|(_, edge)| edge.points.len() >= 2)
+        .map(|(idx, edge)| {
+            let trim = edge_trim(&road_graph, &layout, idx);
+            (SegmentKey::of(edge, trim), (edge, trim))
+        })
+        .collect();
+    let mut removed = 0;
+    for (entity, segment) in &segments {
+        if wanted.remove(&SegmentKey::of(&segment.edge, segment.trim)).is_none() {
+            commands.entity(entity).despawn_recursive();
+            removed += 1;
+        }
+    }
+    let added = wanted.len();
+    for (edge, trim) in wanted.into_values() {
+        let segment = RoadSegment::new(edge.clone(), trim, &config);
+        spawn_segment(&mut commands, &mut meshes, &road_mats, &config, &terrain, segment);
+    }
 
-    // Generate mesh for each edge
-    for edge in road_graph.edges() {
-        if edge.points.len() < 2 {
-            continue;
+    // Junctions: same, but refresh node indices on the ones we keep
+    let mut wanted: HashMap<JunctionKey, RoadJunction> = collect_junctions(&layout, &config)
+        .into_iter()
+        .map(|junction| (JunctionKey::of(&junction.geometry), junction))
+        .collect();
+    for (entity, mut junction) in &mut junctions {
+        match wanted.remove(&JunctionKey::of(&junction.geometry)) {
+            // Same shape; just pick up the shifted node and edge indices
+            Some(current) if current.node != junction.node || !same_edges(&current.geometry, &junction.geometry) => {
+                *junction = current;
+            }
+            Some(_) => {}
+            None => {
+                commands.entity(entity).despawn_recursive();
+                removed += 1;
+            }
         }
+    }
+    let added = added + wanted.len();
+    for junction in wanted.into_values() {
+        spawn_junction(&mut commands, &mut meshes, &road_mats, &config, &terrain, junction);
+    }
 
-        // Skip edges that cross water - bridges handle those
-        if edge.crosses_water {
-            continue;
+    if marker.is_empty() {
+        // Marker entity so dependent systems know roads exist
+        commands.spawn(RoadMeshGenerated);
+        info!("Road meshes generated: {} edges and intersections", added);
+    } else if added > 0 || removed > 0 {
+        info!("Road meshes updated: {} rebuilt, {} removed", added, removed);
+    }
+}
+
+fn same_edges(a: &JunctionGeometry, b: &JunctionGeometry) -> bool {
+    a.legs.iter().map(|leg| leg.edge).eq(b.legs.iter().map(|leg| leg.edge))
+}
+
+/// Road surfaces follow the terrain, so rebuild the owners an edit touched.
+fn rebuild_roads_on_terrain_edit(
+    mut terrain_events: EventReader<TerrainModified>,
+    spawner: RoadSpawner,
+    segments: Query<(Entity, &RoadSegment)>,
+    junctions: Query<(Entity, &RoadJunction)>,
+) {
+    let RoadSpawner { mut commands, mut meshes, road_mats, config, terrain } = spawner;
+    let regions: Vec<Rect> = terrain_events.read().map(|e| e.region).collect();
+    let touched = |bounds: &Rect| regions.iter().any(|r| !r.intersect(*bounds).is_empty());
+
+    for (entity, segment) in &segments {
+        if touched(&segment.bounds) {
+            commands.entity(entity).despawn_recursive();
+            spawn_segment(&mut commands, &mut meshes, &road_mats, &config, &terrain, segment.clone());
+        }
+    }
+    for (entity, junction) in &junctions {
+        if touched(&junction.bounds) {
+            commands.entity(entity).despawn_recursive();
+            spawn_junction(&mut commands, &mut meshes, &road_mats, &config, &terrain, junction.clone());
         }
+    }
+}
 
-        let width = match edge.road_type {
-            RoadType::Highway => config.highway_width,
-            RoadType::Major => config.major_width,
-            RoadType::Minor => config.minor_width,
-            RoadType::Alley => config.alley_width,
-        };
+/// Spawn the owner entity and surface meshes for one edge.
+fn spawn_segment(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    road_mats: &RoadMaterials,
+    config: &RoadMeshConfig,
+    terrain: &HeightMap,
+    segment: RoadSegment,
+) {
+    let width = config.width(segment.edge.road_type);
+    let road_type = segment.edge.road_type;
+    let structure = segment.edge.is_structure();
+    let median = segment.edge.median;
+    let trim = segment.trim;
+    let surface = segment.surface_points();
+    let mut owner = commands.spawn((segment, Transform::IDENTITY, Visibility::default()));
+
+    // Bridges and tunnels - their own meshes handle those
+    if structure {
+        return;
+    }
+    // Short edges can sit entirely inside their junctions
+    let Some(points) = surface else {
+        return;
+    };
 
-        let mesh = create_road_strip_mesh(&edge.points, width, config.road_height, &terrain);
+    owner.with_children(|parent| {
+        let mesh = create_road_strip_mesh(&points, width, config.road_height, terrain);
 
         // Use road type-specific material
-        commands.spawn((
+        parent.spawn((
             Mesh3d(meshes.add(mesh)),
-            MeshMaterial3d(road_mats.get_road_material(edge.road_type)),
+            MeshMaterial3d(road_mats.get_road_material(road_type)),
             Transform::IDENTITY,
-            RoadMesh {
-                road_type: edge.road_type,
-            },
+            RoadMesh { road_type },
         ));
 
+        // Raised median down the centre, stopping short of junction crosswalks
+        if median {
+            let length = polyline_length(&points);
+            let start = if trim.0 > 0.0 { config.median_setback } else { 0.0 };
+            let end = length - if trim.1 > 0.0 { config.median_setback } else { 0.0 };
+            let median_points = slice_polyline(&points, start, end);
+            if end - start > 1.0 && median_points.len() >= 2 {
+                let mesh = create_road_strip_mesh(
+                    &median_points,
+                    config.median_width,
+                    config.road_height + config.curb_height,
+                    terrain,
+                );
+                parent.spawn((
+                    Mesh3d(meshes.add(mesh)),
+                    MeshMaterial3d(road_mats.curb.clone()),
+                    Transform::IDENTITY,
+                    CurbMesh,
+                ));
+            }
+        }
+
         // Add sidewalks and curbs (not for alleys or highways)
-        if edge.road_type == RoadType::Major || edge.road_type == RoadType::Minor {
+        if road_type == RoadType::Major || road_type == RoadType::Minor {
             let curb_offset = width / 2.0 + config.curb_width / 2.0;
             let sidewalk_offset = width / 2.0 + config.curb_width + config.sidewalk_width / 2.0;
             let sidewalk_height = config.road_height + config.curb_height;
 
-            // Left curb (between road and left sidewalk)
-            let left_curb_points = offset_polyline(&edge.points, curb_offset);
-            let left_curb_mesh = create_curb_strip_mesh(
-                &left_curb_points,
-                config.curb_width,
-                config.curb_height,
-                config.road_height,
-                1.0, // Left side
-                &terrain,
-            );
-            commands.spawn((
-                Mesh3d(meshes.add(left_curb_mesh)),
-                MeshMaterial3d(road_mats.curb.clone()),
-                Transform::IDENTITY,
-                CurbMesh,
-            ));
+            // Left side (1.0) then right side (-1.0)
+            for side in [1.0, -1.0] {
+                // Curb between road and sidewalk
+                let curb_points = offset_polyline(&points, curb_offset * side);
+                let curb_mesh = create_curb_strip_mesh(
+                    &curb_points,
+                    config.curb_width,
+                    config.curb_height,
+                    config.road_height,
+                    side,
+                    terrain,
+                );
+                parent.spawn((
+                    Mesh3d(meshes.add(curb_mesh)),
+                    MeshMaterial3d(road_mats.curb.clone()),
+                    Transform::IDENTITY,
+                    CurbMesh,
+                ));
+
+                // Sidewalk (raised to curb height)
+                let sidewalk_points = offset_polyline(&points, sidewalk_offset * side);
+                let sidewalk_mesh =
+                    create_road_strip_mesh(&sidewalk_points, config.sidewalk_width, sidewalk_height, terrain);
+                parent.spawn((
+                    Mesh3d(meshes.add(sidewalk_mesh)),
+                    MeshMaterial3d(road_mats.sidewalk.clone()),
+                    Transform::IDENTITY,
+                    SidewalkMesh,
+                ));
+            }
+        }
+    });
+}
 
-            // Left sidewalk (raised to curb height)
-            let left_points = offset_polyline(&edge.points, sidewalk_offset);
-            let left_mesh = create_road_strip_mesh(&left_points, config.sidewalk_width, sidewalk_height, &terrain);
-            commands.spawn((
-                Mesh3d(meshes.add(left_mesh)),
-                MeshMaterial3d(road_mats.sidewalk.clone()),
-                Transform::IDENTITY,
-                SidewalkMesh,
-            ));
+/// Spawn the owner entity and surface meshes for one intersection.
+fn spawn_junction(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    road_mats: &RoadMaterials,
+    config: &RoadMeshConfig,
+    terrain: &HeightMap,
+    junction: RoadJunction,
+) {
+    let geometry = &junction.geometry;
+    let mesh = create_intersection_mesh(geometry.center, &geometry.outline, config.road_height, terrain);
+
+    // Use the dominant road type's material for this intersection
+    // Prefer highway > major > minor (alleys use the minor material)
+    let intersection_type = if geometry.legs.iter().any(|leg| leg.road_type == RoadType::Highway) {
+        RoadType::Highway
+    } else if geometry.legs.iter().any(|leg| leg.road_type == RoadType::Major) {
+        RoadType::Major
+    } else {
+        RoadType::Minor
+    };
 
-            // Right curb (between road and right sidewalk)
-            let right_curb_points = offset_polyline(&edge.points, -curb_offset);
-            let right_curb_mesh = create_curb_strip_mesh(
-                &right_curb_points,
+    // Curbs and sidewalks round corners where both roads have them
+    let has_sidewalk = |road_type: RoadType| road_type == RoadType::Major || road_type == RoadType::Minor;
+    let corner_curbs: Vec<&[Vec2]> = geometry
+        .corners
+        .iter()
+        .filter(|corner| {
+            has_sidewalk(geometry.legs[corner.legs.0].road_type) && has_sidewalk(geometry.legs[corner.legs.1].road_type)
+        })
+        .map(|corner| corner.curb.as_slice())
+        .filter(|curb| curb.len() >= 2)
+        .collect();
+
+    let mut owner = commands.spawn((Transform::IDENTITY, Visibility::default()));
+    owner.with_children(|parent| {
+        parent.spawn((
+            Mesh3d(meshes.add(mesh)),
+            MeshMaterial3d(road_mats.get_road_material(intersection_type)),
+            Transform::IDENTITY,
+            IntersectionMesh,
+        ));
+
+        // The outline runs counter-clockwise, so the kerb side is on the right
+        let sidewalk_height = config.road_height + config.curb_height;
+        for curb in corner_curbs {
+            let curb_points = offset_polyline(curb, -config.curb_width / 2.0);
+            let curb_mesh = create_curb_strip_mesh(
+                &curb_points,
                 config.curb_width,
                 config.curb_height,
                 config.road_height,
-                -1.0, // Right side
-                &terrain,
+                -1.0,
+                terrain,
             );
-            commands.spawn((
-                Mesh3d(meshes.add(right_curb_mesh)),
+            parent.spawn((
+                Mesh3d(meshes.add(curb_mesh)),
                 MeshMaterial3d(road_mats.curb.clone()),
                 Transform::IDENTITY,
                 CurbMesh,
             ));
 
-            // Right sidewalk (raised to curb height)
-            let right_points = offset_polyline(&edge.points, -sidewalk_offset);
-            let right_mesh = create_road_strip_mesh(&right_points, config.sidewalk_width, sidewalk_height, &terrain);
-            commands.spawn((
-                Mesh3d(meshes.add(right_mesh)),
+            let sidewalk_points = offset_polyline(curb, -(config.curb_width + config.sidewalk_width / 2.0));
+            let sidewalk_mesh =
+                create_road_strip_mesh(&sidewalk_points, config.sidewalk_width, sidewalk_height, terrain);
+            parent.spawn((
+                Mesh3d(meshes.add(sidewalk_mesh)),
                 MeshMaterial3d(road_mats.sidewalk.clone()),
                 Transform::IDENTITY,
                 SidewalkMesh,
             ));
         }
-    }
-
-    // Find the dominant road type at each intersection for material selection
-    let get_intersection_road_type = |node_idx: NodeIndex| -> RoadType {
-        let mut best_type = RoadType::Minor;
-        for neighbor_idx in road_graph.graph.neighbors(node_idx) {
-            if let Some(edge_idx) = road_graph.graph.find_edge(node_idx, neighbor_idx) {
-                if let Some(edge) = road_graph.graph.edge_weight(edge_idx) {
-                    // Prefer highway > major > minor > alley
-                    best_type = match (best_type, edge.road_type) {
-                        (_, RoadType::Highway) => RoadType::Highway,
-                        (RoadType::Highway, _) => RoadType::Highway,
-                        (_, RoadType::Major) => RoadType::Major,
-                        (RoadType::Major, _) => RoadType::Major,
-                        (_, RoadType::Minor) => RoadType::Minor,
-                        (RoadType::Minor, _) => RoadType::Minor,
-                        _ => RoadType::Alley,
-                    };
-                }
-            }
-        }
-        best_type
-    };
-
-    // Generate proper intersection meshes
-    for (node_idx, node) in road_graph.nodes() {
-        let neighbors: Vec<NodeIndex> = road_graph.graph.neighbors(node_idx).collect();
-
-        if neighbors.len() < 2 {
-            continue; // Not a real intersection
-        }
-
-        // Collect directions to all connected roads
-        let mut road_directions: Vec<(Vec2, f32)> = Vec::new();
-
-        for neighbor_idx in &neighbors {
-            if let Some(neighbor_node) = road_graph.graph.node_weight(*neighbor_idx) {
-                let dir = (neighbor_node.position - node.position).normalize_or_zero();
-                // Get the width of this road connection
-                let width = get_road_width_at_node(&road_graph, node_idx, *neighbor_idx, &config);
-                road_directions.push((dir, width));
-            }
-        }
-
-        if road_directions.len() >= 2 {
-            let mesh = create_intersection_mesh(
-                node.position,
-                &road_directions,
-                config.road_height,
-                &terrain,
-            );
-
-            // Use the dominant road type's material for this intersection
-            let intersection_type = get_intersection_road_type(node_idx);
-            commands.spawn((
-                Mesh3d(meshes.add(mesh)),
-                MeshMaterial3d(road_mats.get_road_material(intersection_type)),
-                Transform::IDENTITY,
-                IntersectionMesh,
-            ));
-        }
-    }
-
-    // Marker entity to prevent re-generation
-    commands.spawn(RoadMeshGenerated);
-
-    info!("Road meshes generated");
-}
-
-/// Helper struct for sampling terrain height.
-struct TerrainSampler {
-    perlin: Perlin,
-    noise_scale: f32,
-    height_scale: f32,
-    octaves: u32,
-}
-
-impl TerrainSampler {
-    fn new(config: &TerrainConfig) -> Self {
-        Self {
-            perlin: Perlin::new(config.seed),
-            noise_scale: config.noise_scale,
-            height_scale: config.height_scale,
-            octaves: config.octaves,
-        }
-    }
-
-    /// Sample terrain height at world position (x, z).
-    fn sample(&self, x: f32, z: f32) -> f32 {
-        let mut height = 0.0;
-        let mut amplitude = 1.0;
-        let mut frequency = self.noise_scale;
-        let mut max_amplitude = 0.0;
-
-        for _ in 0..self.octaves {
-            let sample_x = x as f64 * frequency as f64;
-            let sample_z = z as f64 * frequency as f64;
-            height += self.perlin.get([sample_x, sample_z]) as f32 * amplitude;
-            max_amplitude += amplitude;
-            amplitude *= 0.5;
-            frequency *= 2.0;
-        }
-
-        (height / max_amplitude) * self.height_scale
-    }
-}
-
-/// Get the road width for an edge connecting two nodes.
-fn get_road_width_at_node(
-    graph: &RoadGraph,
-    from: NodeIndex,
-    to: NodeIndex,
-    config: &RoadMeshConfig,
-) -> f32 {
-    // Find the edge between these nodes
-    if let Some(edge_idx) = graph.graph.find_edge(from, to) {
-        if let Some(edge) = graph.graph.edge_weight(edge_idx) {
-            return match edge.road_type {
-                RoadType::Highway => config.highway_width,
-                RoadType::Major => config.major_width,
-                RoadType::Minor => config.minor_width,
-                RoadType::Alley => config.alley_width,
-            };
-        }
-    }
-    config.minor_width // Default
+    });
+    owner.insert(junction);
 }
 
-/// Create intersection mesh as a convex polygon connecting all road endpoints.
-fn create_intersection_mesh(
-    center: Vec2,
-    road_directions: &[(Vec2, f32)],
-    height_offset: f32,
-    terrain: &TerrainSampler,
-) -> Mesh {
+/// Create the intersection surface as a fan over its outline.
+fn create_intersection_mesh(center: Vec2, outline: &[Vec2], height_offset: f32, terrain: &HeightMap) -> Mesh {
     let mut vertices: Vec<[f32; 3]> = Vec::new();
     let mut normals: Vec<[f32; 3]> = Vec::new();
     let mut uvs: Vec<[f32; 2]> = Vec::new();
     let mut indices: Vec<u32> = Vec::new();
 
-    // Create corner points for each road connection
-    let mut corner_points: Vec<Vec2> = Vec::new();
-
-    for (dir, width) in road_directions {
-        let half_width = width / 2.0;
-        let perp = Vec2::new(-dir.y, dir.x);
-
-        // Two corners where this road meets the intersection
-        let left = center + *dir * half_width * 0.5 + perp * half_width;
-        let right = center + *dir * half_width * 0.5 - perp * half_width;
-
-        corner_points.push(left);
-        corner_points.push(right);
-    }
-
-    // Sort points by angle around center for proper polygon winding
-    corner_points.sort_by(|a, b| {
-        let angle_a = (a.y - center.y).atan2(a.x - center.x);
-        let angle_b = (b.y - center.y).atan2(b.x - center.x);
-        angle_a.partial_cmp(&angle_b).unwrap()
-    });
-
     // Add center vertex with terrain height
-    let center_height = terrain.sample(center.x, center.y) + height_offset + 0.02;
+    let center_height = terrain.sample_world(center) + height_offset + 0.02;
     vertices.push([center.x, center_height, center.y]);
     normals.push([0.0, 1.0, 0.0]);
     uvs.push([0.5, 0.5]);
 
-    // Add corner vertices with terrain height
-    for point in &corner_points {
-        let point_height = terrain.sample(point.x, point.y) + height_offset + 0.02;
+    // Add outline vertices with terrain height
+    for point in outline {
+        let point_height = terrain.sample_world(*point) + height_offset + 0.02;
         vertices.push([point.x, point_height, point.y]);
         normals.push([0.0, 1.0, 0.0]);
 
@@ -487,14 +651,14 @@ fn create_intersection_mesh(
         uvs.push([uv_x, uv_y]);
     }
 
-    // Create triangles (fan from center)
-    let num_corners = corner_points.len() as u32;
+    // Create triangles (fan from center); the outline runs counter-clockwise
+    // in XZ, so take each pair in reverse for the faces to point up
+    let num_corners = outline.len() as u32;
     for i in 0..num_corners {
         let next = (i + 1) % num_corners;
-        // CCW winding: center, current, next
         indices.push(0);
-        indices.push(i + 1);
         indices.push(next + 1);
+        indices.push(i + 1);
     }
 
     Mesh::new(PrimitiveTopology::TriangleList, default())
@@ -505,7 +669,7 @@ fn create_intersection_mesh(
 }
 
 /// Create a quad strip mesh for a road segment.
-fn create_road_strip_mesh(points: &[Vec2], width: f32, height_offset: f32, terrain: &TerrainSampler) -> Mesh {
+fn create_road_strip_mesh(points: &[Vec2], width: f32, height_offset: f32, terrain: &HeightMap) -> Mesh {
     let half_width = width / 2.0;
     let mut vertices: Vec<[f32; 3]> = Vec::new();
     let mut normals: Vec<[f32; 3]> = Vec::new();
@@ -536,8 +700,8 @@ fn create_road_strip_mesh(points: &[Vec2], width: f32, height_offset: f32, terra
         let right = current - perp * half_width;
 
         // Sample terrain height at each vertex
-        let left_height = terrain.sample(left.x, left.y) + height_offset;
-        let right_height = terrain.sample(right.x, right.y) + height_offset;
+        let left_height = terrain.sample_world(left) + height_offset;
+        let right_height = terrain.sample_world(right) + height_offset;
 
         vertices.push([left.x, left_height, left.y]);
         vertices.push([right.x, right_height, right.y]);
@@ -602,7 +766,7 @@ fn create_curb_strip_mesh(
     curb_height: f32,
     base_height: f32,
     side: f32,
-    terrain: &TerrainSampler,
+    terrain: &HeightMap,
 ) -> Mesh {
     let mut vertices: Vec<[f32; 3]> = Vec::new();
     let mut normals: Vec<[f32; 3]> = Vec::new();
@@ -632,7 +796,7 @@ fn create_curb_strip_mesh(
         let inner = current;
         let outer = current + perp * curb_width;
 
-        let terrain_height = terrain.sample(current.x, current.y);
+        let terrain_height = terrain.sample_world(current);
         let bottom_height = terrain_height + base_height;
         let top_height = bottom_height + curb_height;
 
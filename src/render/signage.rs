@@ -1,6 +1,8 @@
 //! Street signage: street name signs, traffic signs, business signs.
 //!
 //! Spawns various signage throughout the city at intersections and along roads.
+//! Stop and yield signs follow each junction's right-of-way rules and are
+//! attached to the junction, so they are replaced when it is rebuilt.
 
 use bevy::prelude::*;
 use petgraph::graph::NodeIndex;
@@ -8,7 +10,9 @@ use rand::{rngs::StdRng, Rng, SeedableRng};
 use std::f32::consts::PI;
 
 use crate::procgen::roads::{RoadGraph, RoadNodeType, RoadType};
-use crate::render::road_mesh::RoadMeshGenerated;
+use crate::render::road_mesh::{RoadJunction, RoadMeshGenerated};
+use crate::simulation::right_of_way::{junction_control, ApproachControl, JunctionControl};
+use crate::world::terrain::HeightMap;
 
 pub struct SignagePlugin;
 
@@ -16,7 +20,8 @@ impl Plugin for SignagePlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<SignageConfig>()
             .init_resource::<SignageSpawned>()
-            .add_systems(Update, spawn_signage.run_if(should_spawn_signage));
+            .add_systems(Startup, setup_priority_sign_assets)
+            .add_systems(Update, (spawn_signage.run_if(should_spawn_signage), spawn_priority_signs));
     }
 }
 
@@ -52,7 +57,6 @@ pub struct SignageConfig {
     pub sign_height: f32,
     pub pole_radius: f32,
     pub street_sign_prob: f32,
-    pub stop_sign_prob: f32,
     pub speed_sign_prob: f32,
 }
 
@@ -63,7 +67,6 @@ impl Default for SignageConfig {
             sign_height: 2.5,
             pole_radius: 0.04,
             street_sign_prob: 0.7,
-            stop_sign_prob: 0.5,
             speed_sign_prob: 0.3,
         }
     }
@@ -96,12 +99,6 @@ fn spawn_signage(
         ..default()
     });
 
-    let red_sign_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.8, 0.15, 0.1),
-        perceptual_roughness: 0.5,
-        ..default()
-    });
-
     let white_sign_material = materials.add(StandardMaterial {
         base_color: Color::srgb(0.95, 0.95, 0.95),
         perceptual_roughness: 0.6,
@@ -123,9 +120,7 @@ fn spawn_signage(
     // Meshes
     let pole_mesh = meshes.add(Cylinder::new(config.pole_radius, config.sign_height));
     let street_sign_mesh = meshes.add(Cuboid::new(1.2, 0.25, 0.02));
-    let stop_sign_mesh = meshes.add(create_octagon_mesh(0.35));
     let speed_sign_mesh = meshes.add(Cuboid::new(0.5, 0.6, 0.02));
-    let yield_sign_mesh = meshes.add(create_triangle_mesh(0.4));
     let one_way_mesh = meshes.add(Cuboid::new(0.8, 0.25, 0.02));
 
     // Iterate over intersection nodes
@@ -208,49 +203,6 @@ fn spawn_signage(
             sign_count += 1;
         }
 
-        // Place stop signs at some intersections
-        if is_major_intersection && rng.gen::<f32>() < config.stop_sign_prob {
-            for (i, dir) in directions.iter().enumerate() {
-                if rng.gen::<f32>() > 0.5 {
-                    continue;
-                }
-
-                let perp = Vec2::new(-dir.y, dir.x);
-                let offset_side = if i % 2 == 0 { 1.0 } else { -1.0 };
-                let sign_pos = node_pos + perp * 5.0 * offset_side - *dir * 3.0;
-                let angle = dir.y.atan2(dir.x);
-
-                commands
-                    .spawn((
-                        Transform::from_xyz(sign_pos.x, 0.0, sign_pos.y)
-                            .with_rotation(Quat::from_rotation_y(-angle + PI)),
-                        GlobalTransform::default(),
-                        Visibility::Visible,
-                        InheritedVisibility::default(),
-                        ViewVisibility::default(),
-                        StreetSign { sign_type: SignType::StopSign },
-                    ))
-                    .with_children(|parent| {
-                        // Pole
-                        parent.spawn((
-                            Mesh3d(pole_mesh.clone()),
-                            MeshMaterial3d(pole_material.clone()),
-                            Transform::from_xyz(0.0, config.sign_height / 2.0, 0.0),
-                        ));
-
-                        // Stop sign
-                        parent.spawn((
-                            Mesh3d(stop_sign_mesh.clone()),
-                            MeshMaterial3d(red_sign_material.clone()),
-                            Transform::from_xyz(0.0, config.sign_height - 0.2, 0.0),
-                        ));
-                    });
-
-                sign_count += 1;
-                break; // One stop sign per intersection corner
-            }
-        }
-
         // Place speed limit signs on major roads
         if rng.gen::<f32>() < config.speed_sign_prob {
             let dir = directions[rng.gen_range(0..directions.len())];
@@ -327,6 +279,98 @@ fn spawn_signage(
     info!("Spawned {} street signs", sign_count);
 }
 
+/// Meshes and materials for stop and yield signs.
+#[derive(Resource)]
+struct PrioritySignAssets {
+    pole: Handle<Mesh>,
+    octagon: Handle<Mesh>,
+    triangle: Handle<Mesh>,
+    pole_material: Handle<StandardMaterial>,
+    red: Handle<StandardMaterial>,
+}
+
+fn setup_priority_sign_assets(
+    mut commands: Commands,
+    config: Res<SignageConfig>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(PrioritySignAssets {
+        pole: meshes.add(Cylinder::new(config.pole_radius, config.sign_height)),
+        octagon: meshes.add(create_octagon_mesh(0.35)),
+        triangle: meshes.add(create_triangle_mesh(0.7)),
+        pole_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.4, 0.4, 0.42),
+            metallic: 0.6,
+            perceptual_roughness: 0.4,
+            ..default()
+        }),
+        red: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.8, 0.15, 0.1),
+            perceptual_roughness: 0.5,
+            ..default()
+        }),
+    });
+}
+
+/// Put a stop or yield sign at the kerb of every approach that must give way
+/// at newly built junctions.
+fn spawn_priority_signs(
+    mut commands: Commands,
+    junctions: Query<(Entity, &RoadJunction), Added<RoadJunction>>,
+    road_graph: Res<RoadGraph>,
+    terrain: Res<HeightMap>,
+    config: Res<SignageConfig>,
+    assets: Res<PrioritySignAssets>,
+) {
+    for (owner, junction) in &junctions {
+        let geometry = &junction.geometry;
+        let JunctionControl::Signs(controls) = junction_control(geometry, &road_graph) else {
+            continue;
+        };
+
+        commands.entity(owner).with_children(|parent| {
+            for (edge, control) in controls {
+                let (sign_type, plate, angle) = match control {
+                    ApproachControl::Priority => continue,
+                    ApproachControl::Stop => (SignType::StopSign, &assets.octagon, 0.0),
+                    // Point down
+                    ApproachControl::Yield => (SignType::Yield, &assets.triangle, PI),
+                };
+                let Some(leg) = geometry.leg(edge) else {
+                    continue;
+                };
+
+                // On the kerb to the right of arriving traffic, just back from the junction
+                let kerb = Vec2::new(leg.direction.y, -leg.direction.x);
+                let pos = geometry.center + leg.direction * (leg.setback + 1.5) + kerb * (leg.half_width + 0.8);
+                let ground = terrain.sample_world(pos);
+                let facing = Vec3::new(-leg.direction.x, 0.0, -leg.direction.y);
+
+                parent
+                    .spawn((
+                        Transform::from_xyz(pos.x, ground, pos.y).looking_to(facing, Vec3::Y),
+                        Visibility::default(),
+                        StreetSign { sign_type },
+                    ))
+                    .with_children(|sign| {
+                        sign.spawn((
+                            Mesh3d(assets.pole.clone()),
+                            MeshMaterial3d(assets.pole_material.clone()),
+                            Transform::from_xyz(0.0, config.sign_height / 2.0, 0.0),
+                        ));
+                        sign.spawn((
+                            Mesh3d(plate.clone()),
+                            MeshMaterial3d(assets.red.clone()),
+                            Transform::from_xyz(0.0, config.sign_height - 0.2, config.pole_radius + 0.01)
+                                .with_rotation(Quat::from_rotation_z(angle)),
+                        ));
+                    });
+            }
+        });
+    }
+}
+
 /// Create an octagonal mesh for stop signs.
 fn create_octagon_mesh(radius: f32) -> Mesh {
     use bevy::render::mesh::{Indices, PrimitiveTopology};
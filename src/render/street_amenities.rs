@@ -43,6 +43,13 @@ pub struct StreetAmenity {
     pub amenity_type: AmenityType,
 }
 
+/// A rack cyclists can lock up at.
+#[derive(Component)]
+pub struct BikeRack {
+    /// Bikes it holds, two to a loop.
+    pub capacity: u32,
+}
+
 #[derive(Resource)]
 pub struct StreetAmenitiesConfig {
     pub seed: u64,
@@ -353,6 +360,7 @@ fn spawn_bike_rack(
             InheritedVisibility::default(),
             ViewVisibility::default(),
             StreetAmenity { amenity_type: AmenityType::BikeRack },
+            BikeRack { capacity: 6 },
         ))
         .with_children(|parent| {
             // Base bar
@@ -4,7 +4,6 @@
 
 use bevy::prelude::*;
 use bevy::render::mesh::{Indices, PrimitiveTopology};
-use noise::{NoiseFn, Perlin};
 use petgraph::graph::NodeIndex;
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
@@ -12,7 +11,7 @@ use rand::{Rng, SeedableRng};
 use crate::procgen::roads::{RoadGraph, RoadType};
 use crate::render::crosswalks::Crosswalk;
 use crate::render::gpu_culling::GpuCullable;
-use crate::render::instancing::TerrainConfig;
+use crate::world::terrain::{HeightMap, TerrainAnchor};
 use crate::render::road_mesh::RoadMeshGenerated;
 
 pub struct StreetDetailsPlugin;
@@ -103,7 +102,7 @@ fn spawn_street_details(
     mut commands: Commands,
     road_graph: Res<RoadGraph>,
     config: Res<StreetDetailsConfig>,
-    terrain_config: Res<TerrainConfig>,
+    terrain: Res<HeightMap>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut spawned: ResMut<StreetDetailsSpawned>,
@@ -113,7 +112,6 @@ fn spawn_street_details(
     info!("Spawning street details...");
 
     let mut rng = StdRng::seed_from_u64(config.seed);
-    let terrain = TerrainSampler::new(&terrain_config);
 
     // Materials
     let manhole_material = materials.add(StandardMaterial {
@@ -173,7 +171,7 @@ fn spawn_street_details(
                     // Alternate sides slightly
                     let side = if manhole_count % 2 == 0 { 1.0 } else { -1.0 };
                     let manhole_pos = pos + perp * manhole_offset * side;
-                    let height = terrain.sample(manhole_pos.x, manhole_pos.y) + 0.02;
+                    let height = terrain.sample_world(manhole_pos) + 0.02;
 
                     // Random rotation for variety
                     let rotation = Quat::from_rotation_y(rng.gen_range(0.0..std::f32::consts::TAU));
@@ -184,6 +182,7 @@ fn spawn_street_details(
                         Transform::from_xyz(manhole_pos.x, height, manhole_pos.y)
                             .with_rotation(rotation),
                         Manhole,
+                        TerrainAnchor { offset: 0.02 },
                         GpuCullable::new(config.manhole_diameter / 2.0),
                     ));
 
@@ -232,7 +231,7 @@ fn spawn_street_details(
                 let drain_distance = road_width * 0.6;
                 let drain_side = if i % 2 == 0 { 1.0 } else { -1.0 };
                 let drain_pos = node.position + dir * drain_distance + perp * (road_width / 2.0 + 1.0) * drain_side;
-                let height = terrain.sample(drain_pos.x, drain_pos.y) + 0.01;
+                let height = terrain.sample_world(drain_pos) + 0.01;
 
                 // Rotate to face road
                 let angle = dir.y.atan2(dir.x);
@@ -243,6 +242,7 @@ fn spawn_street_details(
                     Transform::from_xyz(drain_pos.x, height, drain_pos.y)
                         .with_rotation(Quat::from_rotation_y(-angle)),
                     StormDrain,
+                    TerrainAnchor { offset: 0.01 },
                     GpuCullable::new(config.drain_width),
                 ));
 
@@ -261,7 +261,7 @@ fn spawn_street_details(
 fn spawn_bollards(
     mut commands: Commands,
     config: Res<StreetDetailsConfig>,
-    terrain_config: Res<TerrainConfig>,
+    terrain: Res<HeightMap>,
     crosswalk_query: Query<&Transform, With<Crosswalk>>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
@@ -272,7 +272,6 @@ fn spawn_bollards(
     info!("Spawning bollards near crosswalks...");
 
     let mut rng = StdRng::seed_from_u64(config.seed + 100);
-    let terrain = TerrainSampler::new(&terrain_config);
 
     let bollard_material = materials.add(StandardMaterial {
         base_color: Color::srgb(0.2, 0.2, 0.22),
@@ -323,7 +322,7 @@ fn spawn_bollards(
                     0.0,
                     crosswalk_pos.z + offset_perp,
                 );
-                let height = terrain.sample(bollard_pos.x, bollard_pos.z);
+                let height = terrain.sample_world(bollard_pos.xz());
 
                 // Main bollard body
                 commands.spawn((
@@ -331,6 +330,7 @@ fn spawn_bollards(
                     MeshMaterial3d(bollard_material.clone()),
                     Transform::from_xyz(bollard_pos.x, height + config.bollard_height / 2.0, bollard_pos.z),
                     Bollard,
+                    TerrainAnchor { offset: config.bollard_height / 2.0 },
                     GpuCullable::new(config.bollard_height),
                 ));
 
@@ -340,6 +340,7 @@ fn spawn_bollards(
                     MeshMaterial3d(bollard_material.clone()),
                     Transform::from_xyz(bollard_pos.x, height + config.bollard_height, bollard_pos.z),
                     Bollard,
+                    TerrainAnchor { offset: config.bollard_height },
                     GpuCullable::new(config.bollard_radius * 1.2),
                 ));
 
@@ -349,6 +350,7 @@ fn spawn_bollards(
                     MeshMaterial3d(bollard_stripe_material.clone()),
                     Transform::from_xyz(bollard_pos.x, height + config.bollard_height * 0.7, bollard_pos.z),
                     Bollard,
+                    TerrainAnchor { offset: config.bollard_height * 0.7 },
                     GpuCullable::new(config.bollard_radius * 1.05),
                 ));
 
@@ -513,44 +515,3 @@ fn create_drain_grate_mesh(width: f32, slot_width: f32) -> Mesh {
         .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
         .with_inserted_indices(Indices::U32(indices))
 }
-
-/// Helper struct for sampling terrain height.
-struct TerrainSampler {
-    perlin: Perlin,
-    noise_scale: f32,
-    height_scale: f32,
-    octaves: u32,
-}
-
-impl TerrainSampler {
-    fn new(config: &TerrainConfig) -> Self {
-        Self {
-            perlin: Perlin::new(config.seed),
-            noise_scale: config.noise_scale,
-            height_scale: config.height_scale,
-            octaves: config.octaves,
-        }
-    }
-
-    fn sample(&self, x: f32, z: f32) -> f32 {
-        if !x.is_finite() || !z.is_finite() {
-            return 0.0;
-        }
-
-        let mut height = 0.0;
-        let mut amplitude = 1.0;
-        let mut frequency = self.noise_scale;
-        let mut max_amplitude = 0.0;
-
-        for _ in 0..self.octaves {
-            let sample_x = x as f64 * frequency as f64;
-            let sample_z = z as f64 * frequency as f64;
-            height += self.perlin.get([sample_x, sample_z]) as f32 * amplitude;
-            max_amplitude += amplitude;
-            amplitude *= 0.5;
-            frequency *= 2.0;
-        }
-
-        (height / max_amplitude) * self.height_scale
-    }
-}
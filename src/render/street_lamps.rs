@@ -5,32 +5,30 @@
 
 use bevy::prelude::*;
 
-use crate::procgen::roads::{RoadGraph, RoadType};
+use crate::procgen::roads::RoadType;
 use crate::render::clustered_shading::{ClusterConfig, DynamicCityLight};
 use crate::render::day_night::TimeOfDay;
 use crate::render::gpu_culling::GpuCullable;
-use crate::render::road_mesh::RoadMeshGenerated;
+use crate::render::road_mesh::RoadSegment;
 
 pub struct StreetLampsPlugin;
 
 impl Plugin for StreetLampsPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<LampConfig>()
-            .init_resource::<StreetLampsSpawned>()
-            .add_systems(Update, spawn_street_lamps.run_if(should_spawn_lamps))
+            .add_systems(Startup, setup_lamp_assets)
+            .add_systems(Update, spawn_segment_lamps)
             .add_systems(Update, update_lamp_brightness);
     }
 }
 
-/// Marker that street lamps have been spawned (prevents re-running).
-#[derive(Resource, Default)]
-pub struct StreetLampsSpawned(pub bool);
-
-fn should_spawn_lamps(
-    road_mesh_query: Query<&RoadMeshGenerated>,
-    spawned: Res<StreetLampsSpawned>,
-) -> bool {
-    !road_mesh_query.is_empty() && !spawned.0
+/// Meshes and materials shared by all street lamps.
+#[derive(Resource)]
+struct LampAssets {
+    pole_mesh: Handle<Mesh>,
+    light_mesh: Handle<Mesh>,
+    pole_material: Handle<StandardMaterial>,
+    light_material: Handle<StandardMaterial>,
 }
 
 #[derive(Component)]
@@ -70,42 +68,42 @@ impl Default for LampConfig {
     }
 }
 
-fn spawn_street_lamps(
+fn setup_lamp_assets(
     mut commands: Commands,
-    road_graph: Res<RoadGraph>,
     config: Res<LampConfig>,
-    cluster_config: Res<ClusterConfig>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    mut spawned: ResMut<StreetLampsSpawned>,
 ) {
-    info!("Spawning street lamps...");
-
-    // Pole material (dark metal)
-    let pole_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.2, 0.2, 0.22),
-        perceptual_roughness: 0.6,
-        metallic: 0.4,
-        ..default()
-    });
-
-    // Light fixture material (glowing warm white)
-    let light_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(1.0, 0.95, 0.8),
-        emissive: LinearRgba::new(1.0, 0.9, 0.7, 1.0),
-        ..default()
+    commands.insert_resource(LampAssets {
+        pole_mesh: meshes.add(Cylinder::new(config.pole_radius, config.pole_height)),
+        light_mesh: meshes.add(Sphere::new(config.light_radius)),
+        // Pole material (dark metal)
+        pole_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.2, 0.2, 0.22),
+            perceptual_roughness: 0.6,
+            metallic: 0.4,
+            ..default()
+        }),
+        // Light fixture material (glowing warm white)
+        light_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(1.0, 0.95, 0.8),
+            emissive: LinearRgba::new(1.0, 0.9, 0.7, 1.0),
+            ..default()
+        }),
     });
+}
 
-    // Meshes
-    let pole_mesh = meshes.add(Cylinder::new(config.pole_radius, config.pole_height));
-    let light_mesh = meshes.add(Sphere::new(config.light_radius));
-
-    let mut lamp_count = 0;
-
-    let mut major_count = 0;
-    let mut minor_count = 0;
+/// Line newly built road segments with lamps.
+fn spawn_segment_lamps(
+    mut commands: Commands,
+    segments: Query<(Entity, &RoadSegment), Added<RoadSegment>>,
+    config: Res<LampConfig>,
+    cluster_config: Res<ClusterConfig>,
+    assets: Res<LampAssets>,
+) {
+    for (owner, segment) in &segments {
+        let edge = &segment.edge;
 
-    for edge in road_graph.edges() {
         // Determine if we should place lamps on this road type
         let (spacing, intensity_factor) = match edge.road_type {
             RoadType::Major => (config.major_spacing, 1.0),
@@ -113,11 +111,12 @@ fn spawn_street_lamps(
             _ => continue, // Skip highways and alleys
         };
 
-        if edge.points.len() < 2 {
+        if edge.tunnel {
             continue;
         }
-
-        let is_minor = edge.road_type == RoadType::Minor;
+        let Some(points) = segment.surface_points() else {
+            continue;
+        };
 
         // Calculate road width for offset
         let road_width = match edge.road_type {
@@ -129,82 +128,74 @@ fn spawn_street_lamps(
 
         let lamp_offset = road_width / 2.0 + config.offset_from_road;
 
-        // Walk along the road and place lamps at intervals
-        let mut accumulated_dist = spacing / 2.0; // Start offset
-        let mut segment_start_dist = 0.0;
-
-        for window in edge.points.windows(2) {
-            let start = window[0];
-            let end = window[1];
-            let segment_length = start.distance(end);
-            let segment_end_dist = segment_start_dist + segment_length;
-
-            let dir = (end - start).normalize_or_zero();
-            let perp = Vec2::new(-dir.y, dir.x);
-
-            // Place lamps within this segment
-            while accumulated_dist < segment_end_dist {
-                let t = (accumulated_dist - segment_start_dist) / segment_length;
-                let pos = start.lerp(end, t);
-
-                // Alternate sides
-                let side = if (lamp_count % 2) == 0 { 1.0 } else { -1.0 };
-                let lamp_pos = pos + perp * lamp_offset * side;
-
-                // Spawn pole
-                commands.spawn((
-                    Mesh3d(pole_mesh.clone()),
-                    MeshMaterial3d(pole_material.clone()),
-                    Transform::from_xyz(lamp_pos.x, config.pole_height / 2.0, lamp_pos.y),
-                    StreetLamp,
-                    GpuCullable::new(config.pole_height / 2.0),
-                ));
-
-                // Spawn light fixture (visual mesh)
-                commands.spawn((
-                    Mesh3d(light_mesh.clone()),
-                    MeshMaterial3d(light_material.clone()),
-                    Transform::from_xyz(lamp_pos.x, config.pole_height + config.light_radius * 0.5, lamp_pos.y),
-                    StreetLamp,
-                    LampFixture,
-                    GpuCullable::new(config.light_radius),
-                ));
-
-                // Spawn real PointLight for dynamic lighting
-                // Minor roads get dimmer lights based on intensity_factor
-                let lamp_intensity = cluster_config.street_lamp_intensity * intensity_factor;
-                commands.spawn((
-                    PointLight {
-                        color: cluster_config.street_lamp_color,
-                        intensity: 0.0, // Managed by DynamicCityLight system
-                        range: cluster_config.street_lamp_radius,
-                        radius: 0.5,
-                        shadows_enabled: cluster_config.point_light_shadows,
-                        ..default()
-                    },
-                    Transform::from_xyz(lamp_pos.x, config.pole_height + config.light_radius * 0.5, lamp_pos.y),
-                    DynamicCityLight::street_lamp(lamp_intensity),
-                    StreetLamp,
-                ));
-
-                lamp_count += 1;
-                if is_minor {
-                    minor_count += 1;
-                } else {
-                    major_count += 1;
+        commands.entity(owner).with_children(|parent| {
+            // Walk along the road and place lamps at intervals
+            let mut lamp_count = 0;
+            let mut accumulated_dist = spacing / 2.0; // Start offset
+            let mut segment_start_dist = 0.0;
+
+            for window in points.windows(2) {
+                let start = window[0];
+                let end = window[1];
+                let segment_length = start.distance(end);
+                let segment_end_dist = segment_start_dist + segment_length;
+
+                let dir = (end - start).normalize_or_zero();
+                let perp = Vec2::new(-dir.y, dir.x);
+
+                // Place lamps within this segment
+                while accumulated_dist < segment_end_dist {
+                    let t = (accumulated_dist - segment_start_dist) / segment_length;
+                    let pos = start.lerp(end, t);
+
+                    // Alternate sides
+                    let side = if (lamp_count % 2) == 0 { 1.0 } else { -1.0 };
+                    let lamp_pos = pos + perp * lamp_offset * side;
+
+                    // Spawn pole
+                    parent.spawn((
+                        Mesh3d(assets.pole_mesh.clone()),
+                        MeshMaterial3d(assets.pole_material.clone()),
+                        Transform::from_xyz(lamp_pos.x, config.pole_height / 2.0, lamp_pos.y),
+                        StreetLamp,
+                        GpuCullable::new(config.pole_height / 2.0),
+                    ));
+
+                    // Spawn light fixture (visual mesh)
+                    parent.spawn((
+                        Mesh3d(assets.light_mesh.clone()),
+                        MeshMaterial3d(assets.light_material.clone()),
+                        Transform::from_xyz(lamp_pos.x, config.pole_height + config.light_radius * 0.5, lamp_pos.y),
+                        StreetLamp,
+                        LampFixture,
+                        GpuCullable::new(config.light_radius),
+                    ));
+
+                    // Spawn real PointLight for dynamic lighting
+                    // Minor roads get dimmer lights based on intensity_factor
+                    let lamp_intensity = cluster_config.street_lamp_intensity * intensity_factor;
+                    parent.spawn((
+                        PointLight {
+                            color: cluster_config.street_lamp_color,
+                            intensity: 0.0, // Managed by DynamicCityLight system
+                            range: cluster_config.street_lamp_radius,
+                            radius: 0.5,
+                            shadows_enabled: cluster_config.point_light_shadows,
+                            ..default()
+                        },
+                        Transform::from_xyz(lamp_pos.x, config.pole_height + config.light_radius * 0.5, lamp_pos.y),
+                        DynamicCityLight::street_lamp(lamp_intensity),
+                        StreetLamp,
+                    ));
+
+                    lamp_count += 1;
+                    accumulated_dist += spacing;
                 }
-                accumulated_dist += spacing;
-            }
 
-            segment_start_dist = segment_end_dist;
-        }
+                segment_start_dist = segment_end_dist;
+            }
+        });
     }
-
-    spawned.0 = true;
-    info!(
-        "Spawned {} street lamps ({} major, {} minor)",
-        lamp_count, major_count, minor_count
-    );
 }
 
 fn update_lamp_brightness(
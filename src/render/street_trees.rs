@@ -1,13 +1,12 @@
 //! Street tree generation along sidewalks.
 
 use bevy::prelude::*;
-use noise::{NoiseFn, Perlin};
 use rand::{Rng, SeedableRng};
 use rand::rngs::StdRng;
 
 use crate::procgen::roads::{RoadGraph, RoadType};
 use crate::render::gpu_culling::GpuCullable;
-use crate::render::instancing::TerrainConfig;
+use crate::world::terrain::{HeightMap, TerrainAnchor};
 use crate::render::road_mesh::RoadMeshGenerated;
 
 pub struct StreetTreesPlugin;
@@ -74,14 +73,13 @@ fn spawn_street_trees(
     mut commands: Commands,
     road_graph: Res<RoadGraph>,
     config: Res<StreetTreeConfig>,
-    terrain_config: Res<TerrainConfig>,
+    terrain: Res<HeightMap>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut spawned: ResMut<StreetTreesSpawned>,
 ) {
     info!("Spawning street trees...");
 
-    let terrain = TerrainSampler::new(&terrain_config);
     let mut rng = StdRng::seed_from_u64(config.seed);
 
     // Create trunk mesh (unit cylinder, scaled per tree)
@@ -150,7 +148,7 @@ fn spawn_street_trees(
                 let foliage_size = rng.gen_range(config.foliage_min..config.foliage_max);
 
                 // Sample terrain height
-                let terrain_height = terrain.sample(tree_pos.x, tree_pos.y);
+                let terrain_height = terrain.sample_world(tree_pos);
 
                 // Random foliage color
                 let foliage_mat = foliage_materials[rng.gen_range(0..foliage_materials.len())].clone();
@@ -164,6 +162,7 @@ fn spawn_street_trees(
                     Transform::from_xyz(tree_pos.x, trunk_y, tree_pos.y)
                         .with_scale(Vec3::new(1.0, tree_height, 1.0)),
                     StreetTree,
+                    TerrainAnchor { offset: tree_height / 2.0 },
                     GpuCullable::new(trunk_radius),
                 ));
 
@@ -175,6 +174,7 @@ fn spawn_street_trees(
                     Transform::from_xyz(tree_pos.x, foliage_y, tree_pos.y)
                         .with_scale(Vec3::splat(foliage_size)),
                     StreetTree,
+                    TerrainAnchor { offset: foliage_y - terrain_height },
                     GpuCullable::new(foliage_size),
                 ));
 
@@ -189,40 +189,3 @@ fn spawn_street_trees(
     spawned.0 = true;
     info!("Spawned {} street trees", tree_count);
 }
-
-/// Helper struct for sampling terrain height.
-struct TerrainSampler {
-    perlin: Perlin,
-    noise_scale: f32,
-    height_scale: f32,
-    octaves: u32,
-}
-
-impl TerrainSampler {
-    fn new(config: &TerrainConfig) -> Self {
-        Self {
-            perlin: Perlin::new(config.seed),
-            noise_scale: config.noise_scale,
-            height_scale: config.height_scale,
-            octaves: config.octaves,
-        }
-    }
-
-    fn sample(&self, x: f32, z: f32) -> f32 {
-        let mut height = 0.0;
-        let mut amplitude = 1.0;
-        let mut frequency = self.noise_scale;
-        let mut max_amplitude = 0.0;
-
-        for _ in 0..self.octaves {
-            let sample_x = x as f64 * frequency as f64;
-            let sample_z = z as f64 * frequency as f64;
-            height += self.perlin.get([sample_x, sample_z]) as f32 * amplitude;
-            max_amplitude += amplitude;
-            amplitude *= 0.5;
-            frequency *= 2.0;
-        }
-
-        (height / max_amplitude) * self.height_scale
-    }
-}
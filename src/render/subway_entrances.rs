@@ -16,6 +16,7 @@ impl Plugin for SubwayEntrancesPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<SubwayEntranceConfig>()
             .init_resource::<SubwayEntrancesSpawned>()
+            .add_systems(Startup, setup_subway_entrance_assets)
             .add_systems(Update, spawn_subway_entrances.run_if(should_spawn_entrances));
     }
 }
@@ -59,12 +60,65 @@ impl Default for SubwayEntranceConfig {
     }
 }
 
-fn spawn_subway_entrances(
+/// Meshes and materials shared by every entrance.
+#[derive(Resource)]
+pub struct SubwayEntranceAssets {
+    frame_material: Handle<StandardMaterial>,
+    glass_material: Handle<StandardMaterial>,
+    sign_material: Handle<StandardMaterial>,
+    stair_material: Handle<StandardMaterial>,
+    canopy_mesh: Handle<Mesh>,
+    post_mesh: Handle<Mesh>,
+    sign_mesh: Handle<Mesh>,
+    stair_mesh: Handle<Mesh>,
+    rail_mesh: Handle<Mesh>,
+    glass_panel_mesh: Handle<Mesh>,
+}
+
+fn setup_subway_entrance_assets(
     mut commands: Commands,
     config: Res<SubwayEntranceConfig>,
-    buildings: Query<(&Building, &Transform)>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(SubwayEntranceAssets {
+        frame_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.25, 0.25, 0.28),
+            metallic: 0.7,
+            perceptual_roughness: 0.4,
+            ..default()
+        }),
+        glass_material: materials.add(StandardMaterial {
+            base_color: Color::srgba(0.4, 0.5, 0.6, 0.5),
+            alpha_mode: AlphaMode::Blend,
+            perceptual_roughness: 0.1,
+            metallic: 0.3,
+            ..default()
+        }),
+        sign_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.8, 0.2, 0.2),
+            emissive: LinearRgba::new(0.5, 0.1, 0.1, 1.0),
+            ..default()
+        }),
+        stair_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.5, 0.5, 0.52),
+            perceptual_roughness: 0.8,
+            ..default()
+        }),
+        canopy_mesh: meshes.add(Cuboid::new(config.entrance_width, 0.15, config.entrance_depth)),
+        post_mesh: meshes.add(Cylinder::new(0.08, config.canopy_height)),
+        sign_mesh: meshes.add(Cuboid::new(1.5, 0.8, 0.1)),
+        stair_mesh: meshes.add(Cuboid::new(config.entrance_width * 0.9, 0.2, config.entrance_depth * 0.8)),
+        rail_mesh: meshes.add(Cylinder::new(0.03, config.entrance_depth)),
+        glass_panel_mesh: meshes.add(Cuboid::new(0.05, config.canopy_height * 0.7, config.entrance_depth * 0.8)),
+    });
+}
+
+fn spawn_subway_entrances(
+    mut commands: Commands,
+    config: Res<SubwayEntranceConfig>,
+    assets: Res<SubwayEntranceAssets>,
+    buildings: Query<(&Building, &Transform)>,
     mut spawned: ResMut<SubwayEntrancesSpawned>,
 ) {
     spawned.0 = true;
@@ -86,41 +140,6 @@ fn spawn_subway_entrances(
         commercial_positions.swap(i, j);
     }
 
-    // Materials
-    let frame_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.25, 0.25, 0.28),
-        metallic: 0.7,
-        perceptual_roughness: 0.4,
-        ..default()
-    });
-
-    let glass_material = materials.add(StandardMaterial {
-        base_color: Color::srgba(0.4, 0.5, 0.6, 0.5),
-        alpha_mode: AlphaMode::Blend,
-        perceptual_roughness: 0.1,
-        metallic: 0.3,
-        ..default()
-    });
-
-    let sign_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.8, 0.2, 0.2),
-        emissive: LinearRgba::new(0.5, 0.1, 0.1, 1.0),
-        ..default()
-    });
-
-    let stair_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.5, 0.5, 0.52),
-        perceptual_roughness: 0.8,
-        ..default()
-    });
-
-    // Meshes
-    let canopy_mesh = meshes.add(Cuboid::new(config.entrance_width, 0.15, config.entrance_depth));
-    let post_mesh = meshes.add(Cylinder::new(0.08, config.canopy_height));
-    let sign_mesh = meshes.add(Cuboid::new(1.5, 0.8, 0.1));
-    let stair_mesh = meshes.add(Cuboid::new(config.entrance_width * 0.9, 0.2, config.entrance_depth * 0.8));
-    let rail_mesh = meshes.add(Cylinder::new(0.03, config.entrance_depth));
-
     for pos in commercial_positions {
         if entrance_count >= config.max_entrances {
             break;
@@ -143,21 +162,41 @@ fn spawn_subway_entrances(
         }
 
         let rotation = Quat::from_rotation_y(rng.gen::<f32>() * PI * 2.0);
+        let entrance = SubwayEntrance {
+            station_name: entrance_count as u32,
+        };
+        let transform = Transform::from_translation(entrance_pos).with_rotation(rotation);
+        spawn_subway_entrance(&mut commands, &config, &assets, transform, entrance);
 
-        commands.spawn((
-            Transform::from_translation(entrance_pos).with_rotation(rotation),
+        placed_positions.push(entrance_pos);
+        entrance_count += 1;
+    }
+
+    info!("Spawned {} subway entrances", entrance_count);
+}
+
+/// Spawn one entrance, with its stairs descending towards local +Z.
+pub fn spawn_subway_entrance(
+    commands: &mut Commands,
+    config: &SubwayEntranceConfig,
+    assets: &SubwayEntranceAssets,
+    transform: Transform,
+    entrance: SubwayEntrance,
+) -> Entity {
+    commands
+        .spawn((
+            transform,
             GlobalTransform::default(),
             Visibility::Visible,
             InheritedVisibility::default(),
             ViewVisibility::default(),
-            SubwayEntrance {
-                station_name: entrance_count as u32,
-            },
-        )).with_children(|parent| {
+            entrance,
+        ))
+        .with_children(|parent| {
             // Canopy/roof
             parent.spawn((
-                Mesh3d(canopy_mesh.clone()),
-                MeshMaterial3d(frame_material.clone()),
+                Mesh3d(assets.canopy_mesh.clone()),
+                MeshMaterial3d(assets.frame_material.clone()),
                 Transform::from_xyz(0.0, config.canopy_height, 0.0),
             ));
 
@@ -167,16 +206,16 @@ fn spawn_subway_entrances(
                 (config.entrance_width / 2.0 - 0.2, -config.entrance_depth / 2.0 + 0.2),
             ] {
                 parent.spawn((
-                    Mesh3d(post_mesh.clone()),
-                    MeshMaterial3d(frame_material.clone()),
+                    Mesh3d(assets.post_mesh.clone()),
+                    MeshMaterial3d(assets.frame_material.clone()),
                     Transform::from_xyz(x, config.canopy_height / 2.0, z),
                 ));
             }
 
             // Metro sign on top
             parent.spawn((
-                Mesh3d(sign_mesh.clone()),
-                MeshMaterial3d(sign_material.clone()),
+                Mesh3d(assets.sign_mesh.clone()),
+                MeshMaterial3d(assets.sign_material.clone()),
                 Transform::from_xyz(0.0, config.canopy_height + 0.5, -config.entrance_depth / 2.0),
             ));
 
@@ -185,8 +224,8 @@ fn spawn_subway_entrances(
                 let stair_y = -0.3 * (i as f32 + 1.0);
                 let stair_z = config.entrance_depth * 0.2 * (i as f32);
                 parent.spawn((
-                    Mesh3d(stair_mesh.clone()),
-                    MeshMaterial3d(stair_material.clone()),
+                    Mesh3d(assets.stair_mesh.clone()),
+                    MeshMaterial3d(assets.stair_material.clone()),
                     Transform::from_xyz(0.0, stair_y, stair_z),
                 ));
             }
@@ -194,27 +233,21 @@ fn spawn_subway_entrances(
             // Handrails
             for x in [-config.entrance_width / 2.0 + 0.3, config.entrance_width / 2.0 - 0.3] {
                 parent.spawn((
-                    Mesh3d(rail_mesh.clone()),
-                    MeshMaterial3d(frame_material.clone()),
+                    Mesh3d(assets.rail_mesh.clone()),
+                    MeshMaterial3d(assets.frame_material.clone()),
                     Transform::from_xyz(x, 0.9, config.entrance_depth / 4.0)
                         .with_rotation(Quat::from_rotation_x(-0.3)),
                 ));
             }
 
             // Glass side panels
-            let glass_panel = meshes.add(Cuboid::new(0.05, config.canopy_height * 0.7, config.entrance_depth * 0.8));
             for x in [-config.entrance_width / 2.0, config.entrance_width / 2.0] {
                 parent.spawn((
-                    Mesh3d(glass_panel.clone()),
-                    MeshMaterial3d(glass_material.clone()),
+                    Mesh3d(assets.glass_panel_mesh.clone()),
+                    MeshMaterial3d(assets.glass_material.clone()),
                     Transform::from_xyz(x, config.canopy_height * 0.4, 0.0),
                 ));
             }
-        });
-
-        placed_positions.push(entrance_pos);
-        entrance_count += 1;
-    }
-
-    info!("Spawned {} subway entrances", entrance_count);
+        })
+        .id()
 }
@@ -2,12 +2,22 @@
 //!
 //! Spawns traffic lights with real PointLight entities for dynamic lighting.
 //! Lights change intensity based on the current phase (red/yellow/green).
+//!
+//! Each incoming road gets its own signal head. Each controller runs a phase
+//! plan laid out from the junction's turns; roads traffic can only leave by
+//! (one-way outbound) get no signal and no share of the cycle. Signals along
+//! a major road are coordinated into a green wave.
+
+use std::collections::{HashMap, HashSet};
 
 use bevy::prelude::*;
-use petgraph::graph::NodeIndex;
+use petgraph::graph::{EdgeIndex, NodeIndex};
 
-use crate::procgen::roads::RoadGraph;
-use crate::render::road_mesh::RoadMeshGenerated;
+use crate::procgen::intersections::TurnKind;
+use crate::procgen::roads::{RoadGraph, RoadType};
+use crate::simulation::right_of_way::{junction_control, JunctionControl};
+use crate::simulation::signal_plans::{actuated_green_ends, green_wave_offsets, Coordination, Movement, PhasePlan, PlanLayout, SignalMode};
+use crate::render::road_mesh::RoadJunction;
 use crate::render::clustered_shading::{cluster_config::traffic_colors, ClusterConfig, DynamicCityLight};
 
 pub struct TrafficLightsPlugin;
@@ -22,38 +32,99 @@ pub enum LightPhase {
 }
 
 /// Controller for a traffic light at an intersection.
-/// One controller per intersection manages the light cycling.
+/// One controller per intersection runs its phase plan.
 #[derive(Component)]
 pub struct TrafficLightController {
+    /// Light shown to the movements of the active phase; everything else is red.
     pub phase: LightPhase,
+    /// Time spent in the current light.
     pub timer: f32,
     pub node_index: NodeIndex,
-    pub green_duration: f32,
-    pub yellow_duration: f32,
-    pub red_duration: f32,
+    /// Incoming roads and the signal group each belongs to, one head each.
+    pub approaches: Vec<(EdgeIndex, usize)>,
+    pub plan: PhasePlan,
+    /// Phase of the plan currently being served.
+    pub active_phase: usize,
+    pub mode: SignalMode,
+    /// Timing was edited by hand; corridor coordination leaves it alone.
+    pub edited: bool,
+    /// Green wave timing, when the signal sits on a coordinated corridor.
+    pub coordination: Option<Coordination>,
+    /// Traffic waiting for each movement, measured by the vehicle simulation.
+    pub demand: Vec<(Movement, f32)>,
+}
+
+impl TrafficLightController {
+    /// Signal shown to traffic arriving along `edge` and turning `kind`.
+    pub fn movement_phase(&self, edge: EdgeIndex, kind: TurnKind) -> LightPhase {
+        if !self.plan.controls((edge, kind)) {
+            return self.phase_for(edge);
+        }
+        self.light_if(self.plan.serves(self.active_phase, (edge, kind)))
+    }
+
+    /// Signal shown to through traffic arriving along `edge`.
+    pub fn phase_for(&self, edge: EdgeIndex) -> LightPhase {
+        if self.plan.controls((edge, TurnKind::Straight)) {
+            return self.light_if(self.plan.serves(self.active_phase, (edge, TurnKind::Straight)));
+        }
+        if !self.approaches.iter().any(|(e, _)| *e == edge) {
+            return LightPhase::Green; // Not a signalled approach
+        }
+        self.light_if(self.plan.serves_approach(self.active_phase, edge))
+    }
+
+    /// Signal shown to the `index`th approach: lit while any of its movements runs.
+    pub fn approach_phase(&self, index: usize) -> LightPhase {
+        self.approaches
+            .get(index)
+            .map_or(LightPhase::Red, |&(edge, _)| self.light_if(self.plan.serves_approach(self.active_phase, edge)))
+    }
+
+    /// Traffic measured waiting for `movement`.
+    pub fn demand_for(&self, movement: Movement) -> f32 {
+        self.demand.iter().find(|(m, _)| *m == movement).map_or(0.0, |(_, d)| *d)
+    }
+
+    fn light_if(&self, served: bool) -> LightPhase {
+        if served {
+            self.phase
+        } else {
+            LightPhase::Red
+        }
+    }
 }
 
+/// A signal's timing was changed by hand, so corridor cycles need refitting.
+#[derive(Event)]
+pub struct SignalPlanEdited;
+
 impl Plugin for TrafficLightsPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<TrafficLightConfig>()
-            .init_resource::<TrafficLightsSpawned>()
+            .add_event::<SignalPlanEdited>()
+            .add_systems(Startup, setup_traffic_light_assets)
             .add_systems(Update, (
-                spawn_traffic_lights.run_if(should_spawn_lights),
-                update_traffic_light_phases,
+                spawn_junction_traffic_lights,
+                refresh_controller_nodes,
+                coordinate_green_waves.after(refresh_controller_nodes),
+                update_traffic_light_phases.after(coordinate_green_waves),
                 update_traffic_signal_intensities.after(update_traffic_light_phases),
             ));
     }
 }
 
-/// Marker that traffic lights have been spawned (prevents re-running).
-#[derive(Resource, Default)]
-pub struct TrafficLightsSpawned(pub bool);
-
-fn should_spawn_lights(
-    road_mesh_query: Query<&RoadMeshGenerated>,
-    spawned: Res<TrafficLightsSpawned>,
-) -> bool {
-    !road_mesh_query.is_empty() && !spawned.0
+/// Meshes and materials shared by all traffic lights.
+#[derive(Resource)]
+struct TrafficLightAssets {
+    pole_mesh: Handle<Mesh>,
+    box_mesh: Handle<Mesh>,
+    light_mesh: Handle<Mesh>,
+    pole_material: Handle<StandardMaterial>,
+    box_material: Handle<StandardMaterial>,
+    red_light: Handle<StandardMaterial>,
+    yellow_light: Handle<StandardMaterial>,
+    green_light: Handle<StandardMaterial>,
 }
 
 #[derive(Component)]
@@ -71,6 +142,8 @@ pub enum TrafficSignalColor {
 #[derive(Component)]
 pub struct TrafficSignalLink {
     pub controller: Entity,
+    /// Index into the controller's approaches.
+    pub approach: usize,
 }
 
 #[derive(Resource)]
@@ -81,7 +154,8 @@ pub struct TrafficLightConfig {
     pub box_height: f32,
     pub box_depth: f32,
     pub light_radius: f32,
-    pub offset_from_center: f32,
+    /// How far behind the curb line poles stand.
+    pub curb_setback: f32,
 }
 
 impl Default for TrafficLightConfig {
@@ -93,226 +167,258 @@ impl Default for TrafficLightConfig {
             box_height: 1.4,      // Traffic signal housing
             box_depth: 0.5,
             light_radius: 0.15,
-            offset_from_center: 7.0,
+            curb_setback: 1.0,
         }
     }
 }
 
-fn spawn_traffic_lights(
+fn setup_traffic_light_assets(
     mut commands: Commands,
-    road_graph: Res<RoadGraph>,
     config: Res<TrafficLightConfig>,
-    cluster_config: Res<ClusterConfig>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    mut spawned: ResMut<TrafficLightsSpawned>,
 ) {
-    info!("Spawning traffic lights...");
-
-    // Materials
-    let pole_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.15, 0.15, 0.15),
-        perceptual_roughness: 0.5,
-        metallic: 0.6,
-        ..default()
-    });
-
-    let box_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.1, 0.1, 0.1),
-        perceptual_roughness: 0.7,
-        metallic: 0.3,
-        ..default()
+    commands.insert_resource(TrafficLightAssets {
+        // Meshes
+        pole_mesh: meshes.add(Cylinder::new(config.pole_radius, config.pole_height)),
+        box_mesh: meshes.add(Cuboid::new(config.box_width, config.box_height, config.box_depth)),
+        light_mesh: meshes.add(Sphere::new(config.light_radius)),
+        // Materials
+        pole_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.15, 0.15, 0.15),
+            perceptual_roughness: 0.5,
+            metallic: 0.6,
+            ..default()
+        }),
+        box_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.1, 0.1, 0.1),
+            perceptual_roughness: 0.7,
+            metallic: 0.3,
+            ..default()
+        }),
+        // Light materials (with emissive for glow effect)
+        red_light: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.8, 0.1, 0.1),
+            emissive: LinearRgba::new(1.0, 0.1, 0.1, 1.0),
+            ..default()
+        }),
+        yellow_light: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.8, 0.7, 0.1),
+            emissive: LinearRgba::new(1.0, 0.85, 0.1, 1.0),
+            ..default()
+        }),
+        green_light: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.1, 0.8, 0.2),
+            emissive: LinearRgba::new(0.1, 1.0, 0.2, 1.0),
+            ..default()
+        }),
     });
+}
 
-    // Light materials (with emissive for glow effect)
-    let red_light = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.8, 0.1, 0.1),
-        emissive: LinearRgba::new(1.0, 0.1, 0.1, 1.0),
-        ..default()
-    });
-
-    let yellow_light = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.8, 0.7, 0.1),
-        emissive: LinearRgba::new(1.0, 0.85, 0.1, 1.0),
-        ..default()
-    });
-
-    let green_light = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.1, 0.8, 0.2),
-        emissive: LinearRgba::new(0.1, 1.0, 0.2, 1.0),
-        ..default()
-    });
-
-    // Meshes
-    let pole_mesh = meshes.add(Cylinder::new(config.pole_radius, config.pole_height));
-    let box_mesh = meshes.add(Cuboid::new(config.box_width, config.box_height, config.box_depth));
-    let light_mesh = meshes.add(Sphere::new(config.light_radius));
-
-    let mut light_count = 0;
-
-    // Find intersections (nodes with 3+ connections)
-    for (node_idx, node) in road_graph.nodes() {
-        let neighbors: Vec<NodeIndex> = road_graph.graph.neighbors(node_idx).collect();
+/// Keep controllers pointing at their intersection's current graph node and edges.
+fn refresh_controller_nodes(
+    junctions: Query<(&RoadJunction, &Children), Changed<RoadJunction>>,
+    mut controllers: Query<&mut TrafficLightController>,
+) {
+    for (junction, children) in &junctions {
+        for child in children.iter() {
+            if let Ok(mut controller) = controllers.get_mut(*child) {
+                controller.node_index = junction.node;
+                controller.approaches = junction.geometry.signal_groups();
+
+                // Re-lay the plan over the new edges, keeping hand-set greens if it still fits
+                let mut plan = PhasePlan::for_geometry(controller.plan.layout, &junction.geometry);
+                if plan.phases.len() == controller.plan.phases.len() {
+                    for (phase, old) in plan.phases.iter_mut().zip(&controller.plan.phases) {
+                        phase.green = old.green;
+                        phase.max_green = old.max_green;
+                    }
+                }
+                controller.active_phase = controller.active_phase.min(plan.phases.len().saturating_sub(1));
+                controller.plan = plan;
+                controller.demand.clear();
+            }
+        }
+    }
+}
 
-        if neighbors.len() < 3 {
-            continue; // Not a real intersection
+/// Spawn signals at newly built intersections where busy roads cross;
+/// elsewhere traffic follows signs or merges.
+fn spawn_junction_traffic_lights(
+    mut commands: Commands,
+    junctions: Query<(Entity, &RoadJunction), Added<RoadJunction>>,
+    road_graph: Res<RoadGraph>,
+    config: Res<TrafficLightConfig>,
+    cluster_config: Res<ClusterConfig>,
+    assets: Res<TrafficLightAssets>,
+) {
+    for (owner, junction) in &junctions {
+        let geometry = &junction.geometry;
+        if junction_control(geometry, &road_graph) != JunctionControl::Signals {
+            continue; // Signs or nothing
         }
+        let approaches = geometry.signal_groups();
+
+        // One head per approach, on the curb return to the right of its incoming lane
+        let count = geometry.legs.len();
+        let heads: Vec<(usize, Vec2, Vec2)> = approaches
+            .iter()
+            .enumerate()
+            .filter_map(|(index, (edge, _))| {
+                let leg_index = geometry.legs.iter().position(|leg| leg.edge == *edge)?;
+                let corner = &geometry.corners[(leg_index + count - 1) % count];
+                Some((index, corner.curb[corner.curb.len() / 2], geometry.legs[leg_index].direction))
+            })
+            .collect();
 
         // Spawn a traffic light controller for this intersection
+        let plan = PhasePlan::for_geometry(PlanLayout::for_junction(geometry), geometry);
         let controller_entity = commands.spawn(TrafficLightController {
             phase: LightPhase::Green,
             timer: 0.0,
-            node_index: node_idx,
-            green_duration: 12.0,
-            yellow_duration: 3.0,
-            red_duration: 12.0,
-        }).id();
-
-        // Get directions to neighboring roads
-        let mut road_directions: Vec<Vec2> = Vec::new();
-        for neighbor_idx in &neighbors {
-            if let Some(neighbor_node) = road_graph.graph.node_weight(*neighbor_idx) {
-                let dir = (neighbor_node.position - node.position).normalize_or_zero();
-                road_directions.push(dir);
+            node_index: junction.node,
+            approaches,
+            plan,
+            active_phase: 0,
+            mode: SignalMode::default(),
+            edited: false,
+            coordination: None,
+            demand: Vec::new(),
+        }).set_parent(owner).id();
+
+        let center = geometry.center;
+
+        commands.entity(owner).with_children(|parent| {
+            for (approach, corner, leg_direction) in heads {
+                let corner_dir = (corner - center).normalize_or_zero();
+
+                if corner_dir.length_squared() < 0.01 {
+                    continue;
+                }
+
+                let light_pos = corner + corner_dir * config.curb_setback;
+
+                // Face the traffic coming in along the road
+                let facing = leg_direction;
+                let angle = facing.y.atan2(facing.x);
+
+                // Spawn pole
+                parent.spawn((
+                    Mesh3d(assets.pole_mesh.clone()),
+                    MeshMaterial3d(assets.pole_material.clone()),
+                    Transform::from_xyz(light_pos.x, config.pole_height / 2.0, light_pos.y),
+                    TrafficLight,
+                ));
+
+                // Spawn traffic light box
+                let box_y = config.pole_height + config.box_height / 2.0;
+                parent.spawn((
+                    Mesh3d(assets.box_mesh.clone()),
+                    MeshMaterial3d(assets.box_material.clone()),
+                    Transform::from_xyz(light_pos.x, box_y, light_pos.y)
+                        .with_rotation(Quat::from_rotation_y(-angle)),
+                    TrafficLight,
+                ));
+
+                // Spawn the three lights (red, yellow, green from top to bottom)
+                let light_spacing = config.box_height / 4.0;
+                let light_offset = config.box_depth / 2.0 + config.light_radius * 0.5;
+
+                // Calculate forward direction for light placement
+                let forward = Vec2::new(facing.x, facing.y).normalize_or_zero();
+
+                // Red light (top)
+                let red_y = box_y + light_spacing;
+                let red_pos = Vec3::new(
+                    light_pos.x + forward.x * light_offset,
+                    red_y,
+                    light_pos.y + forward.y * light_offset,
+                );
+                parent.spawn((
+                    Mesh3d(assets.light_mesh.clone()),
+                    MeshMaterial3d(assets.red_light.clone()),
+                    Transform::from_translation(red_pos),
+                    TrafficLight,
+                ));
+                // Red PointLight (starts off since phase is Green)
+                parent.spawn((
+                    PointLight {
+                        color: traffic_colors::RED,
+                        intensity: 0.0, // Controlled by phase system
+                        range: cluster_config.traffic_light_radius,
+                        radius: 0.12,
+                        shadows_enabled: false,
+                        ..default()
+                    },
+                    Transform::from_translation(red_pos),
+                    DynamicCityLight::traffic_light(cluster_config.traffic_light_intensity),
+                    TrafficSignalColor::Red,
+                    TrafficSignalLink { controller: controller_entity, approach },
+                ));
+
+                // Yellow light (middle)
+                let yellow_pos = Vec3::new(
+                    light_pos.x + forward.x * light_offset,
+                    box_y,
+                    light_pos.y + forward.y * light_offset,
+                );
+                parent.spawn((
+                    Mesh3d(assets.light_mesh.clone()),
+                    MeshMaterial3d(assets.yellow_light.clone()),
+                    Transform::from_translation(yellow_pos),
+                    TrafficLight,
+                ));
+                // Yellow PointLight (starts off since phase is Green)
+                parent.spawn((
+                    PointLight {
+                        color: traffic_colors::YELLOW,
+                        intensity: 0.0, // Controlled by phase system
+                        range: cluster_config.traffic_light_radius,
+                        radius: 0.12,
+                        shadows_enabled: false,
+                        ..default()
+                    },
+                    Transform::from_translation(yellow_pos),
+                    DynamicCityLight::traffic_light(cluster_config.traffic_light_intensity),
+                    TrafficSignalColor::Yellow,
+                    TrafficSignalLink { controller: controller_entity, approach },
+                ));
+
+                // Green light (bottom)
+                let green_y = box_y - light_spacing;
+                let green_pos = Vec3::new(
+                    light_pos.x + forward.x * light_offset,
+                    green_y,
+                    light_pos.y + forward.y * light_offset,
+                );
+                parent.spawn((
+                    Mesh3d(assets.light_mesh.clone()),
+                    MeshMaterial3d(assets.green_light.clone()),
+                    Transform::from_translation(green_pos),
+                    TrafficLight,
+                ));
+                // Green PointLight (starts on since phase is Green)
+                parent.spawn((
+                    PointLight {
+                        color: traffic_colors::GREEN,
+                        intensity: cluster_config.traffic_light_intensity, // On by default
+                        range: cluster_config.traffic_light_radius,
+                        radius: 0.12,
+                        shadows_enabled: false,
+                        ..default()
+                    },
+                    Transform::from_translation(green_pos),
+                    DynamicCityLight::traffic_light(cluster_config.traffic_light_intensity),
+                    TrafficSignalColor::Green,
+                    TrafficSignalLink { controller: controller_entity, approach },
+                ));
             }
-        }
-
-        // Place traffic lights at corners of the intersection
-        for (i, dir) in road_directions.iter().enumerate() {
-            // Rotate 45 degrees to place at corner between roads
-            let next_dir = road_directions[(i + 1) % road_directions.len()];
-            let corner_dir = (*dir + next_dir).normalize_or_zero();
-
-            if corner_dir.length_squared() < 0.01 {
-                continue;
-            }
-
-            let light_pos = node.position + corner_dir * config.offset_from_center;
-
-            // Calculate facing direction (toward intersection center)
-            let facing = -corner_dir;
-            let angle = facing.y.atan2(facing.x);
-
-            // Spawn pole
-            commands.spawn((
-                Mesh3d(pole_mesh.clone()),
-                MeshMaterial3d(pole_material.clone()),
-                Transform::from_xyz(light_pos.x, config.pole_height / 2.0, light_pos.y),
-                TrafficLight,
-            ));
-
-            // Spawn traffic light box
-            let box_y = config.pole_height + config.box_height / 2.0;
-            commands.spawn((
-                Mesh3d(box_mesh.clone()),
-                MeshMaterial3d(box_material.clone()),
-                Transform::from_xyz(light_pos.x, box_y, light_pos.y)
-                    .with_rotation(Quat::from_rotation_y(-angle)),
-                TrafficLight,
-            ));
-
-            // Spawn the three lights (red, yellow, green from top to bottom)
-            let light_spacing = config.box_height / 4.0;
-            let light_offset = config.box_depth / 2.0 + config.light_radius * 0.5;
-
-            // Calculate forward direction for light placement
-            let forward = Vec2::new(facing.x, facing.y).normalize_or_zero();
-
-            // Red light (top)
-            let red_y = box_y + light_spacing;
-            let red_pos = Vec3::new(
-                light_pos.x + forward.x * light_offset,
-                red_y,
-                light_pos.y + forward.y * light_offset,
-            );
-            commands.spawn((
-                Mesh3d(light_mesh.clone()),
-                MeshMaterial3d(red_light.clone()),
-                Transform::from_translation(red_pos),
-                TrafficLight,
-            ));
-            // Red PointLight (starts off since phase is Green)
-            commands.spawn((
-                PointLight {
-                    color: traffic_colors::RED,
-                    intensity: 0.0, // Controlled by phase system
-                    range: cluster_config.traffic_light_radius,
-                    radius: 0.12,
-                    shadows_enabled: false,
-                    ..default()
-                },
-                Transform::from_translation(red_pos),
-                DynamicCityLight::traffic_light(cluster_config.traffic_light_intensity),
-                TrafficSignalColor::Red,
-                TrafficSignalLink { controller: controller_entity },
-            ));
-
-            // Yellow light (middle)
-            let yellow_pos = Vec3::new(
-                light_pos.x + forward.x * light_offset,
-                box_y,
-                light_pos.y + forward.y * light_offset,
-            );
-            commands.spawn((
-                Mesh3d(light_mesh.clone()),
-                MeshMaterial3d(yellow_light.clone()),
-                Transform::from_translation(yellow_pos),
-                TrafficLight,
-            ));
-            // Yellow PointLight (starts off since phase is Green)
-            commands.spawn((
-                PointLight {
-                    color: traffic_colors::YELLOW,
-                    intensity: 0.0, // Controlled by phase system
-                    range: cluster_config.traffic_light_radius,
-                    radius: 0.12,
-                    shadows_enabled: false,
-                    ..default()
-                },
-                Transform::from_translation(yellow_pos),
-                DynamicCityLight::traffic_light(cluster_config.traffic_light_intensity),
-                TrafficSignalColor::Yellow,
-                TrafficSignalLink { controller: controller_entity },
-            ));
-
-            // Green light (bottom)
-            let green_y = box_y - light_spacing;
-            let green_pos = Vec3::new(
-                light_pos.x + forward.x * light_offset,
-                green_y,
-                light_pos.y + forward.y * light_offset,
-            );
-            commands.spawn((
-                Mesh3d(light_mesh.clone()),
-                MeshMaterial3d(green_light.clone()),
-                Transform::from_translation(green_pos),
-                TrafficLight,
-            ));
-            // Green PointLight (starts on since phase is Green)
-            commands.spawn((
-                PointLight {
-                    color: traffic_colors::GREEN,
-                    intensity: cluster_config.traffic_light_intensity, // On by default
-                    range: cluster_config.traffic_light_radius,
-                    radius: 0.12,
-                    shadows_enabled: false,
-                    ..default()
-                },
-                Transform::from_translation(green_pos),
-                DynamicCityLight::traffic_light(cluster_config.traffic_light_intensity),
-                TrafficSignalColor::Green,
-                TrafficSignalLink { controller: controller_entity },
-            ));
-
-            light_count += 1;
-        }
+        });
     }
-
-    spawned.0 = true;
-    info!("Spawned {} traffic lights", light_count);
 }
 
-/// Update traffic light phases based on timers.
+/// Advance each controller through its plan: on a timer, by demand, or in
+/// step with its corridor.
 fn update_traffic_light_phases(
     time: Res<Time>,
     mut controllers: Query<&mut TrafficLightController>,
@@ -320,21 +426,151 @@ fn update_traffic_light_phases(
     let dt = time.delta_secs();
 
     for mut controller in controllers.iter_mut() {
+        let controller = &mut *controller;
+        if controller.plan.phases.is_empty() {
+            continue;
+        }
+
+        if let (SignalMode::Coordinated, Some(coordination)) = (controller.mode, controller.coordination) {
+            let t = time.elapsed_secs() - coordination.offset;
+            let (phase, light) = controller.plan.state_at(t, coordination.cycle, coordination.main_phase);
+            if (phase, light) != (controller.active_phase, controller.phase) {
+                controller.timer = 0.0;
+            }
+            controller.active_phase = phase;
+            controller.phase = light;
+            controller.timer += dt;
+            continue;
+        }
+
         controller.timer += dt;
+        let plan = &controller.plan;
+        let current = &plan.phases[controller.active_phase];
+        let demand = |movement: Movement| controller.demand_for(movement);
+
+        let advance = match controller.phase {
+            LightPhase::Green => match controller.mode {
+                SignalMode::Actuated => actuated_green_ends(
+                    controller.timer,
+                    plan.min_green.min(current.max_green),
+                    current.max_green,
+                    plan.phase_demand(controller.active_phase, demand),
+                    plan.conflicting_demand(controller.active_phase, demand),
+                ),
+                _ => controller.timer >= current.green,
+            },
+            LightPhase::Yellow => controller.timer >= plan.yellow,
+            LightPhase::Red => controller.timer >= plan.all_red,
+        };
+        if !advance {
+            continue;
+        }
 
-        // Check if it's time to transition to next phase
-        let phase_duration = match controller.phase {
-            LightPhase::Green => controller.green_duration,
-            LightPhase::Yellow => controller.yellow_duration,
-            LightPhase::Red => controller.red_duration,
+        // Clearance over, serve the next phase
+        let next = match controller.mode {
+            SignalMode::Actuated => plan.next_phase(controller.active_phase, demand),
+            _ => (controller.active_phase + 1) % plan.phases.len(),
         };
+        controller.timer = 0.0;
+        controller.phase = match controller.phase {
+            LightPhase::Green => LightPhase::Yellow,
+            LightPhase::Yellow => LightPhase::Red,
+            LightPhase::Red => {
+                controller.active_phase = next;
+                LightPhase::Green
+            }
+        };
+    }
+}
+
+/// Speed green waves are timed for, in m/s.
+const PROGRESSION_SPEED: f32 = 12.0;
 
-        if controller.timer >= phase_duration {
-            controller.timer = 0.0;
-            controller.phase = match controller.phase {
-                LightPhase::Green => LightPhase::Yellow,
-                LightPhase::Yellow => LightPhase::Red,
-                LightPhase::Red => LightPhase::Green,
+/// Link signals along major roads into corridors and give each a common cycle
+/// and an offset so traffic released by one arrives at the next on green.
+fn coordinate_green_waves(
+    road_graph: Res<RoadGraph>,
+    added: Query<(), Added<TrafficLightController>>,
+    mut edited: EventReader<SignalPlanEdited>,
+    mut controllers: Query<&mut TrafficLightController>,
+) {
+    let edited = edited.read().count() > 0;
+    if added.is_empty() && !road_graph.is_changed() && !edited {
+        return;
+    }
+
+    let signalled: HashSet<NodeIndex> = controllers.iter().map(|c| c.node_index).collect();
+
+    // Follow major roads out of each signal, through plain bends, to the next signal
+    let mut links = Vec::new();
+    let mut corridor_edge: HashMap<NodeIndex, EdgeIndex> = HashMap::new();
+    for &start in &signalled {
+        for first in road_graph.edges_of_node(start) {
+            let mut node = start;
+            let mut edge = first;
+            let mut distance = 0.0;
+            let mut steps = 0;
+            while let Some(road) = road_graph.edge_by_index(edge) {
+                if road.road_type != RoadType::Major {
+                    break;
+                }
+                let Some((a, b)) = road_graph.edge_endpoints(edge) else { break };
+                let next = if a == node { b } else { a };
+                distance += road.length;
+                steps += 1;
+                if signalled.contains(&next) {
+                    if start < next {
+                        links.push((start, next, distance / PROGRESSION_SPEED));
+                    }
+                    // Through traffic arriving along this edge runs in the main phase
+                    corridor_edge.entry(next).or_insert(edge);
+                    break;
+                }
+                let onward: Vec<EdgeIndex> = road_graph
+                    .edges_of_node(next)
+                    .filter(|&e| e != edge)
+                    .collect();
+                if onward.len() != 1 || steps > 32 {
+                    break;
+                }
+                node = next;
+                edge = onward[0];
+            }
+        }
+    }
+
+    // One cycle per corridor: the longest any of its signals needs
+    let mut cycles: HashMap<NodeIndex, f32> = controllers
+        .iter()
+        .map(|c| (c.node_index, c.plan.cycle_length()))
+        .collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &(a, b, _) in &links {
+            let longest = cycles[&a].max(cycles[&b]);
+            for node in [a, b] {
+                if cycles[&node] < longest {
+                    cycles.insert(node, longest);
+                    changed = true;
+                }
+            }
+        }
+    }
+    let offsets = green_wave_offsets(&links, |node| cycles[&node]);
+
+    for mut controller in controllers.iter_mut() {
+        let node = controller.node_index;
+        controller.coordination = offsets.get(&node).map(|&offset| Coordination {
+            cycle: cycles[&node],
+            offset,
+            main_phase: corridor_edge.get(&node).map_or(0, |&edge| controller.plan.main_phase(edge)),
+        });
+        if !controller.edited {
+            controller.mode = if controller.coordination.is_some() {
+                SignalMode::Coordinated
+            } else {
+                SignalMode::Actuated
             };
         }
     }
@@ -349,7 +585,7 @@ fn update_traffic_signal_intensities(
         // Find the controller for this signal
         if let Ok((_, controller)) = controllers.get(link.controller) {
             // Determine if this signal should be on based on phase
-            let should_be_on = match (controller.phase, signal_color) {
+            let should_be_on = match (controller.approach_phase(link.approach), signal_color) {
                 (LightPhase::Red, TrafficSignalColor::Red) => true,
                 (LightPhase::Yellow, TrafficSignalColor::Yellow) => true,
                 (LightPhase::Green, TrafficSignalColor::Green) => true,
@@ -7,6 +7,7 @@ use bevy::prelude::*;
 use std::f32::consts::PI;
 
 use crate::render::elevated_rail::{ElevatedRailConfig, ElevatedRailSpawned, RailLine};
+use crate::simulation::ridership::{rail_stations, LineKey, Ridership};
 
 pub struct TrainCarsPlugin;
 
@@ -14,6 +15,7 @@ impl Plugin for TrainCarsPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<TrainConfig>()
             .init_resource::<TrainsSpawned>()
+            .add_systems(Startup, setup_train_assets)
             .add_systems(Update, spawn_trains.run_if(should_spawn_trains))
             .add_systems(Update, update_train_movement.run_if(trains_exist));
     }
@@ -51,6 +53,8 @@ pub struct TrainConfig {
     pub speed: f32,
     /// Time stopped at stations (seconds).
     pub station_dwell: f32,
+    /// Passengers each car can carry.
+    pub car_capacity: u32,
 }
 
 impl Default for TrainConfig {
@@ -64,6 +68,7 @@ impl Default for TrainConfig {
             car_gap: 0.5,
             speed: 15.0,
             station_dwell: 5.0,
+            car_capacity: 80,
         }
     }
 }
@@ -81,6 +86,8 @@ pub struct Train {
     pub station_timer: f32,
     /// Whether currently at a station.
     pub at_station: bool,
+    /// Riders aboard, by the index of the station they are going to.
+    pub load: Vec<u32>,
 }
 
 /// Individual train car (follows the lead car).
@@ -96,13 +103,65 @@ pub struct TrainCar {
 #[derive(Component)]
 pub struct TrainWindow;
 
+/// Meshes and materials shared by every train car.
+#[derive(Resource)]
+pub struct TrainAssets {
+    body_material: Handle<StandardMaterial>,
+    pub accent_material: Handle<StandardMaterial>,
+    window_material: Handle<StandardMaterial>,
+    roof_material: Handle<StandardMaterial>,
+    body_mesh: Handle<Mesh>,
+    roof_mesh: Handle<Mesh>,
+    window_mesh: Handle<Mesh>,
+    stripe_mesh: Handle<Mesh>,
+    ac_mesh: Handle<Mesh>,
+}
+
+fn setup_train_assets(
+    mut commands: Commands,
+    config: Res<TrainConfig>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(TrainAssets {
+        body_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.85, 0.85, 0.88),
+            metallic: 0.3,
+            perceptual_roughness: 0.4,
+            ..default()
+        }),
+        accent_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.2, 0.4, 0.7),
+            metallic: 0.5,
+            perceptual_roughness: 0.3,
+            ..default()
+        }),
+        window_material: materials.add(StandardMaterial {
+            base_color: Color::srgba(0.2, 0.25, 0.35, 0.7),
+            alpha_mode: AlphaMode::Blend,
+            metallic: 0.2,
+            perceptual_roughness: 0.1,
+            ..default()
+        }),
+        roof_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.6, 0.6, 0.62),
+            perceptual_roughness: 0.7,
+            ..default()
+        }),
+        body_mesh: meshes.add(Cuboid::new(config.car_length, config.car_height * 0.7, config.car_width)),
+        roof_mesh: meshes.add(Cuboid::new(config.car_length - 0.2, config.car_height * 0.15, config.car_width - 0.1)),
+        window_mesh: meshes.add(Cuboid::new(1.2, config.car_height * 0.3, 0.05)),
+        stripe_mesh: meshes.add(Cuboid::new(config.car_length, 0.3, config.car_width + 0.02)),
+        ac_mesh: meshes.add(Cuboid::new(2.0, 0.3, 1.0)),
+    });
+}
+
 fn spawn_trains(
     mut commands: Commands,
     config: Res<TrainConfig>,
     rail_config: Res<ElevatedRailConfig>,
     rail_line: Res<RailLine>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    assets: Res<TrainAssets>,
     mut spawned: ResMut<TrainsSpawned>,
 ) {
     spawned.0 = true;
@@ -112,50 +171,6 @@ fn spawn_trains(
         return;
     }
 
-    // Materials
-    let body_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.85, 0.85, 0.88),
-        metallic: 0.3,
-        perceptual_roughness: 0.4,
-        ..default()
-    });
-
-    let accent_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.2, 0.4, 0.7),
-        metallic: 0.5,
-        perceptual_roughness: 0.3,
-        ..default()
-    });
-
-    let window_material = materials.add(StandardMaterial {
-        base_color: Color::srgba(0.2, 0.25, 0.35, 0.7),
-        alpha_mode: AlphaMode::Blend,
-        metallic: 0.2,
-        perceptual_roughness: 0.1,
-        ..default()
-    });
-
-    let roof_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.6, 0.6, 0.62),
-        perceptual_roughness: 0.7,
-        ..default()
-    });
-
-    // Meshes
-    let body_mesh = meshes.add(Cuboid::new(
-        config.car_length,
-        config.car_height * 0.7,
-        config.car_width,
-    ));
-    let roof_mesh = meshes.add(Cuboid::new(
-        config.car_length - 0.2,
-        config.car_height * 0.15,
-        config.car_width - 0.1,
-    ));
-    let window_mesh = meshes.add(Cuboid::new(1.2, config.car_height * 0.3, 0.05));
-    let stripe_mesh = meshes.add(Cuboid::new(config.car_length, 0.3, config.car_width + 0.02));
-    let ac_mesh = meshes.add(Cuboid::new(2.0, 0.3, 1.0));
-
     // Spawn trains at evenly spaced positions along the line
     for train_idx in 0..config.train_count {
         let start_progress = train_idx as f32 / config.train_count as f32;
@@ -179,86 +194,110 @@ fn spawn_trains(
                     direction,
                     station_timer: 0.0,
                     at_station: false,
+                    load: Vec::new(),
                 },
             ))
             .id();
 
-        // Spawn all cars as children of a shared parent logic
-        for car_idx in 0..config.cars_per_train {
-            let car_offset = car_idx as f32 * (config.car_length + config.car_gap);
-            let car_progress = start_progress - (car_offset / get_total_rail_length(&rail_line.waypoints)) * direction;
-            let (car_pos, car_rot) = get_rail_position(&rail_line.waypoints, car_progress.rem_euclid(1.0));
-
-            commands
-                .spawn((
-                    Transform::from_xyz(car_pos.x, train_y, car_pos.y).with_rotation(car_rot),
-                    GlobalTransform::default(),
-                    Visibility::Visible,
-                    InheritedVisibility::default(),
-                    ViewVisibility::default(),
-                    TrainCar {
-                        car_index: car_idx,
-                        train_entity,
-                    },
-                ))
-                .with_children(|parent| {
-                    // Main body
+        let total_length = get_total_rail_length(&rail_line.waypoints);
+        spawn_train_cars(
+            &mut commands,
+            &config,
+            &assets,
+            assets.accent_material.clone(),
+            train_entity,
+            config.cars_per_train,
+            |car_idx| {
+                let car_offset = car_idx as f32 * (config.car_length + config.car_gap);
+                let car_progress = start_progress - (car_offset / total_length) * direction;
+                let (car_pos, car_rot) = get_rail_position(&rail_line.waypoints, car_progress.rem_euclid(1.0));
+                Transform::from_xyz(car_pos.x, train_y, car_pos.y).with_rotation(car_rot)
+            },
+        );
+    }
+
+    info!(
+        "Spawned {} trains with {} cars each",
+        config.train_count, config.cars_per_train
+    );
+}
+
+/// Spawn the cars of a train, each placed by `place` from its index, with
+/// `accent` as the colour of the stripe along their sides.
+pub fn spawn_train_cars(
+    commands: &mut Commands,
+    config: &TrainConfig,
+    assets: &TrainAssets,
+    accent: Handle<StandardMaterial>,
+    train_entity: Entity,
+    cars: usize,
+    place: impl Fn(usize) -> Transform,
+) {
+    for car_idx in 0..cars {
+        commands
+            .spawn((
+                place(car_idx),
+                GlobalTransform::default(),
+                Visibility::Visible,
+                InheritedVisibility::default(),
+                ViewVisibility::default(),
+                TrainCar {
+                    car_index: car_idx,
+                    train_entity,
+                },
+            ))
+            .with_children(|parent| {
+                // Main body
+                parent.spawn((
+                    Mesh3d(assets.body_mesh.clone()),
+                    MeshMaterial3d(assets.body_material.clone()),
+                    Transform::IDENTITY,
+                ));
+
+                // Roof
+                parent.spawn((
+                    Mesh3d(assets.roof_mesh.clone()),
+                    MeshMaterial3d(assets.roof_material.clone()),
+                    Transform::from_xyz(0.0, config.car_height * 0.4, 0.0),
+                ));
+
+                // Color stripe
+                parent.spawn((
+                    Mesh3d(assets.stripe_mesh.clone()),
+                    MeshMaterial3d(accent.clone()),
+                    Transform::from_xyz(0.0, 0.0, 0.0),
+                ));
+
+                // Windows on both sides
+                let window_count = 5;
+                for w in 0..window_count {
+                    let x = (w as f32 - (window_count - 1) as f32 / 2.0) * 2.0;
+                    // Front side
                     parent.spawn((
-                        Mesh3d(body_mesh.clone()),
-                        MeshMaterial3d(body_material.clone()),
-                        Transform::IDENTITY,
+                        Mesh3d(assets.window_mesh.clone()),
+                        MeshMaterial3d(assets.window_material.clone()),
+                        Transform::from_xyz(x, config.car_height * 0.1, config.car_width / 2.0 + 0.03),
+                        TrainWindow,
                     ));
-
-                    // Roof
+                    // Back side
                     parent.spawn((
-                        Mesh3d(roof_mesh.clone()),
-                        MeshMaterial3d(roof_material.clone()),
-                        Transform::from_xyz(0.0, config.car_height * 0.4, 0.0),
+                        Mesh3d(assets.window_mesh.clone()),
+                        MeshMaterial3d(assets.window_material.clone()),
+                        Transform::from_xyz(x, config.car_height * 0.1, -config.car_width / 2.0 - 0.03),
+                        TrainWindow,
                     ));
+                }
 
-                    // Color stripe
+                // AC unit on roof
+                if car_idx == 0 || car_idx == cars - 1 {
                     parent.spawn((
-                        Mesh3d(stripe_mesh.clone()),
-                        MeshMaterial3d(accent_material.clone()),
-                        Transform::from_xyz(0.0, 0.0, 0.0),
+                        Mesh3d(assets.ac_mesh.clone()),
+                        MeshMaterial3d(assets.roof_material.clone()),
+                        Transform::from_xyz(0.0, config.car_height * 0.55, 0.0),
                     ));
-
-                    // Windows on both sides
-                    let window_count = 5;
-                    for w in 0..window_count {
-                        let x = (w as f32 - (window_count - 1) as f32 / 2.0) * 2.0;
-                        // Front side
-                        parent.spawn((
-                            Mesh3d(window_mesh.clone()),
-                            MeshMaterial3d(window_material.clone()),
-                            Transform::from_xyz(x, config.car_height * 0.1, config.car_width / 2.0 + 0.03),
-                            TrainWindow,
-                        ));
-                        // Back side
-                        parent.spawn((
-                            Mesh3d(window_mesh.clone()),
-                            MeshMaterial3d(window_material.clone()),
-                            Transform::from_xyz(x, config.car_height * 0.1, -config.car_width / 2.0 - 0.03),
-                            TrainWindow,
-                        ));
-                    }
-
-                    // AC unit on roof
-                    if car_idx == 0 || car_idx == config.cars_per_train - 1 {
-                        parent.spawn((
-                            Mesh3d(ac_mesh.clone()),
-                            MeshMaterial3d(roof_material.clone()),
-                            Transform::from_xyz(0.0, config.car_height * 0.55, 0.0),
-                        ));
-                    }
-                });
-        }
+                }
+            });
     }
-
-    info!(
-        "Spawned {} trains with {} cars each",
-        config.train_count, config.cars_per_train
-    );
 }
 
 fn update_train_movement(
@@ -266,6 +305,7 @@ fn update_train_movement(
     config: Res<TrainConfig>,
     rail_config: Res<ElevatedRailConfig>,
     rail_line: Res<RailLine>,
+    mut ridership: ResMut<Ridership>,
     mut trains: Query<(Entity, &mut Train)>,
     mut cars: Query<(&mut Transform, &TrainCar), Without<Train>>,
 ) {
@@ -276,19 +316,30 @@ fn update_train_movement(
     let dt = time.delta_secs();
     let total_length = get_total_rail_length(&rail_line.waypoints);
     let train_y = rail_config.track_height + config.car_height / 2.0 + 0.4;
+    let stations = rail_stations(&rail_line);
+    let capacity = config.car_capacity * config.cars_per_train as u32;
 
     for (train_entity, mut train) in trains.iter_mut() {
         // Check if at station
-        let near_station = rail_line.stations.iter().any(|&station_idx| {
+        let near_station = stations.iter().position(|&station_idx| {
             let station_progress = station_idx as f32 / (rail_line.waypoints.len() - 1) as f32;
             (train.progress - station_progress).abs() < 0.02
         });
 
-        if near_station && !train.at_station && train.speed_factor > 0.5 {
-            // Arriving at station
+        if let Some(station) = near_station.filter(|_| !train.at_station && train.speed_factor > 0.5) {
+            // Arriving at station; trains turn round at the end stations
             train.at_station = true;
             train.station_timer = config.station_dwell;
             train.speed_factor = 0.0;
+            let forward = if station == 0 {
+                true
+            } else if station == stations.len() - 1 {
+                false
+            } else {
+                train.direction > 0.0
+            };
+            let train = &mut *train;
+            ridership.serve_stop(LineKey::Rail, station, forward, &mut train.load, capacity);
         }
 
         if train.at_station {
@@ -0,0 +1,195 @@
+//! Tunnel portal rendering.
+//!
+//! Tunnel edges have no road surface; each end gets a concrete portal with a
+//! dark mouth set into the hillside. Portals are kept in sync with the road
+//! graph like bridges, so drawing or undoing a tunnel only touches its portals.
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::procgen::intersections::carriageway_width;
+use crate::procgen::road_generator::RoadsGenerated;
+use crate::procgen::roads::{RoadGraph, RoadType};
+use crate::world::terrain::{HeightMap, TerrainAnchor};
+
+pub struct TunnelsPlugin;
+
+impl Plugin for TunnelsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TunnelConfig>()
+            .add_systems(Startup, setup_tunnel_assets)
+            .add_systems(Update, sync_tunnel_portals.run_if(should_sync_tunnels));
+    }
+}
+
+fn should_sync_tunnels(generated: Res<RoadsGenerated>, road_graph: Res<RoadGraph>) -> bool {
+    generated.0 && road_graph.is_changed()
+}
+
+/// Configuration for tunnel portals.
+#[derive(Resource)]
+pub struct TunnelConfig {
+    /// Clear height of the tunnel mouth.
+    pub clearance: f32,
+    /// Thickness of the portal walls and lintel.
+    pub wall_thickness: f32,
+    /// How far the portal extends along the road.
+    pub portal_depth: f32,
+}
+
+impl Default for TunnelConfig {
+    fn default() -> Self {
+        Self {
+            clearance: 5.0,
+            wall_thickness: 0.8,
+            portal_depth: 2.0,
+        }
+    }
+}
+
+/// Shared mesh and materials for portals.
+#[derive(Resource)]
+struct TunnelAssets {
+    unit_box: Handle<Mesh>,
+    concrete: Handle<StandardMaterial>,
+    mouth: Handle<StandardMaterial>,
+}
+
+/// A tunnel portal. Walls, lintel, and mouth are children.
+#[derive(Component)]
+pub struct TunnelPortal {
+    pub road_type: RoadType,
+    /// Road position at the tunnel mouth.
+    pub position: Vec2,
+    /// Horizontal direction pointing into the tunnel.
+    pub inward: Vec2,
+}
+
+/// Identity of a portal, rounded so float noise doesn't cause respawns.
+type PortalKey = (IVec2, IVec2, RoadType);
+
+fn portal_key(position: Vec2, inward: Vec2, road_type: RoadType) -> PortalKey {
+    (
+        (position * 10.0).round().as_ivec2(),
+        (inward * 100.0).round().as_ivec2(),
+        road_type,
+    )
+}
+
+fn setup_tunnel_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(TunnelAssets {
+        unit_box: meshes.add(Cuboid::new(1.0, 1.0, 1.0)),
+        concrete: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.55, 0.53, 0.5),
+            perceptual_roughness: 0.9,
+            ..default()
+        }),
+        mouth: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.02, 0.02, 0.03),
+            perceptual_roughness: 1.0,
+            unlit: true,
+            ..default()
+        }),
+    });
+}
+
+/// Portals wanted by the graph: one at each end of every tunnel edge.
+fn wanted_portals(road_graph: &RoadGraph) -> Vec<TunnelPortal> {
+    let mut portals = Vec::new();
+    for edge in road_graph.edges().filter(|e| e.tunnel && e.points.len() >= 2) {
+        let n = edge.points.len();
+        for (position, next) in [
+            (edge.points[0], edge.points[1]),
+            (edge.points[n - 1], edge.points[n - 2]),
+        ] {
+            portals.push(TunnelPortal {
+                road_type: edge.road_type,
+                position,
+                inward: (next - position).normalize_or_zero(),
+            });
+        }
+    }
+    portals
+}
+
+/// Spawn portals for new tunnels and despawn those whose edge is gone.
+fn sync_tunnel_portals(
+    mut commands: Commands,
+    road_graph: Res<RoadGraph>,
+    terrain: Res<HeightMap>,
+    config: Res<TunnelConfig>,
+    assets: Res<TunnelAssets>,
+    existing: Query<(Entity, &TunnelPortal)>,
+) {
+    let portals = wanted_portals(&road_graph);
+    let mut missing: HashSet<PortalKey> = portals
+        .iter()
+        .map(|p| portal_key(p.position, p.inward, p.road_type))
+        .collect();
+
+    for (entity, portal) in &existing {
+        if !missing.remove(&portal_key(portal.position, portal.inward, portal.road_type)) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    for portal in portals {
+        if missing.remove(&portal_key(portal.position, portal.inward, portal.road_type)) {
+            spawn_portal(&mut commands, &assets, &config, &terrain, portal);
+        }
+    }
+}
+
+/// Spawn one portal frame facing out of the tunnel.
+fn spawn_portal(
+    commands: &mut Commands,
+    assets: &TunnelAssets,
+    config: &TunnelConfig,
+    terrain: &HeightMap,
+    portal: TunnelPortal,
+) {
+    let width = carriageway_width(portal.road_type) + 1.0;
+    let t = config.wall_thickness;
+    let h = config.clearance;
+    let depth = config.portal_depth;
+
+    // Local -Z points into the tunnel
+    let translation = Vec3::new(portal.position.x, terrain.sample_world(portal.position), portal.position.y);
+    let transform = Transform::from_translation(translation)
+        .looking_to(Vec3::new(portal.inward.x, 0.0, portal.inward.y), Vec3::Y);
+
+    commands
+        .spawn((transform, Visibility::default(), TerrainAnchor { offset: 0.0 }, portal))
+        .with_children(|parent| {
+            // Side walls
+            for side in [-1.0, 1.0] {
+                parent.spawn((
+                    Mesh3d(assets.unit_box.clone()),
+                    MeshMaterial3d(assets.concrete.clone()),
+                    Transform::from_xyz(side * (width + t) / 2.0, (h + t) / 2.0, -depth / 2.0)
+                        .with_scale(Vec3::new(t, h + t, depth)),
+                ));
+            }
+
+            // Lintel across the top
+            parent.spawn((
+                Mesh3d(assets.unit_box.clone()),
+                MeshMaterial3d(assets.concrete.clone()),
+                Transform::from_xyz(0.0, h + t / 2.0, -depth / 2.0)
+                    .with_scale(Vec3::new(width + 2.0 * t, t, depth)),
+            ));
+
+            // Dark mouth at the back of the portal
+            parent.spawn((
+                Mesh3d(assets.unit_box.clone()),
+                MeshMaterial3d(assets.mouth.clone()),
+                Transform::from_xyz(0.0, h / 2.0, -depth)
+                    .with_scale(Vec3::new(width, h, 0.1)),
+            ));
+        });
+}
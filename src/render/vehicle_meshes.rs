@@ -16,6 +16,8 @@ pub enum VehicleShape {
     Bus,
     SportsCar,
     Hatchback,
+    /// A bicycle with its rider.
+    Bicycle,
 }
 
 /// Configuration for generating a vehicle mesh.
@@ -48,6 +50,7 @@ pub fn generate_vehicle_mesh(config: &VehicleMeshConfig) -> Mesh {
         VehicleShape::Bus => generate_bus_mesh(config),
         VehicleShape::SportsCar => generate_sports_car_mesh(config),
         VehicleShape::Hatchback => generate_hatchback_mesh(config),
+        VehicleShape::Bicycle => generate_bicycle_mesh(config),
     }
 }
 
@@ -851,6 +854,52 @@ fn generate_hatchback_mesh(config: &VehicleMeshConfig) -> Mesh {
     build_mesh_with_uvs(positions, normals, indices)
 }
 
+/// Generate bicycle mesh - thin frame and a rider leaning over the bars.
+///
+/// The wheels are left out; spawn two `generate_wheel_mesh` wheels turned
+/// side-on at either end of the frame.
+fn generate_bicycle_mesh(config: &VehicleMeshConfig) -> Mesh {
+    // Laid out for a 1.8 m bike, 0.6 m across the bars, 1.75 m to the rider's head
+    let scale = Vec3::new(config.width / 0.6, config.height / 1.75, config.length / 1.8);
+
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut indices = Vec::new();
+
+    let boxes = [
+        // Top tube, seat tube and chain stays
+        (Vec3::new(-0.02, 0.78, -0.38), Vec3::new(0.02, 0.82, 0.18)),
+        (Vec3::new(-0.02, 0.32, 0.14), Vec3::new(0.02, 0.86, 0.20)),
+        (Vec3::new(-0.03, 0.32, 0.14), Vec3::new(0.03, 0.36, 0.56)),
+        // Fork, stem and handlebar
+        (Vec3::new(-0.03, 0.34, -0.58), Vec3::new(0.03, 0.90, -0.52)),
+        (Vec3::new(-0.02, 0.86, -0.56), Vec3::new(0.02, 0.98, -0.44)),
+        (Vec3::new(-0.30, 0.96, -0.48), Vec3::new(0.30, 1.00, -0.42)),
+        // Saddle
+        (Vec3::new(-0.08, 0.88, 0.10), Vec3::new(0.08, 0.93, 0.30)),
+        // Rider: legs, torso, arms and head
+        (Vec3::new(-0.14, 0.45, -0.05), Vec3::new(0.14, 0.92, 0.18)),
+        (Vec3::new(-0.18, 0.92, -0.12), Vec3::new(0.18, 1.40, 0.26)),
+        (Vec3::new(-0.26, 1.02, -0.46), Vec3::new(-0.18, 1.34, -0.08)),
+        (Vec3::new(0.18, 1.02, -0.46), Vec3::new(0.26, 1.34, -0.08)),
+        (Vec3::new(-0.10, 1.46, -0.12), Vec3::new(0.10, 1.70, 0.10)),
+    ];
+    for (min, max) in boxes {
+        add_box(&mut positions, &mut normals, &mut indices, min * scale, max * scale);
+    }
+
+    // Down tube from the bottom bracket up to the head tube
+    add_sloped_box(
+        &mut positions, &mut normals, &mut indices,
+        -0.02 * scale.x, 0.02 * scale.x,
+        0.32 * scale.y,
+        0.84 * scale.y, 0.40 * scale.y,
+        -0.52 * scale.z, 0.14 * scale.z,
+    );
+
+    build_mesh_with_uvs(positions, normals, indices)
+}
+
 /// Generate a wheel mesh (solid cylinder - tire with filled faces)
 pub fn generate_wheel_mesh(radius: f32, width: f32) -> Mesh {
     let segments = 16;
@@ -1,6 +1,7 @@
 //! Water surface rendering with animated waves.
 //!
-//! Creates an animated water surface for rivers using a custom shader.
+//! Creates an animated water surface for rivers, delta channels and the sea
+//! using a custom shader.
 
 use bevy::{
     prelude::*,
@@ -122,7 +123,7 @@ fn spawn_water_mesh(
     config: Res<WaterConfig>,
     mut spawned: ResMut<WaterSpawned>,
 ) {
-    if spawned.0 || !river_generated.0 || river.centerline.is_empty() {
+    if spawned.0 || !river_generated.0 || river.is_empty() {
         return;
     }
 
@@ -156,54 +157,65 @@ fn spawn_water_mesh(
     );
 }
 
-/// Create water mesh as a quad strip following river banks.
+/// Create water mesh as quad strips following each channel's banks, plus
+/// one strip running from the shoreline out to sea.
 fn create_water_mesh(river: &River) -> Mesh {
-    let num_points = river.left_bank.len();
-    if num_points < 2 {
-        // Return empty mesh if river is too short
-        return Mesh::new(
-            PrimitiveTopology::TriangleList,
-            RenderAssetUsages::RENDER_WORLD,
-        );
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut uvs: Vec<[f32; 2]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+
+    let mut strips: Vec<(&[Vec2], Vec<Vec2>)> = vec![(&river.left_bank, river.right_bank.clone())];
+    for channel in &river.distributaries {
+        strips.push((&channel.left_bank, channel.right_bank.clone()));
     }
-
-    let mut positions: Vec<[f32; 3]> = Vec::with_capacity(num_points * 2);
-    let mut normals: Vec<[f32; 3]> = Vec::with_capacity(num_points * 2);
-    let mut uvs: Vec<[f32; 2]> = Vec::with_capacity(num_points * 2);
-
-    // Generate vertices along both banks
-    for i in 0..num_points {
-        let left = river.left_bank[i];
-        let right = river.right_bank[i];
-
-        // Position at water level (Y=0, transform will offset)
-        positions.push([left.x, 0.0, left.y]);
-        positions.push([right.x, 0.0, right.y]);
-
-        // Water surface normal points up
-        normals.push([0.0, 1.0, 0.0]);
-        normals.push([0.0, 1.0, 0.0]);
-
-        // UV: X = 0 for left bank, 1 for right bank
-        // UV: Y = progress along river (0 to 1)
-        let v = i as f32 / (num_points - 1) as f32;
-        uvs.push([0.0, v]);
-        uvs.push([1.0, v]);
+    if let Some(shore) = &river.shoreline {
+        // Reach well past the map edge so the sea has no visible border
+        let reach = river.bounds.size().max_element();
+        let offshore = shore.points.iter().map(|p| *p + shore.seaward * reach).collect();
+        strips.push((&shore.points, offshore));
     }
 
-    // Generate triangle indices (quad strip)
-    let mut indices: Vec<u32> = Vec::with_capacity((num_points - 1) * 6);
-    for i in 0..(num_points - 1) {
-        let base = (i * 2) as u32;
-        // Two triangles per quad (CCW winding for top-facing)
-        // First triangle
-        indices.push(base);
-        indices.push(base + 2);
-        indices.push(base + 1);
-        // Second triangle
-        indices.push(base + 1);
-        indices.push(base + 2);
-        indices.push(base + 3);
+    for (left_bank, right_bank) in &strips {
+        let num_points = left_bank.len().min(right_bank.len());
+        if num_points < 2 {
+            continue;
+        }
+        let first = positions.len() as u32;
+
+        // Generate vertices along both banks
+        for i in 0..num_points {
+            let left = left_bank[i];
+            let right = right_bank[i];
+
+            // Position at water level (Y=0, transform will offset)
+            positions.push([left.x, 0.0, left.y]);
+            positions.push([right.x, 0.0, right.y]);
+
+            // Water surface normal points up
+            normals.push([0.0, 1.0, 0.0]);
+            normals.push([0.0, 1.0, 0.0]);
+
+            // UV: X = 0 for left bank, 1 for right bank
+            // UV: Y = progress along the strip (0 to 1)
+            let v = i as f32 / (num_points - 1) as f32;
+            uvs.push([0.0, v]);
+            uvs.push([1.0, v]);
+        }
+
+        // Generate triangle indices (quad strip)
+        for i in 0..(num_points - 1) {
+            let base = first + (i * 2) as u32;
+            // Two triangles per quad (CCW winding for top-facing)
+            // First triangle
+            indices.push(base);
+            indices.push(base + 2);
+            indices.push(base + 1);
+            // Second triangle
+            indices.push(base + 1);
+            indices.push(base + 2);
+            indices.push(base + 3);
+        }
     }
 
     let mut mesh = Mesh::new(
@@ -1,40 +1,48 @@
 //! Bus route system with buses following defined routes and stopping at bus stops.
 //!
 //! Creates bus routes along major roads and spawns buses that follow them,
-//! stopping at bus stops to pick up passengers.
+//! stopping at bus stops to pick up passengers. Players add their own lines
+//! with the transit tool: a chain of stops routed by shortest path over the
+//! road graph, replanned whenever the roads change. Each route keeps as many
+//! buses running as its service level asks for; fares are collected at stops.
 
 use bevy::prelude::*;
-use petgraph::graph::EdgeIndex;
+use petgraph::graph::{EdgeIndex, NodeIndex};
 use rand::{rngs::StdRng, Rng, SeedableRng};
 use std::f32::consts::PI;
 
-use crate::procgen::roads::{RoadGraph, RoadType};
+use crate::procgen::intersections::lane_side;
+use crate::procgen::roads::{RoadGraph, RoadRemoval, RoadType, RoadsRemoved};
 use crate::render::bus_stops::{BusStop, BusStopsSpawned};
 use crate::render::road_mesh::RoadMeshGenerated;
 
+use super::ridership::{LineKey, Ridership};
+
 pub struct BusRoutesPlugin;
 
 impl Plugin for BusRoutesPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<BusRouteConfig>()
             .init_resource::<BusRoutes>()
-            .init_resource::<BusesSpawned>()
             .init_resource::<RoutesGenerated>()
+            .init_resource::<TransitLedger>()
+            .add_event::<BusLineEdited>()
+            .add_systems(Startup, setup_bus_assets)
             .add_systems(
                 Update,
                 (
                     generate_bus_routes.run_if(should_generate_routes),
-                    spawn_buses.run_if(should_spawn_buses),
+                    reroute_after_road_removal,
+                    replan_player_lines,
+                    sync_route_buses,
                     update_bus_movement,
                     update_bus_transforms,
-                ),
+                )
+                    .chain(),
             );
     }
 }
 
-#[derive(Resource, Default)]
-pub struct BusesSpawned(pub bool);
-
 #[derive(Resource, Default)]
 pub struct RoutesGenerated(pub bool);
 
@@ -46,10 +54,6 @@ fn should_generate_routes(
     !road_mesh.is_empty() && bus_stops_spawned.0 && !routes_generated.0
 }
 
-fn should_spawn_buses(routes: Res<BusRoutes>, spawned: Res<BusesSpawned>) -> bool {
-    !routes.routes.is_empty() && !spawned.0
-}
-
 #[derive(Resource)]
 pub struct BusRouteConfig {
     pub seed: u64,
@@ -60,6 +64,9 @@ pub struct BusRouteConfig {
     pub bus_length: f32,
     pub bus_width: f32,
     pub bus_height: f32,
+    /// How far from a road a player stop may be placed and still snap to it.
+    pub stop_snap_distance: f32,
+    pub bus_capacity: u32,
 }
 
 impl Default for BusRouteConfig {
@@ -73,19 +80,61 @@ impl Default for BusRouteConfig {
             bus_length: 10.0,
             bus_width: 2.5,
             bus_height: 3.0,
+            stop_snap_distance: 15.0,
+            bus_capacity: 60,
         }
     }
 }
 
+/// How many buses a line runs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineService {
+    /// A fixed number of buses.
+    Vehicles(usize),
+    /// Enough buses for one to call at each stop this often, in seconds.
+    Headway(f32),
+}
+
 /// A bus route with waypoints and stop indices.
 #[derive(Clone)]
 pub struct BusRoute {
+    pub name: String,
     /// Edges that make up this route.
     pub edges: Vec<EdgeIndex>,
     /// Indices into edges where bus stops are located.
     pub stop_indices: Vec<usize>,
     /// Route color for identification.
     pub color: Color,
+    /// Stops placed by the player, in calling order. Lines with stops are
+    /// replanned from them whenever the roads change; generated routes have none.
+    pub stops: Vec<Vec2>,
+    pub service: LineService,
+}
+
+impl BusRoute {
+    /// Whether the route was laid out by the player.
+    pub fn is_player_line(&self) -> bool {
+        !self.stops.is_empty()
+    }
+
+    /// Total length of the route's roads.
+    pub fn length(&self, road_graph: &RoadGraph) -> f32 {
+        self.edges
+            .iter()
+            .filter_map(|&edge| road_graph.edge_by_index(edge))
+            .map(|edge| edge.length)
+            .sum()
+    }
+}
+
+/// A player line's stops or service were changed, so it needs replanning.
+#[derive(Event)]
+pub struct BusLineEdited;
+
+/// Riders carried since the last budget tick, for fare income.
+#[derive(Resource, Default)]
+pub struct TransitLedger {
+    pub boardings: u32,
 }
 
 /// Resource holding all bus routes.
@@ -110,10 +159,12 @@ pub struct Bus {
     pub stop_timer: f32,
     /// Direction (1 = forward through route, -1 = reverse).
     pub direction: f32,
+    /// Riders aboard, by the index of the stop they are going to.
+    pub load: Vec<u32>,
 }
 
 /// Route colors for different bus lines.
-const ROUTE_COLORS: &[Color] = &[
+pub const ROUTE_COLORS: &[Color] = &[
     Color::srgb(0.2, 0.5, 0.8),  // Blue
     Color::srgb(0.8, 0.3, 0.2),  // Red
     Color::srgb(0.2, 0.7, 0.3),  // Green
@@ -217,155 +268,440 @@ fn generate_bus_routes(
         }
 
         routes.routes.push(BusRoute {
+            name: format!("Route {}", route_idx + 1),
             edges: route_edges,
             stop_indices,
             color: ROUTE_COLORS[route_idx % ROUTE_COLORS.len()],
+            stops: Vec::new(),
+            service: LineService::Vehicles(config.buses_per_route),
         });
     }
 
     info!("Generated {} bus routes", routes.routes.len());
 }
 
-fn spawn_buses(
+/// Patch routes around removed roads and move their buses onto the new edges.
+///
+/// A gap is bridged by the shortest detour over non-alley roads; if there is
+/// none, the route keeps its longest connected stretch. Buses on a part that
+/// was dropped are despawned, as are routes left shorter than two edges.
+fn reroute_after_road_removal(
     mut commands: Commands,
-    config: Res<BusRouteConfig>,
-    routes: Res<BusRoutes>,
+    mut events: EventReader<RoadsRemoved>,
     road_graph: Res<RoadGraph>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    mut spawned: ResMut<BusesSpawned>,
+    mut routes: ResMut<BusRoutes>,
+    mut buses: Query<(Entity, &mut Bus)>,
 ) {
-    spawned.0 = true;
+    for RoadsRemoved(removal) in events.read() {
+        // Which old route positions lost their edge, and where each position went
+        let lost: Vec<Vec<bool>> = routes
+            .routes
+            .iter()
+            .map(|route| route.edges.iter().map(|&edge| removal.remap.edge(edge).is_none()).collect())
+            .collect();
+        let positions: Vec<Vec<Option<usize>>> = routes
+            .routes
+            .iter_mut()
+            .map(|route| reroute(route, removal, &road_graph))
+            .collect();
+
+        for (entity, mut bus) in &mut buses {
+            // Player lines are replanned from their stops instead
+            if routes.routes.get(bus.route_index).is_some_and(|route| route.is_player_line()) {
+                continue;
+            }
+            let route_len = routes.routes.get(bus.route_index).map_or(0, |route| route.edges.len());
+            let on_lost_edge = lost
+                .get(bus.route_index)
+                .and_then(|flags| flags.get(bus.edge_index))
+                .copied()
+                .unwrap_or(false);
+            match positions.get(bus.route_index).and_then(|map| map.get(bus.edge_index)).copied().flatten() {
+                Some(position) if route_len >= 2 => {
+                    bus.edge_index = position;
+                    if on_lost_edge {
+                        bus.progress = if bus.direction > 0.0 { 0.0 } else { 1.0 };
+                        bus.at_stop = false;
+                    }
+                }
+                _ => commands.entity(entity).despawn_recursive(),
+            }
+        }
+        for route in &mut routes.routes {
+            if route.edges.len() < 2 && !route.is_player_line() {
+                route.edges.clear();
+                route.stop_indices.clear();
+            }
+        }
+    }
+}
 
-    if routes.routes.is_empty() {
-        return;
+/// A connected stretch of a rerouted line: its edges, and (old position, new
+/// position) pairs for the old route positions it covers.
+type RoutePiece = (Vec<EdgeIndex>, Vec<(usize, usize)>);
+
+/// Rebuild one route after a removal, returning each old position's new position.
+fn reroute(route: &mut BusRoute, removal: &RoadRemoval, road_graph: &RoadGraph) -> Vec<Option<usize>> {
+    if route.is_player_line() {
+        return Vec::new();
     }
+    let old_edges = std::mem::take(&mut route.edges);
+    let removed_ends = |edge: EdgeIndex| {
+        removal
+            .edges
+            .iter()
+            .find(|removed| removed.index == edge)
+            .map(|removed| [removed.from, removed.to])
+    };
+    // Node of `kept` standing at one of the removed edge's ends
+    let shared_node = |kept: EdgeIndex, ends: [Vec2; 2]| -> Option<NodeIndex> {
+        let (a, b) = road_graph.edge_endpoints(kept)?;
+        [a, b].into_iter().find(|&node| {
+            road_graph
+                .node_by_index(node)
+                .is_some_and(|n| ends.iter().any(|end| end.distance(n.position) < 0.05))
+        })
+    };
 
-    let mut rng = StdRng::seed_from_u64(config.seed + 1);
-
-    // Meshes
-    let body_mesh = meshes.add(Cuboid::new(
-        config.bus_length,
-        config.bus_height * 0.7,
-        config.bus_width,
-    ));
-    let roof_mesh = meshes.add(Cuboid::new(
-        config.bus_length - 0.5,
-        config.bus_height * 0.15,
-        config.bus_width - 0.2,
-    ));
-    let window_mesh = meshes.add(Cuboid::new(1.5, config.bus_height * 0.25, 0.05));
-
-    let window_material = materials.add(StandardMaterial {
-        base_color: Color::srgba(0.2, 0.3, 0.4, 0.7),
-        alpha_mode: AlphaMode::Blend,
-        ..default()
-    });
+    let mut pieces: Vec<RoutePiece> = vec![(Vec::new(), Vec::new())];
+    let mut i = 0;
+    while i < old_edges.len() {
+        if let Some(edge) = removal.remap.edge(old_edges[i]) {
+            let piece = pieces.last_mut().unwrap();
+            piece.1.push((i, piece.0.len()));
+            piece.0.push(edge);
+            i += 1;
+            continue;
+        }
+        // Run of removed edges i..j
+        let mut j = i;
+        while j < old_edges.len() && removal.remap.edge(old_edges[j]).is_none() {
+            j += 1;
+        }
+        let before = pieces.last().and_then(|piece| piece.0.last().copied());
+        let after = old_edges.get(j).and_then(|&edge| removal.remap.edge(edge));
+        let detour = match (before, after) {
+            (Some(before), Some(after)) => {
+                let start = removed_ends(old_edges[i]).and_then(|ends| shared_node(before, ends));
+                let end = removed_ends(old_edges[j - 1]).and_then(|ends| shared_node(after, ends));
+                start
+                    .zip(end)
+                    .and_then(|(start, end)| road_graph.shortest_path(start, end, |e| e.road_type != RoadType::Alley))
+            }
+            _ => None,
+        };
+        match detour {
+            Some(detour) if !detour.is_empty() => {
+                let piece = pieces.last_mut().unwrap();
+                for old in i..j {
+                    piece.1.push((old, piece.0.len()));
+                }
+                piece.0.extend(detour);
+            }
+            _ => pieces.push((Vec::new(), Vec::new())),
+        }
+        i = j;
+    }
 
-    let roof_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.85, 0.85, 0.88),
-        perceptual_roughness: 0.6,
-        ..default()
-    });
+    let (edges, mapping) = pieces.into_iter().max_by_key(|piece| piece.0.len()).unwrap_or_default();
+    let mut positions = vec![None; old_edges.len()];
+    for (old, new) in mapping {
+        positions[old] = Some(new);
+    }
+    route.stop_indices = route.stop_indices.iter().filter_map(|&stop| positions.get(stop).copied().flatten()).collect();
+    route.stop_indices.dedup();
+    route.edges = edges;
+    positions
+}
 
-    for (route_idx, route) in routes.routes.iter().enumerate() {
-        let body_material = materials.add(StandardMaterial {
-            base_color: route.color,
-            perceptual_roughness: 0.5,
-            metallic: 0.3,
-            ..default()
-        });
+/// Most buses a line may run, whatever its headway.
+pub const MAX_LINE_BUSES: usize = 30;
+
+/// Route a line through `stops` in order over the road graph.
+///
+/// Each stop snaps to the nearest road within `snap_distance`, and each road
+/// is reached from the previous one by the shortest path. Stops with no road
+/// nearby, or none reachable, are left out. Returns the route's edges and the
+/// index of the edge serving each stop.
+pub fn plan_line(road_graph: &RoadGraph, stops: &[Vec2], snap_distance: f32) -> (Vec<EdgeIndex>, Vec<usize>) {
+    let mut edges: Vec<EdgeIndex> = Vec::new();
+    let mut stop_indices = Vec::new();
+    // Node the route has reached, once the first leg fixes its direction
+    let mut end: Option<NodeIndex> = None;
+
+    for &stop in stops {
+        let Some((stop_edge, _, _)) = road_graph.nearest_edge(stop, snap_distance) else {
+            continue;
+        };
+        let Some((a, b)) = road_graph.edge_endpoints(stop_edge) else {
+            continue;
+        };
+        let Some(&last) = edges.last() else {
+            edges.push(stop_edge);
+            stop_indices.push(0);
+            continue;
+        };
+        if last == stop_edge {
+            continue;
+        }
+        let starts: Vec<NodeIndex> = match (end, road_graph.edge_endpoints(last)) {
+            (Some(node), _) => vec![node],
+            (None, Some((la, lb))) => vec![la, lb],
+            (None, None) => continue,
+        };
+        let path_length = |path: &[EdgeIndex]| -> f32 {
+            path.iter().filter_map(|&e| road_graph.edge_by_index(e)).map(|e| e.length).sum()
+        };
+        let best = starts
+            .iter()
+            .flat_map(|&start| [(start, a, b), (start, b, a)])
+            .filter_map(|(start, entry, exit)| {
+                let path = road_graph.shortest_path(start, entry, |_| true)?;
+                Some((path_length(&path), path, exit))
+            })
+            .min_by(|x, y| x.0.total_cmp(&y.0));
+        let Some((_, path, exit)) = best else {
+            continue;
+        };
+        edges.extend(path);
+        edges.push(stop_edge);
+        stop_indices.push(edges.len() - 1);
+        end = Some(exit);
+    }
 
-        // Spawn buses evenly distributed along route
-        for bus_idx in 0..config.buses_per_route {
-            let start_edge_idx = (bus_idx * route.edges.len() / config.buses_per_route) % route.edges.len();
-            let edge = route.edges[start_edge_idx];
-
-            // Get initial position
-            let (pos, dir) = if let Some(edge_data) = road_graph.edge_by_index(edge) {
-                let mid = edge_data.points.len() / 2;
-                if mid < edge_data.points.len() {
-                    let p = edge_data.points[mid];
-                    let d = if mid + 1 < edge_data.points.len() {
-                        (edge_data.points[mid + 1] - p).normalize_or_zero()
-                    } else if mid > 0 {
-                        (p - edge_data.points[mid - 1]).normalize_or_zero()
-                    } else {
-                        Vec2::X
-                    };
-                    (p, d)
-                } else {
-                    (Vec2::ZERO, Vec2::X)
-                }
-            } else {
+    (edges, stop_indices)
+}
+
+/// Time for one vehicle to run a line end to end and back.
+pub fn round_trip_time(route_length: f32, stop_count: usize, speed: f32, stop_duration: f32) -> f32 {
+    2.0 * route_length / speed.max(0.1) + 2.0 * stop_count as f32 * stop_duration
+}
+
+/// Buses needed for one to call at each stop every `headway` seconds.
+pub fn buses_for_headway(route_length: f32, stop_count: usize, headway: f32, speed: f32, stop_duration: f32) -> usize {
+    let round_trip = round_trip_time(route_length, stop_count, speed, stop_duration);
+    ((round_trip / headway.max(1.0)).ceil() as usize).clamp(1, MAX_LINE_BUSES)
+}
+
+/// Buses a route should have running.
+pub fn buses_wanted(route: &BusRoute, road_graph: &RoadGraph, config: &BusRouteConfig) -> usize {
+    if route.edges.len() < 2 {
+        return 0;
+    }
+    match route.service {
+        LineService::Vehicles(count) => count.min(MAX_LINE_BUSES),
+        LineService::Headway(headway) => buses_for_headway(
+            route.length(road_graph),
+            route.stop_indices.len(),
+            headway,
+            config.bus_speed,
+            config.stop_duration,
+        ),
+    }
+}
+
+/// Reroute player lines from their stops after a line is edited or the roads
+/// change, moving each bus onto the nearest edge of its new route.
+fn replan_player_lines(
+    mut edits: EventReader<BusLineEdited>,
+    road_graph: Res<RoadGraph>,
+    config: Res<BusRouteConfig>,
+    mut routes: ResMut<BusRoutes>,
+    mut buses: Query<(&mut Bus, &Transform)>,
+) {
+    let edited = edits.read().count() > 0;
+    if !edited && !road_graph.is_changed() {
+        return;
+    }
+
+    for (route_idx, route) in routes.routes.iter_mut().enumerate() {
+        if !route.is_player_line() {
+            continue;
+        }
+        let (edges, stop_indices) = plan_line(&road_graph, &route.stops, config.stop_snap_distance);
+        if edges == route.edges && stop_indices == route.stop_indices {
+            continue;
+        }
+        route.edges = edges;
+        route.stop_indices = stop_indices;
+
+        for (mut bus, transform) in &mut buses {
+            if bus.route_index != route_idx {
                 continue;
-            };
+            }
+            let position = Vec2::new(transform.translation.x, transform.translation.z);
+            let nearest = route
+                .edges
+                .iter()
+                .enumerate()
+                .filter_map(|(i, &edge)| {
+                    let edge = road_graph.edge_by_index(edge)?;
+                    let distance = edge.points.iter().map(|p| p.distance_squared(position)).fold(f32::INFINITY, f32::min);
+                    Some((i, distance))
+                })
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+            bus.edge_index = nearest.map_or(0, |(i, _)| i);
+            bus.progress = 0.5;
+            bus.at_stop = false;
+            // Stops are renumbered; riders aboard get off
+            bus.load.clear();
+        }
+    }
+}
 
-            let angle = dir.y.atan2(dir.x);
-            let direction = if rng.gen_bool(0.5) { 1.0 } else { -1.0 };
-
-            commands
-                .spawn((
-                    Transform::from_xyz(pos.x, config.bus_height / 2.0 + 0.3, pos.y)
-                        .with_rotation(Quat::from_rotation_y(-angle + PI / 2.0)),
-                    GlobalTransform::default(),
-                    Visibility::Visible,
-                    InheritedVisibility::default(),
-                    ViewVisibility::default(),
-                    Bus {
-                        route_index: route_idx,
-                        edge_index: start_edge_idx,
-                        progress: 0.5,
-                        speed: config.bus_speed,
-                        at_stop: false,
-                        stop_timer: 0.0,
-                        direction,
-                    },
-                ))
-                .with_children(|parent| {
-                    // Body
-                    parent.spawn((
-                        Mesh3d(body_mesh.clone()),
-                        MeshMaterial3d(body_material.clone()),
-                        Transform::IDENTITY,
-                    ));
+/// Meshes and materials shared by every bus.
+#[derive(Resource)]
+pub struct BusAssets {
+    body_mesh: Handle<Mesh>,
+    roof_mesh: Handle<Mesh>,
+    window_mesh: Handle<Mesh>,
+    window_material: Handle<StandardMaterial>,
+    roof_material: Handle<StandardMaterial>,
+}
 
-                    // Roof
-                    parent.spawn((
-                        Mesh3d(roof_mesh.clone()),
-                        MeshMaterial3d(roof_material.clone()),
-                        Transform::from_xyz(0.0, config.bus_height * 0.4, 0.0),
-                    ));
+fn setup_bus_assets(
+    mut commands: Commands,
+    config: Res<BusRouteConfig>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(BusAssets {
+        body_mesh: meshes.add(Cuboid::new(config.bus_length, config.bus_height * 0.7, config.bus_width)),
+        roof_mesh: meshes.add(Cuboid::new(
+            config.bus_length - 0.5,
+            config.bus_height * 0.15,
+            config.bus_width - 0.2,
+        )),
+        window_mesh: meshes.add(Cuboid::new(1.5, config.bus_height * 0.25, 0.05)),
+        window_material: materials.add(StandardMaterial {
+            base_color: Color::srgba(0.2, 0.3, 0.4, 0.7),
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        }),
+        roof_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.85, 0.85, 0.88),
+            perceptual_roughness: 0.6,
+            ..default()
+        }),
+    });
+}
 
-                    // Windows
-                    let window_count = 4;
-                    for w in 0..window_count {
-                        let x = (w as f32 - (window_count - 1) as f32 / 2.0) * 2.0;
-                        // Both sides
-                        for z in [-config.bus_width / 2.0 - 0.03, config.bus_width / 2.0 + 0.03] {
-                            parent.spawn((
-                                Mesh3d(window_mesh.clone()),
-                                MeshMaterial3d(window_material.clone()),
-                                Transform::from_xyz(x, config.bus_height * 0.1, z),
-                            ));
-                        }
-                    }
-                });
+/// Spawn or retire buses so each route runs as many as its service asks for,
+/// spread evenly along the route and alternating direction.
+#[allow(clippy::too_many_arguments)]
+fn sync_route_buses(
+    mut commands: Commands,
+    config: Res<BusRouteConfig>,
+    routes: Res<BusRoutes>,
+    road_graph: Res<RoadGraph>,
+    assets: Res<BusAssets>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut body_materials: Local<Vec<Handle<StandardMaterial>>>,
+    buses: Query<(Entity, &Bus)>,
+) {
+    let mut running: Vec<Vec<Entity>> = vec![Vec::new(); routes.routes.len()];
+    for (entity, bus) in &buses {
+        match running.get_mut(bus.route_index) {
+            Some(route_buses) => route_buses.push(entity),
+            None => commands.entity(entity).despawn_recursive(),
         }
     }
 
-    info!(
-        "Spawned {} buses across {} routes",
-        routes.routes.len() * config.buses_per_route,
-        routes.routes.len()
-    );
+    for (route_idx, route) in routes.routes.iter().enumerate() {
+        // One body material per route, recoloured when the line's colour changes
+        while body_materials.len() <= route_idx {
+            let material = materials.add(StandardMaterial {
+                base_color: route.color,
+                perceptual_roughness: 0.5,
+                metallic: 0.3,
+                ..default()
+            });
+            body_materials.push(material);
+        }
+        let body_material = body_materials[route_idx].clone();
+        if materials.get(&body_material).is_some_and(|m| m.base_color != route.color) {
+            if let Some(material) = materials.get_mut(&body_material) {
+                material.base_color = route.color;
+            }
+        }
+
+        let wanted = buses_wanted(route, &road_graph, &config);
+        let route_buses = &running[route_idx];
+        for &entity in route_buses.iter().skip(wanted) {
+            commands.entity(entity).despawn_recursive();
+        }
+        for bus_idx in route_buses.len()..wanted {
+            let bus = Bus {
+                route_index: route_idx,
+                edge_index: bus_idx * route.edges.len() / wanted,
+                progress: 0.5,
+                speed: config.bus_speed,
+                at_stop: false,
+                stop_timer: 0.0,
+                direction: if bus_idx % 2 == 0 { 1.0 } else { -1.0 },
+                load: Vec::new(),
+            };
+            spawn_bus(&mut commands, &config, &assets, body_material.clone(), bus);
+        }
+    }
 }
 
+fn spawn_bus(
+    commands: &mut Commands,
+    config: &BusRouteConfig,
+    assets: &BusAssets,
+    body_material: Handle<StandardMaterial>,
+    bus: Bus,
+) {
+    commands
+        .spawn((
+            Transform::from_xyz(0.0, config.bus_height / 2.0 + 0.3, 0.0),
+            GlobalTransform::default(),
+            Visibility::Visible,
+            InheritedVisibility::default(),
+            ViewVisibility::default(),
+            bus,
+        ))
+        .with_children(|parent| {
+            // Body
+            parent.spawn((
+                Mesh3d(assets.body_mesh.clone()),
+                MeshMaterial3d(body_material),
+                Transform::IDENTITY,
+            ));
+
+            // Roof
+            parent.spawn((
+                Mesh3d(assets.roof_mesh.clone()),
+                MeshMaterial3d(assets.roof_material.clone()),
+                Transform::from_xyz(0.0, config.bus_height * 0.4, 0.0),
+            ));
+
+            // Windows
+            let window_count = 4;
+            for w in 0..window_count {
+                let x = (w as f32 - (window_count - 1) as f32 / 2.0) * 2.0;
+                // Both sides
+                for z in [-config.bus_width / 2.0 - 0.03, config.bus_width / 2.0 + 0.03] {
+                    parent.spawn((
+                        Mesh3d(assets.window_mesh.clone()),
+                        MeshMaterial3d(assets.window_material.clone()),
+                        Transform::from_xyz(x, config.bus_height * 0.1, z),
+                    ));
+                }
+            }
+        });
+}
+
+/// Move buses along their routes, calling at stops and turning round at
+/// either terminus. Riders get off and on at each call.
 fn update_bus_movement(
     time: Res<Time>,
     config: Res<BusRouteConfig>,
     routes: Res<BusRoutes>,
     road_graph: Res<RoadGraph>,
+    mut ridership: ResMut<Ridership>,
+    mut ledger: ResMut<TransitLedger>,
     mut buses: Query<&mut Bus>,
 ) {
     let dt = time.delta_secs();
@@ -391,58 +727,72 @@ fn update_bus_movement(
         };
 
         let edge_length = edge_data.length.max(1.0);
-        let progress_delta = (bus.speed * dt) / edge_length;
-
-        bus.progress += progress_delta * bus.direction;
-
-        // Check for edge transition
-        if bus.progress >= 1.0 {
-            // Check if this edge has a bus stop
-            if route.stop_indices.contains(&bus.edge_index) {
-                bus.at_stop = true;
-                bus.stop_timer = config.stop_duration;
-                bus.progress = 1.0;
-            }
+        bus.progress += (bus.speed * dt) / edge_length * bus.direction;
 
-            // Move to next edge
-            if bus.direction > 0.0 {
-                bus.edge_index = (bus.edge_index + 1) % route.edges.len();
-            } else {
-                bus.edge_index = if bus.edge_index == 0 {
-                    route.edges.len() - 1
-                } else {
-                    bus.edge_index - 1
-                };
-            }
-            bus.progress = 0.0;
-
-            // Reverse at ends
-            if bus.edge_index == 0 || bus.edge_index == route.edges.len() - 1 {
-                bus.direction = -bus.direction;
-            }
-        } else if bus.progress < 0.0 {
-            // Moving backwards
-            if route.stop_indices.contains(&bus.edge_index) {
-                bus.at_stop = true;
-                bus.stop_timer = config.stop_duration;
-                bus.progress = 0.0;
-            }
+        let finished = if bus.direction > 0.0 { bus.progress >= 1.0 } else { bus.progress <= 0.0 };
+        if !finished {
+            continue;
+        }
+        bus.progress = bus.progress.clamp(0.0, 1.0);
+        let next = bus.edge_index as isize + bus.direction as isize;
+        let turning = next < 0 || next >= route.edges.len() as isize;
+
+        // Call at the stop at the end of this edge
+        if let Some(stop) = route.stop_indices.iter().position(|&i| i == bus.edge_index) {
+            bus.at_stop = true;
+            bus.stop_timer = config.stop_duration;
+            let leaving_forward = (bus.direction > 0.0) != turning;
+            let key = LineKey::Bus(bus.route_index);
+            ledger.boardings += ridership.serve_stop(key, stop, leaving_forward, &mut bus.load, config.bus_capacity);
+        }
 
-            if bus.direction > 0.0 {
-                bus.edge_index = (bus.edge_index + 1) % route.edges.len();
-            } else {
-                bus.edge_index = if bus.edge_index == 0 {
-                    route.edges.len() - 1
-                } else {
-                    bus.edge_index - 1
-                };
-            }
-            bus.progress = 1.0;
+        // Next edge, or turn round at the end of the line
+        if turning {
+            bus.direction = -bus.direction;
+        } else {
+            bus.edge_index = next as usize;
+            bus.progress = if bus.direction > 0.0 { 0.0 } else { 1.0 };
+        }
+    }
+}
 
-            if bus.edge_index == 0 || bus.edge_index == route.edges.len() - 1 {
-                bus.direction = -bus.direction;
-            }
+/// Whether a route runs along its `index`th edge in the order the edge's
+/// points are stored, judged by which end it shares with its neighbours.
+fn runs_forward(road_graph: &RoadGraph, edges: &[EdgeIndex], index: usize) -> bool {
+    let Some(&edge) = edges.get(index) else {
+        return true;
+    };
+    let Some((a, b)) = road_graph.edge_endpoints(edge) else {
+        return true;
+    };
+    // Which of this edge's ends a neighbouring edge meets, if just one; a
+    // repeat of the same edge is a turn-round and says nothing
+    let shared = |neighbour: Option<&EdgeIndex>| {
+        let (x, y) = neighbour.filter(|&&n| n != edge).and_then(|&n| road_graph.edge_endpoints(n))?;
+        match (x == a || y == a, x == b || y == b) {
+            (true, false) => Some(a),
+            (false, true) => Some(b),
+            _ => None,
         }
+    };
+    if let Some(node) = shared(edges.get(index + 1)) {
+        return node == b;
+    }
+    if let Some(node) = shared(index.checked_sub(1).and_then(|i| edges.get(i))) {
+        return node == a;
+    }
+    true
+}
+
+/// Position on a route, `progress` of the way along one of its edges in the
+/// route's direction, and the heading that way.
+pub fn route_point(road_graph: &RoadGraph, route: &BusRoute, edge_index: usize, progress: f32) -> Option<(Vec2, Vec2)> {
+    let edge = road_graph.edge_by_index(*route.edges.get(edge_index)?)?;
+    if runs_forward(road_graph, &route.edges, edge_index) {
+        Some(interpolate_edge(edge.points.as_slice(), progress))
+    } else {
+        let (pos, dir) = interpolate_edge(edge.points.as_slice(), 1.0 - progress);
+        Some((pos, -dir))
     }
 }
 
@@ -456,25 +806,19 @@ fn update_bus_transforms(
         let Some(route) = routes.routes.get(bus.route_index) else {
             continue;
         };
-        let Some(&edge) = route.edges.get(bus.edge_index) else {
+        let Some((pos, dir)) = route_point(&road_graph, route, bus.edge_index, bus.progress) else {
             continue;
         };
-        let Some(edge_data) = road_graph.edge_by_index(edge) else {
-            continue;
-        };
-
-        let (pos, dir) = interpolate_edge(edge_data.points.as_slice(), bus.progress);
 
-        // Offset to right side of road
-        let perp = Vec2::new(-dir.y, dir.x);
-        let offset_pos = pos + perp * 2.0 * bus.direction.signum();
+        // Keep to the driving side
+        let facing = if bus.direction > 0.0 { dir } else { -dir };
+        let offset_pos = pos + lane_side(facing) * 2.0;
 
         transform.translation.x = offset_pos.x;
         transform.translation.z = offset_pos.y;
         transform.translation.y = config.bus_height / 2.0 + 0.3;
 
         // Face direction of travel
-        let facing = if bus.direction > 0.0 { dir } else { -dir };
         let angle = facing.y.atan2(facing.x);
         transform.rotation = Quat::from_rotation_y(-angle + PI / 2.0);
     }
@@ -519,3 +863,71 @@ fn interpolate_edge(points: &[Vec2], progress: f32) -> (Vec2, Vec2) {
     };
     (last, dir)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::procgen::roads::{RoadEdge, RoadNodeType};
+    use smallvec::smallvec;
+
+    /// Nodes 0-1-2 along the x axis with a spur 3-1 from the north, and a
+    /// separate road far off with no connection.
+    fn network() -> RoadGraph {
+        let mut graph = RoadGraph::default();
+        let at = [Vec2::new(0.0, 0.0), Vec2::new(100.0, 0.0), Vec2::new(200.0, 0.0), Vec2::new(100.0, 100.0)];
+        let nodes: Vec<NodeIndex> = at.iter().map(|&p| graph.add_node(p, RoadNodeType::Intersection)).collect();
+        for (a, b) in [(0, 1), (1, 2), (3, 1)] {
+            graph.add_edge_data(nodes[a], nodes[b], RoadEdge::new(smallvec![at[a], at[b]], RoadType::Minor));
+        }
+        let far = [Vec2::new(1000.0, 0.0), Vec2::new(1100.0, 0.0)];
+        let x = graph.add_node(far[0], RoadNodeType::Intersection);
+        let y = graph.add_node(far[1], RoadNodeType::Intersection);
+        graph.add_edge_data(x, y, RoadEdge::new(smallvec![far[0], far[1]], RoadType::Minor));
+        graph
+    }
+
+    #[test]
+    fn lines_follow_shortest_path_between_stops() {
+        let graph = network();
+        let e = EdgeIndex::new;
+        // West end, spur, east end; a stop off any road and one unreachable
+        let stops = [
+            Vec2::new(20.0, 3.0),
+            Vec2::new(103.0, 80.0),
+            Vec2::new(500.0, 500.0),
+            Vec2::new(1050.0, 0.0),
+            Vec2::new(180.0, -2.0),
+        ];
+        let (edges, stop_indices) = plan_line(&graph, &stops, 15.0);
+        // Up the spur and back down it to reach the east end
+        assert_eq!(edges, vec![e(0), e(2), e(2), e(1)]);
+        assert_eq!(stop_indices, vec![0, 1, 3]);
+
+        // The spur is stored north to south
+        assert!(runs_forward(&graph, &edges, 0));
+        assert!(!runs_forward(&graph, &edges, 1));
+        assert!(runs_forward(&graph, &edges, 2));
+        assert!(runs_forward(&graph, &edges, 3));
+
+        let route = BusRoute {
+            name: "Line 1".to_string(),
+            edges,
+            stop_indices,
+            color: ROUTE_COLORS[0],
+            stops: stops.to_vec(),
+            service: LineService::Headway(60.0),
+        };
+        assert_eq!(route.length(&graph), 400.0);
+        // 2 * 400 / 8 + 2 * 3 * 4 = 124 s round trip
+        assert_eq!(buses_wanted(&route, &graph, &BusRouteConfig::default()), 3);
+    }
+
+    #[test]
+    fn headway_sets_fleet_size() {
+        // 1 km line, 5 stops: 2 * 1000 / 10 + 2 * 5 * 4 = 240 s round trip
+        assert_eq!(buses_for_headway(1000.0, 5, 60.0, 10.0, 4.0), 4);
+        assert_eq!(buses_for_headway(1000.0, 5, 100.0, 10.0, 4.0), 3);
+        assert_eq!(buses_for_headway(1000.0, 5, 1000.0, 10.0, 4.0), 1);
+        assert_eq!(buses_for_headway(1000.0, 5, 1.0, 10.0, 4.0), MAX_LINE_BUSES);
+    }
+}
@@ -2,6 +2,8 @@
 //!
 //! Citizens are spawned from residential buildings, assigned jobs at commercial/industrial
 //! buildings, and follow daily schedules (wake, commute, work, return, sleep).
+//! Each commute is announced as a `TripStarted` event for mode choice, and
+//! each evening trip out to shop or relax as an `OutingStarted` event.
 
 use bevy::prelude::*;
 use rand::{rngs::StdRng, Rng, SeedableRng};
@@ -19,6 +21,8 @@ impl Plugin for CitizensPlugin {
         app.init_resource::<CitizenConfig>()
             .init_resource::<CitizenStats>()
             .init_resource::<CitizensSpawned>()
+            .add_event::<TripStarted>()
+            .add_event::<OutingStarted>()
             .add_systems(
                 Update,
                 (
@@ -157,8 +161,6 @@ pub struct Citizen {
     pub needs: Needs,
     /// Current activity state.
     pub state: CitizenState,
-    /// Age in years.
-    pub age: u32,
     /// Time spent in current state (hours).
     pub state_time: f32,
 }
@@ -170,12 +172,27 @@ impl Default for Citizen {
             work: None,
             needs: Needs::default(),
             state: CitizenState::AtHome,
-            age: 30,
             state_time: 0.0,
         }
     }
 }
 
+/// A citizen has set off between two buildings.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct TripStarted {
+    pub citizen: Entity,
+    pub from: Entity,
+    pub to: Entity,
+}
+
+/// A citizen has gone out from home to the nearest shop or park.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct OutingStarted {
+    pub from: Entity,
+    /// `Shopping` or `Leisure`.
+    pub activity: CitizenState,
+}
+
 /// Marker for buildings that can provide jobs.
 #[derive(Component)]
 pub struct Workplace {
@@ -283,16 +300,12 @@ fn spawn_citizens(
                 happiness: rng.gen_range(0.5..0.8),
             };
 
-            // Randomize age (working age)
-            let age = rng.gen_range(18..65);
-
             commands.spawn((
                 Citizen {
                     home: residence,
                     work,
                     needs,
                     state: CitizenState::AtHome,
-                    age,
                     state_time: 0.0,
                 },
                 schedule,
@@ -316,18 +329,31 @@ fn spawn_citizens(
 fn update_citizen_state(
     time_of_day: Option<Res<TimeOfDay>>,
     time: Res<Time>,
-    mut citizens: Query<(&mut Citizen, &DailySchedule)>,
+    mut citizens: Query<(Entity, &mut Citizen, &DailySchedule)>,
+    mut trips: EventWriter<TripStarted>,
+    mut outings: EventWriter<OutingStarted>,
 ) {
     let Some(tod) = time_of_day else { return };
     let hour = tod.hour();
     let dt_hours = time.delta_secs() / 3600.0; // Convert to hours
 
-    for (mut citizen, schedule) in citizens.iter_mut() {
+    for (entity, mut citizen, schedule) in citizens.iter_mut() {
         citizen.state_time += dt_hours;
 
         let new_state = determine_state(hour, &citizen, schedule);
 
         if new_state != citizen.state {
+            if let (CitizenState::Commuting, Some(work)) = (new_state, citizen.work) {
+                // Out to work in the morning, home again in the evening
+                let (from, to) = if hour < schedule.work_start { (citizen.home, work) } else { (work, citizen.home) };
+                trips.send(TripStarted { citizen: entity, from, to });
+            }
+            if matches!(new_state, CitizenState::Shopping | CitizenState::Leisure) {
+                outings.send(OutingStarted {
+                    from: citizen.home,
+                    activity: new_state,
+                });
+            }
             citizen.state = new_state;
             citizen.state_time = 0.0;
         }
@@ -0,0 +1,620 @@
+//! Cycling trips.
+//!
+//! Commuters who chose to cycle ride from the junction nearest where they set
+//! off to the one nearest where they are going. Routes favour bike lanes and
+//! quiet minor streets, put up with major roads where they must, and never use
+//! highways. Riders slow on climbs, stop at red lights, and lock up at the
+//! nearest bike rack with room. Hills on the way, rain and having nowhere to
+//! lock up all count against cycling when a trip picks its mode.
+
+use bevy::prelude::*;
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+use crate::procgen::building_factory::BuildingArchetype;
+use crate::procgen::roads::{RoadEdge, RoadGraph, RoadType, RoadsRemoved};
+use crate::render::building_spawner::Building;
+use crate::render::road_mesh::RoadMeshConfig;
+use crate::render::street_amenities::BikeRack;
+use crate::render::traffic_lights::{LightPhase, TrafficLightController};
+use crate::render::vehicle_meshes::{generate_vehicle_mesh, generate_wheel_mesh, VehicleMeshConfig, VehicleShape};
+use crate::render::weather::WeatherState;
+use crate::world::terrain::HeightMap;
+
+use super::ridership::{TravelMode, TripModeChosen};
+
+pub struct CyclingPlugin;
+
+impl Plugin for CyclingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CyclingConfig>()
+            .init_resource::<CyclingConditions>()
+            .init_resource::<CyclistStats>()
+            .add_systems(
+                Update,
+                (
+                    update_cycling_conditions,
+                    cyclist_road_removal,
+                    spawn_cyclists,
+                    cyclist_movement,
+                    cyclist_transform_sync,
+                    update_cyclist_stats,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Configuration for cycling.
+#[derive(Resource)]
+pub struct CyclingConfig {
+    pub seed: u64,
+    /// Most cyclists out at once; trips beyond this go unseen.
+    pub max_cyclists: usize,
+    /// Riding speed on the flat (units per second).
+    pub speed: f32,
+    pub speed_variance: f32,
+    /// Time spent unlocking and locking up, on every ride.
+    pub overhead: f32,
+    /// Longest trip anyone will cycle.
+    pub max_distance: f32,
+    /// Distance between terrain samples when measuring a trip's climb.
+    pub climb_sample_spacing: f32,
+    /// Seconds added per unit of height climbed.
+    pub climb_penalty: f32,
+    /// How much longer rides feel in the heaviest rain (1.0 = twice as long).
+    pub rain_penalty: f32,
+    /// How much riders slow per unit of grade uphill, and speed up downhill.
+    pub grade_slowdown: f32,
+    /// Farthest from a destination a rack is any use.
+    pub rack_radius: f32,
+    /// Time lost finding somewhere to lock up away from home with no rack free.
+    pub no_rack_penalty: f32,
+    /// Farthest a ride can start or end from a road cyclists may use.
+    pub junction_search_radius: f32,
+    /// Gap between the rider and the kerb.
+    pub kerb_gap: f32,
+}
+
+impl Default for CyclingConfig {
+    fn default() -> Self {
+        Self {
+            seed: 24680,
+            max_cyclists: 60,
+            speed: 4.5, // ~16 km/h
+            speed_variance: 1.0,
+            overhead: 60.0,
+            max_distance: 5000.0,
+            climb_sample_spacing: 20.0,
+            climb_penalty: 15.0,
+            rain_penalty: 1.5,
+            grade_slowdown: 6.0,
+            rack_radius: 80.0,
+            no_rack_penalty: 150.0,
+            junction_search_radius: 150.0,
+            kerb_gap: 0.6,
+        }
+    }
+}
+
+/// One bike rack as riders see it.
+#[derive(Clone, Copy, Debug)]
+pub struct RackSpace {
+    pub position: Vec2,
+    pub capacity: u32,
+    /// Bikes locked here now.
+    pub in_use: u32,
+}
+
+/// What riders weigh up before setting off: the rain and where they can lock up.
+#[derive(Resource, Default)]
+pub struct CyclingConditions {
+    /// Rain intensity (0 = dry, 1 = storm).
+    pub rain: f32,
+    pub racks: Vec<RackSpace>,
+}
+
+impl CyclingConditions {
+    /// Nearest rack within `radius` of `position` with a space free.
+    pub fn free_rack(&self, position: Vec2, radius: f32) -> Option<usize> {
+        self.nearest_rack(position, radius, |rack| rack.in_use < rack.capacity)
+    }
+
+    /// Lock a bike at the nearest free rack; false if there is none.
+    pub fn lock_up(&mut self, position: Vec2, radius: f32) -> bool {
+        let Some(index) = self.free_rack(position, radius) else {
+            return false;
+        };
+        self.racks[index].in_use += 1;
+        true
+    }
+
+    /// Take a bike from the nearest rack with one locked to it.
+    pub fn unlock(&mut self, position: Vec2, radius: f32) {
+        if let Some(index) = self.nearest_rack(position, radius, |rack| rack.in_use > 0) {
+            self.racks[index].in_use -= 1;
+        }
+    }
+
+    fn nearest_rack(&self, position: Vec2, radius: f32, accept: impl Fn(&RackSpace) -> bool) -> Option<usize> {
+        self.racks
+            .iter()
+            .enumerate()
+            .filter(|(_, rack)| accept(rack))
+            .map(|(i, rack)| (i, rack.position.distance(position)))
+            .filter(|&(_, distance)| distance <= radius)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+    }
+}
+
+/// Cycling figures for the city.
+#[derive(Resource, Default)]
+pub struct CyclistStats {
+    /// Cyclists out riding right now.
+    pub riding: usize,
+    /// Rides set off and finished since the city was founded.
+    pub trips: u32,
+    pub arrived: u32,
+    /// Bikes locked at racks, and rack spaces in total.
+    pub racked: u32,
+    pub rack_capacity: u32,
+}
+
+/// Door-to-door time by bike for a trip of `distance` that climbs `climb`
+/// in all, or None beyond cycling range. `rack` is whether there is
+/// somewhere to lock up at the far end.
+pub fn bike_time(distance: f32, climb: f32, rain: f32, rack: bool, config: &CyclingConfig) -> Option<f32> {
+    if distance > config.max_distance {
+        return None;
+    }
+    let riding = distance / config.speed + climb * config.climb_penalty;
+    let locking = if rack { 0.0 } else { config.no_rack_penalty };
+    Some(config.overhead + locking + riding * (1.0 + rain.clamp(0.0, 1.0) * config.rain_penalty))
+}
+
+/// Total height gained riding straight from `from` to `to`; descents don't
+/// give anything back.
+pub fn climb_along(terrain: &HeightMap, from: Vec2, to: Vec2, spacing: f32) -> f32 {
+    let steps = (from.distance(to) / spacing.max(1.0)).ceil().max(1.0) as usize;
+    let mut climb = 0.0;
+    let mut last = terrain.sample_world(from);
+    for i in 1..=steps {
+        let height = terrain.sample_world(from.lerp(to, i as f32 / steps as f32));
+        climb += (height - last).max(0.0);
+        last = height;
+    }
+    climb
+}
+
+/// What a stretch of road costs a cyclist riding it along (`forward`) or
+/// against its points: its length, marked up on roads busy with cars.
+/// Highways and the wrong way down one-way streets are closed to bikes.
+pub fn bike_edge_cost(edge: &RoadEdge, forward: bool) -> f32 {
+    if !edge.direction.allows(forward) {
+        return f32::INFINITY;
+    }
+    if edge.bike_lanes {
+        return edge.length;
+    }
+    let markup = match edge.road_type {
+        RoadType::Highway => return f32::INFINITY,
+        RoadType::Major => 2.0,
+        RoadType::Minor => 1.2,
+        RoadType::Alley => 1.3,
+    };
+    edge.length * markup
+}
+
+/// A rider on their way.
+#[derive(Component)]
+pub struct Cyclist {
+    /// Roads to ride, in order.
+    pub route: Vec<EdgeIndex>,
+    /// Index of the road being ridden.
+    pub leg: usize,
+    pub forward: bool,
+    pub progress: f32,
+    pub speed: f32,
+    /// Junction at the end of the current road.
+    pub next_node: NodeIndex,
+    /// Where the ride ends.
+    pub destination: Vec2,
+    /// Whether the bike is locked at a rack on arrival, rather than at home.
+    pub locks_up: bool,
+    /// Held at a red light.
+    pub waiting: bool,
+}
+
+/// Meshes and materials shared by every cyclist.
+struct CyclistAssets {
+    body: Handle<Mesh>,
+    wheel: Handle<Mesh>,
+    tyre: Handle<StandardMaterial>,
+    colours: Vec<Handle<StandardMaterial>>,
+}
+
+const BIKE_COLOURS: &[(f32, f32, f32)] = &[
+    (0.2, 0.2, 0.8),
+    (0.8, 0.2, 0.2),
+    (0.2, 0.6, 0.2),
+    (0.9, 0.7, 0.1),
+    (0.1, 0.1, 0.1),
+];
+
+const WHEEL_RADIUS: f32 = 0.34;
+
+/// Keep track of the weather and the city's bike racks.
+fn update_cycling_conditions(
+    weather: Option<Res<WeatherState>>,
+    new_racks: Query<(&BikeRack, &Transform), Added<BikeRack>>,
+    mut conditions: ResMut<CyclingConditions>,
+) {
+    let rain = weather.map_or(0.0, |weather| weather.rain_intensity());
+    if conditions.rain != rain {
+        conditions.rain = rain;
+    }
+    for (rack, transform) in &new_racks {
+        conditions.racks.push(RackSpace {
+            position: Vec2::new(transform.translation.x, transform.translation.z),
+            capacity: rack.capacity,
+            in_use: 0,
+        });
+    }
+}
+
+/// Renumber routes after roads are removed; riders on removed roads are despawned.
+fn cyclist_road_removal(
+    mut commands: Commands,
+    mut events: EventReader<RoadsRemoved>,
+    mut cyclists: Query<(Entity, &mut Cyclist)>,
+) {
+    for RoadsRemoved(removal) in events.read() {
+        for (entity, mut cyclist) in &mut cyclists {
+            let route: Option<Vec<EdgeIndex>> = cyclist.route.iter().map(|&e| removal.remap.edge(e)).collect();
+            match (route, removal.remap.node(cyclist.next_node)) {
+                (Some(route), Some(node)) => {
+                    cyclist.route = route;
+                    cyclist.next_node = node;
+                }
+                _ => commands.entity(entity).despawn_recursive(),
+            }
+        }
+    }
+}
+
+/// Junction cyclists may use nearest `position`, within `radius`.
+fn bike_node_near(road_graph: &RoadGraph, position: Vec2, radius: f32) -> Option<NodeIndex> {
+    road_graph.nearest_node_where(position, radius, |idx| {
+        road_graph
+            .edges_of_node(idx)
+            .any(|e| road_graph.edge_by_index(e).is_some_and(|edge| edge.road_type != RoadType::Highway))
+    })
+}
+
+/// Set a rider off for each trip made by bike, taking their bike from the
+/// rack they left it at.
+#[allow(clippy::too_many_arguments)]
+fn spawn_cyclists(
+    mut commands: Commands,
+    mut trips: EventReader<TripModeChosen>,
+    config: Res<CyclingConfig>,
+    road_graph: Res<RoadGraph>,
+    buildings: Query<(&Transform, &Building)>,
+    cyclists: Query<(), With<Cyclist>>,
+    mut conditions: ResMut<CyclingConditions>,
+    mut stats: ResMut<CyclistStats>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut local_rng: Local<Option<StdRng>>,
+    mut assets: Local<Option<CyclistAssets>>,
+) {
+    let mut riding = cyclists.iter().count();
+    for chosen in trips.read() {
+        if chosen.mode != TravelMode::Bike {
+            continue;
+        }
+        let (Ok((from, origin)), Ok((_, destination))) = (buildings.get(chosen.trip.from), buildings.get(chosen.trip.to)) else {
+            continue;
+        };
+        let from = Vec2::new(from.translation.x, from.translation.z);
+        if origin.building_type != BuildingArchetype::Residential {
+            conditions.unlock(from, config.rack_radius);
+        }
+        // Rides made while the roads are full of bikes go unseen
+        if riding >= config.max_cyclists {
+            continue;
+        }
+        let (Some(start), Some(end)) = (
+            bike_node_near(&road_graph, from, config.junction_search_radius),
+            bike_node_near(&road_graph, chosen.to, config.junction_search_radius),
+        ) else {
+            continue;
+        };
+        let Some(route) = road_graph.cheapest_path(start, end, bike_edge_cost).filter(|route| !route.is_empty()) else {
+            continue;
+        };
+        let Some((a, b)) = road_graph.edge_endpoints(route[0]) else {
+            continue;
+        };
+        let forward = a == start;
+
+        let rng = local_rng.get_or_insert_with(|| StdRng::seed_from_u64(config.seed));
+        let assets = assets.get_or_insert_with(|| CyclistAssets {
+            body: meshes.add(generate_vehicle_mesh(&VehicleMeshConfig {
+                length: 1.8,
+                width: 0.6,
+                height: 1.75,
+                shape: VehicleShape::Bicycle,
+            })),
+            wheel: meshes.add(generate_wheel_mesh(WHEEL_RADIUS, 0.05)),
+            tyre: materials.add(StandardMaterial {
+                base_color: Color::srgb(0.08, 0.08, 0.08),
+                perceptual_roughness: 0.9,
+                ..default()
+            }),
+            colours: BIKE_COLOURS
+                .iter()
+                .map(|&(r, g, b)| {
+                    materials.add(StandardMaterial {
+                        base_color: Color::srgb(r, g, b),
+                        perceptual_roughness: 0.6,
+                        ..default()
+                    })
+                })
+                .collect(),
+        });
+
+        let speed = config.speed + rng.gen_range(-config.speed_variance..config.speed_variance);
+        let colour = assets.colours[rng.gen_range(0..assets.colours.len())].clone();
+        commands
+            .spawn((
+                Mesh3d(assets.body.clone()),
+                MeshMaterial3d(colour),
+                Transform::default(),
+                Cyclist {
+                    route,
+                    leg: 0,
+                    forward,
+                    progress: if forward { 0.0 } else { 1.0 },
+                    speed,
+                    next_node: if forward { b } else { a },
+                    destination: chosen.to,
+                    locks_up: destination.building_type != BuildingArchetype::Residential,
+                    waiting: false,
+                },
+            ))
+            .with_children(|bike| {
+                for z in [-0.55, 0.55] {
+                    bike.spawn((
+                        Mesh3d(assets.wheel.clone()),
+                        MeshMaterial3d(assets.tyre.clone()),
+                        Transform::from_xyz(0.0, WHEEL_RADIUS, z)
+                            .with_rotation(Quat::from_rotation_y(std::f32::consts::FRAC_PI_2)),
+                    ));
+                }
+            });
+        riding += 1;
+        stats.trips += 1;
+    }
+}
+
+/// Ride along the route: slower uphill, held at red lights, and locked up
+/// at the end.
+#[allow(clippy::too_many_arguments)]
+fn cyclist_movement(
+    mut commands: Commands,
+    time: Res<Time>,
+    config: Res<CyclingConfig>,
+    road_graph: Res<RoadGraph>,
+    terrain: Res<HeightMap>,
+    traffic_lights: Query<&TrafficLightController>,
+    mut conditions: ResMut<CyclingConditions>,
+    mut stats: ResMut<CyclistStats>,
+    mut cyclists: Query<(Entity, &mut Cyclist)>,
+) {
+    let dt = time.delta_secs();
+    let controllers: HashMap<NodeIndex, &TrafficLightController> =
+        traffic_lights.iter().map(|controller| (controller.node_index, controller)).collect();
+
+    for (entity, mut cyclist) in &mut cyclists {
+        let Some(edge) = cyclist.route.get(cyclist.leg).and_then(|&e| road_graph.edge_by_index(e)) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+        if edge.length <= 0.0 {
+            cyclist.progress = if cyclist.forward { 1.0 } else { 0.0 };
+        }
+
+        // Stop short of the junction while the light is against us
+        let remaining = if cyclist.forward { 1.0 - cyclist.progress } else { cyclist.progress } * edge.length;
+        let last_leg = cyclist.leg + 1 == cyclist.route.len();
+        cyclist.waiting = remaining < 3.0
+            && !last_leg
+            && controllers
+                .get(&cyclist.next_node)
+                .is_some_and(|controller| controller.phase_for(cyclist.route[cyclist.leg]) == LightPhase::Red);
+        if cyclist.waiting {
+            continue;
+        }
+
+        // Hills slow the ride up and speed it down
+        let (position, mut dir) = interpolate_edge_position(&edge.points, cyclist.progress);
+        if !cyclist.forward {
+            dir = -dir;
+        }
+        let grade = (terrain.sample_world(position + dir * 2.0) - terrain.sample_world(position)) / 2.0;
+        let speed = cyclist.speed * (1.0 - grade * config.grade_slowdown).clamp(0.4, 1.5);
+        let step = speed * dt / edge.length.max(0.01);
+        cyclist.progress += if cyclist.forward { step } else { -step };
+
+        let at_end = (cyclist.forward && cyclist.progress >= 1.0) || (!cyclist.forward && cyclist.progress <= 0.0);
+        if !at_end {
+            continue;
+        }
+        if last_leg {
+            if cyclist.locks_up {
+                conditions.lock_up(cyclist.destination, config.rack_radius);
+            }
+            commands.entity(entity).despawn_recursive();
+            stats.arrived += 1;
+            continue;
+        }
+
+        let node = cyclist.next_node;
+        cyclist.leg += 1;
+        let Some((a, b)) = road_graph.edge_endpoints(cyclist.route[cyclist.leg]) else {
+            continue;
+        };
+        cyclist.forward = a == node;
+        cyclist.progress = if cyclist.forward { 0.0 } else { 1.0 };
+        cyclist.next_node = if cyclist.forward { b } else { a };
+    }
+}
+
+/// Place riders along the kerb on the right of the road.
+fn cyclist_transform_sync(
+    config: Res<CyclingConfig>,
+    road_config: Res<RoadMeshConfig>,
+    road_graph: Res<RoadGraph>,
+    terrain: Res<HeightMap>,
+    mut cyclists: Query<(&Cyclist, &mut Transform)>,
+) {
+    for (cyclist, mut transform) in &mut cyclists {
+        let Some(edge) = cyclist.route.get(cyclist.leg).and_then(|&e| road_graph.edge_by_index(e)) else {
+            continue;
+        };
+        let progress = cyclist.progress.clamp(0.0, 1.0);
+        let (center, mut dir) = interpolate_edge_position(&edge.points, progress);
+        if !cyclist.forward {
+            dir = -dir;
+        }
+        // Positive offset is the right of the direction of travel
+        let perp = Vec2::new(-dir.y, dir.x);
+        let offset = (road_config.width(edge.road_type) / 2.0 - config.kerb_gap).max(0.0);
+        let pos = center + perp * offset;
+
+        transform.translation = Vec3::new(pos.x, terrain.sample_world(pos) + edge.deck_lift(progress) + 0.12, pos.y);
+        if dir.length_squared() > 0.001 {
+            transform.rotation = Quat::from_rotation_y((-dir.x).atan2(-dir.y));
+        }
+    }
+}
+
+/// Count riders and racked bikes.
+fn update_cyclist_stats(
+    conditions: Res<CyclingConditions>,
+    cyclists: Query<(), With<Cyclist>>,
+    mut stats: ResMut<CyclistStats>,
+) {
+    let riding = cyclists.iter().count();
+    let racked = conditions.racks.iter().map(|rack| rack.in_use).sum();
+    let rack_capacity = conditions.racks.iter().map(|rack| rack.capacity).sum();
+    if stats.riding != riding || stats.racked != racked || stats.rack_capacity != rack_capacity {
+        stats.riding = riding;
+        stats.racked = racked;
+        stats.rack_capacity = rack_capacity;
+    }
+}
+
+/// Interpolate position and direction along edge waypoints.
+fn interpolate_edge_position(points: &[Vec2], progress: f32) -> (Vec2, Vec2) {
+    if points.len() < 2 {
+        return (points.first().copied().unwrap_or(Vec2::ZERO), Vec2::X);
+    }
+    let total_length: f32 = points.windows(2).map(|w| w[0].distance(w[1])).sum();
+    let target_dist = progress.clamp(0.0, 1.0) * total_length;
+    let mut accumulated = 0.0;
+    for window in points.windows(2) {
+        let seg_len = window[0].distance(window[1]);
+        if seg_len > 0.0 && accumulated + seg_len >= target_dist {
+            let pos = window[0].lerp(window[1], (target_dist - accumulated) / seg_len);
+            return (pos, (window[1] - window[0]) / seg_len);
+        }
+        accumulated += seg_len;
+    }
+    let n = points.len();
+    (points[n - 1], (points[n - 1] - points[n - 2]).normalize_or_zero())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::procgen::roads::{test_road, RoadNodeType, TravelDirection};
+
+    #[test]
+    fn hills_rain_and_missing_racks_slow_the_ride() {
+        let config = CyclingConfig::default();
+        let dry = bike_time(900.0, 0.0, 0.0, true, &config).unwrap();
+        // 60 s to unlock and lock, 200 s riding
+        assert!((dry - 260.0).abs() < 0.01);
+        assert!(bike_time(900.0, 10.0, 0.0, true, &config).unwrap() > dry);
+        assert!(bike_time(900.0, 0.0, 1.0, true, &config).unwrap() > dry);
+        assert_eq!(bike_time(900.0, 0.0, 0.0, false, &config).unwrap(), dry + config.no_rack_penalty);
+        assert_eq!(bike_time(config.max_distance + 1.0, 0.0, 0.0, true, &config), None);
+    }
+
+    #[test]
+    fn climbs_count_only_the_way_up() {
+        // A ridge 20 high at x = 64, flat either side of it
+        let mut heights = HeightMap::generate(32, 32, 0, 0.0);
+        heights.origin = Vec2::ZERO;
+        heights.cell_size = 4.0;
+        for y in 0..32 {
+            for x in 0..32 {
+                heights.set(x, y, (20.0 - (x as f32 * 4.0 - 64.0).abs() / 2.0).max(0.0));
+            }
+        }
+        let over = climb_along(&heights, Vec2::new(0.0, 60.0), Vec2::new(120.0, 60.0), 4.0);
+        assert!((over - 20.0).abs() < 0.5, "climbed {over}");
+        let back = climb_along(&heights, Vec2::new(120.0, 60.0), Vec2::new(0.0, 60.0), 4.0);
+        assert!((back - over).abs() < 0.5);
+        assert_eq!(climb_along(&heights, Vec2::new(0.0, 0.0), Vec2::new(0.0, 120.0), 4.0), 0.0);
+    }
+
+    #[test]
+    fn routes_prefer_quiet_streets_and_avoid_highways() {
+        // A major road straight from A to B, a longer minor detour via C,
+        // and a highway shortcut that bikes may not use
+        let mut graph = RoadGraph::default();
+        let [a, b, c] = [Vec2::new(0.0, 0.0), Vec2::new(200.0, 0.0), Vec2::new(100.0, 60.0)]
+            .map(|p| graph.add_node(p, RoadNodeType::Intersection));
+        let major = test_road(a, b, RoadType::Major, &mut graph);
+        let detour = [test_road(a, c, RoadType::Minor, &mut graph), test_road(c, b, RoadType::Minor, &mut graph)];
+        assert_eq!(graph.cheapest_path(a, b, bike_edge_cost), Some(detour.to_vec()));
+
+        // Bike lanes make the major road the way to go
+        graph.edge_mut(major).unwrap().bike_lanes = true;
+        assert_eq!(graph.cheapest_path(a, b, bike_edge_cost), Some(vec![major]));
+        // Unless it is one-way the other way
+        graph.edge_mut(major).unwrap().direction = TravelDirection::Backward;
+        assert_eq!(graph.cheapest_path(a, b, bike_edge_cost), Some(detour.to_vec()));
+        assert_eq!(graph.cheapest_path(b, a, bike_edge_cost), Some(vec![major]));
+
+        let mut highway_only = RoadGraph::default();
+        let [d, e] = [Vec2::ZERO, Vec2::new(100.0, 0.0)].map(|p| highway_only.add_node(p, RoadNodeType::Intersection));
+        test_road(d, e, RoadType::Highway, &mut highway_only);
+        assert_eq!(highway_only.cheapest_path(d, e, bike_edge_cost), None);
+    }
+
+    #[test]
+    fn riders_lock_up_where_there_is_room() {
+        let mut conditions = CyclingConditions {
+            rain: 0.0,
+            racks: vec![RackSpace {
+                position: Vec2::new(10.0, 0.0),
+                capacity: 1,
+                in_use: 0,
+            }],
+        };
+        assert_eq!(conditions.free_rack(Vec2::ZERO, 50.0), Some(0));
+        assert!(conditions.lock_up(Vec2::ZERO, 50.0));
+        assert!(!conditions.lock_up(Vec2::ZERO, 50.0));
+        assert_eq!(conditions.free_rack(Vec2::ZERO, 50.0), None);
+        conditions.unlock(Vec2::ZERO, 50.0);
+        assert_eq!(conditions.free_rack(Vec2::ZERO, 5.0), None);
+        assert_eq!(conditions.free_rack(Vec2::ZERO, 50.0), Some(0));
+    }
+}
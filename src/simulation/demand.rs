@@ -4,6 +4,10 @@
 //! - Population vs housing capacity (R demand)
 //! - Population vs jobs (C/I demand)
 //! - Zone balance
+//! - Parking around shops (C demand falls when it is scarce)
+//! - Commuters from the region (they fill jobs without needing homes, and shop)
+//! - Visitors staying in hotels or out for the day (C demand)
+//! - Utilities (buildings without power, water or sewage house and employ nobody)
 
 use bevy::prelude::*;
 
@@ -11,6 +15,9 @@ use crate::game_state::GameState;
 use crate::tools::zone_paint::ZoneCell;
 use crate::tools::ZoneType;
 
+use super::parking::{ParkingConfig, ParkingStats};
+use super::utilities::UtilityService;
+
 pub struct DemandPlugin;
 
 impl Plugin for DemandPlugin {
@@ -66,6 +73,16 @@ pub struct CityStats {
     pub developed_residential: u32,
     pub developed_commercial: u32,
     pub developed_industrial: u32,
+    /// Commuters from the region working in the city.
+    pub inbound_commuters: u32,
+    /// Residents working out in the region.
+    pub outbound_commuters: u32,
+    /// Visitors in the city: hotel guests and day trippers.
+    pub visitors: u32,
+    /// Housing lost in homes cut off from power, water or sewage.
+    pub unserviced_housing: u32,
+    /// Buildings cut off from power, water or sewage.
+    pub unserviced_buildings: u32,
 }
 
 impl CityStats {
@@ -81,18 +98,23 @@ impl CityStats {
         if working_pop == 0 {
             return 1.0;
         }
-        (self.total_jobs() as f32 / working_pop as f32).min(1.0)
+        ((self.total_jobs() + self.outbound_commuters) as f32 / working_pop as f32).min(1.0)
     }
 }
 
 fn update_city_stats(
     mut stats: ResMut<CityStats>,
     zone_cells: Query<&ZoneCell>,
-    buildings: Query<&crate::render::building_spawner::Building>,
+    buildings: Query<(&crate::render::building_spawner::Building, Option<&UtilityService>)>,
     population: Res<super::population::Population>,
+    region: Res<super::region::Region>,
+    tourism: Res<super::tourism::Tourism>,
 ) {
     // Update population from Population resource
     stats.population = population.total;
+    stats.inbound_commuters = region.commuters_in;
+    stats.outbound_commuters = region.commuters_out;
+    stats.visitors = tourism.guests + tourism.day_trippers;
     // Count zones
     let mut res_zones = 0u32;
     let mut com_zones = 0u32;
@@ -129,8 +151,18 @@ fn update_city_stats(
     let mut housing = 0u32;
     let mut com_jobs = 0u32;
     let mut ind_jobs = 0u32;
+    let mut unserviced_housing = 0u32;
+    let mut unserviced_buildings = 0u32;
 
-    for building in &buildings {
+    for (building, utilities) in &buildings {
+        // Nobody lives or works in a building cut off from its utilities
+        if utilities.is_some_and(|u| !u.is_served()) {
+            unserviced_buildings += 1;
+            if building.building_type == crate::procgen::building_factory::BuildingArchetype::Residential {
+                unserviced_housing += 20;
+            }
+            continue;
+        }
         match building.building_type {
             crate::procgen::building_factory::BuildingArchetype::Residential => {
                 // Each residential building provides housing for ~10-50 people
@@ -156,9 +188,16 @@ fn update_city_stats(
     stats.housing_capacity = housing;
     stats.commercial_jobs = com_jobs;
     stats.industrial_jobs = ind_jobs;
+    stats.unserviced_housing = unserviced_housing;
+    stats.unserviced_buildings = unserviced_buildings;
 }
 
-fn calculate_demand(stats: Res<CityStats>, mut demand: ResMut<RCIDemand>) {
+fn calculate_demand(
+    stats: Res<CityStats>,
+    parking: Res<ParkingStats>,
+    parking_config: Res<ParkingConfig>,
+    mut demand: ResMut<RCIDemand>,
+) {
     // Base demand starts neutral
     let mut r_demand = 0.0f32;
     let mut c_demand = 0.0f32;
@@ -173,8 +212,9 @@ fn calculate_demand(stats: Res<CityStats>, mut demand: ResMut<RCIDemand>) {
     }
 
     // Residential demand based on jobs vs housing
-    // More jobs than housing = people want to move in
-    let total_jobs = stats.total_jobs();
+    // More jobs than housing = people want to move in; jobs filled by
+    // commuters from the region need no homes
+    let total_jobs = stats.total_jobs().saturating_sub(stats.inbound_commuters);
     if stats.housing_capacity > 0 {
         let job_housing_ratio = total_jobs as f32 / stats.housing_capacity as f32;
         r_demand = (job_housing_ratio - 0.8).clamp(-1.0, 1.0);
@@ -186,9 +226,12 @@ fn calculate_demand(stats: Res<CityStats>, mut demand: ResMut<RCIDemand>) {
 
     // Commercial demand based on population
     // More population = more commercial demand
-    if stats.population > 0 {
+    // Commuters from the region shop near work too, at half the rate, and
+    // visitors shop like residents
+    let shoppers = stats.population + stats.inbound_commuters / 2 + stats.visitors;
+    if shoppers > 0 {
         let pop_per_commercial = if stats.commercial_jobs > 0 {
-            stats.population as f32 / stats.commercial_jobs as f32
+            shoppers as f32 / stats.commercial_jobs as f32
         } else {
             100.0
         };
@@ -239,6 +282,9 @@ fn calculate_demand(stats: Res<CityStats>, mut demand: ResMut<RCIDemand>) {
         i_demand = i_demand.max(0.1);
     }
 
+    // Shoppers go elsewhere when they cannot park
+    c_demand = (c_demand - parking.commercial_scarcity * parking_config.commercial_demand_penalty).clamp(-1.0, 1.0);
+
     demand.residential = r_demand;
     demand.commercial = c_demand;
     demand.industrial = i_demand;
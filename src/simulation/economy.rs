@@ -34,6 +34,16 @@ pub struct EconomyConfig {
     pub road_maintenance: f32,
     /// Cost per service building.
     pub service_cost: f32,
+    /// Running cost per bus in service (per tick).
+    pub bus_operating_cost: f32,
+    /// Fare paid by each bus or metro rider.
+    pub bus_fare: f32,
+    /// Running cost per metro train in service (per tick).
+    pub train_operating_cost: f32,
+    /// Share of its tax a sold-out shop fails to earn.
+    pub stockout_revenue_loss: f32,
+    /// Share of visitors' spending on rooms and in shops taken in tax.
+    pub tourism_tax_rate: f32,
     /// How often to process budget (in seconds).
     pub budget_tick_interval: f32,
 }
@@ -47,6 +57,11 @@ impl Default for EconomyConfig {
             industrial_tax_rate: 20.0,
             road_maintenance: 1.0,
             service_cost: 50.0,
+            bus_operating_cost: 6.0,
+            bus_fare: 2.0,
+            train_operating_cost: 20.0,
+            stockout_revenue_loss: 0.6,
+            tourism_tax_rate: 0.2,
             budget_tick_interval: 1.0, // Every second
         }
     }
@@ -85,11 +100,19 @@ pub struct IncomeBreakdown {
     pub residential_tax: i64,
     pub commercial_tax: i64,
     pub industrial_tax: i64,
+    pub transit_fares: i64,
+    pub exports: i64,
+    pub tourism: i64,
 }
 
 impl IncomeBreakdown {
     pub fn total(&self) -> i64 {
-        self.residential_tax + self.commercial_tax + self.industrial_tax
+        self.residential_tax
+            + self.commercial_tax
+            + self.industrial_tax
+            + self.transit_fares
+            + self.exports
+            + self.tourism
     }
 }
 
@@ -98,12 +121,14 @@ impl IncomeBreakdown {
 pub struct ExpenseBreakdown {
     pub road_maintenance: i64,
     pub service_costs: i64,
+    pub transit_operations: i64,
+    pub imports: i64,
     pub other: i64,
 }
 
 impl ExpenseBreakdown {
     pub fn total(&self) -> i64 {
-        self.road_maintenance + self.service_costs + self.other
+        self.road_maintenance + self.service_costs + self.transit_operations + self.imports + self.other
     }
 }
 
@@ -123,7 +148,10 @@ fn calculate_income(
     config: Res<EconomyConfig>,
     mut budget: ResMut<CityBudget>,
     time: Res<Time>,
-    buildings: Query<&crate::render::building_spawner::Building>,
+    buildings: Query<(&crate::render::building_spawner::Building, Option<&crate::simulation::freight::Goods>)>,
+    mut ledger: ResMut<crate::simulation::bus_routes::TransitLedger>,
+    mut freight: ResMut<crate::simulation::freight::FreightLedger>,
+    mut tourism: ResMut<crate::simulation::tourism::TourismLedger>,
 ) {
     budget.tick_timer += time.delta_secs();
 
@@ -135,13 +163,15 @@ fn calculate_income(
     let mut commercial = 0i64;
     let mut industrial = 0i64;
 
-    for building in &buildings {
+    for (building, goods) in &buildings {
         match building.building_type {
             crate::procgen::building_factory::BuildingArchetype::Residential => {
                 residential += config.residential_tax_rate as i64;
             }
             crate::procgen::building_factory::BuildingArchetype::Commercial => {
-                commercial += config.commercial_tax_rate as i64;
+                // Shops with nothing on the shelves make fewer sales
+                let loss = if goods.is_some_and(|g| g.sold_out()) { config.stockout_revenue_loss } else { 0.0 };
+                commercial += (config.commercial_tax_rate * (1.0 - loss)) as i64;
             }
             crate::procgen::building_factory::BuildingArchetype::Industrial => {
                 industrial += config.industrial_tax_rate as i64;
@@ -153,6 +183,9 @@ fn calculate_income(
         residential_tax: residential,
         commercial_tax: commercial,
         industrial_tax: industrial,
+        transit_fares: (std::mem::take(&mut ledger.boardings) as f32 * config.bus_fare) as i64,
+        exports: std::mem::take(&mut freight.exports) as i64,
+        tourism: (std::mem::take(&mut tourism.spending) * config.tourism_tax_rate) as i64,
     };
 }
 
@@ -160,6 +193,9 @@ fn calculate_expenses(
     config: Res<EconomyConfig>,
     mut budget: ResMut<CityBudget>,
     roads: Res<crate::procgen::roads::RoadGraph>,
+    buses: Query<(), With<crate::simulation::bus_routes::Bus>>,
+    metro_trains: Query<(), With<crate::simulation::rail_network::MetroTrain>>,
+    mut freight: ResMut<crate::simulation::freight::FreightLedger>,
 ) {
     if budget.tick_timer < config.budget_tick_interval {
         return;
@@ -171,6 +207,9 @@ fn calculate_expenses(
     budget.expenses = ExpenseBreakdown {
         road_maintenance,
         service_costs: 0, // TODO: Count service buildings
+        transit_operations: (buses.iter().count() as f32 * config.bus_operating_cost
+            + metro_trains.iter().count() as f32 * config.train_operating_cost) as i64,
+        imports: std::mem::take(&mut freight.imports) as i64,
         other: 0,
     };
 }
@@ -1,15 +1,21 @@
 //! Flow field (Dijkstra map) for agent navigation.
 //!
 //! All agents heading to the same destination share a single flow field.
-//! Flow fields are generated for key destinations (commercial districts,
-//! parks, transit hubs) and cached for reuse.
+//! Fields toward each kind of destination (shops, parks, workplaces, homes)
+//! are kept for the whole city, and fields toward single buildings are
+//! generated on demand and cached. Walking is cheap along streets, dear
+//! across open ground and impossible through water. Every field is rebuilt
+//! when buildings or roads change.
 
 use bevy::prelude::*;
 use std::cmp::Ordering;
 use std::collections::{BinaryHeap, HashMap};
 
 use crate::procgen::building_factory::BuildingArchetype;
-use crate::render::building_spawner::{Building, BuildingsSpawned};
+use crate::procgen::river::River;
+use crate::procgen::roads::{RoadGraph, RoadType};
+use crate::render::building_spawner::{Building, BuildingsSpawned, Park};
+use crate::tools::services::{ServiceBuilding, ServiceType};
 
 pub struct FlowFieldPlugin;
 
@@ -18,7 +24,8 @@ impl Plugin for FlowFieldPlugin {
         app.init_resource::<FlowFieldCache>()
             .init_resource::<FlowFieldConfig>()
             .init_resource::<CityFlowFields>()
-            .add_systems(Update, initialize_city_flow_fields.run_if(should_init_flow_fields));
+            .init_resource::<WalkCosts>()
+            .add_systems(Update, update_city_flow_fields.run_if(should_update_flow_fields));
     }
 }
 
@@ -31,18 +38,37 @@ pub struct FlowFieldConfig {
     pub grid_extent: f32,
     /// Maximum distance to consider in flow field.
     pub max_distance: f32,
+    /// Cost of crossing a cell away from any street, relative to a sidewalk.
+    pub off_street_cost: f32,
+    /// Fields toward single buildings kept at once.
+    pub max_cached_fields: usize,
 }
 
 impl Default for FlowFieldConfig {
     fn default() -> Self {
         Self {
             cell_size: 5.0,
-            grid_extent: 250.0, // -250 to +250 world units
+            grid_extent: 300.0, // -300 to +300 world units, the whole terrain
             max_distance: 500.0,
+            off_street_cost: 4.0,
+            max_cached_fields: 64,
         }
     }
 }
 
+/// Kinds of place pedestrians head for, each with a city-wide flow field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FlowDestination {
+    /// Commercial buildings.
+    Shops,
+    /// Parks, generated or player-placed.
+    Parks,
+    /// Commercial and industrial buildings.
+    Workplaces,
+    /// Residential buildings.
+    Homes,
+}
+
 /// Pre-computed flow fields for common destinations.
 #[derive(Resource, Default)]
 pub struct CityFlowFields {
@@ -50,56 +76,166 @@ pub struct CityFlowFields {
     pub to_commercial: Option<FlowField>,
     /// Flow field toward parks/leisure.
     pub to_parks: Option<FlowField>,
+    /// Flow field toward jobs (for riders walking from their stop to work).
+    pub to_workplaces: Option<FlowField>,
+    /// Flow field toward homes.
+    pub to_homes: Option<FlowField>,
     /// Whether flow fields have been generated.
     pub initialized: bool,
 }
 
-fn should_init_flow_fields(
+impl CityFlowFields {
+    /// The field toward the nearest destination of a kind, if there is one.
+    pub fn field(&self, destination: FlowDestination) -> Option<&FlowField> {
+        match destination {
+            FlowDestination::Shops => self.to_commercial.as_ref(),
+            FlowDestination::Parks => self.to_parks.as_ref(),
+            FlowDestination::Workplaces => self.to_workplaces.as_ref(),
+            FlowDestination::Homes => self.to_homes.as_ref(),
+        }
+    }
+}
+
+/// Cost of walking through each grid cell.
+#[derive(Resource, Default)]
+pub struct WalkCosts {
+    pub width: usize,
+    pub height: usize,
+    pub cell_size: f32,
+    pub origin: Vec2,
+    pub costs: Vec<f32>,
+}
+
+impl WalkCosts {
+    /// Sidewalks of major and minor streets cost 1 per cell, open ground
+    /// `off_street_cost`, and water is impassable unless a street bridges it.
+    pub fn build(config: &FlowFieldConfig, road_graph: &RoadGraph, river: &River) -> Self {
+        let size = (config.grid_extent * 2.0 / config.cell_size) as usize;
+        let origin = Vec2::splat(-config.grid_extent);
+        let mut costs = Vec::with_capacity(size * size);
+        for y in 0..size {
+            for x in 0..size {
+                let center = origin + (Vec2::new(x as f32, y as f32) + 0.5) * config.cell_size;
+                costs.push(if river.contains_point(center) { f32::INFINITY } else { config.off_street_cost });
+            }
+        }
+        let mut walk = Self {
+            width: size,
+            height: size,
+            cell_size: config.cell_size,
+            origin,
+            costs,
+        };
+
+        let step = config.cell_size * 0.5;
+        for edge in road_graph.edges() {
+            if !matches!(edge.road_type, RoadType::Major | RoadType::Minor) {
+                continue;
+            }
+            for window in edge.points.windows(2) {
+                let steps = (window[0].distance(window[1]) / step).ceil().max(1.0) as usize;
+                for i in 0..=steps {
+                    let point = window[0].lerp(window[1], i as f32 / steps as f32);
+                    if let Some((x, y)) = walk.cell_of(point) {
+                        walk.costs[y * size + x] = 1.0;
+                    }
+                }
+            }
+        }
+        walk
+    }
+
+    /// Grid cell containing a world position.
+    pub fn cell_of(&self, pos: Vec2) -> Option<(usize, usize)> {
+        world_to_grid(pos, self.origin, self.cell_size, self.width.min(self.height))
+    }
+
+    /// Cost of entering a cell.
+    pub fn cost(&self, x: usize, y: usize) -> f32 {
+        self.costs.get(y * self.width + x).copied().unwrap_or(f32::INFINITY)
+    }
+
+    /// Flow field toward the nearest of `goals`, or None if none lie on the grid.
+    pub fn flow_field(&self, goals: &[Vec2]) -> Option<FlowField> {
+        let cells: Vec<(usize, usize)> = goals.iter().filter_map(|&goal| self.cell_of(goal)).collect();
+        if cells.is_empty() {
+            return None;
+        }
+        let cost = |x, y| self.cost(x, y);
+        Some(generate_flow_field(self.width, self.height, self.cell_size, self.origin, &cells, Some(&cost)))
+    }
+}
+
+/// Filter for buildings and services placed since the fields were last checked.
+type NewBuildings = Or<(Added<Building>, Added<ServiceBuilding>)>;
+
+/// Run condition: rebuild the fields once buildings are placed, and again
+/// whenever buildings, parks or roads change.
+fn should_update_flow_fields(
     buildings_spawned: Res<BuildingsSpawned>,
     flow_fields: Res<CityFlowFields>,
+    road_graph: Res<RoadGraph>,
+    added_buildings: Query<(), NewBuildings>,
+    mut removed_buildings: RemovedComponents<Building>,
+    mut removed_services: RemovedComponents<ServiceBuilding>,
 ) -> bool {
-    buildings_spawned.0 && !flow_fields.initialized
+    let removed = removed_buildings.read().count() + removed_services.read().count() > 0;
+    buildings_spawned.0
+        && (!flow_fields.initialized || road_graph.is_changed() || !added_buildings.is_empty() || removed)
 }
 
-/// Generate city-wide flow fields once buildings are placed.
-fn initialize_city_flow_fields(
+/// Generate the city-wide flow fields and drop the per-building ones.
+#[allow(clippy::too_many_arguments)]
+fn update_city_flow_fields(
     config: Res<FlowFieldConfig>,
+    road_graph: Res<RoadGraph>,
+    river: Res<River>,
     buildings: Query<(&Building, &Transform)>,
+    parks: Query<&Transform, With<Park>>,
+    services: Query<(&ServiceBuilding, &Transform)>,
+    mut walk_costs: ResMut<WalkCosts>,
     mut flow_fields: ResMut<CityFlowFields>,
+    mut cache: ResMut<FlowFieldCache>,
 ) {
+    let first = !flow_fields.initialized;
     flow_fields.initialized = true;
-
-    let grid_size = (config.grid_extent * 2.0 / config.cell_size) as usize;
-    let origin = Vec2::new(-config.grid_extent, -config.grid_extent);
-
-    // Collect commercial building positions as goals
-    let commercial_goals: Vec<(usize, usize)> = buildings
+    *walk_costs = WalkCosts::build(&config, &road_graph, &river);
+    cache.fields.clear();
+
+    let goals = |archetypes: &[BuildingArchetype]| -> Vec<Vec2> {
+        buildings
+            .iter()
+            .filter(|(b, _)| archetypes.contains(&b.building_type))
+            .map(|(_, t)| Vec2::new(t.translation.x, t.translation.z))
+            .collect()
+    };
+    let commercial_goals = goals(&[BuildingArchetype::Commercial]);
+    let park_goals: Vec<Vec2> = parks
         .iter()
-        .filter(|(b, _)| b.building_type == BuildingArchetype::Commercial)
-        .filter_map(|(_, t)| {
-            let pos = Vec2::new(t.translation.x, t.translation.z);
-            world_to_grid(pos, origin, config.cell_size, grid_size)
-        })
+        .chain(
+            services
+                .iter()
+                .filter(|(s, _)| s.service_type == ServiceType::Park)
+                .map(|(_, t)| t),
+        )
+        .map(|t| Vec2::new(t.translation.x, t.translation.z))
         .collect();
 
-    if !commercial_goals.is_empty() {
-        flow_fields.to_commercial = Some(generate_flow_field(
-            grid_size,
-            grid_size,
-            config.cell_size,
-            origin,
-            &commercial_goals,
-            None,
-        ));
+    flow_fields.to_commercial = walk_costs.flow_field(&commercial_goals);
+    flow_fields.to_parks = walk_costs.flow_field(&park_goals);
+    flow_fields.to_workplaces =
+        walk_costs.flow_field(&goals(&[BuildingArchetype::Commercial, BuildingArchetype::Industrial]));
+    flow_fields.to_homes = walk_costs.flow_field(&goals(&[BuildingArchetype::Residential]));
+
+    if first {
         info!(
-            "Generated flow field to {} commercial locations ({}x{} grid)",
+            "Generated flow fields to {} commercial locations and {} parks ({}x{} grid)",
             commercial_goals.len(),
-            grid_size,
-            grid_size
+            park_goals.len(),
+            walk_costs.width,
+            walk_costs.height
         );
     }
-
-    // Could add park flow fields here if parks have markers
 }
 
 fn world_to_grid(pos: Vec2, origin: Vec2, cell_size: f32, grid_size: usize) -> Option<(usize, usize)> {
@@ -170,6 +306,14 @@ impl FlowField {
         self.directions[self.index(gx, gy)]
     }
 
+    /// Cost to the destination from a world position; None off the grid or
+    /// where the destination can't be reached.
+    pub fn distance_at(&self, world_pos: Vec2) -> Option<f32> {
+        let (gx, gy) = self.world_to_grid(world_pos)?;
+        let distance = self.distances[self.index(gx, gy)];
+        (distance < f32::MAX).then_some(distance)
+    }
+
     /// Sample with bilinear interpolation.
     pub fn sample_smooth(&self, world_pos: Vec2) -> Vec2 {
         let local = world_pos - self.origin;
@@ -231,7 +375,8 @@ impl PartialOrd for DijkstraNode {
 }
 
 /// Cost function for movement (can incorporate terrain, roads, etc.).
-pub type CostFn = fn(usize, usize) -> f32;
+/// Infinite cost makes a cell impassable.
+pub type CostFn<'a> = &'a dyn Fn(usize, usize) -> f32;
 
 /// Generate a flow field using Dijkstra's algorithm.
 pub fn generate_flow_field(
@@ -256,8 +401,8 @@ pub fn generate_flow_field(
         });
     }
 
-    let default_cost: CostFn = |_, _| 1.0;
-    let cost = cost_fn.unwrap_or(default_cost);
+    let default_cost = |_: usize, _: usize| 1.0;
+    let cost = cost_fn.unwrap_or(&default_cost);
 
     // Dijkstra expansion.
     while let Some(current) = heap.pop() {
@@ -364,3 +509,63 @@ pub fn generate_flow_field(
 pub struct FlowFieldCache {
     pub fields: HashMap<Entity, FlowField>,
 }
+
+impl FlowFieldCache {
+    /// Flow field toward one building, generated on first use. When the
+    /// cache is full an arbitrary field is dropped to make room.
+    pub fn field_to(
+        &mut self,
+        building: Entity,
+        position: Vec2,
+        costs: &WalkCosts,
+        config: &FlowFieldConfig,
+    ) -> Option<&FlowField> {
+        if !self.fields.contains_key(&building) {
+            let field = costs.flow_field(&[position])?;
+            if self.fields.len() >= config.max_cached_fields {
+                if let Some(&evicted) = self.fields.keys().next() {
+                    self.fields.remove(&evicted);
+                }
+            }
+            self.fields.insert(building, field);
+        }
+        self.fields.get(&building)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn impassable_cells_are_walked_around() {
+        // A wall across the middle of a 5x5 grid with a gap at the top
+        let wall = |x: usize, y: usize| if x == 2 && y < 4 { f32::INFINITY } else { 1.0 };
+        let field = generate_flow_field(5, 5, 1.0, Vec2::ZERO, &[(4, 0)], Some(&wall));
+
+        assert!(field.distance_at(Vec2::new(2.5, 1.5)).is_none());
+        // Straight across would be 4 steps; round through the gap takes longer
+        let around = field.distance_at(Vec2::new(0.5, 0.5)).unwrap();
+        assert!(around > 6.0);
+        // Heading for the gap, not the wall
+        assert!(field.sample(Vec2::new(0.5, 0.5)).y > 0.0);
+    }
+
+    #[test]
+    fn building_fields_are_cached_up_to_the_limit() {
+        let config = FlowFieldConfig {
+            max_cached_fields: 2,
+            ..default()
+        };
+        let costs = WalkCosts::build(&config, &RoadGraph::default(), &River::default());
+        let mut cache = FlowFieldCache::default();
+        for i in 0..3 {
+            let field = cache.field_to(Entity::from_raw(i), Vec2::new(i as f32 * 10.0, 0.0), &costs, &config);
+            assert_eq!(field.and_then(|f| f.distance_at(Vec2::new(i as f32 * 10.0, 0.0))), Some(0.0));
+        }
+        assert_eq!(cache.fields.len(), 2);
+        assert!(cache.fields.contains_key(&Entity::from_raw(2)));
+        // Goals off the grid have no field
+        assert!(cache.field_to(Entity::from_raw(9), Vec2::splat(1000.0), &costs, &config).is_none());
+    }
+}
@@ -0,0 +1,569 @@
+//! Freight: goods from industry to commerce.
+//!
+//! Industrial buildings make goods and commercial buildings sell them. A shop
+//! running low orders a truckload from the nearest factory with one ready, or
+//! from outside the city through the nearest highway connection to the
+//! region when no factory has one; factories filling up ship their surplus
+//! out the same way. Every load
+//! travels by delivery truck over the road network, routed the way a truck
+//! driver would go: highways wherever they help, side streets only at the
+//! ends. Shops that sell out lose revenue, imports cost the city and exports
+//! earn it money, and the trucks add noise and exhaust to the roads they use.
+
+use bevy::prelude::*;
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+use crate::procgen::building_factory::BuildingArchetype;
+use crate::procgen::roads::{RoadEdge, RoadGraph, RoadType, RoadsRemoved};
+use crate::render::building_spawner::Building;
+use crate::render::vehicle_meshes::generate_vehicle_mesh;
+
+use super::region::{road_node_near, RegionConfig, RegionalConnection};
+use super::vehicle_traffic::{navigation_along, VehicleType};
+use super::vehicles::{MovingVehicle, PlannedRoute, VehicleNavigation};
+
+pub struct FreightPlugin;
+
+impl Plugin for FreightPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FreightConfig>()
+            .init_resource::<FreightLedger>()
+            .init_resource::<FreightStats>()
+            .init_resource::<FreightTraffic>()
+            .add_systems(
+                Update,
+                (
+                    stock_new_buildings,
+                    freight_road_removal,
+                    produce_and_consume_goods,
+                    dispatch_deliveries,
+                    complete_deliveries,
+                    update_freight_traffic,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Configuration for the goods economy.
+#[derive(Resource)]
+pub struct FreightConfig {
+    pub seed: u64,
+    /// Goods each industrial building makes per second.
+    pub production_rate: f32,
+    /// Goods each commercial building sells per second while it has stock.
+    pub consumption_rate: f32,
+    /// Goods an industrial building can store.
+    pub factory_capacity: f32,
+    /// Goods a commercial building can keep on its shelves.
+    pub shop_capacity: f32,
+    /// Goods in one truckload.
+    pub truck_load: f32,
+    /// Shops order when stock plus loads on the way falls below this.
+    pub reorder_level: f32,
+    /// Factories export a load once they hold this much.
+    pub export_level: f32,
+    /// Most delivery trucks on the road at once.
+    pub max_trucks: usize,
+    /// How often orders are placed (seconds).
+    pub dispatch_interval: f32,
+    /// Whether goods can be bought from and sold outside the city.
+    pub outside_trade: bool,
+    /// Price per unit of goods bought from outside.
+    pub import_price: f32,
+    /// Price per unit of goods sold outside, before the region's demand for them.
+    pub export_price: f32,
+    /// Farthest a building can be from the road its trucks use.
+    pub road_search_radius: f32,
+    /// Time over which trucks seen on a road are remembered (seconds).
+    pub traffic_memory: f32,
+    /// Trucks on a road on average that count as half its traffic.
+    pub busy_trucks: f32,
+}
+
+impl Default for FreightConfig {
+    fn default() -> Self {
+        Self {
+            seed: 13579,
+            production_rate: 0.4,
+            consumption_rate: 0.25,
+            factory_capacity: 60.0,
+            shop_capacity: 40.0,
+            truck_load: 20.0,
+            reorder_level: 15.0,
+            export_level: 50.0,
+            max_trucks: 20,
+            dispatch_interval: 2.0,
+            outside_trade: true,
+            import_price: 3.0,
+            export_price: 2.0,
+            road_search_radius: 150.0,
+            traffic_memory: 60.0,
+            busy_trucks: 0.5,
+        }
+    }
+}
+
+/// Goods held by a factory or shop.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Goods {
+    pub stock: f32,
+    pub capacity: f32,
+    /// Truckloads on their way here.
+    pub incoming: u32,
+}
+
+impl Goods {
+    /// Whether a shop should order another load.
+    pub fn needs_delivery(&self, load: f32, reorder_level: f32) -> bool {
+        self.stock + self.incoming as f32 * load < reorder_level
+    }
+
+    pub fn sold_out(&self) -> bool {
+        self.stock <= 0.0
+    }
+}
+
+/// One end of a delivery.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FreightStop {
+    Building(Entity),
+    /// Beyond the map edge, through a highway connection to the region.
+    Outside,
+}
+
+/// A load to move, as planned by dispatch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Shipment {
+    pub from: FreightStop,
+    pub to: FreightStop,
+}
+
+/// A truck carrying a load; its way there is a `PlannedRoute`.
+#[derive(Component)]
+pub struct Delivery {
+    pub to: FreightStop,
+    pub load: f32,
+}
+
+/// Trade since the last budget tick, for the city's accounts.
+#[derive(Resource, Default)]
+pub struct FreightLedger {
+    pub imports: f32,
+    pub exports: f32,
+}
+
+/// Goods economy figures.
+#[derive(Resource, Default)]
+pub struct FreightStats {
+    pub trucks: usize,
+    pub deliveries: u32,
+    /// Loads bought from and sold outside the city.
+    pub imported: u32,
+    pub exported: u32,
+    pub shops: usize,
+    pub shops_sold_out: usize,
+}
+
+/// Trucks seen on each road lately, for noise and pollution.
+#[derive(Resource, Default)]
+pub struct FreightTraffic {
+    /// Average trucks on each road over the last `traffic_memory` seconds.
+    pub per_edge: HashMap<EdgeIndex, f32>,
+}
+
+impl FreightTraffic {
+    /// Share of a road's traffic made up of trucks, from 0 to 1.
+    pub fn truck_share(&self, edge: EdgeIndex, config: &FreightConfig) -> f32 {
+        let trucks = self.per_edge.get(&edge).copied().unwrap_or(0.0);
+        trucks / (trucks + config.busy_trucks)
+    }
+}
+
+/// What a stretch of road costs a truck driven along (`forward`) or against
+/// its points: its length, marked up on roads built for lighter traffic.
+/// One-way roads are closed the wrong way.
+pub fn truck_edge_cost(edge: &RoadEdge, forward: bool) -> f32 {
+    if !edge.direction.allows(forward) {
+        return f32::INFINITY;
+    }
+    let markup = match edge.road_type {
+        RoadType::Highway => 1.0,
+        RoadType::Major => 1.4,
+        RoadType::Minor => 2.0,
+        RoadType::Alley => 3.0,
+    };
+    edge.length * markup
+}
+
+/// Decide which loads move: shops needing stock are served by the nearest
+/// factory with a load ready, or from outside when none has; factories
+/// left with a surplus export it. `factories` holds each factory's stock
+/// and is drawn down as loads are assigned.
+pub fn plan_shipments(
+    shops: &[(Entity, Vec2)],
+    factories: &mut [(Entity, Vec2, f32)],
+    load: f32,
+    export_level: f32,
+    outside: bool,
+) -> Vec<Shipment> {
+    let mut shipments = Vec::new();
+    for &(shop, position) in shops {
+        let nearest = factories
+            .iter_mut()
+            .filter(|(_, _, stock)| *stock >= load)
+            .min_by(|a, b| a.1.distance(position).total_cmp(&b.1.distance(position)));
+        let from = match nearest {
+            Some((factory, _, stock)) => {
+                *stock -= load;
+                FreightStop::Building(*factory)
+            }
+            None if outside => FreightStop::Outside,
+            None => continue,
+        };
+        shipments.push(Shipment {
+            from,
+            to: FreightStop::Building(shop),
+        });
+    }
+    if outside {
+        for (factory, _, stock) in factories.iter_mut() {
+            if *stock >= export_level {
+                *stock -= load;
+                shipments.push(Shipment {
+                    from: FreightStop::Building(*factory),
+                    to: FreightStop::Outside,
+                });
+            }
+        }
+    }
+    shipments
+}
+
+/// Give new factories and shops their stores of goods.
+fn stock_new_buildings(
+    mut commands: Commands,
+    config: Res<FreightConfig>,
+    buildings: Query<(Entity, &Building), Added<Building>>,
+) {
+    for (entity, building) in &buildings {
+        let capacity = match building.building_type {
+            BuildingArchetype::Industrial => config.factory_capacity,
+            BuildingArchetype::Commercial => config.shop_capacity,
+            BuildingArchetype::Residential => continue,
+        };
+        // Shops open half stocked; factories start empty
+        let stock = if building.building_type == BuildingArchetype::Commercial {
+            capacity / 2.0
+        } else {
+            0.0
+        };
+        commands.entity(entity).insert(Goods {
+            stock,
+            capacity,
+            incoming: 0,
+        });
+    }
+}
+
+/// Renumber the roads trucks have been using after roads are removed.
+fn freight_road_removal(mut events: EventReader<RoadsRemoved>, mut traffic: ResMut<FreightTraffic>) {
+    for RoadsRemoved(removal) in events.read() {
+        traffic.per_edge = traffic
+            .per_edge
+            .drain()
+            .filter_map(|(edge, trucks)| removal.remap.edge(edge).map(|edge| (edge, trucks)))
+            .collect();
+    }
+}
+
+/// Factories make goods and shops sell them.
+fn produce_and_consume_goods(
+    time: Res<Time>,
+    config: Res<FreightConfig>,
+    mut buildings: Query<(&Building, &mut Goods)>,
+) {
+    let dt = time.delta_secs();
+    for (building, mut goods) in &mut buildings {
+        match building.building_type {
+            BuildingArchetype::Industrial => {
+                goods.stock = (goods.stock + config.production_rate * dt).min(goods.capacity);
+            }
+            BuildingArchetype::Commercial => {
+                goods.stock = (goods.stock - config.consumption_rate * dt).max(0.0);
+            }
+            BuildingArchetype::Residential => {}
+        }
+    }
+}
+
+/// Meshes and materials shared by every delivery truck.
+struct TruckAssets {
+    body: Handle<Mesh>,
+    liveries: Vec<Handle<StandardMaterial>>,
+}
+
+const TRUCK_LIVERIES: &[(f32, f32, f32)] = &[
+    (0.85, 0.85, 0.82), // White box
+    (0.75, 0.35, 0.1),  // Orange haulier
+    (0.15, 0.3, 0.55),  // Blue haulier
+    (0.3, 0.3, 0.32),   // Grey
+];
+
+/// Place orders and send trucks out with them.
+#[allow(clippy::too_many_arguments)]
+fn dispatch_deliveries(
+    mut commands: Commands,
+    time: Res<Time>,
+    config: Res<FreightConfig>,
+    road_graph: Res<RoadGraph>,
+    region: Res<RegionConfig>,
+    connections: Query<&RegionalConnection>,
+    mut ledger: ResMut<FreightLedger>,
+    mut stats: ResMut<FreightStats>,
+    mut buildings: Query<(Entity, &Building, &Transform, &mut Goods)>,
+    deliveries: Query<&Delivery>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut timer: Local<f32>,
+    mut local_rng: Local<Option<StdRng>>,
+    mut assets: Local<Option<TruckAssets>>,
+) {
+    *timer += time.delta_secs();
+    if *timer < config.dispatch_interval {
+        return;
+    }
+    *timer = 0.0;
+
+    // Count the loads already on their way to each building
+    let mut incoming: HashMap<Entity, u32> = HashMap::new();
+    for delivery in &deliveries {
+        if let FreightStop::Building(to) = delivery.to {
+            *incoming.entry(to).or_default() += 1;
+        }
+    }
+    let mut shops = Vec::new();
+    let mut factories = Vec::new();
+    stats.shops = 0;
+    stats.shops_sold_out = 0;
+    for (entity, building, transform, mut goods) in &mut buildings {
+        let count = incoming.get(&entity).copied().unwrap_or(0);
+        if goods.incoming != count {
+            goods.incoming = count;
+        }
+        let position = Vec2::new(transform.translation.x, transform.translation.z);
+        match building.building_type {
+            BuildingArchetype::Commercial => {
+                stats.shops += 1;
+                stats.shops_sold_out += goods.sold_out() as usize;
+                if goods.needs_delivery(config.truck_load, config.reorder_level) {
+                    shops.push((entity, position));
+                }
+            }
+            BuildingArchetype::Industrial => factories.push((entity, position, goods.stock)),
+            BuildingArchetype::Residential => {}
+        }
+    }
+
+    let room = config.max_trucks.saturating_sub(deliveries.iter().count());
+    if room == 0 {
+        return;
+    }
+    let gateways: Vec<(NodeIndex, Vec2)> = connections
+        .iter()
+        .filter_map(|c| c.road_node)
+        .filter(|&node| road_graph.node_has_edges(node))
+        .filter_map(|node| road_graph.node_by_index(node).map(|n| (node, n.position)))
+        .collect();
+    let outside = config.outside_trade && region.trade_demand > 0.0 && !gateways.is_empty();
+    let shipments = plan_shipments(&shops, &mut factories, config.truck_load, config.export_level, outside);
+
+    let rng = local_rng.get_or_insert_with(|| StdRng::seed_from_u64(config.seed));
+    let assets = assets.get_or_insert_with(|| TruckAssets {
+        body: meshes.add(generate_vehicle_mesh(&VehicleType::Truck.mesh_config())),
+        liveries: TRUCK_LIVERIES
+            .iter()
+            .map(|&(r, g, b)| {
+                materials.add(StandardMaterial {
+                    base_color: Color::srgb(r, g, b),
+                    perceptual_roughness: 0.5,
+                    reflectance: 0.35,
+                    ..default()
+                })
+            })
+            .collect(),
+    });
+
+    let mut sent = 0;
+    for shipment in shipments {
+        if sent >= room {
+            break;
+        }
+        let position_of = |stop: FreightStop| match stop {
+            FreightStop::Building(entity) => buildings
+                .get(entity)
+                .ok()
+                .map(|(_, _, transform, _)| Vec2::new(transform.translation.x, transform.translation.z)),
+            FreightStop::Outside => None,
+        };
+        let (from_at, to_at) = (position_of(shipment.from), position_of(shipment.to));
+        let node_of = |at: Option<Vec2>, other: Option<Vec2>| match at {
+            Some(position) => road_node_near(&road_graph, position, config.road_search_radius),
+            // Outside trade goes through the highway connection nearest the other end
+            None => other.and_then(|p| {
+                gateways
+                    .iter()
+                    .min_by(|a, b| a.1.distance(p).total_cmp(&b.1.distance(p)))
+                    .map(|&(node, _)| node)
+            }),
+        };
+        let (Some(start), Some(end)) = (node_of(from_at, to_at), node_of(to_at, from_at)) else {
+            continue;
+        };
+        let Some(route) = road_graph.cheapest_path(start, end, truck_edge_cost).filter(|route| !route.is_empty()) else {
+            continue;
+        };
+        let speed = 12.0 * VehicleType::Truck.speed_multiplier();
+        let Some(nav) = navigation_along(&road_graph, route[0], start, route.get(1).copied(), speed) else {
+            continue;
+        };
+
+        // Load up, or buy the load in
+        match shipment.from {
+            FreightStop::Building(factory) => {
+                if let Ok((_, _, _, mut goods)) = buildings.get_mut(factory) {
+                    goods.stock = (goods.stock - config.truck_load).max(0.0);
+                }
+            }
+            FreightStop::Outside => {
+                ledger.imports += config.truck_load * config.import_price;
+                stats.imported += 1;
+            }
+        }
+        if let FreightStop::Building(shop) = shipment.to {
+            if let Ok((_, _, _, mut goods)) = buildings.get_mut(shop) {
+                goods.incoming += 1;
+            }
+        }
+
+        let livery = assets.liveries[rng.gen_range(0..assets.liveries.len())].clone();
+        commands.spawn((
+            Mesh3d(assets.body.clone()),
+            MeshMaterial3d(livery),
+            Transform::default(),
+            MovingVehicle,
+            VehicleType::Truck,
+            nav,
+            PlannedRoute::new(route, end, truck_edge_cost),
+            Delivery {
+                to: shipment.to,
+                load: config.truck_load,
+            },
+        ));
+        sent += 1;
+    }
+}
+
+/// Unload trucks at the end of their route: shops are restocked and
+/// exports are paid for.
+fn complete_deliveries(
+    mut commands: Commands,
+    config: Res<FreightConfig>,
+    region: Res<RegionConfig>,
+    trucks: Query<(Entity, &Delivery, &PlannedRoute)>,
+    mut goods: Query<&mut Goods>,
+    mut ledger: ResMut<FreightLedger>,
+    mut stats: ResMut<FreightStats>,
+) {
+    for (entity, delivery, route) in &trucks {
+        if !route.arrived {
+            continue;
+        }
+        match delivery.to {
+            FreightStop::Building(shop) => {
+                if let Ok(mut goods) = goods.get_mut(shop) {
+                    goods.stock = (goods.stock + delivery.load).min(goods.capacity);
+                    goods.incoming = goods.incoming.saturating_sub(1);
+                }
+            }
+            FreightStop::Outside => {
+                ledger.exports += delivery.load * config.export_price * region.trade_demand;
+                stats.exported += 1;
+            }
+        }
+        stats.deliveries += 1;
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Remember which roads trucks have been using.
+fn update_freight_traffic(
+    time: Res<Time>,
+    config: Res<FreightConfig>,
+    trucks: Query<&VehicleNavigation, With<Delivery>>,
+    mut traffic: ResMut<FreightTraffic>,
+    mut stats: ResMut<FreightStats>,
+) {
+    let dt = time.delta_secs();
+    let keep = (-dt / config.traffic_memory.max(0.001)).exp();
+    for trucks in traffic.per_edge.values_mut() {
+        *trucks *= keep;
+    }
+    let mut count = 0;
+    for nav in &trucks {
+        *traffic.per_edge.entry(nav.current_edge).or_default() += 1.0 - keep;
+        count += 1;
+    }
+    traffic.per_edge.retain(|_, trucks| *trucks > 0.001);
+    if stats.trucks != count {
+        stats.trucks = count;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::procgen::roads::{test_road, RoadNodeType};
+
+    #[test]
+    fn trucks_go_the_long_way_round_by_highway() {
+        // A minor street straight from A to B, and a highway round by C
+        let mut graph = RoadGraph::default();
+        let [a, b, c] = [Vec2::new(0.0, 0.0), Vec2::new(200.0, 0.0), Vec2::new(100.0, 60.0)]
+            .map(|p| graph.add_node(p, RoadNodeType::Intersection));
+        test_road(a, b, RoadType::Minor, &mut graph);
+        let highway = [test_road(a, c, RoadType::Highway, &mut graph), test_road(c, b, RoadType::Highway, &mut graph)];
+        assert_eq!(graph.cheapest_path(a, b, truck_edge_cost), Some(highway.to_vec()));
+    }
+
+    #[test]
+    fn shops_are_served_from_the_nearest_factory_then_from_outside() {
+        let [near, far, spare, shop_a, shop_b, shop_c] = [1, 2, 3, 4, 5, 6].map(Entity::from_raw);
+        let shops = [(shop_a, Vec2::ZERO), (shop_b, Vec2::new(10.0, 0.0)), (shop_c, Vec2::new(20.0, 0.0))];
+        let factories = [
+            (near, Vec2::new(50.0, 0.0), 25.0),
+            (far, Vec2::new(500.0, 0.0), 20.0),
+            (spare, Vec2::new(900.0, 0.0), 10.0),
+        ];
+
+        let from = |shipments: &[Shipment], shop| shipments.iter().find(|s| s.to == FreightStop::Building(shop)).map(|s| s.from);
+        let shipments = plan_shipments(&shops, &mut factories.clone(), 20.0, 50.0, true);
+        assert_eq!(from(&shipments, shop_a), Some(FreightStop::Building(near)));
+        assert_eq!(from(&shipments, shop_b), Some(FreightStop::Building(far)));
+        assert_eq!(from(&shipments, shop_c), Some(FreightStop::Outside));
+
+        // Without outside trade the last shop goes without
+        let shipments = plan_shipments(&shops, &mut factories.clone(), 20.0, 50.0, false);
+        assert_eq!(shipments.len(), 2);
+        assert_eq!(from(&shipments, shop_c), None);
+
+        // A full factory with no one to serve exports its surplus
+        let mut full = [(near, Vec2::ZERO, 55.0)];
+        let shipments = plan_shipments(&[], &mut full, 20.0, 50.0, true);
+        assert_eq!(shipments, vec![Shipment { from: FreightStop::Building(near), to: FreightStop::Outside }]);
+        assert_eq!(full[0].2, 35.0);
+    }
+}
@@ -1,23 +1,30 @@
 //! Land value calculation based on multiple environmental and service factors.
 //!
 //! Computes a composite land value score for each location based on:
-//! - Pollution (negative, from industrial buildings)
+//! - Pollution (negative, from industrial buildings and car and truck traffic on nearby roads)
+//! - Traffic noise (negative, mostly for residential zones; trucks are loudest)
 //! - Crime rate (negative, reduced by police coverage)
 //! - Education access (positive, from schools)
 //! - Healthcare access (positive, from hospitals)
 //! - Park access (positive, from parks/green spaces)
 //! - Road access (positive/negative based on zone type)
 //! - Commute time (negative, distance to jobs)
+//! - Parking scarcity (negative, for commercial and industrial zones)
 
 use bevy::prelude::*;
 
 use crate::game_state::GameState;
 use crate::procgen::building_factory::BuildingArchetype;
+use crate::procgen::roads::{RoadGraph, RoadType};
 use crate::render::building_spawner::Building;
 use crate::tools::services::{ServiceBuilding, ServiceType};
 use crate::tools::zone_paint::ZoneCell;
 use crate::tools::ZoneType;
 
+use super::freight::{FreightConfig, FreightTraffic};
+use super::parking::{ParkingConfig, ParkingSupply};
+use super::ridership::ModeShare;
+
 pub struct LandValuePlugin;
 
 impl Plugin for LandValuePlugin {
@@ -40,6 +47,8 @@ pub struct LandValueConfig {
     pub update_interval: f32,
     /// Maximum distance for pollution spread.
     pub pollution_radius: f32,
+    /// Maximum distance exhaust from a road's traffic spreads.
+    pub road_pollution_radius: f32,
     /// Base crime level without police coverage.
     pub base_crime: f32,
     /// Maximum distance to consider for commute.
@@ -53,6 +62,7 @@ impl Default for LandValueConfig {
         Self {
             update_interval: 2.0,
             pollution_radius: 80.0,
+            road_pollution_radius: 30.0,
             base_crime: 0.5,
             max_commute_distance: 200.0,
             grid_size: 20.0,
@@ -65,6 +75,8 @@ impl Default for LandValueConfig {
 pub struct LocationFactors {
     /// Pollution level (0.0 = clean, 1.0 = heavily polluted).
     pub pollution: f32,
+    /// Traffic noise (0.0 = quiet, 1.0 = loud).
+    pub noise: f32,
     /// Crime rate (0.0 = safe, 1.0 = high crime).
     pub crime: f32,
     /// Education access (0.0 = no access, 1.0 = excellent).
@@ -79,6 +91,8 @@ pub struct LocationFactors {
     pub road_access: f32,
     /// Commute time factor (0.0 = long commute, 1.0 = short commute).
     pub commute: f32,
+    /// Parking scarcity nearby (0.0 = plenty free, 1.0 = none free).
+    pub parking_scarcity: f32,
     /// Composite land value (0.0 = undesirable, 1.0 = prime location).
     pub land_value: f32,
 }
@@ -87,12 +101,12 @@ impl LocationFactors {
     /// Calculate composite land value from individual factors.
     pub fn calculate_land_value(&mut self, zone_type: Option<ZoneType>) {
         // Weights vary by zone type
-        let (pollution_weight, crime_weight, edu_weight, health_weight, park_weight, commute_weight) =
+        let (pollution_weight, noise_weight, crime_weight, edu_weight, health_weight, park_weight, commute_weight, parking_weight) =
             match zone_type {
-                Some(ZoneType::Residential) => (-0.25, -0.20, 0.15, 0.10, 0.15, 0.15),
-                Some(ZoneType::Commercial) => (-0.10, -0.25, 0.05, 0.05, 0.10, 0.20),
-                Some(ZoneType::Industrial) => (0.0, -0.15, 0.0, 0.0, 0.0, 0.10),
-                _ => (-0.15, -0.15, 0.10, 0.10, 0.10, 0.10),
+                Some(ZoneType::Residential) => (-0.25, -0.10, -0.20, 0.15, 0.10, 0.15, 0.15, 0.0),
+                Some(ZoneType::Commercial) => (-0.10, -0.03, -0.25, 0.05, 0.05, 0.10, 0.20, -0.20),
+                Some(ZoneType::Industrial) => (0.0, 0.0, -0.15, 0.0, 0.0, 0.0, 0.10, -0.05),
+                _ => (-0.15, -0.05, -0.15, 0.10, 0.10, 0.10, 0.10, 0.0),
             };
 
         // Base value starts at 0.5
@@ -100,11 +114,13 @@ impl LocationFactors {
 
         // Apply weighted factors
         value += self.pollution * pollution_weight;
+        value += self.noise * noise_weight;
         value += self.crime * crime_weight;
         value += self.education * edu_weight;
         value += self.healthcare * health_weight;
         value += self.park_access * park_weight;
         value += self.commute * commute_weight;
+        value += self.parking_scarcity * parking_weight;
         value += self.fire_safety * 0.05;
         value += self.road_access * 0.10;
 
@@ -138,9 +154,16 @@ fn update_land_value_map(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn apply_land_value_to_zones(
     mut commands: Commands,
     config: Res<LandValueConfig>,
+    parking: Res<ParkingSupply>,
+    parking_config: Res<ParkingConfig>,
+    road_graph: Res<RoadGraph>,
+    mode_share: Res<ModeShare>,
+    freight: Res<FreightTraffic>,
+    freight_config: Res<FreightConfig>,
     mut map: ResMut<LandValueMap>,
     zone_cells: Query<(Entity, &ZoneCell, &Transform), Without<ZoneFactors>>,
     mut existing_zones: Query<(Entity, &ZoneCell, &Transform, &mut ZoneFactors)>,
@@ -150,7 +173,7 @@ fn apply_land_value_to_zones(
     // Add ZoneFactors to cells that don't have them
     for (entity, cell, transform) in &zone_cells {
         let pos = Vec2::new(transform.translation.x, transform.translation.z);
-        let factors = calculate_factors_at(pos, cell.zone_type, &config, &buildings, &services);
+        let factors = calculate_factors_at(pos, cell.zone_type, &config, &buildings, &services, &parking, &parking_config, &road_graph, mode_share.car, &freight, &freight_config);
         commands.entity(entity).insert(ZoneFactors(factors));
     }
 
@@ -162,16 +185,52 @@ fn apply_land_value_to_zones(
 
     for (_, cell, transform, mut factors) in &mut existing_zones {
         let pos = Vec2::new(transform.translation.x, transform.translation.z);
-        factors.0 = calculate_factors_at(pos, cell.zone_type, &config, &buildings, &services);
+        factors.0 = calculate_factors_at(pos, cell.zone_type, &config, &buildings, &services, &parking, &parking_config, &road_graph, mode_share.car, &freight, &freight_config);
     }
 }
 
+/// Exhaust and noise from the nearest road, as `(pollution, noise)`:
+/// heavier roads carry more cars, the share of trips made by car sets how
+/// many of them there are, and delivery trucks add to both.
+fn road_traffic_at(
+    pos: Vec2,
+    road_graph: &RoadGraph,
+    car_share: f32,
+    trucks: &FreightTraffic,
+    freight_config: &FreightConfig,
+    radius: f32,
+) -> (f32, f32) {
+    let Some((edge, distance, _)) = road_graph.nearest_edge(pos, radius) else {
+        return (0.0, 0.0);
+    };
+    let traffic = match road_graph.edge_by_index(edge).map(|e| e.road_type) {
+        Some(RoadType::Highway) => 0.6,
+        Some(RoadType::Major) => 0.35,
+        Some(RoadType::Minor) => 0.1,
+        Some(RoadType::Alley) | None => 0.05,
+    };
+    let falloff = 1.0 - distance / radius;
+    let truck_share = trucks.truck_share(edge, freight_config);
+    let cars = traffic * car_share;
+    // A truck is dirtier and louder than the cars it shares the road with
+    let pollution = (cars + truck_share * 0.3) * falloff;
+    let noise = (cars + truck_share * 0.6) * falloff;
+    (pollution, noise.min(1.0))
+}
+
+#[allow(clippy::too_many_arguments)]
 fn calculate_factors_at(
     pos: Vec2,
     zone_type: ZoneType,
     config: &LandValueConfig,
     buildings: &Query<(&Building, &GlobalTransform)>,
     services: &Query<(&ServiceBuilding, &GlobalTransform)>,
+    parking: &ParkingSupply,
+    parking_config: &ParkingConfig,
+    road_graph: &RoadGraph,
+    car_share: f32,
+    trucks: &FreightTraffic,
+    freight_config: &FreightConfig,
 ) -> LocationFactors {
     let mut factors = LocationFactors::default();
 
@@ -202,7 +261,12 @@ fn calculate_factors_at(
             }
         }
     }
+    // Trips made by bike, on foot or by transit leave the air cleaner
+    let (traffic_pollution, noise) =
+        road_traffic_at(pos, road_graph, car_share, trucks, freight_config, config.road_pollution_radius);
+    pollution += traffic_pollution;
     factors.pollution = pollution.clamp(0.0, 1.0);
+    factors.noise = noise;
 
     // Calculate commute factor (closer to jobs = better)
     if job_distance < f32::MAX {
@@ -245,6 +309,8 @@ fn calculate_factors_at(
                 ServiceType::Park => {
                     park_access = park_access.max(coverage);
                 }
+                // Utilities serve through their networks, not by distance
+                ServiceType::PowerPlant | ServiceType::WaterTower | ServiceType::PumpingStation => {}
             }
         }
     }
@@ -268,6 +334,8 @@ fn calculate_factors_at(
     // This would ideally check actual road graph distance
     factors.road_access = 0.7; // Default moderate access for zoned areas
 
+    factors.parking_scarcity = parking.scarcity_at(pos, parking_config);
+
     // Calculate final land value
     factors.calculate_land_value(Some(zone_type));
 
@@ -8,14 +8,24 @@ use bevy::prelude::*;
 pub mod bus_routes;
 pub mod citizens;
 pub mod commute;
+pub mod cycling;
 pub mod demand;
 pub mod economy;
 pub mod flow_field;
+pub mod freight;
 pub mod land_value;
+pub mod parking;
 pub mod pedestrians;
 pub mod population;
+pub mod rail_network;
+pub mod region;
+pub mod ridership;
+pub mod right_of_way;
 pub mod services;
+pub mod signal_plans;
+pub mod tourism;
 pub mod traffic;
+pub mod utilities;
 pub mod vehicle_traffic;
 pub mod vehicles;
 pub mod zones;
@@ -27,6 +37,11 @@ impl Plugin for SimulationPlugin {
         app.add_plugins(vehicle_traffic::MovingVehiclePlugin)
             .add_plugins(bus_routes::BusRoutesPlugin)
             .add_plugins(pedestrians::PedestrianPlugin)
+            .add_plugins(cycling::CyclingPlugin)
+            .add_plugins(freight::FreightPlugin)
+            .add_plugins(region::RegionPlugin)
+            .add_plugins(tourism::TourismPlugin)
+            .add_plugins(utilities::UtilityPlugin)
             .add_plugins(economy::EconomyPlugin)
             .add_plugins(demand::DemandPlugin)
             .add_plugins(population::PopulationPlugin)
@@ -34,6 +49,9 @@ impl Plugin for SimulationPlugin {
             .add_plugins(land_value::LandValuePlugin)
             .add_plugins(services::ServiceCoveragePlugin)
             .add_plugins(commute::CommutePlugin)
+            .add_plugins(ridership::RidershipPlugin)
+            .add_plugins(rail_network::RailNetworkPlugin)
+            .add_plugins(parking::ParkingPlugin)
             .add_plugins(citizens::CitizensPlugin)
             .add_plugins(traffic::TrafficCaPlugin)
             .add_plugins(flow_field::FlowFieldPlugin)
@@ -0,0 +1,549 @@
+//! Parking supply and demand.
+//!
+//! Lots, garages and the curbs of major and minor roads each hold a number
+//! of spaces. A car trip to a shop, office or factory takes the free space
+//! nearest its destination. When nothing is free within walking distance the
+//! driver cruises the surrounding streets for one - adding to the traffic
+//! there - and parks farther out, or gives up. Trips home end in a private
+//! driveway. The time lost searching is costed into whether to drive at all,
+//! and scarce parking around shops lowers commercial land value and demand.
+
+use bevy::prelude::*;
+use petgraph::graph::EdgeIndex;
+use std::collections::HashMap;
+
+use crate::procgen::building_factory::BuildingArchetype;
+use crate::procgen::roads::{RoadEdge, RoadGraph, RoadType};
+use crate::render::building_spawner::Building;
+use crate::render::parking_garages::ParkingGarage;
+use crate::render::parking_lots::ParkingLot;
+
+use super::ridership::{TravelMode, TripModeChosen};
+
+pub struct ParkingPlugin;
+
+impl Plugin for ParkingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ParkingConfig>()
+            .init_resource::<ParkingSupply>()
+            .init_resource::<ParkingSearch>()
+            .init_resource::<ParkingStats>()
+            .add_systems(
+                Update,
+                (
+                    rebuild_parking_supply,
+                    park_car_trips,
+                    update_parking_search,
+                    sync_parking_occupancy,
+                    update_parking_stats,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Configuration for parking.
+#[derive(Resource)]
+pub struct ParkingConfig {
+    /// Farthest a driver will walk between a space and their destination.
+    pub walk_radius: f32,
+    /// Farthest a driver will cruise from their destination looking for a space.
+    pub search_radius: f32,
+    /// Curb length taken by one parked car.
+    pub curb_space: f32,
+    /// Curbside spaces are pooled into stretches about this long.
+    pub curb_stretch: f32,
+    /// Time lost cruising when nothing is free nearby (seconds).
+    pub search_time: f32,
+    /// Share of spaces taken above which parking counts as scarce.
+    pub comfortable_occupancy: f32,
+    /// Commercial demand lost when parking around every shop is full.
+    pub commercial_demand_penalty: f32,
+    /// How often scarcity around shops is measured (seconds).
+    pub update_interval: f32,
+}
+
+impl Default for ParkingConfig {
+    fn default() -> Self {
+        Self {
+            walk_radius: 120.0,
+            search_radius: 350.0,
+            curb_space: 6.0,
+            curb_stretch: 60.0,
+            search_time: 240.0,
+            comfortable_occupancy: 0.85,
+            commercial_demand_penalty: 0.4,
+            update_interval: 2.0,
+        }
+    }
+}
+
+/// Where a group of spaces is.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParkingKind {
+    Lot(Entity),
+    Garage(Entity),
+    /// Both curbs along a stretch of road.
+    Curb { edge: EdgeIndex, start: Vec2, end: Vec2 },
+}
+
+impl ParkingKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ParkingKind::Lot(_) => "Lot",
+            ParkingKind::Garage(_) => "Garage",
+            ParkingKind::Curb { .. } => "Curbside",
+        }
+    }
+}
+
+/// A lot, garage or stretch of curb and how full it is.
+#[derive(Clone, Debug)]
+pub struct ParkingSite {
+    pub kind: ParkingKind,
+    pub position: Vec2,
+    pub capacity: u32,
+    pub occupied: u32,
+}
+
+impl ParkingSite {
+    pub fn new(kind: ParkingKind, position: Vec2, capacity: u32) -> Self {
+        Self {
+            kind,
+            position,
+            capacity,
+            occupied: 0,
+        }
+    }
+
+    pub fn free(&self) -> u32 {
+        self.capacity.saturating_sub(self.occupied)
+    }
+
+    /// Share of spaces taken (0-1).
+    pub fn occupancy(&self) -> f32 {
+        if self.capacity == 0 {
+            return 1.0;
+        }
+        self.occupied as f32 / self.capacity as f32
+    }
+}
+
+/// What identifies a site across rebuilds.
+#[derive(PartialEq, Eq, Hash)]
+enum SiteKey {
+    Facility(Entity),
+    Curb([u32; 4]),
+}
+
+impl SiteKey {
+    fn of(kind: &ParkingKind) -> Self {
+        match *kind {
+            ParkingKind::Lot(entity) | ParkingKind::Garage(entity) => SiteKey::Facility(entity),
+            ParkingKind::Curb { start, end, .. } => {
+                SiteKey::Curb([start.x.to_bits(), start.y.to_bits(), end.x.to_bits(), end.y.to_bits()])
+            }
+        }
+    }
+}
+
+/// How a car trip found somewhere to park.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParkingOutcome {
+    /// A free space within walking distance, by site.
+    Parked(usize),
+    /// Cruised for a space and found one farther out.
+    Searched(usize),
+    /// Cruised and found nothing.
+    Failed,
+}
+
+/// Every parking space in the city and whose car is in it.
+#[derive(Resource, Default)]
+pub struct ParkingSupply {
+    pub sites: Vec<ParkingSite>,
+    /// Site each commuter's car is parked at.
+    parked: HashMap<Entity, usize>,
+}
+
+impl ParkingSupply {
+    /// Replace the sites, keeping the cars parked at any that remain.
+    pub fn set_sites(&mut self, mut sites: Vec<ParkingSite>) {
+        let index: HashMap<SiteKey, usize> = sites
+            .iter()
+            .enumerate()
+            .map(|(i, site)| (SiteKey::of(&site.kind), i))
+            .collect();
+        for site in &mut sites {
+            site.occupied = 0;
+        }
+        for (citizen, old) in std::mem::take(&mut self.parked) {
+            let Some(&new) = self.sites.get(old).and_then(|site| index.get(&SiteKey::of(&site.kind))) else {
+                continue;
+            };
+            if sites[new].free() > 0 {
+                sites[new].occupied += 1;
+                self.parked.insert(citizen, new);
+            }
+        }
+        self.sites = sites;
+    }
+
+    /// Nearest site with a free space within `radius` of `position`.
+    pub fn nearest_free(&self, position: Vec2, radius: f32) -> Option<usize> {
+        self.sites
+            .iter()
+            .enumerate()
+            .filter(|(_, site)| site.free() > 0)
+            .map(|(i, site)| (i, site.position.distance(position)))
+            .filter(|&(_, distance)| distance <= radius)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+    }
+
+    /// Park `citizen`'s car for a trip to `destination`, freeing the space it
+    /// was in before.
+    pub fn park(&mut self, citizen: Entity, destination: Vec2, config: &ParkingConfig) -> ParkingOutcome {
+        self.release(citizen);
+        let outcome = match self.nearest_free(destination, config.search_radius) {
+            Some(site) if self.sites[site].position.distance(destination) <= config.walk_radius => {
+                ParkingOutcome::Parked(site)
+            }
+            Some(site) => ParkingOutcome::Searched(site),
+            None => ParkingOutcome::Failed,
+        };
+        if let ParkingOutcome::Parked(site) | ParkingOutcome::Searched(site) = outcome {
+            self.sites[site].occupied += 1;
+            self.parked.insert(citizen, site);
+        }
+        outcome
+    }
+
+    /// Free the space `citizen`'s car is parked in, if any.
+    pub fn release(&mut self, citizen: Entity) {
+        if let Some(site) = self.parked.remove(&citizen).and_then(|site| self.sites.get_mut(site)) {
+            site.occupied = site.occupied.saturating_sub(1);
+        }
+    }
+
+    /// Time a driver to `destination` can expect to lose looking for a space.
+    pub fn search_time(&self, destination: Vec2, config: &ParkingConfig) -> f32 {
+        if self.nearest_free(destination, config.walk_radius).is_some() {
+            0.0
+        } else {
+            config.search_time
+        }
+    }
+
+    /// Spaces, and spaces taken, within `radius` of `position`.
+    pub fn spaces_near(&self, position: Vec2, radius: f32) -> (u32, u32) {
+        self.sites
+            .iter()
+            .filter(|site| site.position.distance(position) <= radius)
+            .fold((0, 0), |(capacity, occupied), site| {
+                (capacity + site.capacity, occupied + site.occupied)
+            })
+    }
+
+    /// How scarce parking is within walking distance of `position`, from 0
+    /// (plenty free) to 1 (all taken, or none at all).
+    pub fn scarcity_at(&self, position: Vec2, config: &ParkingConfig) -> f32 {
+        let (capacity, occupied) = self.spaces_near(position, config.walk_radius);
+        if capacity == 0 {
+            return 1.0;
+        }
+        let occupancy = occupied as f32 / capacity as f32;
+        ((occupancy - config.comfortable_occupancy) / (1.0 - config.comfortable_occupancy)).clamp(0.0, 1.0)
+    }
+
+    /// Site nearest `position`, within `radius`.
+    pub fn site_at(&self, position: Vec2, radius: f32) -> Option<&ParkingSite> {
+        self.sites
+            .iter()
+            .map(|site| (site, site.position.distance(position)))
+            .filter(|&(_, distance)| distance <= radius)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(site, _)| site)
+    }
+}
+
+/// Cars cruising for a space, by the road they are cruising on.
+#[derive(Resource, Default)]
+pub struct ParkingSearch {
+    pub cruising: HashMap<EdgeIndex, f32>,
+}
+
+impl ParkingSearch {
+    /// Cars cruising along `edge`.
+    pub fn on_edge(&self, edge: EdgeIndex) -> f32 {
+        self.cruising.get(&edge).copied().unwrap_or(0.0)
+    }
+
+    /// Spread one cruising car over the roads around `destination`.
+    fn add(&mut self, road_graph: &RoadGraph, destination: Vec2, config: &ParkingConfig) {
+        let area = Rect::from_center_half_size(destination, Vec2::splat(config.walk_radius));
+        let edges = road_graph.edges_in_rect(area);
+        let share = 1.0 / edges.len().max(1) as f32;
+        for edge in edges {
+            *self.cruising.entry(edge).or_default() += share;
+        }
+    }
+}
+
+/// City-wide parking figures.
+#[derive(Resource, Default)]
+pub struct ParkingStats {
+    pub capacity: u32,
+    pub occupied: u32,
+    /// Cars cruising for a space right now.
+    pub cruising: f32,
+    /// Car trips that parked near their destination, parked after a search,
+    /// or found nowhere.
+    pub parked: u32,
+    pub searched: u32,
+    pub failed: u32,
+    /// Average scarcity of parking around shops and offices (0-1).
+    pub commercial_scarcity: f32,
+    timer: f32,
+}
+
+/// Point `distance` along a polyline.
+fn point_along(points: &[Vec2], distance: f32) -> Vec2 {
+    let mut remaining = distance;
+    for window in points.windows(2) {
+        let span = window[0].distance(window[1]);
+        if remaining <= span && span > 0.0 {
+            return window[0].lerp(window[1], remaining / span);
+        }
+        remaining -= span;
+    }
+    points.last().copied().unwrap_or_default()
+}
+
+/// Curbside stretches along a road; only major and minor streets at ground
+/// level allow parking.
+pub fn curb_sites(edge_index: EdgeIndex, edge: &RoadEdge, config: &ParkingConfig) -> Vec<ParkingSite> {
+    if !matches!(edge.road_type, RoadType::Major | RoadType::Minor) || edge.is_structure() {
+        return Vec::new();
+    }
+    let stretches = (edge.length / config.curb_stretch).round().max(1.0) as usize;
+    let length = edge.length / stretches as f32;
+    let spaces = (length / config.curb_space).floor() as u32 * 2;
+    if spaces == 0 {
+        return Vec::new();
+    }
+    (0..stretches)
+        .map(|i| {
+            let start = point_along(&edge.points, i as f32 * length);
+            let end = point_along(&edge.points, (i + 1) as f32 * length);
+            let kind = ParkingKind::Curb {
+                edge: edge_index,
+                start,
+                end,
+            };
+            ParkingSite::new(kind, (start + end) / 2.0, spaces)
+        })
+        .collect()
+}
+
+/// Gather the lots, garages and curbs again when any of them change.
+#[allow(clippy::too_many_arguments)]
+fn rebuild_parking_supply(
+    config: Res<ParkingConfig>,
+    road_graph: Res<RoadGraph>,
+    lots: Query<(Entity, &ParkingLot, &Transform)>,
+    garages: Query<(Entity, &ParkingGarage, &Transform)>,
+    added_lots: Query<(), Added<ParkingLot>>,
+    added_garages: Query<(), Added<ParkingGarage>>,
+    mut removed_lots: RemovedComponents<ParkingLot>,
+    mut removed_garages: RemovedComponents<ParkingGarage>,
+    mut supply: ResMut<ParkingSupply>,
+) {
+    let removed = removed_lots.read().count() + removed_garages.read().count() > 0;
+    if !road_graph.is_changed() && added_lots.is_empty() && added_garages.is_empty() && !removed {
+        return;
+    }
+
+    let mut sites = Vec::new();
+    for (entity, lot, transform) in &lots {
+        let position = Vec2::new(transform.translation.x, transform.translation.z);
+        sites.push(ParkingSite::new(ParkingKind::Lot(entity), position, lot.capacity));
+    }
+    for (entity, garage, transform) in &garages {
+        let position = Vec2::new(transform.translation.x, transform.translation.z);
+        sites.push(ParkingSite::new(ParkingKind::Garage(entity), position, garage.capacity));
+    }
+    for edge in road_graph.edge_indices() {
+        if let Some(road) = road_graph.edge_by_index(edge) {
+            sites.extend(curb_sites(edge, road, &config));
+        }
+    }
+    supply.set_sites(sites);
+}
+
+/// Find each car trip a space at its destination; the car leaves the space
+/// it was in whichever way its owner travels.
+fn park_car_trips(
+    mut trips: EventReader<TripModeChosen>,
+    config: Res<ParkingConfig>,
+    road_graph: Res<RoadGraph>,
+    buildings: Query<&Building>,
+    mut supply: ResMut<ParkingSupply>,
+    mut search: ResMut<ParkingSearch>,
+    mut stats: ResMut<ParkingStats>,
+) {
+    for chosen in trips.read() {
+        let citizen = chosen.trip.citizen;
+        supply.release(citizen);
+        let home = buildings
+            .get(chosen.trip.to)
+            .is_ok_and(|b| b.building_type == BuildingArchetype::Residential);
+        if chosen.mode != TravelMode::Car || home {
+            continue;
+        }
+        match supply.park(citizen, chosen.to, &config) {
+            ParkingOutcome::Parked(_) => stats.parked += 1,
+            ParkingOutcome::Searched(_) => {
+                stats.searched += 1;
+                search.add(&road_graph, chosen.to, &config);
+            }
+            ParkingOutcome::Failed => {
+                stats.failed += 1;
+                search.add(&road_graph, chosen.to, &config);
+            }
+        }
+    }
+}
+
+/// Cruising cars find a space or give up over about `search_time`. Road
+/// edits renumber the edges, so they end every search.
+fn update_parking_search(
+    time: Res<Time>,
+    config: Res<ParkingConfig>,
+    road_graph: Res<RoadGraph>,
+    mut search: ResMut<ParkingSearch>,
+) {
+    if road_graph.is_changed() {
+        search.cruising.clear();
+        return;
+    }
+    let decay = (-time.delta_secs() / config.search_time).exp();
+    search.cruising.retain(|_, cars| {
+        *cars *= decay;
+        *cars > 0.01
+    });
+}
+
+/// Copy each lot's and garage's occupancy onto it for display.
+fn sync_parking_occupancy(
+    supply: Res<ParkingSupply>,
+    mut lots: Query<&mut ParkingLot>,
+    mut garages: Query<&mut ParkingGarage>,
+) {
+    if !supply.is_changed() {
+        return;
+    }
+    for site in &supply.sites {
+        match site.kind {
+            ParkingKind::Lot(entity) => {
+                if let Ok(mut lot) = lots.get_mut(entity) {
+                    if lot.occupied != site.occupied {
+                        lot.occupied = site.occupied;
+                    }
+                }
+            }
+            ParkingKind::Garage(entity) => {
+                if let Ok(mut garage) = garages.get_mut(entity) {
+                    if garage.occupied != site.occupied {
+                        garage.occupied = site.occupied;
+                    }
+                }
+            }
+            ParkingKind::Curb { .. } => {}
+        }
+    }
+}
+
+fn update_parking_stats(
+    time: Res<Time>,
+    config: Res<ParkingConfig>,
+    supply: Res<ParkingSupply>,
+    search: Res<ParkingSearch>,
+    buildings: Query<(&Building, &Transform)>,
+    mut stats: ResMut<ParkingStats>,
+) {
+    stats.capacity = supply.sites.iter().map(|site| site.capacity).sum();
+    stats.occupied = supply.sites.iter().map(|site| site.occupied).sum();
+    stats.cruising = search.cruising.values().sum();
+
+    stats.timer += time.delta_secs();
+    if stats.timer < config.update_interval {
+        return;
+    }
+    stats.timer = 0.0;
+    let (total, count) = buildings
+        .iter()
+        .filter(|(building, _)| building.building_type == BuildingArchetype::Commercial)
+        .map(|(_, transform)| supply.scarcity_at(Vec2::new(transform.translation.x, transform.translation.z), &config))
+        .fold((0.0, 0), |(total, count), scarcity| (total + scarcity, count + 1));
+    stats.commercial_scarcity = if count > 0 { total / count as f32 } else { 0.0 };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smallvec::smallvec;
+
+    fn lot(index: u32, position: Vec2, capacity: u32) -> ParkingSite {
+        ParkingSite::new(ParkingKind::Lot(Entity::from_raw(index)), position, capacity)
+    }
+
+    #[test]
+    fn full_parking_sends_drivers_searching_then_away() {
+        let config = ParkingConfig::default();
+        let mut supply = ParkingSupply::default();
+        supply.set_sites(vec![lot(100, Vec2::ZERO, 1), lot(101, Vec2::new(300.0, 0.0), 1)]);
+
+        let (first, second, third) = (Entity::from_raw(1), Entity::from_raw(2), Entity::from_raw(3));
+        assert_eq!(supply.park(first, Vec2::ZERO, &config), ParkingOutcome::Parked(0));
+        assert_eq!(supply.search_time(Vec2::ZERO, &config), config.search_time);
+        assert_eq!(supply.park(second, Vec2::ZERO, &config), ParkingOutcome::Searched(1));
+        assert_eq!(supply.park(third, Vec2::ZERO, &config), ParkingOutcome::Failed);
+        assert_eq!(supply.scarcity_at(Vec2::ZERO, &config), 1.0);
+
+        // The first car driving off frees its space
+        supply.release(first);
+        assert_eq!(supply.search_time(Vec2::ZERO, &config), 0.0);
+        assert_eq!(supply.park(third, Vec2::ZERO, &config), ParkingOutcome::Parked(0));
+    }
+
+    #[test]
+    fn rebuilding_keeps_cars_at_sites_that_remain() {
+        let config = ParkingConfig::default();
+        let mut supply = ParkingSupply::default();
+        supply.set_sites(vec![lot(100, Vec2::ZERO, 4), lot(101, Vec2::new(50.0, 0.0), 4)]);
+        supply.park(Entity::from_raw(1), Vec2::ZERO, &config);
+        supply.park(Entity::from_raw(2), Vec2::new(50.0, 0.0), &config);
+
+        // The first lot is demolished and a new one added in front of the second
+        supply.set_sites(vec![lot(102, Vec2::new(80.0, 0.0), 4), lot(101, Vec2::new(50.0, 0.0), 4)]);
+        assert_eq!(supply.sites[0].occupied, 0);
+        assert_eq!(supply.sites[1].occupied, 1);
+        supply.release(Entity::from_raw(2));
+        assert_eq!(supply.sites[1].occupied, 0);
+    }
+
+    #[test]
+    fn curbs_line_major_and_minor_streets_only() {
+        let config = ParkingConfig::default();
+        let points = smallvec![Vec2::ZERO, Vec2::new(120.0, 0.0)];
+        let minor = RoadEdge::new(points, RoadType::Minor);
+        let sites = curb_sites(EdgeIndex::new(0), &minor, &config);
+        assert_eq!(sites.len(), 2);
+        assert_eq!(sites[0].capacity, 20);
+        assert_eq!(sites[1].position, Vec2::new(90.0, 0.0));
+
+        let highway = RoadEdge::new(smallvec![Vec2::ZERO, Vec2::new(120.0, 0.0)], RoadType::Highway);
+        assert!(curb_sites(EdgeIndex::new(0), &highway, &config).is_empty());
+    }
+}
@@ -1,45 +1,93 @@
-//! Pedestrian movement system.
+//! Pedestrian trips.
 //!
-//! Spawns pedestrians that walk along sidewalks, following the road network
-//! with perpendicular offsets from road centerlines.
+//! Pedestrians are citizens out on foot: commuters who chose to walk, transit
+//! riders walking on from the stop they got off at, and residents going out
+//! to the nearest shop or park. They walk the sidewalks of major and minor
+//! streets, and at each corner take the street that leads furthest down the
+//! flow field toward their destination, going indoors at the corner where no
+//! street brings them any closer. Pedestrians on each sidewalk are counted for the density
+//! stat and overlay.
 
 use bevy::prelude::*;
-use noise::{NoiseFn, Perlin};
 use petgraph::graph::{EdgeIndex, NodeIndex};
 use rand::{Rng, SeedableRng};
 use rand::rngs::StdRng;
+use std::collections::HashMap;
 
-use crate::procgen::building_factory::BuildingArchetype;
-use crate::procgen::roads::{RoadGraph, RoadNodeType, RoadType};
+use crate::procgen::roads::{RoadEdge, RoadGraph, RoadType, RoadsRemoved};
 use crate::render::building_spawner::Building;
-use crate::render::instancing::TerrainConfig;
-use crate::render::road_mesh::RoadMeshGenerated;
+use crate::render::day_night::TimeOfDay;
+use crate::world::terrain::HeightMap;
 use crate::render::traffic_lights::{TrafficLightController, LightPhase};
 
+use super::citizens::{CitizenState, OutingStarted};
+use super::flow_field::{CityFlowFields, FlowDestination, FlowField, FlowFieldCache, FlowFieldConfig, WalkCosts};
+use super::ridership::{LineKey, Ridership, TransitNetwork, TravelMode, TripModeChosen};
+
 pub struct PedestrianPlugin;
 
 impl Plugin for PedestrianPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<PedestrianConfig>()
-            .init_resource::<PedestriansInitialized>()
+            .init_resource::<PedestrianTrips>()
+            .init_resource::<PedestrianStats>()
             .add_systems(
                 Update,
                 (
-                    spawn_pedestrians.run_if(should_spawn_pedestrians),
+                    pedestrian_road_removal,
+                    queue_pedestrian_trips,
+                    spawn_pedestrians,
                     check_crosswalk_waiting,
                     pedestrian_movement,
                     pedestrian_edge_transition,
                     pedestrian_transform_sync,
+                    update_pedestrian_stats,
                 )
                     .chain(),
             );
     }
 }
 
+/// Renumber pedestrians after roads are removed; those on removed roads are despawned.
+fn pedestrian_road_removal(
+    mut commands: Commands,
+    mut events: EventReader<RoadsRemoved>,
+    mut agents: Query<(Entity, &mut PedestrianNavigation)>,
+) {
+    for RoadsRemoved(removal) in events.read() {
+        let mut despawned = 0;
+        for (entity, mut nav) in &mut agents {
+            let (Some(edge), Some(destination)) = (
+                removal.remap.edge(nav.current_edge),
+                removal.remap.node(nav.destination_node),
+            ) else {
+                commands.entity(entity).despawn_recursive();
+                despawned += 1;
+                continue;
+            };
+            nav.current_edge = edge;
+            nav.destination_node = destination;
+            nav.previous_node = nav.previous_node.and_then(|node| removal.remap.node(node));
+        }
+        if despawned > 0 {
+            info!("Removed {} pedestrians from demolished roads", despawned);
+        }
+    }
+}
+
 /// Marker component for pedestrians.
 #[derive(Component)]
 pub struct Pedestrian;
 
+/// Where a pedestrian is walking to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PedestrianGoal {
+    /// The nearest place of a kind.
+    Nearest(FlowDestination),
+    /// One building.
+    Building { entity: Entity, position: Vec2 },
+}
+
 /// Navigation state for a moving pedestrian.
 #[derive(Component)]
 pub struct PedestrianNavigation {
@@ -50,6 +98,8 @@ pub struct PedestrianNavigation {
     pub side: f32,  // 1.0 or -1.0 for left/right sidewalk
     pub destination_node: NodeIndex,
     pub previous_node: Option<NodeIndex>,
+    /// Where the trip ends.
+    pub goal: PedestrianGoal,
     /// True if pedestrian is waiting at a crosswalk.
     pub waiting_at_crosswalk: bool,
     /// Time spent waiting (for impatient crossing).
@@ -59,7 +109,10 @@ pub struct PedestrianNavigation {
 /// Configuration for pedestrians.
 #[derive(Resource)]
 pub struct PedestrianConfig {
-    pub target_count: usize,
+    /// Most pedestrians out at once; trips beyond this go unseen.
+    pub max_pedestrians: usize,
+    /// Most pedestrians set off per frame.
+    pub spawns_per_frame: usize,
     pub base_speed: f32,
     pub speed_variance: f32,
     pub body_height: f32,
@@ -67,16 +120,19 @@ pub struct PedestrianConfig {
     pub head_radius: f32,
     pub sidewalk_offset: f32,
     pub seed: u64,
-    /// Radius to check for commercial buildings for crowd density.
-    pub commercial_crowd_radius: f32,
-    /// Multiplier for spawn chance near commercial buildings.
-    pub commercial_crowd_multiplier: f32,
+    /// Farthest a trip can start from a street with sidewalks.
+    pub street_search_radius: f32,
+    /// Pedestrians per 100 m of sidewalk shown as crowded in the overlay.
+    pub crowded_density: f32,
+    /// How often sidewalk density is recounted (seconds).
+    pub stats_interval: f32,
 }
 
 impl Default for PedestrianConfig {
     fn default() -> Self {
         Self {
-            target_count: 50,
+            max_pedestrians: 150,
+            spawns_per_frame: 10,
             base_speed: 1.4,         // ~5 km/h walking speed
             speed_variance: 0.3,     // Some walk faster/slower
             body_height: 1.5,
@@ -84,25 +140,95 @@ impl Default for PedestrianConfig {
             head_radius: 0.15,
             sidewalk_offset: 4.0,    // Distance from road center to sidewalk
             seed: 88888,
-            commercial_crowd_radius: 40.0,
-            commercial_crowd_multiplier: 3.0,
+            street_search_radius: 150.0,
+            crowded_density: 6.0,
+            stats_interval: 1.0,
         }
     }
 }
 
-/// Marker that pedestrian spawning has been initialized.
+/// Walks waiting to set off: where each starts and where it is going.
+#[derive(Resource, Default)]
+pub struct PedestrianTrips {
+    pub queued: Vec<(Vec2, PedestrianGoal)>,
+}
+
+/// Pedestrian figures, city-wide and per sidewalk.
 #[derive(Resource, Default)]
-pub struct PedestriansInitialized(pub bool);
+pub struct PedestrianStats {
+    /// Pedestrians out walking right now.
+    pub walking: usize,
+    /// Walks set off and finished since the city was founded.
+    pub trips: u32,
+    pub arrived: u32,
+    /// Pedestrians on each street's two sidewalks.
+    pub per_edge: HashMap<EdgeIndex, u32>,
+    /// Pedestrians per 100 m of sidewalk, averaged over sidewalks with
+    /// anyone on them, and on the busiest.
+    pub average_density: f32,
+    pub peak_density: f32,
+    timer: f32,
+}
 
-/// Run condition: spawn pedestrians when roads exist and we haven't reached target count.
-fn should_spawn_pedestrians(
-    road_mesh_query: Query<&RoadMeshGenerated>,
-    pedestrian_query: Query<&Pedestrian>,
-    config: Res<PedestrianConfig>,
-    initialized: Res<PedestriansInitialized>,
-) -> bool {
-    !road_mesh_query.is_empty()
-        && (pedestrian_query.iter().count() < config.target_count || !initialized.0)
+impl PedestrianStats {
+    /// Pedestrians per 100 m along `edge`'s sidewalks.
+    pub fn density(&self, edge: EdgeIndex, road_graph: &RoadGraph) -> f32 {
+        let count = self.per_edge.get(&edge).copied().unwrap_or(0);
+        road_graph.edge_by_index(edge).map_or(0.0, |road| sidewalk_density(count, road.length))
+    }
+}
+
+/// Pedestrians per 100 m of sidewalk, counting both sides of the street.
+pub fn sidewalk_density(pedestrians: u32, street_length: f32) -> f32 {
+    if street_length <= 0.0 {
+        return 0.0;
+    }
+    pedestrians as f32 * 100.0 / (street_length * 2.0)
+}
+
+/// Only major and minor streets have sidewalks.
+fn has_sidewalks(edge: &RoadEdge) -> bool {
+    matches!(edge.road_type, RoadType::Major | RoadType::Minor)
+}
+
+/// Street corner with sidewalks nearest `position`, within `radius`.
+fn sidewalk_node_near(road_graph: &RoadGraph, position: Vec2, radius: f32) -> Option<NodeIndex> {
+    road_graph.nearest_node_where(position, radius, |idx| {
+        road_graph.edges_of_node(idx).any(|e| road_graph.edge_by_index(e).is_some_and(has_sidewalks))
+    })
+}
+
+/// The street out of `node` leading furthest down `field`: the edge,
+/// whether it is walked forward, and the corner at its far end. None when
+/// no street brings a pedestrian any closer than `node` itself.
+pub fn next_sidewalk(road_graph: &RoadGraph, field: &FlowField, node: NodeIndex) -> Option<(EdgeIndex, bool, NodeIndex)> {
+    let here = field.distance_at(road_graph.node_by_index(node)?.position)?;
+    road_graph
+        .edges_of_node(node)
+        .filter(|&e| road_graph.edge_by_index(e).is_some_and(has_sidewalks))
+        .filter_map(|e| {
+            let (a, b) = road_graph.edge_endpoints(e)?;
+            let (forward, far) = if a == node { (true, b) } else { (false, a) };
+            let distance = field.distance_at(road_graph.node_by_index(far)?.position)?;
+            Some((e, forward, far, distance))
+        })
+        .filter(|&(.., distance)| distance < here)
+        .min_by(|a, b| a.3.total_cmp(&b.3))
+        .map(|(e, forward, far, _)| (e, forward, far))
+}
+
+/// The flow field leading to a goal, generating a building's on first use.
+fn goal_field<'a>(
+    goal: PedestrianGoal,
+    city: &'a CityFlowFields,
+    cache: &'a mut FlowFieldCache,
+    costs: &WalkCosts,
+    config: &FlowFieldConfig,
+) -> Option<&'a FlowField> {
+    match goal {
+        PedestrianGoal::Nearest(destination) => city.field(destination),
+        PedestrianGoal::Building { entity, position } => cache.field_to(entity, position, costs, config),
+    }
 }
 
 // Clothing color palette
@@ -126,115 +252,147 @@ const SKIN_TONES: &[(f32, f32, f32)] = &[
     (0.36, 0.25, 0.18), // Dark
 ];
 
+/// Meshes and materials shared by every pedestrian.
+struct PedestrianAssets {
+    body: Handle<Mesh>,
+    head: Handle<Mesh>,
+    clothing: Vec<Handle<StandardMaterial>>,
+    skin: Vec<Handle<StandardMaterial>>,
+}
+
+/// Queue a walk for each trip made on foot: walking commutes, riders
+/// leaving their stop, and outings to shops and parks.
+#[allow(clippy::too_many_arguments)]
+fn queue_pedestrian_trips(
+    mut mode_trips: EventReader<TripModeChosen>,
+    mut outings: EventReader<OutingStarted>,
+    ridership: Res<Ridership>,
+    network: Res<TransitNetwork>,
+    time_of_day: Option<Res<TimeOfDay>>,
+    buildings: Query<&Transform, With<Building>>,
+    mut trips: ResMut<PedestrianTrips>,
+    mut seen_alighted: Local<HashMap<(LineKey, usize), u32>>,
+) {
+    let position = |entity: Entity| buildings.get(entity).ok().map(|t| Vec2::new(t.translation.x, t.translation.z));
+
+    for chosen in mode_trips.read() {
+        if chosen.mode != TravelMode::Walk {
+            continue;
+        }
+        if let Some(from) = position(chosen.trip.from) {
+            let goal = PedestrianGoal::Building {
+                entity: chosen.trip.to,
+                position: chosen.to,
+            };
+            trips.queued.push((from, goal));
+        }
+    }
+
+    for outing in outings.read() {
+        let destination = if outing.activity == CitizenState::Shopping {
+            FlowDestination::Shops
+        } else {
+            FlowDestination::Parks
+        };
+        if let Some(from) = position(outing.from) {
+            trips.queued.push((from, PedestrianGoal::Nearest(destination)));
+        }
+    }
+
+    // Riders walk on to work in the morning and home later in the day
+    let destination = if time_of_day.is_some_and(|tod| tod.hour() >= 12.0) {
+        FlowDestination::Homes
+    } else {
+        FlowDestination::Workplaces
+    };
+    for line in &network.lines {
+        let Some(record) = ridership.lines.get(&line.key) else {
+            continue;
+        };
+        for (stop, (riders, &position)) in record.stops.iter().zip(&line.stops).enumerate() {
+            let seen = seen_alighted.entry((line.key, stop)).or_insert(riders.alighted);
+            let new_riders = riders.alighted.saturating_sub(*seen);
+            *seen = riders.alighted;
+            for _ in 0..new_riders {
+                trips.queued.push((position, PedestrianGoal::Nearest(destination)));
+            }
+        }
+    }
+}
+
+/// Set queued walks off from the street corner nearest where they start,
+/// while there is room for more pedestrians.
+#[allow(clippy::too_many_arguments)]
 fn spawn_pedestrians(
     mut commands: Commands,
-    road_graph: Res<RoadGraph>,
     config: Res<PedestrianConfig>,
-    terrain_config: Res<TerrainConfig>,
+    road_graph: Res<RoadGraph>,
+    terrain: Res<HeightMap>,
+    city_fields: Res<CityFlowFields>,
+    walk_costs: Res<WalkCosts>,
+    field_config: Res<FlowFieldConfig>,
+    mut cache: ResMut<FlowFieldCache>,
+    mut trips: ResMut<PedestrianTrips>,
+    mut stats: ResMut<PedestrianStats>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    pedestrian_query: Query<&Pedestrian>,
-    buildings_query: Query<(&Building, &Transform)>,
-    mut initialized: ResMut<PedestriansInitialized>,
+    pedestrian_query: Query<(), With<PedestrianNavigation>>,
     mut local_rng: Local<Option<StdRng>>,
+    mut assets: Local<Option<PedestrianAssets>>,
 ) {
-    let rng = local_rng.get_or_insert_with(|| StdRng::seed_from_u64(config.seed));
-
-    let current_count = pedestrian_query.iter().count();
-    if current_count >= config.target_count {
-        initialized.0 = true;
+    if trips.queued.is_empty() {
         return;
     }
-
-    // Collect commercial building positions for crowd density
-    let commercial_positions: Vec<Vec2> = buildings_query
-        .iter()
-        .filter(|(b, _)| b.building_type == BuildingArchetype::Commercial)
-        .map(|(_, t)| Vec2::new(t.translation.x, t.translation.z))
-        .collect();
-
-    // Collect valid nodes (intersections or endpoints on Major/Minor roads)
-    // and calculate crowd weight based on proximity to commercial areas
-    let valid_nodes: Vec<(NodeIndex, f32)> = road_graph
-        .nodes()
-        .filter_map(|(idx, node)| {
-            let neighbor_count = road_graph.neighbors(idx).count();
-            if neighbor_count >= 1 && node.node_type != RoadNodeType::DeadEnd {
-                // Calculate crowd weight based on nearby commercial buildings
-                let node_pos = node.position;
-                let mut weight = 1.0;
-                for &comm_pos in &commercial_positions {
-                    let dist = node_pos.distance(comm_pos);
-                    if dist < config.commercial_crowd_radius {
-                        let proximity = 1.0 - (dist / config.commercial_crowd_radius);
-                        weight += proximity * (config.commercial_crowd_multiplier - 1.0);
-                    }
-                }
-                Some((idx, weight))
-            } else {
-                None
-            }
-        })
-        .collect();
-
-    if valid_nodes.is_empty() {
-        warn!("No valid nodes found for pedestrian spawning");
-        initialized.0 = true;
+    // Trips made while the sidewalks are full go unseen
+    let room = config.max_pedestrians.saturating_sub(pedestrian_query.iter().count());
+    let keep = trips.queued.len().min(room);
+    let start = trips.queued.len() - keep;
+    trips.queued.drain(..start);
+    let count = trips.queued.len().min(config.spawns_per_frame);
+    let batch: Vec<(Vec2, PedestrianGoal)> = trips.queued.drain(..count).collect();
+    if batch.is_empty() || !city_fields.initialized {
         return;
     }
 
-    // Calculate total weight for weighted random selection
-    let total_weight: f32 = valid_nodes.iter().map(|(_, w)| w).sum();
-
-    // Create meshes
-    let body_mesh = meshes.add(Cylinder::new(config.body_radius, config.body_height));
-    let head_mesh = meshes.add(Sphere::new(config.head_radius));
-
-    let terrain = TerrainSampler::new(&terrain_config);
-
-    // Spawn pedestrians up to target count
-    let to_spawn = (config.target_count - current_count).min(10);
-
-    for _ in 0..to_spawn {
-        // Pick random starting node with weighted selection (favor commercial areas)
-        let roll: f32 = rng.gen::<f32>() * total_weight;
-        let mut cumulative = 0.0;
-        let mut start_node_idx = valid_nodes[0].0;
-        for &(node, weight) in &valid_nodes {
-            cumulative += weight;
-            if roll < cumulative {
-                start_node_idx = node;
-                break;
-            }
-        }
-
-        // Get edges from this node (prefer Major/Minor roads with sidewalks)
-        let edges: Vec<EdgeIndex> = road_graph
-            .edges_of_node(start_node_idx)
-            .filter(|&e| {
-                if let Some(edge) = road_graph.edge_by_index(e) {
-                    matches!(edge.road_type, RoadType::Major | RoadType::Minor)
-                } else {
-                    false
-                }
+    let rng = local_rng.get_or_insert_with(|| StdRng::seed_from_u64(config.seed));
+    let assets = assets.get_or_insert_with(|| PedestrianAssets {
+        body: meshes.add(Cylinder::new(config.body_radius, config.body_height)),
+        head: meshes.add(Sphere::new(config.head_radius)),
+        clothing: CLOTHING_COLORS
+            .iter()
+            .map(|&(r, g, b)| {
+                materials.add(StandardMaterial {
+                    base_color: Color::srgb(r, g, b),
+                    perceptual_roughness: 0.8,
+                    ..default()
+                })
+            })
+            .collect(),
+        skin: SKIN_TONES
+            .iter()
+            .map(|&(r, g, b)| {
+                materials.add(StandardMaterial {
+                    base_color: Color::srgb(r, g, b),
+                    perceptual_roughness: 0.9,
+                    ..default()
+                })
             })
-            .collect();
+            .collect(),
+    });
 
-        if edges.is_empty() {
+    for (origin, goal) in batch {
+        let Some(start_node) = sidewalk_node_near(&road_graph, origin, config.street_search_radius) else {
             continue;
-        }
-
-        // Pick random edge
-        let edge_idx = edges[rng.gen_range(0..edges.len())];
-        let Some((node_a, node_b)) = road_graph.edge_endpoints(edge_idx) else {
+        };
+        let Some(field) = goal_field(goal, &city_fields, &mut cache, &walk_costs, &field_config) else {
             continue;
         };
-
-        // Determine direction
-        let (forward, dest_node) = if node_a == start_node_idx {
-            (true, node_b)
-        } else {
-            (false, node_a)
+        // Trips round the corner are not worth showing
+        let Some((edge_idx, forward, dest_node)) = next_sidewalk(&road_graph, field, start_node) else {
+            continue;
+        };
+        let Some(edge) = road_graph.edge_by_index(edge_idx) else {
+            continue;
         };
 
         // Random speed with variance
@@ -243,72 +401,52 @@ fn spawn_pedestrians(
         // Random side of street
         let side = if rng.gen_bool(0.5) { 1.0 } else { -1.0 };
 
-        // Random clothing color
-        let (cr, cg, cb) = CLOTHING_COLORS[rng.gen_range(0..CLOTHING_COLORS.len())];
-        let body_material = materials.add(StandardMaterial {
-            base_color: Color::srgb(cr, cg, cb),
-            perceptual_roughness: 0.8,
-            ..default()
-        });
-
-        // Random skin tone
-        let (sr, sg, sb) = SKIN_TONES[rng.gen_range(0..SKIN_TONES.len())];
-        let head_material = materials.add(StandardMaterial {
-            base_color: Color::srgb(sr, sg, sb),
-            perceptual_roughness: 0.9,
-            ..default()
-        });
-
         // Get initial position
-        let edge = road_graph.edge_by_index(edge_idx).unwrap();
-        let points = &edge.points;
         let initial_progress = if forward { 0.0 } else { 1.0 };
-        let (pos, dir) = interpolate_edge_position(points, initial_progress);
+        let (pos, dir) = interpolate_edge_position(&edge.points, initial_progress);
 
         // Calculate sidewalk position
         let perp = Vec2::new(-dir.y, dir.x);
         let sidewalk_pos = pos + perp * config.sidewalk_offset * side;
-
-        let terrain_height = terrain.sample(sidewalk_pos.x, sidewalk_pos.y);
-        let body_y = terrain_height + config.body_height / 2.0;
-        let head_y = terrain_height + config.body_height + config.head_radius;
+        let body_y = terrain.sample_world(sidewalk_pos) + config.body_height / 2.0;
 
         // Calculate facing direction
         let facing_dir = if forward { dir } else { -dir };
         let angle = facing_dir.y.atan2(facing_dir.x);
         let rotation = Quat::from_rotation_y(-angle);
 
-        // Spawn body with navigation
-        commands.spawn((
-            Mesh3d(body_mesh.clone()),
-            MeshMaterial3d(body_material),
-            Transform::from_xyz(sidewalk_pos.x, body_y, sidewalk_pos.y).with_rotation(rotation),
-            Pedestrian,
-            PedestrianNavigation {
-                current_edge: edge_idx,
-                forward,
-                progress: initial_progress,
-                speed,
-                side,
-                destination_node: dest_node,
-                previous_node: Some(start_node_idx),
-                waiting_at_crosswalk: false,
-                wait_time: 0.0,
-            },
-        ));
-
-        // Spawn head (follows the body)
-        commands.spawn((
-            Mesh3d(head_mesh.clone()),
-            MeshMaterial3d(head_material),
-            Transform::from_xyz(sidewalk_pos.x, head_y, sidewalk_pos.y),
-            Pedestrian,
-        ));
-    }
-
-    if current_count + to_spawn >= config.target_count {
-        initialized.0 = true;
-        info!("Spawned {} pedestrians", config.target_count);
+        let clothing = assets.clothing[rng.gen_range(0..assets.clothing.len())].clone();
+        let skin = assets.skin[rng.gen_range(0..assets.skin.len())].clone();
+
+        // Spawn body with navigation; the head rides on top
+        commands
+            .spawn((
+                Mesh3d(assets.body.clone()),
+                MeshMaterial3d(clothing),
+                Transform::from_xyz(sidewalk_pos.x, body_y, sidewalk_pos.y).with_rotation(rotation),
+                Pedestrian,
+                PedestrianNavigation {
+                    current_edge: edge_idx,
+                    forward,
+                    progress: initial_progress,
+                    speed,
+                    side,
+                    destination_node: dest_node,
+                    previous_node: Some(start_node),
+                    goal,
+                    waiting_at_crosswalk: false,
+                    wait_time: 0.0,
+                },
+            ))
+            .with_children(|body| {
+                body.spawn((
+                    Mesh3d(assets.head.clone()),
+                    MeshMaterial3d(skin),
+                    Transform::from_xyz(0.0, config.body_height / 2.0 + config.head_radius, 0.0),
+                    Pedestrian,
+                ));
+            });
+        stats.trips += 1;
     }
 }
 
@@ -345,9 +483,9 @@ fn check_crosswalk_waiting(
         let mut should_wait = false;
         for controller in traffic_lights.iter() {
             if controller.node_index == nav.destination_node {
-                // Check if pedestrian should wait (not green for pedestrians)
-                // Pedestrians cross when traffic is red (traffic stops)
-                if controller.phase != LightPhase::Red {
+                // Walk with the traffic running alongside: cross the side
+                // roads only while our own road has green
+                if controller.phase_for(nav.current_edge) != LightPhase::Green {
                     should_wait = true;
                 }
                 break;
@@ -406,15 +544,19 @@ fn pedestrian_movement(
     }
 }
 
-/// Handle pedestrians reaching the end of their current edge.
+/// At each corner, turn onto the street leading closest to the destination,
+/// or go indoors if none leads any closer.
+#[allow(clippy::too_many_arguments)]
 fn pedestrian_edge_transition(
     mut commands: Commands,
     road_graph: Res<RoadGraph>,
+    city_fields: Res<CityFlowFields>,
+    walk_costs: Res<WalkCosts>,
+    field_config: Res<FlowFieldConfig>,
+    mut cache: ResMut<FlowFieldCache>,
+    mut stats: ResMut<PedestrianStats>,
     mut pedestrians: Query<(Entity, &mut PedestrianNavigation), With<Pedestrian>>,
-    mut local_rng: Local<Option<StdRng>>,
 ) {
-    let rng = local_rng.get_or_insert_with(|| StdRng::seed_from_u64(66666));
-
     for (entity, mut nav) in pedestrians.iter_mut() {
         // Check if we've reached the end of the edge
         let at_end = (nav.forward && nav.progress >= 1.0) || (!nav.forward && nav.progress <= 0.0);
@@ -424,40 +566,14 @@ fn pedestrian_edge_transition(
         }
 
         let current_node = nav.destination_node;
+        let next = goal_field(nav.goal, &city_fields, &mut cache, &walk_costs, &field_config)
+            .and_then(|field| next_sidewalk(&road_graph, field, current_node));
 
-        // Get all edges from this node (prefer Major/Minor roads)
-        let edges: Vec<EdgeIndex> = road_graph
-            .edges_of_node(current_node)
-            .filter(|&e| {
-                if e == nav.current_edge {
-                    return false; // Avoid U-turns
-                }
-                if let Some(edge) = road_graph.edge_by_index(e) {
-                    matches!(edge.road_type, RoadType::Major | RoadType::Minor)
-                } else {
-                    false
-                }
-            })
-            .collect();
-
-        if edges.is_empty() {
-            // Dead end or no suitable roads - despawn and respawn elsewhere
-            commands.entity(entity).despawn();
+        let Some((next_edge, forward, dest_node)) = next else {
+            // No street leads any closer: go indoors
+            commands.entity(entity).despawn_recursive();
+            stats.arrived += 1;
             continue;
-        }
-
-        // Pick random next edge
-        let next_edge = edges[rng.gen_range(0..edges.len())];
-        let Some((node_a, node_b)) = road_graph.edge_endpoints(next_edge) else {
-            commands.entity(entity).despawn();
-            continue;
-        };
-
-        // Determine direction on new edge
-        let (forward, dest_node) = if node_a == current_node {
-            (true, node_b)
-        } else {
-            (false, node_a)
         };
 
         // Update navigation state
@@ -474,11 +590,10 @@ fn pedestrian_edge_transition(
 /// Update pedestrian transforms based on their navigation state.
 fn pedestrian_transform_sync(
     road_graph: Res<RoadGraph>,
-    terrain_config: Res<TerrainConfig>,
+    terrain: Res<HeightMap>,
     config: Res<PedestrianConfig>,
     mut pedestrians: Query<(&PedestrianNavigation, &mut Transform), With<Pedestrian>>,
 ) {
-    let terrain = TerrainSampler::new(&terrain_config);
 
     for (nav, mut transform) in pedestrians.iter_mut() {
         let Some(edge) = road_graph.edge_by_index(nav.current_edge) else {
@@ -496,7 +611,7 @@ fn pedestrian_transform_sync(
         let sidewalk_pos = pos + perp * config.sidewalk_offset * nav.side;
 
         // Update position with terrain height
-        let terrain_height = terrain.sample(sidewalk_pos.x, sidewalk_pos.y);
+        let terrain_height = terrain.sample_world(sidewalk_pos);
         let body_y = terrain_height + config.body_height / 2.0;
 
         transform.translation.x = sidewalk_pos.x;
@@ -512,6 +627,39 @@ fn pedestrian_transform_sync(
     }
 }
 
+/// Count pedestrians on each street's sidewalks.
+fn update_pedestrian_stats(
+    time: Res<Time>,
+    config: Res<PedestrianConfig>,
+    road_graph: Res<RoadGraph>,
+    pedestrians: Query<&PedestrianNavigation>,
+    mut stats: ResMut<PedestrianStats>,
+) {
+    stats.timer += time.delta_secs();
+    if stats.timer < config.stats_interval {
+        return;
+    }
+    stats.timer = 0.0;
+
+    let mut per_edge: HashMap<EdgeIndex, u32> = HashMap::new();
+    for nav in &pedestrians {
+        *per_edge.entry(nav.current_edge).or_default() += 1;
+    }
+    let densities: Vec<f32> = per_edge
+        .iter()
+        .filter_map(|(&edge, &count)| road_graph.edge_by_index(edge).map(|road| sidewalk_density(count, road.length)))
+        .collect();
+
+    stats.walking = per_edge.values().sum::<u32>() as usize;
+    stats.average_density = if densities.is_empty() {
+        0.0
+    } else {
+        densities.iter().sum::<f32>() / densities.len() as f32
+    };
+    stats.peak_density = densities.iter().copied().fold(0.0, f32::max);
+    stats.per_edge = per_edge;
+}
+
 /// Interpolate position and direction along edge waypoints.
 fn interpolate_edge_position(points: &[Vec2], progress: f32) -> (Vec2, Vec2) {
     if points.is_empty() {
@@ -556,39 +704,54 @@ fn interpolate_edge_position(points: &[Vec2], progress: f32) -> (Vec2, Vec2) {
     (last, dir)
 }
 
-/// Helper struct for sampling terrain height.
-struct TerrainSampler {
-    perlin: Perlin,
-    noise_scale: f32,
-    height_scale: f32,
-    octaves: u32,
-}
-
-impl TerrainSampler {
-    fn new(config: &TerrainConfig) -> Self {
-        Self {
-            perlin: Perlin::new(config.seed),
-            noise_scale: config.noise_scale,
-            height_scale: config.height_scale,
-            octaves: config.octaves,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::procgen::river::River;
+    use crate::procgen::roads::RoadNodeType;
+    use smallvec::smallvec;
+
+    /// A street running east from A through B to C, with a side street north
+    /// from B to D. Returns the graph and its corners in that order.
+    fn tee() -> (RoadGraph, [NodeIndex; 4]) {
+        let mut graph = RoadGraph::default();
+        let corners = [Vec2::new(0.0, 0.0), Vec2::new(100.0, 0.0), Vec2::new(200.0, 0.0), Vec2::new(100.0, 100.0)]
+            .map(|p| graph.add_node(p, RoadNodeType::Intersection));
+        let [a, b, c, d] = corners;
+        for (from, to) in [(a, b), (b, c), (b, d)] {
+            let points = smallvec![
+                graph.node_by_index(from).unwrap().position,
+                graph.node_by_index(to).unwrap().position
+            ];
+            graph.add_edge_data(from, to, RoadEdge::new(points, RoadType::Minor));
         }
+        (graph, corners)
     }
 
-    fn sample(&self, x: f32, z: f32) -> f32 {
-        let mut height = 0.0;
-        let mut amplitude = 1.0;
-        let mut frequency = self.noise_scale;
-        let mut max_amplitude = 0.0;
-
-        for _ in 0..self.octaves {
-            let sample_x = x as f64 * frequency as f64;
-            let sample_z = z as f64 * frequency as f64;
-            height += self.perlin.get([sample_x, sample_z]) as f32 * amplitude;
-            max_amplitude += amplitude;
-            amplitude *= 0.5;
-            frequency *= 2.0;
-        }
+    #[test]
+    fn pedestrians_follow_the_field_and_stop_nearest_the_goal() {
+        let (graph, [a, b, _, d]) = tee();
+        let costs = WalkCosts::build(&FlowFieldConfig::default(), &graph, &River::default());
+        let field = costs.flow_field(&[Vec2::new(110.0, 120.0)]).unwrap();
+
+        let (edge, forward, far) = next_sidewalk(&graph, &field, a).unwrap();
+        assert_eq!((graph.edge_endpoints(edge), forward, far), (Some((a, b)), true, b));
+        // Up the side street, not on along the main one
+        let (edge, forward, far) = next_sidewalk(&graph, &field, b).unwrap();
+        assert_eq!((graph.edge_endpoints(edge), forward, far), (Some((b, d)), true, d));
+        // No street leads any closer than the corner by the goal
+        assert_eq!(next_sidewalk(&graph, &field, d), None);
+    }
+
+    #[test]
+    fn density_counts_both_sidewalks() {
+        assert_eq!(sidewalk_density(3, 50.0), 3.0);
+        assert_eq!(sidewalk_density(5, 0.0), 0.0);
 
-        (height / max_amplitude) * self.height_scale
+        let (graph, _) = tee();
+        let mut stats = PedestrianStats::default();
+        stats.per_edge.insert(EdgeIndex::new(2), 4);
+        assert_eq!(stats.density(EdgeIndex::new(2), &graph), 2.0);
+        assert_eq!(stats.density(EdgeIndex::new(0), &graph), 0.0);
     }
 }
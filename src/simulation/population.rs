@@ -1,11 +1,14 @@
 //! Population tracking and growth system.
 //!
-//! Population growth is influenced by:
+//! People are born and die in the city, and move in and out through its
+//! connections to the region (see `region`); nobody arrives in a city with
+//! no highway or rail connection. How many come or go is influenced by:
 //! - Housing availability
 //! - Employment rate
 //! - City finances
 //! - Service coverage (health, education, parks)
 //! - Commute quality
+//! - Utilities (people move out of homes without power, water or sewage)
 
 use bevy::prelude::*;
 
@@ -14,6 +17,7 @@ use crate::game_state::GameState;
 use super::commute::CommuteStats;
 use super::demand::CityStats;
 use super::economy::CityBudget;
+use super::region::{migration, Region, RegionConfig};
 use super::services::ServiceEffects;
 
 pub struct PopulationPlugin;
@@ -32,20 +36,24 @@ impl Plugin for PopulationPlugin {
 /// Population configuration.
 #[derive(Resource)]
 pub struct PopulationConfig {
-    /// Base growth rate per tick when conditions are good.
+    /// Natural growth (births over deaths) per tick when conditions are good.
     pub base_growth_rate: f32,
     /// How often to update population (seconds).
     pub update_interval: f32,
     /// Minimum population to start with.
     pub starting_population: u32,
+    /// Share of those living in homes cut off from utilities who move out
+    /// each update.
+    pub utility_exodus: f32,
 }
 
 impl Default for PopulationConfig {
     fn default() -> Self {
         Self {
-            base_growth_rate: 0.02, // 2% growth per update
+            base_growth_rate: 0.005, // 0.5% growth per update
             update_interval: 2.0,
             starting_population: 0,
+            utility_exodus: 0.2,
         }
     }
 }
@@ -77,9 +85,12 @@ impl Default for Population {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn update_population(
     time: Res<Time>,
     config: Res<PopulationConfig>,
+    region_config: Res<RegionConfig>,
+    mut region: ResMut<Region>,
     mut population: ResMut<Population>,
     stats: Res<CityStats>,
     budget: Res<CityBudget>,
@@ -152,15 +163,14 @@ fn update_population(
         growth_modifier *= 0.85;
     }
 
-    // Calculate actual growth
-    let base_growth = if population.total > 0 {
-        (population.total as f32 * config.base_growth_rate * growth_modifier) as i32
-    } else if stats.housing_capacity > 0 {
-        // Initial population - some people move in to available housing
-        (stats.housing_capacity as f32 * 0.1).max(1.0) as i32
-    } else {
-        0
-    };
+    // Calculate actual growth: natural growth, plus people moving in to
+    // empty homes and out of an unattractive city through the connections
+    let natural = (population.total as f32 * config.base_growth_rate * growth_modifier) as i32;
+    let vacancies = stats.housing_capacity.saturating_sub(population.total);
+    let (immigrants, emigrants) = migration(&region_config, &region, growth_modifier, population.total, vacancies);
+    region.immigrants = immigrants;
+    region.emigrants = emigrants;
+    let base_growth = natural + immigrants as i32 - emigrants as i32;
 
     // Apply growth (minimum 0 population)
     let new_pop = (population.total as i32 + base_growth).max(0) as u32;
@@ -168,6 +178,10 @@ fn update_population(
     // Cap at housing capacity
     let new_pop = new_pop.min(stats.housing_capacity.max(population.total));
 
+    // Residents of homes cut off from power, water or sewage move out
+    let cut_off = new_pop.saturating_sub(stats.housing_capacity).min(stats.unserviced_housing);
+    let new_pop = new_pop - (cut_off as f32 * config.utility_exodus).ceil() as u32;
+
     population.change = new_pop as i32 - old_pop as i32;
     population.total = new_pop;
     population.growth_rate = if old_pop > 0 {
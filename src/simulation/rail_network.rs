@@ -0,0 +1,734 @@
+//! Player-built rail and metro network.
+//!
+//! Track forms its own graph, separate from the roads, and each segment runs
+//! on a viaduct, on the ground or in a tunnel. Stations sit on track nodes,
+//! and a line runs between its stations by the shortest path over the track,
+//! with its trains shuttling back and forth along it.
+//!
+//! Fixed-block signalling keeps trains apart: each track segment is a block
+//! in each direction of travel, and a train may only enter a block no other
+//! train holds. A train holds every block its cars stand in, so a following
+//! train waits at the signal until the one ahead has cleared it.
+
+use bevy::prelude::*;
+use petgraph::algo::astar;
+use petgraph::stable_graph::{EdgeIndex, NodeIndex, StableUnGraph};
+use std::collections::HashMap;
+
+use crate::render::elevated_rail::ElevatedRailConfig;
+use crate::render::train_cars::{spawn_train_cars, TrainAssets, TrainCar, TrainConfig};
+use crate::world::terrain::HeightMap;
+
+use super::bus_routes::TransitLedger;
+use super::ridership::{LineKey, Ridership};
+
+pub struct RailNetworkPlugin;
+
+impl Plugin for RailNetworkPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RailNetworkConfig>()
+            .init_resource::<RailNetwork>()
+            .init_resource::<RailBlocks>()
+            .add_event::<RailNetworkEdited>()
+            .add_systems(Update, (replan_metro_lines, sync_line_trains, update_line_trains).chain());
+    }
+}
+
+/// Depth of the rails below the ground in a tunnel.
+pub const TUNNEL_DEPTH: f32 = 12.0;
+/// Most trains a line may run.
+pub const MAX_LINE_TRAINS: usize = 12;
+/// How far short of a red signal a train stops.
+const SIGNAL_GAP: f32 = 2.0;
+
+/// Configuration for the rail network.
+#[derive(Resource)]
+pub struct RailNetworkConfig {
+    /// How close a click must be to a track node or segment to pick it.
+    pub snap_distance: f32,
+    /// Cars in each metro train.
+    pub cars_per_train: usize,
+}
+
+impl Default for RailNetworkConfig {
+    fn default() -> Self {
+        Self {
+            snap_distance: 12.0,
+            cars_per_train: 3,
+        }
+    }
+}
+
+/// Sent when track, stations or lines are edited.
+#[derive(Event)]
+pub struct RailNetworkEdited;
+
+/// Where a track segment runs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TrackLevel {
+    /// On a viaduct above the streets.
+    Elevated,
+    /// On the ground.
+    #[default]
+    AtGrade,
+    /// In a tunnel.
+    Underground,
+}
+
+impl TrackLevel {
+    pub const ALL: [TrackLevel; 3] = [TrackLevel::AtGrade, TrackLevel::Elevated, TrackLevel::Underground];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TrackLevel::Elevated => "Elevated",
+            TrackLevel::AtGrade => "At grade",
+            TrackLevel::Underground => "Underground",
+        }
+    }
+
+    /// Height of the rails above the ground.
+    pub fn height(self, viaduct_height: f32) -> f32 {
+        match self {
+            TrackLevel::Elevated => viaduct_height + 0.3,
+            TrackLevel::AtGrade => 0.3,
+            TrackLevel::Underground => -TUNNEL_DEPTH,
+        }
+    }
+}
+
+/// A point where track segments meet, possibly a station.
+#[derive(Clone, Debug)]
+pub struct RailNode {
+    pub position: Vec2,
+    pub station: Option<String>,
+}
+
+/// A track segment between two nodes.
+#[derive(Clone, Copy, Debug)]
+pub struct RailTrack {
+    pub level: TrackLevel,
+    pub length: f32,
+}
+
+pub type RailGraph = StableUnGraph<RailNode, RailTrack>;
+
+/// The path a line takes over the track.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MetroRoute {
+    /// Track nodes in order, from the first station served to the last.
+    pub nodes: Vec<NodeIndex>,
+    /// Distance along the route to each node.
+    pub distances: Vec<f32>,
+    /// Stations served in order; those the track does not reach are skipped.
+    pub served: Vec<NodeIndex>,
+    /// Distance along the route to each station served.
+    pub stops: Vec<f32>,
+}
+
+impl MetroRoute {
+    pub fn length(&self) -> f32 {
+        self.distances.last().copied().unwrap_or(0.0)
+    }
+}
+
+/// A point along a route.
+#[derive(Clone, Copy, Debug)]
+pub struct RoutePoint {
+    pub position: Vec2,
+    pub heading: Vec2,
+    pub level: TrackLevel,
+    /// Ground height under the track, taken straight between its nodes.
+    pub ground: f32,
+}
+
+/// Point `distance` along a route.
+pub fn route_point(graph: &RailGraph, route: &MetroRoute, distance: f32, terrain: &HeightMap) -> Option<RoutePoint> {
+    if route.nodes.len() < 2 {
+        return None;
+    }
+    let distance = distance.clamp(0.0, route.length());
+    let segment = route.distances.windows(2).position(|d| distance <= d[1]).unwrap_or(route.nodes.len() - 2);
+    let (a, b) = (route.nodes[segment], route.nodes[segment + 1]);
+    let track = graph.find_edge(a, b).map(|edge| graph[edge])?;
+    let (from, to) = (graph[a].position, graph[b].position);
+    let t = ((distance - route.distances[segment]) / track.length.max(0.01)).clamp(0.0, 1.0);
+    Some(RoutePoint {
+        position: from.lerp(to, t),
+        heading: (to - from).normalize_or_zero(),
+        level: track.level,
+        ground: terrain.sample_world(from).lerp(terrain.sample_world(to), t),
+    })
+}
+
+/// Plan a route calling at `stations` in order, by the shortest path over
+/// the track from each to the next.
+pub fn plan_metro_route(graph: &RailGraph, stations: &[NodeIndex]) -> MetroRoute {
+    let mut route = MetroRoute::default();
+    for &station in stations {
+        if graph.node_weight(station).is_none() {
+            continue;
+        }
+        let Some(&last) = route.nodes.last() else {
+            route.nodes.push(station);
+            route.distances.push(0.0);
+            route.served.push(station);
+            route.stops.push(0.0);
+            continue;
+        };
+        if last == station {
+            continue;
+        }
+        let Some((_, path)) = astar(graph, last, |n| n == station, |e| e.weight().length, |_| 0.0) else {
+            continue;
+        };
+        for pair in path.windows(2) {
+            let length = graph.find_edge(pair[0], pair[1]).map_or(0.0, |edge| graph[edge].length);
+            route.distances.push(route.length() + length);
+            route.nodes.push(pair[1]);
+        }
+        route.served.push(station);
+        route.stops.push(route.length());
+    }
+    route
+}
+
+/// A line over the rail network.
+#[derive(Clone, Debug)]
+pub struct MetroLine {
+    pub name: String,
+    pub color: Color,
+    /// Stations in the order the line calls at them.
+    pub stations: Vec<NodeIndex>,
+    /// Trains the line runs.
+    pub trains: usize,
+    pub route: MetroRoute,
+}
+
+impl MetroLine {
+    /// Whether the line reaches at least two stations and can run trains.
+    pub fn is_open(&self) -> bool {
+        self.route.served.len() >= 2
+    }
+}
+
+/// Track, stations and lines laid by the player.
+#[derive(Resource, Default)]
+pub struct RailNetwork {
+    pub graph: RailGraph,
+    /// Lines keep their index for life; a removed line has no stations.
+    pub lines: Vec<MetroLine>,
+}
+
+impl RailNetwork {
+    /// Nearest node to `position` within `radius`.
+    pub fn nearest_node(&self, position: Vec2, radius: f32) -> Option<NodeIndex> {
+        self.graph
+            .node_indices()
+            .map(|n| (n, self.graph[n].position.distance(position)))
+            .filter(|&(_, distance)| distance <= radius)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(n, _)| n)
+    }
+
+    /// Nearest track segment to `position` within `radius`, and the closest
+    /// point on it.
+    pub fn nearest_track(&self, position: Vec2, radius: f32) -> Option<(EdgeIndex, Vec2)> {
+        self.graph
+            .edge_indices()
+            .filter_map(|edge| {
+                let (a, b) = self.graph.edge_endpoints(edge)?;
+                let (from, to) = (self.graph[a].position, self.graph[b].position);
+                let along = to - from;
+                let t = ((position - from).dot(along) / along.length_squared().max(0.01)).clamp(0.0, 1.0);
+                let point = from + along * t;
+                Some((edge, point, point.distance(position)))
+            })
+            .filter(|&(_, _, distance)| distance <= radius)
+            .min_by(|a, b| a.2.total_cmp(&b.2))
+            .map(|(edge, point, _)| (edge, point))
+    }
+
+    /// The node at `position`, reusing any within `snap` of it.
+    pub fn node_at(&mut self, position: Vec2, snap: f32) -> NodeIndex {
+        self.nearest_node(position, snap).unwrap_or_else(|| {
+            self.graph.add_node(RailNode {
+                position,
+                station: None,
+            })
+        })
+    }
+
+    /// Lay track between two nodes, or relay the segment joining them at a
+    /// new level.
+    pub fn lay_track(&mut self, a: NodeIndex, b: NodeIndex, level: TrackLevel) -> Option<EdgeIndex> {
+        if a == b {
+            return None;
+        }
+        if let Some(edge) = self.graph.find_edge(a, b) {
+            self.graph[edge].level = level;
+            return Some(edge);
+        }
+        let length = self.graph[a].position.distance(self.graph[b].position);
+        Some(self.graph.add_edge(a, b, RailTrack { level, length }))
+    }
+
+    /// Split a segment at `position` with a new node, keeping its level.
+    pub fn split_track(&mut self, edge: EdgeIndex, position: Vec2) -> Option<NodeIndex> {
+        let (a, b) = self.graph.edge_endpoints(edge)?;
+        let level = self.graph.remove_edge(edge)?.level;
+        let node = self.graph.add_node(RailNode {
+            position,
+            station: None,
+        });
+        self.lay_track(a, node, level);
+        self.lay_track(node, b, level);
+        Some(node)
+    }
+
+    /// Remove a segment, and either end left with no track unless it is a
+    /// station.
+    pub fn remove_track(&mut self, edge: EdgeIndex) {
+        let Some((a, b)) = self.graph.edge_endpoints(edge) else {
+            return;
+        };
+        self.graph.remove_edge(edge);
+        for node in [a, b] {
+            if self.graph.neighbors(node).next().is_none() && self.graph[node].station.is_none() {
+                self.graph.remove_node(node);
+            }
+        }
+    }
+
+    /// Stop calling at a station; the node stays while track runs through it.
+    pub fn remove_station(&mut self, node: NodeIndex) {
+        let Some(rail_node) = self.graph.node_weight_mut(node) else {
+            return;
+        };
+        rail_node.station = None;
+        for line in &mut self.lines {
+            line.stations.retain(|&s| s != node);
+        }
+        if self.graph.neighbors(node).next().is_none() {
+            self.graph.remove_node(node);
+        }
+    }
+
+    /// Level a station's platforms are built at: that of most of its track,
+    /// preferring underground, then elevated.
+    pub fn station_level(&self, node: NodeIndex) -> TrackLevel {
+        let levels: Vec<TrackLevel> = self.graph.edges(node).map(|e| e.weight().level).collect();
+        TrackLevel::ALL
+            .into_iter()
+            .max_by_key(|&level| levels.iter().filter(|&&l| l == level).count())
+            .unwrap_or_default()
+    }
+
+    /// Direction track runs through a node.
+    pub fn heading_at(&self, node: NodeIndex) -> Vec2 {
+        let here = self.graph[node].position;
+        let sum: Vec2 = self
+            .graph
+            .neighbors(node)
+            .enumerate()
+            .map(|(i, n)| {
+                // Opposite neighbours point the same way along the track
+                let towards = (self.graph[n].position - here).normalize_or_zero();
+                if i % 2 == 0 { towards } else { -towards }
+            })
+            .sum();
+        sum.try_normalize().unwrap_or(Vec2::X)
+    }
+}
+
+/// One track segment, travelled one way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Block {
+    pub edge: EdgeIndex,
+    /// Travelling from the segment's first node to its second.
+    pub forward: bool,
+}
+
+/// Which train holds each block.
+#[derive(Resource, Default)]
+pub struct RailBlocks {
+    holders: HashMap<Block, Entity>,
+}
+
+impl RailBlocks {
+    /// Claim `blocks` for a train, unless another train holds any of them.
+    pub fn try_claim(&mut self, train: Entity, blocks: &[Block]) -> bool {
+        if blocks.iter().any(|b| self.holders.get(b).is_some_and(|&holder| holder != train)) {
+            return false;
+        }
+        for &block in blocks {
+            self.holders.insert(block, train);
+        }
+        true
+    }
+
+    /// Release the blocks a train holds, except those in `keep`.
+    pub fn release(&mut self, train: Entity, keep: &[Block]) {
+        self.holders.retain(|block, holder| *holder != train || keep.contains(block));
+    }
+
+    pub fn holder(&self, block: Block) -> Option<Entity> {
+        self.holders.get(&block).copied()
+    }
+}
+
+/// Block for route segment `segment` travelled in `direction`.
+fn segment_block(graph: &RailGraph, route: &MetroRoute, segment: usize, direction: f32) -> Option<Block> {
+    let (a, b) = (route.nodes[segment], route.nodes[segment + 1]);
+    let edge = graph.find_edge(a, b)?;
+    let (first, _) = graph.edge_endpoints(edge)?;
+    Some(Block {
+        edge,
+        forward: (first == a) == (direction > 0.0),
+    })
+}
+
+/// Blocks a train stands in with its front at `front` and `length` long.
+fn blocks_under(graph: &RailGraph, route: &MetroRoute, front: f32, length: f32, direction: f32) -> Vec<Block> {
+    let rear = front - direction * length;
+    let (low, high) = (front.min(rear), front.max(rear));
+    (0..route.nodes.len().saturating_sub(1))
+        .filter(|&k| route.distances[k] < high && route.distances[k + 1] > low)
+        .filter_map(|k| segment_block(graph, route, k, direction))
+        .collect()
+}
+
+/// A metro train; its cars are `TrainCar`s pointing at it.
+#[derive(Component)]
+pub struct MetroTrain {
+    pub line: usize,
+    /// Distance of the front of the train along the line's route.
+    pub distance: f32,
+    /// 1 = towards the last station, -1 = back towards the first.
+    pub direction: f32,
+    /// Time left at the current station.
+    pub dwell_timer: f32,
+    /// Whether waiting at a red signal.
+    pub held: bool,
+    /// Riders aboard, by the index of the station they are going to.
+    pub load: Vec<u32>,
+}
+
+/// Move a train up to `step` along its route, stopping at the next station
+/// or short of the first block another train holds. Returns the index of
+/// the station it arrived at, if any.
+fn advance_train(
+    graph: &RailGraph,
+    route: &MetroRoute,
+    entity: Entity,
+    train: &mut MetroTrain,
+    step: f32,
+    length: f32,
+    blocks: &mut RailBlocks,
+) -> Option<usize> {
+    const EPSILON: f32 = 0.01;
+    let direction = train.direction;
+    let ahead = |d: f32| (d - train.distance) * direction;
+
+    let mut target = (train.distance + direction * step).clamp(0.0, route.length());
+    let mut arrived = route
+        .stops
+        .iter()
+        .enumerate()
+        .filter(|&(_, &stop)| ahead(stop) > EPSILON && ahead(stop) <= ahead(target))
+        .min_by(|a, b| ahead(*a.1).total_cmp(&ahead(*b.1)))
+        .map(|(k, &stop)| {
+            target = stop;
+            k
+        });
+
+    // Signals at each node passed protect the block beyond
+    let mut boundaries: Vec<usize> = (0..route.nodes.len())
+        .filter(|&k| ahead(route.distances[k]) >= 0.0 && ahead(route.distances[k]) < ahead(target))
+        .collect();
+    boundaries.sort_by(|&a, &b| ahead(route.distances[a]).total_cmp(&ahead(route.distances[b])));
+    train.held = false;
+    for k in boundaries {
+        let segment = if direction > 0.0 { Some(k) } else { k.checked_sub(1) };
+        let Some(block) = segment.filter(|&s| s + 1 < route.nodes.len()).and_then(|s| segment_block(graph, route, s, direction))
+        else {
+            continue;
+        };
+        if blocks.holder(block).is_some_and(|holder| holder != entity) {
+            let signal = route.distances[k] - direction * SIGNAL_GAP;
+            target = if ahead(signal) > 0.0 { signal } else { train.distance };
+            arrived = None;
+            train.held = true;
+            break;
+        }
+    }
+
+    let under = blocks_under(graph, route, target, length, direction);
+    if !blocks.try_claim(entity, &under) {
+        train.held = true;
+        return None;
+    }
+    blocks.release(entity, &under);
+    train.distance = target;
+    arrived
+}
+
+/// Replan every line after the network is edited, putting each train back
+/// on its line's new route.
+fn replan_metro_lines(
+    mut edits: EventReader<RailNetworkEdited>,
+    mut network: ResMut<RailNetwork>,
+    mut blocks: ResMut<RailBlocks>,
+    mut trains: Query<(Entity, &mut MetroTrain)>,
+) {
+    if edits.read().count() == 0 {
+        return;
+    }
+    let network = &mut *network;
+    for (index, line) in network.lines.iter_mut().enumerate() {
+        line.stations.retain(|&s| network.graph.node_weight(s).is_some_and(|n| n.station.is_some()));
+        let route = plan_metro_route(&network.graph, &line.stations);
+        if route == line.route {
+            continue;
+        }
+        line.route = route;
+        for (entity, mut train) in &mut trains {
+            if train.line != index {
+                continue;
+            }
+            // Stations are renumbered; riders aboard get off
+            train.distance = train.distance.clamp(0.0, line.route.length());
+            train.dwell_timer = 0.0;
+            train.load.clear();
+            blocks.release(entity, &[]);
+        }
+    }
+}
+
+/// Spawn and retire trains so each line runs as many as it asks for.
+#[allow(clippy::too_many_arguments)]
+fn sync_line_trains(
+    mut commands: Commands,
+    network: Res<RailNetwork>,
+    config: Res<RailNetworkConfig>,
+    train_config: Res<TrainConfig>,
+    assets: Res<TrainAssets>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut accent_materials: Local<Vec<Handle<StandardMaterial>>>,
+    mut blocks: ResMut<RailBlocks>,
+    trains: Query<(Entity, &MetroTrain)>,
+    cars: Query<(Entity, &TrainCar)>,
+) {
+    let mut running: Vec<Vec<Entity>> = vec![Vec::new(); network.lines.len()];
+    let mut retired = Vec::new();
+    for (entity, train) in &trains {
+        match running.get_mut(train.line) {
+            Some(line_trains) => line_trains.push(entity),
+            None => retired.push(entity),
+        }
+    }
+
+    let train_length = train_length(&train_config, config.cars_per_train);
+    for (index, line) in network.lines.iter().enumerate() {
+        // One stripe material per line, recoloured when the line's colour changes
+        while accent_materials.len() <= index {
+            let material = materials.add(StandardMaterial {
+                base_color: line.color,
+                metallic: 0.5,
+                perceptual_roughness: 0.3,
+                ..default()
+            });
+            accent_materials.push(material);
+        }
+        let accent = accent_materials[index].clone();
+        if materials.get(&accent).is_some_and(|m| m.base_color != line.color) {
+            if let Some(material) = materials.get_mut(&accent) {
+                material.base_color = line.color;
+            }
+        }
+
+        let wanted = if line.is_open() { line.trains } else { 0 };
+        retired.extend(running[index].iter().skip(wanted));
+        let length = line.route.length();
+        for train_idx in running[index].len()..wanted {
+            // Spread along the line, alternately heading each way
+            let direction = if train_idx % 2 == 0 { 1.0 } else { -1.0 };
+            let at = length * train_idx as f32 / wanted as f32;
+            let distance = if direction > 0.0 { at.max(train_length) } else { at.min(length - train_length) };
+            let train = commands
+                .spawn((
+                    Transform::default(),
+                    MetroTrain {
+                        line: index,
+                        distance: distance.clamp(0.0, length),
+                        direction,
+                        dwell_timer: 0.0,
+                        held: false,
+                        load: Vec::new(),
+                    },
+                ))
+                .id();
+            spawn_train_cars(&mut commands, &train_config, &assets, accent.clone(), train, config.cars_per_train, |_| {
+                Transform::from_xyz(0.0, -TUNNEL_DEPTH, 0.0)
+            });
+        }
+    }
+
+    for &train in &retired {
+        blocks.release(train, &[]);
+        commands.entity(train).despawn_recursive();
+    }
+    for (entity, car) in &cars {
+        if retired.contains(&car.train_entity) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// Length of a train from the front of its first car to the back of its last.
+fn train_length(config: &TrainConfig, cars: usize) -> f32 {
+    cars as f32 * (config.car_length + config.car_gap) - config.car_gap
+}
+
+/// Run trains along their lines: call at stations, turn round at either
+/// end, obey the signals, and place their cars on the track.
+#[allow(clippy::too_many_arguments)]
+fn update_line_trains(
+    time: Res<Time>,
+    network: Res<RailNetwork>,
+    config: Res<RailNetworkConfig>,
+    train_config: Res<TrainConfig>,
+    rail_config: Res<ElevatedRailConfig>,
+    terrain: Res<HeightMap>,
+    mut blocks: ResMut<RailBlocks>,
+    mut ridership: ResMut<Ridership>,
+    mut ledger: ResMut<TransitLedger>,
+    mut trains: Query<(Entity, &mut MetroTrain)>,
+    mut cars: Query<(&mut Transform, &mut Visibility, &TrainCar), Without<MetroTrain>>,
+) {
+    let dt = time.delta_secs();
+    let length = train_length(&train_config, config.cars_per_train);
+    let capacity = train_config.car_capacity * config.cars_per_train as u32;
+    let mut fronts: HashMap<Entity, (usize, f32, f32)> = HashMap::new();
+
+    for (entity, mut train) in &mut trains {
+        let Some(line) = network.lines.get(train.line).filter(|line| line.is_open()) else {
+            continue;
+        };
+        let route = &line.route;
+
+        if train.dwell_timer > 0.0 {
+            train.dwell_timer -= dt;
+        } else {
+            // Turn round at either end of the line; the last car leads the way back
+            let at_end = if train.direction > 0.0 { train.distance >= route.length() - 0.01 } else { train.distance <= 0.01 };
+            if at_end {
+                train.distance = (train.distance - train.direction * length).clamp(0.0, route.length());
+                train.direction = -train.direction;
+            }
+            let train = &mut *train;
+            if let Some(stop) = advance_train(&network.graph, route, entity, train, train_config.speed * dt, length, &mut blocks) {
+                train.dwell_timer = train_config.station_dwell;
+                let last = route.stops.len() - 1;
+                let forward = stop == 0 || (stop < last && train.direction > 0.0);
+                ledger.boardings +=
+                    ridership.serve_stop(LineKey::Metro(train.line), stop, forward, &mut train.load, capacity);
+            }
+        }
+        fronts.insert(entity, (train.line, train.distance, train.direction));
+    }
+
+    for (mut transform, mut visibility, car) in &mut cars {
+        let Some(&(line, front, direction)) = fronts.get(&car.train_entity) else {
+            continue;
+        };
+        let route = &network.lines[line].route;
+        let offset = train_config.car_length / 2.0 + car.car_index as f32 * (train_config.car_length + train_config.car_gap);
+        let Some(point) = route_point(&network.graph, route, front - direction * offset, &terrain) else {
+            continue;
+        };
+        let y = point.ground + point.level.height(rail_config.track_height) + train_config.car_height / 2.0 + 0.1;
+        let heading = point.heading * direction;
+        transform.translation = Vec3::new(point.position.x, y, point.position.y);
+        transform.rotation = Quat::from_rotation_y(-heading.y.atan2(heading.x));
+        *visibility = if point.level == TrackLevel::Underground { Visibility::Hidden } else { Visibility::Visible };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stations at x = 0, 200 and 400 joined in a line, and one off on its own.
+    fn network() -> (RailNetwork, Vec<NodeIndex>) {
+        let mut network = RailNetwork::default();
+        let nodes: Vec<NodeIndex> = [0.0, 100.0, 200.0, 300.0, 400.0]
+            .iter()
+            .map(|&x| network.node_at(Vec2::new(x, 0.0), 1.0))
+            .collect();
+        for pair in nodes.windows(2) {
+            network.lay_track(pair[0], pair[1], TrackLevel::AtGrade);
+        }
+        let island = network.node_at(Vec2::new(0.0, 500.0), 1.0);
+        for &n in &[nodes[0], nodes[2], nodes[4], island] {
+            network.graph[n].station = Some("Station".to_string());
+        }
+        (network, vec![nodes[0], nodes[2], nodes[4], island])
+    }
+
+    #[test]
+    fn routes_run_between_stations_over_the_track() {
+        let (mut network, stations) = network();
+        let route = plan_metro_route(&network.graph, &[stations[0], stations[3], stations[2], stations[1]]);
+        // The island is unreachable; the line doubles back from the far end
+        assert_eq!(route.served, vec![stations[0], stations[2], stations[1]]);
+        assert_eq!(route.stops, vec![0.0, 400.0, 600.0]);
+        assert_eq!(route.nodes.len(), 7);
+
+        // Splitting a segment keeps the route the same length through the new node
+        let (edge, point) = network.nearest_track(Vec2::new(50.0, 4.0), 10.0).unwrap();
+        assert_eq!(point, Vec2::new(50.0, 0.0));
+        network.split_track(edge, point);
+        let route = plan_metro_route(&network.graph, &[stations[0], stations[1]]);
+        assert_eq!(route.nodes.len(), 4);
+        assert_eq!(route.length(), 200.0);
+    }
+
+    #[test]
+    fn trains_wait_at_signals_for_the_block_ahead() {
+        let (network, stations) = network();
+        let route = plan_metro_route(&network.graph, &[stations[0], stations[1]]);
+        let mut blocks = RailBlocks::default();
+        let (leader, follower) = (Entity::from_raw(1), Entity::from_raw(2));
+        let train = |distance| MetroTrain {
+            line: 0,
+            distance,
+            direction: 1.0,
+            dwell_timer: 0.0,
+            held: false,
+            load: Vec::new(),
+        };
+
+        // The leader stands in the second block, the follower in the first
+        let mut ahead = train(150.0);
+        let mut behind = train(60.0);
+        assert_eq!(advance_train(&network.graph, &route, leader, &mut ahead, 0.0, 30.0, &mut blocks), None);
+        advance_train(&network.graph, &route, follower, &mut behind, 80.0, 30.0, &mut blocks);
+        assert!(behind.held);
+        assert_eq!(behind.distance, 100.0 - SIGNAL_GAP);
+
+        // The leader calls at the terminus, still in the second block
+        assert_eq!(advance_train(&network.graph, &route, leader, &mut ahead, 90.0, 30.0, &mut blocks), Some(1));
+        assert_eq!(ahead.distance, 200.0);
+        advance_train(&network.graph, &route, follower, &mut behind, 20.0, 30.0, &mut blocks);
+        assert!(behind.held);
+
+        // Turning round, it takes the block the other way and frees this one
+        ahead.distance = 170.0;
+        ahead.direction = -1.0;
+        advance_train(&network.graph, &route, leader, &mut ahead, 0.0, 30.0, &mut blocks);
+        assert!(!ahead.held);
+        advance_train(&network.graph, &route, follower, &mut behind, 20.0, 30.0, &mut blocks);
+        assert!(!behind.held);
+        assert_eq!(behind.distance, 118.0);
+    }
+}
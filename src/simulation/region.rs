@@ -0,0 +1,821 @@
+//! The region beyond the map, reached through connections at its edge.
+//!
+//! Highway and rail connections sit on the map boundary. Procedural maps get
+//! a highway connection wherever the generated roads run off toward the edge
+//! and a rail connection on the far side for the player's track to reach; in
+//! sandbox the player places them with the connection tool. A highway
+//! connection joins whichever road reaches it, and a rail connection the
+//! track that reaches it.
+//!
+//! Everything from outside comes through them: people moving in and out,
+//! visitors, freight imports and exports, and commuters who live in the
+//! region and work in the city or the other way round. The region itself is a simple
+//! configurable model of its population, jobs, pay and appetite for the
+//! city's goods, with presets for steady times, a boom, a recession and
+//! isolation to try the city's growth under different outside conditions.
+
+use bevy::prelude::*;
+use petgraph::graph::NodeIndex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::game_state::{GameMode, GameState};
+use crate::procgen::building_factory::BuildingArchetype;
+use crate::procgen::road_generator::RoadsGenerated;
+use crate::procgen::roads::{RoadEdge, RoadGraph, RoadType};
+use crate::render::building_spawner::Building;
+use crate::render::day_night::TimeOfDay;
+use crate::render::vehicle_meshes::generate_vehicle_mesh;
+use crate::world::terrain::HeightMap;
+
+use super::bus_routes::TransitLedger;
+use super::demand::CityStats;
+use super::rail_network::RailNetwork;
+use super::vehicle_traffic::{navigation_along, VehicleType};
+use super::vehicles::{MovingVehicle, PlannedRoute};
+
+pub struct RegionPlugin;
+
+impl Plugin for RegionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RegionConfig>()
+            .init_resource::<Region>()
+            .add_systems(Startup, setup_connection_assets)
+            .add_systems(
+                Update,
+                (
+                    place_procedural_connections,
+                    bind_connections,
+                    update_region,
+                    spawn_commuter_cars,
+                    finish_commuter_trips,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Unemployment in the region that neither pushes people out nor keeps them.
+const NORMAL_UNEMPLOYMENT: f32 = 0.06;
+/// Share of people of working age, in the region as in the city.
+const WORKING_SHARE: f32 = 0.6;
+/// Most traffic the connections carry together, in highways' worth.
+const MAX_CAPACITY: f32 = 2.0;
+/// Closest two automatically placed highway connections may be.
+const AUTO_CONNECTION_SPACING: f32 = 200.0;
+/// Share of rail commuters boarding a train each update during commuting hours.
+const RAIL_BOARDINGS_PER_UPDATE: f32 = 0.05;
+
+/// Outside conditions to try the city under.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RegionScenario {
+    /// A region growing slowly with ordinary unemployment.
+    #[default]
+    Steady,
+    /// Jobs and money everywhere: fewer people move, more commute, and
+    /// goods sell well.
+    Boom,
+    /// Jobs are scarce outside: people come looking for work, but the
+    /// region buys less and offers the city's jobless little.
+    Recession,
+    /// Nobody comes or goes, whatever connections are built.
+    Isolated,
+}
+
+impl RegionScenario {
+    pub const ALL: [RegionScenario; 4] = [
+        RegionScenario::Steady,
+        RegionScenario::Boom,
+        RegionScenario::Recession,
+        RegionScenario::Isolated,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RegionScenario::Steady => "Steady",
+            RegionScenario::Boom => "Boom",
+            RegionScenario::Recession => "Recession",
+            RegionScenario::Isolated => "Isolated",
+        }
+    }
+
+    /// The scenario after this one, wrapping round.
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&s| s == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+/// The model of the region outside the map.
+#[derive(Resource, Clone, Debug)]
+pub struct RegionConfig {
+    /// Preset the rest of the model was taken from.
+    pub scenario: RegionScenario,
+    /// People living in the region when the game starts.
+    pub population: f32,
+    /// Regional population growth per update; negative shrinks it.
+    pub growth_rate: f32,
+    /// Share of the region's workers without a job.
+    pub unemployment: f32,
+    /// Pay in the region relative to the city; higher keeps people there.
+    pub wages: f32,
+    /// Share of the region's people looking to move each update.
+    pub migration_rate: f32,
+    /// Share of the city's people leaving each update once it has nothing
+    /// left to offer them.
+    pub emigration_rate: f32,
+    /// Share of the region's workers willing to commute into the city.
+    pub commute_share: f32,
+    /// Share of the city's jobless who find work out in the region.
+    pub outside_job_share: f32,
+    /// Outside demand for the city's goods; scales what exports earn.
+    pub trade_demand: f32,
+    /// Share of the region's people setting off to visit the city each
+    /// update, were it the most attractive place to go.
+    pub visitor_rate: f32,
+    /// How often the region is updated (seconds).
+    pub update_interval: f32,
+    /// How close a road or track must come to a connection to join it.
+    pub snap_distance: f32,
+    /// Farthest from the map edge a generated road can end and still get a
+    /// highway connection.
+    pub edge_margin: f32,
+    /// Most highway connections placed on a procedural map.
+    pub max_auto_connections: usize,
+    /// Commuters represented by each commuter car on the road.
+    pub commuters_per_car: f32,
+    /// Most commuter cars on the road at once.
+    pub max_commuter_cars: usize,
+    pub seed: u64,
+}
+
+impl RegionConfig {
+    pub fn scenario(scenario: RegionScenario) -> Self {
+        let steady = Self {
+            scenario: RegionScenario::Steady,
+            population: 250_000.0,
+            growth_rate: 0.0001,
+            unemployment: NORMAL_UNEMPLOYMENT,
+            wages: 1.0,
+            migration_rate: 0.00016,
+            emigration_rate: 0.02,
+            commute_share: 0.004,
+            outside_job_share: 0.4,
+            trade_demand: 1.0,
+            visitor_rate: 0.00004,
+            update_interval: 2.0,
+            snap_distance: 40.0,
+            edge_margin: 150.0,
+            max_auto_connections: 4,
+            commuters_per_car: 40.0,
+            max_commuter_cars: 12,
+            seed: 86420,
+        };
+        let config = match scenario {
+            RegionScenario::Steady => steady,
+            RegionScenario::Boom => Self {
+                growth_rate: 0.0005,
+                unemployment: 0.03,
+                wages: 1.15,
+                migration_rate: 0.00024,
+                commute_share: 0.006,
+                outside_job_share: 0.7,
+                trade_demand: 1.5,
+                visitor_rate: 0.00006,
+                ..steady
+            },
+            RegionScenario::Recession => Self {
+                growth_rate: -0.0002,
+                unemployment: 0.14,
+                wages: 0.85,
+                migration_rate: 0.0001,
+                commute_share: 0.003,
+                outside_job_share: 0.15,
+                trade_demand: 0.6,
+                visitor_rate: 0.00002,
+                ..steady
+            },
+            RegionScenario::Isolated => Self {
+                migration_rate: 0.0,
+                emigration_rate: 0.0,
+                commute_share: 0.0,
+                outside_job_share: 0.0,
+                trade_demand: 0.0,
+                visitor_rate: 0.0,
+                ..steady
+            },
+        };
+        Self { scenario, ..config }
+    }
+}
+
+impl Default for RegionConfig {
+    fn default() -> Self {
+        Self::scenario(RegionScenario::Steady)
+    }
+}
+
+/// How a connection reaches the region.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectionKind {
+    Highway,
+    Rail,
+}
+
+impl ConnectionKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ConnectionKind::Highway => "Highway",
+            ConnectionKind::Rail => "Rail",
+        }
+    }
+
+    /// Traffic the connection can carry, relative to a highway.
+    pub fn capacity(self) -> f32 {
+        match self {
+            ConnectionKind::Highway => 1.0,
+            ConnectionKind::Rail => 0.75,
+        }
+    }
+
+    /// What the player pays to build one.
+    pub fn cost(self) -> i64 {
+        match self {
+            ConnectionKind::Highway => 8000,
+            ConnectionKind::Rail => 12000,
+        }
+    }
+}
+
+/// A gateway at the map edge to the region beyond.
+#[derive(Component, Clone, Debug)]
+pub struct RegionalConnection {
+    pub kind: ConnectionKind,
+    pub position: Vec2,
+    /// Road junction the connection joins, once a road reaches it.
+    pub road_node: Option<NodeIndex>,
+    /// Track node the connection joins, once track reaches it.
+    pub rail_node: Option<NodeIndex>,
+}
+
+impl RegionalConnection {
+    pub fn new(kind: ConnectionKind, position: Vec2) -> Self {
+        Self {
+            kind,
+            position,
+            road_node: None,
+            rail_node: None,
+        }
+    }
+
+    /// Whether a road or track reaches the connection, as its kind needs.
+    pub fn is_joined(&self) -> bool {
+        match self.kind {
+            ConnectionKind::Highway => self.road_node.is_some(),
+            ConnectionKind::Rail => self.rail_node.is_some(),
+        }
+    }
+}
+
+/// The region's state, and who crossed the map edge at the last update.
+#[derive(Resource, Default)]
+pub struct Region {
+    pub population: f32,
+    /// Traffic the joined connections can carry, in highways' worth.
+    pub capacity: f32,
+    /// Of that, rail connections at stations with a line running.
+    pub rail_capacity: f32,
+    /// People who moved into and out of the city.
+    pub immigrants: u32,
+    pub emigrants: u32,
+    /// Commuters from the region working in the city, and city residents
+    /// working in the region.
+    pub commuters_in: u32,
+    pub commuters_out: u32,
+    update_timer: f32,
+    boardings: f32,
+}
+
+impl Region {
+    /// Share of inbound commuters who come by train.
+    pub fn rail_share(&self) -> f32 {
+        if self.capacity > 0.0 {
+            self.rail_capacity / self.capacity
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Marker for cars bringing commuters across the map edge.
+#[derive(Component)]
+pub struct CommuterCar;
+
+/// Traffic the joined connections of these kinds can carry together.
+pub fn gateway_capacity(kinds: impl IntoIterator<Item = ConnectionKind>) -> f32 {
+    kinds.into_iter().map(ConnectionKind::capacity).sum::<f32>().min(MAX_CAPACITY)
+}
+
+/// People moving into and out of the city over one update, as `(in, out)`.
+/// Newcomers are drawn by how attractive the city is and pushed by hard
+/// times in the region, and only come while there are homes for them;
+/// residents leave an unattractive city, sooner if the region pays well.
+pub fn migration(
+    config: &RegionConfig,
+    region: &Region,
+    attractiveness: f32,
+    city_population: u32,
+    vacancies: u32,
+) -> (u32, u32) {
+    if region.capacity <= 0.0 {
+        return (0, 0);
+    }
+    let push = ((1.0 + (config.unemployment - NORMAL_UNEMPLOYMENT) * 4.0) / config.wages.max(0.1)).max(0.0);
+    let inbound = region.population * config.migration_rate * region.capacity * attractiveness.max(0.0) * push;
+    let outbound = city_population as f32 * config.emigration_rate * (1.0 - attractiveness).max(0.0) * config.wages;
+    ((inbound as u32).min(vacancies), (outbound as u32).min(city_population))
+}
+
+/// Commuters crossing the map edge each working day, as `(into the city,
+/// out to the region)`: jobs the city's own workers can't fill go to the
+/// region's commuters, and the city's jobless look for work outside.
+pub fn commuters(config: &RegionConfig, region: &Region, open_jobs: u32, jobless: u32) -> (u32, u32) {
+    if region.capacity <= 0.0 {
+        return (0, 0);
+    }
+    let reach = region.capacity.min(1.0);
+    let willing = region.population * WORKING_SHARE * config.commute_share * reach;
+    let inbound = (open_jobs as f32).min(willing);
+    let outbound = jobless as f32 * config.outside_job_share * (1.0 - config.unemployment) * reach;
+    (inbound as u32, outbound as u32)
+}
+
+/// Dead-end roads near the map edge to put highway connections on, best
+/// first: highways before other roads, then those nearest the edge, and
+/// never two within `AUTO_CONNECTION_SPACING` of each other.
+pub fn find_gateway_nodes(road_graph: &RoadGraph, bounds: Rect, margin: f32, max: usize) -> Vec<NodeIndex> {
+    let mut candidates: Vec<(NodeIndex, Vec2, f32)> = road_graph
+        .nodes()
+        .filter(|&(idx, _)| road_graph.node_degree(idx) == 1)
+        .filter_map(|(idx, node)| {
+            let to_edge = distance_to_edge(bounds, node.position);
+            if to_edge > margin {
+                return None;
+            }
+            let highway = road_graph
+                .edges_of_node(idx)
+                .any(|e| road_graph.edge_by_index(e).is_some_and(|edge| edge.road_type == RoadType::Highway));
+            Some((idx, node.position, if highway { to_edge } else { to_edge + margin }))
+        })
+        .collect();
+    candidates.sort_by(|a, b| a.2.total_cmp(&b.2));
+
+    let mut chosen: Vec<(NodeIndex, Vec2)> = Vec::new();
+    for (idx, position, _) in candidates {
+        if chosen.len() >= max {
+            break;
+        }
+        if chosen.iter().all(|(_, p)| p.distance(position) >= AUTO_CONNECTION_SPACING) {
+            chosen.push((idx, position));
+        }
+    }
+    chosen.into_iter().map(|(idx, _)| idx).collect()
+}
+
+/// How far inside the map `position` lies.
+pub fn distance_to_edge(bounds: Rect, position: Vec2) -> f32 {
+    (position.x - bounds.min.x)
+        .min(bounds.max.x - position.x)
+        .min(position.y - bounds.min.y)
+        .min(bounds.max.y - position.y)
+        .max(0.0)
+}
+
+/// Point on the map edge nearest `position`.
+pub fn edge_point(bounds: Rect, position: Vec2) -> Vec2 {
+    let p = position.clamp(bounds.min, bounds.max);
+    let gaps = [p.x - bounds.min.x, bounds.max.x - p.x, p.y - bounds.min.y, bounds.max.y - p.y];
+    let nearest = (0..4).min_by(|&a, &b| gaps[a].total_cmp(&gaps[b])).unwrap_or(0);
+    match nearest {
+        0 => Vec2::new(bounds.min.x, p.y),
+        1 => Vec2::new(bounds.max.x, p.y),
+        2 => Vec2::new(p.x, bounds.min.y),
+        _ => Vec2::new(p.x, bounds.max.y),
+    }
+}
+
+/// What a stretch of road costs a commuter driven along (`forward`) or
+/// against its points: its length, a little dearer on smaller roads.
+/// One-way roads are closed the wrong way.
+pub fn commuter_edge_cost(edge: &RoadEdge, forward: bool) -> f32 {
+    if !edge.direction.allows(forward) {
+        return f32::INFINITY;
+    }
+    let markup = match edge.road_type {
+        RoadType::Highway => 1.0,
+        RoadType::Major => 1.1,
+        RoadType::Minor => 1.3,
+        RoadType::Alley => 2.0,
+    };
+    edge.length * markup
+}
+
+/// Road junctions of the highway connections a road reaches.
+pub fn road_gateways<'a>(
+    road_graph: &RoadGraph,
+    connections: impl IntoIterator<Item = &'a RegionalConnection>,
+) -> Vec<NodeIndex> {
+    connections
+        .into_iter()
+        .filter_map(|c| c.road_node)
+        .filter(|&node| road_graph.node_has_edges(node))
+        .collect()
+}
+
+/// The gateway nearest `position`.
+pub fn nearest_gateway(road_graph: &RoadGraph, gateways: &[NodeIndex], position: Vec2) -> Option<NodeIndex> {
+    let distance = |n: NodeIndex| road_graph.node_by_index(n).map_or(f32::MAX, |node| node.position.distance(position));
+    gateways.iter().copied().min_by(|&a, &b| distance(a).total_cmp(&distance(b)))
+}
+
+/// Junction with a road nearest `position`, within `radius`.
+pub fn road_node_near(road_graph: &RoadGraph, position: Vec2, radius: f32) -> Option<NodeIndex> {
+    road_graph.nearest_node_where(position, radius, |idx| road_graph.node_has_edges(idx))
+}
+
+/// Meshes and materials for the signs marking connections.
+#[derive(Resource)]
+pub struct ConnectionAssets {
+    post: Handle<Mesh>,
+    highway_board: Handle<Mesh>,
+    rail_board: Handle<Mesh>,
+    post_material: Handle<StandardMaterial>,
+    highway_material: Handle<StandardMaterial>,
+    rail_material: Handle<StandardMaterial>,
+}
+
+fn setup_connection_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(ConnectionAssets {
+        post: meshes.add(Cuboid::new(0.3, 1.0, 0.3)),
+        highway_board: meshes.add(Cuboid::new(9.0, 2.5, 0.2)),
+        rail_board: meshes.add(Cuboid::new(4.0, 1.0, 0.4)),
+        post_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.55, 0.55, 0.58),
+            metallic: 0.6,
+            perceptual_roughness: 0.5,
+            ..default()
+        }),
+        highway_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.1, 0.42, 0.22),
+            perceptual_roughness: 0.6,
+            ..default()
+        }),
+        rail_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.75, 0.15, 0.1),
+            perceptual_roughness: 0.6,
+            ..default()
+        }),
+    });
+}
+
+/// Spawn a connection with its sign: a green gantry over the highway, or a
+/// red buffer stop at the end of the line, facing into the map.
+pub fn spawn_connection(
+    commands: &mut Commands,
+    assets: &ConnectionAssets,
+    terrain: &HeightMap,
+    connection: RegionalConnection,
+) -> Entity {
+    let position = connection.position;
+    let centre = terrain.bounds().center();
+    let inward = (centre - position).normalize_or(Vec2::X);
+    let transform = Transform::from_xyz(position.x, terrain.sample_world(position), position.y)
+        .with_rotation(Quat::from_rotation_y(inward.x.atan2(inward.y)));
+    let (spread, height, board, material) = match connection.kind {
+        ConnectionKind::Highway => (4.5, 6.0, &assets.highway_board, &assets.highway_material),
+        ConnectionKind::Rail => (1.8, 1.2, &assets.rail_board, &assets.rail_material),
+    };
+    commands
+        .spawn((transform, Visibility::default(), connection))
+        .with_children(|parent| {
+            for side in [-1.0, 1.0] {
+                parent.spawn((
+                    Mesh3d(assets.post.clone()),
+                    MeshMaterial3d(assets.post_material.clone()),
+                    Transform::from_xyz(side * spread, height / 2.0, 0.0).with_scale(Vec3::new(1.0, height, 1.0)),
+                ));
+            }
+            parent.spawn((
+                Mesh3d(board.clone()),
+                MeshMaterial3d(material.clone()),
+                Transform::from_xyz(0.0, height, 0.0),
+            ));
+        })
+        .id()
+}
+
+/// Connect a newly generated map to the region: highway connections where
+/// its roads run off toward the edge, and a rail connection on the far side.
+#[allow(clippy::too_many_arguments)]
+fn place_procedural_connections(
+    mut commands: Commands,
+    mode: Res<State<GameMode>>,
+    generated: Res<RoadsGenerated>,
+    config: Res<RegionConfig>,
+    road_graph: Res<RoadGraph>,
+    terrain: Res<HeightMap>,
+    assets: Res<ConnectionAssets>,
+    mut placed: Local<bool>,
+) {
+    if *placed || *mode.get() != GameMode::Procedural || !generated.0 {
+        return;
+    }
+    *placed = true;
+
+    let bounds = terrain.bounds();
+    let gateways = find_gateway_nodes(&road_graph, bounds, config.edge_margin, config.max_auto_connections);
+    for &node in &gateways {
+        let Some(position) = road_graph.node_by_index(node).map(|n| n.position) else {
+            continue;
+        };
+        spawn_connection(&mut commands, &assets, &terrain, RegionalConnection::new(ConnectionKind::Highway, position));
+    }
+    let first = gateways.first().and_then(|&node| road_graph.node_by_index(node)).map(|n| n.position);
+    let rail = edge_point(bounds, bounds.center() * 2.0 - first.unwrap_or(bounds.min));
+    spawn_connection(&mut commands, &assets, &terrain, RegionalConnection::new(ConnectionKind::Rail, rail));
+    info!("Placed {} highway connections and a rail connection at the map edge", gateways.len());
+}
+
+/// Join each connection to the road or track that reaches it.
+fn bind_connections(
+    config: Res<RegionConfig>,
+    road_graph: Res<RoadGraph>,
+    rail: Res<RailNetwork>,
+    mut connections: Query<&mut RegionalConnection>,
+    added: Query<(), Added<RegionalConnection>>,
+) {
+    if !road_graph.is_changed() && !rail.is_changed() && added.is_empty() {
+        return;
+    }
+    for mut connection in &mut connections {
+        let (road_node, rail_node) = match connection.kind {
+            ConnectionKind::Highway => (road_node_near(&road_graph, connection.position, config.snap_distance), None),
+            ConnectionKind::Rail => (None, rail.nearest_node(connection.position, config.snap_distance)),
+        };
+        if connection.road_node != road_node || connection.rail_node != rail_node {
+            connection.road_node = road_node;
+            connection.rail_node = rail_node;
+        }
+    }
+}
+
+/// Grow the region and work out who commutes across the map edge; rail
+/// commuters pay their fares during commuting hours.
+#[allow(clippy::too_many_arguments)]
+fn update_region(
+    time: Res<Time>,
+    config: Res<RegionConfig>,
+    time_of_day: Res<TimeOfDay>,
+    stats: Res<CityStats>,
+    rail: Res<RailNetwork>,
+    connections: Query<&RegionalConnection>,
+    mut region: ResMut<Region>,
+    mut ledger: ResMut<TransitLedger>,
+) {
+    region.update_timer += time.delta_secs();
+    if region.update_timer < config.update_interval {
+        return;
+    }
+    region.update_timer = 0.0;
+
+    if region.population <= 0.0 {
+        region.population = config.population;
+    }
+    region.population = (region.population * (1.0 + config.growth_rate)).max(0.0);
+
+    let joined = || connections.iter().filter(|c| c.is_joined());
+    region.capacity = gateway_capacity(joined().map(|c| c.kind));
+    // Trains only bring commuters to connections a line calls at
+    let served = |node: NodeIndex| {
+        rail.lines
+            .iter()
+            .any(|line| line.is_open() && line.route.served.contains(&node))
+    };
+    region.rail_capacity =
+        gateway_capacity(joined().filter(|c| c.rail_node.is_some_and(served)).map(|c| c.kind)).min(region.capacity);
+
+    let working = (stats.population as f32 * WORKING_SHARE) as u32;
+    let jobs = stats.total_jobs();
+    let (commuters_in, commuters_out) =
+        commuters(&config, &region, jobs.saturating_sub(working), working.saturating_sub(jobs));
+    region.commuters_in = commuters_in;
+    region.commuters_out = commuters_out;
+
+    let hour = time_of_day.hour();
+    if (6.0..10.0).contains(&hour) || (16.0..20.0).contains(&hour) {
+        region.boardings += commuters_in as f32 * region.rail_share() * RAIL_BOARDINGS_PER_UPDATE;
+        let whole = region.boardings.floor();
+        ledger.boardings += whole as u32;
+        region.boardings -= whole;
+    }
+}
+
+/// Put the commuters crossing the map edge by road on the road, a car for
+/// every few: mornings into the city's workplaces and out from its homes,
+/// evenings the other way.
+#[allow(clippy::too_many_arguments)]
+fn spawn_commuter_cars(
+    mut commands: Commands,
+    time: Res<Time>,
+    config: Res<RegionConfig>,
+    region: Res<Region>,
+    time_of_day: Res<TimeOfDay>,
+    road_graph: Res<RoadGraph>,
+    connections: Query<&RegionalConnection>,
+    buildings: Query<(&Building, &Transform)>,
+    cars: Query<(), With<CommuterCar>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut timer: Local<f32>,
+    mut local_rng: Local<Option<StdRng>>,
+    mut body: Local<Option<Handle<Mesh>>>,
+) {
+    *timer += time.delta_secs();
+    if *timer < 1.5 {
+        return;
+    }
+    *timer = 0.0;
+
+    let hour = time_of_day.hour();
+    let morning = (6.0..10.0).contains(&hour);
+    if !morning && !(16.0..20.0).contains(&hour) {
+        return;
+    }
+    let by_road_in = region.commuters_in as f32 * (1.0 - region.rail_share());
+    let by_road_out = region.commuters_out as f32;
+    let wanted = (((by_road_in + by_road_out) / config.commuters_per_car).ceil() as usize).min(config.max_commuter_cars);
+    if cars.iter().count() >= wanted {
+        return;
+    }
+    let gateways = road_gateways(&road_graph, &connections);
+    if gateways.is_empty() {
+        return;
+    }
+
+    let rng = local_rng.get_or_insert_with(|| StdRng::seed_from_u64(config.seed));
+    // Regional workers drive to work; residents working outside drive out from home
+    let regional_worker = rng.gen::<f32>() * (by_road_in + by_road_out) < by_road_in;
+    let wanted_building = |building: &Building| match building.building_type {
+        BuildingArchetype::Residential => !regional_worker,
+        BuildingArchetype::Commercial | BuildingArchetype::Industrial => regional_worker,
+    };
+    let places: Vec<Vec2> = buildings
+        .iter()
+        .filter(|(building, _)| wanted_building(building))
+        .map(|(_, transform)| Vec2::new(transform.translation.x, transform.translation.z))
+        .collect();
+    if places.is_empty() {
+        return;
+    }
+    let place = places[rng.gen_range(0..places.len())];
+    let Some(city_node) = road_node_near(&road_graph, place, 150.0) else {
+        return;
+    };
+    let Some(gateway) = nearest_gateway(&road_graph, &gateways, place) else {
+        return;
+    };
+    // Coming in: regional workers in the morning, residents in the evening
+    let (start, end) = if morning == regional_worker { (gateway, city_node) } else { (city_node, gateway) };
+    let body = body
+        .get_or_insert_with(|| meshes.add(generate_vehicle_mesh(&VehicleType::Sedan.mesh_config())))
+        .clone();
+    if let Some(mut car) = spawn_route_car(&mut commands, &road_graph, &mut materials, rng, body, start, end) {
+        car.insert(CommuterCar);
+    }
+}
+
+/// Put a sedan on the road at `start`, driving the cheapest way to `end`.
+pub fn spawn_route_car<'a>(
+    commands: &'a mut Commands,
+    road_graph: &RoadGraph,
+    materials: &mut Assets<StandardMaterial>,
+    rng: &mut StdRng,
+    body: Handle<Mesh>,
+    start: NodeIndex,
+    end: NodeIndex,
+) -> Option<EntityCommands<'a>> {
+    let route = road_graph.cheapest_path(start, end, commuter_edge_cost).filter(|route| !route.is_empty())?;
+    let speed = 12.0 * VehicleType::Sedan.speed_multiplier();
+    let nav = navigation_along(road_graph, route[0], start, route.get(1).copied(), speed)?;
+    let (r, g, b) = VehicleType::Sedan.body_color(rng);
+    let paint = materials.add(StandardMaterial {
+        base_color: Color::srgb(r, g, b),
+        perceptual_roughness: 0.5,
+        reflectance: 0.35,
+        ..default()
+    });
+    Some(commands.spawn((
+        Mesh3d(body),
+        MeshMaterial3d(paint),
+        Transform::default(),
+        MovingVehicle,
+        VehicleType::Sedan,
+        nav,
+        PlannedRoute::new(route, end, commuter_edge_cost),
+    )))
+}
+
+/// Take commuter cars off the road once they reach work, home or the edge.
+fn finish_commuter_trips(mut commands: Commands, cars: Query<(Entity, &PlannedRoute), With<CommuterCar>>) {
+    for (entity, route) in &cars {
+        if route.arrived {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::procgen::roads::{test_road, RoadNodeType};
+
+    fn region(capacity: f32) -> Region {
+        Region {
+            population: 250_000.0,
+            capacity,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn highway_connections_go_on_dead_ends_at_the_map_edge() {
+        let bounds = Rect::new(0.0, 0.0, 1000.0, 1000.0);
+        let mut graph = RoadGraph::default();
+        let [centre, minor_end, highway_end, beside, inland] = [
+            Vec2::new(500.0, 500.0),
+            Vec2::new(500.0, 10.0),
+            Vec2::new(60.0, 500.0),
+            Vec2::new(60.0, 600.0),
+            Vec2::new(500.0, 700.0),
+        ]
+        .map(|p| graph.add_node(p, RoadNodeType::Intersection));
+        test_road(centre, minor_end, RoadType::Minor, &mut graph);
+        test_road(centre, inland, RoadType::Minor, &mut graph);
+        assert_eq!(find_gateway_nodes(&graph, bounds, 150.0, 4), vec![minor_end]);
+
+        // Highways come first, and a second road end close by is passed over
+        test_road(centre, highway_end, RoadType::Highway, &mut graph);
+        test_road(centre, beside, RoadType::Minor, &mut graph);
+        assert_eq!(find_gateway_nodes(&graph, bounds, 150.0, 4), vec![highway_end, minor_end]);
+        assert_eq!(find_gateway_nodes(&graph, bounds, 150.0, 1), vec![highway_end]);
+        assert!(find_gateway_nodes(&graph, bounds, 5.0, 4).is_empty());
+
+        assert_eq!(edge_point(bounds, Vec2::new(60.0, 500.0)), Vec2::new(0.0, 500.0));
+        assert_eq!(edge_point(bounds, Vec2::new(400.0, 1200.0)), Vec2::new(400.0, 1000.0));
+    }
+
+    #[test]
+    fn nobody_crosses_without_a_connection() {
+        let config = RegionConfig::default();
+        assert_eq!(gateway_capacity([]), 0.0);
+        assert_eq!(migration(&config, &region(0.0), 1.5, 1000, 500), (0, 0));
+        assert_eq!(commuters(&config, &region(0.0), 300, 300), (0, 0));
+
+        let capacity = gateway_capacity([ConnectionKind::Highway, ConnectionKind::Rail, ConnectionKind::Highway]);
+        assert_eq!(capacity, MAX_CAPACITY);
+        let (inbound, _) = migration(&config, &region(1.0), 1.5, 1000, 500);
+        assert!(inbound > 0);
+    }
+
+    #[test]
+    fn outside_conditions_shape_migration_and_commuting() {
+        let steady = RegionConfig::scenario(RegionScenario::Steady);
+        let recession = RegionConfig::scenario(RegionScenario::Recession);
+        let boom = RegionConfig::scenario(RegionScenario::Boom);
+        let isolated = RegionConfig::scenario(RegionScenario::Isolated);
+        let connected = region(1.0);
+
+        // Newcomers only come while there are homes for them
+        assert_eq!(migration(&steady, &connected, 1.5, 1000, 10).0, 10);
+        // Hard times outside push more people toward the city per head
+        let per_head = |config: &RegionConfig| {
+            migration(config, &connected, 1.0, 0, u32::MAX).0 as f32 / config.migration_rate
+        };
+        assert!(per_head(&recession) > per_head(&steady));
+        // An unattractive city loses people, faster when the region pays well
+        let leaving = |config: &RegionConfig| migration(config, &connected, 0.5, 10_000, 0).1;
+        assert!(leaving(&steady) > 0 && leaving(&boom) > leaving(&steady));
+
+        // Jobless residents find work outside more easily in a boom
+        assert!(commuters(&boom, &connected, 0, 100).1 > commuters(&recession, &connected, 0, 100).1);
+        // Regional commuters only fill jobs the city can't
+        assert_eq!(commuters(&steady, &connected, 50, 0), (50, 0));
+        assert_eq!(migration(&isolated, &connected, 1.5, 1000, 500), (0, 0));
+        assert_eq!(commuters(&isolated, &connected, 300, 300), (0, 0));
+    }
+}
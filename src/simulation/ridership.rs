@@ -0,0 +1,602 @@
+//! Transit ridership from trip demand.
+//!
+//! Every commute picks a mode: car, transit, bike or walking. Each is costed as a
+//! travel time - for transit the walk to and from the stops, half the
+//! headway spent waiting, and the time on board - and the trip draws a mode
+//! with logit weights on those times, so the quickest mode wins most trips
+//! but not all. Transit riders queue at their boarding stop for a vehicle
+//! heading their way; buses and trains carry them up to capacity and let them
+//! off at their stop. The share of trips made by car sets how busy the roads
+//! are, so good transit thins out road traffic. Driving somewhere with no
+//! free parking nearby is costed with the time spent looking for a space;
+//! cycling is slowed by hills and rain, and by having nowhere to lock up.
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::collections::HashMap;
+
+use crate::procgen::roads::RoadGraph;
+use crate::procgen::building_factory::BuildingArchetype;
+use crate::render::building_spawner::Building;
+use crate::render::elevated_rail::RailLine;
+use crate::render::train_cars::{Train, TrainConfig};
+use crate::world::terrain::HeightMap;
+
+use super::bus_routes::{round_trip_time, route_point, Bus, BusRouteConfig, BusRoutes};
+use super::citizens::TripStarted;
+use super::cycling::{bike_time, climb_along, CyclingConditions, CyclingConfig};
+use super::parking::{ParkingConfig, ParkingSupply};
+use super::rail_network::{MetroTrain, RailNetwork};
+use super::traffic::{TrafficCaStats, TrafficConfig};
+
+pub struct RidershipPlugin;
+
+impl Plugin for RidershipPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RidershipConfig>()
+            .init_resource::<TransitNetwork>()
+            .init_resource::<Ridership>()
+            .init_resource::<ModeShare>()
+            .add_event::<TripModeChosen>()
+            .add_systems(Update, (update_transit_network, choose_trip_modes, count_riders_on_board).chain());
+    }
+}
+
+/// Roads and footpaths are longer than the straight line between two places.
+const DETOUR: f32 = 1.3;
+
+/// Configuration for mode choice.
+#[derive(Resource)]
+pub struct RidershipConfig {
+    pub seed: u64,
+    /// Walking speed (units per second).
+    pub walk_speed: f32,
+    /// Driving speed on empty roads.
+    pub car_speed: f32,
+    /// Time spent getting to the car and parking it, on every drive.
+    pub car_overhead: f32,
+    /// Farthest a rider will walk to or from a stop.
+    pub max_stop_walk: f32,
+    /// How much longer walking to a stop and waiting feel than riding.
+    pub access_weight: f32,
+    /// How strongly trips favour the quickest mode, per second of difference.
+    pub choice_sensitivity: f32,
+    /// Weight of each new trip in the running mode shares.
+    pub share_smoothing: f32,
+}
+
+impl Default for RidershipConfig {
+    fn default() -> Self {
+        Self {
+            seed: 31337,
+            walk_speed: 1.4,
+            car_speed: 10.0,
+            car_overhead: 180.0,
+            max_stop_walk: 400.0,
+            access_weight: 1.5,
+            choice_sensitivity: 0.005,
+            share_smoothing: 0.02,
+        }
+    }
+}
+
+/// Ways to make a trip.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TravelMode {
+    Car,
+    Transit,
+    Bike,
+    Walk,
+}
+
+/// A trip has set off by the chosen mode.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct TripModeChosen {
+    pub trip: TripStarted,
+    pub mode: TravelMode,
+    /// Where the trip ends.
+    pub to: Vec2,
+}
+
+/// Which vehicles serve a line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LineKey {
+    /// A bus route, by index.
+    Bus(usize),
+    /// The elevated railway.
+    Rail,
+    /// A player-built metro line, by index.
+    Metro(usize),
+}
+
+/// A line as riders see it: where it stops, how far apart, how fast and how often.
+#[derive(Clone, Debug)]
+pub struct TransitLine {
+    pub key: LineKey,
+    pub name: String,
+    pub stops: Vec<Vec2>,
+    /// Distance along the line to each stop.
+    pub distances: Vec<f32>,
+    pub speed: f32,
+    /// Time spent at each stop.
+    pub dwell: f32,
+    /// Seconds between vehicles; infinite with none running.
+    pub headway: f32,
+}
+
+/// Every line riders can use, refreshed each second.
+#[derive(Resource, Default)]
+pub struct TransitNetwork {
+    pub lines: Vec<TransitLine>,
+    refresh_timer: f32,
+}
+
+/// A single-line transit journey.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TransitTrip {
+    /// Index into the network's lines.
+    pub line: usize,
+    pub board: usize,
+    pub alight: usize,
+    /// Door-to-door time, with walking and waiting weighted.
+    pub time: f32,
+}
+
+/// Quickest journey between two places on a single line, if any line has
+/// stops within walking distance of both.
+pub fn best_transit_trip(network: &TransitNetwork, from: Vec2, to: Vec2, config: &RidershipConfig) -> Option<TransitTrip> {
+    network
+        .lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.headway.is_finite())
+        .filter_map(|(index, line)| {
+            let nearest = |p: Vec2| {
+                line.stops
+                    .iter()
+                    .enumerate()
+                    .map(|(i, stop)| (i, stop.distance(p)))
+                    .filter(|&(_, distance)| distance <= config.max_stop_walk)
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+            };
+            let (board, walk_in) = nearest(from)?;
+            let (alight, walk_out) = nearest(to)?;
+            if board == alight {
+                return None;
+            }
+            let ride = (line.distances[alight] - line.distances[board]).abs() / line.speed
+                + board.abs_diff(alight) as f32 * line.dwell;
+            let access = (walk_in + walk_out) / config.walk_speed + line.headway / 2.0;
+            Some(TransitTrip {
+                line: index,
+                board,
+                alight,
+                time: ride + config.access_weight * access,
+            })
+        })
+        .min_by(|a, b| a.time.total_cmp(&b.time))
+}
+
+/// Share of trips each mode wins given its travel time, by logit.
+pub fn mode_shares(times: &[(TravelMode, f32)], sensitivity: f32) -> Vec<(TravelMode, f32)> {
+    let best = times.iter().map(|&(_, t)| t).fold(f32::INFINITY, f32::min);
+    let weights: Vec<f32> = times.iter().map(|&(_, t)| (-sensitivity * (t - best)).exp()).collect();
+    let total: f32 = weights.iter().sum();
+    times.iter().zip(weights).map(|(&(mode, _), w)| (mode, w / total)).collect()
+}
+
+/// Passengers at one stop.
+#[derive(Clone, Debug, Default)]
+pub struct StopRidership {
+    /// Riders waiting, by the stop they are going to.
+    pub waiting: Vec<u32>,
+    /// Riders who have boarded here.
+    pub boarded: u32,
+    /// Riders who have got off here.
+    pub alighted: u32,
+}
+
+impl StopRidership {
+    pub fn waiting_total(&self) -> u32 {
+        self.waiting.iter().sum()
+    }
+}
+
+/// Passengers on one line.
+#[derive(Clone, Debug, Default)]
+pub struct LineRidership {
+    pub stops: Vec<StopRidership>,
+    /// Riders carried since the line opened.
+    pub riders: u32,
+    /// Riders on board right now.
+    pub on_board: u32,
+}
+
+impl LineRidership {
+    pub fn waiting(&self) -> u32 {
+        self.stops.iter().map(|stop| stop.waiting_total()).sum()
+    }
+}
+
+/// Passengers on every line.
+#[derive(Resource, Default)]
+pub struct Ridership {
+    pub lines: HashMap<LineKey, LineRidership>,
+}
+
+impl Ridership {
+    /// Serve a vehicle calling at `stop`: riders for this stop get off, then
+    /// riders waiting for stops the way it leaves (`forward` = towards later
+    /// stops) board while there is room. `load` holds the riders on board by
+    /// destination stop. Returns how many boarded.
+    pub fn serve_stop(&mut self, key: LineKey, stop: usize, forward: bool, load: &mut Vec<u32>, capacity: u32) -> u32 {
+        let Some(line) = self.lines.get_mut(&key) else {
+            return 0;
+        };
+        let stop_count = line.stops.len();
+        let Some(here) = line.stops.get_mut(stop) else {
+            return 0;
+        };
+        load.resize(stop_count, 0);
+
+        here.alighted += std::mem::take(&mut load[stop]);
+
+        let mut room = capacity.saturating_sub(load.iter().sum());
+        let mut boarded = 0;
+        for (destination, (waiting, aboard)) in here.waiting.iter_mut().zip(load.iter_mut()).enumerate() {
+            if destination == stop || (destination > stop) != forward {
+                continue;
+            }
+            let count = (*waiting).min(room);
+            *waiting -= count;
+            *aboard += count;
+            room -= count;
+            boarded += count;
+        }
+        here.boarded += boarded;
+        line.riders += boarded;
+        boarded
+    }
+}
+
+/// Running share of trips made by each mode.
+#[derive(Resource)]
+pub struct ModeShare {
+    pub car: f32,
+    pub transit: f32,
+    pub bike: f32,
+    pub walk: f32,
+    /// Trips counted so far.
+    pub trips: u32,
+}
+
+impl Default for ModeShare {
+    fn default() -> Self {
+        Self {
+            car: 0.85,
+            transit: 0.0,
+            bike: 0.0,
+            walk: 0.15,
+            trips: 0,
+        }
+    }
+}
+
+impl ModeShare {
+    /// Blend one trip into the running shares.
+    pub fn record(&mut self, mode: TravelMode, smoothing: f32) {
+        let hit = |m: TravelMode| if m == mode { 1.0 } else { 0.0 };
+        self.car += (hit(TravelMode::Car) - self.car) * smoothing;
+        self.transit += (hit(TravelMode::Transit) - self.transit) * smoothing;
+        self.bike += (hit(TravelMode::Bike) - self.bike) * smoothing;
+        self.walk += (hit(TravelMode::Walk) - self.walk) * smoothing;
+        self.trips += 1;
+    }
+}
+
+/// Distance along a polyline to each of its points.
+fn cumulative_lengths(points: &[Vec2]) -> Vec<f32> {
+    let mut total = 0.0;
+    let mut lengths = vec![0.0];
+    for w in points.windows(2) {
+        total += w[0].distance(w[1]);
+        lengths.push(total);
+    }
+    lengths
+}
+
+/// Rebuild the network from the bus routes, railway and metro lines, and
+/// keep a ridership record for each of its lines.
+#[allow(clippy::too_many_arguments)]
+fn update_transit_network(
+    time: Res<Time>,
+    road_graph: Res<RoadGraph>,
+    routes: Res<BusRoutes>,
+    bus_config: Res<BusRouteConfig>,
+    rail_line: Option<Res<RailLine>>,
+    rail_network: Res<RailNetwork>,
+    train_config: Res<TrainConfig>,
+    buses: Query<&Bus>,
+    trains: Query<&Train>,
+    metro_trains: Query<&MetroTrain>,
+    mut network: ResMut<TransitNetwork>,
+    mut ridership: ResMut<Ridership>,
+) {
+    network.refresh_timer -= time.delta_secs();
+    if network.refresh_timer > 0.0 && !routes.is_changed() && !rail_network.is_changed() {
+        return;
+    }
+    network.refresh_timer = 1.0;
+
+    let mut lines = Vec::new();
+    for (index, route) in routes.routes.iter().enumerate() {
+        if route.stop_indices.len() < 2 {
+            continue;
+        }
+        // Distance along the route to the end of each edge
+        let mut ends = Vec::with_capacity(route.edges.len());
+        let mut total = 0.0;
+        for &edge in &route.edges {
+            total += road_graph.edge_by_index(edge).map_or(0.0, |e| e.length);
+            ends.push(total);
+        }
+        let stops: Vec<Vec2> = route
+            .stop_indices
+            .iter()
+            .filter_map(|&i| route_point(&road_graph, route, i, 1.0).map(|(p, _)| p))
+            .collect();
+        if stops.len() != route.stop_indices.len() {
+            continue;
+        }
+        let running = buses.iter().filter(|bus| bus.route_index == index).count();
+        let round_trip = round_trip_time(total, stops.len(), bus_config.bus_speed, bus_config.stop_duration);
+        lines.push(TransitLine {
+            key: LineKey::Bus(index),
+            name: route.name.clone(),
+            distances: route.stop_indices.iter().map(|&i| ends[i]).collect(),
+            stops,
+            speed: bus_config.bus_speed,
+            dwell: bus_config.stop_duration,
+            headway: if running > 0 { round_trip / running as f32 } else { f32::INFINITY },
+        });
+    }
+
+    if let Some(rail) = rail_line.filter(|rail| rail.waypoints.len() >= 2 && rail.stations.len() >= 2) {
+        let lengths = cumulative_lengths(&rail.waypoints);
+        let stations = rail_stations(&rail);
+        let running = trains.iter().count();
+        let round_trip = round_trip_time(
+            lengths.last().copied().unwrap_or(0.0),
+            stations.len(),
+            train_config.speed,
+            train_config.station_dwell,
+        );
+        lines.push(TransitLine {
+            key: LineKey::Rail,
+            name: "Elevated rail".to_string(),
+            stops: stations.iter().map(|&i| rail.waypoints[i]).collect(),
+            distances: stations.iter().map(|&i| lengths[i]).collect(),
+            speed: train_config.speed,
+            dwell: train_config.station_dwell,
+            headway: if running > 0 { round_trip / running as f32 } else { f32::INFINITY },
+        });
+    }
+
+    for (index, line) in rail_network.lines.iter().enumerate().filter(|(_, line)| line.is_open()) {
+        let route = &line.route;
+        let running = metro_trains.iter().filter(|train| train.line == index).count();
+        let round_trip = round_trip_time(route.length(), route.stops.len(), train_config.speed, train_config.station_dwell);
+        lines.push(TransitLine {
+            key: LineKey::Metro(index),
+            name: line.name.clone(),
+            stops: route.served.iter().map(|&n| rail_network.graph[n].position).collect(),
+            distances: route.stops.clone(),
+            speed: train_config.speed,
+            dwell: train_config.station_dwell,
+            headway: if running > 0 { round_trip / running as f32 } else { f32::INFINITY },
+        });
+    }
+
+    // Lines whose stops changed start their queues afresh
+    ridership.lines.retain(|key, _| lines.iter().any(|line| line.key == *key));
+    for line in &lines {
+        let record = ridership.lines.entry(line.key).or_default();
+        if record.stops.len() != line.stops.len() {
+            let stop = StopRidership {
+                waiting: vec![0; line.stops.len()],
+                ..default()
+            };
+            record.stops = vec![stop; line.stops.len()];
+        }
+    }
+    network.lines = lines;
+}
+
+/// Railway stations in order along the line, as waypoint indices.
+pub fn rail_stations(rail: &RailLine) -> Vec<usize> {
+    let mut stations: Vec<usize> = rail.stations.iter().copied().filter(|&i| i < rail.waypoints.len()).collect();
+    stations.sort_unstable();
+    stations.dedup();
+    stations
+}
+
+/// Pick a mode for each trip that sets off, and queue transit riders at
+/// their stop.
+#[allow(clippy::too_many_arguments)]
+fn choose_trip_modes(
+    mut trips: EventReader<TripStarted>,
+    config: Res<RidershipConfig>,
+    network: Res<TransitNetwork>,
+    traffic_stats: Res<TrafficCaStats>,
+    traffic_config: Res<TrafficConfig>,
+    parking: Res<ParkingSupply>,
+    parking_config: Res<ParkingConfig>,
+    cycling: Res<CyclingConditions>,
+    cycling_config: Res<CyclingConfig>,
+    terrain: Res<HeightMap>,
+    buildings: Query<(&Transform, &Building)>,
+    mut ridership: ResMut<Ridership>,
+    mut share: ResMut<ModeShare>,
+    mut chosen: EventWriter<TripModeChosen>,
+    mut rng: Local<Option<StdRng>>,
+) {
+    let rng = rng.get_or_insert_with(|| StdRng::seed_from_u64(config.seed));
+    // Driving slows as the roads fill up
+    let flow = if traffic_stats.total_vehicles > 0 {
+        (traffic_stats.average_velocity / traffic_config.max_velocity as f32).clamp(0.25, 1.0)
+    } else {
+        1.0
+    };
+
+    for trip in trips.read() {
+        let (Ok((from, _)), Ok((to, destination))) = (buildings.get(trip.from), buildings.get(trip.to)) else {
+            continue;
+        };
+        let from = Vec2::new(from.translation.x, from.translation.z);
+        let to = Vec2::new(to.translation.x, to.translation.z);
+        let distance = from.distance(to) * DETOUR;
+        // Home has a driveway and a shed; anywhere else needs a space
+        let home = destination.building_type == BuildingArchetype::Residential;
+        let parking_search = if home { 0.0 } else { parking.search_time(to, &parking_config) };
+
+        let mut options = vec![
+            (TravelMode::Car, config.car_overhead + parking_search + distance / (config.car_speed * flow)),
+            (TravelMode::Walk, distance / config.walk_speed),
+        ];
+        let climb = climb_along(&terrain, from, to, cycling_config.climb_sample_spacing);
+        let rack = home || cycling.free_rack(to, cycling_config.rack_radius).is_some();
+        if let Some(time) = bike_time(distance, climb, cycling.rain, rack, &cycling_config) {
+            options.push((TravelMode::Bike, time));
+        }
+        let transit = best_transit_trip(&network, from, to, &config);
+        if let Some(transit) = transit {
+            options.push((TravelMode::Transit, transit.time));
+        }
+
+        let shares = mode_shares(&options, config.choice_sensitivity);
+        let mut roll = rng.gen::<f32>();
+        let mut mode = TravelMode::Car;
+        for (option, probability) in shares {
+            mode = option;
+            if roll < probability {
+                break;
+            }
+            roll -= probability;
+        }
+
+        if let (TravelMode::Transit, Some(transit)) = (mode, transit) {
+            let key = network.lines[transit.line].key;
+            let waiting = ridership
+                .lines
+                .get_mut(&key)
+                .and_then(|line| line.stops.get_mut(transit.board))
+                .and_then(|stop| stop.waiting.get_mut(transit.alight));
+            if let Some(waiting) = waiting {
+                *waiting += 1;
+            }
+        }
+        share.record(mode, config.share_smoothing);
+        chosen.send(TripModeChosen {
+            trip: *trip,
+            mode,
+            to,
+        });
+    }
+}
+
+/// Total up the riders aboard each line's vehicles.
+fn count_riders_on_board(
+    buses: Query<&Bus>,
+    trains: Query<&Train>,
+    metro_trains: Query<&MetroTrain>,
+    mut ridership: ResMut<Ridership>,
+) {
+    for line in ridership.lines.values_mut() {
+        line.on_board = 0;
+    }
+    for bus in &buses {
+        if let Some(line) = ridership.lines.get_mut(&LineKey::Bus(bus.route_index)) {
+            line.on_board += bus.load.iter().sum::<u32>();
+        }
+    }
+    if let Some(line) = ridership.lines.get_mut(&LineKey::Rail) {
+        line.on_board += trains.iter().map(|train| train.load.iter().sum::<u32>()).sum::<u32>();
+    }
+    for train in &metro_trains {
+        if let Some(line) = ridership.lines.get_mut(&LineKey::Metro(train.line)) {
+            line.on_board += train.load.iter().sum::<u32>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn straight_line(headway: f32) -> TransitNetwork {
+        TransitNetwork {
+            lines: vec![TransitLine {
+                key: LineKey::Bus(0),
+                name: "Line 1".to_string(),
+                stops: vec![Vec2::new(0.0, 0.0), Vec2::new(500.0, 0.0), Vec2::new(1000.0, 0.0)],
+                distances: vec![0.0, 500.0, 1000.0],
+                speed: 10.0,
+                dwell: 4.0,
+                headway,
+            }],
+            refresh_timer: 0.0,
+        }
+    }
+
+    #[test]
+    fn transit_trips_use_nearest_stops() {
+        let config = RidershipConfig::default();
+        let network = straight_line(60.0);
+        let trip = best_transit_trip(&network, Vec2::new(-14.0, 0.0), Vec2::new(1000.0, 28.0), &config).unwrap();
+        assert_eq!((trip.board, trip.alight), (0, 2));
+        // 100 s riding and 8 s stopping; 30 s walking and 30 s waiting, weighted
+        assert!((trip.time - (108.0 + 1.5 * 60.0)).abs() < 0.01);
+
+        // Too far from any stop, or no service
+        assert!(best_transit_trip(&network, Vec2::new(0.0, 900.0), Vec2::new(1000.0, 0.0), &config).is_none());
+        assert!(best_transit_trip(&straight_line(f32::INFINITY), Vec2::ZERO, Vec2::new(1000.0, 0.0), &config).is_none());
+    }
+
+    #[test]
+    fn quicker_modes_win_more_trips() {
+        let shares = mode_shares(&[(TravelMode::Car, 300.0), (TravelMode::Transit, 400.0), (TravelMode::Walk, 900.0)], 0.005);
+        let total: f32 = shares.iter().map(|(_, p)| p).sum();
+        assert!((total - 1.0).abs() < 1e-5);
+        assert!(shares[0].1 > shares[1].1 && shares[1].1 > shares[2].1);
+
+        let better = mode_shares(&[(TravelMode::Car, 300.0), (TravelMode::Transit, 250.0), (TravelMode::Walk, 900.0)], 0.005);
+        assert!(better[1].1 > shares[1].1);
+    }
+
+    #[test]
+    fn vehicles_fill_to_capacity_in_their_direction() {
+        let mut ridership = Ridership::default();
+        let stop = StopRidership {
+            waiting: vec![4, 0, 30],
+            ..default()
+        };
+        ridership.lines.insert(
+            LineKey::Bus(0),
+            LineRidership {
+                stops: vec![StopRidership::default(), stop, StopRidership::default()],
+                ..default()
+            },
+        );
+
+        // Five aboard get off here, five ride on; room for 20 more
+        let mut load = vec![0, 5, 5];
+        let boarded = ridership.serve_stop(LineKey::Bus(0), 1, true, &mut load, 25);
+        assert_eq!(boarded, 20);
+        assert_eq!(load, vec![0, 0, 25]);
+        let line = &ridership.lines[&LineKey::Bus(0)];
+        assert_eq!(line.stops[1].alighted, 5);
+        // Riders going the other way are left waiting
+        assert_eq!(line.stops[1].waiting, vec![4, 0, 10]);
+        assert_eq!(line.riders, 20);
+    }
+}
@@ -0,0 +1,194 @@
+//! Right-of-way at junctions.
+//!
+//! Junctions where two busy roads cross get traffic signals. Everywhere else
+//! priority follows the road hierarchy: the highest class of road through a
+//! junction has priority, roads one class below yield to it and lesser roads
+//! stop first. Where every road is the same class, everyone stops and goes
+//! in turn. Roundabout entries yield to the ring.
+
+use petgraph::graph::EdgeIndex;
+
+use crate::procgen::intersections::{JunctionGeometry, TurnKind};
+use crate::procgen::roads::{RoadGraph, RoadNodeType, RoadType};
+
+/// A vehicle's way through a junction: the road it arrives by, the road it
+/// leaves by and the turn between them.
+pub type Crossing = (EdgeIndex, EdgeIndex, TurnKind);
+
+/// Rule for traffic arriving along one road.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ApproachControl {
+    /// Carry on unless something is in the way.
+    Priority,
+    /// Give way to priority traffic, without stopping if the way is clear.
+    Yield,
+    /// Stop at the line, then give way.
+    Stop,
+}
+
+/// How a junction is controlled.
+#[derive(Clone, Debug, PartialEq)]
+pub enum JunctionControl {
+    /// Plain bends, dead ends and merges: nothing crosses.
+    Free,
+    /// Traffic signals.
+    Signals,
+    /// Signs, with the rule for each incoming road.
+    Signs(Vec<(EdgeIndex, ApproachControl)>),
+}
+
+impl JunctionControl {
+    /// Rule for traffic arriving along `edge`.
+    pub fn approach(&self, edge: EdgeIndex) -> ApproachControl {
+        match self {
+            JunctionControl::Signs(controls) => controls
+                .iter()
+                .find(|(e, _)| *e == edge)
+                .map_or(ApproachControl::Priority, |(_, control)| *control),
+            _ => ApproachControl::Priority,
+        }
+    }
+}
+
+/// Place of a road class in the hierarchy.
+pub fn road_rank(road_type: RoadType) -> u8 {
+    match road_type {
+        RoadType::Highway => 3,
+        RoadType::Major => 2,
+        RoadType::Minor => 1,
+        RoadType::Alley => 0,
+    }
+}
+
+/// Signals are warranted where major roads or highways feed the junction
+/// from both crossing directions.
+pub fn warrants_signals(geometry: &JunctionGeometry) -> bool {
+    let groups = geometry.signal_groups();
+    let busy = |group: usize| {
+        groups.iter().any(|&(edge, g)| {
+            g == group && geometry.leg(edge).is_some_and(|leg| road_rank(leg.road_type) >= road_rank(RoadType::Major))
+        })
+    };
+    busy(0) && busy(1)
+}
+
+/// Sign rules for incoming roads of the given classes.
+pub fn priority_controls(approaches: &[(EdgeIndex, RoadType)]) -> Vec<(EdgeIndex, ApproachControl)> {
+    let top = approaches.iter().map(|&(_, t)| road_rank(t)).max().unwrap_or(0);
+    let all_equal = approaches.iter().all(|&(_, t)| road_rank(t) == top);
+    approaches
+        .iter()
+        .map(|&(edge, road_type)| {
+            let control = match top - road_rank(road_type) {
+                _ if all_equal => ApproachControl::Stop,
+                0 => ApproachControl::Priority,
+                1 => ApproachControl::Yield,
+                _ => ApproachControl::Stop,
+            };
+            (edge, control)
+        })
+        .collect()
+}
+
+/// How the junction at `geometry` is controlled.
+pub fn junction_control(geometry: &JunctionGeometry, road_graph: &RoadGraph) -> JunctionControl {
+    if geometry.legs.len() < 3 {
+        return JunctionControl::Free;
+    }
+    let node_type = road_graph.node_by_index(geometry.node).map(|n| n.node_type);
+    let incoming = geometry.legs.iter().filter(|leg| leg.incoming);
+
+    match node_type {
+        Some(RoadNodeType::Roundabout) => {
+            // Entries give way to the ring
+            let on_ring = |neighbor| {
+                road_graph
+                    .node_by_index(neighbor)
+                    .is_some_and(|n| n.node_type == RoadNodeType::Roundabout)
+            };
+            JunctionControl::Signs(
+                incoming
+                    .map(|leg| {
+                        let control = if on_ring(leg.neighbor) { ApproachControl::Priority } else { ApproachControl::Yield };
+                        (leg.edge, control)
+                    })
+                    .collect(),
+            )
+        }
+        Some(merge) if merge.is_free_flow() => JunctionControl::Free,
+        _ if warrants_signals(geometry) => JunctionControl::Signals,
+        _ => {
+            let approaches: Vec<(EdgeIndex, RoadType)> = incoming.map(|leg| (leg.edge, leg.road_type)).collect();
+            if approaches.len() < 2 {
+                return JunctionControl::Free; // Only one way in; nothing to give way to
+            }
+            JunctionControl::Signs(priority_controls(&approaches))
+        }
+    }
+}
+
+/// Whether two ways through the same junction cross or merge.
+///
+/// Traffic from the same road follows in line; opposing straight-on traffic
+/// passes side by side; a kerbside turn only meets traffic heading for the
+/// same road.
+pub fn crossings_conflict(a: Crossing, b: Crossing) -> bool {
+    if a.0 == b.0 {
+        return false;
+    }
+    if a.1 == b.1 {
+        return true;
+    }
+    if a.2 == TurnKind::Straight && b.2 == TurnKind::Straight && a.0 == b.1 && a.1 == b.0 {
+        return false;
+    }
+    a.2 != TurnKind::Right && b.2 != TurnKind::Right
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hierarchy_decides_priority() {
+        let e = EdgeIndex::new;
+        let controls = priority_controls(&[
+            (e(0), RoadType::Major),
+            (e(1), RoadType::Minor),
+            (e(2), RoadType::Major),
+            (e(3), RoadType::Alley),
+        ]);
+        assert_eq!(
+            controls,
+            vec![
+                (e(0), ApproachControl::Priority),
+                (e(1), ApproachControl::Yield),
+                (e(2), ApproachControl::Priority),
+                (e(3), ApproachControl::Stop),
+            ]
+        );
+
+        // Equal roads: all-way stop
+        let controls = priority_controls(&[(e(0), RoadType::Minor), (e(1), RoadType::Minor), (e(2), RoadType::Minor)]);
+        assert!(controls.iter().all(|(_, c)| *c == ApproachControl::Stop));
+    }
+
+    #[test]
+    fn crossing_conflicts() {
+        let e = EdgeIndex::new;
+        // Four-way junction, roads 0..4 counter-clockwise
+        let north_south = (e(0), e(2), TurnKind::Straight);
+        let south_north = (e(2), e(0), TurnKind::Straight);
+        let east_west = (e(1), e(3), TurnKind::Straight);
+        let south_left = (e(2), e(1), TurnKind::Left);
+        let west_right = (e(3), e(0), TurnKind::Right);
+        let east_right = (e(1), e(2), TurnKind::Right);
+
+        assert!(!crossings_conflict(north_south, south_north));
+        assert!(crossings_conflict(north_south, east_west));
+        assert!(crossings_conflict(north_south, south_left));
+        assert!(!crossings_conflict(west_right, east_right));
+        assert!(crossings_conflict(east_right, north_south));
+        assert!(!crossings_conflict(north_south, (e(0), e(1), TurnKind::Left)));
+    }
+}
@@ -170,6 +170,8 @@ fn calculate_city_coverage(
                         let access = 1.0 - (distance / radius);
                         total_park_access += access;
                     }
+                    // Utilities serve through their networks, not by distance
+                    ServiceType::PowerPlant | ServiceType::WaterTower | ServiceType::PumpingStation => {}
                 }
             }
         }
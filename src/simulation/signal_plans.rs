@@ -0,0 +1,398 @@
+//! Signal timing plans for signalised junctions.
+//!
+//! A plan is an ordered list of phases, each giving green to a set of
+//! movements (an incoming road and the turn taken from it). Plans are laid
+//! out from a junction's signal groups and turn paths, and run fixed-time,
+//! actuated by the traffic waiting at them, or coordinated with their
+//! neighbours along a major road as a green wave.
+
+use std::collections::{HashMap, VecDeque};
+
+use petgraph::graph::{EdgeIndex, NodeIndex};
+
+use crate::procgen::intersections::{JunctionGeometry, TurnKind};
+use crate::procgen::roads::RoadType;
+use crate::render::traffic_lights::LightPhase;
+
+/// Traffic arriving along an edge and turning a given way.
+pub type Movement = (EdgeIndex, TurnKind);
+
+/// How a junction's movements are grouped into phases.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlanLayout {
+    /// Opposite approaches share a phase; left turns filter through gaps.
+    #[default]
+    Paired,
+    /// Like paired, but left turns get their own protected phase first.
+    ProtectedLeft,
+    /// Every approach gets a phase of its own.
+    Split,
+}
+
+impl PlanLayout {
+    /// The next layout in the edit cycle.
+    pub fn next(self) -> Self {
+        match self {
+            PlanLayout::Paired => PlanLayout::ProtectedLeft,
+            PlanLayout::ProtectedLeft => PlanLayout::Split,
+            PlanLayout::Split => PlanLayout::Paired,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PlanLayout::Paired => "Paired",
+            PlanLayout::ProtectedLeft => "Protected left",
+            PlanLayout::Split => "Split",
+        }
+    }
+
+    /// Default layout: protected lefts where a major road or highway comes in.
+    pub fn for_junction(geometry: &JunctionGeometry) -> Self {
+        let busy = geometry
+            .legs
+            .iter()
+            .any(|leg| leg.incoming && matches!(leg.road_type, RoadType::Highway | RoadType::Major));
+        if busy {
+            PlanLayout::ProtectedLeft
+        } else {
+            PlanLayout::Paired
+        }
+    }
+}
+
+/// How a signal decides when to change.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SignalMode {
+    /// Every phase gets its set green time.
+    FixedTime,
+    /// Greens run from a minimum up to a maximum while traffic keeps arriving,
+    /// and phases nobody is waiting for are skipped.
+    #[default]
+    Actuated,
+    /// Fixed cycle shared with the neighbouring signals, offset for a green wave.
+    Coordinated,
+}
+
+impl SignalMode {
+    /// The next mode in the edit cycle.
+    pub fn next(self) -> Self {
+        match self {
+            SignalMode::FixedTime => SignalMode::Actuated,
+            SignalMode::Actuated => SignalMode::Coordinated,
+            SignalMode::Coordinated => SignalMode::FixedTime,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SignalMode::FixedTime => "Fixed time",
+            SignalMode::Actuated => "Actuated",
+            SignalMode::Coordinated => "Coordinated",
+        }
+    }
+}
+
+/// One phase of a plan.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignalPhase {
+    /// Movements with green during this phase.
+    pub movements: Vec<Movement>,
+    /// Green time in fixed-time and coordinated modes.
+    pub green: f32,
+    /// Longest an actuated green may be extended to.
+    pub max_green: f32,
+}
+
+/// Ordered phases with the intervals between them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhasePlan {
+    pub layout: PlanLayout,
+    pub phases: Vec<SignalPhase>,
+    /// Shortest green an actuated phase runs for.
+    pub min_green: f32,
+    pub yellow: f32,
+    /// All-red clearance before the next phase.
+    pub all_red: f32,
+}
+
+/// Green wave timing for a signal on a coordinated corridor.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Coordination {
+    /// Cycle length shared by the corridor.
+    pub cycle: f32,
+    /// When in the cycle the main phase turns green.
+    pub offset: f32,
+    /// Phase serving through traffic along the corridor.
+    pub main_phase: usize,
+}
+
+impl PhasePlan {
+    /// Lay out phases for incoming roads in their signal groups, given every
+    /// turn `(from, to, kind)` through the junction.
+    pub fn build(layout: PlanLayout, approaches: &[(EdgeIndex, usize)], turns: &[(EdgeIndex, EdgeIndex, TurnKind)]) -> Self {
+        let movements_of = |edge: EdgeIndex| -> Vec<Movement> {
+            let mut movements: Vec<Movement> = Vec::new();
+            for &(from, _, kind) in turns {
+                if from == edge && kind != TurnKind::UTurn && !movements.contains(&(edge, kind)) {
+                    movements.push((edge, kind));
+                }
+            }
+            movements
+        };
+        let phase = |movements: Vec<Movement>, green: f32| SignalPhase {
+            movements,
+            green,
+            max_green: green * 2.5,
+        };
+
+        let groups = approaches.iter().map(|&(_, g)| g + 1).max().unwrap_or(0);
+        let mut phases = Vec::new();
+        match layout {
+            PlanLayout::Split => {
+                for &(edge, _) in approaches {
+                    phases.push(phase(movements_of(edge), 10.0));
+                }
+            }
+            PlanLayout::Paired | PlanLayout::ProtectedLeft => {
+                for group in 0..groups {
+                    let members: Vec<EdgeIndex> =
+                        approaches.iter().filter(|&&(_, g)| g == group).map(|&(e, _)| e).collect();
+                    let all: Vec<Movement> = members.iter().flat_map(|&e| movements_of(e)).collect();
+                    let (lefts, others): (Vec<Movement>, Vec<Movement>) =
+                        all.iter().partition(|(_, kind)| *kind == TurnKind::Left);
+
+                    // Lefts only need protecting from oncoming traffic in the same group
+                    if layout == PlanLayout::ProtectedLeft && members.len() > 1 && !lefts.is_empty() {
+                        phases.push(phase(lefts, 6.0));
+                        phases.push(phase(others, 12.0));
+                    } else {
+                        phases.push(phase(all, 12.0));
+                    }
+                }
+            }
+        }
+        phases.retain(|p| !p.movements.is_empty());
+
+        Self {
+            layout,
+            phases,
+            min_green: 5.0,
+            yellow: 3.0,
+            all_red: 2.0,
+        }
+    }
+
+    /// Plan for a junction from its geometry.
+    pub fn for_geometry(layout: PlanLayout, geometry: &JunctionGeometry) -> Self {
+        let turns: Vec<(EdgeIndex, EdgeIndex, TurnKind)> = geometry
+            .turn_paths
+            .iter()
+            .map(|path| (path.from_edge, path.to_edge, path.kind))
+            .collect();
+        Self::build(layout, &geometry.signal_groups(), &turns)
+    }
+
+    /// Whether `phase` gives green to `movement`.
+    pub fn serves(&self, phase: usize, movement: Movement) -> bool {
+        self.phases.get(phase).is_some_and(|p| p.movements.contains(&movement))
+    }
+
+    /// Whether `phase` gives green to any movement from `edge`.
+    pub fn serves_approach(&self, phase: usize, edge: EdgeIndex) -> bool {
+        self.phases.get(phase).is_some_and(|p| p.movements.iter().any(|(e, _)| *e == edge))
+    }
+
+    /// Whether any phase controls `movement`.
+    pub fn controls(&self, movement: Movement) -> bool {
+        self.phases.iter().any(|p| p.movements.contains(&movement))
+    }
+
+    /// Phase serving through traffic arriving along `edge`.
+    pub fn main_phase(&self, edge: EdgeIndex) -> usize {
+        (0..self.phases.len())
+            .find(|&i| self.serves(i, (edge, TurnKind::Straight)))
+            .unwrap_or(0)
+    }
+
+    /// Length of one cycle at the set green times.
+    pub fn cycle_length(&self) -> f32 {
+        self.phases.iter().map(|p| p.green + self.yellow + self.all_red).sum()
+    }
+
+    /// Phase and light `t` seconds into a cycle of `cycle` seconds that starts
+    /// with `first` turning green. Time beyond the plan's own cycle lengthens
+    /// the first phase's green.
+    pub fn state_at(&self, t: f32, cycle: f32, first: usize) -> (usize, LightPhase) {
+        let count = self.phases.len();
+        if count == 0 {
+            return (0, LightPhase::Red);
+        }
+        let extra = (cycle - self.cycle_length()).max(0.0);
+        let mut t = t.rem_euclid(cycle.max(f32::EPSILON));
+        for step in 0..count {
+            let index = (first + step) % count;
+            let green = self.phases[index].green + if step == 0 { extra } else { 0.0 };
+            if t < green {
+                return (index, LightPhase::Green);
+            }
+            t -= green;
+            if t < self.yellow {
+                return (index, LightPhase::Yellow);
+            }
+            t -= self.yellow;
+            if t < self.all_red {
+                return (index, LightPhase::Red);
+            }
+            t -= self.all_red;
+        }
+        ((first + count - 1) % count, LightPhase::Red)
+    }
+
+    /// Next phase to serve after `current`: the next one in order that has
+    /// traffic waiting, or simply the next one if nobody is waiting.
+    pub fn next_phase(&self, current: usize, demand: impl Fn(Movement) -> f32) -> usize {
+        let count = self.phases.len().max(1);
+        (1..=count)
+            .map(|step| (current + step) % count)
+            .find(|&i| self.phase_demand(i, &demand) > 0.0)
+            .unwrap_or((current + 1) % count)
+    }
+
+    /// Traffic waiting for the movements of `phase`.
+    pub fn phase_demand(&self, phase: usize, demand: impl Fn(Movement) -> f32) -> f32 {
+        self.phases.get(phase).map_or(0.0, |p| p.movements.iter().map(|&m| demand(m)).sum())
+    }
+
+    /// Traffic waiting for movements `phase` does not serve.
+    pub fn conflicting_demand(&self, phase: usize, demand: impl Fn(Movement) -> f32) -> f32 {
+        let mut waiting: Vec<Movement> = self
+            .phases
+            .iter()
+            .flat_map(|p| p.movements.iter().copied())
+            .filter(|&m| !self.serves(phase, m))
+            .collect();
+        waiting.dedup();
+        waiting.into_iter().map(demand).sum()
+    }
+}
+
+/// Whether an actuated green that has run `elapsed` seconds should end.
+///
+/// It holds for the minimum, then gaps out once its own traffic has cleared,
+/// or maxes out; with nobody else waiting it rests on green.
+pub fn actuated_green_ends(elapsed: f32, min_green: f32, max_green: f32, served: f32, waiting: f32) -> bool {
+    if waiting <= 0.0 || elapsed < min_green {
+        return false;
+    }
+    served <= 0.0 || elapsed >= max_green
+}
+
+/// Green wave offsets along corridors of signals.
+///
+/// `links` joins neighbouring signals with the travel time between them.
+/// Each connected corridor starts at zero from its lowest-numbered signal and
+/// each neighbour turns green that much later, wrapped into the cycle.
+pub fn green_wave_offsets(links: &[(NodeIndex, NodeIndex, f32)], cycle: impl Fn(NodeIndex) -> f32) -> HashMap<NodeIndex, f32> {
+    let mut neighbours: HashMap<NodeIndex, Vec<(NodeIndex, f32)>> = HashMap::new();
+    for &(a, b, time) in links {
+        neighbours.entry(a).or_default().push((b, time));
+        neighbours.entry(b).or_default().push((a, time));
+    }
+    let mut roots: Vec<NodeIndex> = neighbours.keys().copied().collect();
+    roots.sort();
+
+    let mut offsets = HashMap::new();
+    for root in roots {
+        if offsets.contains_key(&root) {
+            continue;
+        }
+        offsets.insert(root, 0.0);
+        let mut queue = VecDeque::from([root]);
+        while let Some(node) = queue.pop_front() {
+            let base = offsets[&node];
+            for &(next, time) in &neighbours[&node] {
+                if let std::collections::hash_map::Entry::Vacant(slot) = offsets.entry(next) {
+                    slot.insert((base + time).rem_euclid(cycle(next).max(1.0)));
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    offsets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Four-way junction: edges 0 and 2 opposite in group 0, 1 and 3 in group 1.
+    type Turn = (EdgeIndex, EdgeIndex, TurnKind);
+
+    fn crossroads() -> (Vec<(EdgeIndex, usize)>, Vec<Turn>) {
+        let e = EdgeIndex::new;
+        let approaches = vec![(e(0), 0), (e(1), 1), (e(2), 0), (e(3), 1)];
+        let mut turns = Vec::new();
+        for from in 0..4 {
+            turns.push((e(from), e((from + 2) % 4), TurnKind::Straight));
+            turns.push((e(from), e((from + 1) % 4), TurnKind::Right));
+            turns.push((e(from), e((from + 3) % 4), TurnKind::Left));
+        }
+        (approaches, turns)
+    }
+
+    #[test]
+    fn layouts_group_movements() {
+        let (approaches, turns) = crossroads();
+        let paired = PhasePlan::build(PlanLayout::Paired, &approaches, &turns);
+        assert_eq!(paired.phases.len(), 2);
+        assert!(paired.serves(0, (EdgeIndex::new(2), TurnKind::Left)));
+
+        let protected = PhasePlan::build(PlanLayout::ProtectedLeft, &approaches, &turns);
+        assert_eq!(protected.phases.len(), 4);
+        // Protected lefts run alone, then the through and right movements
+        assert!(protected.phases[0].movements.iter().all(|(_, kind)| *kind == TurnKind::Left));
+        assert!(!protected.serves(1, (EdgeIndex::new(0), TurnKind::Left)));
+        assert_eq!(protected.main_phase(EdgeIndex::new(1)), 3);
+
+        let split = PhasePlan::build(PlanLayout::Split, &approaches, &turns);
+        assert_eq!(split.phases.len(), 4);
+        assert!(split.serves_approach(2, EdgeIndex::new(2)));
+    }
+
+    #[test]
+    fn coordinated_cycle_stretches_main_phase() {
+        let (approaches, turns) = crossroads();
+        let plan = PhasePlan::build(PlanLayout::Paired, &approaches, &turns);
+        // Own cycle is 2 × (12 + 3 + 2) = 34s; run it in a 44s cycle from phase 1
+        assert_eq!(plan.cycle_length(), 34.0);
+        assert_eq!(plan.state_at(21.0, 44.0, 1), (1, LightPhase::Green));
+        assert_eq!(plan.state_at(23.0, 44.0, 1), (1, LightPhase::Yellow));
+        assert_eq!(plan.state_at(28.0, 44.0, 1), (0, LightPhase::Green));
+        assert_eq!(plan.state_at(44.0 + 1.0, 44.0, 1), (1, LightPhase::Green));
+    }
+
+    #[test]
+    fn actuation_skips_empty_phases_and_rests_on_green() {
+        let (approaches, turns) = crossroads();
+        let plan = PhasePlan::build(PlanLayout::ProtectedLeft, &approaches, &turns);
+        let waiting_on_3 = |(edge, kind): Movement| if edge == EdgeIndex::new(3) && kind == TurnKind::Straight { 2.0 } else { 0.0 };
+        assert_eq!(plan.next_phase(0, waiting_on_3), 3);
+        assert!(plan.conflicting_demand(0, waiting_on_3) > 0.0);
+
+        assert!(!actuated_green_ends(20.0, 5.0, 30.0, 0.0, 0.0));
+        assert!(!actuated_green_ends(3.0, 5.0, 30.0, 0.0, 4.0));
+        assert!(actuated_green_ends(6.0, 5.0, 30.0, 0.0, 4.0));
+        assert!(!actuated_green_ends(6.0, 5.0, 30.0, 3.0, 4.0));
+        assert!(actuated_green_ends(30.0, 5.0, 30.0, 3.0, 4.0));
+    }
+
+    #[test]
+    fn green_wave_offsets_follow_travel_time() {
+        let n = NodeIndex::new;
+        let offsets = green_wave_offsets(&[(n(4), n(7), 10.0), (n(7), n(2), 30.0)], |_| 34.0);
+        assert_eq!(offsets[&n(2)], 0.0);
+        assert_eq!(offsets[&n(7)], 30.0);
+        assert_eq!(offsets[&n(4)], 6.0);
+    }
+}
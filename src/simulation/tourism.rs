@@ -0,0 +1,578 @@
+//! Tourism: visitors drawn from the region by the city's sights.
+//!
+//! Landmarks and large parks are attractions, each with an attractiveness
+//! score. Visitors set off from the region in proportion to how attractive
+//! the city is, more in summer and fewer in bad weather, and arrive through
+//! the regional connections: by car through a highway connection, or by
+//! train where a line calls at a rail connection. Those who find a hotel
+//! room stay a while; the rest make a day of it and go home. Guests walk out
+//! from their hotels to the sights and on to the shops.
+//!
+//! Hotels are a kind of commercial building. When visitors are turned away
+//! for want of rooms, the business nearest an attraction opens as a hotel.
+//! Room charges and visitors' spending are taxed for the city.
+//!
+//! The calendar counts days as the sun goes round, and the seasons turn
+//! with them.
+
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::game_state::GameState;
+use crate::procgen::building_factory::BuildingArchetype;
+use crate::procgen::roads::RoadGraph;
+use crate::render::building_spawner::{Building, Park};
+use crate::render::day_night::{lerp_scalar, TimeOfDay};
+use crate::render::landmarks::Landmark;
+use crate::render::vehicle_meshes::generate_vehicle_mesh;
+use crate::render::weather::{Weather, WeatherState};
+use crate::tools::services::{ServiceBuilding, ServiceType, ServicesConfig};
+
+use super::bus_routes::TransitLedger;
+use super::flow_field::FlowDestination;
+use super::pedestrians::{PedestrianGoal, PedestrianTrips};
+use super::region::{nearest_gateway, road_gateways, road_node_near, spawn_route_car, Region, RegionConfig, RegionalConnection};
+use super::vehicle_traffic::VehicleType;
+use super::vehicles::PlannedRoute;
+
+pub struct TourismPlugin;
+
+impl Plugin for TourismPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TourismConfig>()
+            .init_resource::<Tourism>()
+            .init_resource::<TourismLedger>()
+            .add_systems(
+                Update,
+                (
+                    score_attractions,
+                    advance_calendar,
+                    update_tourism,
+                    send_sightseers,
+                    spawn_tourist_cars,
+                    finish_tourist_trips,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Tourism settings.
+#[derive(Resource, Clone, Debug)]
+pub struct TourismConfig {
+    /// How often visitors come and go (seconds).
+    pub update_interval: f32,
+    /// Days in each season.
+    pub days_per_season: u32,
+    /// Smallest park that draws visitors (square metres).
+    pub min_park_area: f32,
+    /// Attractiveness of a park per 100 square metres.
+    pub park_attractiveness: f32,
+    /// Total attractiveness at which the city draws half the visitors it
+    /// could; more sights still help, but less and less.
+    pub half_appeal: f32,
+    /// How long hotel guests stay, in updates.
+    pub stay: f32,
+    /// Rooms in each hotel.
+    pub hotel_rooms: u32,
+    /// Farthest from an attraction a business can open as a hotel.
+    pub hotel_radius: f32,
+    /// What a guest pays for their room each update.
+    pub room_rate: f32,
+    /// What a guest spends in shops each update.
+    pub guest_spend: f32,
+    /// What a day tripper spends over their visit.
+    pub day_trip_spend: f32,
+    /// Share of guests going out sightseeing each update.
+    pub sightseeing_share: f32,
+    /// Visitors represented by each visitor car on the road.
+    pub visitors_per_car: f32,
+    /// Most visitor cars on the road at once.
+    pub max_visitor_cars: usize,
+    pub seed: u64,
+}
+
+impl Default for TourismConfig {
+    fn default() -> Self {
+        Self {
+            update_interval: 2.0,
+            days_per_season: 1,
+            min_park_area: 800.0,
+            park_attractiveness: 1.0,
+            half_appeal: 100.0,
+            stay: 30.0,
+            hotel_rooms: 40,
+            hotel_radius: 250.0,
+            room_rate: 4.0,
+            guest_spend: 2.0,
+            day_trip_spend: 8.0,
+            sightseeing_share: 0.02,
+            visitors_per_car: 8.0,
+            max_visitor_cars: 8,
+            seed: 97531,
+        }
+    }
+}
+
+/// Time of year.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Season {
+    #[default]
+    Spring,
+    Summer,
+    Autumn,
+    Winter,
+}
+
+impl Season {
+    /// The season on a given day of the game.
+    pub fn on_day(day: u32, days_per_season: u32) -> Self {
+        match (day / days_per_season.max(1)) % 4 {
+            0 => Season::Spring,
+            1 => Season::Summer,
+            2 => Season::Autumn,
+            _ => Season::Winter,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Season::Spring => "Spring",
+            Season::Summer => "Summer",
+            Season::Autumn => "Autumn",
+            Season::Winter => "Winter",
+        }
+    }
+
+    /// How many set off to visit, relative to spring.
+    pub fn appeal(self) -> f32 {
+        match self {
+            Season::Spring => 1.0,
+            Season::Summer => 1.5,
+            Season::Autumn => 0.8,
+            Season::Winter => 0.4,
+        }
+    }
+}
+
+/// How many set off to visit in this weather, relative to a clear day.
+pub fn weather_appeal(weather: Weather) -> f32 {
+    match weather {
+        Weather::Clear => 1.0,
+        Weather::Foggy => 0.8,
+        Weather::Rainy => 0.55,
+        Weather::Stormy => 0.25,
+    }
+}
+
+/// Something visitors come to see.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Attraction {
+    pub score: f32,
+}
+
+/// A commercial building letting rooms to visitors.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Hotel {
+    pub rooms: u32,
+    pub guests: u32,
+}
+
+/// Marker for cars bringing visitors into the city.
+#[derive(Component)]
+pub struct TouristCar;
+
+/// The calendar, and visitors at the last update.
+#[derive(Resource, Default)]
+pub struct Tourism {
+    /// Days since the game started.
+    pub day: u32,
+    pub season: Season,
+    /// Attractiveness of every sight in the city together.
+    pub attractiveness: f32,
+    /// Visitors arriving at the last update.
+    pub arrivals: u32,
+    /// Hotel guests staying in the city.
+    pub guests: u32,
+    /// Hotel rooms in the city.
+    pub rooms: u32,
+    pub hotels: u32,
+    /// Visitors at the last update who found no room and went home at the
+    /// end of the day.
+    pub day_trippers: u32,
+    /// Visitors' spending at the last update.
+    pub spending: f32,
+    last_time: Option<f32>,
+    update_timer: f32,
+    /// Fractions of a visitor, rail rider and car carried to the next update.
+    pending: f32,
+    boardings: f32,
+    cars_due: f32,
+}
+
+/// Visitors' spending since the last budget tick, for tourism tax.
+#[derive(Resource, Default)]
+pub struct TourismLedger {
+    pub spending: f32,
+}
+
+/// Attractiveness of a park of the given size: nothing for small parks,
+/// then in proportion to its area.
+pub fn park_attractiveness(config: &TourismConfig, size: Vec2) -> f32 {
+    let area = size.x * size.y;
+    if area < config.min_park_area {
+        return 0.0;
+    }
+    area / 100.0 * config.park_attractiveness
+}
+
+/// Visitors setting off from the region over one update. Only connections
+/// bring them, and the more there is to see the more come, in good weather
+/// and in summer especially.
+pub fn visitor_arrivals(
+    region_config: &RegionConfig,
+    region: &Region,
+    config: &TourismConfig,
+    attractiveness: f32,
+    season: Season,
+    weather: f32,
+) -> f32 {
+    if region.capacity <= 0.0 || attractiveness <= 0.0 {
+        return 0.0;
+    }
+    let appeal = attractiveness / (attractiveness + config.half_appeal);
+    region.population * region_config.visitor_rate * region.capacity.min(1.0) * appeal * season.appeal() * weather
+}
+
+/// Book arriving visitors into hotel rooms, filling the hotels in the order
+/// given. Returns how many found a room.
+pub fn book_rooms(hotels: &mut [Hotel], visitors: u32) -> u32 {
+    let mut left = visitors;
+    for hotel in hotels.iter_mut() {
+        let booked = hotel.rooms.saturating_sub(hotel.guests).min(left);
+        hotel.guests += booked;
+        left -= booked;
+    }
+    visitors - left
+}
+
+/// The business nearest an attraction, within `radius` of it, to open as
+/// a hotel.
+pub fn pick_hotel_site(candidates: &[(Entity, Vec2)], attractions: &[Vec2], radius: f32) -> Option<Entity> {
+    candidates
+        .iter()
+        .filter_map(|&(entity, position)| {
+            let nearest = attractions.iter().map(|a| a.distance(position)).min_by(f32::total_cmp)?;
+            (nearest <= radius).then_some((entity, nearest))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(entity, _)| entity)
+}
+
+/// Give new landmarks and large parks their attractiveness.
+fn score_attractions(
+    mut commands: Commands,
+    config: Res<TourismConfig>,
+    services_config: Res<ServicesConfig>,
+    landmarks: Query<(Entity, &Landmark), Added<Landmark>>,
+    parks: Query<(Entity, &Park), Added<Park>>,
+    services: Query<(Entity, &ServiceBuilding), Added<ServiceBuilding>>,
+) {
+    for (entity, landmark) in &landmarks {
+        commands.entity(entity).insert(Attraction {
+            score: landmark.kind.attractiveness(),
+        });
+    }
+    let scored_parks = parks
+        .iter()
+        .map(|(entity, park)| (entity, park.size))
+        .chain(
+            services
+                .iter()
+                .filter(|(_, service)| service.service_type == ServiceType::Park)
+                // Placed parks are square, twice the service size across
+                .map(|(entity, _)| (entity, Vec2::splat(services_config.building_size * 2.0))),
+        );
+    for (entity, size) in scored_parks {
+        let score = park_attractiveness(&config, size);
+        if score > 0.0 {
+            commands.entity(entity).insert(Attraction { score });
+        }
+    }
+}
+
+/// Count the days as the sun comes round, and move on the seasons.
+fn advance_calendar(config: Res<TourismConfig>, time_of_day: Res<TimeOfDay>, mut tourism: ResMut<Tourism>) {
+    if tourism.last_time.is_some_and(|last| time_of_day.time < last) {
+        tourism.day += 1;
+        let season = Season::on_day(tourism.day, config.days_per_season);
+        if season != tourism.season {
+            info!("{} has come", season.name());
+            tourism.season = season;
+        }
+    }
+    tourism.last_time = Some(time_of_day.time);
+}
+
+/// Bring in visitors, check guests in and out of hotels, and open another
+/// hotel when there are no rooms to be had.
+#[allow(clippy::too_many_arguments)]
+fn update_tourism(
+    mut commands: Commands,
+    time: Res<Time>,
+    config: Res<TourismConfig>,
+    region_config: Res<RegionConfig>,
+    region: Res<Region>,
+    weather: Res<WeatherState>,
+    attractions: Query<(&Attraction, &Transform)>,
+    mut hotels: Query<(&mut Hotel, &Transform)>,
+    businesses: Query<(Entity, &Building, &Transform), Without<Hotel>>,
+    mut tourism: ResMut<Tourism>,
+    mut ledger: ResMut<TourismLedger>,
+    mut transit: ResMut<TransitLedger>,
+) {
+    tourism.update_timer += time.delta_secs();
+    if tourism.update_timer < config.update_interval {
+        return;
+    }
+    tourism.update_timer = 0.0;
+
+    let flat = |transform: &Transform| Vec2::new(transform.translation.x, transform.translation.z);
+    let sights: Vec<Vec2> = attractions.iter().map(|(_, transform)| flat(transform)).collect();
+    tourism.attractiveness = attractions.iter().map(|(attraction, _)| attraction.score).sum();
+
+    // Guests leave at the end of their stay
+    for (mut hotel, _) in &mut hotels {
+        let leaving = (hotel.guests as f32 / config.stay.max(1.0)).ceil() as u32;
+        hotel.guests = hotel.guests.saturating_sub(leaving);
+    }
+
+    let appeal = lerp_scalar(
+        weather_appeal(weather.current),
+        weather_appeal(weather.target),
+        weather.transition,
+    );
+    tourism.pending += visitor_arrivals(&region_config, &region, &config, tourism.attractiveness, tourism.season, appeal);
+    let arrivals = tourism.pending.floor() as u32;
+    tourism.pending -= arrivals as f32;
+
+    // Hotels nearest the sights fill first
+    let mut by_sights: Vec<_> = hotels
+        .iter_mut()
+        .map(|(hotel, transform)| {
+            let position = flat(transform);
+            let distance = sights.iter().map(|s| s.distance(position)).min_by(f32::total_cmp).unwrap_or(f32::MAX);
+            (distance, hotel)
+        })
+        .collect();
+    by_sights.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut rooms: Vec<Hotel> = by_sights.iter().map(|(_, hotel)| **hotel).collect();
+    let booked = book_rooms(&mut rooms, arrivals);
+    for ((_, hotel), booked) in by_sights.iter_mut().zip(rooms) {
+        **hotel = booked;
+    }
+
+    tourism.arrivals = arrivals;
+    tourism.day_trippers = arrivals - booked;
+    tourism.hotels = by_sights.len() as u32;
+    tourism.rooms = by_sights.iter().map(|(_, hotel)| hotel.rooms).sum();
+    tourism.guests = by_sights.iter().map(|(_, hotel)| hotel.guests).sum();
+    tourism.spending = tourism.guests as f32 * (config.room_rate + config.guest_spend)
+        + tourism.day_trippers as f32 * config.day_trip_spend;
+    ledger.spending += tourism.spending;
+
+    // Visitors by train pay their fares; the rest drive in
+    let rail_share = region.rail_share();
+    tourism.boardings += arrivals as f32 * rail_share;
+    let whole = tourism.boardings.floor();
+    transit.boardings += whole as u32;
+    tourism.boardings -= whole;
+    tourism.cars_due += arrivals as f32 * (1.0 - rail_share) / config.visitors_per_car.max(1.0);
+
+    // Turned away for want of rooms: the best placed business opens as a hotel
+    if tourism.day_trippers > 0 {
+        let candidates: Vec<(Entity, Vec2)> = businesses
+            .iter()
+            .filter(|(_, building, _)| building.building_type == BuildingArchetype::Commercial)
+            .map(|(entity, _, transform)| (entity, flat(transform)))
+            .collect();
+        if let Some(entity) = pick_hotel_site(&candidates, &sights, config.hotel_radius) {
+            commands.entity(entity).insert(Hotel {
+                rooms: config.hotel_rooms,
+                guests: 0,
+            });
+            info!("A hotel has opened with {} rooms", config.hotel_rooms);
+        }
+    }
+}
+
+/// Send hotel guests out on foot to the sights, and visitors at the sights
+/// on to the shops.
+fn send_sightseers(
+    time: Res<Time>,
+    config: Res<TourismConfig>,
+    hotels: Query<(&Hotel, &Transform)>,
+    attractions: Query<(Entity, &Attraction, &Transform)>,
+    mut trips: ResMut<PedestrianTrips>,
+    mut timer: Local<f32>,
+    mut local_rng: Local<Option<StdRng>>,
+) {
+    *timer += time.delta_secs();
+    if *timer < config.update_interval {
+        return;
+    }
+    *timer = 0.0;
+
+    let sights: Vec<(Entity, Vec2, f32)> = attractions
+        .iter()
+        .map(|(entity, attraction, transform)| {
+            (entity, Vec2::new(transform.translation.x, transform.translation.z), attraction.score)
+        })
+        .collect();
+    let total: f32 = sights.iter().map(|&(_, _, score)| score).sum();
+    if total <= 0.0 {
+        return;
+    }
+    let rng = local_rng.get_or_insert_with(|| StdRng::seed_from_u64(config.seed));
+    for (hotel, transform) in &hotels {
+        let from = Vec2::new(transform.translation.x, transform.translation.z);
+        let outings = hotel.guests as f32 * config.sightseeing_share;
+        let walkers = outings as u32 + u32::from(rng.gen::<f32>() < outings.fract());
+        for _ in 0..walkers {
+            // The better the sight, the more go to see it
+            let mut pick = rng.gen::<f32>() * total;
+            let Some(&(entity, position, _)) = sights.iter().find(|&&(_, _, score)| {
+                pick -= score;
+                pick <= 0.0
+            }) else {
+                continue;
+            };
+            trips.queued.push((from, PedestrianGoal::Building { entity, position }));
+            trips.queued.push((position, PedestrianGoal::Nearest(FlowDestination::Shops)));
+        }
+    }
+}
+
+/// Drive arriving visitors in from the nearest highway connection to their
+/// hotel, or to a sight if they found no room.
+#[allow(clippy::too_many_arguments)]
+fn spawn_tourist_cars(
+    mut commands: Commands,
+    config: Res<TourismConfig>,
+    road_graph: Res<RoadGraph>,
+    connections: Query<&RegionalConnection>,
+    hotels: Query<&Transform, With<Hotel>>,
+    attractions: Query<&Transform, With<Attraction>>,
+    cars: Query<(), With<TouristCar>>,
+    mut tourism: ResMut<Tourism>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut local_rng: Local<Option<StdRng>>,
+    mut body: Local<Option<Handle<Mesh>>>,
+) {
+    if tourism.cars_due < 1.0 {
+        return;
+    }
+    tourism.cars_due -= 1.0;
+    if cars.iter().count() >= config.max_visitor_cars {
+        return;
+    }
+    let gateways = road_gateways(&road_graph, &connections);
+    let rng = local_rng.get_or_insert_with(|| StdRng::seed_from_u64(config.seed));
+    let destinations: Vec<&Transform> = if hotels.is_empty() {
+        attractions.iter().collect()
+    } else {
+        hotels.iter().collect()
+    };
+    let destinations: Vec<Vec2> = destinations
+        .into_iter()
+        .map(|transform| Vec2::new(transform.translation.x, transform.translation.z))
+        .collect();
+    if destinations.is_empty() {
+        return;
+    }
+    let place = destinations[rng.gen_range(0..destinations.len())];
+    let (Some(start), Some(end)) = (
+        nearest_gateway(&road_graph, &gateways, place),
+        road_node_near(&road_graph, place, 150.0),
+    ) else {
+        return;
+    };
+    let body = body
+        .get_or_insert_with(|| meshes.add(generate_vehicle_mesh(&VehicleType::Sedan.mesh_config())))
+        .clone();
+    if let Some(mut car) = spawn_route_car(&mut commands, &road_graph, &mut materials, rng, body, start, end) {
+        car.insert(TouristCar);
+    }
+}
+
+/// Take visitor cars off the road once they arrive.
+fn finish_tourist_trips(mut commands: Commands, cars: Query<(Entity, &PlannedRoute), With<TouristCar>>) {
+    for (entity, route) in &cars {
+        if route.arrived {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visitors_come_for_the_sights_in_good_weather_and_summer() {
+        let config = TourismConfig::default();
+        let region_config = RegionConfig::default();
+        let mut region = Region::default();
+        region.population = 250_000.0;
+        let arrivals = |region: &Region, attractiveness, season, weather| {
+            visitor_arrivals(&region_config, region, &config, attractiveness, season, weather)
+        };
+
+        // Nobody comes without a connection, or with nothing to see
+        assert_eq!(arrivals(&region, 100.0, Season::Spring, 1.0), 0.0);
+        region.capacity = 1.0;
+        assert_eq!(arrivals(&region, 0.0, Season::Spring, 1.0), 0.0);
+
+        let spring = arrivals(&region, 100.0, Season::Spring, 1.0);
+        assert!((spring - 5.0).abs() < 1e-3);
+        assert!(arrivals(&region, 300.0, Season::Spring, 1.0) < spring * 3.0);
+        assert!(arrivals(&region, 100.0, Season::Summer, 1.0) > spring);
+        assert!(arrivals(&region, 100.0, Season::Winter, 1.0) < spring);
+        assert!(arrivals(&region, 100.0, Season::Spring, weather_appeal(Weather::Stormy)) < spring / 2.0);
+
+        assert_eq!(Season::on_day(0, 2), Season::Spring);
+        assert_eq!(Season::on_day(3, 2), Season::Summer);
+        assert_eq!(Season::on_day(7, 2), Season::Winter);
+        assert_eq!(Season::on_day(8, 2), Season::Spring);
+    }
+
+    #[test]
+    fn only_large_parks_are_attractions() {
+        let config = TourismConfig::default();
+        assert_eq!(park_attractiveness(&config, Vec2::new(20.0, 30.0)), 0.0);
+        assert_eq!(park_attractiveness(&config, Vec2::new(30.0, 40.0)), 12.0);
+    }
+
+    #[test]
+    fn visitors_fill_hotels_then_make_a_day_trip() {
+        let mut hotels = [
+            Hotel { rooms: 40, guests: 35 },
+            Hotel { rooms: 40, guests: 0 },
+        ];
+        assert_eq!(book_rooms(&mut hotels, 20), 20);
+        assert_eq!((hotels[0].guests, hotels[1].guests), (40, 15));
+        assert_eq!(book_rooms(&mut hotels, 30), 25);
+        assert_eq!(hotels[1].guests, 40);
+
+        // The business nearest a sight opens as the next hotel
+        let [near, far, farther] = [1, 2, 3].map(Entity::from_raw);
+        let sights = [Vec2::ZERO, Vec2::new(1000.0, 0.0)];
+        let candidates = [(far, Vec2::new(0.0, 120.0)), (near, Vec2::new(950.0, 0.0)), (farther, Vec2::new(500.0, 0.0))];
+        assert_eq!(pick_hotel_site(&candidates, &sights, 250.0), Some(near));
+        assert_eq!(pick_hotel_site(&candidates[2..], &sights, 250.0), None);
+        assert_eq!(pick_hotel_site(&candidates, &[], 250.0), None);
+    }
+}
@@ -11,9 +11,11 @@ use bevy::prelude::*;
 use petgraph::graph::EdgeIndex;
 use rand::{rngs::StdRng, Rng, SeedableRng};
 
-use crate::procgen::roads::{RoadGraph, RoadType};
+use crate::procgen::roads::RoadGraph;
 use crate::render::road_mesh::RoadMeshGenerated;
 
+use super::parking::ParkingSearch;
+use super::ridership::ModeShare;
 use super::SimulationTick;
 
 pub struct TrafficCaPlugin;
@@ -35,11 +37,13 @@ impl Plugin for TrafficCaPlugin {
     }
 }
 
+/// Build lanes once roads exist, and again whenever roads are edited.
 fn should_initialize_ca(
     road_mesh: Query<&RoadMeshGenerated>,
+    road_graph: Res<RoadGraph>,
     state: Res<TrafficCaState>,
 ) -> bool {
-    !road_mesh.is_empty() && !state.initialized
+    !road_mesh.is_empty() && (!state.initialized || road_graph.is_changed())
 }
 
 /// Configuration for CA traffic simulation.
@@ -51,6 +55,8 @@ pub struct TrafficConfig {
     pub max_velocity: u8,
     /// Probability of random slowdown.
     pub slowdown_prob: f32,
+    /// Density lanes are topped up to if every trip were made by car.
+    pub full_car_density: f32,
 }
 
 impl Default for TrafficConfig {
@@ -59,6 +65,7 @@ impl Default for TrafficConfig {
             cell_size: 7.5, // Typical car length + gap
             max_velocity: 5,
             slowdown_prob: 0.3,
+            full_car_density: 0.095,
         }
     }
 }
@@ -237,7 +244,8 @@ fn initialize_ca_roads(
     mut state: ResMut<TrafficCaState>,
 ) {
     state.initialized = true;
-    state.rng_seed = 54321;
+    state.segments.clear();
+    state.segment_edges.clear();
 
     let mut rng = StdRng::seed_from_u64(state.rng_seed);
 
@@ -252,13 +260,8 @@ fn initialize_ca_roads(
             continue; // Skip very short segments
         }
 
-        // Determine lane count based on road type
-        let (forward_lanes, backward_lanes) = match edge.road_type {
-            RoadType::Highway => (3, 3),
-            RoadType::Major => (2, 2),
-            RoadType::Minor => (1, 1),
-            RoadType::Alley => (1, 0), // One-way alleys
-        };
+        // Lane count from road type and one-way direction
+        let (forward_lanes, backward_lanes) = edge.lane_counts();
 
         let segment = CaRoadSegment::new(cell_count, forward_lanes, backward_lanes);
 
@@ -324,8 +327,13 @@ fn update_traffic_ca(
 }
 
 /// Spawn and despawn vehicles at segment boundaries.
+///
+/// Lanes are topped up in proportion to the share of trips made by car, so
+/// trips won by transit or walking are cars kept off the road.
 fn spawn_despawn_vehicles(
     config: Res<TrafficConfig>,
+    mode_share: Res<ModeShare>,
+    parking_search: Res<ParkingSearch>,
     mut state: ResMut<TrafficCaState>,
     mut tick_events: EventReader<SimulationTick>,
 ) {
@@ -337,12 +345,20 @@ fn spawn_despawn_vehicles(
     let mut rng = StdRng::seed_from_u64(state.rng_seed.wrapping_add(1000));
 
     // Target density to maintain
-    let target_density = 0.08;
+    let target_density = config.full_car_density * mode_share.car;
     let spawn_chance = 0.1;
     let despawn_chance = 0.05;
 
-    for segment in state.segments.iter_mut() {
+    let state = &mut *state;
+    for (segment, &edge) in state.segments.iter_mut().zip(&state.segment_edges) {
         let current_density = segment.average_density();
+        // Drivers cruising for parking add to the traffic on their streets
+        let cruising = parking_search.on_edge(edge);
+        let target_density = if cruising > 0.0 {
+            target_density + cruising / segment.total_capacity().max(1) as f32
+        } else {
+            target_density
+        };
 
         // Spawn at entrances if below target
         if current_density < target_density && rng.gen::<f32>() < spawn_chance {
@@ -0,0 +1,817 @@
+//! Utility networks: power, water and sewage.
+//!
+//! Power plants, water towers and pumping stations are service buildings,
+//! each feeding a network of its kind. Power lines, water mains and sewers
+//! all run along the roads, so every connected stretch of road carries one
+//! network of each kind, and a plant joins the networks of the road it
+//! stands by. Buildings draw from a road within reach; power also passes
+//! from building to building where they stand close together.
+//!
+//! A network serves the buildings nearest its plants first, until its
+//! capacity runs out. Buildings without power, water or sewage show an icon
+//! and house and employ nobody, and nothing new grows where the networks
+//! don't reach or have nothing to spare.
+//!
+//! A generated city starts with enough plants on its outskirts to serve
+//! every building in it.
+
+use bevy::prelude::*;
+use bevy::render::primitives::Aabb;
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use crate::game_state::GameState;
+use crate::procgen::building_factory::BuildingArchetype;
+use crate::procgen::road_generator::RoadsGenerated;
+use crate::procgen::roads::RoadGraph;
+use crate::render::building_spawner::{Building, BuildingsSpawned};
+use crate::tools::services::{spawn_service_building, ServiceBuilding, ServiceType, ServicesConfig};
+
+pub struct UtilityPlugin;
+
+impl Plugin for UtilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<UtilityConfig>()
+            .init_resource::<UtilityNetworks>()
+            .init_resource::<StartingUtilitiesPlaced>()
+            .add_systems(Startup, setup_utility_assets)
+            .add_systems(
+                Update,
+                (
+                    place_starting_utilities.run_if(should_place_starting_utilities),
+                    update_utility_networks,
+                    update_utility_icons,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Utility network settings.
+#[derive(Resource, Clone, Debug)]
+pub struct UtilityConfig {
+    /// How often the networks are worked out again (seconds).
+    pub update_interval: f32,
+    /// Farthest a building can stand from the road that serves it.
+    pub reach: f32,
+    /// Buildings this close together, centre to centre, pass power on.
+    pub adjacency: f32,
+    /// What each power plant generates (MW).
+    pub power_plant_capacity: f32,
+    /// What each water tower supplies (kL).
+    pub water_tower_capacity: f32,
+    /// What each pumping station takes away (kL).
+    pub pumping_station_capacity: f32,
+    /// Capacity the starting plants leave over the generated city's demand.
+    pub starting_headroom: f32,
+    /// Height of the icon over a building that is cut off.
+    pub icon_lift: f32,
+}
+
+impl Default for UtilityConfig {
+    fn default() -> Self {
+        Self {
+            update_interval: 2.0,
+            reach: 30.0,
+            adjacency: 25.0,
+            power_plant_capacity: 2000.0,
+            water_tower_capacity: 1500.0,
+            pumping_station_capacity: 1500.0,
+            starting_headroom: 1.25,
+            icon_lift: 6.0,
+        }
+    }
+}
+
+impl UtilityConfig {
+    /// What each plant of a utility can supply.
+    pub fn capacity(&self, utility: Utility) -> f32 {
+        match utility {
+            Utility::Power => self.power_plant_capacity,
+            Utility::Water => self.water_tower_capacity,
+            Utility::Sewage => self.pumping_station_capacity,
+        }
+    }
+}
+
+/// A kind of utility network.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Utility {
+    Power,
+    Water,
+    Sewage,
+}
+
+impl Utility {
+    pub const ALL: [Utility; 3] = [Utility::Power, Utility::Water, Utility::Sewage];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Utility::Power => "Power",
+            Utility::Water => "Water",
+            Utility::Sewage => "Sewage",
+        }
+    }
+
+    pub fn unit(self) -> &'static str {
+        match self {
+            Utility::Power => "MW",
+            Utility::Water | Utility::Sewage => "kL",
+        }
+    }
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// The service building that feeds this utility.
+    pub fn producer(self) -> ServiceType {
+        match self {
+            Utility::Power => ServiceType::PowerPlant,
+            Utility::Water => ServiceType::WaterTower,
+            Utility::Sewage => ServiceType::PumpingStation,
+        }
+    }
+
+    /// The utility a service building feeds, if any.
+    pub fn fed_by(service_type: ServiceType) -> Option<Self> {
+        Self::ALL.into_iter().find(|utility| utility.producer() == service_type)
+    }
+
+    /// What a building of the given kind draws from this utility.
+    pub fn demand(self, archetype: BuildingArchetype) -> f32 {
+        match (self, archetype) {
+            (Utility::Power, BuildingArchetype::Residential) => 1.0,
+            (Utility::Power, BuildingArchetype::Commercial) => 2.0,
+            (Utility::Power, BuildingArchetype::Industrial) => 3.0,
+            (Utility::Water, BuildingArchetype::Residential) => 2.0,
+            (Utility::Water, BuildingArchetype::Commercial) => 1.0,
+            (Utility::Water, BuildingArchetype::Industrial) => 2.5,
+            (Utility::Sewage, BuildingArchetype::Residential) => 2.0,
+            (Utility::Sewage, BuildingArchetype::Commercial) => 1.0,
+            (Utility::Sewage, BuildingArchetype::Industrial) => 2.0,
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Utility::Power => Color::srgb(0.95, 0.8, 0.2),
+            Utility::Water => Color::srgb(0.3, 0.7, 0.95),
+            Utility::Sewage => Color::srgb(0.55, 0.45, 0.3),
+        }
+    }
+
+    /// The utility after this one, for cycling the overlay.
+    pub fn next(self) -> Option<Self> {
+        match self {
+            Utility::Power => Some(Utility::Water),
+            Utility::Water => Some(Utility::Sewage),
+            Utility::Sewage => None,
+        }
+    }
+}
+
+/// Which utilities reach a building.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UtilityService {
+    pub power: bool,
+    pub water: bool,
+    pub sewage: bool,
+}
+
+impl UtilityService {
+    pub fn has(&self, utility: Utility) -> bool {
+        match utility {
+            Utility::Power => self.power,
+            Utility::Water => self.water,
+            Utility::Sewage => self.sewage,
+        }
+    }
+
+    fn set(&mut self, utility: Utility, served: bool) {
+        match utility {
+            Utility::Power => self.power = served,
+            Utility::Water => self.water = served,
+            Utility::Sewage => self.sewage = served,
+        }
+    }
+
+    /// The first utility the building goes without, if any.
+    pub fn missing(&self) -> Option<Utility> {
+        Utility::ALL.into_iter().find(|&utility| !self.has(utility))
+    }
+
+    pub fn is_served(&self) -> bool {
+        self.missing().is_none()
+    }
+}
+
+/// One utility network: the plants of one kind along a connected stretch
+/// of road, and the buildings drawing on them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UtilityNetwork {
+    pub utility: Utility,
+    /// The stretch of road the network runs along.
+    pub component: usize,
+    pub producers: u32,
+    /// What the plants can supply.
+    pub capacity: f32,
+    /// What the buildings on the network would draw.
+    pub demand: f32,
+    /// What the network delivers.
+    pub supply: f32,
+    pub served: u32,
+    pub unserved: u32,
+}
+
+impl UtilityNetwork {
+    fn new(utility: Utility, component: usize) -> Self {
+        Self {
+            utility,
+            component,
+            producers: 0,
+            capacity: 0.0,
+            demand: 0.0,
+            supply: 0.0,
+            served: 0,
+            unserved: 0,
+        }
+    }
+
+    /// Demand as a share of capacity.
+    pub fn load(&self) -> f32 {
+        if self.capacity > 0.0 {
+            self.demand / self.capacity
+        } else {
+            0.0
+        }
+    }
+
+    pub fn spare(&self) -> f32 {
+        (self.capacity - self.supply).max(0.0)
+    }
+}
+
+/// The city's utility networks as of the last update.
+#[derive(Resource, Default)]
+pub struct UtilityNetworks {
+    pub networks: Vec<UtilityNetwork>,
+    /// Buildings out of reach of any network of each utility.
+    pub unconnected: [u32; 3],
+    edge_components: HashMap<EdgeIndex, usize>,
+    segments: SegmentGrid,
+    update_timer: f32,
+}
+
+impl UtilityNetworks {
+    /// The network of a utility along a stretch of road.
+    pub fn network(&self, utility: Utility, component: usize) -> Option<&UtilityNetwork> {
+        self.networks
+            .iter()
+            .find(|network| network.utility == utility && network.component == component)
+    }
+
+    /// The network of a utility running along a road.
+    pub fn edge_network(&self, utility: Utility, edge: EdgeIndex) -> Option<&UtilityNetwork> {
+        self.edge_components
+            .get(&edge)
+            .and_then(|&component| self.network(utility, component))
+    }
+
+    /// Whether a new building at `position` would get power, water and
+    /// sewage.
+    pub fn can_serve(&self, position: Vec2, reach: f32) -> bool {
+        let Some((component, _)) = self.segments.nearest(position, reach) else {
+            return false;
+        };
+        Utility::ALL.into_iter().all(|utility| {
+            self.network(utility, component)
+                .is_some_and(|network| network.spare() > 0.0)
+        })
+    }
+
+    /// Capacity, demand and supply of a utility across all its networks.
+    pub fn totals(&self, utility: Utility) -> (f32, f32, f32) {
+        self.networks
+            .iter()
+            .filter(|network| network.utility == utility)
+            .fold((0.0, 0.0, 0.0), |(capacity, demand, supply), network| {
+                (capacity + network.capacity, demand + network.demand, supply + network.supply)
+            })
+    }
+}
+
+/// Whether the generated city has been given its starting plants.
+#[derive(Resource, Default)]
+pub struct StartingUtilitiesPlaced(pub bool);
+
+/// Meshes and materials for the icons over buildings that are cut off.
+#[derive(Resource)]
+pub struct UtilityAssets {
+    icon: Handle<Mesh>,
+    materials: [Handle<StandardMaterial>; 3],
+}
+
+/// Icon floating over a building that goes without a utility.
+#[derive(Component)]
+pub struct UtilityIcon {
+    pub building: Entity,
+    pub utility: Utility,
+}
+
+/// A building drawing on a utility.
+#[derive(Clone, Copy, Debug)]
+pub struct Consumer {
+    pub position: Vec2,
+    /// The stretch of road it draws from, if one is in reach.
+    pub component: Option<usize>,
+    pub demand: f32,
+}
+
+/// A plant feeding a utility.
+#[derive(Clone, Copy, Debug)]
+pub struct Producer {
+    pub position: Vec2,
+    pub component: usize,
+    pub capacity: f32,
+}
+
+/// Share each network's capacity among its consumers, nearest to one of
+/// its plants first. Returns whether each consumer is served, and the
+/// networks.
+pub fn allocate(utility: Utility, producers: &[Producer], consumers: &[Consumer]) -> (Vec<bool>, Vec<UtilityNetwork>) {
+    let mut networks: BTreeMap<usize, UtilityNetwork> = BTreeMap::new();
+    for producer in producers {
+        let network = networks
+            .entry(producer.component)
+            .or_insert_with(|| UtilityNetwork::new(utility, producer.component));
+        network.producers += 1;
+        network.capacity += producer.capacity;
+    }
+
+    let mut queue: Vec<(usize, usize, f32)> = consumers
+        .iter()
+        .enumerate()
+        .filter_map(|(i, consumer)| {
+            let component = consumer.component.filter(|c| networks.contains_key(c))?;
+            let distance = producers
+                .iter()
+                .filter(|producer| producer.component == component)
+                .map(|producer| producer.position.distance(consumer.position))
+                .fold(f32::INFINITY, f32::min);
+            Some((i, component, distance))
+        })
+        .collect();
+    queue.sort_by(|a, b| a.1.cmp(&b.1).then(a.2.total_cmp(&b.2)));
+
+    let mut served = vec![false; consumers.len()];
+    for (i, component, _) in queue {
+        let demand = consumers[i].demand;
+        let network = networks.get_mut(&component).expect("queued consumers are on a network");
+        network.demand += demand;
+        if network.supply + demand <= network.capacity {
+            network.supply += demand;
+            network.served += 1;
+            served[i] = true;
+        } else {
+            network.unserved += 1;
+        }
+    }
+    (served, networks.into_values().collect())
+}
+
+/// Pass power on from building to building: a building with no line in
+/// reach joins the network of any building within `distance` that has one.
+pub fn spread_between_buildings(positions: &[Vec2], components: &mut [Option<usize>], distance: f32) {
+    let cell = |p: Vec2| ((p.x / distance).floor() as i32, (p.y / distance).floor() as i32);
+    let mut grid: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+    for (i, &position) in positions.iter().enumerate() {
+        grid.entry(cell(position)).or_default().push(i);
+    }
+
+    let mut open: VecDeque<usize> = (0..positions.len()).filter(|&i| components[i].is_some()).collect();
+    while let Some(i) = open.pop_front() {
+        let (cx, cy) = cell(positions[i]);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                let Some(neighbours) = grid.get(&(cx + dx, cy + dy)) else {
+                    continue;
+                };
+                for &j in neighbours {
+                    if components[j].is_none() && positions[i].distance(positions[j]) <= distance {
+                        components[j] = components[i];
+                        open.push_back(j);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Number the connected stretches of road.
+fn road_components(road_graph: &RoadGraph) -> HashMap<NodeIndex, usize> {
+    let mut components = HashMap::new();
+    let mut next = 0;
+    for (start, _) in road_graph.nodes() {
+        if components.contains_key(&start) || !road_graph.node_has_edges(start) {
+            continue;
+        }
+        let mut open = vec![start];
+        components.insert(start, next);
+        while let Some(node) = open.pop() {
+            for neighbour in road_graph.neighbors(node) {
+                if components.insert(neighbour, next).is_none() {
+                    open.push(neighbour);
+                }
+            }
+        }
+        next += 1;
+    }
+    components
+}
+
+/// A stretch of line or pipe along one segment of road.
+#[derive(Clone, Copy, Debug)]
+struct Segment {
+    a: Vec2,
+    b: Vec2,
+    component: usize,
+}
+
+/// Road segments bucketed by grid cell, to find the road nearest a building.
+#[derive(Default)]
+struct SegmentGrid {
+    cells: HashMap<(i32, i32), Vec<Segment>>,
+}
+
+impl SegmentGrid {
+    const CELL: f32 = 40.0;
+
+    fn cell(p: Vec2) -> (i32, i32) {
+        ((p.x / Self::CELL).floor() as i32, (p.y / Self::CELL).floor() as i32)
+    }
+
+    fn build(road_graph: &RoadGraph, edge_components: &HashMap<EdgeIndex, usize>) -> Self {
+        let mut grid = Self::default();
+        for (&edge_index, &component) in edge_components {
+            let Some(edge) = road_graph.edge_by_index(edge_index) else {
+                continue;
+            };
+            for pair in edge.points.windows(2) {
+                let segment = Segment {
+                    a: pair[0],
+                    b: pair[1],
+                    component,
+                };
+                let (min, max) = (Self::cell(pair[0].min(pair[1])), Self::cell(pair[0].max(pair[1])));
+                for x in min.0..=max.0 {
+                    for y in min.1..=max.1 {
+                        grid.cells.entry((x, y)).or_default().push(segment);
+                    }
+                }
+            }
+        }
+        grid
+    }
+
+    /// The stretch of road nearest `position` within `max_distance`, and how
+    /// far away it is.
+    fn nearest(&self, position: Vec2, max_distance: f32) -> Option<(usize, f32)> {
+        let min = Self::cell(position - Vec2::splat(max_distance));
+        let max = Self::cell(position + Vec2::splat(max_distance));
+        let mut best: Option<(usize, f32)> = None;
+        for x in min.0..=max.0 {
+            for y in min.1..=max.1 {
+                for segment in self.cells.get(&(x, y)).into_iter().flatten() {
+                    let distance = distance_to_segment(position, segment.a, segment.b);
+                    if distance <= max_distance && best.is_none_or(|(_, d)| distance < d) {
+                        best = Some((segment.component, distance));
+                    }
+                }
+            }
+        }
+        best
+    }
+}
+
+fn distance_to_segment(p: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let t = if ab.length_squared() > 0.0 {
+        ((p - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    p.distance(a + ab * t)
+}
+
+fn edge_components(road_graph: &RoadGraph) -> HashMap<EdgeIndex, usize> {
+    let nodes = road_components(road_graph);
+    road_graph
+        .edge_indices()
+        .filter_map(|edge| {
+            let (a, _) = road_graph.edge_endpoints(edge)?;
+            Some((edge, *nodes.get(&a)?))
+        })
+        .collect()
+}
+
+fn setup_utility_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let material = |utility: Utility| StandardMaterial {
+        base_color: utility.color(),
+        emissive: utility.color().to_linear() * 2.0,
+        unlit: true,
+        ..default()
+    };
+    commands.insert_resource(UtilityAssets {
+        icon: meshes.add(Cuboid::new(2.5, 2.5, 2.5)),
+        materials: Utility::ALL.map(|utility| materials.add(material(utility))),
+    });
+}
+
+fn should_place_starting_utilities(
+    roads: Res<RoadsGenerated>,
+    buildings: Res<BuildingsSpawned>,
+    placed: Res<StartingUtilitiesPlaced>,
+) -> bool {
+    roads.0 && buildings.0 && !placed.0
+}
+
+/// Give the generated city enough plants of each kind, on the outskirts of
+/// every stretch of road with buildings along it.
+#[allow(clippy::too_many_arguments)]
+fn place_starting_utilities(
+    mut commands: Commands,
+    config: Res<UtilityConfig>,
+    services_config: Res<ServicesConfig>,
+    road_graph: Res<RoadGraph>,
+    buildings: Query<(&Building, &Transform)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut placed: ResMut<StartingUtilitiesPlaced>,
+) {
+    placed.0 = true;
+    let nodes = road_components(&road_graph);
+    let segments = SegmentGrid::build(&road_graph, &edge_components(&road_graph));
+
+    // Demand of the buildings along each stretch of road
+    let mut demand: BTreeMap<usize, [f32; 3]> = BTreeMap::new();
+    let mut positions = Vec::new();
+    for (building, transform) in &buildings {
+        let position = transform.translation.xz();
+        positions.push(position);
+        let Some((component, _)) = segments.nearest(position, config.reach) else {
+            continue;
+        };
+        let totals = demand.entry(component).or_default();
+        for utility in Utility::ALL {
+            totals[utility.index()] += utility.demand(building.building_type);
+        }
+    }
+    if positions.is_empty() {
+        return;
+    }
+    let centre = positions.iter().copied().sum::<Vec2>() / positions.len() as f32;
+    let spacing = services_config.building_size * 3.0;
+
+    let mut sites: Vec<Vec2> = Vec::new();
+    for (component, totals) in demand {
+        // Junctions farthest from any building first
+        let mut candidates: Vec<(Vec2, f32)> = nodes
+            .iter()
+            .filter(|&(_, &c)| c == component)
+            .filter_map(|(&node, _)| road_graph.node_by_index(node))
+            .map(|node| {
+                let clearance = positions
+                    .iter()
+                    .map(|p| p.distance(node.position))
+                    .fold(f32::INFINITY, f32::min);
+                (node.position, clearance)
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.x.total_cmp(&b.0.x)).then(a.0.y.total_cmp(&b.0.y)));
+
+        for utility in Utility::ALL {
+            let needed = (totals[utility.index()] * config.starting_headroom / config.capacity(utility)).ceil() as usize;
+            let mut built = 0;
+            for &(junction, _) in &candidates {
+                if built >= needed {
+                    break;
+                }
+                let site = junction + (junction - centre).normalize_or_zero() * services_config.building_size;
+                if sites.iter().any(|s| s.distance(site) < spacing) {
+                    continue;
+                }
+                spawn_service_building(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    &services_config,
+                    utility.producer(),
+                    site,
+                );
+                sites.push(site);
+                built += 1;
+            }
+        }
+    }
+    info!("Placed {} starting utility plants", sites.len());
+}
+
+/// Work out the networks again and which buildings they reach.
+#[allow(clippy::too_many_arguments)]
+fn update_utility_networks(
+    mut commands: Commands,
+    time: Res<Time>,
+    config: Res<UtilityConfig>,
+    road_graph: Res<RoadGraph>,
+    mut networks: ResMut<UtilityNetworks>,
+    plants: Query<(&ServiceBuilding, &Transform)>,
+    buildings: Query<(Entity, &Building, &Transform, Option<&UtilityService>)>,
+) {
+    networks.update_timer += time.delta_secs();
+    if networks.update_timer < config.update_interval {
+        return;
+    }
+    networks.update_timer = 0.0;
+
+    let edge_components = edge_components(&road_graph);
+    let segments = SegmentGrid::build(&road_graph, &edge_components);
+
+    let buildings: Vec<_> = buildings.iter().collect();
+    let positions: Vec<Vec2> = buildings.iter().map(|(_, _, transform, _)| transform.translation.xz()).collect();
+    let on_road: Vec<Option<usize>> = positions
+        .iter()
+        .map(|&position| segments.nearest(position, config.reach).map(|(component, _)| component))
+        .collect();
+
+    let mut service = vec![UtilityService::default(); buildings.len()];
+    let mut all_networks = Vec::new();
+    let mut unconnected = [0; 3];
+    for utility in Utility::ALL {
+        let producers: Vec<Producer> = plants
+            .iter()
+            .filter(|(plant, _)| Utility::fed_by(plant.service_type) == Some(utility))
+            .filter_map(|(plant, transform)| {
+                let position = transform.translation.xz();
+                let (component, _) = segments.nearest(position, plant.radius)?;
+                Some(Producer {
+                    position,
+                    component,
+                    capacity: config.capacity(utility),
+                })
+            })
+            .collect();
+
+        let mut components = on_road.clone();
+        if utility == Utility::Power {
+            spread_between_buildings(&positions, &mut components, config.adjacency);
+        }
+        let consumers: Vec<Consumer> = buildings
+            .iter()
+            .zip(&positions)
+            .zip(components)
+            .map(|(((_, building, _, _), &position), component)| Consumer {
+                position,
+                component,
+                demand: utility.demand(building.building_type),
+            })
+            .collect();
+
+        let (served, found) = allocate(utility, &producers, &consumers);
+        unconnected[utility.index()] = consumers
+            .iter()
+            .filter(|consumer| {
+                consumer
+                    .component
+                    .is_none_or(|c| !found.iter().any(|network| network.component == c))
+            })
+            .count() as u32;
+        for (i, served) in served.into_iter().enumerate() {
+            service[i].set(utility, served);
+        }
+        all_networks.extend(found);
+    }
+
+    for ((entity, _, _, current), service) in buildings.iter().zip(service) {
+        if current.copied() != Some(service) {
+            commands.entity(*entity).insert(service);
+        }
+    }
+    networks.networks = all_networks;
+    networks.unconnected = unconnected;
+    networks.edge_components = edge_components;
+    networks.segments = segments;
+}
+
+/// Float an icon over each building that goes without a utility, coloured
+/// for the first one it lacks.
+fn update_utility_icons(
+    mut commands: Commands,
+    time: Res<Time>,
+    config: Res<UtilityConfig>,
+    assets: Res<UtilityAssets>,
+    buildings: Query<(Entity, &UtilityService, &Transform, Option<&Aabb>), With<Building>>,
+    mut icons: Query<(Entity, &mut UtilityIcon, &mut Transform, &mut MeshMaterial3d<StandardMaterial>), Without<Building>>,
+) {
+    let mut wanted: HashMap<Entity, (Utility, Vec3)> = buildings
+        .iter()
+        .filter_map(|(entity, service, transform, aabb)| {
+            let utility = service.missing()?;
+            let top = aabb.map_or(0.0, |aabb| aabb.center.y + aabb.half_extents.y) * transform.scale.y;
+            Some((entity, (utility, transform.translation + Vec3::Y * (top + config.icon_lift))))
+        })
+        .collect();
+
+    let spin = Quat::from_rotation_y(time.elapsed_secs() * 1.5) * Quat::from_rotation_x(std::f32::consts::FRAC_PI_4);
+    for (entity, mut icon, mut transform, mut material) in &mut icons {
+        let Some((utility, position)) = wanted.remove(&icon.building) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        if icon.utility != utility {
+            icon.utility = utility;
+            material.0 = assets.materials[utility.index()].clone();
+        }
+        transform.translation = position;
+        transform.rotation = spin;
+    }
+
+    for (building, (utility, position)) in wanted {
+        commands.spawn((
+            Mesh3d(assets.icon.clone()),
+            MeshMaterial3d(assets.materials[utility.index()].clone()),
+            Transform::from_translation(position).with_rotation(spin),
+            UtilityIcon { building, utility },
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn consumer(x: f32, component: Option<usize>, demand: f32) -> Consumer {
+        Consumer {
+            position: Vec2::new(x, 0.0),
+            component,
+            demand,
+        }
+    }
+
+    #[test]
+    fn networks_serve_the_nearest_buildings_until_capacity_runs_out() {
+        let producers = [Producer {
+            position: Vec2::ZERO,
+            component: 0,
+            capacity: 5.0,
+        }];
+        let consumers = [
+            consumer(300.0, Some(0), 2.0),
+            consumer(100.0, Some(0), 2.0),
+            consumer(200.0, Some(0), 2.0),
+            // On a stretch of road with no plant
+            consumer(10.0, Some(1), 1.0),
+            // Out of reach of any road
+            consumer(20.0, None, 1.0),
+        ];
+
+        let (served, networks) = allocate(Utility::Water, &producers, &consumers);
+        assert_eq!(served, vec![false, true, true, false, false]);
+        assert_eq!(networks.len(), 1);
+        let network = &networks[0];
+        assert_eq!((network.producers, network.served, network.unserved), (1, 2, 1));
+        assert_eq!((network.capacity, network.demand, network.supply), (5.0, 6.0, 4.0));
+        assert!(network.load() > 1.0);
+    }
+
+    #[test]
+    fn power_passes_between_neighbouring_buildings() {
+        let positions = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(20.0, 0.0),
+            Vec2::new(40.0, 0.0),
+            Vec2::new(100.0, 0.0),
+        ];
+        let mut components = [Some(3), None, None, None];
+        spread_between_buildings(&positions, &mut components, 25.0);
+        assert_eq!(components, [Some(3), Some(3), Some(3), None]);
+    }
+
+    #[test]
+    fn buildings_report_the_first_utility_they_lack() {
+        let mut service = UtilityService::default();
+        assert_eq!(service.missing(), Some(Utility::Power));
+        service.set(Utility::Power, true);
+        service.set(Utility::Sewage, true);
+        assert_eq!(service.missing(), Some(Utility::Water));
+        service.set(Utility::Water, true);
+        assert!(service.is_served());
+        assert_eq!(Utility::fed_by(ServiceType::PumpingStation), Some(Utility::Sewage));
+        assert_eq!(Utility::fed_by(ServiceType::School), None);
+    }
+}
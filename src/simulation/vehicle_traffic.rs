@@ -2,20 +2,27 @@
 //!
 //! Spawns vehicles that drive along the road network, following waypoints
 //! and stopping at intersections. Supports multiple vehicle types including
-//! sedans, SUVs, trucks, vans, and buses.
+//! sedans, SUVs, trucks, vans, and buses. Vehicles sent on errands, such as
+//! delivery trucks and commuters from outside the city, carry a
+//! `PlannedRoute` and follow it instead of wandering.
+
+use std::collections::{HashMap, HashSet};
 
 use bevy::prelude::*;
-use noise::{NoiseFn, Perlin};
 use petgraph::graph::{EdgeIndex, NodeIndex};
 use rand::{Rng, SeedableRng};
 use rand::rngs::StdRng;
 
-use crate::procgen::roads::{RoadGraph, RoadNodeType, RoadType};
-use crate::render::instancing::TerrainConfig;
+use crate::procgen::intersections::{lane_offset, JunctionLayout, TurnKind};
+use crate::procgen::roads::{RoadGraph, RoadNodeType, RoadType, RoadsRemoved};
+use crate::world::terrain::HeightMap;
 use crate::render::road_mesh::RoadMeshGenerated;
 use crate::render::traffic_lights::{LightPhase, TrafficLightController};
 use crate::render::vehicle_meshes::{generate_vehicle_mesh, generate_wheel_mesh, VehicleMeshConfig, VehicleShape};
-use crate::simulation::vehicles::{MovingVehicle, VehicleNavigation};
+use crate::simulation::right_of_way::{crossings_conflict, junction_control, ApproachControl, Crossing, JunctionControl};
+use crate::simulation::signal_plans::Movement;
+use crate::simulation::traffic::TrafficCaState;
+use crate::simulation::vehicles::{JunctionTurn, MovingVehicle, PlannedRoute, VehicleNavigation};
 
 /// Different types of vehicles with varying sizes and speeds.
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
@@ -91,7 +98,8 @@ impl VehicleType {
         match self {
             VehicleType::Sedan => 40.0,
             VehicleType::SUV => 25.0,
-            VehicleType::Truck => 10.0,
+            // Trucks only run freight deliveries
+            VehicleType::Truck => 0.0,
             VehicleType::Van => 15.0,
             VehicleType::Bus => 10.0,
             // Emergency vehicles are rare
@@ -229,10 +237,13 @@ impl Plugin for MovingVehiclePlugin {
             .add_systems(
                 Update,
                 (
+                    vehicle_road_removal,
                     spawn_moving_vehicles.run_if(should_spawn_vehicles),
+                    measure_signal_demand,
                     vehicle_traffic_light_check,
                     vehicle_movement,
                     vehicle_edge_transition,
+                    reroute_planned_vehicles,
                     vehicle_lane_change,
                     vehicle_transform_sync,
                     update_emergency_sirens,
@@ -243,6 +254,52 @@ impl Plugin for MovingVehiclePlugin {
     }
 }
 
+/// Renumber vehicles after roads are removed; those on removed roads, or
+/// whose route's end was removed, are despawned. Routes that lost a road
+/// are replanned.
+fn vehicle_road_removal(
+    mut commands: Commands,
+    mut events: EventReader<RoadsRemoved>,
+    mut agents: Query<(Entity, &mut VehicleNavigation, Option<&mut PlannedRoute>)>,
+) {
+    for RoadsRemoved(removal) in events.read() {
+        let mut despawned = 0;
+        for (entity, mut nav, route) in &mut agents {
+            let (Some(edge), Some(destination)) = (
+                removal.remap.edge(nav.current_edge),
+                removal.remap.node(nav.destination_node),
+            ) else {
+                commands.entity(entity).despawn_recursive();
+                despawned += 1;
+                continue;
+            };
+            if let Some(mut route) = route {
+                let Some(target) = removal.remap.node(route.target_node) else {
+                    commands.entity(entity).despawn_recursive();
+                    despawned += 1;
+                    continue;
+                };
+                route.target_node = target;
+                match route.edges.iter().map(|&e| removal.remap.edge(e)).collect() {
+                    Some(edges) => route.edges = edges,
+                    None => {
+                        route.edges = vec![edge];
+                        route.leg = 0;
+                        route.lost = true;
+                    }
+                }
+            }
+            nav.current_edge = edge;
+            nav.destination_node = destination;
+            nav.previous_node = nav.previous_node.and_then(|node| removal.remap.node(node));
+            nav.next_edge = nav.next_edge.and_then(|edge| removal.remap.edge(edge));
+        }
+        if despawned > 0 {
+            info!("Removed {} vehicles from demolished roads", despawned);
+        }
+    }
+}
+
 /// Configuration for moving vehicles.
 #[derive(Resource)]
 pub struct MovingVehicleConfig {
@@ -270,7 +327,7 @@ pub struct VehiclesInitialized(pub bool);
 /// Run condition: spawn vehicles when roads exist and we haven't reached target count.
 fn should_spawn_vehicles(
     road_mesh_query: Query<&RoadMeshGenerated>,
-    vehicle_query: Query<&MovingVehicle>,
+    vehicle_query: Query<&MovingVehicle, Without<PlannedRoute>>,
     config: Res<MovingVehicleConfig>,
     initialized: Res<VehiclesInitialized>,
 ) -> bool {
@@ -294,10 +351,10 @@ fn spawn_moving_vehicles(
     mut commands: Commands,
     road_graph: Res<RoadGraph>,
     config: Res<MovingVehicleConfig>,
-    terrain_config: Res<TerrainConfig>,
+    terrain: Res<HeightMap>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    vehicle_query: Query<&MovingVehicle>,
+    vehicle_query: Query<&MovingVehicle, Without<PlannedRoute>>,
     mut initialized: ResMut<VehiclesInitialized>,
     mut local_rng: Local<Option<StdRng>>,
 ) {
@@ -338,7 +395,6 @@ fn spawn_moving_vehicles(
         (0.9, 0.9, 0.9),  // White shuttle
     ];
 
-    let terrain = TerrainSampler::new(&terrain_config);
 
     // Spawn vehicles up to target count
     let to_spawn = (config.target_count - current_count).min(5); // Spawn max 5 per frame
@@ -351,8 +407,11 @@ fn spawn_moving_vehicles(
         // Pick random intersection
         let start_node_idx = intersections[rng.gen_range(0..intersections.len())];
 
-        // Get edges from this node
-        let edges: Vec<EdgeIndex> = road_graph.edges_of_node(start_node_idx).collect();
+        // Get edges we may drive away from this node on
+        let edges: Vec<EdgeIndex> = road_graph
+            .edges_of_node(start_node_idx)
+            .filter(|&e| can_leave(&road_graph, e, start_node_idx))
+            .collect();
         if edges.is_empty() {
             continue;
         }
@@ -427,7 +486,7 @@ fn spawn_moving_vehicles(
             (p, -d)
         };
 
-        let terrain_height = terrain.sample(pos.x, pos.y);
+        let terrain_height = terrain.sample_world(pos);
         let road_surface = terrain_height + 0.12; // Road height offset
         let body_y = road_surface + height * 0.35;
         let angle = (-dir.x).atan2(-dir.y);
@@ -451,18 +510,11 @@ fn spawn_moving_vehicles(
                 stopping: false,
                 // Lane offset: drive on the right side of the road
                 // Offset depends on road type (wider roads = more offset)
-                lane_offset: match edge.road_type {
-                    RoadType::Highway => 3.0, // 3m from center
-                    RoadType::Major => 2.0,   // 2m from center
-                    RoadType::Minor => 1.5,   // 1.5m from center
-                    RoadType::Alley => 0.0,   // Center for narrow alleys
-                } * if forward { 1.0 } else { -1.0 }, // Right side based on direction
-                target_lane_offset: match edge.road_type {
-                    RoadType::Highway => 3.0,
-                    RoadType::Major => 2.0,
-                    RoadType::Minor => 1.5,
-                    RoadType::Alley => 0.0,
-                } * if forward { 1.0 } else { -1.0 },
+                lane_offset: lane_offset(edge.road_type, edge.direction.is_one_way()),
+                target_lane_offset: lane_offset(edge.road_type, edge.direction.is_one_way()),
+                turn: None,
+                next_edge: None,
+                halted: false,
             },
         )).id();
 
@@ -533,20 +585,167 @@ fn spawn_moving_vehicles(
     }
 }
 
-/// Check for traffic lights at upcoming intersections and stop if red.
+/// Distance from a junction within which vehicles follow its signs.
+const SIGN_APPROACH_DISTANCE: f32 = 20.0;
+/// Priority traffic due at the junction sooner than this, in seconds, is given way to.
+const GIVE_WAY_TIME: f32 = 3.0;
+
+/// A vehicle nearing a sign-controlled junction, as seen at the start of the frame.
+struct SignApproach {
+    entity: Entity,
+    node: NodeIndex,
+    from: EdgeIndex,
+    crossing: Option<Crossing>,
+    control: ApproachControl,
+    stopping: bool,
+    halted: bool,
+    /// Seconds until it reaches the junction at its current speed.
+    eta: f32,
+}
+
+impl SignApproach {
+    /// Set off again after giving way, so later arrivals wait for it.
+    fn released(&self) -> bool {
+        !self.stopping && (self.control == ApproachControl::Yield || self.halted)
+    }
+}
+
+/// Whether two ways through a junction conflict; a vehicle that hasn't chosen
+/// its exit yet is assumed to conflict with traffic from other roads.
+fn ways_conflict(a_from: EdgeIndex, a: Option<Crossing>, b_from: EdgeIndex, b: Option<Crossing>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => crossings_conflict(a, b),
+        _ => a_from != b_from,
+    }
+}
+
+/// Check for traffic lights at upcoming intersections and stop if red, give
+/// way to circulating traffic when entering a roundabout, and follow the
+/// signs elsewhere: yield to priority traffic, halt at stop signs, and never
+/// enter while a conflicting vehicle is crossing.
 fn vehicle_traffic_light_check(
-    mut vehicles: Query<&mut VehicleNavigation, With<MovingVehicle>>,
+    road_graph: Res<RoadGraph>,
+    layout: Res<JunctionLayout>,
+    mut vehicles: Query<(Entity, &mut VehicleNavigation), With<MovingVehicle>>,
     traffic_lights: Query<&TrafficLightController>,
 ) {
-    // Build a quick lookup of node -> light phase
-    let mut light_phases: std::collections::HashMap<NodeIndex, LightPhase> =
-        std::collections::HashMap::new();
+    // Build a quick lookup of node -> controller
+    let mut controllers: HashMap<NodeIndex, &TrafficLightController> = HashMap::new();
 
     for controller in traffic_lights.iter() {
-        light_phases.insert(controller.node_index, controller.phase);
+        controllers.insert(controller.node_index, controller);
     }
 
-    for mut nav in vehicles.iter_mut() {
+    // Roundabout nodes with circulating traffic about to pass or passing through
+    let is_roundabout = |node: NodeIndex| {
+        road_graph
+            .node_by_index(node)
+            .is_some_and(|n| n.node_type == RoadNodeType::Roundabout)
+    };
+    let on_ring = |edge: EdgeIndex| {
+        road_graph
+            .edge_endpoints(edge)
+            .is_some_and(|(a, b)| is_roundabout(a) && is_roundabout(b))
+    };
+    let mut occupied = HashSet::new();
+    for (_, nav) in vehicles.iter() {
+        match (nav.turn.is_some(), nav.previous_node) {
+            (true, Some(node)) if is_roundabout(node) => {
+                occupied.insert(node);
+            }
+            _ => {
+                let halfway = if nav.forward { nav.progress > 0.5 } else { nav.progress < 0.5 };
+                if halfway && on_ring(nav.current_edge) {
+                    occupied.insert(nav.destination_node);
+                }
+            }
+        }
+    }
+
+    // Who is crossing, and who is nearing, each sign-controlled junction
+    let distance_to_junction = |nav: &VehicleNavigation| {
+        let Some(edge) = road_graph.edge_by_index(nav.current_edge) else {
+            return f32::INFINITY;
+        };
+        let along = if nav.forward { 1.0 - nav.progress } else { nav.progress };
+        (along * edge.length - layout.setback(nav.destination_node, nav.current_edge)).max(0.0)
+    };
+    let mut controls: HashMap<NodeIndex, JunctionControl> = HashMap::new();
+    let mut crossing_now: HashMap<NodeIndex, Vec<(Entity, Crossing)>> = HashMap::new();
+    let mut nearing: Vec<SignApproach> = Vec::new();
+    for (entity, nav) in vehicles.iter() {
+        if let (Some(turn), Some(node)) = (&nav.turn, nav.previous_node) {
+            crossing_now.entry(node).or_default().push((entity, (turn.from_edge, nav.current_edge, turn.kind)));
+            continue;
+        }
+        let node = nav.destination_node;
+        if controllers.contains_key(&node) || is_roundabout(node) {
+            continue;
+        }
+        let distance = distance_to_junction(nav);
+        if distance > SIGN_APPROACH_DISTANCE {
+            continue;
+        }
+        let control = controls
+            .entry(node)
+            .or_insert_with(|| layout.get(node).map_or(JunctionControl::Free, |g| junction_control(g, &road_graph)));
+        if !matches!(control, JunctionControl::Signs(_)) {
+            continue;
+        }
+        nearing.push(SignApproach {
+            entity,
+            node,
+            from: nav.current_edge,
+            crossing: nav.next_edge.and_then(|next| {
+                let kind = layout.turn_path(node, nav.current_edge, next)?.kind;
+                Some((nav.current_edge, next, kind))
+            }),
+            control: control.approach(nav.current_edge),
+            stopping: nav.stopping,
+            halted: nav.halted,
+            eta: distance / nav.speed.max(0.1),
+        });
+    }
+
+    for (entity, mut nav) in vehicles.iter_mut() {
+        // Already committed to a turn through the junction
+        if nav.turn.is_some() {
+            nav.stopping = false;
+            continue;
+        }
+
+        // Following signs at an unsignalled junction
+        if let Some(me) = nearing.iter().find(|a| a.entity == entity) {
+            let blocked_inside = crossing_now.get(&me.node).is_some_and(|inside| {
+                inside
+                    .iter()
+                    .any(|&(other, way)| other != entity && ways_conflict(me.from, me.crossing, way.0, Some(way)))
+            });
+            let must_give_way = || {
+                nearing.iter().any(|other| {
+                    other.entity != entity
+                        && other.node == me.node
+                        && ways_conflict(me.from, me.crossing, other.from, other.crossing)
+                        && match other.control {
+                            ApproachControl::Priority => !other.stopping && other.eta < GIVE_WAY_TIME,
+                            // First to set off goes first; simultaneous starts go by entity order
+                            _ => other.released() && (me.stopping || other.entity < entity),
+                        }
+                })
+            };
+            nav.stopping = match me.control {
+                ApproachControl::Priority => blocked_inside,
+                ApproachControl::Stop if !nav.halted => {
+                    if nav.speed <= 0.1 && nav.stopping {
+                        nav.halted = true;
+                    }
+                    true
+                }
+                ApproachControl::Yield | ApproachControl::Stop => blocked_inside || must_give_way(),
+            };
+            continue;
+        }
+
         // Check if approaching the end of the edge (progress > 0.7)
         let approaching_end = if nav.forward {
             nav.progress > 0.7
@@ -561,11 +760,18 @@ fn vehicle_traffic_light_check(
         }
 
         // Check if there's a traffic light at our destination node
-        if let Some(&phase) = light_phases.get(&nav.destination_node) {
-            // Stop for red or yellow lights
+        if let Some(controller) = controllers.get(&nav.destination_node) {
+            // Stop for red or yellow for the turn we're about to make
+            let phase = match upcoming_turn(&layout, &nav) {
+                Some(kind) => controller.movement_phase(nav.current_edge, kind),
+                None => controller.phase_for(nav.current_edge),
+            };
             nav.stopping = matches!(phase, LightPhase::Red | LightPhase::Yellow);
+        } else if is_roundabout(nav.destination_node) && !on_ring(nav.current_edge) {
+            // Entering a roundabout: give way to traffic already on the ring
+            nav.stopping = occupied.contains(&nav.destination_node);
         } else {
-            // No traffic light at this intersection
+            // Free-flowing junction
             nav.stopping = false;
         }
     }
@@ -592,6 +798,16 @@ fn vehicle_movement(
             continue;
         }
 
+        // Inside a junction, follow the turn path instead of the edge
+        let distance = nav.speed * dt;
+        if let Some(turn) = nav.turn.as_mut() {
+            turn.distance += distance;
+            if turn.distance >= turn.length {
+                nav.turn = None;
+            }
+            continue;
+        }
+
         // Get edge length
         let Some(edge) = road_graph.edge_by_index(nav.current_edge) else {
             continue;
@@ -603,7 +819,6 @@ fn vehicle_movement(
         }
 
         // Calculate progress delta
-        let distance = nav.speed * dt;
         let progress_delta = distance / edge_length;
 
         // Update progress based on direction
@@ -619,14 +834,27 @@ fn vehicle_movement(
 fn vehicle_edge_transition(
     mut commands: Commands,
     road_graph: Res<RoadGraph>,
-    mut vehicles: Query<(Entity, &mut VehicleNavigation), With<MovingVehicle>>,
+    layout: Res<JunctionLayout>,
+    mut vehicles: Query<(Entity, &mut VehicleNavigation, Option<&mut PlannedRoute>), With<MovingVehicle>>,
     mut local_rng: Local<Option<StdRng>>,
 ) {
     let rng = local_rng.get_or_insert_with(|| StdRng::seed_from_u64(77777));
 
-    for (entity, mut nav) in vehicles.iter_mut() {
-        // Check if we've reached the end of the edge
-        let at_end = (nav.forward && nav.progress >= 1.0) || (!nav.forward && nav.progress <= 0.0);
+    for (entity, mut nav, mut route) in vehicles.iter_mut() {
+        if nav.turn.is_some() {
+            continue;
+        }
+
+        // Check if we've reached the junction boundary at the end of the edge
+        let Some(edge_length) = road_graph.edge_by_index(nav.current_edge).map(|e| e.length) else {
+            continue;
+        };
+        let margin = if edge_length > 0.0 {
+            layout.setback(nav.destination_node, nav.current_edge) / edge_length
+        } else {
+            0.0
+        };
+        let at_end = (nav.forward && nav.progress >= 1.0 - margin) || (!nav.forward && nav.progress <= margin);
 
         if !at_end {
             continue;
@@ -635,13 +863,23 @@ fn vehicle_edge_transition(
         // We've reached the destination node
         let current_node = nav.destination_node;
 
+        // Vehicles on an errand stop at the end of their route
+        if let Some(route) = route.as_mut() {
+            if route.on_last_leg() && current_node == route.target_node {
+                route.arrived = true;
+                nav.speed = 0.0;
+                continue;
+            }
+        }
+
         // Get all edges from this node
         let edges: Vec<EdgeIndex> = road_graph.edges_of_node(current_node).collect();
 
         // Filter out the edge we came from (to avoid immediate U-turn)
+        // and one-way roads pointing at us
         let valid_edges: Vec<EdgeIndex> = edges
             .into_iter()
-            .filter(|&e| e != nav.current_edge)
+            .filter(|&e| e != nav.current_edge && can_leave(&road_graph, e, current_node))
             .collect();
 
         if valid_edges.is_empty() {
@@ -650,8 +888,14 @@ fn vehicle_edge_transition(
             continue;
         }
 
-        // Pick random next edge
-        let next_edge = valid_edges[rng.gen_range(0..valid_edges.len())];
+        // Take the road picked on the way in, or a random one if it's gone
+        let next_edge = match nav.next_edge {
+            Some(planned) if valid_edges.contains(&planned) => planned,
+            _ => valid_edges[rng.gen_range(0..valid_edges.len())],
+        };
+        if let Some(route) = route.as_mut() {
+            route.advance(next_edge);
+        }
         let Some((node_a, node_b)) = road_graph.edge_endpoints(next_edge) else {
             commands.entity(entity).despawn();
             continue;
@@ -675,28 +919,186 @@ fn vehicle_edge_transition(
             nav.target_speed = 12.0 * road_speed_mult; // Base speed * road mult
         }
 
-        // Update lane offset for new road
-        let new_lane_offset = if let Some(edge) = road_graph.edge_by_index(next_edge) {
-            let base_offset = match edge.road_type {
-                RoadType::Highway => 3.0,
-                RoadType::Major => 2.0,
-                RoadType::Minor => 1.5,
-                RoadType::Alley => 0.0,
-            };
-            base_offset * if forward { 1.0 } else { -1.0 }
-        } else {
-            0.0
+        // Lane offset for the new road, and where its surface starts
+        let (new_lane_offset, entry) = match road_graph.edge_by_index(next_edge) {
+            Some(edge) if edge.length > 0.0 => (
+                lane_offset(edge.road_type, edge.direction.is_one_way()),
+                (layout.setback(current_node, next_edge) / edge.length).min(0.5),
+            ),
+            Some(edge) => (lane_offset(edge.road_type, edge.direction.is_one_way()), 0.0),
+            None => (0.0, 0.0),
         };
 
+        // Follow the junction's turn path into the new lane
+        nav.turn = layout
+            .turn_path(current_node, nav.current_edge, next_edge)
+            .map(|path| JunctionTurn {
+                from_edge: path.from_edge,
+                kind: path.kind,
+                points: path.points.clone(),
+                length: path.length,
+                distance: 0.0,
+            });
+        if nav.turn.is_some() {
+            nav.lane_offset = new_lane_offset;
+        }
+
         // Update navigation state
         nav.previous_node = Some(current_node);
         nav.current_edge = next_edge;
         nav.forward = forward;
-        nav.progress = if forward { 0.0 } else { 1.0 };
+        nav.progress = if forward { entry } else { 1.0 - entry };
         nav.destination_node = dest_node;
         nav.stopping = false;
+        nav.halted = false;
         nav.target_lane_offset = new_lane_offset;
-        // Smooth transition to new lane over time
+        // Without a turn path, smooth transition to new lane over time
+
+        // Decide now where to go next, so signals ahead can see the turn;
+        // vehicles on an errand follow their route
+        if let Some(route) = route.as_ref() {
+            nav.next_edge = route.planned_next();
+            continue;
+        }
+        let onward: Vec<EdgeIndex> = road_graph
+            .edges_of_node(dest_node)
+            .filter(|&e| e != next_edge && can_leave(&road_graph, e, dest_node))
+            .collect();
+        nav.next_edge = (!onward.is_empty()).then(|| onward[rng.gen_range(0..onward.len())]);
+    }
+}
+
+/// Find a new way for vehicles that turned off their route; those left
+/// with no way to their destination are taken off the road.
+fn reroute_planned_vehicles(
+    mut commands: Commands,
+    road_graph: Res<RoadGraph>,
+    mut vehicles: Query<(Entity, &mut PlannedRoute, &mut VehicleNavigation)>,
+) {
+    for (entity, mut route, mut nav) in &mut vehicles {
+        if !route.lost || nav.turn.is_some() {
+            continue;
+        }
+        let onward = if nav.destination_node == route.target_node {
+            Some(Vec::new())
+        } else {
+            road_graph.cheapest_path(nav.destination_node, route.target_node, route.cost)
+        };
+        let Some(onward) = onward else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+        route.edges = std::iter::once(nav.current_edge).chain(onward).collect();
+        route.leg = 0;
+        route.lost = false;
+        nav.next_edge = route.planned_next();
+    }
+}
+
+/// Navigation for a vehicle setting off from `start` along `edge` at
+/// `speed`, about to take `next_edge` at the far end.
+pub fn navigation_along(
+    road_graph: &RoadGraph,
+    edge: EdgeIndex,
+    start: NodeIndex,
+    next_edge: Option<EdgeIndex>,
+    speed: f32,
+) -> Option<VehicleNavigation> {
+    let (a, b) = road_graph.edge_endpoints(edge)?;
+    let data = road_graph.edge_by_index(edge)?;
+    let forward = a == start;
+    let offset = lane_offset(data.road_type, data.direction.is_one_way());
+    Some(VehicleNavigation {
+        current_edge: edge,
+        forward,
+        progress: if forward { 0.0 } else { 1.0 },
+        speed,
+        target_speed: speed,
+        destination_node: if forward { b } else { a },
+        previous_node: Some(start),
+        stopping: false,
+        lane_offset: offset,
+        target_lane_offset: offset,
+        turn: None,
+        next_edge,
+        halted: false,
+    })
+}
+
+/// Turn a vehicle will make at the end of its current edge, if it has chosen one.
+fn upcoming_turn(layout: &JunctionLayout, nav: &VehicleNavigation) -> Option<TurnKind> {
+    let next = nav.next_edge?;
+    layout
+        .turn_path(nav.destination_node, nav.current_edge, next)
+        .map(|path| path.kind)
+}
+
+/// Fraction of an incoming lane, nearest the junction, counted as its queue.
+const QUEUE_REACH: f32 = 1.0 / 3.0;
+
+/// Measure the traffic waiting at each signal, per movement, for actuated
+/// control: vehicles closing on the junction by the turn they will make, and
+/// stopped or crawling CA traffic at the head of each incoming lane.
+fn measure_signal_demand(
+    road_graph: Res<RoadGraph>,
+    layout: Res<JunctionLayout>,
+    ca: Res<TrafficCaState>,
+    vehicles: Query<&VehicleNavigation, With<MovingVehicle>>,
+    mut controllers: Query<&mut TrafficLightController>,
+) {
+    let mut waiting: HashMap<NodeIndex, Vec<(Movement, f32)>> = HashMap::new();
+    fn add(waiting: &mut HashMap<NodeIndex, Vec<(Movement, f32)>>, node: NodeIndex, movement: Movement, amount: f32) {
+        let entries = waiting.entry(node).or_default();
+        match entries.iter_mut().find(|(m, _)| *m == movement) {
+            Some((_, total)) => *total += amount,
+            None => entries.push((movement, amount)),
+        }
+    }
+
+    for nav in vehicles.iter() {
+        let approaching = if nav.forward { nav.progress > 0.5 } else { nav.progress < 0.5 };
+        if nav.turn.is_some() || !approaching {
+            continue;
+        }
+        let kind = upcoming_turn(&layout, nav).unwrap_or(TurnKind::Straight);
+        add(&mut waiting, nav.destination_node, (nav.current_edge, kind), 1.0);
+    }
+
+    if ca.initialized {
+        for mut controller in controllers.iter_mut() {
+            let node = controller.node_index;
+            for &(edge, _) in &controller.approaches {
+                let (Some(Some(segment)), Some((_, b))) = (ca.edge_to_segment.get(edge.index()), road_graph.edge_endpoints(edge)) else {
+                    continue;
+                };
+                let Some(segment) = ca.segments.get(*segment) else { continue };
+                // Lanes run toward the far end of their own direction
+                let lanes = if b == node { &segment.forward_lanes } else { &segment.backward_lanes };
+                let queued: usize = lanes
+                    .iter()
+                    .map(|lane| {
+                        let start = ((lane.length as f32) * (1.0 - QUEUE_REACH)) as usize;
+                        lane.cells[start.min(lane.cells.len())..].iter().filter(|c| matches!(c, Some(v) if *v <= 1)).count()
+                    })
+                    .sum();
+                if queued > 0 {
+                    add(&mut waiting, node, (edge, TurnKind::Straight), queued as f32 * 0.5);
+                }
+            }
+            controller.demand = waiting.remove(&node).unwrap_or_default();
+        }
+    } else {
+        for mut controller in controllers.iter_mut() {
+            controller.demand = waiting.remove(&controller.node_index).unwrap_or_default();
+        }
+    }
+}
+
+/// Whether traffic may drive along `edge` away from `node`.
+fn can_leave(road_graph: &RoadGraph, edge: EdgeIndex, node: NodeIndex) -> bool {
+    match (road_graph.edge_endpoints(edge), road_graph.edge_by_index(edge)) {
+        (Some((a, _)), Some(data)) => data.direction.allows(a == node),
+        _ => false,
     }
 }
 
@@ -724,38 +1126,46 @@ fn vehicle_lane_change(
 /// Update vehicle transforms based on their navigation state.
 fn vehicle_transform_sync(
     road_graph: Res<RoadGraph>,
-    terrain_config: Res<TerrainConfig>,
+    terrain: Res<HeightMap>,
     mut vehicles: Query<(&VehicleNavigation, &VehicleType, &mut Transform), With<MovingVehicle>>,
 ) {
-    let terrain = TerrainSampler::new(&terrain_config);
 
     for (nav, vehicle_type, mut transform) in vehicles.iter_mut() {
-        let Some(edge) = road_graph.edge_by_index(nav.current_edge) else {
-            continue;
-        };
+        // Overpass decks carry traffic above the ground
+        let mut lift = 0.0;
+        let (pos, dir) = if let Some(turn) = &nav.turn {
+            // Turn paths already run along the lanes
+            let progress = if turn.length > 0.0 { turn.distance / turn.length } else { 1.0 };
+            interpolate_edge_position(&turn.points, progress)
+        } else {
+            let Some(edge) = road_graph.edge_by_index(nav.current_edge) else {
+                continue;
+            };
 
-        // Clamp progress to valid range
-        let progress = nav.progress.clamp(0.0, 1.0);
+            // Clamp progress to valid range
+            let progress = nav.progress.clamp(0.0, 1.0);
 
-        // Get position and direction along edge
-        let (center_pos, mut dir) = interpolate_edge_position(&edge.points, progress);
+            // Get position and direction along edge
+            let (center_pos, mut dir) = interpolate_edge_position(&edge.points, progress);
+            lift = edge.deck_lift(progress);
 
-        // Flip direction if traveling backward
-        if !nav.forward {
-            dir = -dir;
-        }
+            // Flip direction if traveling backward
+            if !nav.forward {
+                dir = -dir;
+            }
 
-        // Apply lane offset (perpendicular to road direction)
-        // Positive offset = right side of road (in direction of travel)
-        let perp = Vec2::new(-dir.y, dir.x); // Perpendicular vector
-        let pos = center_pos + perp * nav.lane_offset;
+            // Apply lane offset (perpendicular to direction of travel)
+            // Positive offset = right side of road (in direction of travel)
+            let perp = Vec2::new(-dir.y, dir.x); // Perpendicular vector
+            (center_pos + perp * nav.lane_offset, dir)
+        };
 
         // Get vehicle height from type
         let (_, _, height) = vehicle_type.dimensions();
 
         // Update position with terrain and road height
-        let terrain_height = terrain.sample(pos.x, pos.y);
-        let road_surface = terrain_height + 0.12; // Road height offset
+        let terrain_height = terrain.sample_world(pos);
+        let road_surface = terrain_height + lift + 0.12; // Road height offset
         let body_y = road_surface + height * 0.35;
 
         transform.translation.x = pos.x;
@@ -879,40 +1289,3 @@ fn interpolate_edge_position(points: &[Vec2], progress: f32) -> (Vec2, Vec2) {
     };
     (last, dir)
 }
-
-/// Helper struct for sampling terrain height.
-struct TerrainSampler {
-    perlin: Perlin,
-    noise_scale: f32,
-    height_scale: f32,
-    octaves: u32,
-}
-
-impl TerrainSampler {
-    fn new(config: &TerrainConfig) -> Self {
-        Self {
-            perlin: Perlin::new(config.seed),
-            noise_scale: config.noise_scale,
-            height_scale: config.height_scale,
-            octaves: config.octaves,
-        }
-    }
-
-    fn sample(&self, x: f32, z: f32) -> f32 {
-        let mut height = 0.0;
-        let mut amplitude = 1.0;
-        let mut frequency = self.noise_scale;
-        let mut max_amplitude = 0.0;
-
-        for _ in 0..self.octaves {
-            let sample_x = x as f64 * frequency as f64;
-            let sample_z = z as f64 * frequency as f64;
-            height += self.perlin.get([sample_x, sample_z]) as f32 * amplitude;
-            max_amplitude += amplitude;
-            amplitude *= 0.5;
-            frequency *= 2.0;
-        }
-
-        (height / max_amplitude) * self.height_scale
-    }
-}
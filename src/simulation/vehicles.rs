@@ -5,6 +5,9 @@
 use bevy::prelude::*;
 use petgraph::graph::{EdgeIndex, NodeIndex};
 
+use crate::procgen::intersections::TurnKind;
+use crate::procgen::roads::RoadEdge;
+
 /// Vehicle component.
 #[derive(Component)]
 pub struct Vehicle {
@@ -80,4 +83,92 @@ pub struct VehicleNavigation {
     pub lane_offset: f32,
     /// Target lane offset (for smooth lane changes).
     pub target_lane_offset: f32,
+    /// Path being followed through a junction, between edges.
+    pub turn: Option<JunctionTurn>,
+    /// Road chosen to leave the upcoming junction by, so signals know the turn.
+    pub next_edge: Option<EdgeIndex>,
+    /// Has come to a full stop at the stop sign ahead.
+    pub halted: bool,
+}
+
+/// Progress along a turn path from one edge's lane to the next.
+#[derive(Clone, Debug)]
+pub struct JunctionTurn {
+    /// Road the turn was entered from.
+    pub from_edge: EdgeIndex,
+    pub kind: TurnKind,
+    pub points: Vec<Vec2>,
+    pub length: f32,
+    /// Distance travelled along the path.
+    pub distance: f32,
+}
+
+/// Roads a vehicle on an errand drives in order, instead of wandering.
+#[derive(Component, Clone, Debug)]
+pub struct PlannedRoute {
+    /// Roads to drive, starting with the one the vehicle is on.
+    pub edges: Vec<EdgeIndex>,
+    /// Index of the road being driven.
+    pub leg: usize,
+    /// Junction the route ends at.
+    pub target_node: NodeIndex,
+    /// What a road costs this vehicle, driven along (`true`) or against its
+    /// points, for finding a new way when it goes astray.
+    pub cost: fn(&RoadEdge, bool) -> f32,
+    /// Turned off the route and needs a new one.
+    pub lost: bool,
+    /// Reached the end of the route.
+    pub arrived: bool,
+}
+
+impl PlannedRoute {
+    pub fn new(edges: Vec<EdgeIndex>, target_node: NodeIndex, cost: fn(&RoadEdge, bool) -> f32) -> Self {
+        Self {
+            edges,
+            leg: 0,
+            target_node,
+            cost,
+            lost: false,
+            arrived: false,
+        }
+    }
+
+    /// Road the route takes after the current one.
+    pub fn planned_next(&self) -> Option<EdgeIndex> {
+        self.edges.get(self.leg + 1).copied()
+    }
+
+    /// Whether the vehicle is on the last road of its route.
+    pub fn on_last_leg(&self) -> bool {
+        !self.lost && self.leg + 1 >= self.edges.len()
+    }
+
+    /// Record the road taken at a junction, noting when it leaves the route.
+    pub fn advance(&mut self, taken: EdgeIndex) {
+        if self.planned_next() == Some(taken) {
+            self.leg += 1;
+        } else {
+            self.edges = vec![taken];
+            self.leg = 0;
+            self.lost = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vehicles_that_leave_their_route_are_marked_lost() {
+        let [e0, e1, e2, other] = [0, 1, 2, 3].map(EdgeIndex::new);
+        let mut route = PlannedRoute::new(vec![e0, e1, e2], NodeIndex::new(0), |edge, _| edge.length);
+        route.advance(e1);
+        assert_eq!((route.leg, route.planned_next(), route.on_last_leg()), (1, Some(e2), false));
+        route.advance(e2);
+        assert!(route.on_last_leg());
+        route.advance(other);
+        assert!(route.lost && !route.on_last_leg());
+        assert_eq!(route.edges, vec![other]);
+    }
 }
@@ -4,6 +4,7 @@
 //! - RCI demand for the zone type
 //! - Land value (environmental factors, services, pollution, crime)
 //! - Service coverage
+//! - Utilities (nothing grows without power, water and sewage to spare)
 //!
 //! When conditions are met, a construction site is spawned first. The building
 //! appears when construction completes.
@@ -18,6 +19,7 @@ use crate::tools::zone_paint::{ZoneCell, ZonePaintConfig};
 
 use super::demand::RCIDemand;
 use super::land_value::ZoneFactors;
+use super::utilities::{UtilityConfig, UtilityNetworks};
 
 pub struct ZoneGrowthPlugin;
 
@@ -66,6 +68,7 @@ pub struct GrownBuilding {
     pub growth_time: f32,
 }
 
+#[allow(clippy::too_many_arguments)]
 fn process_zone_growth(
     mut commands: Commands,
     time: Res<Time>,
@@ -73,6 +76,8 @@ fn process_zone_growth(
     zone_config: Res<ZonePaintConfig>,
     construction_config: Res<ConstructionConfig>,
     demand: Res<RCIDemand>,
+    utility_config: Res<UtilityConfig>,
+    utilities: Res<UtilityNetworks>,
     mut timer: Local<f32>,
     mut rng_seed: Local<u64>,
     mut zone_cells: Query<(Entity, &mut ZoneCell, &Transform, Option<&ZoneFactors>)>,
@@ -115,6 +120,12 @@ fn process_zone_growth(
             continue;
         }
 
+        // Nothing grows where the utility networks don't reach or are full
+        let position = Vec2::new(transform.translation.x, transform.translation.z);
+        if !utilities.can_serve(position, utility_config.reach) {
+            continue;
+        }
+
         // Building height influenced by land value
         // Higher land value = taller buildings (more valuable to develop)
         let height_multiplier = 0.7 + land_value * 0.6; // Range: 0.7 to 1.3
@@ -0,0 +1,273 @@
+//! Connection tool - link the city to the region beyond the map edge.
+//!
+//! Tab switches between highway and rail connections. A click near the map
+//! edge places one on the edge itself, where a road or track drawn to it
+//! will join it; a right click removes the nearest one. , / . try the city
+//! under a different regional scenario, and the panel reports who is moving
+//! and commuting across the edge.
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use super::history::{stash_entity, CommandHistory, HistoryEntry, PlayerAction};
+use super::ActiveTool;
+use crate::game_state::GameState;
+use crate::simulation::economy::CityBudget;
+use crate::simulation::region::{
+    distance_to_edge, edge_point, spawn_connection, ConnectionAssets, ConnectionKind, Region, RegionConfig,
+    RegionalConnection,
+};
+use crate::world::terrain::HeightMap;
+
+pub struct ConnectionToolPlugin;
+
+impl Plugin for ConnectionToolPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ConnectionToolState>()
+            .add_systems(
+                Update,
+                (edit_connection_keys, handle_connection_input, update_connection_panel, draw_connection_preview)
+                    .chain()
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_state(ActiveTool::Connection)),
+            )
+            .add_systems(Update, cleanup_on_tool_change.run_if(in_state(GameState::Playing)));
+    }
+}
+
+/// A right click removes a connection this close to the cursor.
+const PICK_DISTANCE: f32 = 30.0;
+
+const PANEL_BG: Color = Color::srgba(0.02, 0.02, 0.04, 0.94);
+const BORDER: Color = Color::srgb(0.2, 0.7, 0.4);
+const TEXT_COLOR: Color = Color::srgb(0.8, 0.85, 1.0);
+
+/// What the connection tool is working on.
+#[derive(Resource)]
+pub struct ConnectionToolState {
+    pub kind: ConnectionKind,
+    /// Where on the map edge the next connection would go, if the cursor is
+    /// close enough to it.
+    pub hover: Option<Vec2>,
+}
+
+impl Default for ConnectionToolState {
+    fn default() -> Self {
+        Self {
+            kind: ConnectionKind::Highway,
+            hover: None,
+        }
+    }
+}
+
+/// Marker for the connection panel.
+#[derive(Component)]
+struct ConnectionPanel;
+
+fn edit_connection_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut config: ResMut<RegionConfig>,
+    mut state: ResMut<ConnectionToolState>,
+) {
+    if keys.just_pressed(KeyCode::Tab) {
+        state.kind = match state.kind {
+            ConnectionKind::Highway => ConnectionKind::Rail,
+            ConnectionKind::Rail => ConnectionKind::Highway,
+        };
+    }
+    let step = if keys.just_pressed(KeyCode::Period) {
+        1
+    } else if keys.just_pressed(KeyCode::Comma) {
+        3
+    } else {
+        return;
+    };
+    let mut scenario = config.scenario;
+    for _ in 0..step {
+        scenario = scenario.next();
+    }
+    *config = RegionConfig::scenario(scenario);
+    info!("Region scenario: {}", scenario.name());
+}
+
+/// Place a connection on the map edge with a left click, remove the nearest
+/// one with a right click.
+#[allow(clippy::too_many_arguments)]
+fn handle_connection_input(
+    mut commands: Commands,
+    mouse: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
+    terrain: Res<HeightMap>,
+    config: Res<RegionConfig>,
+    assets: Res<ConnectionAssets>,
+    connections: Query<(Entity, &RegionalConnection)>,
+    mut budget: ResMut<CityBudget>,
+    mut history: ResMut<CommandHistory>,
+    mut state: ResMut<ConnectionToolState>,
+) {
+    state.hover = None;
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_q.get_single() else {
+        return;
+    };
+    let Some(cursor) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+    else {
+        return;
+    };
+    let bounds = terrain.bounds();
+    if distance_to_edge(bounds, cursor) <= config.edge_margin {
+        state.hover = Some(edge_point(bounds, cursor));
+    }
+
+    if mouse.just_pressed(MouseButton::Right) {
+        let nearest = connections
+            .iter()
+            .map(|(entity, connection)| (entity, connection, connection.position.distance(cursor)))
+            .filter(|&(_, _, distance)| distance <= PICK_DISTANCE)
+            .min_by(|a, b| a.2.total_cmp(&b.2));
+        if let Some((entity, connection, _)) = nearest {
+            let label = format!("remove {} connection", connection.kind.name().to_lowercase());
+            commands.queue(move |world: &mut World| stash_entity(world, entity));
+            history.push(HistoryEntry::new(label, 0, vec![PlayerAction::Removed(vec![entity])]));
+        }
+        return;
+    }
+
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let Some(position) = state.hover else {
+        return;
+    };
+    let cost = state.kind.cost();
+    if budget.funds < cost {
+        info!("Cannot afford a connection (${} needed, ${} available)", cost, budget.funds);
+        return;
+    }
+    let entity = spawn_connection(&mut commands, &assets, &terrain, RegionalConnection::new(state.kind, position));
+    budget.funds -= cost;
+    history.push(HistoryEntry::new(
+        format!("build {} connection", state.kind.name().to_lowercase()),
+        cost,
+        vec![PlayerAction::Spawned(vec![entity])],
+    ));
+    info!("Built {} connection at ({:.1}, {:.1}) for ${}", state.kind.name(), position.x, position.y, cost);
+}
+
+fn connection_report(
+    state: &ConnectionToolState,
+    config: &RegionConfig,
+    region: &Region,
+    connections: &[&RegionalConnection],
+) -> String {
+    let mut lines = vec![format!("{} CONNECTION  ${}", state.kind.name().to_uppercase(), state.kind.cost())];
+    for kind in [ConnectionKind::Highway, ConnectionKind::Rail] {
+        let built = connections.iter().filter(|c| c.kind == kind);
+        let joined = built.clone().filter(|c| c.is_joined()).count();
+        lines.push(format!(" {}: {} built, {} joined", kind.name(), built.count(), joined));
+    }
+    lines.push(String::new());
+    lines.push(format!("REGION  {}  pop {:.0}", config.scenario.name(), region.population));
+    lines.push(format!(" capacity {:.2}  by rail {:.0}%", region.capacity, region.rail_share() * 100.0));
+    lines.push(format!(" moving in {}  out {}", region.immigrants, region.emigrants));
+    lines.push(format!(" commuting in {}  out {}", region.commuters_in, region.commuters_out));
+    lines.push(format!(" unemployment {:.0}%  wages x{:.2}", config.unemployment * 100.0, config.wages));
+
+    lines.push(String::new());
+    lines.push("Click: build  Right click: remove".to_string());
+    lines.push("Tab: highway / rail  ,/.: scenario".to_string());
+    lines.join("\n")
+}
+
+fn update_connection_panel(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    state: Res<ConnectionToolState>,
+    config: Res<RegionConfig>,
+    region: Res<Region>,
+    connections: Query<&RegionalConnection>,
+    mut panel_q: Query<&mut Text, With<ConnectionPanel>>,
+) {
+    let connections: Vec<_> = connections.iter().collect();
+    let report = connection_report(&state, &config, &region, &connections);
+    if let Ok(mut text) = panel_q.get_single_mut() {
+        text.0 = report;
+        return;
+    }
+    commands.spawn((
+        Text::new(report),
+        TextFont {
+            font: asset_server.load("fonts/ShareTechMono-Regular.ttf"),
+            font_size: 13.0,
+            ..default()
+        },
+        TextColor(TEXT_COLOR),
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Px(10.0),
+            bottom: Val::Px(40.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            border: UiRect::all(Val::Px(1.0)),
+            ..default()
+        },
+        BackgroundColor(PANEL_BG),
+        BorderColor(BORDER),
+        ConnectionPanel,
+    ));
+}
+
+/// Mark the spot on the edge the next connection would take, and ring the
+/// existing ones: solid once joined, faint while nothing reaches them.
+fn draw_connection_preview(
+    mut gizmos: Gizmos,
+    state: Res<ConnectionToolState>,
+    config: Res<RegionConfig>,
+    budget: Res<CityBudget>,
+    terrain: Res<HeightMap>,
+    connections: Query<&RegionalConnection>,
+) {
+    let lift = |p: Vec2| Vec3::new(p.x, terrain.sample_world(p) + 1.0, p.y);
+    let flat = |p: Vec2| Isometry3d::new(lift(p), Quat::from_rotation_x(std::f32::consts::FRAC_PI_2));
+    let kind_color = |kind: ConnectionKind| match kind {
+        ConnectionKind::Highway => Color::srgb(0.2, 0.8, 0.4),
+        ConnectionKind::Rail => Color::srgb(0.9, 0.3, 0.2),
+    };
+
+    for connection in &connections {
+        let color = kind_color(connection.kind);
+        let color = if connection.is_joined() { color } else { color.with_alpha(0.35) };
+        gizmos.circle(flat(connection.position), config.snap_distance, color);
+    }
+
+    let Some(position) = state.hover else {
+        return;
+    };
+    let color = if budget.funds >= state.kind.cost() {
+        kind_color(state.kind)
+    } else {
+        Color::srgb(0.9, 0.3, 0.3)
+    };
+    gizmos.circle(flat(position), config.snap_distance, color);
+    gizmos.line(lift(position), lift(position) + Vec3::Y * 12.0, color);
+}
+
+/// Hide the panel when switching to another tool.
+fn cleanup_on_tool_change(
+    mut commands: Commands,
+    tool: Res<State<ActiveTool>>,
+    mut state: ResMut<ConnectionToolState>,
+    panel_q: Query<Entity, With<ConnectionPanel>>,
+) {
+    if !tool.is_changed() || *tool.get() == ActiveTool::Connection {
+        return;
+    }
+    for entity in &panel_q {
+        commands.entity(entity).despawn_recursive();
+    }
+    state.hover = None;
+}
@@ -1,13 +1,22 @@
-//! Demolish tool - remove buildings, zones, and roads.
+//! Demolish tool - remove buildings, parking lots and garages, zones, and roads.
+//!
+//! Roads under the cursor or drag rectangle are removed along with any nodes
+//! they leave unconnected. Everything one click or drag removes is a single
+//! undo step (Ctrl+Z / Ctrl+Y) that refunds the demolition cost.
 
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 
 use super::{ActiveTool, ToolState};
+use super::history::{stash_entity, CommandHistory, HistoryEntry, PlayerAction};
+use super::road_draw::{removal_action, RoadMeshDirty};
 use crate::game_state::GameState;
+use crate::procgen::roads::{RoadGraph, RoadsRemoved};
 use crate::render::building_spawner::Building;
+use crate::render::parking_garages::ParkingGarage;
+use crate::render::parking_lots::ParkingLot;
+use crate::simulation::economy::CityBudget;
 use crate::simulation::zones::GrownBuilding;
-use crate::tools::road_draw::RoadMeshDirty;
 use crate::tools::zone_paint::{ZoneCell, ZoneGrid};
 
 pub struct DemolishPlugin;
@@ -15,9 +24,10 @@ pub struct DemolishPlugin;
 impl Plugin for DemolishPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<DemolishConfig>()
+            .init_resource::<DemolishStroke>()
             .add_systems(
                 Update,
-                (handle_demolish_input, update_demolish_preview, apply_demolish)
+                (handle_demolish_input, update_demolish_preview, apply_demolish, apply_road_demolish)
                     .chain()
                     .run_if(in_state(GameState::Playing))
                     .run_if(is_demolish_active),
@@ -39,6 +49,8 @@ pub struct DemolishConfig {
     pub cost_per_building: i64,
     /// Cost per zone cell cleared.
     pub cost_per_zone: i64,
+    /// Cost per metre of road removed.
+    pub cost_per_road_metre: f32,
 }
 
 impl Default for DemolishConfig {
@@ -47,6 +59,7 @@ impl Default for DemolishConfig {
             demolish_radius: 5.0,
             cost_per_building: 100,
             cost_per_zone: 10,
+            cost_per_road_metre: 2.0,
         }
     }
 }
@@ -150,33 +163,58 @@ fn update_demolish_preview(
     }
 }
 
+/// Area the current click or drag demolishes: the drag rectangle, or a
+/// square around the cursor for a click.
+fn demolish_area(tool_state: &ToolState, config: &DemolishConfig) -> Option<Rect> {
+    let end_pos = tool_state.drag_end?;
+    match tool_state.drag_start {
+        Some(start) if (end_pos - start).abs().max_element() >= 1.0 => Some(Rect::from_corners(start, end_pos)),
+        _ => Some(Rect::from_center_half_size(end_pos, Vec2::splat(config.demolish_radius))),
+    }
+}
+
+/// What the current demolish click has removed so far, recorded as one undo step.
+#[derive(Resource, Default)]
+struct DemolishStroke {
+    /// Buildings and zone cells taken away (hidden until the step expires).
+    removed: Vec<Entity>,
+    /// Money spent on them.
+    cost: i64,
+}
+
+/// Filter for parking lots and garages.
+type ParkingFacility = Or<(With<ParkingLot>, With<ParkingGarage>)>;
+
+/// Hide an entity until its history entry expires, so demolition can be undone.
+fn stash(commands: &mut Commands, stroke: &mut DemolishStroke, entity: Entity) {
+    if !stroke.removed.contains(&entity) {
+        stroke.removed.push(entity);
+        commands.queue(move |world: &mut World| stash_entity(world, entity));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn apply_demolish(
     mut commands: Commands,
     mouse: Res<ButtonInput<MouseButton>>,
     tool_state: Res<ToolState>,
     config: Res<DemolishConfig>,
     mut zone_grid: ResMut<ZoneGrid>,
-    mut budget: ResMut<crate::simulation::economy::CityBudget>,
+    mut budget: ResMut<CityBudget>,
+    mut stroke: ResMut<DemolishStroke>,
     buildings: Query<(Entity, &GlobalTransform), With<Building>>,
     grown_buildings: Query<(Entity, &GrownBuilding)>,
     zone_cells: Query<(Entity, &ZoneCell, &GlobalTransform)>,
-    mut dirty_events: EventWriter<RoadMeshDirty>,
+    parking: Query<(Entity, &GlobalTransform), ParkingFacility>,
 ) {
     if !mouse.just_released(MouseButton::Left) {
         return;
     }
 
-    let Some(end_pos) = tool_state.drag_end else {
+    let Some(area) = demolish_area(&tool_state, &config) else {
         return;
     };
-
-    // Get demolish area
-    let (min, max) = if let Some(start) = tool_state.drag_start {
-        (start.min(end_pos), start.max(end_pos))
-    } else {
-        let half = config.demolish_radius;
-        (end_pos - Vec2::splat(half), end_pos + Vec2::splat(half))
-    };
+    let (min, max) = (area.min, area.max);
 
     let mut buildings_demolished = 0;
     let mut zones_cleared = 0;
@@ -188,7 +226,17 @@ fn apply_demolish(
         let pos_2d = Vec2::new(pos.x, pos.z);
 
         if pos_2d.x >= min.x && pos_2d.x <= max.x && pos_2d.y >= min.y && pos_2d.y <= max.y {
-            commands.entity(entity).despawn_recursive();
+            stash(&mut commands, &mut stroke, entity);
+            buildings_demolished += 1;
+            total_cost += config.cost_per_building;
+        }
+    }
+
+    // Parking lots and garages count as buildings
+    for (entity, transform) in &parking {
+        let pos = transform.translation();
+        if area.contains(Vec2::new(pos.x, pos.z)) {
+            stash(&mut commands, &mut stroke, entity);
             buildings_demolished += 1;
             total_cost += config.cost_per_building;
         }
@@ -201,7 +249,7 @@ fn apply_demolish(
             let pos_2d = Vec2::new(pos.x, pos.z);
 
             if pos_2d.x >= min.x && pos_2d.x <= max.x && pos_2d.y >= min.y && pos_2d.y <= max.y {
-                // The building entity was already despawned above if it has Building component
+                // The building entity was already removed above if it has Building component
                 // Just need to clear the zone cell reference
                 if let Ok((zone_entity, _, _)) = zone_cells.get(grown.zone_cell) {
                     commands.entity(zone_entity).try_insert(ZoneCellCleared);
@@ -219,15 +267,15 @@ fn apply_demolish(
             // Remove from grid
             zone_grid.cells.remove(&cell.grid_pos);
 
-            // Despawn building if any
+            // Remove building if any
             if let Some(building_entity) = cell.building {
-                commands.entity(building_entity).despawn_recursive();
+                stash(&mut commands, &mut stroke, building_entity);
                 buildings_demolished += 1;
                 total_cost += config.cost_per_building;
             }
 
-            // Despawn zone cell
-            commands.entity(entity).despawn_recursive();
+            // Remove zone cell
+            stash(&mut commands, &mut stroke, entity);
             zones_cleared += 1;
             total_cost += config.cost_per_zone;
         }
@@ -235,16 +283,69 @@ fn apply_demolish(
 
     // Deduct cost from budget
     budget.funds -= total_cost;
+    stroke.cost = total_cost;
 
     if buildings_demolished > 0 || zones_cleared > 0 {
         info!(
             "Demolished {} buildings, cleared {} zones. Cost: ${}",
             buildings_demolished, zones_cleared, total_cost
         );
+    }
+}
+
+/// Remove roads in the demolish area, leaving agents and bus routes to
+/// follow the `RoadsRemoved` event, then record the whole demolition.
+#[allow(clippy::too_many_arguments)]
+fn apply_road_demolish(
+    mouse: Res<ButtonInput<MouseButton>>,
+    tool_state: Res<ToolState>,
+    config: Res<DemolishConfig>,
+    mut stroke: ResMut<DemolishStroke>,
+    mut road_graph: ResMut<RoadGraph>,
+    mut history: ResMut<CommandHistory>,
+    mut budget: ResMut<CityBudget>,
+    mut dirty_events: EventWriter<RoadMeshDirty>,
+    mut removed_events: EventWriter<RoadsRemoved>,
+) {
+    if !mouse.just_released(MouseButton::Left) {
+        return;
+    }
+    let Some(area) = demolish_area(&tool_state, &config) else {
+        return;
+    };
+    let DemolishStroke { removed, mut cost } = std::mem::take(&mut *stroke);
+    let mut actions = Vec::new();
+    if !removed.is_empty() {
+        actions.push(PlayerAction::Removed(removed));
+    }
 
-        // Trigger mesh updates if needed
+    let edges = road_graph.edges_in_rect(area);
+    let length: f32 = edges
+        .iter()
+        .filter_map(|&idx| road_graph.edge_by_index(idx))
+        .map(|edge| edge.length)
+        .sum();
+    let road_cost = (length * config.cost_per_road_metre).round() as i64;
+    if edges.is_empty() {
+        // Nothing to do for roads
+    } else if budget.funds < road_cost {
+        warn!("Cannot afford to demolish {:.0}m of road: costs ${}, have ${}", length, road_cost, budget.funds);
+    } else {
+        let removal = road_graph.remove_edges(&edges);
+        budget.funds -= road_cost;
+        cost += road_cost;
+        info!(
+            "Demolished {} road segments and {} nodes. Cost: ${}",
+            removal.edges.len(),
+            removal.nodes.len(),
+            road_cost
+        );
+        actions.push(PlayerAction::Road(removal_action(&removal)));
+        removed_events.send(RoadsRemoved(removal));
         dirty_events.send(RoadMeshDirty);
     }
+
+    history.push(HistoryEntry::new("demolish", cost, actions));
 }
 
 /// Marker for zone cells that need their building reference cleared.
@@ -0,0 +1,468 @@
+//! Undo/redo shared by every player tool.
+//!
+//! Each click or stroke is recorded as one `HistoryEntry`: the actions that
+//! reverse it and the money it cost. Ctrl+Z reverts the latest entry and
+//! refunds its cost; Ctrl+Y (or Ctrl+Shift+Z) replays it and charges again.
+//! Buildings, zones and services taken away by an action are hidden rather
+//! than despawned, and only despawned once their entry leaves the history.
+//! Rail edits keep the track graph from before and after, and bus line edits
+//! the line's stops.
+
+#![allow(dead_code)]
+
+use bevy::prelude::*;
+
+use super::road_draw::RoadAction;
+use super::services::ServiceBuilding;
+use super::zone_paint::{ZoneCell, ZoneGrid};
+use crate::game_state::GameState;
+use crate::render::building_spawner::Building;
+use crate::render::parking_garages::ParkingGarage;
+use crate::render::parking_lots::ParkingLot;
+use crate::simulation::bus_routes::{BusLineEdited, BusRoutes};
+use crate::simulation::economy::CityBudget;
+use crate::simulation::rail_network::{RailGraph, RailNetwork, RailNetworkEdited};
+use crate::simulation::region::RegionalConnection;
+use crate::simulation::tourism::{Attraction, Hotel};
+use crate::simulation::zones::GrownBuilding;
+use crate::world::terrain::HeightMap;
+
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CommandHistory>().add_systems(
+            Update,
+            (handle_undo_redo, despawn_expired)
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// One reversible change made by a tool.
+#[derive(Clone, Debug)]
+pub enum PlayerAction {
+    /// Roads added, removed or modified.
+    Road(RoadAction),
+    /// Entities the action created; undo hides them, redo shows them again.
+    Spawned(Vec<Entity>),
+    /// Entities the action took away (hidden, see [`stash_entity`]); undo brings them back.
+    Removed(Vec<Entity>),
+    /// Height samples changed: grid position, height before and after.
+    Terrain(Vec<(usize, usize, f32, f32)>),
+    /// Rail track and stations before and after the change.
+    Rail { before: RailGraph, after: RailGraph },
+    /// Stops of a bus line, by route index, before and after the change.
+    BusStops { line: usize, before: Vec<Vec2>, after: Vec<Vec2> },
+}
+
+impl PlayerAction {
+    /// Reverse the action.
+    fn undo(&self, world: &mut World) {
+        match self {
+            PlayerAction::Road(action) => action.inverse().apply(world),
+            PlayerAction::Spawned(entities) => {
+                for &entity in entities {
+                    stash_entity(world, entity);
+                }
+            }
+            PlayerAction::Removed(entities) => {
+                for &entity in entities {
+                    restore_entity(world, entity);
+                }
+            }
+            PlayerAction::Terrain(samples) => {
+                let mut heights = world.resource_mut::<HeightMap>();
+                for &(x, y, before, _) in samples {
+                    heights.set(x, y, before);
+                }
+            }
+            PlayerAction::Rail { before, .. } => set_rail_graph(world, before),
+            PlayerAction::BusStops { line, before, .. } => set_bus_stops(world, *line, before),
+        }
+    }
+
+    /// Perform the action again after an undo.
+    fn redo(&self, world: &mut World) {
+        match self {
+            PlayerAction::Road(action) => action.apply(world),
+            PlayerAction::Spawned(entities) => {
+                for &entity in entities {
+                    restore_entity(world, entity);
+                }
+            }
+            PlayerAction::Removed(entities) => {
+                for &entity in entities {
+                    stash_entity(world, entity);
+                }
+            }
+            PlayerAction::Terrain(samples) => {
+                let mut heights = world.resource_mut::<HeightMap>();
+                for &(x, y, _, after) in samples {
+                    heights.set(x, y, after);
+                }
+            }
+            PlayerAction::Rail { after, .. } => set_rail_graph(world, after),
+            PlayerAction::BusStops { line, after, .. } => set_bus_stops(world, *line, after),
+        }
+    }
+
+    /// Entities left hidden while the action is in the given state.
+    fn hidden_entities(&self, undone: bool) -> &[Entity] {
+        match (self, undone) {
+            (PlayerAction::Spawned(entities), true) | (PlayerAction::Removed(entities), false) => entities,
+            _ => &[],
+        }
+    }
+}
+
+/// One undo step: everything a click or stroke did, and what it cost.
+#[derive(Clone, Debug)]
+pub struct HistoryEntry {
+    /// Short description for the log.
+    pub label: String,
+    /// Money spent (negative for a refund); returned on undo.
+    pub cost: i64,
+    pub actions: Vec<PlayerAction>,
+}
+
+impl HistoryEntry {
+    pub fn new(label: impl Into<String>, cost: i64, actions: Vec<PlayerAction>) -> Self {
+        Self {
+            label: label.into(),
+            cost,
+            actions,
+        }
+    }
+}
+
+/// Undo and redo stacks for all tools.
+#[derive(Resource)]
+pub struct CommandHistory {
+    /// Stack of entries that can be undone.
+    undo_stack: Vec<HistoryEntry>,
+    /// Stack of entries that can be redone.
+    redo_stack: Vec<HistoryEntry>,
+    /// Maximum history size.
+    max_size: usize,
+    /// Hidden entities whose entries fell out of the history, to despawn.
+    expired: Vec<Entity>,
+}
+
+impl CommandHistory {
+    /// Record a new entry. Clears the redo stack.
+    pub fn push(&mut self, entry: HistoryEntry) {
+        if entry.actions.is_empty() {
+            return;
+        }
+        for undone in std::mem::take(&mut self.redo_stack) {
+            self.expire(&undone, true);
+        }
+        self.push_undo_only(entry);
+    }
+
+    /// Push to undo stack without clearing redo (for redo operations).
+    fn push_undo_only(&mut self, entry: HistoryEntry) {
+        self.undo_stack.push(entry);
+
+        // Limit history size
+        if self.max_size > 0 && self.undo_stack.len() > self.max_size {
+            let oldest = self.undo_stack.remove(0);
+            self.expire(&oldest, false);
+        }
+    }
+
+    /// Queue the entities an entry was keeping hidden for despawning.
+    fn expire(&mut self, entry: &HistoryEntry, undone: bool) {
+        for action in &entry.actions {
+            self.expired.extend_from_slice(action.hidden_entities(undone));
+        }
+    }
+
+    /// Check if undo is available.
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    /// Check if redo is available.
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Clear all history, despawning everything it kept hidden.
+    pub fn clear(&mut self) {
+        for entry in std::mem::take(&mut self.undo_stack) {
+            self.expire(&entry, false);
+        }
+        for entry in std::mem::take(&mut self.redo_stack) {
+            self.expire(&entry, true);
+        }
+    }
+}
+
+impl Default for CommandHistory {
+    fn default() -> Self {
+        Self {
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            max_size: 100, // Keep last 100 actions
+            expired: Vec::new(),
+        }
+    }
+}
+
+/// Game components taken off an entity while it is removed but undoable.
+#[derive(Component)]
+pub struct Stashed {
+    building: Option<Building>,
+    grown: Option<GrownBuilding>,
+    service: Option<ServiceBuilding>,
+    zone: Option<ZoneCell>,
+    lot: Option<ParkingLot>,
+    garage: Option<ParkingGarage>,
+    connection: Option<RegionalConnection>,
+    attraction: Option<Attraction>,
+    hotel: Option<Hotel>,
+    visibility: Option<Visibility>,
+}
+
+/// Take an entity out of the game without despawning it: hide it and strip
+/// the components other systems look for, so an undo can put it back.
+pub fn stash_entity(world: &mut World, entity: Entity) {
+    let Ok(mut entity_mut) = world.get_entity_mut(entity) else {
+        return;
+    };
+    if entity_mut.contains::<Stashed>() {
+        return;
+    }
+    let stashed = Stashed {
+        building: entity_mut.take::<Building>(),
+        grown: entity_mut.take::<GrownBuilding>(),
+        service: entity_mut.take::<ServiceBuilding>(),
+        zone: entity_mut.take::<ZoneCell>(),
+        lot: entity_mut.take::<ParkingLot>(),
+        garage: entity_mut.take::<ParkingGarage>(),
+        connection: entity_mut.take::<RegionalConnection>(),
+        attraction: entity_mut.take::<Attraction>(),
+        hotel: entity_mut.take::<Hotel>(),
+        visibility: entity_mut.take::<Visibility>(),
+    };
+    let zone = stashed.zone.as_ref().map(|cell| (cell.grid_pos, cell.building));
+    entity_mut.insert((stashed, Visibility::Hidden));
+
+    if let Some((pos, building)) = zone {
+        let mut grid = world.resource_mut::<ZoneGrid>();
+        if grid.get(pos) == Some(entity) {
+            grid.remove(pos);
+        }
+        // A building grown on the cell goes with it
+        if let Some(building) = building {
+            stash_entity(world, building);
+        }
+    }
+}
+
+/// Put back an entity hidden by [`stash_entity`].
+pub fn restore_entity(world: &mut World, entity: Entity) {
+    let Ok(mut entity_mut) = world.get_entity_mut(entity) else {
+        return;
+    };
+    let Some(stashed) = entity_mut.take::<Stashed>() else {
+        return;
+    };
+    entity_mut.insert(stashed.visibility.unwrap_or_default());
+    if let Some(building) = stashed.building {
+        entity_mut.insert(building);
+    }
+    if let Some(grown) = stashed.grown {
+        entity_mut.insert(grown);
+    }
+    if let Some(service) = stashed.service {
+        entity_mut.insert(service);
+    }
+    if let Some(lot) = stashed.lot {
+        entity_mut.insert(lot);
+    }
+    if let Some(garage) = stashed.garage {
+        entity_mut.insert(garage);
+    }
+    if let Some(connection) = stashed.connection {
+        entity_mut.insert(connection);
+    }
+    if let Some(attraction) = stashed.attraction {
+        entity_mut.insert(attraction);
+    }
+    if let Some(hotel) = stashed.hotel {
+        entity_mut.insert(hotel);
+    }
+    if let Some(zone) = stashed.zone {
+        let (pos, building) = (zone.grid_pos, zone.building);
+        entity_mut.insert(zone);
+        let mut grid = world.resource_mut::<ZoneGrid>();
+        if !grid.is_zoned(pos) {
+            grid.insert(pos, entity);
+        }
+        if let Some(building) = building {
+            restore_entity(world, building);
+        }
+    }
+}
+
+/// Put the rail network's track and stations back as they were, dropping
+/// line stops at stations that no longer exist.
+fn set_rail_graph(world: &mut World, graph: &RailGraph) {
+    let mut network = world.resource_mut::<RailNetwork>();
+    network.graph = graph.clone();
+    let RailNetwork { graph, lines } = &mut *network;
+    for line in lines {
+        line.stations
+            .retain(|&node| graph.node_weight(node).is_some_and(|n| n.station.is_some()));
+    }
+    world.send_event(RailNetworkEdited);
+}
+
+/// Give a bus line the stops it had, clearing its route if it has none left.
+fn set_bus_stops(world: &mut World, line: usize, stops: &[Vec2]) {
+    let mut routes = world.resource_mut::<BusRoutes>();
+    let Some(route) = routes.routes.get_mut(line) else {
+        return;
+    };
+    route.stops = stops.to_vec();
+    if route.stops.is_empty() {
+        route.edges.clear();
+        route.stop_indices.clear();
+    }
+    world.send_event(BusLineEdited);
+}
+
+/// Height samples that differ between `before` and the current map.
+pub fn terrain_changes(before: &[f32], heights: &HeightMap) -> Vec<(usize, usize, f32, f32)> {
+    before
+        .iter()
+        .zip(&heights.data)
+        .enumerate()
+        .filter(|(_, (old, new))| (*old - *new).abs() > 1e-5)
+        .map(|(i, (old, new))| (i % heights.width, i / heights.width, *old, *new))
+        .collect()
+}
+
+/// Handle undo (Ctrl+Z) and redo (Ctrl+Y or Ctrl+Shift+Z) for every tool.
+fn handle_undo_redo(world: &mut World) {
+    let keyboard = world.resource::<ButtonInput<KeyCode>>();
+    let ctrl_pressed = keyboard.pressed(KeyCode::ControlLeft) || keyboard.pressed(KeyCode::ControlRight);
+    let shift_pressed = keyboard.pressed(KeyCode::ShiftLeft) || keyboard.pressed(KeyCode::ShiftRight);
+    let undo = ctrl_pressed && keyboard.just_pressed(KeyCode::KeyZ) && !shift_pressed;
+    let redo = ctrl_pressed && (keyboard.just_pressed(KeyCode::KeyY) || (shift_pressed && keyboard.just_pressed(KeyCode::KeyZ)));
+
+    if undo {
+        let Some(entry) = world.resource_mut::<CommandHistory>().undo_stack.pop() else {
+            return;
+        };
+        for action in entry.actions.iter().rev() {
+            action.undo(world);
+        }
+        world.resource_mut::<CityBudget>().funds += entry.cost;
+        if entry.cost > 0 {
+            info!("Undo: {} (refunded ${})", entry.label, entry.cost);
+        } else {
+            info!("Undo: {}", entry.label);
+        }
+        world
+            .resource_mut::<CommandHistory>()
+            .redo_stack
+            .push(entry);
+    } else if redo {
+        let Some(entry) = world.resource_mut::<CommandHistory>().redo_stack.pop() else {
+            return;
+        };
+        let funds = world.resource::<CityBudget>().funds;
+        if entry.cost > 0 && funds < entry.cost {
+            warn!("Cannot afford to redo {}: costs ${}, have ${}", entry.label, entry.cost, funds);
+            world.resource_mut::<CommandHistory>().redo_stack.push(entry);
+            return;
+        }
+        for action in &entry.actions {
+            action.redo(world);
+        }
+        world.resource_mut::<CityBudget>().funds -= entry.cost;
+        info!("Redo: {}", entry.label);
+        world
+            .resource_mut::<CommandHistory>()
+            .push_undo_only(entry);
+    }
+}
+
+/// Despawn hidden entities whose history entries are gone.
+fn despawn_expired(mut commands: Commands, mut history: ResMut<CommandHistory>) {
+    for entity in history.expired.drain(..) {
+        if let Some(entity_commands) = commands.get_entity(entity) {
+            entity_commands.despawn_recursive();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::procgen::lot_engine::ZoneType;
+
+    fn press(world: &mut World, keys: &[KeyCode]) {
+        let mut keyboard = world.resource_mut::<ButtonInput<KeyCode>>();
+        keyboard.release_all();
+        keyboard.clear();
+        for &key in keys {
+            keyboard.press(key);
+        }
+    }
+
+    #[test]
+    fn undo_refunds_and_restores_then_redo_replays() {
+        let mut world = World::new();
+        world.init_resource::<CommandHistory>();
+        world.init_resource::<ButtonInput<KeyCode>>();
+        world.init_resource::<ZoneGrid>();
+        world.insert_resource(CityBudget { funds: 100, ..default() });
+        world.insert_resource(HeightMap::generate(4, 4, 1, 10.0));
+
+        let mut heights = world.resource_mut::<HeightMap>();
+        let before = heights.data.clone();
+        heights.set(1, 2, 42.0);
+        let samples = terrain_changes(&before, &heights);
+        assert_eq!(samples.len(), 1);
+
+        let pos = IVec2::new(3, 5);
+        let cell = world
+            .spawn((
+                ZoneCell {
+                    grid_pos: pos,
+                    zone_type: ZoneType::Residential,
+                    development_level: 0,
+                    building: None,
+                },
+                Visibility::default(),
+            ))
+            .id();
+        world.resource_mut::<ZoneGrid>().insert(pos, cell);
+        world.resource_mut::<CommandHistory>().push(HistoryEntry::new(
+            "test",
+            50,
+            vec![PlayerAction::Spawned(vec![cell]), PlayerAction::Terrain(samples)],
+        ));
+
+        press(&mut world, &[KeyCode::ControlLeft, KeyCode::KeyZ]);
+        handle_undo_redo(&mut world);
+        assert_eq!(world.resource::<CityBudget>().funds, 150);
+        assert_eq!(world.resource::<HeightMap>().data, before);
+        assert!(!world.resource::<ZoneGrid>().is_zoned(pos));
+        assert!(world.get::<ZoneCell>(cell).is_none());
+        assert!(world.resource::<CommandHistory>().can_redo());
+
+        press(&mut world, &[KeyCode::ControlLeft, KeyCode::KeyY]);
+        handle_undo_redo(&mut world);
+        assert_eq!(world.resource::<CityBudget>().funds, 100);
+        assert_eq!(world.resource::<HeightMap>().data[2 * 4 + 1], 42.0);
+        assert_eq!(world.resource::<ZoneGrid>().get(pos), Some(cell));
+        assert!(world.resource::<CommandHistory>().can_undo());
+    }
+}
@@ -0,0 +1,240 @@
+//! Landmark tool - build clock towers and churches to draw visitors.
+//!
+//! Tab switches between landmarks and , / . turn the next one a quarter
+//! turn. A click builds it at the cursor. The panel reports what the city's
+//! sights draw: the season, visitors arriving, and hotel rooms taken.
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use std::f32::consts::FRAC_PI_2;
+
+use super::history::{CommandHistory, HistoryEntry, PlayerAction};
+use super::ActiveTool;
+use crate::game_state::GameState;
+use crate::render::landmarks::{spawn_landmark, LandmarkAssets, LandmarkKind};
+use crate::simulation::economy::CityBudget;
+use crate::simulation::tourism::{Tourism, TourismConfig};
+use crate::world::terrain::HeightMap;
+
+pub struct LandmarkToolPlugin;
+
+impl Plugin for LandmarkToolPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LandmarkToolState>()
+            .add_systems(
+                Update,
+                (edit_landmark_keys, handle_landmark_input, update_landmark_panel, draw_landmark_preview)
+                    .chain()
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_state(ActiveTool::Landmark)),
+            )
+            .add_systems(Update, cleanup_on_tool_change.run_if(in_state(GameState::Playing)));
+    }
+}
+
+const PANEL_BG: Color = Color::srgba(0.02, 0.02, 0.04, 0.94);
+const BORDER: Color = Color::srgb(0.85, 0.7, 0.3);
+const TEXT_COLOR: Color = Color::srgb(0.8, 0.85, 1.0);
+
+/// What the landmark tool is working on.
+#[derive(Resource)]
+pub struct LandmarkToolState {
+    pub kind: LandmarkKind,
+    /// Quarter turns of the next landmark.
+    pub turns: u32,
+    pub hover: Option<Vec2>,
+}
+
+impl Default for LandmarkToolState {
+    fn default() -> Self {
+        Self {
+            kind: LandmarkKind::ClockTower,
+            turns: 0,
+            hover: None,
+        }
+    }
+}
+
+impl LandmarkToolState {
+    fn rotation(&self) -> f32 {
+        self.turns as f32 * FRAC_PI_2
+    }
+}
+
+/// Marker for the landmark panel.
+#[derive(Component)]
+struct LandmarkPanel;
+
+fn edit_landmark_keys(keys: Res<ButtonInput<KeyCode>>, mut state: ResMut<LandmarkToolState>) {
+    if keys.just_pressed(KeyCode::Tab) {
+        state.kind = match state.kind {
+            LandmarkKind::ClockTower => LandmarkKind::Church,
+            LandmarkKind::Church => LandmarkKind::ClockTower,
+        };
+    }
+    if keys.just_pressed(KeyCode::Comma) {
+        state.turns = (state.turns + 3) % 4;
+    }
+    if keys.just_pressed(KeyCode::Period) {
+        state.turns = (state.turns + 1) % 4;
+    }
+}
+
+/// Build a landmark at the cursor with a left click.
+#[allow(clippy::too_many_arguments)]
+fn handle_landmark_input(
+    mut commands: Commands,
+    mouse: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
+    terrain: Res<HeightMap>,
+    assets: Res<LandmarkAssets>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut budget: ResMut<CityBudget>,
+    mut history: ResMut<CommandHistory>,
+    mut state: ResMut<LandmarkToolState>,
+) {
+    state.hover = None;
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_q.get_single() else {
+        return;
+    };
+    let Some(cursor) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+    else {
+        return;
+    };
+    state.hover = Some(cursor);
+
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let cost = state.kind.cost();
+    if budget.funds < cost {
+        info!("Cannot afford a landmark (${} needed, ${} available)", cost, budget.funds);
+        return;
+    }
+    let base_y = terrain.sample_world(cursor);
+    let entity = spawn_landmark(&mut commands, &mut meshes, &assets, state.kind, cursor, base_y, state.rotation());
+    budget.funds -= cost;
+    history.push(HistoryEntry::new(
+        format!("build {}", state.kind.name().to_lowercase()),
+        cost,
+        vec![PlayerAction::Spawned(vec![entity])],
+    ));
+    info!("Built {} at ({:.1}, {:.1}) for ${}", state.kind.name(), cursor.x, cursor.y, cost);
+}
+
+fn landmark_report(state: &LandmarkToolState, tourism: &Tourism) -> String {
+    let mut lines = vec![format!(
+        "{}  ${}  attractiveness {:.0}",
+        state.kind.name().to_uppercase(),
+        state.kind.cost(),
+        state.kind.attractiveness()
+    )];
+    lines.push(String::new());
+    lines.push(format!("TOURISM  {}, day {}", tourism.season.name(), tourism.day + 1));
+    lines.push(format!(" sights: attractiveness {:.0}", tourism.attractiveness));
+    lines.push(format!(
+        " visitors: {} arriving  {} day trippers",
+        tourism.arrivals, tourism.day_trippers
+    ));
+    lines.push(format!(
+        " hotels: {}  {} / {} rooms taken",
+        tourism.hotels, tourism.guests, tourism.rooms
+    ));
+    lines.push(format!(" spending ${:.0}", tourism.spending));
+
+    lines.push(String::new());
+    lines.push("Click: build  Tab: landmark".to_string());
+    lines.push(",/.: turn".to_string());
+    lines.join("\n")
+}
+
+fn update_landmark_panel(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    state: Res<LandmarkToolState>,
+    tourism: Res<Tourism>,
+    mut panel_q: Query<&mut Text, With<LandmarkPanel>>,
+) {
+    let report = landmark_report(&state, &tourism);
+    if let Ok(mut text) = panel_q.get_single_mut() {
+        text.0 = report;
+        return;
+    }
+    commands.spawn((
+        Text::new(report),
+        TextFont {
+            font: asset_server.load("fonts/ShareTechMono-Regular.ttf"),
+            font_size: 13.0,
+            ..default()
+        },
+        TextColor(TEXT_COLOR),
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Px(10.0),
+            bottom: Val::Px(40.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            border: UiRect::all(Val::Px(1.0)),
+            ..default()
+        },
+        BackgroundColor(PANEL_BG),
+        BorderColor(BORDER),
+        LandmarkPanel,
+    ));
+}
+
+/// Outline the next landmark, an arrow for the way it faces, and the ring
+/// within which businesses may open as hotels for its visitors.
+fn draw_landmark_preview(
+    mut gizmos: Gizmos,
+    state: Res<LandmarkToolState>,
+    config: Res<TourismConfig>,
+    budget: Res<CityBudget>,
+    terrain: Res<HeightMap>,
+) {
+    let Some(cursor) = state.hover else {
+        return;
+    };
+    let lift = |p: Vec2| Vec3::new(p.x, terrain.sample_world(p) + 1.0, p.y);
+    let color = if budget.funds >= state.kind.cost() {
+        Color::srgb(0.9, 0.75, 0.3)
+    } else {
+        Color::srgb(0.9, 0.3, 0.3)
+    };
+    let size = match state.kind {
+        LandmarkKind::ClockTower => Vec2::splat(6.5),
+        LandmarkKind::Church => Vec2::new(12.0, 27.0),
+    };
+    // Landmarks face -Z before they are turned
+    let turn = Vec2::from_angle(-state.rotation());
+    let corners = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0), (-1.0, -1.0)]
+        .map(|(x, y)| lift(cursor + turn.rotate(Vec2::new(x * size.x, y * size.y) / 2.0)));
+    gizmos.linestrip(corners, color);
+    gizmos.arrow(lift(cursor), lift(cursor + turn.rotate(Vec2::new(0.0, -size.y))), color);
+    gizmos.circle(
+        Isometry3d::new(lift(cursor), Quat::from_rotation_x(FRAC_PI_2)),
+        config.hotel_radius,
+        color.with_alpha(0.3),
+    );
+}
+
+/// Hide the panel when switching to another tool.
+fn cleanup_on_tool_change(
+    mut commands: Commands,
+    tool: Res<State<ActiveTool>>,
+    mut state: ResMut<LandmarkToolState>,
+    panel_q: Query<Entity, With<LandmarkPanel>>,
+) {
+    if !tool.is_changed() || *tool.get() == ActiveTool::Landmark {
+        return;
+    }
+    for entity in &panel_q {
+        commands.entity(entity).despawn_recursive();
+    }
+    state.hover = None;
+}
@@ -1,17 +1,36 @@
 //! Player tools for interacting with the city.
 //!
-//! Tools allow the player to modify the city: zoning land, drawing roads,
-//! demolishing buildings, and placing services.
+//! Tools allow the player to modify the city: zoning land, drawing and
+//! modifying roads, demolishing buildings, placing services and utility
+//! plants, and shaping terrain. Every tool records its changes in a shared
+//! undo/redo history. The query tool inspects objects and edits traffic
+//! signal timing; the transit tool lays out bus lines, the rail tool builds
+//! track, stations and metro lines, the parking tool builds lots and
+//! garages, the connection tool links the city to the region beyond the
+//! map edge, and the landmark tool builds sights for visitors to see.
 
 use bevy::prelude::*;
 
+pub mod connections;
 pub mod demolish;
+pub mod history;
+pub mod landmarks;
+pub mod parking;
+pub mod query;
+pub mod rail;
 pub mod road_draw;
+pub mod road_modify;
+pub mod road_snap;
+pub mod road_templates;
 pub mod services;
+pub mod terraform;
+pub mod transit;
 pub mod zone_paint;
 
 pub use crate::procgen::lot_engine::ZoneType;
+pub use road_modify::RoadModifyMode;
 pub use services::ServiceType;
+pub use terraform::TerraformMode;
 
 pub struct ToolsPlugin;
 
@@ -19,10 +38,19 @@ impl Plugin for ToolsPlugin {
     fn build(&self, app: &mut App) {
         app.init_state::<ActiveTool>()
             .init_resource::<ToolState>()
+            .add_plugins(history::HistoryPlugin)
             .add_plugins(zone_paint::ZonePaintPlugin)
             .add_plugins(road_draw::RoadDrawPlugin)
+            .add_plugins(road_modify::RoadModifyPlugin)
             .add_plugins(demolish::DemolishPlugin)
-            .add_plugins(services::ServicesPlugin);
+            .add_plugins(services::ServicesPlugin)
+            .add_plugins(terraform::TerraformPlugin)
+            .add_plugins(query::QueryPlugin)
+            .add_plugins(transit::TransitPlugin)
+            .add_plugins(rail::RailToolPlugin)
+            .add_plugins(parking::ParkingToolPlugin)
+            .add_plugins(connections::ConnectionToolPlugin)
+            .add_plugins(landmarks::LandmarkToolPlugin);
     }
 }
 
@@ -36,16 +64,30 @@ pub enum ActiveTool {
     ZonePaint(ZoneType),
     /// Road drawing tool - click to place nodes.
     RoadDraw,
+    /// Road modification tool - click or drag along roads to change them.
+    RoadModify(RoadModifyMode),
     /// Demolish tool - click to remove buildings/roads.
     Demolish,
-    /// Service placement tool.
+    /// Service placement tool, for utility plants too.
     PlaceService(ServiceType),
+    /// Terraform tool - brush to reshape the ground.
+    Terraform(TerraformMode),
     /// Query tool - click to inspect objects.
     Query,
+    /// Transit tool - click roads to lay out bus lines.
+    Transit,
+    /// Rail tool - lay track, place stations and define metro lines.
+    Rail,
+    /// Parking tool - build parking lots and garages.
+    Parking,
+    /// Connection tool - place highway and rail connections to the region.
+    Connection,
+    /// Landmark tool - build clock towers and churches.
+    Landmark,
 }
 
 /// Shared state for tool interactions.
-#[derive(Resource, Default)]
+#[derive(Resource)]
 pub struct ToolState {
     /// Whether the user is currently dragging.
     pub is_dragging: bool,
@@ -57,6 +99,17 @@ pub struct ToolState {
     pub brush_size: f32,
 }
 
+impl Default for ToolState {
+    fn default() -> Self {
+        Self {
+            is_dragging: false,
+            drag_start: None,
+            drag_end: None,
+            brush_size: 16.0,
+        }
+    }
+}
+
 impl ToolState {
     /// Get the drag rectangle if a drag is in progress.
     pub fn drag_rect(&self) -> Option<Rect> {
@@ -0,0 +1,315 @@
+//! Parking tool - build surface lots and multi-storey garages.
+//!
+//! Tab switches between lots and garages and , / . set a garage's floors.
+//! A click builds one at the cursor, turned to face the nearest road. The
+//! parking occupancy overlay is shown while the tool is active, and the
+//! panel reports how full the city's parking is.
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use rand::{rngs::StdRng, SeedableRng};
+
+use super::history::{CommandHistory, HistoryEntry, PlayerAction};
+use super::ActiveTool;
+use crate::game_state::GameState;
+use crate::procgen::roads::RoadGraph;
+use crate::render::parking_garages::{spawn_parking_garage, ParkingGarageAssets, ParkingGarageConfig, SPACES_PER_FLOOR};
+use crate::render::parking_lots::{lot_capacity, spawn_parking_lot, ParkingLotAssets, ParkingLotConfig};
+use crate::simulation::economy::CityBudget;
+use crate::simulation::parking::{ParkingConfig, ParkingStats, ParkingSupply};
+use crate::world::terrain::HeightMap;
+
+pub struct ParkingToolPlugin;
+
+impl Plugin for ParkingToolPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ParkingToolState>()
+            .add_systems(
+                Update,
+                (edit_parking_keys, handle_parking_input, update_parking_panel, draw_parking_preview)
+                    .chain()
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_state(ActiveTool::Parking)),
+            )
+            .add_systems(Update, cleanup_on_tool_change.run_if(in_state(GameState::Playing)));
+    }
+}
+
+/// Cost of a surface lot.
+const LOT_COST: i64 = 2000;
+
+/// Cost of each floor of a garage.
+const GARAGE_FLOOR_COST: i64 = 1500;
+
+/// A new facility turns to face a road within this distance.
+const ROAD_ALIGN_DISTANCE: f32 = 40.0;
+
+/// The panel describes a lot, garage or curb this close to the cursor.
+const SITE_PICK_DISTANCE: f32 = 15.0;
+
+const PANEL_BG: Color = Color::srgba(0.02, 0.02, 0.04, 0.94);
+const BORDER: Color = Color::srgb(0.3, 0.5, 1.0);
+const TEXT_COLOR: Color = Color::srgb(0.8, 0.85, 1.0);
+
+/// What a click builds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Facility {
+    #[default]
+    Lot,
+    Garage,
+}
+
+/// What the parking tool is working on.
+#[derive(Resource)]
+pub struct ParkingToolState {
+    pub facility: Facility,
+    /// Floors of the next garage.
+    pub floors: u32,
+    /// Where the next facility would go and which way it faces.
+    pub hover: Option<(Vec2, Vec2)>,
+}
+
+impl Default for ParkingToolState {
+    fn default() -> Self {
+        Self {
+            facility: Facility::Lot,
+            floors: 4,
+            hover: None,
+        }
+    }
+}
+
+impl ParkingToolState {
+    fn cost(&self) -> i64 {
+        match self.facility {
+            Facility::Lot => LOT_COST,
+            Facility::Garage => GARAGE_FLOOR_COST * self.floors as i64,
+        }
+    }
+}
+
+/// Marker for the parking panel.
+#[derive(Component)]
+struct ParkingPanel;
+
+fn edit_parking_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    garage_config: Res<ParkingGarageConfig>,
+    mut state: ResMut<ParkingToolState>,
+) {
+    if keys.just_pressed(KeyCode::Tab) {
+        state.facility = match state.facility {
+            Facility::Lot => Facility::Garage,
+            Facility::Garage => Facility::Lot,
+        };
+    }
+    if state.facility != Facility::Garage {
+        return;
+    }
+    if keys.just_pressed(KeyCode::Comma) {
+        state.floors = state.floors.saturating_sub(1).max(garage_config.min_floors);
+    }
+    if keys.just_pressed(KeyCode::Period) {
+        state.floors = (state.floors + 1).min(garage_config.max_floors);
+    }
+}
+
+/// Build a lot or garage at the cursor with a left click.
+#[allow(clippy::too_many_arguments)]
+fn handle_parking_input(
+    mut commands: Commands,
+    mouse: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
+    road_graph: Res<RoadGraph>,
+    terrain: Res<HeightMap>,
+    lot_config: Res<ParkingLotConfig>,
+    lot_assets: Res<ParkingLotAssets>,
+    garage_config: Res<ParkingGarageConfig>,
+    garage_assets: Res<ParkingGarageAssets>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut budget: ResMut<CityBudget>,
+    mut history: ResMut<CommandHistory>,
+    mut state: ResMut<ParkingToolState>,
+    mut rng: Local<Option<StdRng>>,
+) {
+    state.hover = None;
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_q.get_single() else {
+        return;
+    };
+    let Some(cursor) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+    else {
+        return;
+    };
+    let facing = road_graph
+        .nearest_edge(cursor, ROAD_ALIGN_DISTANCE)
+        .map_or(Vec2::X, |(_, _, direction)| direction);
+    state.hover = Some((cursor, facing));
+
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let cost = state.cost();
+    if budget.funds < cost {
+        info!("Cannot afford parking (${} needed, ${} available)", cost, budget.funds);
+        return;
+    }
+
+    let transform = Transform::from_xyz(cursor.x, terrain.sample_world(cursor), cursor.y)
+        .with_rotation(Quat::from_rotation_y(-facing.y.atan2(facing.x)));
+    let (entity, label) = match state.facility {
+        Facility::Lot => {
+            let rng = rng.get_or_insert_with(|| StdRng::seed_from_u64(lot_config.seed));
+            let entity = spawn_parking_lot(&mut commands, &lot_config, &lot_assets, transform, rng);
+            (entity, "build parking lot".to_string())
+        }
+        Facility::Garage => {
+            let entity =
+                spawn_parking_garage(&mut commands, &garage_config, &garage_assets, &mut meshes, transform, state.floors);
+            (entity, format!("build {}-floor parking garage", state.floors))
+        }
+    };
+    budget.funds -= cost;
+    history.push(HistoryEntry::new(label, cost, vec![PlayerAction::Spawned(vec![entity])]));
+    info!("Built parking at ({:.1}, {:.1}) for ${}", cursor.x, cursor.y, cost);
+}
+
+fn parking_report(
+    state: &ParkingToolState,
+    stats: &ParkingStats,
+    supply: &ParkingSupply,
+    config: &ParkingConfig,
+    lot_config: &ParkingLotConfig,
+) -> String {
+    let mut lines = vec![match state.facility {
+        Facility::Lot => format!("PARKING LOT  {} spaces  ${}", lot_capacity(lot_config), state.cost()),
+        Facility::Garage => format!(
+            "PARKING GARAGE  {} floors  {} spaces  ${}",
+            state.floors,
+            state.floors * SPACES_PER_FLOOR,
+            state.cost()
+        ),
+    }];
+
+    let occupancy = if stats.capacity > 0 {
+        stats.occupied as f32 / stats.capacity as f32 * 100.0
+    } else {
+        0.0
+    };
+    lines.push(format!(" {} / {} spaces taken ({:.0}%)", stats.occupied, stats.capacity, occupancy));
+    lines.push(format!(" {:.1} cars cruising for a space", stats.cruising));
+    lines.push(format!(
+        " trips: {} parked  {} searched  {} gave up",
+        stats.parked, stats.searched, stats.failed
+    ));
+    lines.push(format!(" scarcity around shops: {:.0}%", stats.commercial_scarcity * 100.0));
+
+    if let Some((cursor, _)) = state.hover {
+        let (capacity, occupied) = supply.spaces_near(cursor, config.walk_radius);
+        lines.push(format!(" within walking distance: {} / {} taken", occupied, capacity));
+        if let Some(site) = supply.site_at(cursor, SITE_PICK_DISTANCE) {
+            lines.push(format!(" {}: {} / {} taken", site.kind.name(), site.occupied, site.capacity));
+        }
+    }
+
+    lines.push(String::new());
+    lines.push("Click: build  Tab: lot / garage".to_string());
+    if state.facility == Facility::Garage {
+        lines.push(",/.: floors -/+".to_string());
+    }
+    lines.join("\n")
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_parking_panel(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    state: Res<ParkingToolState>,
+    stats: Res<ParkingStats>,
+    supply: Res<ParkingSupply>,
+    config: Res<ParkingConfig>,
+    lot_config: Res<ParkingLotConfig>,
+    mut panel_q: Query<&mut Text, With<ParkingPanel>>,
+) {
+    let report = parking_report(&state, &stats, &supply, &config, &lot_config);
+    if let Ok(mut text) = panel_q.get_single_mut() {
+        text.0 = report;
+        return;
+    }
+    commands.spawn((
+        Text::new(report),
+        TextFont {
+            font: asset_server.load("fonts/ShareTechMono-Regular.ttf"),
+            font_size: 13.0,
+            ..default()
+        },
+        TextColor(TEXT_COLOR),
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Px(10.0),
+            bottom: Val::Px(40.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            border: UiRect::all(Val::Px(1.0)),
+            ..default()
+        },
+        BackgroundColor(PANEL_BG),
+        BorderColor(BORDER),
+        ParkingPanel,
+    ));
+}
+
+/// Outline the footprint of the next lot or garage and the walking distance around it.
+fn draw_parking_preview(
+    mut gizmos: Gizmos,
+    state: Res<ParkingToolState>,
+    config: Res<ParkingConfig>,
+    lot_config: Res<ParkingLotConfig>,
+    garage_config: Res<ParkingGarageConfig>,
+    budget: Res<CityBudget>,
+    terrain: Res<HeightMap>,
+) {
+    let Some((cursor, facing)) = state.hover else {
+        return;
+    };
+    let lift = |p: Vec2, h: f32| Vec3::new(p.x, terrain.sample_world(p) + h, p.y);
+    let size = match state.facility {
+        Facility::Lot => Vec2::new(lot_config.lot_width, lot_config.lot_depth),
+        Facility::Garage => Vec2::new(garage_config.width, garage_config.depth),
+    };
+    let color = if budget.funds >= state.cost() {
+        Color::srgb(0.3, 0.9, 0.4)
+    } else {
+        Color::srgb(0.9, 0.3, 0.3)
+    };
+
+    let across = Vec2::new(-facing.y, facing.x);
+    let corners = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0), (-1.0, -1.0)]
+        .map(|(x, y)| lift(cursor + facing * x * size.x / 2.0 + across * y * size.y / 2.0, 1.0));
+    gizmos.linestrip(corners, color);
+    gizmos.circle(
+        Isometry3d::new(lift(cursor, 0.8), Quat::from_rotation_x(std::f32::consts::FRAC_PI_2)),
+        config.walk_radius,
+        color.with_alpha(0.3),
+    );
+}
+
+/// Hide the panel when switching to another tool.
+fn cleanup_on_tool_change(
+    mut commands: Commands,
+    tool: Res<State<ActiveTool>>,
+    mut state: ResMut<ParkingToolState>,
+    panel_q: Query<Entity, With<ParkingPanel>>,
+) {
+    if !tool.is_changed() || *tool.get() == ActiveTool::Parking {
+        return;
+    }
+    for entity in &panel_q {
+        commands.entity(entity).despawn_recursive();
+    }
+    state.hover = None;
+}
@@ -0,0 +1,324 @@
+//! Query tool - click to inspect objects.
+//!
+//! Clicking near a signalised junction selects its traffic light and shows
+//! its phase plan: mode, layout, each phase's green time and what it serves,
+//! the traffic waiting, and any green wave it belongs to. The timing can be
+//! edited in place; edited signals keep their timing when corridors are
+//! recoordinated.
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use super::ActiveTool;
+use crate::game_state::GameState;
+use crate::procgen::intersections::{JunctionLayout, TurnKind};
+use crate::procgen::roads::RoadGraph;
+use crate::render::traffic_lights::{LightPhase, SignalPlanEdited, TrafficLightController};
+use crate::simulation::signal_plans::PhasePlan;
+use crate::world::terrain::HeightMap;
+
+pub struct QueryPlugin;
+
+impl Plugin for QueryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<QueryState>()
+            .add_systems(
+                Update,
+                (handle_query_input, edit_selected_signal, update_signal_panel, draw_selected_signal)
+                    .chain()
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_state(ActiveTool::Query)),
+            )
+            .add_systems(Update, cleanup_on_tool_change.run_if(in_state(GameState::Playing)));
+    }
+}
+
+/// Farthest a click may be from a junction centre to select its signal.
+const PICK_RADIUS: f32 = 25.0;
+/// Range hand-set green times are kept within.
+const GREEN_RANGE: (f32, f32) = (4.0, 60.0);
+
+const PANEL_BG: Color = Color::srgba(0.02, 0.03, 0.02, 0.94);
+const BORDER: Color = Color::srgb(0.0, 0.7, 0.4);
+const TEXT_COLOR: Color = Color::srgb(0.7, 1.0, 0.8);
+
+/// What the query tool has selected.
+#[derive(Resource, Default)]
+pub struct QueryState {
+    /// Traffic light controller being inspected.
+    pub selected: Option<Entity>,
+    /// Phase whose green time the edit keys change.
+    pub edit_phase: usize,
+}
+
+/// Marker for the signal timing panel.
+#[derive(Component)]
+struct SignalPanel;
+
+/// Select the signal nearest a click, or clear the selection.
+fn handle_query_input(
+    mouse: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
+    road_graph: Res<RoadGraph>,
+    controllers: Query<(Entity, &TrafficLightController)>,
+    mut state: ResMut<QueryState>,
+) {
+    if keys.just_pressed(KeyCode::Escape) || mouse.just_pressed(MouseButton::Right) {
+        state.selected = None;
+        return;
+    }
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_q.get_single() else {
+        return;
+    };
+    let Some(cursor) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+    else {
+        return;
+    };
+
+    let nearest = controllers
+        .iter()
+        .filter_map(|(entity, controller)| {
+            let node = road_graph.node_by_index(controller.node_index)?;
+            Some((entity, node.position.distance(cursor)))
+        })
+        .filter(|&(_, distance)| distance <= PICK_RADIUS)
+        .min_by(|a, b| a.1.total_cmp(&b.1));
+
+    if let Some((entity, _)) = nearest {
+        if state.selected != Some(entity) {
+            state.edit_phase = 0;
+        }
+        state.selected = Some(entity);
+    } else {
+        state.selected = None;
+    }
+}
+
+/// Keys for the selected signal: Tab picks a phase, `,` / `.` shorten or
+/// lengthen its green, M cycles the control mode and L the phase layout.
+fn edit_selected_signal(
+    keys: Res<ButtonInput<KeyCode>>,
+    layout: Res<JunctionLayout>,
+    mut state: ResMut<QueryState>,
+    mut controllers: Query<&mut TrafficLightController>,
+    mut edited: EventWriter<SignalPlanEdited>,
+) {
+    let Some(mut controller) = state.selected.and_then(|entity| controllers.get_mut(entity).ok()) else {
+        return;
+    };
+    let phase_count = controller.plan.phases.len();
+    if phase_count == 0 {
+        return;
+    }
+    state.edit_phase %= phase_count;
+
+    if keys.just_pressed(KeyCode::Tab) {
+        state.edit_phase = (state.edit_phase + 1) % phase_count;
+    }
+
+    let step = if keys.just_pressed(KeyCode::Period) {
+        1.0
+    } else if keys.just_pressed(KeyCode::Comma) {
+        -1.0
+    } else {
+        0.0
+    };
+    if step != 0.0 {
+        let phase = &mut controller.plan.phases[state.edit_phase];
+        phase.green = (phase.green + step).clamp(GREEN_RANGE.0, GREEN_RANGE.1);
+        phase.max_green = phase.max_green.max(phase.green);
+        controller.edited = true;
+    }
+
+    if keys.just_pressed(KeyCode::KeyM) {
+        controller.mode = controller.mode.next();
+        controller.edited = true;
+    }
+
+    if keys.just_pressed(KeyCode::KeyL) {
+        let Some(geometry) = layout.get(controller.node_index) else {
+            return;
+        };
+        controller.plan = PhasePlan::for_geometry(controller.plan.layout.next(), geometry);
+        controller.active_phase = 0;
+        controller.phase = LightPhase::Green;
+        controller.timer = 0.0;
+        controller.edited = true;
+        state.edit_phase = 0;
+    }
+
+    if keys.any_just_pressed([KeyCode::Comma, KeyCode::Period, KeyCode::KeyM, KeyCode::KeyL]) {
+        edited.send(SignalPlanEdited);
+    }
+}
+
+/// Short label for a movement, e.g. "#12 left".
+fn movement_label(edge: petgraph::graph::EdgeIndex, kind: TurnKind) -> String {
+    let turn = match kind {
+        TurnKind::Straight => "ahead",
+        TurnKind::Left => "left",
+        TurnKind::Right => "right",
+        TurnKind::UTurn => "u-turn",
+    };
+    format!("#{} {}", edge.index(), turn)
+}
+
+/// Describe the selected signal's plan and current state.
+fn signal_report(controller: &TrafficLightController, edit_phase: usize) -> String {
+    let plan = &controller.plan;
+    let mut lines = vec![
+        format!("SIGNAL @ node {}", controller.node_index.index()),
+        format!("Mode: {}{}", controller.mode.name(), if controller.edited { " (edited)" } else { "" }),
+        format!("Layout: {}", plan.layout.name()),
+        format!(
+            "Cycle: {:.0}s  min {:.0}s  yellow {:.0}s  all-red {:.0}s",
+            plan.cycle_length(),
+            plan.min_green,
+            plan.yellow,
+            plan.all_red
+        ),
+    ];
+    if let Some(coordination) = controller.coordination {
+        lines.push(format!(
+            "Green wave: cycle {:.0}s  offset {:.0}s  main phase {}",
+            coordination.cycle,
+            coordination.offset,
+            coordination.main_phase + 1
+        ));
+    }
+
+    for (index, phase) in plan.phases.iter().enumerate() {
+        let light = if index == controller.active_phase {
+            match controller.phase {
+                LightPhase::Green => "GREEN",
+                LightPhase::Yellow => "YELLOW",
+                LightPhase::Red => "ALL RED",
+            }
+        } else {
+            ""
+        };
+        let cursor = if index == edit_phase { ">" } else { " " };
+        lines.push(format!(
+            "{}{} green {:.0}s (max {:.0}s)  waiting {:.1}  {}",
+            cursor,
+            index + 1,
+            phase.green,
+            phase.max_green,
+            plan.phase_demand(index, |m| controller.demand_for(m)),
+            light
+        ));
+        let movements: Vec<String> = phase.movements.iter().map(|&(edge, kind)| movement_label(edge, kind)).collect();
+        lines.push(format!("    {}", movements.join(", ")));
+    }
+
+    lines.push(String::new());
+    lines.push("Tab: phase  ,/.: green -/+  M: mode  L: layout  Esc: close".to_string());
+    lines.join("\n")
+}
+
+/// Show, refresh or hide the timing panel for the selected signal.
+fn update_signal_panel(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut state: ResMut<QueryState>,
+    controllers: Query<&TrafficLightController>,
+    mut panel_q: Query<(Entity, &mut Text), With<SignalPanel>>,
+) {
+    let controller = state.selected.and_then(|entity| controllers.get(entity).ok());
+    let Some(controller) = controller else {
+        // Selection cleared, or the junction was rebuilt without a signal
+        state.selected = None;
+        for (entity, _) in &panel_q {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    };
+
+    let report = signal_report(controller, state.edit_phase);
+    if let Ok((_, mut text)) = panel_q.get_single_mut() {
+        text.0 = report;
+        return;
+    }
+    commands.spawn((
+        Text::new(report),
+        TextFont {
+            font: asset_server.load("fonts/ShareTechMono-Regular.ttf"),
+            font_size: 13.0,
+            ..default()
+        },
+        TextColor(TEXT_COLOR),
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Px(10.0),
+            bottom: Val::Px(40.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            border: UiRect::all(Val::Px(1.0)),
+            ..default()
+        },
+        BackgroundColor(PANEL_BG),
+        BorderColor(BORDER),
+        SignalPanel,
+    ));
+}
+
+/// Ring the selected junction and mark each approach with its light.
+fn draw_selected_signal(
+    mut gizmos: Gizmos,
+    state: Res<QueryState>,
+    layout: Res<JunctionLayout>,
+    terrain: Res<HeightMap>,
+    controllers: Query<&TrafficLightController>,
+) {
+    let Some(controller) = state.selected.and_then(|entity| controllers.get(entity).ok()) else {
+        return;
+    };
+    let Some(geometry) = layout.get(controller.node_index) else {
+        return;
+    };
+    let lift = |p: Vec2| Vec3::new(p.x, terrain.sample_world(p) + 0.5, p.y);
+    let center = geometry.center;
+    gizmos.circle(
+        Isometry3d::new(lift(center), Quat::from_rotation_x(std::f32::consts::FRAC_PI_2)),
+        PICK_RADIUS * 0.8,
+        BORDER,
+    );
+
+    for (index, &(edge, _)) in controller.approaches.iter().enumerate() {
+        let Some(leg) = geometry.legs.iter().find(|leg| leg.edge == edge) else {
+            continue;
+        };
+        let color = match controller.approach_phase(index) {
+            LightPhase::Green => Color::srgb(0.2, 1.0, 0.3),
+            LightPhase::Yellow => Color::srgb(1.0, 0.85, 0.1),
+            LightPhase::Red => Color::srgb(1.0, 0.2, 0.2),
+        };
+        let start = center + leg.direction * leg.setback;
+        gizmos.line(lift(start), lift(start + leg.direction * 10.0), color);
+    }
+}
+
+/// Clear the selection and panel when switching to another tool.
+fn cleanup_on_tool_change(
+    mut commands: Commands,
+    tool: Res<State<ActiveTool>>,
+    mut state: ResMut<QueryState>,
+    panel_q: Query<Entity, With<SignalPanel>>,
+) {
+    if !tool.is_changed() || *tool.get() == ActiveTool::Query {
+        return;
+    }
+    for entity in &panel_q {
+        commands.entity(entity).despawn_recursive();
+    }
+    *state = QueryState::default();
+}
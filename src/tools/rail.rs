@@ -0,0 +1,480 @@
+//! Rail tool - lay track, place stations and define metro lines.
+//!
+//! The tool has three modes, cycled with M. In track mode, clicks lay track
+//! from point to point at the chosen level, joining existing track where a
+//! click lands near it. In station mode a click makes the track node under
+//! the cursor a station, splitting the track if needed. In line mode clicks
+//! on stations add them to the line being edited, which runs by the shortest
+//! path over the track between them.
+//!
+//! Laying and removing track and opening and closing stations go in the
+//! undo history; undoing a build refunds it.
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use petgraph::stable_graph::NodeIndex;
+
+use super::history::{CommandHistory, HistoryEntry, PlayerAction};
+use super::ActiveTool;
+use crate::game_state::GameState;
+use crate::simulation::economy::CityBudget;
+use crate::simulation::rail_network::{
+    MetroLine, MetroRoute, RailNetwork, RailNetworkConfig, RailNetworkEdited, TrackLevel, MAX_LINE_TRAINS,
+};
+use crate::simulation::ridership::{LineKey, Ridership};
+use crate::world::terrain::HeightMap;
+
+pub struct RailToolPlugin;
+
+impl Plugin for RailToolPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RailToolState>()
+            .add_systems(
+                Update,
+                (handle_rail_input, edit_rail_keys, update_rail_panel, draw_rail_network)
+                    .chain()
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_state(ActiveTool::Rail)),
+            )
+            .add_systems(Update, cleanup_on_tool_change.run_if(in_state(GameState::Playing)));
+    }
+}
+
+/// Cost of a station.
+const STATION_COST: i64 = 1500;
+
+/// Cost of track per unit length at each level.
+fn track_cost(level: TrackLevel) -> f32 {
+    match level {
+        TrackLevel::AtGrade => 3.0,
+        TrackLevel::Elevated => 8.0,
+        TrackLevel::Underground => 20.0,
+    }
+}
+
+/// Colours offered for metro lines, cycled with L.
+const LINE_COLORS: &[Color] = &[
+    Color::srgb(0.9, 0.15, 0.15), // Red
+    Color::srgb(0.1, 0.45, 0.9),  // Blue
+    Color::srgb(0.1, 0.7, 0.3),   // Green
+    Color::srgb(0.95, 0.55, 0.1), // Orange
+    Color::srgb(0.6, 0.6, 0.65),  // Silver
+];
+
+const PANEL_BG: Color = Color::srgba(0.02, 0.02, 0.04, 0.94);
+const BORDER: Color = Color::srgb(0.3, 0.5, 1.0);
+const TEXT_COLOR: Color = Color::srgb(0.8, 0.85, 1.0);
+
+/// What a click does.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RailMode {
+    /// Lay track at a level.
+    #[default]
+    Track,
+    /// Place stations on track.
+    Station,
+    /// Add stations to a line.
+    Line,
+}
+
+/// What the rail tool is working on.
+#[derive(Resource, Default)]
+pub struct RailToolState {
+    pub mode: RailMode,
+    /// Level new track is laid at.
+    pub level: TrackLevel,
+    /// Where the track being laid starts.
+    pub anchor: Option<Vec2>,
+    /// Index of the line being edited.
+    pub editing: Option<usize>,
+    /// Point under the cursor, snapped to the track when near it.
+    pub hover: Option<Vec2>,
+}
+
+/// Marker for the rail panel.
+#[derive(Component)]
+struct RailPanel;
+
+/// Lay track, place stations or extend a line with a left click; a right
+/// click undoes the nearest.
+#[allow(clippy::too_many_arguments)]
+fn handle_rail_input(
+    mouse: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
+    config: Res<RailNetworkConfig>,
+    mut network: ResMut<RailNetwork>,
+    mut budget: ResMut<CityBudget>,
+    mut history: ResMut<CommandHistory>,
+    mut state: ResMut<RailToolState>,
+    mut edited: EventWriter<RailNetworkEdited>,
+) {
+    state.hover = None;
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_q.get_single() else {
+        return;
+    };
+    let Some(cursor) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+    else {
+        return;
+    };
+    let snap = config.snap_distance;
+    let node_near = network.nearest_node(cursor, snap);
+    let track_near = network.nearest_track(cursor, snap);
+    state.hover = match (node_near, track_near) {
+        (Some(node), _) => Some(network.graph[node].position),
+        (None, Some((_, point))) => Some(point),
+        (None, None) => (state.mode == RailMode::Track).then_some(cursor),
+    };
+
+    // Track and station edits are recorded as the graph before and after
+    let before = network.graph.clone();
+
+    if mouse.just_pressed(MouseButton::Right) {
+        match state.mode {
+            RailMode::Track if state.anchor.is_some() => state.anchor = None,
+            RailMode::Track => {
+                if let Some((edge, _)) = track_near {
+                    network.remove_track(edge);
+                    let after = network.graph.clone();
+                    history.push(HistoryEntry::new("remove track", 0, vec![PlayerAction::Rail { before, after }]));
+                    edited.send(RailNetworkEdited);
+                }
+            }
+            RailMode::Station => {
+                if let Some(node) = node_near.filter(|&n| network.graph[n].station.is_some()) {
+                    let name = network.graph[node].station.clone().unwrap_or_default();
+                    info!("Closed {}", name);
+                    network.remove_station(node);
+                    let after = network.graph.clone();
+                    history.push(HistoryEntry::new(
+                        format!("close {}", name),
+                        0,
+                        vec![PlayerAction::Rail { before, after }],
+                    ));
+                    edited.send(RailNetworkEdited);
+                }
+            }
+            RailMode::Line => {
+                let Some(node) = node_near else {
+                    return;
+                };
+                if let Some(line) = state.editing.and_then(|index| network.lines.get_mut(index)) {
+                    if let Some(i) = line.stations.iter().rposition(|&s| s == node) {
+                        line.stations.remove(i);
+                        edited.send(RailNetworkEdited);
+                    }
+                }
+            }
+        }
+        return;
+    }
+
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let Some(point) = state.hover else {
+        return;
+    };
+
+    match state.mode {
+        RailMode::Track => {
+            let Some(anchor) = state.anchor else {
+                state.anchor = Some(point);
+                return;
+            };
+            let cost = (anchor.distance(point) * track_cost(state.level)) as i64;
+            if budget.funds < cost {
+                info!("Cannot afford this track (${} needed, ${} available)", cost, budget.funds);
+                return;
+            }
+            let a = place_node(&mut network, anchor, snap);
+            let b = place_node(&mut network, point, snap);
+            if network.lay_track(a, b, state.level).is_some() {
+                budget.funds -= cost;
+                let after = network.graph.clone();
+                history.push(HistoryEntry::new(
+                    format!("lay {} track", state.level.name().to_lowercase()),
+                    cost,
+                    vec![PlayerAction::Rail { before, after }],
+                ));
+                edited.send(RailNetworkEdited);
+            }
+            // Carry on from here
+            state.anchor = Some(point);
+        }
+        RailMode::Station => {
+            if node_near.is_none() && track_near.is_none() {
+                return;
+            }
+            let node = place_node(&mut network, point, snap);
+            if network.graph[node].station.is_some() {
+                return;
+            }
+            if budget.funds < STATION_COST {
+                info!("Cannot afford a station (${} needed, ${} available)", STATION_COST, budget.funds);
+                return;
+            }
+            budget.funds -= STATION_COST;
+            let number = network.graph.node_weights().filter(|n| n.station.is_some()).count() + 1;
+            let name = format!("Station {}", number);
+            info!("Opened {}", name);
+            network.graph[node].station = Some(name.clone());
+            let after = network.graph.clone();
+            history.push(HistoryEntry::new(
+                format!("open {}", name),
+                STATION_COST,
+                vec![PlayerAction::Rail { before, after }],
+            ));
+            edited.send(RailNetworkEdited);
+        }
+        RailMode::Line => {
+            let Some(node) = node_near.filter(|&n| network.graph[n].station.is_some()) else {
+                return;
+            };
+            let editing = match state.editing.filter(|&index| network.lines.get(index).is_some()) {
+                Some(index) => index,
+                None => {
+                    let number = network.lines.len() + 1;
+                    network.lines.push(MetroLine {
+                        name: format!("Metro {}", number),
+                        color: LINE_COLORS[(number - 1) % LINE_COLORS.len()],
+                        stations: Vec::new(),
+                        trains: 2,
+                        route: MetroRoute::default(),
+                    });
+                    info!("Started metro line {}", number);
+                    network.lines.len() - 1
+                }
+            };
+            network.lines[editing].stations.push(node);
+            state.editing = Some(editing);
+            edited.send(RailNetworkEdited);
+        }
+    }
+}
+
+/// The node at a point on or near the track, splitting a segment if the
+/// point lies along it.
+fn place_node(network: &mut RailNetwork, point: Vec2, snap: f32) -> NodeIndex {
+    if let Some(node) = network.nearest_node(point, snap) {
+        return node;
+    }
+    if let Some(node) = network.nearest_track(point, snap).and_then(|(edge, on_track)| network.split_track(edge, on_track)) {
+        return node;
+    }
+    network.node_at(point, snap)
+}
+
+/// Keys: M cycles the mode. In track mode Tab cycles the level. In line
+/// mode Enter finishes the line, Tab picks the next line, `,` / `.` change
+/// its trains, L cycles its colour and Delete removes it.
+fn edit_rail_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut network: ResMut<RailNetwork>,
+    mut state: ResMut<RailToolState>,
+    mut edited: EventWriter<RailNetworkEdited>,
+) {
+    if keys.just_pressed(KeyCode::KeyM) {
+        state.mode = match state.mode {
+            RailMode::Track => RailMode::Station,
+            RailMode::Station => RailMode::Line,
+            RailMode::Line => RailMode::Track,
+        };
+        state.anchor = None;
+    }
+
+    if state.mode == RailMode::Track {
+        if keys.just_pressed(KeyCode::Tab) {
+            let current = TrackLevel::ALL.iter().position(|&l| l == state.level).unwrap_or(0);
+            state.level = TrackLevel::ALL[(current + 1) % TrackLevel::ALL.len()];
+        }
+        return;
+    }
+    if state.mode != RailMode::Line {
+        return;
+    }
+
+    if keys.just_pressed(KeyCode::Enter) {
+        state.editing = None;
+    }
+    if keys.just_pressed(KeyCode::Tab) {
+        let lines: Vec<usize> = (0..network.lines.len()).filter(|&i| !network.lines[i].stations.is_empty()).collect();
+        state.editing = match state.editing.and_then(|current| lines.iter().position(|&i| i == current)) {
+            Some(position) => lines.get(position + 1).copied(),
+            None => lines.first().copied(),
+        };
+    }
+
+    let Some(line) = state.editing.and_then(|index| network.lines.get_mut(index)) else {
+        return;
+    };
+    if keys.just_pressed(KeyCode::Period) {
+        line.trains = (line.trains + 1).min(MAX_LINE_TRAINS);
+    }
+    if keys.just_pressed(KeyCode::Comma) {
+        line.trains = line.trains.saturating_sub(1).max(1);
+    }
+    if keys.just_pressed(KeyCode::KeyL) {
+        let current = LINE_COLORS.iter().position(|&c| c == line.color);
+        line.color = LINE_COLORS[current.map_or(0, |i| (i + 1) % LINE_COLORS.len())];
+    }
+    if keys.just_pressed(KeyCode::Delete) {
+        info!("Removed metro line {}", line.name);
+        line.stations.clear();
+        state.editing = None;
+    }
+
+    if keys.any_just_pressed([KeyCode::Comma, KeyCode::Period, KeyCode::KeyL, KeyCode::Delete]) {
+        edited.send(RailNetworkEdited);
+    }
+}
+
+/// Describe the tool's mode and the metro lines.
+fn rail_report(network: &RailNetwork, state: &RailToolState, ridership: &Ridership) -> String {
+    let mut lines = vec![match state.mode {
+        RailMode::Track => format!("TRACK ({})", state.level.name()),
+        RailMode::Station => "STATIONS".to_string(),
+        RailMode::Line => "METRO LINES".to_string(),
+    }];
+
+    let track: f32 = network.graph.edge_weights().map(|t| t.length).sum();
+    let stations = network.graph.node_weights().filter(|n| n.station.is_some()).count();
+    lines.push(format!(" {:.1} km of track, {} stations", track / 1000.0, stations));
+
+    for (index, line) in network.lines.iter().enumerate().filter(|(_, line)| !line.stations.is_empty()) {
+        let status = if line.is_open() {
+            format!("{} trains  {:.1} km", line.trains, line.route.length() / 1000.0)
+        } else {
+            "not connected".to_string()
+        };
+        let riders = ridership.lines.get(&LineKey::Metro(index)).map_or(0, |r| r.riders);
+        lines.push(format!(
+            "{}{}: {} stations  {}  {} riders",
+            if state.editing == Some(index) { ">" } else { " " },
+            line.name,
+            line.stations.len(),
+            status,
+            riders
+        ));
+    }
+
+    lines.push(String::new());
+    lines.push("M: mode".to_string());
+    match state.mode {
+        RailMode::Track => {
+            lines.push(format!("Click: lay track (${:.0}/m)  Right click: stop / remove", track_cost(state.level)));
+            lines.push("Tab: elevated / at grade / underground".to_string());
+        }
+        RailMode::Station => {
+            lines.push(format!("Click track: add station (${})  Right click: close", STATION_COST));
+        }
+        RailMode::Line => {
+            lines.push("Click: add station  Right click: drop station".to_string());
+            lines.push("Enter: finish  Tab: next line  ,/.: trains -/+".to_string());
+            lines.push("L: colour  Del: remove line".to_string());
+        }
+    }
+    lines.join("\n")
+}
+
+/// Show and refresh the rail panel.
+fn update_rail_panel(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    state: Res<RailToolState>,
+    network: Res<RailNetwork>,
+    ridership: Res<Ridership>,
+    mut panel_q: Query<&mut Text, With<RailPanel>>,
+) {
+    let report = rail_report(&network, &state, &ridership);
+    if let Ok(mut text) = panel_q.get_single_mut() {
+        text.0 = report;
+        return;
+    }
+    commands.spawn((
+        Text::new(report),
+        TextFont {
+            font: asset_server.load("fonts/ShareTechMono-Regular.ttf"),
+            font_size: 13.0,
+            ..default()
+        },
+        TextColor(TEXT_COLOR),
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Px(10.0),
+            bottom: Val::Px(40.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            border: UiRect::all(Val::Px(1.0)),
+            ..default()
+        },
+        BackgroundColor(PANEL_BG),
+        BorderColor(BORDER),
+        RailPanel,
+    ));
+}
+
+/// Colour track is drawn in at each level.
+fn level_color(level: TrackLevel) -> Color {
+    match level {
+        TrackLevel::Elevated => Color::srgb(1.0, 0.6, 0.2),
+        TrackLevel::AtGrade => Color::srgb(0.85, 0.85, 0.85),
+        TrackLevel::Underground => Color::srgb(0.3, 0.5, 1.0),
+    }
+}
+
+/// Draw the track by level, the stations, the lines and the track being laid.
+fn draw_rail_network(
+    mut gizmos: Gizmos,
+    state: Res<RailToolState>,
+    network: Res<RailNetwork>,
+    terrain: Res<HeightMap>,
+) {
+    let lift = |p: Vec2, h: f32| Vec3::new(p.x, terrain.sample_world(p) + h, p.y);
+    let flat = Quat::from_rotation_x(std::f32::consts::FRAC_PI_2);
+    let graph = &network.graph;
+
+    for edge in graph.edge_indices() {
+        let Some((a, b)) = graph.edge_endpoints(edge) else {
+            continue;
+        };
+        let color = level_color(graph[edge].level);
+        gizmos.line(lift(graph[a].position, 1.5), lift(graph[b].position, 1.5), color);
+    }
+    for node in graph.node_weights().filter(|n| n.station.is_some()) {
+        gizmos.circle(Isometry3d::new(lift(node.position, 1.5), flat), 8.0, Color::WHITE);
+    }
+
+    for (index, line) in network.lines.iter().enumerate() {
+        let color = if state.editing == Some(index) { line.color } else { line.color.with_alpha(0.6) };
+        gizmos.linestrip(line.route.nodes.iter().map(|&n| lift(graph[n].position, 2.5)), color);
+    }
+
+    if let Some(hover) = state.hover {
+        gizmos.circle(Isometry3d::new(lift(hover, 1.5), flat), 4.0, Color::WHITE);
+        if let Some(anchor) = state.anchor.filter(|_| state.mode == RailMode::Track) {
+            gizmos.line(lift(anchor, 1.5), lift(hover, 1.5), level_color(state.level));
+        }
+    }
+}
+
+/// Stop laying track and hide the panel when switching to another tool.
+fn cleanup_on_tool_change(
+    mut commands: Commands,
+    tool: Res<State<ActiveTool>>,
+    mut state: ResMut<RailToolState>,
+    panel_q: Query<Entity, With<RailPanel>>,
+) {
+    if !tool.is_changed() || *tool.get() == ActiveTool::Rail {
+        return;
+    }
+    for entity in &panel_q {
+        commands.entity(entity).despawn_recursive();
+    }
+    state.anchor = None;
+    state.editing = None;
+}
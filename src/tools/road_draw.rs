@@ -1,16 +1,37 @@
 //! Road drawing tool - click to place road nodes and edges.
 //!
-//! Supports both straight and curved (bezier) road drawing.
-//! Includes undo/redo functionality (Ctrl+Z / Ctrl+Y).
-
+//! Supports straight and curved (bezier) roads, roads parallel to an
+//! existing one, whole street grids dragged out as a rectangle, and
+//! roundabout and interchange templates (cycle with B). Endpoints can snap to the world grid or to 15° steps from the
+//! connected roads (cycle with M); the length and angle being drawn are
+//! shown next to the cursor.
+//! Roads that cross the river become bridges; roads too steep to grade can
+//! be bored as tunnels (toggle with N).
+//! Each placed segment is one undo step in the shared command history.
+
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
-use petgraph::graph::NodeIndex;
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
 use smallvec::SmallVec;
 
+use super::history::{terrain_changes, CommandHistory, HistoryEntry, PlayerAction};
+use super::road_snap::{
+    angle_to_references, connected_directions, parallel_points, snap_to_grid, snap_to_references, street_grid_lines,
+    street_grid_segments, RoadSnap,
+};
+use super::road_templates::handle_template_input;
 use super::ActiveTool;
 use crate::game_state::GameState;
-use crate::procgen::roads::{RoadEdge, RoadGraph, RoadNodeType, RoadType};
+use crate::procgen::grading::{apply_road_grade, max_grade, plan_road_grade, GradeError, GradeProfile, GradingConfig};
+use crate::procgen::intersections::carriageway_width;
+use crate::procgen::river::{River, WaterCrossing};
+use crate::procgen::roads::{
+    polyline_length, slice_polyline, RoadEdge, RoadGraph, RoadNodeType, RoadRemoval, RoadType, RoadsRemoved,
+};
+use crate::simulation::economy::CityBudget;
+use crate::world::terrain::HeightMap;
 
 pub struct RoadDrawPlugin;
 
@@ -18,15 +39,20 @@ impl Plugin for RoadDrawPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<RoadDrawState>()
             .init_resource::<RoadDrawConfig>()
-            .init_resource::<RoadHistory>()
             .add_event::<RoadMeshDirty>()
             .add_systems(
                 Update,
                 (
                     handle_draw_mode_toggle,
-                    handle_undo_redo,
+                    handle_tunnel_toggle,
+                    handle_guide_keys,
                     handle_road_draw_input,
+                    handle_parallel_input,
+                    handle_street_grid_input,
+                    handle_template_input,
                     update_road_preview,
+                    update_guide_preview,
+                    update_measure_readout,
                     cleanup_on_tool_change,
                 )
                     .chain()
@@ -47,6 +73,49 @@ pub enum RoadDrawMode {
     Straight,
     /// Quadratic bezier curve (drag to define curve).
     Curved,
+    /// Copy of the road under the cursor, offset to the cursor's side.
+    Parallel,
+    /// Rectangular grid of streets filling a dragged rectangle.
+    StreetGrid,
+    /// Roundabout at the cursor, or replacing the junction under it.
+    Roundabout,
+    /// Highway diamond interchange with a crossroad passing over.
+    DiamondInterchange,
+    /// Highway cloverleaf interchange with a crossroad passing over.
+    Cloverleaf,
+}
+
+impl RoadDrawMode {
+    /// The next mode in the toggle cycle.
+    pub fn next(self) -> Self {
+        match self {
+            RoadDrawMode::Straight => RoadDrawMode::Curved,
+            RoadDrawMode::Curved => RoadDrawMode::Parallel,
+            RoadDrawMode::Parallel => RoadDrawMode::StreetGrid,
+            RoadDrawMode::StreetGrid => RoadDrawMode::Roundabout,
+            RoadDrawMode::Roundabout => RoadDrawMode::DiamondInterchange,
+            RoadDrawMode::DiamondInterchange => RoadDrawMode::Cloverleaf,
+            RoadDrawMode::Cloverleaf => RoadDrawMode::Straight,
+        }
+    }
+
+    /// Display name.
+    pub fn name(self) -> &'static str {
+        match self {
+            RoadDrawMode::Straight => "Straight",
+            RoadDrawMode::Curved => "Curved (Bezier)",
+            RoadDrawMode::Parallel => "Parallel",
+            RoadDrawMode::StreetGrid => "Street grid",
+            RoadDrawMode::Roundabout => "Roundabout",
+            RoadDrawMode::DiamondInterchange => "Diamond interchange",
+            RoadDrawMode::Cloverleaf => "Cloverleaf interchange",
+        }
+    }
+
+    /// Whether the mode chains segments from the last placed node.
+    pub(super) fn chains(self) -> bool {
+        matches!(self, RoadDrawMode::Straight | RoadDrawMode::Curved)
+    }
 }
 
 /// Configuration for road drawing.
@@ -58,10 +127,37 @@ pub struct RoadDrawConfig {
     pub road_type: RoadType,
     /// Preview node size.
     pub preview_size: f32,
-    /// Current draw mode (straight or curved).
+    /// Current draw mode.
     pub draw_mode: RoadDrawMode,
+    /// How endpoints snap while drawing.
+    pub snap: RoadSnap,
+    /// Spacing of the world grid used by grid snapping.
+    pub grid_size: f32,
+    /// Angle step for angle snapping, in degrees.
+    pub angle_step: f32,
+    /// Distance between a parallel road and the road it copies.
+    /// Kept above `snap_distance` so the copy does not snap onto the original.
+    pub parallel_offset: f32,
+    /// Preferred block size for street grids.
+    pub block_size: f32,
+    /// Radius of the ring placed in roundabout mode.
+    pub roundabout_radius: f32,
     /// Number of segments in a bezier curve.
     pub curve_segments: usize,
+    /// Construction cost per metre of minor road; other classes scale by width.
+    pub cost_per_metre: f32,
+    /// Cost multiplier for the stretch of road that bridges water.
+    pub bridge_cost_multiplier: f32,
+    /// Longest water crossing a single bridge may span.
+    pub max_bridge_span: f32,
+    /// Build segments too steep to grade as tunnels instead.
+    pub allow_tunnels: bool,
+    /// Cost multiplier for tunnelled road.
+    pub tunnel_cost_multiplier: f32,
+    /// Longest tunnel that can be bored.
+    pub max_tunnel_length: f32,
+    /// Minimum ground above the roadway for a tunnel to be worth boring.
+    pub min_tunnel_cover: f32,
 }
 
 impl Default for RoadDrawConfig {
@@ -71,7 +167,20 @@ impl Default for RoadDrawConfig {
             road_type: RoadType::Minor,
             preview_size: 3.0,
             draw_mode: RoadDrawMode::Straight,
+            snap: RoadSnap::Off,
+            grid_size: 10.0,
+            angle_step: 15.0,
+            parallel_offset: 30.0,
+            block_size: 60.0,
+            roundabout_radius: 18.0,
             curve_segments: 8,
+            cost_per_metre: 10.0,
+            bridge_cost_multiplier: 6.0,
+            max_bridge_span: 120.0,
+            allow_tunnels: false,
+            tunnel_cost_multiplier: 10.0,
+            max_tunnel_length: 250.0,
+            min_tunnel_cover: 4.0,
         }
     }
 }
@@ -83,6 +192,10 @@ pub struct RoadDrawState {
     pub last_node: Option<petgraph::graph::NodeIndex>,
     /// Current hover position.
     pub hover_pos: Option<Vec2>,
+    /// Where a node would go: the hover position after node, grid or angle snapping.
+    pub target_pos: Option<Vec2>,
+    /// Cursor position on screen, for the readout.
+    pub cursor_screen: Option<Vec2>,
     /// Whether we're snapping to an existing node.
     pub snapping_to: Option<petgraph::graph::NodeIndex>,
     /// For curved mode: start position when dragging.
@@ -91,104 +204,168 @@ pub struct RoadDrawState {
     pub is_dragging: bool,
     /// For curved mode: the control point offset perpendicular to the line.
     pub curve_offset: f32,
+    /// How the pending straight segment would be built, if a node is placed.
+    pub pending: Option<Result<SegmentPlan, SegmentError>>,
+    /// Corner where the street grid drag started.
+    pub grid_start: Option<Vec2>,
+    /// Segments previewed by the parallel and street grid modes.
+    pub guide: Vec<(Vec2, Vec2)>,
+    /// Whether every guide segment can be built.
+    pub guide_buildable: bool,
+    /// Length and angle text shown next to the cursor.
+    pub readout: Option<String>,
+}
+
+/// How a road segment will be built.
+#[derive(Clone, Debug)]
+pub enum SegmentPlan {
+    /// Ordinary road graded into the terrain.
+    Road(GradeProfile),
+    /// Bridge across water, with graded land sections either side.
+    Bridge {
+        crossing: WaterCrossing,
+        approach: Option<(Vec<Vec2>, GradeProfile)>,
+        departure: Option<(Vec<Vec2>, GradeProfile)>,
+    },
+    /// Tunnel bored straight through the terrain.
+    Tunnel,
+}
+
+/// Why a road segment cannot be built.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SegmentError {
+    /// The road (or a bridge approach) cannot meet its grade.
+    Grade(GradeError),
+    /// A tunnel was allowed but could not be bored.
+    Tunnel(TunnelError),
+    /// The segment starts or ends in the water.
+    EndsInWater,
+    /// The crossing reaches out to sea.
+    OpenSea,
+    /// The water is wider than a bridge may span.
+    SpanTooLong { span: f32, max: f32 },
+}
+
+/// Why a tunnel cannot be bored.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TunnelError {
+    /// Longer than the maximum tunnel length.
+    TooLong { length: f32, max: f32 },
+    /// The portals are further apart vertically than the grade allows.
+    TooSteep { required: f32, max: f32 },
+    /// Not enough ground above the roadway - grade the road instead.
+    Shallow { cover: f32, min: f32 },
 }
 
 /// An undoable road action.
+///
+/// Nodes are identified by position rather than index, since removing roads
+/// renumbers the graph.
 #[derive(Clone, Debug)]
 pub enum RoadAction {
-    /// A node was added (stores position, type, and the assigned index).
-    AddNode {
-        position: Vec2,
-        node_type: RoadNodeType,
-        node_index: NodeIndex,
+    /// Nodes and edges added in one step (a segment, or a bridge with its approaches).
+    AddSegments {
+        /// Nodes created by the step.
+        nodes: Vec<(Vec2, RoadNodeType)>,
+        /// Edges created by the step, in drawing order, by end positions.
+        edges: Vec<(Vec2, Vec2, RoadEdge)>,
     },
-    /// An edge was added between two nodes.
-    AddEdge {
-        from: NodeIndex,
-        to: NodeIndex,
-        edge: RoadEdge,
+    /// Edges removed, along with the listed nodes they left unconnected.
+    RemoveSegments {
+        nodes: Vec<(Vec2, RoadNodeType)>,
+        edges: Vec<(Vec2, Vec2, RoadEdge)>,
     },
-    /// A compound action: node + edge added together.
-    AddNodeAndEdge {
-        node_position: Vec2,
-        node_type: RoadNodeType,
-        node_index: NodeIndex,
-        from: NodeIndex,
-        edge: RoadEdge,
+    /// Existing edges changed in place (class, direction, median, bike lanes).
+    ModifyEdges {
+        /// End positions with the edge before and after the change.
+        edges: Vec<(Vec2, Vec2, RoadEdge, RoadEdge)>,
     },
 }
 
-/// History of road actions for undo/redo.
-#[derive(Resource)]
-pub struct RoadHistory {
-    /// Stack of actions that can be undone.
-    undo_stack: Vec<RoadAction>,
-    /// Stack of actions that can be redone.
-    redo_stack: Vec<RoadAction>,
-    /// Maximum history size.
-    max_size: usize,
-}
-
-impl RoadHistory {
-    /// Push a new action to the history. Clears redo stack.
-    pub fn push(&mut self, action: RoadAction) {
-        self.undo_stack.push(action);
-        self.redo_stack.clear();
-
-        // Limit history size
-        if self.max_size > 0 && self.undo_stack.len() > self.max_size {
-            self.undo_stack.remove(0);
+impl RoadAction {
+    /// The action that reverses this one.
+    pub fn inverse(&self) -> RoadAction {
+        match self {
+            RoadAction::AddSegments { nodes, edges } => RoadAction::RemoveSegments {
+                nodes: nodes.clone(),
+                edges: edges.clone(),
+            },
+            RoadAction::RemoveSegments { nodes, edges } => RoadAction::AddSegments {
+                nodes: nodes.clone(),
+                edges: edges.clone(),
+            },
+            RoadAction::ModifyEdges { edges } => RoadAction::ModifyEdges {
+                edges: edges
+                    .iter()
+                    .map(|(from, to, before, after)| (*from, *to, after.clone(), before.clone()))
+                    .collect(),
+            },
         }
     }
 
-    /// Push to undo stack without clearing redo (for redo operations).
-    fn push_undo_only(&mut self, action: RoadAction) {
-        self.undo_stack.push(action);
-
-        // Limit history size
-        if self.max_size > 0 && self.undo_stack.len() > self.max_size {
-            self.undo_stack.remove(0);
+    /// Make the change to the road graph, keeping the road tool's last node valid.
+    pub fn apply(&self, world: &mut World) {
+        let drawing = matches!(world.resource::<State<ActiveTool>>().get(), ActiveTool::RoadDraw);
+        let mut last_node = world.resource::<RoadDrawState>().last_node;
+        let mut road_graph = world.resource_mut::<RoadGraph>();
+        let mut removed = None;
+
+        match self {
+            RoadAction::AddSegments { nodes, edges } => {
+                for (position, node_type) in nodes {
+                    road_graph.add_node(*position, *node_type);
+                }
+                for (from, to, edge) in edges {
+                    if let (Some(a), Some(b)) = (road_graph.node_at(*from), road_graph.node_at(*to)) {
+                        road_graph.add_edge_data(a, b, edge.clone());
+                    }
+                }
+                // Carry on drawing from the end of the restored road
+                let end = edges.last().map(|(_, to, _)| *to).or(nodes.last().map(|(position, _)| *position));
+                last_node = end.and_then(|end| road_graph.node_at(end));
+            }
+            RoadAction::RemoveSegments { nodes, edges } => {
+                let edge_indices: Vec<EdgeIndex> = edges
+                    .iter()
+                    .filter_map(|(from, to, edge)| find_edge_at(&road_graph, *from, *to, edge))
+                    .collect();
+                let node_indices: Vec<NodeIndex> = nodes
+                    .iter()
+                    .filter_map(|(position, _)| road_graph.node_at(*position))
+                    .collect();
+                let removal = road_graph.remove_edges_and_nodes(&edge_indices, Some(&node_indices));
+                // Step back to where the removed road started
+                last_node = last_node
+                    .and_then(|node| removal.remap.node(node))
+                    .or_else(|| edges.first().and_then(|(from, _, _)| road_graph.node_at(*from)));
+                removed = Some(removal);
+            }
+            RoadAction::ModifyEdges { edges } => {
+                for (from, to, before, after) in edges {
+                    let found = find_edge_at(&road_graph, *from, *to, before);
+                    if let Some(edge) = found.and_then(|idx| road_graph.edge_mut(idx)) {
+                        *edge = after.clone();
+                    }
+                }
+            }
         }
-    }
-
-    /// Pop an action for undo. Returns None if nothing to undo.
-    pub fn pop_undo(&mut self) -> Option<RoadAction> {
-        self.undo_stack.pop()
-    }
-
-    /// Push an action to redo stack.
-    pub fn push_redo(&mut self, action: RoadAction) {
-        self.redo_stack.push(action);
-    }
-
-    /// Pop an action for redo. Returns None if nothing to redo.
-    pub fn pop_redo(&mut self) -> Option<RoadAction> {
-        self.redo_stack.pop()
-    }
-
-    /// Check if undo is available.
-    pub fn can_undo(&self) -> bool {
-        !self.undo_stack.is_empty()
-    }
 
-    /// Check if redo is available.
-    pub fn can_redo(&self) -> bool {
-        !self.redo_stack.is_empty()
-    }
-
-    /// Clear all history.
-    pub fn clear(&mut self) {
-        self.undo_stack.clear();
-        self.redo_stack.clear();
+        if drawing {
+            world.resource_mut::<RoadDrawState>().last_node = last_node;
+        }
+        if let Some(removal) = removed.filter(|removal| !removal.edges.is_empty() || !removal.nodes.is_empty()) {
+            world.send_event(RoadsRemoved(removal));
+        }
+        world.send_event(RoadMeshDirty);
     }
-}
 
-impl Default for RoadHistory {
-    fn default() -> Self {
-        Self {
-            undo_stack: Vec::new(),
-            redo_stack: Vec::new(),
-            max_size: 100, // Keep last 100 actions
+    /// Short description for the history log.
+    pub fn describe(&self) -> String {
+        match self {
+            RoadAction::AddSegments { edges, .. } if edges.is_empty() => "place road node".to_string(),
+            RoadAction::AddSegments { edges, .. } => format!("build {} road segments", edges.len()),
+            RoadAction::RemoveSegments { edges, .. } => format!("demolish {} road segments", edges.len()),
+            RoadAction::ModifyEdges { edges } => format!("change {} roads", edges.len()),
         }
     }
 }
@@ -206,126 +383,412 @@ struct NodePreview;
 struct EdgePreview;
 
 /// Run condition: check if road draw tool is active.
-fn is_road_draw_active(tool: &State<ActiveTool>) -> bool {
+pub(super) fn is_road_draw_active(tool: &State<ActiveTool>) -> bool {
     matches!(tool.get(), ActiveTool::RoadDraw)
 }
 
-/// Toggle draw mode with 'B' key.
+/// Cycle draw mode with 'B' key.
 fn handle_draw_mode_toggle(
     keyboard: Res<ButtonInput<KeyCode>>,
     tool: Res<State<ActiveTool>>,
     mut config: ResMut<RoadDrawConfig>,
+    mut state: ResMut<RoadDrawState>,
 ) {
     if !is_road_draw_active(&tool) {
         return;
     }
 
     if keyboard.just_pressed(KeyCode::KeyB) {
-        config.draw_mode = match config.draw_mode {
-            RoadDrawMode::Straight => {
-                info!("Road draw mode: Curved (Bezier)");
-                RoadDrawMode::Curved
-            }
-            RoadDrawMode::Curved => {
-                info!("Road draw mode: Straight");
-                RoadDrawMode::Straight
-            }
-        };
+        config.draw_mode = config.draw_mode.next();
+        info!("Road draw mode: {}", config.draw_mode.name());
+
+        // Parallel and grid roads start fresh rather than from the last node
+        if !config.draw_mode.chains() {
+            state.last_node = None;
+            state.is_dragging = false;
+            state.curve_start = None;
+            state.curve_offset = 0.0;
+        }
+        state.grid_start = None;
+        state.guide.clear();
     }
 }
 
-/// Handle undo (Ctrl+Z) and redo (Ctrl+Y or Ctrl+Shift+Z).
-fn handle_undo_redo(
+/// Cycle snapping with 'M'; ',' and '.' change the parallel offset, block
+/// size or roundabout radius.
+fn handle_guide_keys(
     keyboard: Res<ButtonInput<KeyCode>>,
-    mut road_graph: ResMut<RoadGraph>,
-    mut history: ResMut<RoadHistory>,
-    mut state: ResMut<RoadDrawState>,
-    mut dirty_events: EventWriter<RoadMeshDirty>,
+    tool: Res<State<ActiveTool>>,
+    mut config: ResMut<RoadDrawConfig>,
 ) {
-    let ctrl_pressed = keyboard.pressed(KeyCode::ControlLeft) || keyboard.pressed(KeyCode::ControlRight);
-    let shift_pressed = keyboard.pressed(KeyCode::ShiftLeft) || keyboard.pressed(KeyCode::ShiftRight);
-
-    // Undo: Ctrl+Z
-    if ctrl_pressed && keyboard.just_pressed(KeyCode::KeyZ) && !shift_pressed {
-        if let Some(action) = history.pop_undo() {
-            // Apply reverse of the action
-            match &action {
-                RoadAction::AddNode { node_index, .. } => {
-                    // Remove the node (this also removes connected edges)
-                    road_graph.remove_node(*node_index);
-                    // Clear last_node if it was this node
-                    if state.last_node == Some(*node_index) {
-                        state.last_node = None;
-                    }
-                    info!("Undo: removed node");
-                }
-                RoadAction::AddEdge { from, to, .. } => {
-                    // Find and remove the edge between these nodes
-                    if let Some(edge_idx) = road_graph.find_edge(*from, *to) {
-                        road_graph.remove_edge(edge_idx);
-                        info!("Undo: removed edge");
-                    }
-                }
-                RoadAction::AddNodeAndEdge { node_index, from, .. } => {
-                    // First remove the edge, then the node
-                    if let Some(edge_idx) = road_graph.find_edge(*from, *node_index) {
-                        road_graph.remove_edge(edge_idx);
-                    }
-                    road_graph.remove_node(*node_index);
-                    // Clear last_node if it was this node
-                    if state.last_node == Some(*node_index) {
-                        state.last_node = Some(*from); // Go back to previous node
-                    }
-                    info!("Undo: removed node and edge");
-                }
+    if !is_road_draw_active(&tool) {
+        return;
+    }
+
+    if keyboard.just_pressed(KeyCode::KeyM) {
+        config.snap = config.snap.next();
+        match config.snap {
+            RoadSnap::Off => info!("Road snapping off"),
+            RoadSnap::Grid => info!("Road snapping: {:.0}m grid", config.grid_size),
+            RoadSnap::Angle => info!("Road snapping: {:.0}° steps from connected roads", config.angle_step),
+        }
+    }
+
+    let step = match (keyboard.just_pressed(KeyCode::Comma), keyboard.just_pressed(KeyCode::Period)) {
+        (true, false) => -1.0,
+        (false, true) => 1.0,
+        _ => return,
+    };
+    match config.draw_mode {
+        RoadDrawMode::Parallel => {
+            let min = config.snap_distance + 5.0;
+            config.parallel_offset = (config.parallel_offset + step * 5.0).clamp(min, 200.0);
+            info!("Parallel offset: {:.0}m", config.parallel_offset);
+        }
+        RoadDrawMode::StreetGrid => {
+            config.block_size = (config.block_size + step * 10.0).clamp(30.0, 300.0);
+            info!("Street grid block size: {:.0}m", config.block_size);
+        }
+        RoadDrawMode::Roundabout => {
+            config.roundabout_radius = (config.roundabout_radius + step * 2.0).clamp(10.0, 40.0);
+            info!("Roundabout radius: {:.0}m", config.roundabout_radius);
+        }
+        _ => {}
+    }
+}
+
+/// Toggle tunnelling of over-steep segments with 'N' key.
+fn handle_tunnel_toggle(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    tool: Res<State<ActiveTool>>,
+    mut config: ResMut<RoadDrawConfig>,
+) {
+    if !is_road_draw_active(&tool) {
+        return;
+    }
+
+    if keyboard.just_pressed(KeyCode::KeyN) {
+        config.allow_tunnels = !config.allow_tunnels;
+        if config.allow_tunnels {
+            info!("Tunnels enabled: segments too steep to grade will be bored through");
+        } else {
+            info!("Tunnels disabled");
+        }
+    }
+}
+
+/// Undo record for a removal made with [`RoadGraph::remove_edges`].
+pub fn removal_action(removal: &RoadRemoval) -> RoadAction {
+    RoadAction::RemoveSegments {
+        nodes: removal.nodes.iter().map(|node| (node.position, node.node_type)).collect(),
+        edges: removal
+            .edges
+            .iter()
+            .map(|removed| (removed.from, removed.to, removed.edge.clone()))
+            .collect(),
+    }
+}
+
+/// Edge equal to `edge` between the nodes standing at `from` and `to`.
+fn find_edge_at(road_graph: &RoadGraph, from: Vec2, to: Vec2, edge: &RoadEdge) -> Option<EdgeIndex> {
+    let (a, b) = (road_graph.node_at(from)?, road_graph.node_at(to)?);
+    road_graph
+        .graph
+        .edges_connecting(a, b)
+        .find(|edge_ref| edge_ref.weight() == edge)
+        .map(|edge_ref| edge_ref.id())
+}
+
+/// Log why a road segment could not be built.
+fn report_segment_error(err: SegmentError, config: &RoadDrawConfig) {
+    let road_type = config.road_type;
+    match err {
+        SegmentError::Grade(GradeError::TooSteep { required, max }) => warn!(
+            "{:?} road too steep: needs {:.0}% grade, limit is {:.0}%{}",
+            road_type,
+            required * 100.0,
+            max * 100.0,
+            tunnel_hint(config)
+        ),
+        SegmentError::Grade(GradeError::ExcessiveCut { depth, max }) => warn!(
+            "{:?} road needs {:.1}m of cut/fill, limit is {:.1}m{}",
+            road_type,
+            depth,
+            max,
+            tunnel_hint(config)
+        ),
+        SegmentError::Grade(GradeError::TooShort) => warn!("{:?} road is too short to grade", road_type),
+        SegmentError::Tunnel(TunnelError::TooLong { length, max }) => warn!(
+            "Tunnel would be {:.0}m long, limit is {:.0}m",
+            length, max
+        ),
+        SegmentError::Tunnel(TunnelError::TooSteep { required, max }) => warn!(
+            "{:?} tunnel too steep: needs {:.0}% grade, limit is {:.0}%",
+            road_type,
+            required * 100.0,
+            max * 100.0
+        ),
+        SegmentError::Tunnel(TunnelError::Shallow { cover, min }) => warn!(
+            "Only {:.1}m of ground above the tunnel, need {:.1}m",
+            cover, min
+        ),
+        SegmentError::EndsInWater => warn!("Roads cannot start or end in water"),
+        SegmentError::OpenSea => warn!("Bridges cannot be built out to sea"),
+        SegmentError::SpanTooLong { span, max } => warn!(
+            "Bridge would span {:.0}m of water, limit is {:.0}m",
+            span, max
+        ),
+    }
+}
+
+/// Suggest tunnelling when it is switched off.
+fn tunnel_hint(config: &RoadDrawConfig) -> &'static str {
+    if config.allow_tunnels {
+        ""
+    } else {
+        " (press N to allow tunnels)"
+    }
+}
+
+/// A segment waiting to be built: start node, points, and plan.
+type PendingSegment = (NodeIndex, SmallVec<[Vec2; 8]>, Result<SegmentPlan, SegmentError>);
+
+/// Shortest land section worth grading separately from a bridge.
+const MIN_APPROACH_LENGTH: f32 = 1.0;
+
+/// Work out how a road along `points` would be built.
+pub fn plan_segment(
+    points: &[Vec2],
+    config: &RoadDrawConfig,
+    heights: &HeightMap,
+    river: &River,
+    grading: &GradingConfig,
+) -> Result<SegmentPlan, SegmentError> {
+    let road_type = config.road_type;
+    let (Some(&start), Some(&end)) = (points.first(), points.last()) else {
+        return Err(SegmentError::EndsInWater);
+    };
+    if river.contains_point(start) || river.contains_point(end) {
+        return Err(SegmentError::EndsInWater);
+    }
+
+    if let Some(crossing) = river.crossing_span(points) {
+        let span = crossing.span();
+        if span > config.max_bridge_span {
+            return Err(SegmentError::SpanTooLong {
+                span,
+                max: config.max_bridge_span,
+            });
+        }
+        let samples = (span / 5.0).ceil().max(1.0) as usize;
+        if (0..=samples).any(|i| river.in_sea(crossing.entry.lerp(crossing.exit, i as f32 / samples as f32))) {
+            return Err(SegmentError::OpenSea);
+        }
+
+        // Grade the land on either side of the water
+        let grade_section = |from: f32, to: f32| {
+            if to - from < MIN_APPROACH_LENGTH {
+                return Ok(None);
             }
+            let section = slice_polyline(points, from, to).to_vec();
+            plan_road_grade(heights, &section, road_type, grading)
+                .map(|profile| Some((section, profile)))
+                .map_err(SegmentError::Grade)
+        };
+        return Ok(SegmentPlan::Bridge {
+            crossing,
+            approach: grade_section(0.0, crossing.entry_distance)?,
+            departure: grade_section(crossing.exit_distance, polyline_length(points))?,
+        });
+    }
 
-            // Save for redo
-            history.push_redo(action);
-            dirty_events.send(RoadMeshDirty);
-        }
-    }
-
-    // Redo: Ctrl+Y or Ctrl+Shift+Z
-    if ctrl_pressed && (keyboard.just_pressed(KeyCode::KeyY) || (shift_pressed && keyboard.just_pressed(KeyCode::KeyZ))) {
-        if let Some(action) = history.pop_redo() {
-            // Re-apply the action and create updated action with new indices
-            let new_action = match &action {
-                RoadAction::AddNode { position, node_type, .. } => {
-                    let new_idx = road_graph.add_node(*position, *node_type);
-                    state.last_node = Some(new_idx);
-                    info!("Redo: added node");
-                    RoadAction::AddNode {
-                        position: *position,
-                        node_type: *node_type,
-                        node_index: new_idx,
-                    }
-                }
-                RoadAction::AddEdge { from, to, edge } => {
-                    road_graph.add_edge_data(*from, *to, edge.clone());
-                    info!("Redo: added edge");
-                    action.clone()
+    match plan_road_grade(heights, points, road_type, grading) {
+        Ok(profile) => Ok(SegmentPlan::Road(profile)),
+        Err(_) if config.allow_tunnels => plan_tunnel(heights, points, road_type, config)
+            .map(|()| SegmentPlan::Tunnel)
+            .map_err(SegmentError::Tunnel),
+        Err(err) => Err(SegmentError::Grade(err)),
+    }
+}
+
+/// Check that a tunnel along `points` is short, gentle, and deep enough.
+///
+/// The roadway runs at a constant grade between the ground at each portal.
+pub fn plan_tunnel(
+    heights: &HeightMap,
+    points: &[Vec2],
+    road_type: RoadType,
+    config: &RoadDrawConfig,
+) -> Result<(), TunnelError> {
+    let length = polyline_length(points);
+    if length > config.max_tunnel_length {
+        return Err(TunnelError::TooLong {
+            length,
+            max: config.max_tunnel_length,
+        });
+    }
+
+    let start = heights.sample_world(points[0]);
+    let end = heights.sample_world(points[points.len() - 1]);
+    let required = (end - start).abs() / length.max(0.001);
+    let max = max_grade(road_type);
+    if required > max {
+        return Err(TunnelError::TooSteep { required, max });
+    }
+
+    // Deepest ground above the roadway
+    let mut cover = 0.0f32;
+    let mut travelled = 0.0;
+    for window in points.windows(2) {
+        let len = window[0].distance(window[1]);
+        let steps = (len / 2.0).ceil().max(1.0) as usize;
+        for i in 0..=steps {
+            let t = i as f32 / steps as f32;
+            let road = start + (end - start) * (travelled + len * t) / length.max(0.001);
+            cover = cover.max(heights.sample_world(window[0].lerp(window[1], t)) - road);
+        }
+        travelled += len;
+    }
+    if cover < config.min_tunnel_cover {
+        return Err(TunnelError::Shallow {
+            cover,
+            min: config.min_tunnel_cover,
+        });
+    }
+
+    Ok(())
+}
+
+/// Construction cost per metre of plain road of a given class.
+pub fn cost_per_metre(road_type: RoadType, config: &RoadDrawConfig) -> f32 {
+    let width_factor = match road_type {
+        RoadType::Highway => 3.0,
+        RoadType::Major => 2.0,
+        RoadType::Minor => 1.0,
+        RoadType::Alley => 0.5,
+    };
+    config.cost_per_metre * width_factor
+}
+
+/// Construction cost of a planned segment.
+pub fn segment_cost(plan: &SegmentPlan, points: &[Vec2], config: &RoadDrawConfig) -> i64 {
+    let rate = cost_per_metre(config.road_type, config);
+    let length = polyline_length(points);
+
+    let cost = match plan {
+        SegmentPlan::Road(_) => length * rate,
+        SegmentPlan::Bridge { crossing, .. } => {
+            let span = crossing.exit_distance - crossing.entry_distance;
+            (length - span) * rate + span * rate * config.bridge_cost_multiplier
+        }
+        SegmentPlan::Tunnel => length * rate * config.tunnel_cost_multiplier,
+    };
+    cost.round() as i64
+}
+
+/// Add a planned segment from `from` to the end of `points`.
+///
+/// Grades the terrain under land sections and returns the node the segment
+/// ended on with the undo record, or None if it would loop back on itself.
+fn place_segment(
+    road_graph: &mut RoadGraph,
+    heights: &mut HeightMap,
+    grading: &GradingConfig,
+    config: &RoadDrawConfig,
+    from: NodeIndex,
+    points: SmallVec<[Vec2; 8]>,
+    plan: SegmentPlan,
+) -> Option<(NodeIndex, RoadAction)> {
+    let road_type = config.road_type;
+    let end = *points.last()?;
+    let snapping = road_graph.find_nearest(end, config.snap_distance).is_some();
+    let end_node = road_graph.snap_or_create(end, config.snap_distance, RoadNodeType::Intersection);
+    if end_node == from {
+        return None;
+    }
+    let end_pos = road_graph.node_by_index(end_node).map_or(end, |n| n.position);
+
+    let edge = match plan {
+        SegmentPlan::Road(profile) => {
+            apply_road_grade(heights, &profile, road_type, grading);
+            RoadEdge::new(points, road_type)
+        }
+        SegmentPlan::Tunnel => RoadEdge::new_tunnel(points, road_type),
+        SegmentPlan::Bridge {
+            crossing,
+            approach,
+            departure,
+        } => {
+            let mut nodes = Vec::new();
+            let mut edges = Vec::new();
+            if !snapping {
+                nodes.push((end_pos, RoadNodeType::Intersection));
+            }
+
+            // Land section up to the near bank
+            let entry_node = match approach {
+                Some((section, profile)) => {
+                    let node = road_graph.add_node(crossing.entry, RoadNodeType::Intersection);
+                    nodes.push((crossing.entry, RoadNodeType::Intersection));
+                    apply_road_grade(heights, &profile, road_type, grading);
+                    edges.push((from, node, RoadEdge::new(SmallVec::from_vec(section), road_type)));
+                    node
                 }
-                RoadAction::AddNodeAndEdge { node_position, node_type, from, edge, .. } => {
-                    let new_idx = road_graph.add_node(*node_position, *node_type);
-                    road_graph.add_edge_data(*from, new_idx, edge.clone());
-                    state.last_node = Some(new_idx);
-                    info!("Redo: added node and edge");
-                    RoadAction::AddNodeAndEdge {
-                        node_position: *node_position,
-                        node_type: *node_type,
-                        node_index: new_idx,
-                        from: *from,
-                        edge: edge.clone(),
-                    }
+                None => from,
+            };
+            let exit_node = match departure {
+                Some(_) => {
+                    let node = road_graph.add_node(crossing.exit, RoadNodeType::Intersection);
+                    nodes.push((crossing.exit, RoadNodeType::Intersection));
+                    node
                 }
+                None => end_node,
             };
 
-            // Push back to undo stack without clearing redo
-            history.push_undo_only(new_action);
-            dirty_events.send(RoadMeshDirty);
+            // The bridge itself, bank to bank
+            let deck_start = road_graph.node_by_index(entry_node).map_or(crossing.entry, |n| n.position);
+            let deck_end = road_graph.node_by_index(exit_node).map_or(crossing.exit, |n| n.position);
+            edges.push((
+                entry_node,
+                exit_node,
+                RoadEdge::new_bridge(
+                    SmallVec::from_slice(&[deck_start, deck_end]),
+                    road_type,
+                    crossing.entry,
+                    crossing.exit,
+                ),
+            ));
+
+            // Land section from the far bank
+            if let Some((section, profile)) = departure {
+                apply_road_grade(heights, &profile, road_type, grading);
+                edges.push((exit_node, end_node, RoadEdge::new(SmallVec::from_vec(section), road_type)));
+            }
+
+            let position = |node: NodeIndex| road_graph.node_by_index(node).map_or(Vec2::ZERO, |n| n.position);
+            let placed = edges
+                .iter()
+                .map(|(a, b, edge)| (position(*a), position(*b), edge.clone()))
+                .collect();
+            for (a, b, edge) in edges {
+                road_graph.add_edge_data(a, b, edge);
+            }
+            return Some((end_node, RoadAction::AddSegments { nodes, edges: placed }));
         }
-    }
+    };
+
+    let from_pos = road_graph.node_by_index(from).map_or(end, |n| n.position);
+    road_graph.add_edge_data(from, end_node, edge.clone());
+    let nodes = if snapping {
+        Vec::new()
+    } else {
+        vec![(end_pos, RoadNodeType::Intersection)]
+    };
+    let action = RoadAction::AddSegments {
+        nodes,
+        edges: vec![(from_pos, end_pos, edge)],
+    };
+    Some((end_node, action))
 }
 
 /// Generate points along a quadratic bezier curve.
@@ -351,21 +814,99 @@ fn calculate_control_point(start: Vec2, end: Vec2, offset: f32) -> Vec2 {
     midpoint + perpendicular * offset
 }
 
+/// Readout for a segment: length, compass bearing, and the angle it makes
+/// with the nearest connected road.
+fn segment_readout(start: Vec2, end: Vec2, length: f32, references: &[Vec2], snap: RoadSnap) -> String {
+    let delta = end - start;
+    let bearing = delta.to_angle().to_degrees().rem_euclid(360.0);
+    let mut text = format!("{:.0} m  {:.0}°", length, bearing);
+    if let Some(turn) = angle_to_references(delta, references) {
+        text.push_str(&format!("  ({:.0}° to road)", turn));
+    }
+    match snap {
+        RoadSnap::Off => {}
+        RoadSnap::Grid => text.push_str("  [grid]"),
+        RoadSnap::Angle => text.push_str("  [angle]"),
+    }
+    text
+}
+
+/// Build a segment that does not continue from an existing node: its start
+/// snaps to a nearby node or gets a new one.
+fn place_standalone(
+    road_graph: &mut RoadGraph,
+    heights: &mut HeightMap,
+    grading: &GradingConfig,
+    config: &RoadDrawConfig,
+    points: SmallVec<[Vec2; 8]>,
+    plan: SegmentPlan,
+) -> Option<RoadAction> {
+    let start = *points.first()?;
+    let snapping = road_graph.find_nearest(start, config.snap_distance).is_some();
+    let from = road_graph.snap_or_create(start, config.snap_distance, RoadNodeType::Intersection);
+    let start_pos = road_graph.node_by_index(from).map_or(start, |n| n.position);
+    let Some((_, mut action)) = place_segment(road_graph, heights, grading, config, from, points, plan) else {
+        if !snapping {
+            road_graph.remove_node(from);
+        }
+        return None;
+    };
+    if let RoadAction::AddSegments { nodes, .. } = &mut action {
+        if !snapping {
+            nodes.insert(0, (start_pos, RoadNodeType::Intersection));
+        }
+    }
+    Some(action)
+}
+
+/// Combine the records of several placed segments into one.
+fn merge_actions(actions: Vec<RoadAction>) -> RoadAction {
+    let (mut all_nodes, mut all_edges) = (Vec::new(), Vec::new());
+    for action in actions {
+        if let RoadAction::AddSegments { nodes, edges } = action {
+            all_nodes.extend(nodes);
+            all_edges.extend(edges);
+        }
+    }
+    RoadAction::AddSegments {
+        nodes: all_nodes,
+        edges: all_edges,
+    }
+}
+
+/// Mouse, keyboard and the camera the cursor is read through.
+#[derive(SystemParam)]
+struct DrawInput<'w, 's> {
+    mouse: Res<'w, ButtonInput<MouseButton>>,
+    keyboard: Res<'w, ButtonInput<KeyCode>>,
+    windows: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    camera_q: Query<'w, 's, (&'static Camera, &'static GlobalTransform)>,
+}
+
+/// What a segment is previewed against, paid from and recorded in.
+#[derive(SystemParam)]
+struct SegmentBuild<'w> {
+    grading: Res<'w, GradingConfig>,
+    river: Res<'w, River>,
+    heights: ResMut<'w, HeightMap>,
+    budget: ResMut<'w, CityBudget>,
+    history: ResMut<'w, CommandHistory>,
+    dirty_events: EventWriter<'w, RoadMeshDirty>,
+}
+
 fn handle_road_draw_input(
-    mouse: Res<ButtonInput<MouseButton>>,
-    keyboard: Res<ButtonInput<KeyCode>>,
-    windows: Query<&Window, With<PrimaryWindow>>,
-    camera_q: Query<(&Camera, &GlobalTransform)>,
+    input: DrawInput,
     tool: Res<State<ActiveTool>>,
     mut road_graph: ResMut<RoadGraph>,
     mut state: ResMut<RoadDrawState>,
-    mut history: ResMut<RoadHistory>,
     config: Res<RoadDrawConfig>,
-    mut dirty_events: EventWriter<RoadMeshDirty>,
+    build: SegmentBuild,
 ) {
     if !is_road_draw_active(&tool) {
         return;
     }
+    let DrawInput { mouse, keyboard, windows, camera_q } = input;
+    let SegmentBuild { grading, river, mut heights, mut budget, mut history, mut dirty_events } = build;
 
     let Ok(window) = windows.get_single() else {
         return;
@@ -375,12 +916,14 @@ fn handle_road_draw_input(
     };
 
     // Get cursor position in world space
-    let Some(cursor_pos) = window.cursor_position() else {
-        state.hover_pos = None;
-        return;
-    };
-    let Ok(world_pos) = camera.viewport_to_world_2d(camera_transform, cursor_pos) else {
+    state.cursor_screen = window.cursor_position();
+    let Some(world_pos) = state
+        .cursor_screen
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+    else {
         state.hover_pos = None;
+        state.target_pos = None;
+        state.readout = None;
         return;
     };
 
@@ -388,86 +931,65 @@ fn handle_road_draw_input(
 
     // Check if we're snapping to an existing node
     state.snapping_to = road_graph.find_nearest(world_pos, config.snap_distance);
-
-    // Get snapped position
-    let actual_pos = if let Some(snap_node) = state.snapping_to {
-        road_graph
-            .node_by_index(snap_node)
-            .map(|n| n.position)
-            .unwrap_or(world_pos)
-    } else {
-        world_pos
+    let prev_pos = state
+        .last_node
+        .and_then(|n| road_graph.node_by_index(n))
+        .map(|n| n.position);
+
+    // Existing nodes win over grid and angle snapping
+    let actual_pos = match (state.snapping_to.and_then(|n| road_graph.node_by_index(n)), config.snap, prev_pos) {
+        (Some(node), _, _) => node.position,
+        (None, RoadSnap::Grid, _) => snap_to_grid(world_pos, config.grid_size),
+        (None, RoadSnap::Angle, Some(prev)) if config.draw_mode.chains() => {
+            let references = state.last_node.map(|n| connected_directions(&road_graph, n)).unwrap_or_default();
+            snap_to_references(prev, world_pos, &references, config.angle_step.to_radians())
+        }
+        _ => world_pos,
     };
+    state.target_pos = Some(actual_pos);
 
-    match config.draw_mode {
-        RoadDrawMode::Straight => {
-            // Straight mode: click to place nodes, auto-connect to previous
-            if mouse.just_pressed(MouseButton::Left) {
-                // Check if we're snapping to existing or creating new
-                let snapping = state.snapping_to.is_some();
-                let node_type = if state.last_node.is_some() {
-                    RoadNodeType::Intersection
-                } else {
-                    RoadNodeType::Endpoint
-                };
+    if !config.draw_mode.chains() {
+        state.pending = None;
+        return;
+    }
 
-                let new_node = road_graph.snap_or_create(world_pos, config.snap_distance, node_type);
+    // Work out how the pending straight segment would be built
+    state.pending = prev_pos.map(|prev| plan_segment(&[prev, actual_pos], &config, &heights, &river, &grading));
+    let references = state.last_node.map(|n| connected_directions(&road_graph, n)).unwrap_or_default();
+    state.readout = prev_pos.map(|prev| segment_readout(prev, actual_pos, prev.distance(actual_pos), &references, config.snap));
 
-                // Connect to previous node if exists
-                if let Some(prev_node) = state.last_node {
-                    if new_node != prev_node {
-                        let prev_pos = road_graph
-                            .node_by_index(prev_node)
-                            .map(|n| n.position)
-                            .unwrap_or(world_pos);
-                        let new_pos = road_graph
-                            .node_by_index(new_node)
-                            .map(|n| n.position)
-                            .unwrap_or(world_pos);
-
-                        let points: SmallVec<[Vec2; 8]> = SmallVec::from_slice(&[prev_pos, new_pos]);
-                        let edge = RoadEdge::new(points.clone(), config.road_type);
-                        road_graph.add_edge(prev_node, new_node, points, config.road_type);
-
-                        // Record action for undo
-                        if snapping {
-                            // Just added an edge to existing node
-                            history.push(RoadAction::AddEdge {
-                                from: prev_node,
-                                to: new_node,
-                                edge,
-                            });
-                        } else {
-                            // Added new node + edge
-                            history.push(RoadAction::AddNodeAndEdge {
-                                node_position: new_pos,
-                                node_type,
-                                node_index: new_node,
-                                from: prev_node,
-                                edge,
-                            });
-                        }
-
-                        info!(
-                            "Road edge placed: {:?} -> {:?} ({:?})",
-                            prev_pos, new_pos, config.road_type
-                        );
-
-                        dirty_events.send(RoadMeshDirty);
-                    }
-                } else {
-                    // First node placed
-                    if !snapping {
-                        history.push(RoadAction::AddNode {
-                            position: actual_pos,
-                            node_type,
-                            node_index: new_node,
-                        });
-                    }
-                    info!("Road node placed at {:?}", world_pos);
-                }
+    // Segment to build this frame, if any
+    let mut to_build: Option<PendingSegment> = None;
 
+    match config.draw_mode {
+        // Straight mode: click to place nodes, auto-connect to previous
+        RoadDrawMode::Straight if mouse.just_pressed(MouseButton::Left) => {
+            if let (Some(prev_node), Some(plan)) = (state.last_node, state.pending.clone()) {
+                let prev_pos = road_graph
+                    .node_by_index(prev_node)
+                    .map(|n| n.position)
+                    .unwrap_or(world_pos);
+                to_build = Some((prev_node, SmallVec::from_slice(&[prev_pos, actual_pos]), plan));
+            } else {
+                // First node placed
+                let snapping = state.snapping_to.is_some();
+                let new_node = road_graph.snap_or_create(
+                    actual_pos,
+                    config.snap_distance,
+                    RoadNodeType::Endpoint,
+                );
+                if !snapping {
+                    history.push(HistoryEntry::new(
+                        "place road node",
+                        0,
+                        vec![PlayerAction::Road(RoadAction::AddSegments {
+                            nodes: vec![(actual_pos, RoadNodeType::Endpoint)],
+                            edges: Vec::new(),
+                        })],
+                    ));
+                }
                 state.last_node = Some(new_node);
+                info!("Road node placed at {:?}", actual_pos);
             }
         }
         RoadDrawMode::Curved => {
@@ -486,22 +1008,25 @@ fn handle_road_draw_input(
                     // No previous node - just place the first node
                     let snapping = state.snapping_to.is_some();
                     let new_node = road_graph.snap_or_create(
-                        world_pos,
+                        actual_pos,
                         config.snap_distance,
                         RoadNodeType::Endpoint,
                     );
 
                     // Record first node for undo (if not snapping to existing)
                     if !snapping {
-                        history.push(RoadAction::AddNode {
-                            position: actual_pos,
-                            node_type: RoadNodeType::Endpoint,
-                            node_index: new_node,
-                        });
+                        history.push(HistoryEntry::new(
+                            "place road node",
+                            0,
+                            vec![PlayerAction::Road(RoadAction::AddSegments {
+                                nodes: vec![(actual_pos, RoadNodeType::Endpoint)],
+                                edges: Vec::new(),
+                            })],
+                        ));
                     }
 
                     state.last_node = Some(new_node);
-                    info!("Road node placed at {:?}", world_pos);
+                    info!("Road node placed at {:?}", actual_pos);
                 }
             }
 
@@ -513,62 +1038,26 @@ fn handle_road_draw_input(
                     let to_cursor = world_pos - start;
                     let perpendicular = Vec2::new(-line_dir.y, line_dir.x);
                     state.curve_offset = to_cursor.dot(perpendicular);
+
+                    let control = calculate_control_point(start, actual_pos, state.curve_offset);
+                    let length = polyline_length(&generate_bezier_points(start, control, actual_pos, config.curve_segments));
+                    state.readout = Some(segment_readout(start, actual_pos, length, &references, config.snap));
                 }
             }
 
-            // On release, create the curved edge
+            // On release, plan the curved edge
             if mouse.just_released(MouseButton::Left) && state.is_dragging {
                 if let Some(prev_node) = state.last_node {
-                    let snapping = state.snapping_to.is_some();
-                    let new_node = road_graph.snap_or_create(
-                        actual_pos,
-                        config.snap_distance,
-                        RoadNodeType::Intersection,
-                    );
-
-                    if new_node != prev_node {
-                        let prev_pos = road_graph
-                            .node_by_index(prev_node)
-                            .map(|n| n.position)
-                            .unwrap_or(world_pos);
-                        let new_pos = road_graph
-                            .node_by_index(new_node)
-                            .map(|n| n.position)
-                            .unwrap_or(world_pos);
-
-                        // Generate bezier curve points
-                        let control = calculate_control_point(prev_pos, new_pos, state.curve_offset);
-                        let points = generate_bezier_points(prev_pos, control, new_pos, config.curve_segments);
-                        let edge = RoadEdge::new(points.clone(), config.road_type);
-
-                        road_graph.add_edge(prev_node, new_node, points, config.road_type);
-
-                        // Record action for undo
-                        if snapping {
-                            history.push(RoadAction::AddEdge {
-                                from: prev_node,
-                                to: new_node,
-                                edge,
-                            });
-                        } else {
-                            history.push(RoadAction::AddNodeAndEdge {
-                                node_position: new_pos,
-                                node_type: RoadNodeType::Intersection,
-                                node_index: new_node,
-                                from: prev_node,
-                                edge,
-                            });
-                        }
-
-                        info!(
-                            "Curved road edge placed: {:?} -> {:?} (offset: {:.1})",
-                            prev_pos, new_pos, state.curve_offset
-                        );
-
-                        dirty_events.send(RoadMeshDirty);
-                    }
+                    let prev_pos = road_graph
+                        .node_by_index(prev_node)
+                        .map(|n| n.position)
+                        .unwrap_or(world_pos);
 
-                    state.last_node = Some(new_node);
+                    // Generate bezier curve points
+                    let control = calculate_control_point(prev_pos, actual_pos, state.curve_offset);
+                    let points = generate_bezier_points(prev_pos, control, actual_pos, config.curve_segments);
+                    let plan = plan_segment(&points, &config, &heights, &river, &grading);
+                    to_build = Some((prev_node, points, plan));
                 }
 
                 state.is_dragging = false;
@@ -576,6 +1065,46 @@ fn handle_road_draw_input(
                 state.curve_offset = 0.0;
             }
         }
+        _ => {}
+    }
+
+    match to_build {
+        Some((_, _, Err(err))) => report_segment_error(err, &config),
+        Some((prev_node, points, Ok(plan))) => {
+            let cost = segment_cost(&plan, &points, &config);
+            if budget.funds < cost {
+                warn!(
+                    "Cannot afford {:?} road: costs ${}, have ${}",
+                    config.road_type, cost, budget.funds
+                );
+            } else {
+                let kind = match plan {
+                    SegmentPlan::Road(_) => "Road",
+                    SegmentPlan::Bridge { .. } => "Bridge",
+                    SegmentPlan::Tunnel => "Tunnel",
+                };
+                let (start, end) = (points[0], points[points.len() - 1]);
+                let heights_before = heights.data.clone();
+                if let Some((end_node, action)) =
+                    place_segment(&mut road_graph, &mut heights, &grading, &config, prev_node, points, plan)
+                {
+                    budget.funds -= cost;
+                    let graded = terrain_changes(&heights_before, &heights);
+                    history.push(HistoryEntry::new(
+                        action.describe(),
+                        cost,
+                        vec![PlayerAction::Road(action), PlayerAction::Terrain(graded)],
+                    ));
+                    state.last_node = Some(end_node);
+                    info!(
+                        "{} placed: {:?} -> {:?} ({:?}) for ${}",
+                        kind, start, end, config.road_type, cost
+                    );
+                    dirty_events.send(RoadMeshDirty);
+                }
+            }
+        }
+        None => {}
     }
 
     // Cancel/finish with right click or Escape
@@ -590,6 +1119,166 @@ fn handle_road_draw_input(
     }
 }
 
+/// Parallel mode: click to copy the road under the cursor to the cursor's side.
+fn handle_parallel_input(
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut road_graph: ResMut<RoadGraph>,
+    mut state: ResMut<RoadDrawState>,
+    config: Res<RoadDrawConfig>,
+    build: SegmentBuild,
+) {
+    if config.draw_mode != RoadDrawMode::Parallel {
+        return;
+    }
+    let SegmentBuild { grading, river, mut heights, mut budget, mut history, mut dirty_events } = build;
+    state.guide.clear();
+    let Some(cursor) = state.hover_pos else {
+        return;
+    };
+
+    // The road the cursor is beside, within a little more than the offset
+    let source = road_graph
+        .nearest_edge(cursor, config.parallel_offset * 1.5)
+        .and_then(|(idx, _, _)| road_graph.edge_by_index(idx));
+    let Some(source) = source else {
+        state.readout = Some(format!("Parallel {:.0} m: point at a road", config.parallel_offset));
+        return;
+    };
+    let points = parallel_points(&source.points, config.parallel_offset, cursor);
+    let plan = plan_segment(&points, &config, &heights, &river, &grading);
+    let length = polyline_length(&points);
+    state.guide = points.windows(2).map(|w| (w[0], w[1])).collect();
+    state.guide_buildable = plan.is_ok();
+    state.readout = Some(format!("Parallel {:.0} m  {:.0} m long", config.parallel_offset, length));
+
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let plan = match plan {
+        Ok(plan) => plan,
+        Err(err) => return report_segment_error(err, &config),
+    };
+    let cost = segment_cost(&plan, &points, &config);
+    if budget.funds < cost {
+        warn!("Cannot afford parallel {:?} road: costs ${}, have ${}", config.road_type, cost, budget.funds);
+        return;
+    }
+    let heights_before = heights.data.clone();
+    if let Some(action) = place_standalone(&mut road_graph, &mut heights, &grading, &config, points, plan) {
+        budget.funds -= cost;
+        let graded = terrain_changes(&heights_before, &heights);
+        info!("Parallel {:?} road placed: {:.0}m for ${}", config.road_type, length, cost);
+        history.push(HistoryEntry::new(
+            action.describe(),
+            cost,
+            vec![PlayerAction::Road(action), PlayerAction::Terrain(graded)],
+        ));
+        dirty_events.send(RoadMeshDirty);
+    }
+}
+
+/// Shortest side of a street grid rectangle.
+const MIN_GRID_SIDE: f32 = 20.0;
+
+/// Street grid mode: drag a rectangle, release to build a street on every block side.
+fn handle_street_grid_input(
+    mouse: Res<ButtonInput<MouseButton>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut road_graph: ResMut<RoadGraph>,
+    mut state: ResMut<RoadDrawState>,
+    config: Res<RoadDrawConfig>,
+    build: SegmentBuild,
+) {
+    if config.draw_mode != RoadDrawMode::StreetGrid {
+        return;
+    }
+    let SegmentBuild { grading, river, mut heights, mut budget, mut history, mut dirty_events } = build;
+    if mouse.just_pressed(MouseButton::Right) || keyboard.just_pressed(KeyCode::Escape) {
+        state.grid_start = None;
+    }
+    if mouse.just_pressed(MouseButton::Left) {
+        state.grid_start = state.target_pos;
+    }
+    state.guide.clear();
+    let (Some(start), Some(corner)) = (state.grid_start, state.target_pos) else {
+        state.readout = None;
+        return;
+    };
+
+    let rect = Rect::from_corners(start, corner);
+    let released = mouse.just_released(MouseButton::Left);
+    if released {
+        state.grid_start = None;
+    }
+    if rect.width() < MIN_GRID_SIDE || rect.height() < MIN_GRID_SIDE {
+        state.readout = Some(format!("{:.0} × {:.0} m", rect.width(), rect.height()));
+        return;
+    }
+
+    let (xs, ys) = street_grid_lines(rect, config.block_size);
+    let segments = street_grid_segments(&xs, &ys);
+    let plans: Vec<_> = segments
+        .iter()
+        .map(|&(a, b)| plan_segment(&[a, b], &config, &heights, &river, &grading))
+        .collect();
+    let cost: i64 = segments
+        .iter()
+        .zip(&plans)
+        .filter_map(|(&(a, b), plan)| plan.as_ref().ok().map(|plan| segment_cost(plan, &[a, b], &config)))
+        .sum();
+    let blocked = plans.iter().filter(|plan| plan.is_err()).count();
+    state.guide = segments.clone();
+    state.guide_buildable = blocked == 0;
+    state.readout = Some(format!(
+        "{:.0} × {:.0} m  {}×{} blocks of {:.0} × {:.0} m  ${}",
+        rect.width(),
+        rect.height(),
+        xs.len() - 1,
+        ys.len() - 1,
+        rect.width() / (xs.len() - 1) as f32,
+        rect.height() / (ys.len() - 1) as f32,
+        cost
+    ));
+
+    if !released {
+        return;
+    }
+    if budget.funds < cost {
+        warn!("Cannot afford street grid: costs ${}, have ${}", cost, budget.funds);
+        return;
+    }
+
+    let heights_before = heights.data.clone();
+    let mut actions = Vec::new();
+    for ((a, b), plan) in segments.into_iter().zip(plans) {
+        let Ok(plan) = plan else {
+            continue;
+        };
+        if let Some(action) =
+            place_standalone(&mut road_graph, &mut heights, &grading, &config, SmallVec::from_slice(&[a, b]), plan)
+        {
+            actions.push(action);
+        }
+    }
+    if actions.is_empty() {
+        warn!("No street in the grid can be built here");
+        return;
+    }
+    budget.funds -= cost;
+    if blocked > 0 {
+        warn!("Skipped {} grid streets that cannot be built", blocked);
+    }
+    info!("Street grid placed: {} segments for ${}", actions.len(), cost);
+    let action = merge_actions(actions);
+    let graded = terrain_changes(&heights_before, &heights);
+    history.push(HistoryEntry::new(
+        format!("street grid {}×{}", xs.len() - 1, ys.len() - 1),
+        cost,
+        vec![PlayerAction::Road(action), PlayerAction::Terrain(graded)],
+    ));
+    dirty_events.send(RoadMeshDirty);
+}
+
 fn update_road_preview(
     mut commands: Commands,
     state: Res<RoadDrawState>,
@@ -622,14 +1311,7 @@ fn update_road_preview(
     };
 
     // Get actual position (snapped or raw)
-    let actual_pos = if let Some(snap_node) = state.snapping_to {
-        road_graph
-            .node_by_index(snap_node)
-            .map(|n| n.position)
-            .unwrap_or(hover_pos)
-    } else {
-        hover_pos
-    };
+    let actual_pos = state.target_pos.unwrap_or(hover_pos);
 
     // Node preview color (green for new, yellow for snap)
     let node_color = if state.snapping_to.is_some() {
@@ -665,17 +1347,16 @@ fn update_road_preview(
         let length = prev_pos.distance(actual_pos);
         let angle = (actual_pos - prev_pos).to_angle();
 
-        let road_width = match config.road_type {
-            RoadType::Highway => 12.0,
-            RoadType::Major => 8.0,
-            RoadType::Minor => 5.0,
-            RoadType::Alley => 3.0,
-        };
+        let road_width = carriageway_width(config.road_type);
 
-        // Different color for curved mode (purple) vs straight (grey)
-        let edge_color = match config.draw_mode {
-            RoadDrawMode::Straight => Color::srgba(0.4, 0.4, 0.5, 0.6),
-            RoadDrawMode::Curved => Color::srgba(0.6, 0.3, 0.7, 0.6),
+        // Red if unbuildable, blue for bridges, brown for tunnels,
+        // otherwise curved mode (purple) vs straight (grey)
+        let edge_color = match (&state.pending, config.draw_mode) {
+            (Some(Err(_)), _) => Color::srgba(0.9, 0.2, 0.2, 0.6),
+            (Some(Ok(SegmentPlan::Bridge { .. })), _) => Color::srgba(0.2, 0.5, 0.9, 0.6),
+            (Some(Ok(SegmentPlan::Tunnel)), _) => Color::srgba(0.55, 0.4, 0.25, 0.6),
+            (_, RoadDrawMode::Curved) => Color::srgba(0.6, 0.3, 0.7, 0.6),
+            _ => Color::srgba(0.4, 0.4, 0.5, 0.6),
         };
 
         if let Some((_, mut transform, mut sprite)) = edge_preview.iter_mut().next() {
@@ -707,6 +1388,100 @@ fn update_road_preview(
     }
 }
 
+/// Marker for the parallel and street grid preview segments.
+#[derive(Component)]
+struct GuidePreview;
+
+/// Draw the parallel road or street grid about to be built.
+fn update_guide_preview(
+    mut commands: Commands,
+    state: Res<RoadDrawState>,
+    config: Res<RoadDrawConfig>,
+    previews: Query<Entity, With<GuidePreview>>,
+    mut shown: Local<(Vec<(Vec2, Vec2)>, bool)>,
+) {
+    if shown.0 == state.guide && shown.1 == state.guide_buildable {
+        return;
+    }
+    *shown = (state.guide.clone(), state.guide_buildable);
+    for entity in &previews {
+        commands.entity(entity).despawn();
+    }
+
+    let width = carriageway_width(config.road_type);
+    let color = if state.guide_buildable {
+        Color::srgba(0.3, 0.7, 0.8, 0.6)
+    } else {
+        Color::srgba(0.9, 0.2, 0.2, 0.6)
+    };
+    for &(a, b) in &state.guide {
+        let center = (a + b) / 2.0;
+        let angle = (b - a).to_angle();
+        commands.spawn((
+            Sprite {
+                color,
+                custom_size: Some(Vec2::new(a.distance(b), width)),
+                ..default()
+            },
+            Transform::from_translation(Vec3::new(center.x, 0.3, center.y)).with_rotation(
+                Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2) * Quat::from_rotation_z(-angle),
+            ),
+            GuidePreview,
+            RoadPreview,
+        ));
+    }
+}
+
+/// Marker for the length/angle text next to the cursor.
+#[derive(Component)]
+struct MeasureReadout;
+
+/// Keep the readout next to the cursor.
+fn update_measure_readout(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    state: Res<RoadDrawState>,
+    tool: Res<State<ActiveTool>>,
+    mut readout_q: Query<(Entity, &mut Text, &mut Node), With<MeasureReadout>>,
+) {
+    let shown = state.readout.as_ref().zip(state.cursor_screen).filter(|_| is_road_draw_active(&tool));
+    let Some((text, cursor)) = shown else {
+        for (entity, _, _) in &readout_q {
+            commands.entity(entity).despawn();
+        }
+        return;
+    };
+
+    let (left, top) = (Val::Px(cursor.x + 18.0), Val::Px(cursor.y + 18.0));
+    if let Some((_, mut current, mut node)) = readout_q.iter_mut().next() {
+        if current.0 != *text {
+            current.0 = text.clone();
+        }
+        node.left = left;
+        node.top = top;
+    } else {
+        commands.spawn((
+            Text::new(text.clone()),
+            TextFont {
+                font: asset_server.load("fonts/ShareTechMono-Regular.ttf"),
+                font_size: 14.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.95, 0.95, 0.9)),
+            BackgroundColor(Color::srgba(0.05, 0.05, 0.08, 0.75)),
+            Node {
+                position_type: PositionType::Absolute,
+                left,
+                top,
+                padding: UiRect::axes(Val::Px(6.0), Val::Px(3.0)),
+                ..default()
+            },
+            MeasureReadout,
+            RoadPreview,
+        ));
+    }
+}
+
 fn cleanup_on_tool_change(
     mut commands: Commands,
     tool: Res<State<ActiveTool>>,
@@ -725,6 +1500,11 @@ fn cleanup_on_tool_change(
         state.curve_start = None;
         state.is_dragging = false;
         state.curve_offset = 0.0;
+        state.pending = None;
+        state.target_pos = None;
+        state.grid_start = None;
+        state.guide.clear();
+        state.readout = None;
 
         // Despawn all previews
         for entity in &previews {
@@ -732,3 +1512,50 @@ fn cleanup_on_tool_change(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Flat ground with a ridge across the middle, running along Y.
+    fn ridge(height: f32) -> HeightMap {
+        let mut heights = HeightMap::generate(32, 32, 0, 0.0);
+        heights.origin = Vec2::ZERO;
+        heights.cell_size = 4.0;
+        for y in 0..32 {
+            for x in 0..32 {
+                let from_crest = (x as f32 - 16.0).abs() * 4.0;
+                heights.set(x, y, (height - from_crest).max(0.0));
+            }
+        }
+        heights
+    }
+
+    #[test]
+    fn tunnel_needs_cover_and_length_limit() {
+        let config = RoadDrawConfig::default();
+        let through = [Vec2::new(20.0, 60.0), Vec2::new(108.0, 60.0)];
+
+        assert_eq!(plan_tunnel(&ridge(30.0), &through, RoadType::Minor, &config), Ok(()));
+        assert!(matches!(
+            plan_tunnel(&ridge(2.0), &through, RoadType::Minor, &config),
+            Err(TunnelError::Shallow { .. })
+        ));
+
+        let short = RoadDrawConfig {
+            max_tunnel_length: 50.0,
+            ..default()
+        };
+        assert!(matches!(
+            plan_tunnel(&ridge(30.0), &through, RoadType::Minor, &short),
+            Err(TunnelError::TooLong { .. })
+        ));
+    }
+
+    #[test]
+    fn slice_follows_polyline() {
+        let points = [Vec2::ZERO, Vec2::new(10.0, 0.0), Vec2::new(10.0, 10.0)];
+        let section = slice_polyline(&points, 5.0, 15.0);
+        assert_eq!(section.as_slice(), &[Vec2::new(5.0, 0.0), Vec2::new(10.0, 0.0), Vec2::new(10.0, 5.0)]);
+    }
+}
@@ -0,0 +1,523 @@
+//! Road modification tool - change roads that are already built.
+//!
+//! Click or drag along roads to upgrade or downgrade their class, make them
+//! one-way, or add and remove medians and bike lanes. Upgrades and new
+//! fittings are charged per metre; downgrades refund part of the difference.
+//! Each stroke is a single undo step (Ctrl+Z / Ctrl+Y).
+
+#![allow(dead_code)]
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use petgraph::graph::EdgeIndex;
+
+use super::history::{terrain_changes, CommandHistory, HistoryEntry, PlayerAction};
+use super::road_draw::{cost_per_metre, RoadAction, RoadDrawConfig, RoadMeshDirty};
+use super::ActiveTool;
+use crate::game_state::GameState;
+use crate::procgen::grading::{apply_road_grade, plan_road_grade, GradingConfig};
+use crate::procgen::intersections::carriageway_width;
+use crate::procgen::roads::{RoadEdge, RoadGraph, RoadType, TravelDirection};
+use crate::simulation::economy::CityBudget;
+use crate::world::terrain::HeightMap;
+
+pub struct RoadModifyPlugin;
+
+impl Plugin for RoadModifyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RoadModifyConfig>()
+            .init_resource::<RoadModifyState>()
+            .add_systems(
+                Update,
+                (
+                    handle_modify_input,
+                    apply_modify_stroke,
+                    finish_modify_stroke,
+                    update_modify_preview,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(is_road_modify_active),
+            )
+            .add_systems(Update, cleanup_on_tool_change.run_if(in_state(GameState::Playing)));
+    }
+}
+
+/// Run condition: check if the road modification tool is active.
+fn is_road_modify_active(tool: Res<State<ActiveTool>>) -> bool {
+    matches!(tool.get(), ActiveTool::RoadModify(_))
+}
+
+/// What the road modification tool changes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RoadModifyMode {
+    /// Raise roads one class (alley → minor → major → highway).
+    Upgrade,
+    /// Lower roads one class.
+    Downgrade,
+    /// Click to cycle two-way/one-way, or drag to set the one-way direction.
+    OneWay,
+    /// Add or remove a raised median.
+    Median,
+    /// Add or remove painted bike lanes.
+    BikeLanes,
+}
+
+impl RoadModifyMode {
+    /// Get the display name for this mode.
+    pub fn name(&self) -> &'static str {
+        match self {
+            RoadModifyMode::Upgrade => "Upgrade",
+            RoadModifyMode::Downgrade => "Downgrade",
+            RoadModifyMode::OneWay => "One-way",
+            RoadModifyMode::Median => "Median",
+            RoadModifyMode::BikeLanes => "Bike lanes",
+        }
+    }
+
+    /// Get the display color for this mode.
+    pub fn color(&self) -> Color {
+        match self {
+            RoadModifyMode::Upgrade => Color::srgb(0.4, 0.8, 0.5),
+            RoadModifyMode::Downgrade => Color::srgb(0.8, 0.5, 0.4),
+            RoadModifyMode::OneWay => Color::srgb(0.9, 0.8, 0.3),
+            RoadModifyMode::Median => Color::srgb(0.6, 0.6, 0.55),
+            RoadModifyMode::BikeLanes => Color::srgb(0.3, 0.7, 0.4),
+        }
+    }
+}
+
+/// Configuration for the road modification tool.
+#[derive(Resource)]
+pub struct RoadModifyConfig {
+    /// Extra distance beyond the road edge at which a road can be picked.
+    pub pick_margin: f32,
+    /// Cursor travel before a one-way press counts as a drag.
+    pub drag_threshold: f32,
+    /// Fraction of the class difference refunded on a downgrade.
+    pub downgrade_refund: f32,
+    /// Cost per metre of a new median.
+    pub median_cost_per_metre: f32,
+    /// Cost per metre of new bike lanes (both sides).
+    pub bike_lane_cost_per_metre: f32,
+    /// Flat cost of re-signing a road's direction.
+    pub one_way_cost: i64,
+}
+
+impl Default for RoadModifyConfig {
+    fn default() -> Self {
+        Self {
+            pick_margin: 3.0,
+            drag_threshold: 4.0,
+            downgrade_refund: 0.5,
+            median_cost_per_metre: 4.0,
+            bike_lane_cost_per_metre: 3.0,
+            one_way_cost: 100,
+        }
+    }
+}
+
+/// Progress of the current modification stroke.
+#[derive(Resource, Default)]
+pub struct RoadModifyState {
+    /// Cursor position on the ground.
+    pub cursor: Option<Vec2>,
+    /// Road under the cursor and its direction there.
+    pub hovered: Option<(EdgeIndex, Vec2)>,
+    /// Whether a stroke is in progress.
+    pub stroking: bool,
+    /// Cursor position the last drag step was measured from.
+    pub drag_anchor: Option<Vec2>,
+    /// Whether the cursor has moved far enough to count as a drag.
+    pub dragged: bool,
+    /// Median/bike lane setting chosen by the first road of the stroke.
+    pub toggle_to: Option<bool>,
+    /// Edges already changed by this stroke.
+    pub touched: HashSet<EdgeIndex>,
+    /// Endpoint positions with the edge before and after, for the undo record.
+    pub changes: Vec<(Vec2, Vec2, RoadEdge, RoadEdge)>,
+    /// Terrain heights when the stroke began, so regrading can be undone.
+    pub heights_before: Vec<f32>,
+    /// Net money spent by the stroke (negative for refunds).
+    pub stroke_cost: i64,
+    /// Whether the last attempted change was refused for lack of funds.
+    pub blocked: bool,
+}
+
+impl RoadModifyState {
+    /// Charge for and write one edge change, recording it for undo.
+    fn apply_change(&mut self, road_graph: &mut RoadGraph, idx: EdgeIndex, after: RoadEdge, cost: i64, budget: &mut CityBudget) -> bool {
+        if cost > 0 && budget.funds < cost {
+            if !self.blocked {
+                warn!("Cannot afford road change: costs ${}, have ${}", cost, budget.funds);
+            }
+            self.blocked = true;
+            return false;
+        }
+        let Some((a, b)) = road_graph.edge_endpoints(idx) else {
+            return false;
+        };
+        let (Some(a), Some(b)) = (road_graph.node_by_index(a), road_graph.node_by_index(b)) else {
+            return false;
+        };
+        let (a, b) = (a.position, b.position);
+        let Some(edge) = road_graph.edge_mut(idx) else {
+            return false;
+        };
+        let before = std::mem::replace(edge, after.clone());
+        budget.funds -= cost;
+        self.stroke_cost += cost;
+        self.blocked = false;
+        self.changes.push((a, b, before, after));
+        true
+    }
+}
+
+/// One class up, if there is one.
+pub fn upgraded(road_type: RoadType) -> Option<RoadType> {
+    match road_type {
+        RoadType::Alley => Some(RoadType::Minor),
+        RoadType::Minor => Some(RoadType::Major),
+        RoadType::Major => Some(RoadType::Highway),
+        RoadType::Highway => None,
+    }
+}
+
+/// One class down, if there is one.
+pub fn downgraded(road_type: RoadType) -> Option<RoadType> {
+    match road_type {
+        RoadType::Highway => Some(RoadType::Major),
+        RoadType::Major => Some(RoadType::Minor),
+        RoadType::Minor => Some(RoadType::Alley),
+        RoadType::Alley => None,
+    }
+}
+
+/// Change a road's class, dropping fittings the new class can't carry.
+pub fn with_road_type(edge: &RoadEdge, road_type: RoadType) -> RoadEdge {
+    let mut changed = edge.clone();
+    changed.road_type = road_type;
+    changed.median &= changed.supports_median();
+    changed.bike_lanes &= changed.supports_bike_lanes();
+    changed
+}
+
+/// Change a road's direction, dropping a median from one-way roads.
+pub fn with_direction(edge: &RoadEdge, direction: TravelDirection) -> RoadEdge {
+    let mut changed = edge.clone();
+    changed.direction = direction;
+    changed.median &= changed.supports_median();
+    changed
+}
+
+/// Money owed for turning `before` into `after`; negative means a refund.
+pub fn modification_cost(
+    before: &RoadEdge,
+    after: &RoadEdge,
+    draw_config: &RoadDrawConfig,
+    config: &RoadModifyConfig,
+) -> i64 {
+    let structure_multiplier = if before.tunnel {
+        draw_config.tunnel_cost_multiplier
+    } else if before.crosses_water {
+        draw_config.bridge_cost_multiplier
+    } else {
+        1.0
+    };
+    let class = (cost_per_metre(after.road_type, draw_config) - cost_per_metre(before.road_type, draw_config))
+        * before.length
+        * structure_multiplier;
+    let mut cost = if class < 0.0 { class * config.downgrade_refund } else { class };
+
+    if after.median && !before.median {
+        cost += config.median_cost_per_metre * before.length;
+    }
+    if after.bike_lanes && !before.bike_lanes {
+        cost += config.bike_lane_cost_per_metre * before.length;
+    }
+    let mut cost = cost.round() as i64;
+    if after.direction != before.direction {
+        cost += config.one_way_cost;
+    }
+    cost
+}
+
+/// Next setting when clicking a road with the one-way tool.
+fn next_direction(direction: TravelDirection) -> TravelDirection {
+    match direction {
+        TravelDirection::Both => TravelDirection::Forward,
+        TravelDirection::Forward => TravelDirection::Backward,
+        TravelDirection::Backward => TravelDirection::Both,
+    }
+}
+
+/// Track the cursor and the road under it; start strokes.
+fn handle_modify_input(
+    mouse: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
+    road_graph: Res<RoadGraph>,
+    heights: Res<HeightMap>,
+    config: Res<RoadModifyConfig>,
+    mut state: ResMut<RoadModifyState>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_q.get_single() else {
+        return;
+    };
+
+    state.cursor = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok());
+
+    // Widest road half-width plus the margin; then check against the picked road's own width
+    state.hovered = state.cursor.and_then(|pos| {
+        let widest = carriageway_width(RoadType::Highway) / 2.0;
+        let (idx, distance, tangent) = road_graph.nearest_edge(pos, widest + config.pick_margin)?;
+        let edge = road_graph.edge_by_index(idx)?;
+        let half_width = carriageway_width(edge.road_type) / 2.0;
+        (distance <= half_width + config.pick_margin).then_some((idx, tangent))
+    });
+
+    if mouse.just_pressed(MouseButton::Left) && state.cursor.is_some() {
+        state.stroking = true;
+        state.drag_anchor = state.cursor;
+        state.dragged = false;
+        state.toggle_to = None;
+        state.touched.clear();
+        state.changes.clear();
+        state.heights_before = heights.data.clone();
+        state.stroke_cost = 0;
+        state.blocked = false;
+    }
+}
+
+/// Apply the active mode to each road the stroke passes over.
+#[allow(clippy::too_many_arguments)]
+fn apply_modify_stroke(
+    mouse: Res<ButtonInput<MouseButton>>,
+    tool: Res<State<ActiveTool>>,
+    config: Res<RoadModifyConfig>,
+    draw_config: Res<RoadDrawConfig>,
+    grading: Res<GradingConfig>,
+    mut state: ResMut<RoadModifyState>,
+    mut road_graph: ResMut<RoadGraph>,
+    mut heights: ResMut<HeightMap>,
+    mut budget: ResMut<CityBudget>,
+    mut dirty_events: EventWriter<RoadMeshDirty>,
+) {
+    let ActiveTool::RoadModify(mode) = *tool.get() else {
+        return;
+    };
+    if !state.stroking || !mouse.pressed(MouseButton::Left) {
+        return;
+    }
+    let (Some(cursor), Some((idx, tangent))) = (state.cursor, state.hovered) else {
+        return;
+    };
+
+    // One-way strokes wait for the cursor to move; the drag sets the direction
+    let mut drag_direction = None;
+    if mode == RoadModifyMode::OneWay {
+        let Some(anchor) = state.drag_anchor else {
+            return;
+        };
+        let motion = cursor - anchor;
+        if motion.length() < config.drag_threshold {
+            return;
+        }
+        state.dragged = true;
+        state.drag_anchor = Some(cursor);
+        let along = motion.normalize().dot(tangent);
+        if along.abs() < 0.5 {
+            return;
+        }
+        drag_direction = Some(if along > 0.0 { TravelDirection::Forward } else { TravelDirection::Backward });
+    }
+    if state.touched.contains(&idx) {
+        return;
+    }
+    let Some(before) = road_graph.edge_by_index(idx).cloned() else {
+        return;
+    };
+
+    let after = match mode {
+        RoadModifyMode::Upgrade => upgraded(before.road_type).map(|ty| with_road_type(&before, ty)),
+        RoadModifyMode::Downgrade => downgraded(before.road_type).map(|ty| with_road_type(&before, ty)),
+        RoadModifyMode::OneWay => drag_direction.map(|direction| with_direction(&before, direction)),
+        RoadModifyMode::Median => {
+            let on = *state.toggle_to.get_or_insert(!before.median);
+            (before.supports_median() || !on).then(|| RoadEdge { median: on, ..before.clone() })
+        }
+        RoadModifyMode::BikeLanes => {
+            let on = *state.toggle_to.get_or_insert(!before.bike_lanes);
+            (before.supports_bike_lanes() || !on).then(|| RoadEdge { bike_lanes: on, ..before.clone() })
+        }
+    };
+    state.touched.insert(idx);
+    let Some(after) = after.filter(|after| *after != before) else {
+        return;
+    };
+
+    let cost = modification_cost(&before, &after, &draw_config, &config);
+    if state.apply_change(&mut road_graph, idx, after, cost, &mut budget) {
+        // A wider road needs a wider graded corridor
+        if let Some(edge) = road_graph.edge_by_index(idx).filter(|edge| !edge.is_structure()) {
+            if let Ok(profile) = plan_road_grade(&heights, &edge.points, edge.road_type, &grading) {
+                apply_road_grade(&mut heights, &profile, edge.road_type, &grading);
+            }
+        }
+        dirty_events.send(RoadMeshDirty);
+    }
+}
+
+/// End the stroke: a one-way click cycles the road, then the stroke becomes one undo step.
+#[allow(clippy::too_many_arguments)]
+fn finish_modify_stroke(
+    mouse: Res<ButtonInput<MouseButton>>,
+    tool: Res<State<ActiveTool>>,
+    config: Res<RoadModifyConfig>,
+    draw_config: Res<RoadDrawConfig>,
+    mut state: ResMut<RoadModifyState>,
+    mut road_graph: ResMut<RoadGraph>,
+    heights: Res<HeightMap>,
+    mut budget: ResMut<CityBudget>,
+    mut history: ResMut<CommandHistory>,
+    mut dirty_events: EventWriter<RoadMeshDirty>,
+) {
+    let ActiveTool::RoadModify(mode) = *tool.get() else {
+        return;
+    };
+    if !state.stroking || !mouse.just_released(MouseButton::Left) {
+        return;
+    }
+    state.stroking = false;
+
+    if mode == RoadModifyMode::OneWay && !state.dragged {
+        if let Some((idx, _)) = state.hovered {
+            if let Some(before) = road_graph.edge_by_index(idx).cloned() {
+                let after = with_direction(&before, next_direction(before.direction));
+                let cost = modification_cost(&before, &after, &draw_config, &config);
+                if state.apply_change(&mut road_graph, idx, after, cost, &mut budget) {
+                    dirty_events.send(RoadMeshDirty);
+                }
+            }
+        }
+    }
+
+    if state.changes.is_empty() {
+        return;
+    }
+    let edges = std::mem::take(&mut state.changes);
+    if state.stroke_cost >= 0 {
+        info!("{}: changed {} roads for ${}", mode.name(), edges.len(), state.stroke_cost);
+    } else {
+        info!("{}: changed {} roads, refunded ${}", mode.name(), edges.len(), -state.stroke_cost);
+    }
+    let graded = terrain_changes(&std::mem::take(&mut state.heights_before), &heights);
+    let action = RoadAction::ModifyEdges { edges };
+    history.push(HistoryEntry::new(
+        action.describe(),
+        state.stroke_cost,
+        vec![PlayerAction::Road(action), PlayerAction::Terrain(graded)],
+    ));
+}
+
+/// Marker for the highlight over the hovered road.
+#[derive(Component)]
+struct RoadModifyPreview;
+
+/// Highlight the hovered road in the mode's colour.
+fn update_modify_preview(
+    mut commands: Commands,
+    tool: Res<State<ActiveTool>>,
+    state: Res<RoadModifyState>,
+    road_graph: Res<RoadGraph>,
+    heights: Res<HeightMap>,
+    preview_q: Query<Entity, With<RoadModifyPreview>>,
+    mut shown: Local<Option<(EdgeIndex, bool)>>,
+) {
+    let ActiveTool::RoadModify(mode) = *tool.get() else {
+        return;
+    };
+    let wanted = state.hovered.map(|(idx, _)| (idx, state.blocked));
+    if wanted == *shown && !road_graph.is_changed() && (wanted.is_none() || !preview_q.is_empty()) {
+        return;
+    }
+    *shown = wanted;
+    for entity in &preview_q {
+        commands.entity(entity).despawn();
+    }
+    let Some(edge) = wanted.and_then(|(idx, _)| road_graph.edge_by_index(idx)) else {
+        return;
+    };
+
+    let color = if state.blocked {
+        Color::srgba(0.9, 0.2, 0.2, 0.4)
+    } else {
+        mode.color().with_alpha(0.4)
+    };
+    let width = carriageway_width(edge.road_type) + 2.0;
+    for window in edge.points.windows(2) {
+        let center = (window[0] + window[1]) / 2.0;
+        let length = window[0].distance(window[1]);
+        let angle = (window[1] - window[0]).to_angle();
+        commands.spawn((
+            Sprite {
+                color,
+                custom_size: Some(Vec2::new(length, width)),
+                ..default()
+            },
+            Transform::from_translation(Vec3::new(center.x, heights.sample_world(center) + 0.4, center.y))
+                .with_rotation(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2) * Quat::from_rotation_z(-angle)),
+            RoadModifyPreview,
+        ));
+    }
+}
+
+fn cleanup_on_tool_change(
+    mut commands: Commands,
+    tool: Res<State<ActiveTool>>,
+    mut state: ResMut<RoadModifyState>,
+    preview_q: Query<Entity, With<RoadModifyPreview>>,
+) {
+    if !tool.is_changed() || matches!(tool.get(), ActiveTool::RoadModify(_)) {
+        return;
+    }
+    for entity in &preview_q {
+        commands.entity(entity).despawn();
+    }
+    *state = RoadModifyState::default();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smallvec::smallvec;
+
+    #[test]
+    fn downgrade_refunds_part_and_drops_fittings() {
+        let draw_config = RoadDrawConfig::default();
+        let config = RoadModifyConfig::default();
+        let mut major = RoadEdge::new(smallvec![Vec2::ZERO, Vec2::new(100.0, 0.0)], RoadType::Major);
+        major.median = true;
+        major.bike_lanes = true;
+
+        let minor = with_road_type(&major, RoadType::Minor);
+        assert!(!minor.median && minor.bike_lanes);
+        // Major→minor is 10/m over 100m; half comes back
+        assert_eq!(modification_cost(&major, &minor, &draw_config, &config), -500);
+        assert_eq!(modification_cost(&minor, &with_road_type(&minor, RoadType::Major), &draw_config, &config), 1000);
+        // Putting the median back costs extra
+        assert_eq!(modification_cost(&minor, &major, &draw_config, &config), 1400);
+
+        let one_way = with_direction(&major, TravelDirection::Forward);
+        assert!(!one_way.median);
+        assert_eq!(one_way.lane_counts(), (4, 0));
+        assert_eq!(modification_cost(&major, &one_way, &draw_config, &config), config.one_way_cost);
+    }
+}
@@ -0,0 +1,206 @@
+//! Snapping and guide geometry for the road drawing tool.
+//!
+//! Pure functions: snapping points to the world grid or to angle steps
+//! relative to the roads already at a node, offsetting a road sideways for
+//! parallel drawing, and laying out a rectangular street grid.
+
+use bevy::prelude::*;
+use petgraph::graph::NodeIndex;
+use smallvec::SmallVec;
+
+use crate::procgen::roads::RoadGraph;
+
+/// How road endpoints are snapped while drawing (toggle with M).
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum RoadSnap {
+    /// Free placement.
+    #[default]
+    Off,
+    /// Snap to the world grid.
+    Grid,
+    /// Snap the segment's angle to fixed steps from the connected roads.
+    Angle,
+}
+
+impl RoadSnap {
+    /// The next setting in the toggle cycle.
+    pub fn next(self) -> Self {
+        match self {
+            RoadSnap::Off => RoadSnap::Grid,
+            RoadSnap::Grid => RoadSnap::Angle,
+            RoadSnap::Angle => RoadSnap::Off,
+        }
+    }
+}
+
+/// Nearest grid intersection to `pos`.
+pub fn snap_to_grid(pos: Vec2, grid_size: f32) -> Vec2 {
+    if grid_size <= 0.0 {
+        return pos;
+    }
+    (pos / grid_size).round() * grid_size
+}
+
+/// Rotate `delta` to the nearest multiple of `step` radians from `reference`,
+/// keeping its length.
+pub fn snap_angle(delta: Vec2, reference: Vec2, step: f32) -> Vec2 {
+    let length = delta.length();
+    if length < f32::EPSILON || step <= 0.0 {
+        return delta;
+    }
+    let base = reference.to_angle();
+    let relative = delta.to_angle() - base;
+    let snapped = (relative / step).round() * step + base;
+    Vec2::from_angle(snapped) * length
+}
+
+/// Directions leaving `node` along each road connected to it.
+pub fn connected_directions(road_graph: &RoadGraph, node: NodeIndex) -> Vec<Vec2> {
+    let Some(origin) = road_graph.node_by_index(node).map(|n| n.position) else {
+        return Vec::new();
+    };
+    road_graph
+        .edges_of_node(node)
+        .filter_map(|idx| road_graph.edge_by_index(idx))
+        .filter(|edge| edge.points.len() >= 2)
+        .map(|edge| {
+            let (first, last) = (edge.points[0], edge.points[edge.points.len() - 1]);
+            if first.distance_squared(origin) <= last.distance_squared(origin) {
+                edge.points[1] - first
+            } else {
+                edge.points[edge.points.len() - 2] - last
+            }
+        })
+        .filter_map(|dir| dir.try_normalize())
+        .collect()
+}
+
+/// Snap `target` so the segment from `origin` turns off one of `references`
+/// by a whole number of `step`s, using the reference that needs the least
+/// correction. Without references the world X axis is used.
+pub fn snap_to_references(origin: Vec2, target: Vec2, references: &[Vec2], step: f32) -> Vec2 {
+    let delta = target - origin;
+    let fallback = [Vec2::X];
+    let references = if references.is_empty() { &fallback[..] } else { references };
+    references
+        .iter()
+        .map(|&reference| origin + snap_angle(delta, reference, step))
+        .min_by(|a, b| a.distance_squared(target).total_cmp(&b.distance_squared(target)))
+        .unwrap_or(target)
+}
+
+/// Angle between `delta` and the closest of `references`, in degrees (0-180).
+pub fn angle_to_references(delta: Vec2, references: &[Vec2]) -> Option<f32> {
+    references
+        .iter()
+        .map(|reference| reference.angle_to(delta).abs().to_degrees())
+        .min_by(f32::total_cmp)
+}
+
+/// `points` shifted sideways by `offset` (positive to the left of travel).
+///
+/// Interior points move along the bisector of their two segments so the
+/// copy stays the same distance from the original.
+pub fn offset_polyline(points: &[Vec2], offset: f32) -> SmallVec<[Vec2; 8]> {
+    let normal = |a: Vec2, b: Vec2| (b - a).normalize_or_zero().perp();
+    let last = points.len().saturating_sub(1);
+    points
+        .iter()
+        .enumerate()
+        .map(|(i, &point)| {
+            let before = (i > 0).then(|| normal(points[i - 1], point));
+            let after = (i < last).then(|| normal(point, points[i + 1]));
+            let shift = match (before, after) {
+                (Some(a), Some(b)) => {
+                    let bisector = (a + b).normalize_or_zero();
+                    // Longer shift at a bend keeps both segments at the offset
+                    let cos = bisector.dot(a).max(0.25);
+                    bisector * offset / cos
+                }
+                (Some(n), None) | (None, Some(n)) => n * offset,
+                (None, None) => Vec2::ZERO,
+            };
+            point + shift
+        })
+        .collect()
+}
+
+/// Copy of `points` offset by `distance` towards the side `cursor` is on.
+pub fn parallel_points(points: &[Vec2], distance: f32, cursor: Vec2) -> SmallVec<[Vec2; 8]> {
+    let left = offset_polyline(points, distance);
+    let right = offset_polyline(points, -distance);
+    let closest = |line: &[Vec2]| {
+        line.iter()
+            .map(|p| p.distance_squared(cursor))
+            .fold(f32::MAX, f32::min)
+    };
+    if closest(&left) <= closest(&right) {
+        left
+    } else {
+        right
+    }
+}
+
+/// Street grid filling `rect`: the cut lines along each axis, spaced as close
+/// to `block_size` as fits the rectangle exactly.
+pub fn street_grid_lines(rect: Rect, block_size: f32) -> (Vec<f32>, Vec<f32>) {
+    let lines = |min: f32, max: f32| {
+        let blocks = ((max - min) / block_size.max(1.0)).round().max(1.0) as usize;
+        (0..=blocks)
+            .map(|i| min + (max - min) * i as f32 / blocks as f32)
+            .collect::<Vec<_>>()
+    };
+    (lines(rect.min.x, rect.max.x), lines(rect.min.y, rect.max.y))
+}
+
+/// Every street segment of a grid, one per block side.
+pub fn street_grid_segments(xs: &[f32], ys: &[f32]) -> Vec<(Vec2, Vec2)> {
+    let mut segments = Vec::new();
+    for &y in ys {
+        for pair in xs.windows(2) {
+            segments.push((Vec2::new(pair[0], y), Vec2::new(pair[1], y)));
+        }
+    }
+    for &x in xs {
+        for pair in ys.windows(2) {
+            segments.push((Vec2::new(x, pair[0]), Vec2::new(x, pair[1])));
+        }
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn angle_snap_is_relative_to_reference() {
+        let reference = Vec2::from_angle(10f32.to_radians());
+        let snapped = snap_angle(Vec2::from_angle(28f32.to_radians()) * 20.0, reference, 15f32.to_radians());
+        assert!((snapped.length() - 20.0).abs() < 1e-4);
+        assert!((snapped.to_angle().to_degrees() - 25.0).abs() < 1e-3);
+
+        assert_eq!(snap_to_grid(Vec2::new(14.0, -6.0), 10.0), Vec2::new(10.0, -10.0));
+    }
+
+    #[test]
+    fn offset_keeps_distance_round_bends() {
+        let points = [Vec2::ZERO, Vec2::new(10.0, 0.0), Vec2::new(10.0, 10.0)];
+        let offset = offset_polyline(&points, 2.0);
+        assert_eq!(offset[0], Vec2::new(0.0, 2.0));
+        assert!(offset[1].distance(Vec2::new(8.0, 2.0)) < 1e-4);
+        assert_eq!(offset[2], Vec2::new(8.0, 10.0));
+
+        let right = parallel_points(&points, 2.0, Vec2::new(5.0, -3.0));
+        assert_eq!(right[0], Vec2::new(0.0, -2.0));
+    }
+
+    #[test]
+    fn street_grid_fits_rectangle() {
+        let (xs, ys) = street_grid_lines(Rect::new(0.0, 0.0, 130.0, 50.0), 60.0);
+        assert_eq!(xs, vec![0.0, 65.0, 130.0]);
+        assert_eq!(ys, vec![0.0, 50.0]);
+        // Two streets of two segments along X, three of one along Y
+        assert_eq!(street_grid_segments(&xs, &ys).len(), 7);
+    }
+}
@@ -0,0 +1,386 @@
+//! Roundabout and interchange placement for the road drawing tool.
+//!
+//! In the template draw modes a click builds a whole road network at once:
+//! a roundabout ring at the cursor (replacing the junction if the cursor is
+//! on one), or a diamond or cloverleaf interchange aligned with the nearest
+//! road. The template is previewed under the cursor with its cost, and the
+//! build is one undo step.
+
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use bevy::prelude::*;
+use petgraph::graph::{EdgeIndex, NodeIndex};
+
+use super::history::{terrain_changes, CommandHistory, HistoryEntry, PlayerAction};
+use super::road_draw::{
+    cost_per_metre, removal_action, RoadAction, RoadDrawConfig, RoadDrawMode, RoadDrawState, RoadMeshDirty,
+};
+use crate::procgen::grading::{apply_road_grade, plan_road_grade, GradeError, GradingConfig};
+use crate::procgen::interchanges::{
+    clip_outside_circle, interchange, roundabout, InterchangeKind, RoadTemplate, TemplateConfig,
+};
+use crate::procgen::river::River;
+use crate::procgen::roads::{polyline_length, RoadEdge, RoadGraph, RoadType, RoadsRemoved};
+use crate::simulation::economy::CityBudget;
+use crate::world::terrain::HeightMap;
+
+/// How far from the cursor to look for a road to align an interchange with.
+const ALIGN_DISTANCE: f32 = 40.0;
+
+/// Closest two roads may meet a roundabout, in radians.
+const MIN_LEG_SEPARATION: f32 = 20.0 * PI / 180.0;
+
+/// Shortest stretch of an approach road left outside a roundabout.
+const MIN_LEG_LENGTH: f32 = 5.0;
+
+/// Why a template cannot be built.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TemplateError {
+    /// A road in the template cannot meet its grade.
+    Grade(GradeError),
+    /// Part of the template is in the water.
+    InWater,
+    /// A roundabout cannot replace a junction on a bridge, tunnel or overpass.
+    OnStructure,
+    /// An approach road ends too close to the junction for the ring.
+    LegTooShort,
+    /// Two approach roads meet the ring almost on top of each other.
+    LegsTooClose,
+}
+
+impl TemplateError {
+    fn message(self) -> String {
+        match self {
+            TemplateError::Grade(GradeError::TooSteep { required, max }) => format!(
+                "too steep: needs {:.0}% grade, limit is {:.0}%",
+                required * 100.0,
+                max * 100.0
+            ),
+            TemplateError::Grade(GradeError::ExcessiveCut { depth, max }) => {
+                format!("needs {:.1}m of cut/fill, limit is {:.1}m", depth, max)
+            }
+            TemplateError::Grade(GradeError::TooShort) => "a road is too short to grade".to_string(),
+            TemplateError::InWater => "cannot be built in water".to_string(),
+            TemplateError::OnStructure => "cannot replace a junction on a bridge or tunnel".to_string(),
+            TemplateError::LegTooShort => "a connected road is shorter than the radius".to_string(),
+            TemplateError::LegsTooClose => "two connected roads are too close together".to_string(),
+        }
+    }
+}
+
+/// An existing road reconnected to a new roundabout.
+struct TemplateLeg {
+    /// Position of the road's far end node.
+    far: Vec2,
+    /// Template node on the ring it now ends at.
+    ring: usize,
+    /// The road, clipped at the ring, in its original point order.
+    edge: RoadEdge,
+    /// Whether the far end is the first of the edge's points.
+    far_first: bool,
+}
+
+/// A template ready to build, with the junction it replaces.
+struct TemplatePlan {
+    template: RoadTemplate,
+    /// Junction node and its roads, removed before the template is added.
+    replaces: Option<(NodeIndex, Vec<EdgeIndex>)>,
+    legs: Vec<TemplateLeg>,
+}
+
+/// Ring class for a roundabout joining roads of `road_type`: highways are
+/// brought down to major roads.
+fn ring_type(road_type: RoadType) -> RoadType {
+    match road_type {
+        RoadType::Highway => RoadType::Major,
+        other => other,
+    }
+}
+
+/// Roundabout centred on `center`; if `node` is given, its roads are cut
+/// back to the ring and reconnected.
+fn plan_roundabout(
+    road_graph: &RoadGraph,
+    center: Vec2,
+    node: Option<NodeIndex>,
+    config: &RoadDrawConfig,
+    templates: &TemplateConfig,
+) -> Result<TemplatePlan, TemplateError> {
+    let radius = config.roundabout_radius;
+    let Some(node) = node else {
+        let angles = [0.0, FRAC_PI_2, PI, PI + FRAC_PI_2];
+        let (template, _) = roundabout(center, radius, &angles, ring_type(config.road_type), templates);
+        return Ok(TemplatePlan {
+            template,
+            replaces: None,
+            legs: Vec::new(),
+        });
+    };
+
+    // Cut each road at the ring
+    let mut cut = Vec::new();
+    let mut edges = Vec::new();
+    for idx in road_graph.edges_of_node(node) {
+        let (Some((a, b)), Some(edge)) = (road_graph.edge_endpoints(idx), road_graph.edge_by_index(idx)) else {
+            continue;
+        };
+        if a == b {
+            continue;
+        }
+        if edge.is_structure() {
+            return Err(TemplateError::OnStructure);
+        }
+        let far_node = if a == node { b } else { a };
+        let far = road_graph.node_by_index(far_node).map_or(center, |n| n.position);
+        let points = clip_outside_circle(&edge.points, center, radius).ok_or(TemplateError::LegTooShort)?;
+        if polyline_length(&points) < MIN_LEG_LENGTH {
+            return Err(TemplateError::LegTooShort);
+        }
+        let far_first = points[0].distance(far) < points[points.len() - 1].distance(far);
+        let ring_point = if far_first { points[points.len() - 1] } else { points[0] };
+        cut.push(((ring_point - center).to_angle(), far, far_first, points, edge.clone()));
+        edges.push(idx);
+    }
+
+    // Legs must meet the ring far enough apart for their own nodes
+    let mut angles: Vec<f32> = cut.iter().map(|c| c.0).collect();
+    angles.sort_by(f32::total_cmp);
+    let wraps = angles.len() > 1 && angles[0] + 2.0 * PI - angles[angles.len() - 1] < MIN_LEG_SEPARATION;
+    if wraps || angles.windows(2).any(|w| w[1] - w[0] < MIN_LEG_SEPARATION) {
+        return Err(TemplateError::LegsTooClose);
+    }
+
+    let road_type = cut
+        .iter()
+        .map(|c| ring_type(c.4.road_type))
+        .min_by_key(|t| *t as u8)
+        .unwrap_or(ring_type(config.road_type));
+    let leg_angles: Vec<f32> = if cut.is_empty() {
+        vec![0.0, FRAC_PI_2, PI, PI + FRAC_PI_2]
+    } else {
+        cut.iter().map(|c| c.0).collect()
+    };
+    let (template, ring_nodes) = roundabout(center, radius, &leg_angles, road_type, templates);
+    let legs = cut
+        .into_iter()
+        .zip(ring_nodes)
+        .map(|((_, far, far_first, mut points, mut edge), ring)| {
+            // End exactly on the ring node
+            let ring_pos = template.nodes[ring].position;
+            if far_first {
+                *points.last_mut().unwrap() = ring_pos;
+            } else {
+                points[0] = ring_pos;
+            }
+            edge.length = polyline_length(&points);
+            edge.points = points;
+            TemplateLeg {
+                far,
+                ring,
+                edge,
+                far_first,
+            }
+        })
+        .collect();
+
+    Ok(TemplatePlan {
+        template,
+        replaces: Some((node, edges)),
+        legs,
+    })
+}
+
+/// Interchange at `center`, with the highway along the nearest road.
+fn plan_interchange(
+    road_graph: &RoadGraph,
+    kind: InterchangeKind,
+    center: Vec2,
+    config: &RoadDrawConfig,
+    templates: &TemplateConfig,
+) -> TemplatePlan {
+    let axis = road_graph
+        .nearest_edge(center, ALIGN_DISTANCE)
+        .map_or(Vec2::X, |(_, _, direction)| direction);
+    TemplatePlan {
+        template: interchange(kind, center, axis, ring_type(config.road_type), templates),
+        replaces: None,
+        legs: Vec::new(),
+    }
+}
+
+/// Check the template can be built where it is and price it.
+fn check_template(
+    template: &RoadTemplate,
+    config: &RoadDrawConfig,
+    heights: &HeightMap,
+    river: &River,
+    grading: &GradingConfig,
+) -> Result<i64, TemplateError> {
+    let mut cost = 0.0;
+    for placed in &template.edges {
+        let edge = &placed.edge;
+        if edge.points.iter().any(|&p| river.contains_point(p)) {
+            return Err(TemplateError::InWater);
+        }
+        let rate = cost_per_metre(edge.road_type, config);
+        if edge.overpass {
+            cost += edge.length * rate * config.bridge_cost_multiplier;
+        } else {
+            plan_road_grade(heights, &edge.points, edge.road_type, grading).map_err(TemplateError::Grade)?;
+            cost += edge.length * rate;
+        }
+    }
+    Ok(cost.round() as i64)
+}
+
+/// Add a planned template to the graph, grading the ground under it.
+///
+/// Returns the undo records in the order they were made.
+fn build_template(
+    road_graph: &mut RoadGraph,
+    heights: &mut HeightMap,
+    grading: &GradingConfig,
+    config: &RoadDrawConfig,
+    plan: TemplatePlan,
+    removed_events: &mut EventWriter<RoadsRemoved>,
+) -> Vec<RoadAction> {
+    let mut actions = Vec::new();
+
+    // Take out the junction the template replaces
+    if let Some((node, edges)) = &plan.replaces {
+        let removal = road_graph.remove_edges_and_nodes(edges, Some(&[*node]));
+        actions.push(removal_action(&removal));
+        removed_events.send(RoadsRemoved(removal));
+    }
+
+    let mut nodes = Vec::new();
+    let indices: Vec<NodeIndex> = plan
+        .template
+        .nodes
+        .iter()
+        .map(|node| {
+            if node.attach && road_graph.find_nearest(node.position, config.snap_distance).is_some() {
+                return road_graph.snap_or_create(node.position, config.snap_distance, node.node_type);
+            }
+            nodes.push((node.position, node.node_type));
+            road_graph.add_node(node.position, node.node_type)
+        })
+        .collect();
+    let position = |graph: &RoadGraph, node: NodeIndex| graph.node_by_index(node).map_or(Vec2::ZERO, |n| n.position);
+
+    let mut edges = Vec::new();
+    for placed in plan.template.edges {
+        let (from, to) = (indices[placed.from], indices[placed.to]);
+        let (from_pos, to_pos) = (position(road_graph, from), position(road_graph, to));
+        let mut edge = placed.edge;
+
+        // Ends may have snapped onto existing nodes
+        let last = edge.points.len() - 1;
+        edge.points[0] = from_pos;
+        edge.points[last] = to_pos;
+        edge.length = polyline_length(&edge.points);
+        if !edge.overpass {
+            if let Ok(profile) = plan_road_grade(heights, &edge.points, edge.road_type, grading) {
+                apply_road_grade(heights, &profile, edge.road_type, grading);
+            }
+        }
+        road_graph.add_edge_data(from, to, edge.clone());
+        edges.push((from_pos, to_pos, edge));
+    }
+
+    // Reconnect the roads that met the replaced junction
+    for leg in plan.legs {
+        let (Some(far), ring) = (road_graph.node_at(leg.far), indices[leg.ring]) else {
+            continue;
+        };
+        let ring_pos = position(road_graph, ring);
+        let (from, to, from_pos, to_pos) = if leg.far_first {
+            (far, ring, leg.far, ring_pos)
+        } else {
+            (ring, far, ring_pos, leg.far)
+        };
+        road_graph.add_edge_data(from, to, leg.edge.clone());
+        edges.push((from_pos, to_pos, leg.edge));
+    }
+
+    actions.push(RoadAction::AddSegments { nodes, edges });
+    actions
+}
+
+/// Roundabout and interchange modes: preview the template at the cursor and
+/// build it on click.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn handle_template_input(
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut road_graph: ResMut<RoadGraph>,
+    mut state: ResMut<RoadDrawState>,
+    mut history: ResMut<CommandHistory>,
+    config: Res<RoadDrawConfig>,
+    grading: Res<GradingConfig>,
+    river: Res<River>,
+    mut heights: ResMut<HeightMap>,
+    mut budget: ResMut<CityBudget>,
+    mut dirty_events: EventWriter<RoadMeshDirty>,
+    mut removed_events: EventWriter<RoadsRemoved>,
+) {
+    let kind = match config.draw_mode {
+        RoadDrawMode::Roundabout => None,
+        RoadDrawMode::DiamondInterchange => Some(InterchangeKind::Diamond),
+        RoadDrawMode::Cloverleaf => Some(InterchangeKind::Cloverleaf),
+        _ => return,
+    };
+    state.guide.clear();
+    let Some(center) = state.target_pos else {
+        return;
+    };
+
+    let templates = TemplateConfig::default();
+    let plan = match kind {
+        None => plan_roundabout(&road_graph, center, state.snapping_to, &config, &templates),
+        Some(kind) => Ok(plan_interchange(&road_graph, kind, center, &config, &templates)),
+    };
+    let name = config.draw_mode.name();
+    let title = match kind {
+        None => format!("{} r {:.0} m", name, config.roundabout_radius),
+        Some(_) => name.to_string(),
+    };
+    let priced = plan.and_then(|plan| {
+        let cost = check_template(&plan.template, &config, &heights, &river, &grading)?;
+        Ok((plan, cost))
+    });
+
+    match &priced {
+        Ok((plan, cost)) => {
+            state.guide = plan.template.segments();
+            state.guide_buildable = true;
+            state.readout = Some(format!("{}  ${}", title, cost));
+        }
+        Err(err) => {
+            state.guide_buildable = false;
+            state.readout = Some(format!("{}: {}", title, err.message()));
+        }
+    }
+
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let (plan, cost) = match priced {
+        Ok(priced) => priced,
+        Err(err) => return warn!("{} {}", name, err.message()),
+    };
+    if budget.funds < cost {
+        warn!("Cannot afford {}: costs ${}, have ${}", name.to_lowercase(), cost, budget.funds);
+        return;
+    }
+
+    let heights_before = heights.data.clone();
+    let actions = build_template(&mut road_graph, &mut heights, &grading, &config, plan, &mut removed_events);
+    budget.funds -= cost;
+    let graded = terrain_changes(&heights_before, &heights);
+    let mut recorded: Vec<PlayerAction> = actions.into_iter().map(PlayerAction::Road).collect();
+    recorded.push(PlayerAction::Terrain(graded));
+    history.push(HistoryEntry::new(format!("build {}", name.to_lowercase()), cost, recorded));
+    state.last_node = None;
+    info!("{} placed at {:?} for ${}", name, center, cost);
+    dirty_events.send(RoadMeshDirty);
+}
@@ -1,8 +1,12 @@
-//! Service placement tool - place police, fire, hospital, and school buildings.
+//! Service placement tool - place police, fire, hospital, and school buildings,
+//! and the power plants, water towers and pumping stations that feed the
+//! utility networks.
 
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 
+use super::history::{CommandHistory, HistoryEntry, PlayerAction};
 use super::ActiveTool;
 use crate::game_state::GameState;
 
@@ -34,6 +38,9 @@ pub enum ServiceType {
     Hospital,
     School,
     Park,
+    PowerPlant,
+    WaterTower,
+    PumpingStation,
 }
 
 impl ServiceType {
@@ -45,10 +52,14 @@ impl ServiceType {
             ServiceType::Hospital => "Hospital",
             ServiceType::School => "School",
             ServiceType::Park => "Park",
+            ServiceType::PowerPlant => "Power Plant",
+            ServiceType::WaterTower => "Water Tower",
+            ServiceType::PumpingStation => "Pumping Station",
         }
     }
 
-    /// Get the effect radius for this service.
+    /// Get the effect radius for this service. Utilities hook up to a road
+    /// within this distance.
     pub fn radius(&self) -> f32 {
         match self {
             ServiceType::Police => 100.0,
@@ -56,6 +67,7 @@ impl ServiceType {
             ServiceType::Hospital => 120.0,
             ServiceType::School => 60.0,
             ServiceType::Park => 40.0,
+            ServiceType::PowerPlant | ServiceType::WaterTower | ServiceType::PumpingStation => 50.0,
         }
     }
 
@@ -67,6 +79,9 @@ impl ServiceType {
             ServiceType::Hospital => 10000,
             ServiceType::School => 6000,
             ServiceType::Park => 1000,
+            ServiceType::PowerPlant => 12000,
+            ServiceType::WaterTower => 6000,
+            ServiceType::PumpingStation => 7000,
         }
     }
 
@@ -78,6 +93,9 @@ impl ServiceType {
             ServiceType::Hospital => Color::srgb(0.9, 0.9, 0.9), // White
             ServiceType::School => Color::srgb(0.9, 0.7, 0.2),  // Yellow
             ServiceType::Park => Color::srgb(0.2, 0.7, 0.3),    // Green
+            ServiceType::PowerPlant => Color::srgb(0.95, 0.8, 0.2), // Amber
+            ServiceType::WaterTower => Color::srgb(0.3, 0.7, 0.95), // Sky blue
+            ServiceType::PumpingStation => Color::srgb(0.55, 0.45, 0.3), // Brown
         }
     }
 }
@@ -115,16 +133,23 @@ struct ServicePreview;
 #[derive(Component)]
 struct RadiusPreview;
 
+/// Everything a service building is spawned with.
+#[derive(SystemParam)]
+struct ServiceSpawner<'w, 's> {
+    commands: Commands<'w, 's>,
+    meshes: ResMut<'w, Assets<Mesh>>,
+    materials: ResMut<'w, Assets<StandardMaterial>>,
+    config: Res<'w, ServicesConfig>,
+}
+
 fn handle_service_placement(
-    mut commands: Commands,
     mouse: Res<ButtonInput<MouseButton>>,
     windows: Query<&Window, With<PrimaryWindow>>,
     camera_q: Query<(&Camera, &GlobalTransform)>,
     tool: Res<State<ActiveTool>>,
-    config: Res<ServicesConfig>,
     mut budget: ResMut<crate::simulation::economy::CityBudget>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut history: ResMut<CommandHistory>,
+    spawner: ServiceSpawner,
 ) {
     let service_type = match tool.get() {
         ActiveTool::PlaceService(st) => *st,
@@ -164,6 +189,39 @@ fn handle_service_placement(
     // Deduct cost
     budget.funds -= cost;
 
+    let ServiceSpawner { mut commands, mut meshes, mut materials, config } = spawner;
+    let entity = spawn_service_building(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &config,
+        service_type,
+        world_pos,
+    );
+    history.push(HistoryEntry::new(
+        format!("place {}", service_type.name()),
+        cost,
+        vec![PlayerAction::Spawned(vec![entity])],
+    ));
+
+    info!(
+        "Placed {} at ({:.1}, {:.1}) for ${}",
+        service_type.name(),
+        world_pos.x,
+        world_pos.y,
+        cost
+    );
+}
+
+/// Spawn a service building of the given type centred on `position`.
+pub fn spawn_service_building(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    config: &ServicesConfig,
+    service_type: ServiceType,
+    position: Vec2,
+) -> Entity {
     // Create service building mesh
     let height = match service_type {
         ServiceType::Park => 1.0, // Parks are flat
@@ -187,23 +245,17 @@ fn handle_service_placement(
         height / 2.0
     };
 
-    commands.spawn((
-        Mesh3d(mesh),
-        MeshMaterial3d(material),
-        Transform::from_translation(Vec3::new(world_pos.x, y_pos, world_pos.y)),
-        ServiceBuilding {
-            service_type,
-            radius: service_type.radius(),
-        },
-    ));
-
-    info!(
-        "Placed {} at ({:.1}, {:.1}) for ${}",
-        service_type.name(),
-        world_pos.x,
-        world_pos.y,
-        cost
-    );
+    commands
+        .spawn((
+            Mesh3d(mesh),
+            MeshMaterial3d(material),
+            Transform::from_translation(Vec3::new(position.x, y_pos, position.y)),
+            ServiceBuilding {
+                service_type,
+                radius: service_type.radius(),
+            },
+        ))
+        .id()
 }
 
 fn update_service_preview(
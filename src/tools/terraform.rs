@@ -0,0 +1,678 @@
+//! Terraform tool - raise, lower, flatten, smooth and level the terrain.
+//!
+//! Brush strokes edit the shared `HeightMap` a few times per second while the
+//! mouse is held. Every stroke is paid for per cubic metre of earth moved,
+//! is refused where it would disturb a building, and re-grades the roads it
+//! touched once the mouse is released. Each stroke is one undo step.
+
+#![allow(dead_code)]
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::render::primitives::Aabb;
+use bevy::window::PrimaryWindow;
+
+use super::history::{terrain_changes, CommandHistory, HistoryEntry, PlayerAction};
+use super::services::ServiceBuilding;
+use super::{ActiveTool, ToolState};
+use crate::game_state::GameState;
+use crate::procgen::grading::{apply_road_grade, corridor_half_width, plan_road_grade, GradingConfig};
+use crate::procgen::roads::RoadGraph;
+use crate::render::building_spawner::Building;
+use crate::simulation::economy::CityBudget;
+use crate::world::terrain::HeightMap;
+
+pub struct TerraformPlugin;
+
+impl Plugin for TerraformPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TerraformConfig>()
+            .init_resource::<TerraformState>()
+            .add_systems(
+                Update,
+                (
+                    handle_brush_resize,
+                    handle_terraform_input,
+                    apply_terraform_brush,
+                    finish_terraform_stroke,
+                    update_terraform_preview,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(is_terraform_active),
+            )
+            .add_systems(OnExit(GameState::Playing), cleanup_terraform_preview)
+            .add_systems(Update, cleanup_on_tool_change.run_if(in_state(GameState::Playing)));
+    }
+}
+
+/// Run condition: check if the terraform tool is active.
+fn is_terraform_active(tool: Res<State<ActiveTool>>) -> bool {
+    matches!(tool.get(), ActiveTool::Terraform(_))
+}
+
+/// Brush behaviour of the terraform tool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TerraformMode {
+    /// Push the ground up under the brush.
+    Raise,
+    /// Dig the ground down under the brush.
+    Lower,
+    /// Pull the ground towards the height sampled when the stroke began.
+    Flatten,
+    /// Average out bumps under the brush.
+    Smooth,
+    /// Drag between two points to cut an even ramp between their heights.
+    Level,
+}
+
+impl TerraformMode {
+    /// Get the display name for this mode.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TerraformMode::Raise => "Raise",
+            TerraformMode::Lower => "Lower",
+            TerraformMode::Flatten => "Flatten",
+            TerraformMode::Smooth => "Smooth",
+            TerraformMode::Level => "Level",
+        }
+    }
+
+    /// Get the display color for this mode.
+    pub fn color(&self) -> Color {
+        match self {
+            TerraformMode::Raise => Color::srgb(0.8, 0.6, 0.3),
+            TerraformMode::Lower => Color::srgb(0.5, 0.4, 0.3),
+            TerraformMode::Flatten => Color::srgb(0.7, 0.7, 0.5),
+            TerraformMode::Smooth => Color::srgb(0.5, 0.7, 0.5),
+            TerraformMode::Level => Color::srgb(0.6, 0.6, 0.8),
+        }
+    }
+}
+
+/// Configuration for the terraform tool.
+#[derive(Resource)]
+pub struct TerraformConfig {
+    /// Height change per second at the brush center for raise/lower/flatten.
+    pub strength: f32,
+    /// Fraction of the way to the neighbour average per second when smoothing.
+    pub smooth_rate: f32,
+    /// Seconds between brush applications while the mouse is held.
+    pub tick_interval: f32,
+    /// Money charged per cubic metre of earth moved.
+    pub cost_per_cubic_metre: f32,
+    /// Extra margin kept clear around building footprints.
+    pub building_clearance: f32,
+    /// Smallest brush radius.
+    pub min_brush: f32,
+    /// Largest brush radius.
+    pub max_brush: f32,
+    /// Brush radius change per key press.
+    pub brush_step: f32,
+}
+
+impl Default for TerraformConfig {
+    fn default() -> Self {
+        Self {
+            strength: 4.0,
+            smooth_rate: 2.0,
+            tick_interval: 0.1,
+            cost_per_cubic_metre: 0.5,
+            building_clearance: 2.0,
+            min_brush: 4.0,
+            max_brush: 60.0,
+            brush_step: 2.0,
+        }
+    }
+}
+
+/// Why a brush application was refused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TerraformBlock {
+    /// The brush would disturb the ground under a building.
+    Building,
+    /// The city cannot pay for the earth moved.
+    Funds,
+}
+
+/// Progress of the current terraform stroke.
+#[derive(Resource, Default)]
+pub struct TerraformState {
+    /// Brush center on the ground, if the cursor is over the map.
+    pub cursor: Option<Vec2>,
+    /// Whether a stroke is in progress.
+    pub stroking: bool,
+    /// Time accumulated towards the next brush application.
+    pub tick_timer: f32,
+    /// Height the flatten brush pulls towards.
+    pub flatten_target: Option<f32>,
+    /// Start point and height of a level drag.
+    pub level_start: Option<(Vec2, f32)>,
+    /// World region edited by the current stroke.
+    pub stroke_region: Option<Rect>,
+    /// Earth moved by the current stroke, in cubic metres.
+    pub stroke_volume: f32,
+    /// Money spent on the current stroke.
+    pub stroke_cost: i64,
+    /// Terrain heights when the stroke began, for the undo record.
+    pub heights_before: Vec<f32>,
+    /// Fractional cost carried between applications.
+    pub unpaid: f32,
+    /// Reason the last application was refused.
+    pub blocked: Option<TerraformBlock>,
+}
+
+/// A planned set of height changes, not yet written to the map.
+#[derive(Clone, Debug, Default)]
+pub struct TerrainEdit {
+    /// Grid sample and its new height.
+    pub cells: Vec<(usize, usize, f32)>,
+    /// Earth moved (cut plus fill) in cubic metres.
+    pub volume: f32,
+}
+
+impl TerrainEdit {
+    /// Whether any changed sample lies inside one of `footprints`.
+    pub fn touches(&self, heights: &HeightMap, footprints: &[Rect]) -> bool {
+        self.cells.iter().any(|(x, y, _)| {
+            let pos = heights.grid_to_world(*x, *y);
+            footprints.iter().any(|rect| rect.contains(pos))
+        })
+    }
+
+    /// World region covered by the changed samples.
+    pub fn region(&self, heights: &HeightMap) -> Option<Rect> {
+        self.cells.iter().fold(None, |region, (x, y, _)| {
+            let pos = heights.grid_to_world(*x, *y);
+            let cell = Rect::from_center_half_size(pos, Vec2::splat(heights.cell_size));
+            Some(region.map_or(cell, |r: Rect| r.union(cell)))
+        })
+    }
+
+    /// Record a sample's new height, skipping negligible changes.
+    fn push(&mut self, heights: &HeightMap, x: usize, y: usize, old: f32, new: f32) {
+        let delta = new - old;
+        if delta.abs() < 1e-4 {
+            return;
+        }
+        self.cells.push((x, y, new));
+        self.volume += delta.abs() * heights.cell_size * heights.cell_size;
+    }
+
+    /// Write the planned heights into the map.
+    pub fn apply(&self, heights: &mut HeightMap) {
+        for (x, y, h) in &self.cells {
+            heights.set(*x, *y, *h);
+        }
+    }
+}
+
+/// Brush weight at `dist` from the center: 1 at the center, easing to 0 at `radius`.
+fn falloff(dist: f32, radius: f32) -> f32 {
+    if dist >= radius {
+        return 0.0;
+    }
+    let t = 1.0 - dist / radius;
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Plan one application of a circular brush.
+///
+/// `amount` is the height change at the center for raise, lower and flatten,
+/// and the blend fraction at the center for smooth. `target` is the flatten
+/// height. Level strokes are planned with [`plan_level`].
+pub fn plan_brush(
+    heights: &HeightMap,
+    mode: TerraformMode,
+    center: Vec2,
+    radius: f32,
+    amount: f32,
+    target: Option<f32>,
+) -> TerrainEdit {
+    let mut edit = TerrainEdit::default();
+    let reach = Vec2::splat(radius);
+    let (xs, ys) = heights.grid_range(center - reach, center + reach);
+
+    for y in ys {
+        for x in xs.clone() {
+            let weight = falloff(heights.grid_to_world(x, y).distance(center), radius);
+            if weight <= 0.0 {
+                continue;
+            }
+
+            let ground = heights.get(x, y);
+            let new = match mode {
+                TerraformMode::Raise => ground + amount * weight,
+                TerraformMode::Lower => ground - amount * weight,
+                TerraformMode::Flatten => {
+                    let Some(target) = target else { continue };
+                    let step = amount * weight;
+                    ground + (target - ground).clamp(-step, step)
+                }
+                TerraformMode::Smooth => {
+                    let average = neighbour_average(heights, x, y);
+                    ground + (average - ground) * (amount * weight).min(1.0)
+                }
+                TerraformMode::Level => continue,
+            };
+
+            edit.push(heights, x, y, ground, new);
+        }
+    }
+
+    edit
+}
+
+/// Plan a level stroke: an even ramp from `start` to `end`, `radius` wide on each side.
+pub fn plan_level(
+    heights: &HeightMap,
+    start: (Vec2, f32),
+    end: (Vec2, f32),
+    radius: f32,
+) -> TerrainEdit {
+    let mut edit = TerrainEdit::default();
+    let (a, ha) = start;
+    let (b, hb) = end;
+    let ab = b - a;
+    let len_sq = ab.length_squared();
+
+    let reach = Vec2::splat(radius);
+    let (xs, ys) = heights.grid_range(a.min(b) - reach, a.max(b) + reach);
+
+    for y in ys {
+        for x in xs.clone() {
+            let pos = heights.grid_to_world(x, y);
+            let t = if len_sq > 0.0 {
+                ((pos - a).dot(ab) / len_sq).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            // Flat across the middle of the ramp, easing back to the ground at its edge
+            let weight = (falloff(pos.distance(a + ab * t), radius) * 2.0).min(1.0);
+            if weight <= 0.0 {
+                continue;
+            }
+
+            let ground = heights.get(x, y);
+            let target = ha + (hb - ha) * t;
+            edit.push(heights, x, y, ground, ground + (target - ground) * weight);
+        }
+    }
+
+    edit
+}
+
+/// Mean height of the 3x3 neighbourhood around a grid sample.
+fn neighbour_average(heights: &HeightMap, x: usize, y: usize) -> f32 {
+    let mut sum = 0.0;
+    let mut count = 0.0;
+    for ny in y.saturating_sub(1)..=(y + 1).min(heights.height - 1) {
+        for nx in x.saturating_sub(1)..=(x + 1).min(heights.width - 1) {
+            sum += heights.get(nx, ny);
+            count += 1.0;
+        }
+    }
+    sum / count
+}
+
+/// World bounds of everything the terraform brush must not dig under.
+type BuildingBounds<'w, 's> = Query<
+    'w,
+    's,
+    (&'static GlobalTransform, &'static Aabb),
+    Or<(With<Building>, With<ServiceBuilding>)>,
+>;
+
+/// Ground footprints of buildings, padded by the configured clearance.
+fn building_footprints(
+    buildings: &BuildingBounds,
+    clearance: f32,
+) -> Vec<Rect> {
+    buildings
+        .iter()
+        .map(|(transform, aabb)| {
+            let affine = transform.affine();
+            let center = affine.transform_point3a(aabb.center);
+            let m = affine.matrix3;
+            let half = Vec3::from(
+                m.x_axis.abs() * aabb.half_extents.x
+                    + m.y_axis.abs() * aabb.half_extents.y
+                    + m.z_axis.abs() * aabb.half_extents.z,
+            );
+            Rect::from_center_half_size(
+                Vec2::new(center.x, center.z),
+                half.xz() + Vec2::splat(clearance),
+            )
+        })
+        .collect()
+}
+
+/// Cost of moving `volume` cubic metres, carrying fractions between calls.
+fn charge(state: &TerraformState, config: &TerraformConfig, volume: f32) -> (i64, f32) {
+    let owed = state.unpaid + volume * config.cost_per_cubic_metre;
+    let whole = owed.floor();
+    (whole as i64, owed - whole)
+}
+
+fn handle_brush_resize(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    config: Res<TerraformConfig>,
+    mut tool_state: ResMut<ToolState>,
+) {
+    let mut size = tool_state.brush_size;
+    if keyboard.just_pressed(KeyCode::Comma) {
+        size -= config.brush_step;
+    }
+    if keyboard.just_pressed(KeyCode::Period) {
+        size += config.brush_step;
+    }
+    let size = size.clamp(config.min_brush, config.max_brush);
+    if size != tool_state.brush_size {
+        tool_state.brush_size = size;
+    }
+}
+
+fn handle_terraform_input(
+    mouse: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
+    tool: Res<State<ActiveTool>>,
+    heights: Res<HeightMap>,
+    mut state: ResMut<TerraformState>,
+) {
+    let ActiveTool::Terraform(mode) = *tool.get() else {
+        return;
+    };
+
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_q.get_single() else {
+        return;
+    };
+
+    state.cursor = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok());
+
+    if mouse.just_pressed(MouseButton::Left) {
+        let Some(pos) = state.cursor else {
+            return;
+        };
+        let ground = heights.sample_world(pos);
+
+        state.stroking = true;
+        // Apply on the first frame so single clicks do something
+        state.tick_timer = f32::MAX;
+        state.stroke_region = None;
+        state.stroke_volume = 0.0;
+        state.stroke_cost = 0;
+        state.heights_before = heights.data.clone();
+        state.blocked = None;
+        state.flatten_target = (mode == TerraformMode::Flatten).then_some(ground);
+        state.level_start = (mode == TerraformMode::Level).then_some((pos, ground));
+    }
+}
+
+/// The ground a brush shapes, the buildings it keeps clear of and the
+/// funds that pay for it.
+#[derive(SystemParam)]
+struct BrushSite<'w, 's> {
+    heights: ResMut<'w, HeightMap>,
+    budget: ResMut<'w, CityBudget>,
+    buildings: BuildingBounds<'w, 's>,
+}
+
+fn apply_terraform_brush(
+    mouse: Res<ButtonInput<MouseButton>>,
+    time: Res<Time>,
+    tool: Res<State<ActiveTool>>,
+    tool_state: Res<ToolState>,
+    config: Res<TerraformConfig>,
+    mut state: ResMut<TerraformState>,
+    site: BrushSite,
+) {
+    let BrushSite { mut heights, mut budget, buildings } = site;
+    let ActiveTool::Terraform(mode) = *tool.get() else {
+        return;
+    };
+    if !state.stroking {
+        return;
+    }
+    let Some(pos) = state.cursor else {
+        return;
+    };
+    let radius = tool_state.brush_size;
+
+    let edit = if mode == TerraformMode::Level {
+        // Level strokes are applied once, when the drag ends
+        let Some(start) = state.level_start else {
+            return;
+        };
+        if !mouse.just_released(MouseButton::Left) {
+            return;
+        }
+        plan_level(&heights, start, (pos, heights.sample_world(pos)), radius)
+    } else {
+        if !mouse.pressed(MouseButton::Left) {
+            return;
+        }
+        state.tick_timer += time.delta_secs();
+        if state.tick_timer < config.tick_interval {
+            return;
+        }
+        let dt = state.tick_timer.min(config.tick_interval);
+        state.tick_timer = 0.0;
+
+        let amount = match mode {
+            TerraformMode::Smooth => config.smooth_rate * dt,
+            _ => config.strength * dt,
+        };
+        plan_brush(&heights, mode, pos, radius, amount, state.flatten_target)
+    };
+
+    if edit.cells.is_empty() {
+        return;
+    }
+
+    let footprints = building_footprints(&buildings, config.building_clearance);
+    if edit.touches(&heights, &footprints) {
+        if state.blocked != Some(TerraformBlock::Building) {
+            warn!("Cannot terraform under existing buildings");
+        }
+        state.blocked = Some(TerraformBlock::Building);
+        return;
+    }
+
+    let (cost, unpaid) = charge(&state, &config, edit.volume);
+    if budget.funds < cost {
+        if state.blocked != Some(TerraformBlock::Funds) {
+            info!(
+                "Cannot afford terraforming (${} needed, ${} available)",
+                cost, budget.funds
+            );
+        }
+        state.blocked = Some(TerraformBlock::Funds);
+        return;
+    }
+
+    budget.funds -= cost;
+    state.unpaid = unpaid;
+    state.stroke_cost += cost;
+    state.stroke_volume += edit.volume;
+    state.blocked = None;
+    if let Some(region) = edit.region(&heights) {
+        state.stroke_region = Some(state.stroke_region.map_or(region, |r| r.union(region)));
+    }
+    edit.apply(&mut heights);
+}
+
+/// End the stroke on release and re-grade the roads it disturbed.
+fn finish_terraform_stroke(
+    mouse: Res<ButtonInput<MouseButton>>,
+    road_graph: Res<RoadGraph>,
+    grading: Res<GradingConfig>,
+    mut state: ResMut<TerraformState>,
+    mut heights: ResMut<HeightMap>,
+    mut history: ResMut<CommandHistory>,
+) {
+    if !state.stroking || !mouse.just_released(MouseButton::Left) {
+        return;
+    }
+    state.stroking = false;
+    state.flatten_target = None;
+    state.level_start = None;
+
+    let Some(region) = state.stroke_region.take() else {
+        return;
+    };
+
+    let mut regraded = 0;
+    let mut too_steep = 0;
+    for edge in road_graph.edges() {
+        if edge.is_structure() || edge.points.len() < 2 {
+            continue;
+        }
+        let reach = corridor_half_width(edge.road_type, &grading) + grading.shoulder_width;
+        let touched = edge.points.windows(2).any(|w| {
+            let segment = Rect::from_corners(w[0], w[1]).inflate(reach);
+            !segment.intersect(region).is_empty()
+        });
+        if !touched {
+            continue;
+        }
+
+        match plan_road_grade(&heights, &edge.points, edge.road_type, &grading) {
+            Ok(profile) => {
+                apply_road_grade(&mut heights, &profile, edge.road_type, &grading);
+                regraded += 1;
+            }
+            Err(_) => too_steep += 1,
+        }
+    }
+
+    info!(
+        "Terraformed {:.0} m³ for ${} ({} roads regraded)",
+        state.stroke_volume, state.stroke_cost, regraded
+    );
+    if too_steep > 0 {
+        warn!("{} roads are now steeper than their grade allows", too_steep);
+    }
+    let samples = terrain_changes(&std::mem::take(&mut state.heights_before), &heights);
+    history.push(HistoryEntry::new(
+        "terraform",
+        state.stroke_cost,
+        vec![PlayerAction::Terrain(samples)],
+    ));
+}
+
+/// Marker for the terraform brush preview.
+#[derive(Component)]
+struct TerraformPreview;
+
+fn update_terraform_preview(
+    mut commands: Commands,
+    tool: Res<State<ActiveTool>>,
+    tool_state: Res<ToolState>,
+    state: Res<TerraformState>,
+    heights: Res<HeightMap>,
+    mut preview_q: Query<(Entity, &mut Transform, &mut Sprite), With<TerraformPreview>>,
+) {
+    let ActiveTool::Terraform(mode) = *tool.get() else {
+        return;
+    };
+
+    let Some(pos) = state.cursor else {
+        for (entity, _, _) in &preview_q {
+            commands.entity(entity).despawn();
+        }
+        return;
+    };
+
+    let color = if state.blocked.is_some() {
+        Color::srgba(0.9, 0.2, 0.2, 0.4)
+    } else {
+        mode.color().with_alpha(0.35)
+    };
+    let size = Vec2::splat(tool_state.brush_size * 2.0);
+    let translation = Vec3::new(pos.x, heights.sample_world(pos) + 0.4, pos.y);
+
+    if let Some((_, mut transform, mut sprite)) = preview_q.iter_mut().next() {
+        transform.translation = translation;
+        sprite.custom_size = Some(size);
+        sprite.color = color;
+    } else {
+        commands.spawn((
+            Sprite {
+                color,
+                custom_size: Some(size),
+                ..default()
+            },
+            Transform::from_translation(translation)
+                .with_rotation(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2)),
+            TerraformPreview,
+        ));
+    }
+}
+
+fn cleanup_terraform_preview(mut commands: Commands, preview_q: Query<Entity, With<TerraformPreview>>) {
+    for entity in &preview_q {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn cleanup_on_tool_change(
+    mut commands: Commands,
+    tool: Res<State<ActiveTool>>,
+    mut state: ResMut<TerraformState>,
+    preview_q: Query<Entity, With<TerraformPreview>>,
+) {
+    if !tool.is_changed() || matches!(tool.get(), ActiveTool::Terraform(_)) {
+        return;
+    }
+    for entity in &preview_q {
+        commands.entity(entity).despawn();
+    }
+    *state = TerraformState::default();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flat(height: f32) -> HeightMap {
+        let mut heights = HeightMap::generate(32, 32, 0, 0.0);
+        heights.origin = Vec2::ZERO;
+        heights.cell_size = 4.0;
+        heights.data.fill(height);
+        heights
+    }
+
+    #[test]
+    fn raise_and_lower_move_matching_volume() {
+        let mut heights = flat(5.0);
+        let center = Vec2::splat(64.0);
+
+        let raise = plan_brush(&heights, TerraformMode::Raise, center, 12.0, 1.0, None);
+        assert!(raise.volume > 0.0);
+        raise.apply(&mut heights);
+        assert!((heights.sample_world(center) - 6.0).abs() < 1e-3);
+
+        let lower = plan_brush(&heights, TerraformMode::Lower, center, 12.0, 1.0, None);
+        assert!((lower.volume - raise.volume).abs() < 1e-3);
+        lower.apply(&mut heights);
+        assert!(heights.data.iter().all(|h| (h - 5.0).abs() < 1e-3));
+    }
+
+    #[test]
+    fn level_ramps_between_endpoints() {
+        let heights = flat(0.0);
+        let start = (Vec2::new(20.0, 64.0), 0.0);
+        let end = (Vec2::new(100.0, 64.0), 8.0);
+
+        let mut levelled = heights.clone();
+        plan_level(&heights, start, end, 8.0).apply(&mut levelled);
+        let mid = levelled.sample_world(Vec2::new(60.0, 64.0));
+        assert!((mid - 4.0).abs() < 0.1, "midpoint height {}", mid);
+    }
+}
@@ -0,0 +1,385 @@
+//! Transit tool - lay out bus lines.
+//!
+//! Clicking a road adds a stop to the line being edited, starting a new line
+//! if none is. The line is routed between its stops by shortest path and
+//! rerouted whenever its roads change. Each line has a name, a colour and a
+//! service level, either a fixed number of buses or a target headway.
+//! Placing and removing stops go in the undo history.
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use super::history::{CommandHistory, HistoryEntry, PlayerAction};
+use super::ActiveTool;
+use crate::game_state::GameState;
+use crate::procgen::roads::RoadGraph;
+use crate::simulation::bus_routes::{
+    buses_wanted, BusLineEdited, BusRoute, BusRouteConfig, BusRoutes, LineService, MAX_LINE_BUSES,
+};
+use crate::simulation::economy::CityBudget;
+use crate::simulation::ridership::{Ridership, TransitNetwork};
+use crate::world::terrain::HeightMap;
+
+pub struct TransitPlugin;
+
+impl Plugin for TransitPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TransitToolState>()
+            .add_systems(
+                Update,
+                (handle_transit_input, edit_line_service, update_line_panel, draw_lines)
+                    .chain()
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_state(ActiveTool::Transit)),
+            )
+            .add_systems(Update, cleanup_on_tool_change.run_if(in_state(GameState::Playing)));
+    }
+}
+
+/// Cost of placing a stop.
+const STOP_COST: i64 = 250;
+/// Farthest a right click may be from a stop to remove it.
+const STOP_PICK_RADIUS: f32 = 20.0;
+/// Step and range for headways set with the edit keys, in seconds.
+const HEADWAY_STEP: f32 = 15.0;
+const HEADWAY_RANGE: (f32, f32) = (30.0, 600.0);
+
+/// Colours offered for player lines, cycled with L.
+const LINE_COLORS: &[Color] = &[
+    Color::srgb(0.85, 0.2, 0.55), // Magenta
+    Color::srgb(0.1, 0.65, 0.65), // Teal
+    Color::srgb(0.55, 0.3, 0.8),  // Purple
+    Color::srgb(0.9, 0.75, 0.1),  // Yellow
+    Color::srgb(0.45, 0.3, 0.2),  // Brown
+];
+
+const PANEL_BG: Color = Color::srgba(0.02, 0.02, 0.04, 0.94);
+const BORDER: Color = Color::srgb(0.3, 0.5, 1.0);
+const TEXT_COLOR: Color = Color::srgb(0.8, 0.85, 1.0);
+
+/// What the transit tool is working on.
+#[derive(Resource, Default)]
+pub struct TransitToolState {
+    /// Route index of the line being edited.
+    pub editing: Option<usize>,
+    /// Road point under the cursor a click would place a stop at.
+    pub hover: Option<Vec2>,
+}
+
+/// Marker for the line panel.
+#[derive(Component)]
+struct LinePanel;
+
+/// Place stops with a left click and remove the nearest with a right click.
+#[allow(clippy::too_many_arguments)]
+fn handle_transit_input(
+    mouse: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
+    road_graph: Res<RoadGraph>,
+    config: Res<BusRouteConfig>,
+    mut routes: ResMut<BusRoutes>,
+    mut budget: ResMut<CityBudget>,
+    mut state: ResMut<TransitToolState>,
+    mut history: ResMut<CommandHistory>,
+    mut edited: EventWriter<BusLineEdited>,
+) {
+    state.hover = None;
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_q.get_single() else {
+        return;
+    };
+    let Some(cursor) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+    else {
+        return;
+    };
+    let on_road = road_graph.nearest_edge(cursor, config.stop_snap_distance).is_some();
+    if on_road {
+        state.hover = Some(cursor);
+    }
+
+    if mouse.just_pressed(MouseButton::Right) {
+        let Some(index) = state.editing.filter(|&index| index < routes.routes.len()) else {
+            return;
+        };
+        let line = &mut routes.routes[index];
+        let nearest = line
+            .stops
+            .iter()
+            .enumerate()
+            .map(|(i, stop)| (i, stop.distance(cursor)))
+            .filter(|&(_, distance)| distance <= STOP_PICK_RADIUS)
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((i, _)) = nearest {
+            let before = line.stops.clone();
+            line.stops.remove(i);
+            history.push(HistoryEntry::new(
+                "remove bus stop",
+                0,
+                vec![PlayerAction::BusStops { line: index, before, after: line.stops.clone() }],
+            ));
+            if line.stops.is_empty() {
+                line.edges.clear();
+                line.stop_indices.clear();
+                state.editing = None;
+            }
+            edited.send(BusLineEdited);
+        }
+        return;
+    }
+
+    if !mouse.just_pressed(MouseButton::Left) || !on_road {
+        return;
+    }
+    if budget.funds < STOP_COST {
+        info!("Cannot afford a bus stop (${} needed, ${} available)", STOP_COST, budget.funds);
+        return;
+    }
+    budget.funds -= STOP_COST;
+
+    let editing = match state.editing.filter(|&index| routes.routes.get(index).is_some_and(|r| r.is_player_line())) {
+        Some(index) => index,
+        None => {
+            let number = routes.routes.iter().filter(|r| r.is_player_line()).count() + 1;
+            routes.routes.push(BusRoute {
+                name: format!("Line {}", number),
+                edges: Vec::new(),
+                stop_indices: Vec::new(),
+                color: LINE_COLORS[(number - 1) % LINE_COLORS.len()],
+                stops: Vec::new(),
+                service: LineService::Vehicles(2),
+            });
+            info!("Started bus line {}", number);
+            routes.routes.len() - 1
+        }
+    };
+    let before = routes.routes[editing].stops.clone();
+    routes.routes[editing].stops.push(cursor);
+    history.push(HistoryEntry::new(
+        format!("place stop on {}", routes.routes[editing].name),
+        STOP_COST,
+        vec![PlayerAction::BusStops { line: editing, before, after: routes.routes[editing].stops.clone() }],
+    ));
+    state.editing = Some(editing);
+    edited.send(BusLineEdited);
+}
+
+/// Keys for the line being edited: Enter finishes it, Tab picks the next
+/// line, `,` / `.` lower or raise its service, M switches between a bus
+/// count and a headway, L cycles its colour and Delete removes it.
+fn edit_line_service(
+    keys: Res<ButtonInput<KeyCode>>,
+    road_graph: Res<RoadGraph>,
+    config: Res<BusRouteConfig>,
+    mut routes: ResMut<BusRoutes>,
+    mut state: ResMut<TransitToolState>,
+    mut edited: EventWriter<BusLineEdited>,
+) {
+    if keys.just_pressed(KeyCode::Enter) {
+        state.editing = None;
+    }
+    if keys.just_pressed(KeyCode::Tab) {
+        let lines: Vec<usize> = (0..routes.routes.len()).filter(|&i| routes.routes[i].is_player_line()).collect();
+        state.editing = match state.editing.and_then(|current| lines.iter().position(|&i| i == current)) {
+            Some(position) => lines.get(position + 1).copied(),
+            None => lines.first().copied(),
+        };
+    }
+
+    let Some(line) = state.editing.and_then(|index| routes.routes.get_mut(index)) else {
+        return;
+    };
+    let step: i32 = if keys.just_pressed(KeyCode::Period) {
+        1
+    } else if keys.just_pressed(KeyCode::Comma) {
+        -1
+    } else {
+        0
+    };
+    if step != 0 {
+        line.service = match line.service {
+            LineService::Vehicles(count) => {
+                LineService::Vehicles((count as i32 + step).clamp(1, MAX_LINE_BUSES as i32) as usize)
+            }
+            // A higher service is a shorter headway
+            LineService::Headway(headway) => {
+                LineService::Headway((headway - step as f32 * HEADWAY_STEP).clamp(HEADWAY_RANGE.0, HEADWAY_RANGE.1))
+            }
+        };
+    }
+
+    if keys.just_pressed(KeyCode::KeyM) {
+        line.service = match line.service {
+            LineService::Vehicles(_) => LineService::Headway(120.0),
+            // Keep the buses already running
+            LineService::Headway(_) => LineService::Vehicles(buses_wanted(line, &road_graph, &config).max(1)),
+        };
+    }
+
+    if keys.just_pressed(KeyCode::KeyL) {
+        let current = LINE_COLORS.iter().position(|&c| c == line.color);
+        line.color = LINE_COLORS[current.map_or(0, |i| (i + 1) % LINE_COLORS.len())];
+    }
+
+    if keys.just_pressed(KeyCode::Delete) {
+        info!("Removed bus line {}", line.name);
+        line.stops.clear();
+        line.edges.clear();
+        line.stop_indices.clear();
+        state.editing = None;
+    }
+
+    if keys.any_just_pressed([KeyCode::Comma, KeyCode::Period, KeyCode::KeyM, KeyCode::KeyL, KeyCode::Delete]) {
+        edited.send(BusLineEdited);
+    }
+}
+
+/// Describe the player's lines.
+fn lines_report(
+    routes: &BusRoutes,
+    road_graph: &RoadGraph,
+    config: &BusRouteConfig,
+    editing: Option<usize>,
+    network: &TransitNetwork,
+    ridership: &Ridership,
+) -> String {
+    let mut lines = vec!["BUS LINES".to_string()];
+    for (index, route) in routes.routes.iter().enumerate().filter(|(_, r)| r.is_player_line()) {
+        let service = match route.service {
+            LineService::Vehicles(count) => format!("{} buses", count),
+            LineService::Headway(headway) => {
+                format!("every {:.0}s ({} buses)", headway, buses_wanted(route, road_graph, config))
+            }
+        };
+        let served = route.stop_indices.len();
+        let unserved = if served < route.stops.len() {
+            format!(" ({} unreachable)", route.stops.len() - served)
+        } else {
+            String::new()
+        };
+        lines.push(format!(
+            "{}{}: {} stops{}  {:.1} km  {}",
+            if editing == Some(index) { ">" } else { " " },
+            route.name,
+            route.stops.len(),
+            unserved,
+            route.length(road_graph) / 1000.0,
+            service
+        ));
+    }
+    if lines.len() == 1 {
+        lines.push(" Click a road to start a line".to_string());
+    }
+
+    // Riders on every line, player-drawn or not
+    lines.push(String::new());
+    lines.push("RIDERSHIP".to_string());
+    for line in &network.lines {
+        let Some(record) = ridership.lines.get(&line.key) else {
+            continue;
+        };
+        lines.push(format!(
+            " {}: {} riders  {} waiting  {} on board",
+            line.name,
+            record.riders,
+            record.waiting(),
+            record.on_board
+        ));
+    }
+
+    lines.push(String::new());
+    lines.push(format!("Click: add stop (${})  Right click: remove stop", STOP_COST));
+    lines.push("Enter: finish  Tab: next line  ,/.: service -/+".to_string());
+    lines.push("M: buses/headway  L: colour  Del: remove line".to_string());
+    lines.join("\n")
+}
+
+/// Show and refresh the line panel.
+#[allow(clippy::too_many_arguments)]
+fn update_line_panel(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    state: Res<TransitToolState>,
+    routes: Res<BusRoutes>,
+    road_graph: Res<RoadGraph>,
+    config: Res<BusRouteConfig>,
+    network: Res<TransitNetwork>,
+    ridership: Res<Ridership>,
+    mut panel_q: Query<&mut Text, With<LinePanel>>,
+) {
+    let report = lines_report(&routes, &road_graph, &config, state.editing, &network, &ridership);
+    if let Ok(mut text) = panel_q.get_single_mut() {
+        text.0 = report;
+        return;
+    }
+    commands.spawn((
+        Text::new(report),
+        TextFont {
+            font: asset_server.load("fonts/ShareTechMono-Regular.ttf"),
+            font_size: 13.0,
+            ..default()
+        },
+        TextColor(TEXT_COLOR),
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Px(10.0),
+            bottom: Val::Px(40.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            border: UiRect::all(Val::Px(1.0)),
+            ..default()
+        },
+        BackgroundColor(PANEL_BG),
+        BorderColor(BORDER),
+        LinePanel,
+    ));
+}
+
+/// Draw every route and its stops; generated routes are drawn faintly.
+fn draw_lines(
+    mut gizmos: Gizmos,
+    state: Res<TransitToolState>,
+    routes: Res<BusRoutes>,
+    road_graph: Res<RoadGraph>,
+    terrain: Res<HeightMap>,
+) {
+    let lift = |p: Vec2| Vec3::new(p.x, terrain.sample_world(p) + 1.5, p.y);
+    let flat = Quat::from_rotation_x(std::f32::consts::FRAC_PI_2);
+
+    for (index, route) in routes.routes.iter().enumerate() {
+        let editing = state.editing == Some(index);
+        let color = if route.is_player_line() { route.color } else { route.color.with_alpha(0.4) };
+        for &edge in &route.edges {
+            let Some(edge) = road_graph.edge_by_index(edge) else {
+                continue;
+            };
+            gizmos.linestrip(edge.points.iter().map(|&p| lift(p)), color);
+        }
+        for &stop in &route.stops {
+            gizmos.circle(Isometry3d::new(lift(stop), flat), if editing { 6.0 } else { 4.0 }, color);
+        }
+    }
+
+    if let Some(hover) = state.hover {
+        gizmos.circle(Isometry3d::new(lift(hover), flat), 4.0, Color::WHITE);
+    }
+}
+
+/// Finish editing and hide the panel when switching to another tool.
+fn cleanup_on_tool_change(
+    mut commands: Commands,
+    tool: Res<State<ActiveTool>>,
+    mut state: ResMut<TransitToolState>,
+    panel_q: Query<Entity, With<LinePanel>>,
+) {
+    if !tool.is_changed() || *tool.get() == ActiveTool::Transit {
+        return;
+    }
+    for entity in &panel_q {
+        commands.entity(entity).despawn_recursive();
+    }
+    *state = TransitToolState::default();
+}
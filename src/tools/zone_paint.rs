@@ -3,6 +3,7 @@
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 
+use super::history::{CommandHistory, HistoryEntry, PlayerAction};
 use super::{ActiveTool, ToolState, ZoneType};
 use crate::game_state::GameState;
 
@@ -204,6 +205,7 @@ fn apply_zone_on_release(
     active_tool: Res<State<ActiveTool>>,
     config: Res<ZonePaintConfig>,
     mut zone_grid: ResMut<ZoneGrid>,
+    mut history: ResMut<CommandHistory>,
 ) {
     if !mouse.just_released(MouseButton::Left) {
         return;
@@ -224,7 +226,7 @@ fn apply_zone_on_release(
     let min_grid = world_to_grid(rect.min, config.cell_size);
     let max_grid = world_to_grid(rect.max, config.cell_size);
 
-    let mut cells_created = 0;
+    let mut cells_created = Vec::new();
 
     // Create zone cells for each grid position
     for gx in min_grid.x..=max_grid.x {
@@ -254,15 +256,21 @@ fn apply_zone_on_release(
                 .id();
 
             zone_grid.insert(grid_pos, entity);
-            cells_created += 1;
+            cells_created.push(entity);
         }
     }
 
-    if cells_created > 0 {
+    if !cells_created.is_empty() {
         info!(
             "Zoned {} cells as {:?} from {:?} to {:?}",
-            cells_created, zone_type, min_grid, max_grid
+            cells_created.len(), zone_type, min_grid, max_grid
         );
+        // The whole drag is one undo step
+        history.push(HistoryEntry::new(
+            format!("zone {} cells as {:?}", cells_created.len(), zone_type),
+            0,
+            vec![PlayerAction::Spawned(cells_created)],
+        ));
     }
 
     // Clear drag state
@@ -1,17 +1,36 @@
-//! Debug rendering for roads and tensor fields using Bevy gizmos.
+//! Debug rendering for roads, tensor fields, pedestrian flow, parking
+//! occupancy and utility networks using Bevy gizmos. The utility overlay
+//! also lists each network's supply, demand and capacity in a panel.
 
 use bevy::prelude::*;
 
 use crate::procgen::road_generator::RoadsGenerated;
 use crate::procgen::roads::{RoadGraph, RoadType};
 use crate::procgen::tensor::TensorField;
+use crate::simulation::flow_field::CityFlowFields;
+use crate::simulation::parking::{ParkingKind, ParkingSearch, ParkingSupply};
+use crate::simulation::pedestrians::{PedestrianConfig, PedestrianStats};
+use crate::simulation::utilities::{Utility, UtilityNetworks, UtilityService};
+use crate::tools::services::ServiceBuilding;
+use crate::tools::ActiveTool;
 use crate::ui::DebugConfig;
+use crate::world::terrain::HeightMap;
 
 pub struct DebugRenderPlugin;
 
 impl Plugin for DebugRenderPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, (render_roads, render_tensor_field));
+        app.add_systems(
+            Update,
+            (
+                render_roads,
+                render_tensor_field,
+                render_pedestrian_flow,
+                render_parking_occupancy,
+                render_utility_networks,
+                update_utility_panel,
+            ),
+        );
     }
 }
 
@@ -52,6 +71,8 @@ fn render_roads(
             crate::procgen::roads::RoadNodeType::Intersection => Color::srgb(0.0, 1.0, 0.0),
             crate::procgen::roads::RoadNodeType::Endpoint => Color::srgb(1.0, 0.0, 0.0),
             crate::procgen::roads::RoadNodeType::DeadEnd => Color::srgb(1.0, 0.5, 0.0),
+            crate::procgen::roads::RoadNodeType::Roundabout => Color::srgb(0.0, 0.8, 1.0),
+            crate::procgen::roads::RoadNodeType::Ramp => Color::srgb(0.8, 0.0, 1.0),
         };
 
         // Draw cross marker
@@ -105,3 +126,228 @@ fn render_tensor_field(
         y += grid_spacing;
     }
 }
+
+/// Colour for a share of parking spaces taken: green when empty through
+/// yellow to red when full.
+fn occupancy_color(occupancy: f32) -> Color {
+    let t = occupancy.clamp(0.0, 1.0);
+    if t < 0.5 {
+        Color::srgb(t * 2.0, 0.85, 0.2)
+    } else {
+        Color::srgb(1.0, 0.85 * (1.0 - t) * 2.0, 0.2)
+    }
+}
+
+/// Render pedestrian flow: each street's sidewalks coloured by how crowded
+/// they are, over arrows of the flow field toward the shops.
+fn render_pedestrian_flow(
+    config: Res<DebugConfig>,
+    pedestrians: Res<PedestrianConfig>,
+    stats: Res<PedestrianStats>,
+    flow_fields: Res<CityFlowFields>,
+    road_graph: Res<RoadGraph>,
+    terrain: Res<HeightMap>,
+    mut gizmos: Gizmos,
+) {
+    if !config.show_flow_fields {
+        return;
+    }
+    let lift = |p: Vec2, h: f32| Vec3::new(p.x, terrain.sample_world(p) + h, p.y);
+
+    if let Some(field) = &flow_fields.to_commercial {
+        let stride = 4;
+        for y in (0..field.height).step_by(stride) {
+            for x in (0..field.width).step_by(stride) {
+                let direction = field.directions[y * field.width + x];
+                if direction == Vec2::ZERO {
+                    continue;
+                }
+                let center = field.origin + (Vec2::new(x as f32, y as f32) + 0.5) * field.cell_size;
+                let tip = center + direction * field.cell_size * 1.5;
+                gizmos.arrow(lift(center, 0.5), lift(tip, 0.5), Color::srgba(0.3, 0.6, 1.0, 0.5));
+            }
+        }
+    }
+
+    for &edge in stats.per_edge.keys() {
+        let Some(road) = road_graph.edge_by_index(edge) else {
+            continue;
+        };
+        let crowding = stats.density(edge, &road_graph) / pedestrians.crowded_density;
+        let color = occupancy_color(crowding);
+        for side in [1.0, -1.0] {
+            for w in road.points.windows(2) {
+                let dir = (w[1] - w[0]).normalize_or_zero();
+                let offset = Vec2::new(-dir.y, dir.x) * pedestrians.sidewalk_offset * side;
+                gizmos.line(lift(w[0] + offset, 0.6), lift(w[1] + offset, 0.6), color);
+            }
+        }
+    }
+}
+
+/// Render parking occupancy: lots and garages as rings, curbside stretches
+/// as lines, and streets with drivers cruising for a space in magenta.
+/// Shown with the overlay toggle or while the parking tool is active.
+fn render_parking_occupancy(
+    config: Res<DebugConfig>,
+    tool: Res<State<ActiveTool>>,
+    supply: Res<ParkingSupply>,
+    search: Res<ParkingSearch>,
+    road_graph: Res<RoadGraph>,
+    terrain: Res<HeightMap>,
+    mut gizmos: Gizmos,
+) {
+    if !config.show_parking && *tool.get() != ActiveTool::Parking {
+        return;
+    }
+    let lift = |p: Vec2, h: f32| Vec3::new(p.x, terrain.sample_world(p) + h, p.y);
+    let flat = Quat::from_rotation_x(std::f32::consts::FRAC_PI_2);
+
+    for site in &supply.sites {
+        let color = occupancy_color(site.occupancy());
+        match site.kind {
+            ParkingKind::Lot(_) | ParkingKind::Garage(_) => {
+                let radius = 4.0 + (site.capacity as f32).sqrt();
+                gizmos.circle(Isometry3d::new(lift(site.position, 1.0), flat), radius, color);
+            }
+            ParkingKind::Curb { start, end, .. } => {
+                gizmos.line(lift(start, 0.8), lift(end, 0.8), color);
+            }
+        }
+    }
+
+    for (&edge, &cars) in &search.cruising {
+        let Some(road) = road_graph.edge_by_index(edge) else {
+            continue;
+        };
+        let color = Color::srgba(1.0, 0.2, 0.9, (cars * 2.0).clamp(0.2, 1.0));
+        gizmos.linestrip(road.points.iter().map(|&p| lift(p, 1.6)), color);
+    }
+}
+
+/// The utility to show: the one picked with the overlay toggle, or the one
+/// fed by the plant being placed.
+fn shown_utility(config: &DebugConfig, tool: &ActiveTool) -> Option<Utility> {
+    match tool {
+        ActiveTool::PlaceService(service_type) => Utility::fed_by(*service_type).or(config.show_utilities),
+        _ => config.show_utilities,
+    }
+}
+
+/// Render a utility network: each road coloured by the load on the network
+/// running along it, grey where no plant feeds it, with rings around the
+/// plants and a mark on every building it fails to reach.
+#[allow(clippy::too_many_arguments)]
+fn render_utility_networks(
+    config: Res<DebugConfig>,
+    tool: Res<State<ActiveTool>>,
+    networks: Res<UtilityNetworks>,
+    road_graph: Res<RoadGraph>,
+    terrain: Res<HeightMap>,
+    plants: Query<(&ServiceBuilding, &Transform)>,
+    buildings: Query<(&UtilityService, &Transform)>,
+    mut gizmos: Gizmos,
+) {
+    let Some(utility) = shown_utility(&config, tool.get()) else {
+        return;
+    };
+    let lift = |p: Vec2, h: f32| Vec3::new(p.x, terrain.sample_world(p) + h, p.y);
+    let flat = Quat::from_rotation_x(std::f32::consts::FRAC_PI_2);
+
+    for edge in road_graph.edge_indices() {
+        let Some(road) = road_graph.edge_by_index(edge) else {
+            continue;
+        };
+        let color = match networks.edge_network(utility, edge) {
+            Some(network) => occupancy_color(network.load()),
+            None => Color::srgba(0.5, 0.5, 0.5, 0.5),
+        };
+        gizmos.linestrip(road.points.iter().map(|&p| lift(p, 1.2)), color);
+    }
+
+    for (plant, transform) in &plants {
+        if Utility::fed_by(plant.service_type) == Some(utility) {
+            let position = transform.translation.xz();
+            gizmos.circle(Isometry3d::new(lift(position, 1.0), flat), plant.radius, utility.color());
+        }
+    }
+
+    for (service, transform) in &buildings {
+        if !service.has(utility) {
+            let position = transform.translation.xz();
+            gizmos.cross(Isometry3d::new(lift(position, 1.5), flat), 3.0, Color::srgb(1.0, 0.2, 0.2));
+        }
+    }
+}
+
+/// Marker for the utility network panel.
+#[derive(Component)]
+struct UtilityPanel;
+
+fn utility_report(utility: Utility, networks: &UtilityNetworks) -> String {
+    let unit = utility.unit();
+    let (capacity, demand, supply) = networks.totals(utility);
+    let mut lines = vec![format!("{} NETWORKS", utility.name().to_uppercase())];
+    lines.push(format!(" capacity {:.0} {unit}  demand {:.0} {unit}", capacity, demand));
+    lines.push(format!(" supplied {:.0} {unit}", supply));
+    lines.push(format!(" out of reach: {} buildings", networks.unconnected[utility.index()]));
+    let mut shown: Vec<_> = networks.networks.iter().filter(|n| n.utility == utility).collect();
+    shown.sort_by(|a, b| b.capacity.total_cmp(&a.capacity));
+    for (i, network) in shown.iter().enumerate() {
+        lines.push(String::new());
+        lines.push(format!(
+            "#{}  {} plants  load {:.0}%",
+            i + 1,
+            network.producers,
+            network.load() * 100.0
+        ));
+        lines.push(format!(
+            " {:.0} / {:.0} {unit}  demand {:.0} {unit}",
+            network.supply, network.capacity, network.demand
+        ));
+        lines.push(format!(" {} served  {} cut off", network.served, network.unserved));
+    }
+    lines.join("\n")
+}
+
+/// Show the supply, demand and capacity of the networks on the overlay.
+fn update_utility_panel(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    config: Res<DebugConfig>,
+    tool: Res<State<ActiveTool>>,
+    networks: Res<UtilityNetworks>,
+    mut panel_q: Query<(Entity, &mut Text), With<UtilityPanel>>,
+) {
+    let Some(utility) = shown_utility(&config, tool.get()) else {
+        for (entity, _) in &panel_q {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    };
+    let report = utility_report(utility, &networks);
+    if let Ok((_, mut text)) = panel_q.get_single_mut() {
+        text.0 = report;
+        return;
+    }
+    commands.spawn((
+        Text::new(report),
+        TextFont {
+            font: asset_server.load("fonts/ShareTechMono-Regular.ttf"),
+            font_size: 13.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.8, 0.85, 1.0)),
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.0),
+            bottom: Val::Px(40.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            border: UiRect::all(Val::Px(1.0)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.02, 0.02, 0.04, 0.94)),
+        BorderColor(utility.color()),
+        UtilityPanel,
+    ));
+}
@@ -2,14 +2,20 @@
 //!
 //! Menu flow:
 //! 1. Mode selection (Sandbox vs Procedural)
-//! 2. If Procedural: Terrain preset selection
+//! 2. If Procedural: Terrain preset and master seed selection
 //! 3. Start game
 
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::ecs::system::SystemParam;
+use bevy::input::ButtonState;
 use bevy::prelude::*;
 
 use crate::game_state::{GameMode, GameState};
 use crate::render::day_night::TimeOfDay;
 use crate::simulation::SimulationConfig;
+use crate::world::seed::{SeedBundle, DEFAULT_MASTER_SEED};
+
+pub use crate::world::presets::TerrainPreset;
 
 pub struct MenuPlugin;
 
@@ -23,6 +29,8 @@ impl Plugin for MenuPlugin {
                 (
                     handle_mode_selection,
                     handle_terrain_selection,
+                    handle_seed_input,
+                    update_seed_field,
                     handle_back_button,
                     handle_start_game,
                     start_sandbox_immediately,
@@ -55,6 +63,8 @@ pub struct MenuState {
     pub phase: MenuPhase,
     pub selected_mode: Option<GameMode>,
     pub selected_terrain: TerrainPreset,
+    /// Master seed typed by the player (may include a `preset:` prefix).
+    pub seed_input: String,
 }
 
 impl Default for MenuState {
@@ -64,18 +74,11 @@ impl Default for MenuState {
             phase: MenuPhase::ModeSelection,
             selected_mode: None,
             selected_terrain: TerrainPreset::Balanced,
+            seed_input: String::new(),
         }
     }
 }
 
-/// Available terrain presets to start the simulation with.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-pub enum TerrainPreset {
-    Balanced,
-    Coastal,
-    Highlands,
-}
-
 impl TerrainPreset {
     fn label(&self) -> &'static str {
         match self {
@@ -88,7 +91,7 @@ impl TerrainPreset {
     fn subtitle(&self) -> &'static str {
         match self {
             TerrainPreset::Balanced => "Flat build area with gentle variation.",
-            TerrainPreset::Coastal => "Rivers, inlets, and elevated plateaus.",
+            TerrainPreset::Coastal => "River delta fanning out into the sea.",
             TerrainPreset::Highlands => "Steeper slopes and wind corridors.",
         }
     }
@@ -103,9 +106,15 @@ struct ModeButton(GameMode);
 #[derive(Component)]
 struct TerrainButton(TerrainPreset);
 
+#[derive(Component)]
+struct SeedInputText;
+
 #[derive(Component)]
 struct StartButton;
 
+/// Longest master seed the menu accepts.
+const MAX_SEED_LENGTH: usize = 32;
+
 #[derive(Component)]
 struct BackButton;
 
@@ -324,6 +333,59 @@ fn spawn_terrain_selection_content(
             }
         });
 
+    // Master seed field
+    panel
+        .spawn((Node {
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(10.0),
+            ..default()
+        },))
+        .with_children(|row| {
+            row.spawn((
+                Text::new("SEED:"),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(MUTED_TEXT),
+            ));
+
+            row.spawn((
+                Node {
+                    flex_grow: 1.0,
+                    padding: UiRect::axes(Val::Px(10.0), Val::Px(6.0)),
+                    border: UiRect::all(Val::Px(1.5)),
+                    ..default()
+                },
+                BackgroundColor(BUTTON_IDLE),
+                BorderColor(BORDER),
+            ))
+            .with_children(|field| {
+                field.spawn((
+                    Text::new(seed_field_text(&menu_state.seed_input)),
+                    TextFont {
+                        font: font.clone(),
+                        font_size: 16.0,
+                        ..default()
+                    },
+                    TextColor(PRIMARY_TEXT),
+                    SeedInputText,
+                ));
+            });
+        });
+
+    panel.spawn((
+        Text::new("Type any text. The same seed and preset always build the same city."),
+        TextFont {
+            font: font.clone(),
+            font_size: 12.0,
+            ..default()
+        },
+        TextColor(MUTED_TEXT),
+    ));
+
     panel
         .spawn((Node {
             flex_direction: FlexDirection::Row,
@@ -435,7 +497,7 @@ fn spawn_terrain_button(
 
             let tags = match preset {
                 TerrainPreset::Balanced => "Predictable rivers | Low erosion | Grid-friendly",
-                TerrainPreset::Coastal => "Tidal flats | Mixed elevation | Scenic skylines",
+                TerrainPreset::Coastal => "Delta channels | Beaches | Waterfront grid",
                 TerrainPreset::Highlands => "Ridge lines | Wind corridors | Dramatic vistas",
             };
 
@@ -486,6 +548,60 @@ fn handle_terrain_selection(
     }
 }
 
+/// Text shown in the seed field, with a caret.
+fn seed_field_text(input: &str) -> String {
+    if input.is_empty() {
+        format!("_ ({})", DEFAULT_MASTER_SEED)
+    } else {
+        format!("{}_", input)
+    }
+}
+
+/// Type into the seed field while choosing terrain.
+fn handle_seed_input(
+    mut menu_state: ResMut<MenuState>,
+    mut keyboard_events: EventReader<KeyboardInput>,
+) {
+    if menu_state.phase != MenuPhase::TerrainSelection {
+        keyboard_events.clear();
+        return;
+    }
+
+    for event in keyboard_events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Backspace => {
+                menu_state.seed_input.pop();
+            }
+            Key::Space if menu_state.seed_input.len() < MAX_SEED_LENGTH => {
+                menu_state.seed_input.push(' ');
+            }
+            Key::Character(chars) => {
+                for c in chars.chars().filter(|c| !c.is_control()) {
+                    if menu_state.seed_input.len() < MAX_SEED_LENGTH {
+                        menu_state.seed_input.push(c);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn update_seed_field(
+    menu_state: Res<MenuState>,
+    mut fields: Query<&mut Text, With<SeedInputText>>,
+) {
+    if !menu_state.is_changed() {
+        return;
+    }
+    for mut text in &mut fields {
+        **text = seed_field_text(&menu_state.seed_input);
+    }
+}
+
 /// Handle back button clicks.
 fn handle_back_button(
     mut menu_state: ResMut<MenuState>,
@@ -499,15 +615,32 @@ fn handle_back_button(
     }
 }
 
+/// Clock and state switches that leave the menu for a running game.
+#[derive(SystemParam)]
+struct GameStart<'w> {
+    sim: ResMut<'w, SimulationConfig>,
+    tod: ResMut<'w, TimeOfDay>,
+    next_game_state: ResMut<'w, NextState<GameState>>,
+    next_game_mode: ResMut<'w, NextState<GameMode>>,
+}
+
+impl GameStart<'_> {
+    /// Unpause the clocks and enter `mode`.
+    fn start(&mut self, mode: GameMode) {
+        self.sim.paused = false;
+        self.tod.paused = false;
+        self.next_game_state.set(GameState::Playing);
+        self.next_game_mode.set(mode);
+    }
+}
+
 fn handle_start_game(
     mut commands: Commands,
     mut menu_state: ResMut<MenuState>,
     interactions: Query<&Interaction, (With<StartButton>, Changed<Interaction>)>,
     menu_roots: Query<Entity, With<MenuRoot>>,
-    mut sim: ResMut<SimulationConfig>,
-    mut tod: ResMut<TimeOfDay>,
-    mut next_game_state: ResMut<NextState<GameState>>,
-    mut next_game_mode: ResMut<NextState<GameMode>>,
+    mut seeds: ResMut<SeedBundle>,
+    mut game: GameStart,
 ) {
     if !menu_state.active {
         return;
@@ -517,22 +650,17 @@ fn handle_start_game(
         if *interaction == Interaction::Pressed {
             let mode = menu_state.selected_mode.unwrap_or(GameMode::Procedural);
 
-            sim.paused = false;
-            tod.paused = false;
-            menu_state.active = false;
+            // World generation reads the preset and master seed from the bundle
+            *seeds = SeedBundle::parse(&menu_state.seed_input, menu_state.selected_terrain);
 
-            // Set game state and mode
-            next_game_state.set(GameState::Playing);
-            next_game_mode.set(mode);
+            menu_state.active = false;
+            game.start(mode);
 
             for entity in &menu_roots {
                 commands.entity(entity).despawn_recursive();
             }
 
-            info!(
-                "Starting simulation: mode={:?}, terrain={:?}",
-                mode, menu_state.selected_terrain
-            );
+            info!("Starting simulation: mode={:?}, seed={}", mode, *seeds);
         }
     }
 }
@@ -542,22 +670,15 @@ fn start_sandbox_immediately(
     mut commands: Commands,
     mut menu_state: ResMut<MenuState>,
     menu_roots: Query<Entity, With<MenuRoot>>,
-    mut sim: ResMut<SimulationConfig>,
-    mut tod: ResMut<TimeOfDay>,
-    mut next_game_state: ResMut<NextState<GameState>>,
-    mut next_game_mode: ResMut<NextState<GameMode>>,
+    mut game: GameStart,
 ) {
     if !menu_state.active {
         return;
     }
 
     if menu_state.selected_mode == Some(GameMode::Sandbox) {
-        sim.paused = false;
-        tod.paused = false;
         menu_state.active = false;
-
-        next_game_state.set(GameState::Playing);
-        next_game_mode.set(GameMode::Sandbox);
+        game.start(GameMode::Sandbox);
 
         for entity in &menu_roots {
             commands.entity(entity).despawn_recursive();
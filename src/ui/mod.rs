@@ -8,7 +8,9 @@ use bevy::{
 use crate::render::day_night::TimeOfDay;
 use crate::render::gpu_culling::CullStats;
 use crate::render::building_spawner::Building;
+use crate::simulation::utilities::Utility;
 use crate::simulation::SimulationConfig;
+use crate::world::seed::SeedBundle;
 
 pub mod debug_render;
 pub mod menu;
@@ -34,6 +36,7 @@ impl Plugin for UiPlugin {
                     update_frame_stats,
                     update_time_display,
                     update_sim_status,
+                    update_seed_display,
                     handle_hud_buttons,
                     update_hud_button_styles,
                     handle_time_controls,
@@ -51,6 +54,9 @@ pub struct DebugConfig {
     pub show_road_graph: bool,
     pub show_flow_fields: bool,
     pub show_grid: bool,
+    pub show_parking: bool,
+    /// Utility network shown on the overlay, if any.
+    pub show_utilities: Option<Utility>,
 }
 
 impl Default for DebugConfig {
@@ -61,6 +67,8 @@ impl Default for DebugConfig {
             show_road_graph: false, // Disabled - using mesh rendering now
             show_flow_fields: false,
             show_grid: false,
+            show_parking: false,
+            show_utilities: None,
         }
     }
 }
@@ -81,6 +89,10 @@ struct DebugInfoText;
 #[derive(Component)]
 struct SimStatusText;
 
+/// Marker for the world seed text.
+#[derive(Component)]
+struct SeedText;
+
 /// Marker for frame stats text (entities, draw calls, culling).
 #[derive(Component)]
 struct FrameStatsText;
@@ -98,6 +110,8 @@ enum HudAction {
     ToggleRoadGraph,
     ToggleFlow,
     ToggleGrid,
+    ToggleParking,
+    CycleUtilities,
 }
 
 #[derive(Clone, Copy)]
@@ -180,6 +194,17 @@ fn setup_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
                 SimStatusText,
             ));
 
+            parent.spawn((
+                Text::new("SEED: --"),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(HUD_TEXT),
+                SeedText,
+            ));
+
             parent.spawn((
                 Text::new("[P] Pause | [ [ / ] ] Time | [1-4] Dawn/Day/Dusk/Night"),
                 TextFont {
@@ -286,6 +311,8 @@ fn setup_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
                     spawn_hud_button(row, &font, "FLOW", HudAction::ToggleFlow);
                     spawn_hud_button(row, &font, "TENSOR", HudAction::ToggleTensor);
                     spawn_hud_button(row, &font, "ROADS", HudAction::ToggleRoadGraph);
+                    spawn_hud_button(row, &font, "PARKING", HudAction::ToggleParking);
+                    spawn_hud_button(row, &font, "UTILITY", HudAction::CycleUtilities);
                 });
         });
 
@@ -383,6 +410,15 @@ fn handle_hud_buttons(
             HudAction::ToggleGrid => {
                 debug.show_grid = !debug.show_grid;
             }
+            HudAction::ToggleParking => {
+                debug.show_parking = !debug.show_parking;
+            }
+            HudAction::CycleUtilities => {
+                debug.show_utilities = match debug.show_utilities {
+                    None => Some(Utility::Power),
+                    Some(utility) => utility.next(),
+                };
+            }
         }
     }
 }
@@ -400,6 +436,8 @@ fn update_hud_button_styles(
             HudAction::ToggleRoadGraph => debug.show_road_graph,
             HudAction::ToggleFlow => debug.show_flow_fields,
             HudAction::ToggleGrid => debug.show_grid,
+            HudAction::ToggleParking => debug.show_parking,
+            HudAction::CycleUtilities => debug.show_utilities.is_some(),
             HudAction::SpeedDown | HudAction::SpeedUp | HudAction::SetTime(_) => false,
         };
 
@@ -551,6 +589,15 @@ fn update_sim_status(
     }
 }
 
+/// Show the seed code so a city can be reproduced from a screenshot.
+fn update_seed_display(seeds: Res<SeedBundle>, mut query: Query<&mut Text, With<SeedText>>) {
+    if seeds.is_changed() {
+        for mut text in &mut query {
+            **text = format!("SEED: {}", *seeds);
+        }
+    }
+}
+
 fn handle_time_controls(
     keys: Res<ButtonInput<KeyCode>>,
     mut tod: ResMut<TimeOfDay>,
@@ -5,7 +5,10 @@ use bevy::prelude::*;
 use crate::game_state::GameState;
 use crate::simulation::demand::RCIDemand;
 use crate::simulation::economy::CityBudget;
+use crate::simulation::freight::FreightStats;
+use crate::simulation::pedestrians::PedestrianStats;
 use crate::simulation::population::Population;
+use crate::simulation::ridership::{ModeShare, Ridership};
 
 pub struct StatsBarPlugin;
 
@@ -14,7 +17,7 @@ impl Plugin for StatsBarPlugin {
         app.add_systems(OnEnter(GameState::Playing), setup_stats_bar)
             .add_systems(
                 Update,
-                update_stats_bar.run_if(in_state(GameState::Playing)),
+                (update_stats_bar, update_mode_share_text).run_if(in_state(GameState::Playing)),
             );
     }
 }
@@ -28,6 +31,9 @@ struct PopulationText;
 #[derive(Component)]
 struct FundsText;
 
+#[derive(Component)]
+struct ModeShareText;
+
 #[derive(Component)]
 struct DemandMeter(DemandType);
 
@@ -125,6 +131,35 @@ fn setup_stats_bar(mut commands: Commands, asset_server: Res<AssetServer>) {
                 ));
             });
 
+            // Mode share section
+            bar.spawn((Node {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(8.0),
+                ..default()
+            },))
+            .with_children(|section| {
+                section.spawn((
+                    Text::new("TRIPS:"),
+                    TextFont {
+                        font: font.clone(),
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(MUTED_TEXT),
+                ));
+                section.spawn((
+                    Text::new("-"),
+                    TextFont {
+                        font: font.clone(),
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(TEXT_COLOR),
+                    ModeShareText,
+                ));
+            });
+
             // RCI Demand meters
             bar.spawn((Node {
                 flex_direction: FlexDirection::Row,
@@ -237,6 +272,34 @@ fn update_stats_bar(
     }
 }
 
+fn update_mode_share_text(
+    share: Res<ModeShare>,
+    ridership: Res<Ridership>,
+    pedestrians: Res<PedestrianStats>,
+    freight: Res<FreightStats>,
+    mut text: Query<&mut Text, With<ModeShareText>>,
+) {
+    if !share.is_changed() && !ridership.is_changed() && !pedestrians.is_changed() && !freight.is_changed() {
+        return;
+    }
+    let riders: u32 = ridership.lines.values().map(|line| line.riders).sum();
+    for mut text in &mut text {
+        **text = format!(
+            "CAR {:.0}%  TRANSIT {:.0}%  BIKE {:.0}%  WALK {:.0}%  RIDERS {}  PEDS {} ({:.1}/100M PEAK)  TRUCKS {}  SOLD OUT {}/{}",
+            share.car * 100.0,
+            share.transit * 100.0,
+            share.bike * 100.0,
+            share.walk * 100.0,
+            format_number(riders as i64),
+            pedestrians.walking,
+            pedestrians.peak_density,
+            freight.trucks,
+            freight.shops_sold_out,
+            freight.shops
+        );
+    }
+}
+
 fn format_number(n: i64) -> String {
     if n.abs() >= 1_000_000 {
         format!("{:.1}M", n as f64 / 1_000_000.0)
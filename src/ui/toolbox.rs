@@ -5,7 +5,7 @@ use bevy::prelude::*;
 use crate::game_state::GameState;
 use crate::procgen::roads::RoadType;
 use crate::tools::road_draw::RoadDrawConfig;
-use crate::tools::{ActiveTool, ServiceType, ZoneType};
+use crate::tools::{ActiveTool, RoadModifyMode, ServiceType, TerraformMode, ZoneType};
 
 pub struct ToolboxPlugin;
 
@@ -115,6 +115,11 @@ fn setup_toolbox(mut commands: Commands, asset_server: Res<AssetServer>) {
             spawn_road_type_button(panel, &font, "Mj", RoadType::Major, Color::srgb(0.5, 0.5, 0.6));
             spawn_road_type_button(panel, &font, "Mn", RoadType::Minor, Color::srgb(0.4, 0.45, 0.5));
             spawn_road_type_button(panel, &font, "Al", RoadType::Alley, Color::srgb(0.35, 0.38, 0.4));
+            spawn_tool_button(panel, &font, "R+", ActiveTool::RoadModify(RoadModifyMode::Upgrade), RoadModifyMode::Upgrade.color());
+            spawn_tool_button(panel, &font, "R-", ActiveTool::RoadModify(RoadModifyMode::Downgrade), RoadModifyMode::Downgrade.color());
+            spawn_tool_button(panel, &font, "1W", ActiveTool::RoadModify(RoadModifyMode::OneWay), RoadModifyMode::OneWay.color());
+            spawn_tool_button(panel, &font, "Md", ActiveTool::RoadModify(RoadModifyMode::Median), RoadModifyMode::Median.color());
+            spawn_tool_button(panel, &font, "Bk", ActiveTool::RoadModify(RoadModifyMode::BikeLanes), RoadModifyMode::BikeLanes.color());
 
             // Services section
             panel.spawn((
@@ -136,6 +141,30 @@ fn setup_toolbox(mut commands: Commands, asset_server: Res<AssetServer>) {
             spawn_tool_button(panel, &font, "Ho", ActiveTool::PlaceService(ServiceType::Hospital), Color::srgb(0.9, 0.9, 0.9));
             spawn_tool_button(panel, &font, "Sc", ActiveTool::PlaceService(ServiceType::School), Color::srgb(0.9, 0.7, 0.2));
             spawn_tool_button(panel, &font, "Pk", ActiveTool::PlaceService(ServiceType::Park), Color::srgb(0.2, 0.7, 0.3));
+            spawn_tool_button(panel, &font, "Pw", ActiveTool::PlaceService(ServiceType::PowerPlant), ServiceType::PowerPlant.color());
+            spawn_tool_button(panel, &font, "Wt", ActiveTool::PlaceService(ServiceType::WaterTower), ServiceType::WaterTower.color());
+            spawn_tool_button(panel, &font, "Ps", ActiveTool::PlaceService(ServiceType::PumpingStation), ServiceType::PumpingStation.color());
+
+            // Terrain section
+            panel.spawn((
+                Text::new("Terrain:"),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(MUTED_TEXT),
+                Node {
+                    margin: UiRect::top(Val::Px(8.0)),
+                    ..default()
+                },
+            ));
+
+            spawn_tool_button(panel, &font, "T+", ActiveTool::Terraform(TerraformMode::Raise), TerraformMode::Raise.color());
+            spawn_tool_button(panel, &font, "T-", ActiveTool::Terraform(TerraformMode::Lower), TerraformMode::Lower.color());
+            spawn_tool_button(panel, &font, "Fl", ActiveTool::Terraform(TerraformMode::Flatten), TerraformMode::Flatten.color());
+            spawn_tool_button(panel, &font, "Sm", ActiveTool::Terraform(TerraformMode::Smooth), TerraformMode::Smooth.color());
+            spawn_tool_button(panel, &font, "Lv", ActiveTool::Terraform(TerraformMode::Level), TerraformMode::Level.color());
 
             // Other tools
             panel.spawn((
@@ -154,6 +183,11 @@ fn setup_toolbox(mut commands: Commands, asset_server: Res<AssetServer>) {
 
             spawn_tool_button(panel, &font, "X", ActiveTool::Demolish, Color::srgb(0.9, 0.3, 0.3));
             spawn_tool_button(panel, &font, "?", ActiveTool::Query, Color::srgb(0.5, 0.5, 0.5));
+            spawn_tool_button(panel, &font, "Bu", ActiveTool::Transit, Color::srgb(0.3, 0.5, 1.0));
+            spawn_tool_button(panel, &font, "Mt", ActiveTool::Rail, Color::srgb(0.9, 0.2, 0.2));
+            spawn_tool_button(panel, &font, "Pk", ActiveTool::Parking, Color::srgb(0.3, 0.4, 0.9));
+            spawn_tool_button(panel, &font, "Rg", ActiveTool::Connection, Color::srgb(0.2, 0.7, 0.4));
+            spawn_tool_button(panel, &font, "Lm", ActiveTool::Landmark, Color::srgb(0.85, 0.7, 0.3));
         });
 }
 
@@ -206,6 +240,7 @@ fn handle_tool_buttons(
 
 fn handle_keyboard_shortcuts(
     keyboard: Res<ButtonInput<KeyCode>>,
+    current_tool: Res<State<ActiveTool>>,
     mut next_tool: ResMut<NextState<ActiveTool>>,
     mut road_config: ResMut<RoadDrawConfig>,
 ) {
@@ -242,10 +277,61 @@ fn handle_keyboard_shortcuts(
     if keyboard.just_pressed(KeyCode::KeyX) {
         next_tool.set(ActiveTool::Demolish);
     }
+    // H cycles terraform modes (T is taken by the camera)
+    if keyboard.just_pressed(KeyCode::KeyH) {
+        let mode = match current_tool.get() {
+            ActiveTool::Terraform(TerraformMode::Raise) => TerraformMode::Lower,
+            ActiveTool::Terraform(TerraformMode::Lower) => TerraformMode::Flatten,
+            ActiveTool::Terraform(TerraformMode::Flatten) => TerraformMode::Smooth,
+            ActiveTool::Terraform(TerraformMode::Smooth) => TerraformMode::Level,
+            _ => TerraformMode::Raise,
+        };
+        next_tool.set(ActiveTool::Terraform(mode));
+    }
+    // U cycles road modification modes
+    if keyboard.just_pressed(KeyCode::KeyU) {
+        let mode = match current_tool.get() {
+            ActiveTool::RoadModify(RoadModifyMode::Upgrade) => RoadModifyMode::Downgrade,
+            ActiveTool::RoadModify(RoadModifyMode::Downgrade) => RoadModifyMode::OneWay,
+            ActiveTool::RoadModify(RoadModifyMode::OneWay) => RoadModifyMode::Median,
+            ActiveTool::RoadModify(RoadModifyMode::Median) => RoadModifyMode::BikeLanes,
+            _ => RoadModifyMode::Upgrade,
+        };
+        next_tool.set(ActiveTool::RoadModify(mode));
+    }
     // Note: Q conflicts with camera rotate, use V for Query/View
     if keyboard.just_pressed(KeyCode::KeyV) {
         next_tool.set(ActiveTool::Query);
     }
+    // J for the transit (bus line) tool
+    if keyboard.just_pressed(KeyCode::KeyJ) {
+        next_tool.set(ActiveTool::Transit);
+    }
+    // K for the rail (track, station and metro line) tool
+    if keyboard.just_pressed(KeyCode::KeyK) {
+        next_tool.set(ActiveTool::Rail);
+    }
+    // O for the parking (lot and garage) tool
+    if keyboard.just_pressed(KeyCode::KeyO) {
+        next_tool.set(ActiveTool::Parking);
+    }
+    // 5 for the regional connection tool
+    if keyboard.just_pressed(KeyCode::Digit5) {
+        next_tool.set(ActiveTool::Connection);
+    }
+    // 6 for the landmark tool
+    if keyboard.just_pressed(KeyCode::Digit6) {
+        next_tool.set(ActiveTool::Landmark);
+    }
+    // 7 cycles the utility plants
+    if keyboard.just_pressed(KeyCode::Digit7) {
+        let service = match current_tool.get() {
+            ActiveTool::PlaceService(ServiceType::PowerPlant) => ServiceType::WaterTower,
+            ActiveTool::PlaceService(ServiceType::WaterTower) => ServiceType::PumpingStation,
+            _ => ServiceType::PowerPlant,
+        };
+        next_tool.set(ActiveTool::PlaceService(service));
+    }
 
     // Escape to deselect
     if keyboard.just_pressed(KeyCode::Escape) {
@@ -4,14 +4,46 @@
 
 use bevy::prelude::*;
 
+use crate::game_state::GameMode;
+use crate::procgen::river::generate_river;
+
 pub mod grid;
+pub mod presets;
+pub mod seed;
 pub mod terrain;
 
 pub struct WorldPlugin;
 
 impl Plugin for WorldPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<WorldConfig>();
+        app.init_resource::<WorldConfig>()
+            .init_resource::<seed::SeedBundle>()
+            .init_resource::<terrain::HeightMap>()
+            .add_event::<terrain::TerrainModified>()
+            // Each new world is configured from the seed bundle, then gets a
+            // fresh height map (after the river is laid out)
+            .add_systems(
+                OnEnter(GameMode::Procedural),
+                (
+                    (seed::apply_seed_bundle, seed::reseed_subsystems)
+                        .chain()
+                        .before(generate_river),
+                    terrain::regenerate_height_map.after(generate_river),
+                ),
+            )
+            .add_systems(
+                OnEnter(GameMode::Sandbox),
+                (
+                    seed::apply_seed_bundle,
+                    seed::reseed_subsystems,
+                    terrain::regenerate_height_map,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                (terrain::publish_terrain_edits, terrain::follow_terrain_edits).chain(),
+            );
     }
 }
 
@@ -0,0 +1,103 @@
+//! Terrain presets offered by the main menu.
+//!
+//! A preset decides the character of a procedural world: how rough the
+//! terrain is, whether the river ends in a delta and a sea, and how the road
+//! tensor field balances grid, radial, water and contour influences.
+
+use bevy::prelude::*;
+
+use crate::procgen::river::RiverConfig;
+use crate::procgen::road_generator::RoadGenConfig;
+use crate::world::terrain::TerrainConfig;
+
+/// Available terrain presets to start the simulation with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TerrainPreset {
+    /// Gentle plains split by a single river.
+    #[default]
+    Balanced,
+    /// Low-lying coast where the river fans out into a delta.
+    Coastal,
+    /// Steep hills with a narrow river gorge.
+    Highlands,
+}
+
+impl TerrainPreset {
+    /// Short identifier used in seed strings and logs.
+    pub fn key(&self) -> &'static str {
+        match self {
+            TerrainPreset::Balanced => "balanced",
+            TerrainPreset::Coastal => "coastal",
+            TerrainPreset::Highlands => "highlands",
+        }
+    }
+
+    /// Parse a preset from its `key`.
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "balanced" => Some(TerrainPreset::Balanced),
+            "coastal" => Some(TerrainPreset::Coastal),
+            "highlands" => Some(TerrainPreset::Highlands),
+            _ => None,
+        }
+    }
+
+    /// Configure terrain, river and road generation for this preset.
+    ///
+    /// Every field the preset cares about is reset, so applying a preset is
+    /// idempotent. Seeds are left untouched.
+    pub fn apply(&self, terrain: &mut TerrainConfig, river: &mut RiverConfig, roads: &mut RoadGenConfig) {
+        *terrain = TerrainConfig {
+            seed: terrain.seed,
+            ..TerrainConfig::default()
+        };
+        *river = RiverConfig {
+            seed: river.seed,
+            ..RiverConfig::default()
+        };
+        *roads = RoadGenConfig::default();
+
+        match self {
+            TerrainPreset::Balanced => {}
+            TerrainPreset::Coastal => {
+                // Low, smooth land so the coast and delta dominate
+                terrain.height_scale = 4.0;
+                terrain.noise_scale = 0.005;
+                terrain.octaves = 3;
+
+                river.river_width = 36.0;
+                river.meander_amplitude = 55.0;
+                river.delta_channels = 4;
+                river.delta_start = 0.5;
+                river.delta_spread = 65.0;
+                river.coastline = true;
+                river.shore_position = 0.78;
+                river.shore_roughness = 30.0;
+                river.beach_width = 45.0;
+
+                // Streets follow the waterfront; downtown sits near the river mouth
+                roads.water_influence = 0.02;
+                roads.radial_decay = 0.012;
+                roads.downtown_center = Vec2::new(60.0, 40.0);
+                roads.contour_sensitivity = 6.0;
+            }
+            TerrainPreset::Highlands => {
+                // Tall, rugged relief
+                terrain.height_scale = 30.0;
+                terrain.noise_scale = 0.006;
+                terrain.octaves = 6;
+
+                river.river_width = 18.0;
+                river.width_variation = 0.2;
+                river.meander_amplitude = 110.0;
+                river.bank_slope_width = 25.0;
+
+                // Roads hug contours and switch back sooner; weaker grid
+                roads.contour_sensitivity = 28.0;
+                roads.switchback_length = 40.0;
+                roads.radial_decay = 0.015;
+                roads.water_influence = 0.008;
+            }
+        }
+    }
+}
@@ -0,0 +1,247 @@
+//! Seed bundle: everything needed to regenerate a procedural world.
+//!
+//! A single master seed string, together with the terrain preset, derives
+//! the seed of every randomised subsystem. The bundle is written as a short
+//! code (`coastal:harbour`) that the HUD shows and the menu accepts, so a
+//! city from a bug report can be rebuilt exactly.
+
+use std::fmt;
+
+use bevy::prelude::*;
+
+use super::presets::TerrainPreset;
+use crate::procgen::building_factory::BuildingFactoryConfig;
+use crate::procgen::lot_engine::LotEngineConfig;
+use crate::procgen::river::RiverConfig;
+use crate::procgen::road_generator::RoadGenConfig;
+use crate::render::balconies::BalconyConfig;
+use crate::render::billboards::BillboardConfig;
+use crate::render::building_spawner::BuildingConfig;
+use crate::render::bus_stops::BusStopConfig;
+use crate::render::construction_sites::ConstructionConfig;
+use crate::render::elevated_rail::ElevatedRailConfig;
+use crate::render::entrance_lights::EntranceLightConfig;
+use crate::render::graffiti::GraffitiConfig;
+use crate::render::landmarks::LandmarkConfig;
+use crate::render::nature_details::NatureDetailsConfig;
+use crate::render::neon_signs::NeonSignConfig;
+use crate::render::parked_cars::ParkedCarConfig;
+use crate::render::parking_garages::ParkingGarageConfig;
+use crate::render::parking_lots::ParkingLotConfig;
+use crate::render::rooftop_details::RooftopDetailConfig;
+use crate::render::signage::SignageConfig;
+use crate::render::storefronts::StorefrontConfig;
+use crate::render::street_amenities::StreetAmenitiesConfig;
+use crate::render::street_details::StreetDetailsConfig;
+use crate::render::street_furniture::StreetFurnitureConfig;
+use crate::render::street_trees::StreetTreeConfig;
+use crate::render::street_vendors::StreetVendorConfig;
+use crate::render::subway_entrances::SubwayEntranceConfig;
+use crate::render::utilities::UtilitiesConfig;
+use crate::render::vehicle_lights::VehicleLightConfig;
+use crate::render::window_lights::WindowLightConfig;
+use crate::simulation::bus_routes::BusRouteConfig;
+use crate::simulation::citizens::CitizenConfig;
+use crate::simulation::cycling::CyclingConfig;
+use crate::simulation::freight::FreightConfig;
+use crate::simulation::pedestrians::PedestrianConfig;
+use crate::simulation::region::RegionConfig;
+use crate::simulation::ridership::RidershipConfig;
+use crate::simulation::tourism::TourismConfig;
+use crate::simulation::traffic::TrafficCaState;
+use crate::simulation::vehicle_traffic::MovingVehicleConfig;
+use crate::simulation::zones::ZoneGrowthConfig;
+use crate::world::terrain::TerrainConfig;
+
+/// Master seed used when the player leaves the seed field empty.
+pub const DEFAULT_MASTER_SEED: &str = "isocity";
+
+/// The inputs a procedural world was generated from.
+///
+/// Applying the same bundle again reproduces the same city.
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct SeedBundle {
+    /// Master seed string entered in the menu.
+    pub master: String,
+    /// Landscape preset chosen in the menu.
+    pub preset: TerrainPreset,
+}
+
+impl Default for SeedBundle {
+    fn default() -> Self {
+        Self {
+            master: DEFAULT_MASTER_SEED.to_string(),
+            preset: TerrainPreset::default(),
+        }
+    }
+}
+
+impl SeedBundle {
+    /// Deterministic seed for a named subsystem.
+    ///
+    /// Uses FNV-1a and a SplitMix64 finaliser rather than `std`'s hasher,
+    /// whose output is not guaranteed to be stable between Rust releases.
+    pub fn derive(&self, subsystem: &str) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in self.master.bytes().chain([0]).chain(subsystem.bytes()) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+
+        let mut z = hash.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Derived seed for subsystems that take 32-bit seeds.
+    pub fn derive_u32(&self, subsystem: &str) -> u32 {
+        (self.derive(subsystem) >> 32) as u32
+    }
+
+    /// Parse a seed code. `preset:master` sets both; a bare string only
+    /// replaces the master seed and keeps `fallback`'s preset.
+    pub fn parse(code: &str, fallback: TerrainPreset) -> Self {
+        let code = code.trim();
+        if let Some((preset, master)) = code.split_once(':') {
+            if let Some(preset) = TerrainPreset::from_key(preset) {
+                return Self {
+                    master: master.to_string(),
+                    preset,
+                };
+            }
+        }
+
+        Self {
+            master: if code.is_empty() {
+                DEFAULT_MASTER_SEED.to_string()
+            } else {
+                code.to_string()
+            },
+            preset: fallback,
+        }
+    }
+}
+
+impl fmt::Display for SeedBundle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.preset.key(), self.master)
+    }
+}
+
+/// Configure world generation from the seed bundle before anything is generated.
+pub fn apply_seed_bundle(
+    bundle: Res<SeedBundle>,
+    mut terrain: ResMut<TerrainConfig>,
+    mut river: ResMut<RiverConfig>,
+    mut roads: ResMut<RoadGenConfig>,
+) {
+    bundle.preset.apply(&mut terrain, &mut river, &mut roads);
+    terrain.seed = bundle.derive_u32("terrain");
+    river.seed = bundle.derive_u32("river");
+
+    info!("Generating world from seed {}", *bundle);
+}
+
+/// Derive every other subsystem's seed from the bundle.
+pub fn reseed_subsystems(world: &mut World) {
+    let bundle = world.resource::<SeedBundle>().clone();
+
+    // Procedural generation
+    reseed(world, &bundle, "lots", |c: &mut LotEngineConfig, s| c.seed = s);
+    reseed(world, &bundle, "building_factory", |c: &mut BuildingFactoryConfig, s| c.seed = s);
+    reseed(world, &bundle, "buildings", |c: &mut BuildingConfig, s| c.seed = s);
+
+    // Simulation
+    reseed(world, &bundle, "citizens", |c: &mut CitizenConfig, s| c.seed = s);
+    reseed(world, &bundle, "bus_routes", |c: &mut BusRouteConfig, s| c.seed = s);
+    reseed(world, &bundle, "pedestrians", |c: &mut PedestrianConfig, s| c.seed = s);
+    reseed(world, &bundle, "vehicles", |c: &mut MovingVehicleConfig, s| c.seed = s);
+    reseed(world, &bundle, "zone_growth", |c: &mut ZoneGrowthConfig, s| c.seed = s);
+    reseed(world, &bundle, "traffic_ca", |c: &mut TrafficCaState, s| c.rng_seed = s);
+    reseed(world, &bundle, "ridership", |c: &mut RidershipConfig, s| c.seed = s);
+    reseed(world, &bundle, "cycling", |c: &mut CyclingConfig, s| c.seed = s);
+    reseed(world, &bundle, "freight", |c: &mut FreightConfig, s| c.seed = s);
+    reseed(world, &bundle, "region", |c: &mut RegionConfig, s| c.seed = s);
+    reseed(world, &bundle, "tourism", |c: &mut TourismConfig, s| c.seed = s);
+
+    // Set dressing
+    reseed(world, &bundle, "balconies", |c: &mut BalconyConfig, s| c.seed = s);
+    reseed(world, &bundle, "billboards", |c: &mut BillboardConfig, s| c.seed = s);
+    reseed(world, &bundle, "bus_stops", |c: &mut BusStopConfig, s| c.seed = s);
+    reseed(world, &bundle, "construction", |c: &mut ConstructionConfig, s| c._seed = s);
+    reseed(world, &bundle, "elevated_rail", |c: &mut ElevatedRailConfig, s| c.seed = s);
+    reseed(world, &bundle, "entrance_lights", |c: &mut EntranceLightConfig, s| c.seed = s);
+    reseed(world, &bundle, "graffiti", |c: &mut GraffitiConfig, s| c.seed = s);
+    reseed(world, &bundle, "landmarks", |c: &mut LandmarkConfig, s| c.seed = s);
+    reseed(world, &bundle, "nature", |c: &mut NatureDetailsConfig, s| c.seed = s);
+    reseed(world, &bundle, "neon_signs", |c: &mut NeonSignConfig, s| c.seed = s);
+    reseed(world, &bundle, "parked_cars", |c: &mut ParkedCarConfig, s| c.seed = s);
+    reseed(world, &bundle, "parking_garages", |c: &mut ParkingGarageConfig, s| c.seed = s);
+    reseed(world, &bundle, "parking_lots", |c: &mut ParkingLotConfig, s| c.seed = s);
+    reseed(world, &bundle, "rooftops", |c: &mut RooftopDetailConfig, s| c.seed = s);
+    reseed(world, &bundle, "signage", |c: &mut SignageConfig, s| c.seed = s);
+    reseed(world, &bundle, "storefronts", |c: &mut StorefrontConfig, s| c.seed = s);
+    reseed(world, &bundle, "street_amenities", |c: &mut StreetAmenitiesConfig, s| c.seed = s);
+    reseed(world, &bundle, "street_details", |c: &mut StreetDetailsConfig, s| c.seed = s);
+    reseed(world, &bundle, "street_furniture", |c: &mut StreetFurnitureConfig, s| c.seed = s);
+    reseed(world, &bundle, "street_trees", |c: &mut StreetTreeConfig, s| c.seed = s);
+    reseed(world, &bundle, "street_vendors", |c: &mut StreetVendorConfig, s| c.seed = s);
+    reseed(world, &bundle, "subway_entrances", |c: &mut SubwayEntranceConfig, s| c.seed = s);
+    reseed(world, &bundle, "utilities", |c: &mut UtilitiesConfig, s| c.seed = s);
+    reseed(world, &bundle, "vehicle_lights", |c: &mut VehicleLightConfig, s| c.seed = s);
+    reseed(world, &bundle, "window_lights", |c: &mut WindowLightConfig, s| c.seed = s);
+}
+
+/// Set one resource's seed, if the resource exists.
+fn reseed<R: Resource>(world: &mut World, bundle: &SeedBundle, subsystem: &str, set: impl FnOnce(&mut R, u64)) {
+    if let Some(mut resource) = world.get_resource_mut::<R>() {
+        set(&mut resource, bundle.derive(subsystem));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derived_seeds_are_stable_and_distinct() {
+        let bundle = SeedBundle::parse("harbour", TerrainPreset::Coastal);
+        assert_eq!(bundle.derive("river"), SeedBundle::parse("harbour", TerrainPreset::Balanced).derive("river"));
+        assert_ne!(bundle.derive("river"), bundle.derive("terrain"));
+        assert_ne!(bundle.derive("river"), SeedBundle::parse("harbor", TerrainPreset::Coastal).derive("river"));
+    }
+
+    #[test]
+    fn seed_code_round_trips() {
+        let bundle = SeedBundle::parse("bug 1234", TerrainPreset::Highlands);
+        assert_eq!(bundle.to_string(), "highlands:bug 1234");
+        assert_eq!(SeedBundle::parse(&bundle.to_string(), TerrainPreset::Balanced), bundle);
+        assert_eq!(SeedBundle::parse("  ", TerrainPreset::Balanced), SeedBundle::default());
+    }
+
+    #[test]
+    fn simulation_seeds_come_from_the_master_seed() {
+        let bundle = SeedBundle::parse("harbour", TerrainPreset::Balanced);
+        let mut world = World::new();
+        world.insert_resource(bundle.clone());
+        world.init_resource::<RidershipConfig>();
+        world.init_resource::<CyclingConfig>();
+        world.init_resource::<FreightConfig>();
+        world.init_resource::<RegionConfig>();
+        world.init_resource::<TourismConfig>();
+        reseed_subsystems(&mut world);
+
+        let seeds = [
+            ("ridership", world.resource::<RidershipConfig>().seed, RidershipConfig::default().seed),
+            ("cycling", world.resource::<CyclingConfig>().seed, CyclingConfig::default().seed),
+            ("freight", world.resource::<FreightConfig>().seed, FreightConfig::default().seed),
+            ("region", world.resource::<RegionConfig>().seed, RegionConfig::default().seed),
+            ("tourism", world.resource::<TourismConfig>().seed, TourismConfig::default().seed),
+        ];
+        for (subsystem, seed, default) in seeds {
+            assert_ne!(seed, default, "{subsystem} kept its default seed");
+            assert_eq!(seed, bundle.derive(subsystem));
+        }
+    }
+}
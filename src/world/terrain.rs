@@ -1,17 +1,98 @@
 //! Terrain generation and height maps.
+//!
+//! The `HeightMap` resource is the single source of truth for ground height.
+//! Everything that sits on the terrain samples it instead of re-evaluating
+//! noise, and every edit (river carving, road grading, terraforming) goes
+//! through it so a `TerrainModified` event reaches all consumers.
 
 #![allow(dead_code)]
 
 use bevy::prelude::*;
 use noise::{NoiseFn, Perlin};
 
-/// Terrain height map.
+use crate::procgen::river::River;
+
+/// Configuration for terrain generation.
 #[derive(Resource)]
+pub struct TerrainConfig {
+    /// Size of the terrain in world units.
+    pub size: f32,
+    /// Number of subdivisions per axis.
+    pub resolution: u32,
+    /// Maximum height variation from noise.
+    pub height_scale: f32,
+    /// Noise frequency (higher = more hills).
+    pub noise_scale: f32,
+    /// Number of octaves for fractal noise.
+    pub octaves: u32,
+    /// Random seed for noise.
+    pub seed: u32,
+}
+
+impl Default for TerrainConfig {
+    fn default() -> Self {
+        Self {
+            size: 600.0,
+            resolution: 128,
+            height_scale: 8.0,
+            noise_scale: 0.008,
+            octaves: 4,
+            seed: 42,
+        }
+    }
+}
+
+/// Sent when a region of the height map has been edited.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct TerrainModified {
+    /// World-space XZ bounds of the edited samples.
+    pub region: Rect,
+}
+
+/// Keeps an entity at a fixed clearance above the ground when the terrain
+/// under it is edited.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct TerrainAnchor {
+    /// Height of the entity's translation above the terrain.
+    pub offset: f32,
+}
+
+impl TerrainAnchor {
+    /// Anchor an entity at `translation`, keeping its current clearance.
+    pub fn at(heights: &HeightMap, translation: Vec3) -> Self {
+        Self {
+            offset: translation.y - heights.sample_world(translation.xz()),
+        }
+    }
+}
+
+/// Terrain height map.
+///
+/// Heights are stored on a regular grid of `width * height` samples. Grid
+/// coordinates map to world XZ through `origin` and `cell_size`, so the same
+/// map can be sampled either in grid space (`sample`) or in world space
+/// (`sample_world`).
+#[derive(Resource, Clone, Debug)]
 pub struct HeightMap {
     pub width: usize,
     pub height: usize,
     pub data: Vec<f32>,
     pub scale: f32,
+    /// World XZ position of grid sample (0, 0).
+    pub origin: Vec2,
+    /// World distance between adjacent grid samples.
+    pub cell_size: f32,
+    /// Bounds of edits not yet announced through `TerrainModified`.
+    dirty: Option<Rect>,
+}
+
+impl FromWorld for HeightMap {
+    fn from_world(world: &mut World) -> Self {
+        match world.get_resource::<TerrainConfig>() {
+            Some(config) => Self::from_terrain_config(config),
+            None => Self::from_terrain_config(&TerrainConfig::default()),
+        }
+    }
 }
 
 impl HeightMap {
@@ -47,7 +128,187 @@ impl HeightMap {
             height,
             data,
             scale,
+            origin: Vec2::ZERO,
+            cell_size: 1.0,
+            dirty: None,
+        }
+    }
+
+    /// Build a height map matching the terrain mesh described by `config`.
+    ///
+    /// One sample is taken per terrain mesh vertex using the same fractal
+    /// Perlin noise, so bilinear sampling reproduces the rendered surface.
+    pub fn from_terrain_config(config: &TerrainConfig) -> Self {
+        let perlin = Perlin::new(config.seed);
+        let samples = config.resolution as usize + 1;
+        let cell_size = config.size / config.resolution as f32;
+        let origin = Vec2::splat(-config.size / 2.0);
+
+        let mut data = Vec::with_capacity(samples * samples);
+        for z in 0..samples {
+            for x in 0..samples {
+                let world = origin + Vec2::new(x as f32, z as f32) * cell_size;
+                data.push(fractal_height(
+                    &perlin,
+                    world,
+                    config.noise_scale,
+                    config.height_scale,
+                    config.octaves,
+                ));
+            }
+        }
+
+        Self {
+            width: samples,
+            height: samples,
+            data,
+            scale: config.height_scale,
+            origin,
+            cell_size,
+            dirty: None,
+        }
+    }
+
+    /// Build the height map for a new world: noise terrain with the river
+    /// channel carved in. The whole map is marked as modified.
+    pub fn for_world(config: &TerrainConfig, river: &River) -> Self {
+        let mut heights = Self::from_terrain_config(config);
+        heights.carve_river(river);
+        heights.mark_dirty(heights.bounds());
+        heights
+    }
+
+    /// World-space XZ extent covered by the map.
+    pub fn bounds(&self) -> Rect {
+        let extent = Vec2::new((self.width - 1) as f32, (self.height - 1) as f32) * self.cell_size;
+        Rect::from_corners(self.origin, self.origin + extent)
+    }
+
+    /// Convert a world XZ position to (fractional) grid coordinates.
+    pub fn world_to_grid(&self, pos: Vec2) -> Vec2 {
+        (pos - self.origin) / self.cell_size
+    }
+
+    /// Convert grid coordinates to a world XZ position.
+    pub fn grid_to_world(&self, x: usize, y: usize) -> Vec2 {
+        self.origin + Vec2::new(x as f32, y as f32) * self.cell_size
+    }
+
+    /// Height stored at a grid sample.
+    pub fn get(&self, x: usize, y: usize) -> f32 {
+        self.data[y * self.width + x]
+    }
+
+    /// Overwrite the height stored at a grid sample.
+    pub fn set(&mut self, x: usize, y: usize, value: f32) {
+        self.data[y * self.width + x] = value;
+
+        // Bilinear sampling spreads a sample's influence over adjacent cells
+        let pos = self.grid_to_world(x, y);
+        let reach = Vec2::splat(self.cell_size);
+        self.mark_dirty(Rect::from_corners(pos - reach, pos + reach));
+    }
+
+    /// Record that a world-space region changed outside of `set`.
+    pub fn mark_dirty(&mut self, region: Rect) {
+        self.dirty = Some(match self.dirty {
+            Some(existing) => existing.union(region),
+            None => region,
+        });
+    }
+
+    /// Whether edits are waiting to be announced.
+    pub fn has_pending_edits(&self) -> bool {
+        self.dirty.is_some()
+    }
+
+    /// Take the accumulated edit region, leaving the map clean.
+    pub fn take_dirty(&mut self) -> Option<Rect> {
+        self.dirty.take()
+    }
+
+    /// Sample height at a world XZ position (bilinear interpolation).
+    pub fn sample_world(&self, pos: Vec2) -> f32 {
+        if !pos.is_finite() {
+            return 0.0;
         }
+        let grid = self.world_to_grid(pos);
+        self.sample(grid.x, grid.y)
+    }
+
+    /// Height gradient (dh/dx, dh/dz) at a world position.
+    pub fn gradient(&self, pos: Vec2) -> Vec2 {
+        let e = self.cell_size;
+        let dx = self.sample_world(pos + Vec2::X * e) - self.sample_world(pos - Vec2::X * e);
+        let dz = self.sample_world(pos + Vec2::Y * e) - self.sample_world(pos - Vec2::Y * e);
+        Vec2::new(dx, dz) / (2.0 * e)
+    }
+
+    /// Steepest slope (rise over run) at a world position.
+    pub fn slope(&self, pos: Vec2) -> f32 {
+        self.gradient(pos).length()
+    }
+
+    /// Upward surface normal at a world position.
+    pub fn normal(&self, pos: Vec2) -> Vec3 {
+        let gradient = self.gradient(pos);
+        Vec3::new(-gradient.x, 1.0, -gradient.y).normalize()
+    }
+
+    /// Lower the terrain into the river channels with sloped banks, and
+    /// below the shoreline into a sea bed with a beach.
+    pub fn carve_river(&mut self, river: &River) {
+        if river.is_empty() {
+            return;
+        }
+
+        let (xs, ys) = self.grid_range(river.bounds.min, river.bounds.max);
+        for y in ys {
+            for x in xs.clone() {
+                let pos = self.grid_to_world(x, y);
+                let mut height = self.get(x, y);
+
+                if let Some(shore) = &river.shoreline {
+                    let shore_dist = shore.signed_distance(pos);
+                    if shore_dist < 0.0 {
+                        // Sea bed shelves away from the beach
+                        let depth = 1.0 + (-shore_dist / shore.beach_width).min(3.0);
+                        height = river.water_level - depth;
+                    } else if shore_dist < shore.beach_width {
+                        let t = shore_dist / shore.beach_width;
+                        let t_smooth = t * t * (3.0 - 2.0 * t);
+                        let beach_bottom = river.water_level - 1.0;
+                        height = beach_bottom + (height - beach_bottom) * t_smooth;
+                    }
+                }
+
+                let river_dist = river.channel_distance(pos);
+                if river_dist < 0.0 {
+                    // Inside river - set to riverbed (below water level)
+                    height = height.min(river.water_level - 1.0);
+                } else if river_dist < river.bank_slope_width {
+                    // Bank slope - smooth transition from riverbed to terrain
+                    let t = river_dist / river.bank_slope_width;
+                    // Ease in/out for smoother banks
+                    let t_smooth = t * t * (3.0 - 2.0 * t);
+                    let bank_bottom = river.water_level - 0.5;
+                    height = height.min(bank_bottom + (height - bank_bottom) * t_smooth);
+                }
+
+                if height != self.get(x, y) {
+                    self.set(x, y, height);
+                }
+            }
+        }
+    }
+
+    /// Grid sample range covering a world-space rectangle, clamped to the map.
+    pub fn grid_range(&self, min: Vec2, max: Vec2) -> (std::ops::RangeInclusive<usize>, std::ops::RangeInclusive<usize>) {
+        let lo = self.world_to_grid(min).floor().max(Vec2::ZERO);
+        let hi = self.world_to_grid(max).ceil();
+        let x_max = (hi.x.max(0.0) as usize).min(self.width - 1);
+        let y_max = (hi.y.max(0.0) as usize).min(self.height - 1);
+        ((lo.x as usize).min(x_max)..=x_max, (lo.y as usize).min(y_max)..=y_max)
     }
 
     /// Sample height at a position (bilinear interpolation).
@@ -75,9 +336,71 @@ impl HeightMap {
     }
 }
 
+/// Fractal Perlin noise height, matching the terrain mesh generator.
+fn fractal_height(perlin: &Perlin, pos: Vec2, scale: f32, height_scale: f32, octaves: u32) -> f32 {
+    let mut height = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = scale;
+    let mut max_amplitude = 0.0;
+
+    for _ in 0..octaves {
+        let sample_x = pos.x as f64 * frequency as f64;
+        let sample_z = pos.y as f64 * frequency as f64;
+        height += perlin.get([sample_x, sample_z]) as f32 * amplitude;
+        max_amplitude += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+
+    (height / max_amplitude) * height_scale
+}
+
 /// Water body definition.
 #[derive(Clone, Debug)]
 pub struct WaterBody {
     pub boundary: Vec<Vec2>,
     pub water_level: f32,
 }
+
+/// Regenerate the height map when a new world starts.
+pub fn regenerate_height_map(
+    config: Res<TerrainConfig>,
+    river: Res<River>,
+    mut heights: ResMut<HeightMap>,
+) {
+    *heights = HeightMap::for_world(&config, &river);
+    info!(
+        "Height map generated: {}x{} samples, {:.1}m cells",
+        heights.width, heights.height, heights.cell_size
+    );
+}
+
+/// Announce accumulated height map edits as a `TerrainModified` event.
+pub fn publish_terrain_edits(
+    mut heights: ResMut<HeightMap>,
+    mut events: EventWriter<TerrainModified>,
+) {
+    if !heights.has_pending_edits() {
+        return;
+    }
+    // Taking the region is bookkeeping, not a terrain change
+    if let Some(region) = heights.bypass_change_detection().take_dirty() {
+        events.send(TerrainModified { region });
+    }
+}
+
+/// Re-seat anchored entities on the terrain after it has been edited.
+pub fn follow_terrain_edits(
+    mut events: EventReader<TerrainModified>,
+    heights: Res<HeightMap>,
+    mut anchored: Query<(&TerrainAnchor, &mut Transform)>,
+) {
+    for event in events.read() {
+        for (anchor, mut transform) in &mut anchored {
+            let pos = transform.translation.xz();
+            if event.region.contains(pos) {
+                transform.translation.y = heights.sample_world(pos) + anchor.offset;
+            }
+        }
+    }
+}